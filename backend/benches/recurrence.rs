@@ -0,0 +1,154 @@
+//! Expansion of recurring events into entries, run with `cargo bench`.
+
+use std::collections::HashMap;
+
+use bimetable_db::utils::events::map_events;
+use bimetable_db::utils::events::{QEvent, QOverride};
+use bimetable_domain::events::models::{
+    EntriesPage, EventPrivileges, OverrideStrategy, RecurrenceRule, RecurrenceRuleKind, TimeRange,
+};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use time::macros::datetime;
+use time::{Duration, Weekday};
+use uuid::Uuid;
+
+const KINDS: [RecurrenceRuleKind; 6] = [
+    RecurrenceRuleKind::Daily,
+    RecurrenceRuleKind::Weekly {
+        week_map: 0b1111111,
+    },
+    RecurrenceRuleKind::Weekly {
+        week_map: 0b1010100,
+    },
+    RecurrenceRuleKind::Monthly { is_by_day: true },
+    RecurrenceRuleKind::Monthly { is_by_day: false },
+    RecurrenceRuleKind::Yearly { is_by_day: false },
+];
+
+fn year() -> TimeRange {
+    TimeRange::new(
+        datetime!(2023-01-01 0:00 UTC),
+        datetime!(2024-01-01 0:00 UTC),
+    )
+}
+
+fn first_entry(i: usize) -> TimeRange {
+    TimeRange::new_relative(
+        datetime!(2022-09-01 8:00 UTC) + Duration::minutes(15 * i as i64),
+        Duration::minutes(45 + (i % 4) as i64 * 30),
+    )
+}
+
+fn rule(kind: RecurrenceRuleKind) -> RecurrenceRule {
+    RecurrenceRule {
+        span: None,
+        interval: 1,
+        kind,
+    }
+}
+
+/// Events of every kind, started before the search range and repeating forever.
+fn events(count: usize) -> Vec<QEvent> {
+    (0..count)
+        .map(|i| QEvent {
+            id: Uuid::new_v4(),
+            name: format!("Event {i}"),
+            description: None,
+            time_range: first_entry(i),
+            deleted_at: None,
+            recurrence_rule: Some(rule(KINDS[i % KINDS.len()])),
+            privileges: EventPrivileges::Owned,
+            override_strategy: OverrideStrategy::Latest,
+        })
+        .collect()
+}
+
+/// Shifts a few early entries of every event by a day.
+fn overrides(events: &[QEvent]) -> Vec<QOverride> {
+    events
+        .iter()
+        .flat_map(|event| {
+            (0..4).map(|week| {
+                let range = event
+                    .time_range
+                    .checked_add(Duration::weeks(20 + week))
+                    .unwrap();
+                QOverride {
+                    event_id: event.id,
+                    override_starts_at: range.start,
+                    override_ends_at: range.end,
+                    created_at: datetime!(2022-09-01 0:00 UTC),
+                    name: Some("Moved".to_string()),
+                    description: None,
+                    starts_at: Some(Duration::days(1)),
+                    ends_at: Some(Duration::days(1)),
+                    deleted_at: None,
+                    status: None,
+                    added_participants: vec![],
+                    excluded_participants: vec![],
+                }
+            })
+        })
+        .collect()
+}
+
+fn get_event_range(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get_event_range");
+    for kind in KINDS {
+        let rule = rule(kind);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{kind:?}")),
+            &rule,
+            |b, rule| b.iter(|| rule.get_event_range(black_box(year()), first_entry(0))),
+        );
+    }
+    group.finish();
+}
+
+fn map_year_of_events(c: &mut Criterion) {
+    let mut group = c.benchmark_group("map_events");
+    group.sample_size(20);
+    for count in [100, 500] {
+        group.bench_function(BenchmarkId::new("year", count), |b| {
+            b.iter_batched(
+                || events(count),
+                |events| {
+                    map_events(
+                        vec![],
+                        HashMap::new(),
+                        events,
+                        year(),
+                        Weekday::Monday,
+                        Uuid::nil(),
+                        EntriesPage::default(),
+                    )
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("year_with_overrides", count), |b| {
+            b.iter_batched(
+                || {
+                    let events = events(count);
+                    (overrides(&events), events)
+                },
+                |(overrides, events)| {
+                    map_events(
+                        overrides,
+                        HashMap::new(),
+                        events,
+                        year(),
+                        Weekday::Monday,
+                        Uuid::nil(),
+                        EntriesPage::default(),
+                    )
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, get_event_range, map_year_of_events);
+criterion_main!(benches);
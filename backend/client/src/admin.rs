@@ -0,0 +1,33 @@
+use crate::{Client, Result};
+use bimetable_domain::api::admin::{
+    DailyStats, GetStatsQuery, MaintenanceStatus, MintSignupCodes, RetentionPolicy, SignupCode,
+};
+use reqwest::Method;
+use uuid::Uuid;
+
+impl Client {
+    pub async fn get_maintenance(&self) -> Result<MaintenanceStatus> {
+        Self::json(self.request(Method::GET, "/admin/maintenance")).await
+    }
+
+    pub async fn set_maintenance(&self, status: &MaintenanceStatus) -> Result<MaintenanceStatus> {
+        Self::json(self.request(Method::PUT, "/admin/maintenance").json(status)).await
+    }
+
+    pub async fn get_stats(&self, query: &GetStatsQuery) -> Result<Vec<DailyStats>> {
+        Self::json(self.request(Method::GET, "/admin/stats").query(query)).await
+    }
+
+    pub async fn retention_policy(&self) -> Result<RetentionPolicy> {
+        Self::json(self.request(Method::GET, "/admin/retention")).await
+    }
+
+    pub async fn mint_signup_codes(&self, body: &MintSignupCodes) -> Result<Vec<SignupCode>> {
+        Self::json(self.request(Method::POST, "/admin/signup-codes").json(body)).await
+    }
+
+    pub async fn deactivate_user(&self, id: Uuid) -> Result<()> {
+        let path = format!("/admin/users/{id}/deactivate");
+        Self::empty(self.request(Method::PATCH, &path)).await
+    }
+}
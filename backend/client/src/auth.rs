@@ -0,0 +1,51 @@
+use crate::{Client, Result};
+use bimetable_domain::api::auth::{AuthTokens, LoginCredentials, RegisterCredentials};
+use reqwest::Method;
+use serde_json::Value;
+
+impl Client {
+    /// Registers the user and keeps their session cookies.
+    pub async fn register(&self, credentials: &RegisterCredentials) -> Result<()> {
+        Self::empty(
+            self.request(Method::POST, "/auth/register")
+                .json(credentials),
+        )
+        .await
+    }
+
+    /// Logs the user in and keeps their session cookies.
+    pub async fn login(&self, credentials: &LoginCredentials) -> Result<()> {
+        Self::empty(self.request(Method::POST, "/auth/login").json(credentials)).await
+    }
+
+    /// Issues tokens for [`Client::with_token`] instead of cookies.
+    pub async fn issue_token(&self, credentials: &LoginCredentials) -> Result<AuthTokens> {
+        Self::json(self.request(Method::POST, "/auth/token").json(credentials)).await
+    }
+
+    /// Exchanges the refresh token from [`Client::issue_token`] for a new pair of tokens.
+    pub async fn refresh_token(&self, refresh_token: &str) -> Result<AuthTokens> {
+        let client = self.clone().with_token(refresh_token);
+        Self::json(client.request(Method::POST, "/auth/token/refresh")).await
+    }
+
+    pub async fn validate(&self) -> Result<Value> {
+        Self::json(self.request(Method::POST, "/auth/validate")).await
+    }
+
+    pub async fn logout(&self) -> Result<()> {
+        Self::empty(self.request(Method::POST, "/auth/logout")).await
+    }
+
+    pub async fn logout_all(&self) -> Result<()> {
+        Self::empty(self.request(Method::POST, "/auth/logout-all")).await
+    }
+
+    pub async fn refresh(&self) -> Result<()> {
+        Self::empty(self.request(Method::POST, "/auth/refresh")).await
+    }
+
+    pub async fn deactivate(&self) -> Result<()> {
+        Self::empty(self.request(Method::POST, "/auth/deactivate")).await
+    }
+}
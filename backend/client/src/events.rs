@@ -0,0 +1,221 @@
+use crate::{Client, Result};
+use bimetable_domain::api::events::{
+    CombinedBusy, CreateEvent, CreateEventQuery, CreateEventResult, Event, EventDigest,
+    EventExport, EventFeedQuery, EventFeedToken, EventsPage, GetCombinedQuery, GetEventQuery,
+    GetEventsQuery, ImportEventsResult, ImportScheduleQuery, ImportTimetableQuery, NewEventOwner,
+    OverrideEvent, OverrideEventData, PauseEvent, SplitEvent, SuggestSlot, SuggestedSlots,
+    UpdateCoOwner, UpdateEditPrivilege, UpdateEditPrivileges, UpdateEvent, UpdateEventDigest,
+    UpdateEventOwner, UpdateOverrideStrategy, UpdateRecurrence, UpdateRecurrenceResult,
+};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Method;
+use serde_json::Value;
+use uuid::Uuid;
+
+const XLSX: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+
+/// Event routes, except for `/events/{id}/presence`, which is a WebSocket.
+impl Client {
+    pub async fn get_events(&self, query: &GetEventsQuery) -> Result<EventsPage> {
+        Self::json(self.request(Method::GET, "/events").query(query)).await
+    }
+
+    pub async fn create_event(
+        &self,
+        query: &CreateEventQuery,
+        event: &CreateEvent,
+    ) -> Result<CreateEventResult> {
+        Self::json(
+            self.request(Method::PUT, "/events")
+                .query(query)
+                .json(event),
+        )
+        .await
+    }
+
+    pub async fn get_combined_events(&self, query: &GetCombinedQuery) -> Result<CombinedBusy> {
+        Self::json(self.request(Method::GET, "/events/combined").query(query)).await
+    }
+
+    pub async fn suggest_slot(&self, body: &SuggestSlot) -> Result<SuggestedSlots> {
+        Self::json(
+            self.request(Method::POST, "/events/suggest-slot")
+                .json(body),
+        )
+        .await
+    }
+
+    /// Imports events from the bytes of an xlsx timetable.
+    pub async fn import_xlsx(
+        &self,
+        query: &ImportTimetableQuery,
+        timetable: Vec<u8>,
+    ) -> Result<ImportEventsResult> {
+        Self::json(
+            self.request(Method::POST, "/events/import/xlsx")
+                .query(query)
+                .header(CONTENT_TYPE, XLSX)
+                .body(timetable),
+        )
+        .await
+    }
+
+    /// Imports events from a Google Classroom course calendar or a Teams calendar export.
+    pub async fn import_classroom(
+        &self,
+        query: &ImportScheduleQuery,
+        export: &Value,
+    ) -> Result<ImportEventsResult> {
+        Self::json(
+            self.request(Method::POST, "/events/import/classroom")
+                .query(query)
+                .json(export),
+        )
+        .await
+    }
+
+    pub async fn get_event(&self, id: Uuid, query: &GetEventQuery) -> Result<Event> {
+        Self::json(
+            self.request(Method::GET, &format!("/events/{id}"))
+                .query(query),
+        )
+        .await
+    }
+
+    pub async fn update_event(&self, id: Uuid, body: &UpdateEvent) -> Result<()> {
+        Self::empty(
+            self.request(Method::PATCH, &format!("/events/{id}"))
+                .json(body),
+        )
+        .await
+    }
+
+    pub async fn delete_event_permanently(&self, id: Uuid) -> Result<()> {
+        Self::empty(self.request(Method::DELETE, &format!("/events/{id}"))).await
+    }
+
+    pub async fn delete_event_temporarily(&self, id: Uuid) -> Result<()> {
+        let path = format!("/events/temp-delete/{id}");
+        Self::empty(self.request(Method::PATCH, &path)).await
+    }
+
+    pub async fn update_recurrence(
+        &self,
+        id: Uuid,
+        body: &UpdateRecurrence,
+    ) -> Result<UpdateRecurrenceResult> {
+        let path = format!("/events/{id}/recurrence");
+        Self::json(self.request(Method::PATCH, &path).json(body)).await
+    }
+
+    pub async fn split_event(&self, id: Uuid, body: &SplitEvent) -> Result<CreateEventResult> {
+        let path = format!("/events/{id}/split");
+        Self::json(self.request(Method::PATCH, &path).json(body)).await
+    }
+
+    pub async fn pause_event(&self, id: Uuid, body: &PauseEvent) -> Result<()> {
+        let path = format!("/events/{id}/pause");
+        Self::empty(self.request(Method::PATCH, &path).json(body)).await
+    }
+
+    pub async fn update_override_strategy(
+        &self,
+        id: Uuid,
+        body: &UpdateOverrideStrategy,
+    ) -> Result<()> {
+        let path = format!("/events/{id}/override-strategy");
+        Self::empty(self.request(Method::PATCH, &path).json(body)).await
+    }
+
+    pub async fn get_event_digest(&self, id: Uuid) -> Result<EventDigest> {
+        Self::json(self.request(Method::GET, &format!("/events/{id}/digest"))).await
+    }
+
+    pub async fn update_event_digest(
+        &self,
+        id: Uuid,
+        body: &UpdateEventDigest,
+    ) -> Result<EventDigest> {
+        let path = format!("/events/{id}/digest");
+        Self::json(self.request(Method::PUT, &path).json(body)).await
+    }
+
+    pub async fn delete_event_digest(&self, id: Uuid) -> Result<()> {
+        Self::empty(self.request(Method::DELETE, &format!("/events/{id}/digest"))).await
+    }
+
+    pub async fn create_event_feed(&self, id: Uuid) -> Result<EventFeedToken> {
+        Self::json(self.request(Method::PUT, &format!("/events/{id}/feed"))).await
+    }
+
+    /// Fetches the iCalendar feed of the event, it's authenticated by the feed token only.
+    pub async fn get_event_feed(&self, id: Uuid, query: &EventFeedQuery) -> Result<String> {
+        let path = format!("/events/{id}/feed.ics");
+        let res = Self::send(self.request(Method::GET, &path).query(query)).await?;
+        Ok(res.text().await?)
+    }
+
+    pub async fn export_event(&self, id: Uuid) -> Result<EventExport> {
+        Self::json(self.request(Method::POST, &format!("/events/{id}/export.ics"))).await
+    }
+
+    pub async fn create_event_override(&self, id: Uuid, body: &OverrideEvent) -> Result<()> {
+        let path = format!("/events/override/{id}");
+        Self::empty(self.request(Method::PATCH, &path).json(body)).await
+    }
+
+    pub async fn create_occurrence_override(
+        &self,
+        id: Uuid,
+        occurrence_id: Uuid,
+        body: &OverrideEventData,
+    ) -> Result<()> {
+        let path = format!("/events/{id}/occurrences/{occurrence_id}/override");
+        Self::empty(self.request(Method::PATCH, &path).json(body)).await
+    }
+
+    pub async fn lock_occurrence(&self, id: Uuid, occurrence_id: Uuid) -> Result<()> {
+        let path = format!("/events/{id}/occurrences/{occurrence_id}/lock");
+        Self::empty(self.request(Method::PUT, &path)).await
+    }
+
+    pub async fn unlock_occurrence(&self, id: Uuid, occurrence_id: Uuid) -> Result<()> {
+        let path = format!("/events/{id}/occurrences/{occurrence_id}/lock");
+        Self::empty(self.request(Method::DELETE, &path)).await
+    }
+
+    pub async fn update_edit_privilege(&self, id: Uuid, body: &UpdateEditPrivilege) -> Result<()> {
+        let path = format!("/events/set-edit/{id}");
+        Self::empty(self.request(Method::PATCH, &path).json(body)).await
+    }
+
+    pub async fn update_edit_privileges(
+        &self,
+        id: Uuid,
+        body: &UpdateEditPrivileges,
+    ) -> Result<()> {
+        let path = format!("/events/{id}/privileges");
+        Self::empty(self.request(Method::PATCH, &path).json(body)).await
+    }
+
+    pub async fn update_co_owner(&self, id: Uuid, body: &UpdateCoOwner) -> Result<()> {
+        let path = format!("/events/{id}/co-owners");
+        Self::empty(self.request(Method::PATCH, &path).json(body)).await
+    }
+
+    pub async fn update_event_owner(&self, id: Uuid, body: &UpdateEventOwner) -> Result<()> {
+        let path = format!("/events/set-owner/{id}");
+        Self::empty(self.request(Method::PATCH, &path).json(body)).await
+    }
+
+    pub async fn leave_event(&self, id: Uuid) -> Result<()> {
+        let path = format!("/events/leave-event/{id}");
+        Self::empty(self.request(Method::DELETE, &path)).await
+    }
+
+    /// Leaves the owned event, handing it over to the new owner.
+    pub async fn leave_owned_event(&self, id: Uuid, body: &NewEventOwner) -> Result<()> {
+        let path = format!("/events/remove-owner/{id}");
+        Self::empty(self.request(Method::PATCH, &path).json(body)).await
+    }
+}
@@ -0,0 +1,42 @@
+use crate::{Client, Result};
+use bimetable_domain::api::invitations::{
+    CreateDirectInvitation, CreateEmailInvitation, InvitationCount, ReceivedInvitation,
+    RespondDirectInvitation,
+};
+use reqwest::Method;
+
+impl Client {
+    pub async fn create_invitation(&self, invitation: &CreateDirectInvitation) -> Result<()> {
+        Self::empty(
+            self.request(Method::PUT, "/events/invitations/create")
+                .json(invitation),
+        )
+        .await
+    }
+
+    /// Invites an email address, which may not have an account yet.
+    pub async fn create_email_invitation(&self, invitation: &CreateEmailInvitation) -> Result<()> {
+        Self::empty(
+            self.request(Method::PUT, "/events/invitations/email")
+                .json(invitation),
+        )
+        .await
+    }
+
+    pub async fn fetch_invitations(&self) -> Result<Vec<ReceivedInvitation>> {
+        Self::json(self.request(Method::GET, "/events/invitations/fetch")).await
+    }
+
+    pub async fn count_invitations(&self) -> Result<InvitationCount> {
+        Self::json(self.request(Method::GET, "/events/invitations/count")).await
+    }
+
+    pub async fn mark_invitations_seen(&self) -> Result<()> {
+        Self::empty(self.request(Method::PATCH, "/events/invitations/seen")).await
+    }
+
+    pub async fn respond_to_invitation(&self, response: &RespondDirectInvitation) -> Result<()> {
+        let path = format!("/events/invitations/respond/{}", response.event_id);
+        Self::empty(self.request(Method::PATCH, &path).json(response)).await
+    }
+}
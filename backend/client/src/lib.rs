@@ -0,0 +1,104 @@
+//! Typed client of the bimetable v1 API.
+//!
+//! Requests and responses reuse the models of the server, so the client can't drift from the routes it calls.
+//! Sessions are kept either in cookies, after [`Client::login`], or as a bearer token set with [`Client::with_token`].
+
+mod admin;
+mod auth;
+mod events;
+mod invitations;
+mod reminders;
+mod search;
+mod users;
+
+use bimetable_domain::api::API_V1;
+use reqwest::header::AUTHORIZATION;
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+
+pub use bimetable_domain::api;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error("Request failed with {status}: {body}")]
+    Status { status: StatusCode, body: String },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Clone)]
+pub struct Client {
+    base_url: String,
+    http: reqwest::Client,
+    token: Option<String>,
+}
+
+impl Client {
+    /// Creates a client of the server at `base_url`, e.g. `http://localhost:3000`, keeping session cookies.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        let http = reqwest::Client::builder()
+            .cookie_store(true)
+            .build()
+            .expect("Failed to build reqwest client");
+        Self::with_http(base_url, http)
+    }
+
+    /// Creates a client sending its requests with the given reqwest client.
+    pub fn with_http(base_url: impl Into<String>, http: reqwest::Client) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            http,
+            token: None,
+        }
+    }
+
+    /// Authenticates further requests with the access token, see [`Client::issue_token`].
+    pub fn with_token(mut self, access_token: impl Into<String>) -> Self {
+        self.token = Some(access_token.into());
+        self
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{API_V1}{path}", self.base_url)
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self.http.request(method, self.url(path));
+        match &self.token {
+            Some(token) => request.header(AUTHORIZATION, format!("Bearer {token}")),
+            None => request,
+        }
+    }
+
+    async fn send(request: RequestBuilder) -> Result<Response> {
+        let res = request.send().await?;
+        let status = res.status();
+        if status.is_client_error() || status.is_server_error() {
+            let body = res.text().await.unwrap_or_default();
+            return Err(Error::Status { status, body });
+        }
+        Ok(res)
+    }
+
+    async fn json<T: DeserializeOwned>(request: RequestBuilder) -> Result<T> {
+        Ok(Self::send(request).await?.json().await?)
+    }
+
+    async fn empty(request: RequestBuilder) -> Result<()> {
+        Self::send(request).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_are_versioned() {
+        let client = Client::new("http://localhost:3000/");
+        assert_eq!(client.url("/events"), "http://localhost:3000/api/v1/events");
+    }
+}
@@ -0,0 +1,18 @@
+use crate::{Client, Result};
+use bimetable_domain::api::reminders::{CreateReminder, CreateReminderResult, Reminder};
+use reqwest::Method;
+use uuid::Uuid;
+
+impl Client {
+    pub async fn get_reminders(&self) -> Result<Vec<Reminder>> {
+        Self::json(self.request(Method::GET, "/reminders")).await
+    }
+
+    pub async fn create_reminder(&self, reminder: &CreateReminder) -> Result<CreateReminderResult> {
+        Self::json(self.request(Method::PUT, "/reminders").json(reminder)).await
+    }
+
+    pub async fn delete_reminder(&self, id: Uuid) -> Result<()> {
+        Self::empty(self.request(Method::DELETE, &format!("/reminders/{id}"))).await
+    }
+}
@@ -0,0 +1,15 @@
+use crate::{Client, Result};
+use bimetable_domain::api::search::{
+    SearchEvents, SearchEventsResult, SearchUsers, SearchUsersResult,
+};
+use reqwest::Method;
+
+impl Client {
+    pub async fn search_users(&self, query: &SearchUsers) -> Result<Vec<SearchUsersResult>> {
+        Self::json(self.request(Method::GET, "/search/users").query(query)).await
+    }
+
+    pub async fn search_events(&self, query: &SearchEvents) -> Result<SearchEventsResult> {
+        Self::json(self.request(Method::GET, "/search/events").query(query)).await
+    }
+}
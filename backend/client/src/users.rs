@@ -0,0 +1,69 @@
+use crate::{Client, Result};
+use bimetable_domain::api::users::{
+    CreateQuietHours, QuietHours, UpdateUserPreferences, UserArchive, UserHandle, UserPreferences,
+};
+use reqwest::Method;
+use uuid::Uuid;
+
+impl Client {
+    pub async fn get_preferences(&self) -> Result<UserPreferences> {
+        Self::json(self.request(Method::GET, "/users/preferences")).await
+    }
+
+    pub async fn update_preferences(
+        &self,
+        preferences: &UpdateUserPreferences,
+    ) -> Result<UserPreferences> {
+        Self::json(
+            self.request(Method::PATCH, "/users/preferences")
+                .json(preferences),
+        )
+        .await
+    }
+
+    pub async fn get_quiet_hours(&self) -> Result<Vec<QuietHours>> {
+        Self::json(self.request(Method::GET, "/users/preferences/quiet-hours")).await
+    }
+
+    pub async fn add_quiet_hours(&self, body: &CreateQuietHours) -> Result<QuietHours> {
+        Self::json(
+            self.request(Method::PUT, "/users/preferences/quiet-hours")
+                .json(body),
+        )
+        .await
+    }
+
+    pub async fn remove_quiet_hours(&self, id: Uuid) -> Result<()> {
+        let path = format!("/users/preferences/quiet-hours/{id}");
+        Self::empty(self.request(Method::DELETE, &path)).await
+    }
+
+    /// Shares busy times of the user with the other user.
+    pub async fn grant_visibility(&self, user_id: Uuid) -> Result<()> {
+        let path = format!("/users/visibility/{user_id}");
+        Self::empty(self.request(Method::PUT, &path)).await
+    }
+
+    pub async fn revoke_visibility(&self, user_id: Uuid) -> Result<()> {
+        let path = format!("/users/visibility/{user_id}");
+        Self::empty(self.request(Method::DELETE, &path)).await
+    }
+
+    /// Lists the current and former handles of a user.
+    pub async fn get_handles(&self, user_id: Uuid) -> Result<Vec<UserHandle>> {
+        Self::json(self.request(Method::GET, &format!("/users/{user_id}/handles"))).await
+    }
+
+    /// Erases the user with all of their data.
+    pub async fn delete_account(&self) -> Result<()> {
+        Self::empty(self.request(Method::DELETE, "/users/me")).await
+    }
+
+    pub async fn request_export(&self) -> Result<UserArchive> {
+        Self::json(self.request(Method::POST, "/users/me/export")).await
+    }
+
+    pub async fn get_export(&self, id: Uuid) -> Result<UserArchive> {
+        Self::json(self.request(Method::GET, &format!("/users/me/export/{id}"))).await
+    }
+}
@@ -0,0 +1,585 @@
+use crate::config::{get_env, try_get_env, try_get_secret_env};
+use bimetable_domain::limits::{
+    OverrideShiftLimit, RepetitionLimit, DEFAULT_MAX_REPETITIONS,
+    DEFAULT_OVERRIDE_SHIFT_LIMIT_HOURS,
+};
+use reqwest::Url;
+use secrecy::Secret;
+use serde::Deserialize;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::str::FromStr;
+use time::Duration;
+use tracing::warn;
+
+pub const NAME_PORT: &str = "PORT";
+pub const NAME_ORIGIN: &str = "WEBSITE_URL";
+pub const NAME_MAINTENANCE: &str = "MAINTENANCE";
+pub const NAME_REALTIME_BRIDGE: &str = "REALTIME_BRIDGE";
+pub const NAME_PRESENCE_TTL_SECONDS: &str = "PRESENCE_TTL_SECONDS";
+pub const NAME_OVERRIDE_SHIFT_LIMIT: &str = "OVERRIDE_SHIFT_LIMIT_HOURS";
+pub const NAME_MAX_REPETITIONS: &str = "MAX_RECURRENCE_REPETITIONS";
+pub const NAME_EXPANSION_THREADS: &str = "EXPANSION_THREADS";
+pub const NAME_SEARCH_CACHE_SECONDS: &str = "SEARCH_CACHE_SECONDS";
+pub const NAME_COMPRESSION: &str = "COMPRESSION";
+pub const NAME_COMPRESSION_MIN_BYTES: &str = "COMPRESSION_MIN_BYTES";
+pub const NAME_METRICS_TOKEN: &str = "METRICS_TOKEN";
+pub const NAME_SCIM_TOKEN: &str = "SCIM_TOKEN";
+pub const NAME_ERROR_REPORTING_DSN: &str = "ERROR_REPORTING_DSN";
+pub const NAME_INVITATION_HOURLY_CAP: &str = "INVITATION_HOURLY_CAP";
+pub const NAME_SWAGGER: &str = "SWAGGER";
+pub const NAME_EXAMPLE_ROUTES: &str = "EXAMPLE_ROUTES";
+pub const NAME_SQL_LOGGING: &str = "SQL_LOGGING";
+pub const NAME_ADMIN_ROUTES: &str = "ADMIN_ROUTES";
+pub const NAME_REALTIME_ROUTES: &str = "REALTIME_ROUTES";
+pub const NAME_SWAGGER_USER: &str = "SWAGGER_USER";
+pub const NAME_SWAGGER_PASSWORD: &str = "SWAGGER_PASSWORD";
+pub const NAME_STORAGE: &str = "STORAGE";
+pub const NAME_STORAGE_DIR: &str = "STORAGE_DIR";
+pub const NAME_STORAGE_SIGNING_KEY: &str = "STORAGE_SIGNING_KEY";
+pub const NAME_S3_ENDPOINT: &str = "S3_ENDPOINT";
+pub const NAME_S3_REGION: &str = "S3_REGION";
+pub const NAME_S3_BUCKET: &str = "S3_BUCKET";
+pub const NAME_S3_ACCESS_KEY: &str = "S3_ACCESS_KEY";
+pub const NAME_S3_SECRET_KEY: &str = "S3_SECRET_KEY";
+pub const NAME_WEBHOOK_ALLOWED_HOSTS: &str = "WEBHOOK_ALLOWED_HOSTS";
+
+const DEFAULT_HOST: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 1);
+const DEFAULT_PORT: u16 = 3001;
+const DEFAULT_ORIGIN: &str = "http://127.0.0.1";
+const DEFAULT_INVITATION_HOURLY_CAP: u32 = 50;
+const DEFAULT_EXPANSION_THREADS: u16 = 0;
+const DEFAULT_SEARCH_CACHE_SECONDS: u64 = 5;
+const DEFAULT_PRESENCE_TTL_SECONDS: u32 = 30;
+const DEFAULT_COMPRESSION_MIN_BYTES: u16 = 1024;
+const DEFAULT_STORAGE_DIR: &str = "storage";
+
+#[derive(Deserialize)]
+pub struct ApplicationSettingsModel {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub origin: Option<String>,
+    pub maintenance: Option<bool>,
+    pub realtime_bridge: Option<RealtimeBridgeKind>,
+    pub presence_ttl_seconds: Option<u32>,
+    pub override_shift_limit_hours: Option<u32>,
+    pub max_repetitions: Option<u32>,
+    pub expansion_threads: Option<u16>,
+    pub search_cache_seconds: Option<u64>,
+    pub compression: Option<Vec<CompressionAlgorithm>>,
+    pub compression_min_bytes: Option<u16>,
+    pub metrics_token: Option<Secret<String>>,
+    pub scim_token: Option<Secret<String>>,
+    pub error_reporting_dsn: Option<Secret<String>>,
+    pub invitation_hourly_cap: Option<u32>,
+    pub swagger: Option<SwaggerAccess>,
+    pub swagger_user: Option<String>,
+    pub swagger_password: Option<Secret<String>>,
+    pub example_routes: Option<bool>,
+    pub sql_logging: Option<bool>,
+    pub admin_routes: Option<bool>,
+    pub realtime_routes: Option<bool>,
+    pub storage: Option<StorageKind>,
+    pub storage_dir: Option<String>,
+    pub storage_signing_key: Option<Secret<String>>,
+    pub s3_endpoint: Option<String>,
+    pub s3_region: Option<String>,
+    pub s3_bucket: Option<String>,
+    pub s3_access_key: Option<String>,
+    pub s3_secret_key: Option<Secret<String>>,
+    pub webhook_allowed_hosts: Option<Vec<String>>,
+}
+
+impl ApplicationSettingsModel {
+    pub fn to_settings(self) -> ApplicationSettings {
+        let host = self.host.map_or_else(
+            || {
+                warn!("Using default host");
+                DEFAULT_HOST
+            },
+            |host| Ipv4Addr::from_str(&host).expect("Incorrect host"),
+        );
+        let port = self.port.unwrap_or_else(|| {
+            warn!("Using default port");
+            DEFAULT_PORT
+        });
+
+        let addr = SocketAddr::new(IpAddr::V4(host), port);
+
+        let mut settings =
+            ApplicationSettings::new(addr, self.origin.unwrap_or(DEFAULT_ORIGIN.to_string()));
+        settings.maintenance = self.maintenance.unwrap_or(false);
+        settings.realtime_bridge = self.realtime_bridge.unwrap_or_default();
+        if let Some(seconds) = self.presence_ttl_seconds {
+            settings.presence_ttl_seconds = seconds;
+        }
+        if let Some(hours) = self.override_shift_limit_hours {
+            settings.override_shift_limit_hours = hours;
+        }
+        if let Some(max) = self.max_repetitions {
+            settings.max_repetitions = max;
+        }
+        if let Some(threads) = self.expansion_threads {
+            settings.expansion_threads = threads;
+        }
+        if let Some(seconds) = self.search_cache_seconds {
+            settings.search_cache_seconds = seconds;
+        }
+        if let Some(algorithms) = self.compression {
+            settings.compression = algorithms;
+        }
+        if let Some(min_bytes) = self.compression_min_bytes {
+            settings.compression_min_bytes = min_bytes;
+        }
+        settings.metrics_token = self.metrics_token;
+        settings.scim_token = self.scim_token;
+        settings.error_reporting_dsn = self.error_reporting_dsn;
+        if let Some(cap) = self.invitation_hourly_cap {
+            settings.invitation_hourly_cap = cap;
+        }
+        settings.swagger = self.swagger;
+        settings.swagger_user = self.swagger_user;
+        settings.swagger_password = self.swagger_password;
+        settings.example_routes = self.example_routes;
+        settings.sql_logging = self.sql_logging.unwrap_or(false);
+        if let Some(enabled) = self.admin_routes {
+            settings.admin_routes = enabled;
+        }
+        if let Some(enabled) = self.realtime_routes {
+            settings.realtime_routes = enabled;
+        }
+        settings.storage = self.storage.unwrap_or_default();
+        if let Some(dir) = self.storage_dir {
+            settings.storage_dir = dir;
+        }
+        settings.storage_signing_key = self.storage_signing_key;
+        settings.s3_endpoint = self.s3_endpoint;
+        settings.s3_region = self.s3_region;
+        settings.s3_bucket = self.s3_bucket;
+        settings.s3_access_key = self.s3_access_key;
+        settings.s3_secret_key = self.s3_secret_key;
+        if let Some(hosts) = self.webhook_allowed_hosts {
+            settings.webhook_allowed_hosts = hosts;
+        }
+        settings
+    }
+}
+#[derive(Deserialize, Clone)]
+pub struct ApplicationSettings {
+    pub addr: SocketAddr,
+    pub origin: String,
+    /// Whether the server enables the maintenance mode on start, it's left as is otherwise
+    pub maintenance: bool,
+    /// How realtime messages reach other instances
+    pub realtime_bridge: RealtimeBridgeKind,
+    /// How long editors stay present without refreshing their claims
+    pub presence_ttl_seconds: u32,
+    /// How far overrides may move entries from their original occurrences
+    pub override_shift_limit_hours: u32,
+    /// How many entries a recurring event may have
+    pub max_repetitions: u32,
+    /// How many threads expand recurring events into entries, one per CPU with 0
+    pub expansion_threads: u16,
+    /// How long search results are reused, caching is disabled with 0
+    pub search_cache_seconds: u64,
+    /// Algorithms responses may be compressed with, compression is disabled without any
+    pub compression: Vec<CompressionAlgorithm>,
+    /// Smallest response body that gets compressed
+    pub compression_min_bytes: u16,
+    /// Bearer token of `/metrics` scrapes, the endpoint is disabled without it
+    pub metrics_token: Option<Secret<String>>,
+    /// Bearer token of identity systems provisioning users at `/scim/v2`, the endpoints are disabled without it
+    pub scim_token: Option<Secret<String>>,
+    /// Sentry compatible DSN receiving unexpected errors and panics, which are only logged without it
+    pub error_reporting_dsn: Option<Secret<String>>,
+    /// How many invitations a single user may send within an hour
+    pub invitation_hourly_cap: u32,
+    /// Who may browse `/swagger-ui`, open in development and disabled in production when unset
+    pub swagger: Option<SwaggerAccess>,
+    /// Basic auth credentials letting operators into a protected Swagger UI
+    pub swagger_user: Option<String>,
+    pub swagger_password: Option<Secret<String>>,
+    /// Whether the example routes at `/ex` are served, only in development when unset
+    pub example_routes: Option<bool>,
+    /// Whether SQL statements are logged and counted in `x-db-queries`, only honored in development
+    pub sql_logging: bool,
+    /// Whether the admin routes at `/admin` are served
+    pub admin_routes: bool,
+    /// Whether the presence WebSocket of events is served
+    pub realtime_routes: bool,
+    /// Where exports and other generated files are kept
+    pub storage: StorageKind,
+    /// Directory of the local storage
+    pub storage_dir: String,
+    /// Key signing download URLs of the local storage, random for each start when unset
+    pub storage_signing_key: Option<Secret<String>>,
+    /// Origin of an S3 compatible service, AWS of the region by default
+    pub s3_endpoint: Option<String>,
+    pub s3_region: Option<String>,
+    pub s3_bucket: Option<String>,
+    pub s3_access_key: Option<String>,
+    pub s3_secret_key: Option<Secret<String>>,
+    /// Hosts reminder webhooks may reach even at non-public addresses, e.g. services of the local network
+    pub webhook_allowed_hosts: Vec<String>,
+}
+
+impl ApplicationSettings {
+    pub fn new(addr: SocketAddr, origin: String) -> Self {
+        Self {
+            addr,
+            origin,
+            maintenance: false,
+            realtime_bridge: RealtimeBridgeKind::default(),
+            presence_ttl_seconds: DEFAULT_PRESENCE_TTL_SECONDS,
+            override_shift_limit_hours: DEFAULT_OVERRIDE_SHIFT_LIMIT_HOURS,
+            max_repetitions: DEFAULT_MAX_REPETITIONS,
+            expansion_threads: DEFAULT_EXPANSION_THREADS,
+            search_cache_seconds: DEFAULT_SEARCH_CACHE_SECONDS,
+            compression: CompressionAlgorithm::all(),
+            compression_min_bytes: DEFAULT_COMPRESSION_MIN_BYTES,
+            metrics_token: None,
+            scim_token: None,
+            error_reporting_dsn: None,
+            invitation_hourly_cap: DEFAULT_INVITATION_HOURLY_CAP,
+            swagger: None,
+            swagger_user: None,
+            swagger_password: None,
+            example_routes: None,
+            sql_logging: false,
+            admin_routes: true,
+            realtime_routes: true,
+            storage: StorageKind::default(),
+            storage_dir: DEFAULT_STORAGE_DIR.to_string(),
+            storage_signing_key: None,
+            s3_endpoint: None,
+            s3_region: None,
+            s3_bucket: None,
+            s3_access_key: None,
+            s3_secret_key: None,
+            webhook_allowed_hosts: Vec::new(),
+        }
+    }
+
+    pub fn override_shift_limit(&self) -> OverrideShiftLimit {
+        OverrideShiftLimit(Duration::hours(self.override_shift_limit_hours.into()))
+    }
+
+    pub fn repetition_limit(&self) -> RepetitionLimit {
+        RepetitionLimit(self.max_repetitions)
+    }
+
+    pub fn invitation_cap(&self) -> InvitationCap {
+        InvitationCap(self.invitation_hourly_cap)
+    }
+
+    pub fn website_origin(&self) -> WebsiteOrigin {
+        WebsiteOrigin(self.origin.trim_end_matches('/').to_string())
+    }
+
+    pub fn presence_ttl(&self) -> Duration {
+        Duration::seconds(self.presence_ttl_seconds.into())
+    }
+
+    pub fn scim_token(&self) -> ScimToken {
+        ScimToken(self.scim_token.clone())
+    }
+
+    /// Problems of the listening address and the origin of the website.
+    pub fn address_problems(&self) -> Vec<String> {
+        let mut problems = vec![];
+        if self.addr.port() == 0 {
+            problems.push("The port must not be 0".to_string());
+        }
+        match Url::parse(&self.origin) {
+            Ok(origin) => {
+                if !matches!(origin.scheme(), "http" | "https") {
+                    problems.push(format!(
+                        "The origin {} is not served over HTTP",
+                        self.origin
+                    ));
+                }
+                if !origin.has_host() {
+                    problems.push(format!("The origin {} has no host", self.origin));
+                }
+                if origin.path() != "/" || origin.query().is_some() {
+                    problems.push(format!(
+                        "The origin {} must not have a path or a query",
+                        self.origin
+                    ));
+                }
+            }
+            Err(e) => problems.push(format!("The origin {} is invalid: {e}", self.origin)),
+        }
+        problems
+    }
+
+    pub fn from_env() -> Self {
+        let host = Ipv4Addr::new(0, 0, 0, 0);
+        let port = get_env(NAME_PORT)
+            .parse::<u16>()
+            .expect("Invalid port number");
+        Self {
+            addr: SocketAddr::new(IpAddr::V4(host), port),
+            origin: get_env(NAME_ORIGIN),
+            maintenance: try_get_env(NAME_MAINTENANCE)
+                .is_some_and(|x| x.parse::<bool>().expect("Invalid maintenance flag")),
+            realtime_bridge: try_get_env(NAME_REALTIME_BRIDGE)
+                .map_or_else(RealtimeBridgeKind::default, |x| {
+                    RealtimeBridgeKind::try_from(x).expect("Invalid realtime bridge")
+                }),
+            presence_ttl_seconds: try_get_env(NAME_PRESENCE_TTL_SECONDS)
+                .map_or(DEFAULT_PRESENCE_TTL_SECONDS, |x| {
+                    x.parse::<u32>().expect("Invalid presence TTL seconds")
+                }),
+            override_shift_limit_hours: try_get_env(NAME_OVERRIDE_SHIFT_LIMIT)
+                .map_or(DEFAULT_OVERRIDE_SHIFT_LIMIT_HOURS, |x| {
+                    x.parse::<u32>().expect("Invalid override shift limit")
+                }),
+            max_repetitions: try_get_env(NAME_MAX_REPETITIONS).map_or(
+                DEFAULT_MAX_REPETITIONS,
+                |x| {
+                    x.parse::<u32>()
+                        .expect("Invalid max recurrence repetitions")
+                },
+            ),
+            expansion_threads: try_get_env(NAME_EXPANSION_THREADS)
+                .map_or(DEFAULT_EXPANSION_THREADS, |x| {
+                    x.parse::<u16>().expect("Invalid expansion threads")
+                }),
+            search_cache_seconds: try_get_env(NAME_SEARCH_CACHE_SECONDS)
+                .map_or(DEFAULT_SEARCH_CACHE_SECONDS, |x| {
+                    x.parse::<u64>().expect("Invalid search cache seconds")
+                }),
+            compression: try_get_env(NAME_COMPRESSION).map_or_else(
+                CompressionAlgorithm::all,
+                |x| {
+                    x.split(',')
+                        .map(str::trim)
+                        .filter(|algorithm| !algorithm.is_empty())
+                        .map(|algorithm| {
+                            CompressionAlgorithm::try_from(algorithm.to_string())
+                                .expect("Invalid compression algorithm")
+                        })
+                        .collect()
+                },
+            ),
+            compression_min_bytes: try_get_env(NAME_COMPRESSION_MIN_BYTES)
+                .map_or(DEFAULT_COMPRESSION_MIN_BYTES, |x| {
+                    x.parse::<u16>().expect("Invalid compression min bytes")
+                }),
+            metrics_token: try_get_secret_env(NAME_METRICS_TOKEN),
+            scim_token: try_get_secret_env(NAME_SCIM_TOKEN),
+            error_reporting_dsn: try_get_secret_env(NAME_ERROR_REPORTING_DSN),
+            invitation_hourly_cap: try_get_env(NAME_INVITATION_HOURLY_CAP)
+                .map_or(DEFAULT_INVITATION_HOURLY_CAP, |x| {
+                    x.parse::<u32>().expect("Invalid invitation hourly cap")
+                }),
+            swagger: try_get_env(NAME_SWAGGER)
+                .map(|x| SwaggerAccess::try_from(x).expect("Invalid Swagger UI access")),
+            swagger_user: try_get_env(NAME_SWAGGER_USER),
+            swagger_password: try_get_secret_env(NAME_SWAGGER_PASSWORD),
+            example_routes: try_get_env(NAME_EXAMPLE_ROUTES)
+                .map(|x| x.parse::<bool>().expect("Invalid example routes flag")),
+            sql_logging: try_get_env(NAME_SQL_LOGGING)
+                .is_some_and(|x| x.parse::<bool>().expect("Invalid SQL logging flag")),
+            admin_routes: try_get_env(NAME_ADMIN_ROUTES)
+                .is_none_or(|x| x.parse::<bool>().expect("Invalid admin routes flag")),
+            realtime_routes: try_get_env(NAME_REALTIME_ROUTES)
+                .is_none_or(|x| x.parse::<bool>().expect("Invalid realtime routes flag")),
+            storage: try_get_env(NAME_STORAGE).map_or_else(StorageKind::default, |x| {
+                StorageKind::try_from(x).expect("Invalid storage")
+            }),
+            storage_dir: try_get_env(NAME_STORAGE_DIR)
+                .unwrap_or_else(|| DEFAULT_STORAGE_DIR.to_string()),
+            storage_signing_key: try_get_secret_env(NAME_STORAGE_SIGNING_KEY),
+            s3_endpoint: try_get_env(NAME_S3_ENDPOINT),
+            s3_region: try_get_env(NAME_S3_REGION),
+            s3_bucket: try_get_env(NAME_S3_BUCKET),
+            s3_access_key: try_get_env(NAME_S3_ACCESS_KEY),
+            s3_secret_key: try_get_secret_env(NAME_S3_SECRET_KEY),
+            webhook_allowed_hosts: try_get_env(NAME_WEBHOOK_ALLOWED_HOSTS)
+                .map(|x| {
+                    x.split(',')
+                        .map(str::trim)
+                        .filter(|host| !host.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl Default for ApplicationSettings {
+    fn default() -> Self {
+        Self {
+            addr: SocketAddr::new(IpAddr::V4(DEFAULT_HOST), DEFAULT_PORT),
+            origin: "http://127.0.0.1".to_string(),
+            maintenance: false,
+            realtime_bridge: RealtimeBridgeKind::default(),
+            presence_ttl_seconds: DEFAULT_PRESENCE_TTL_SECONDS,
+            override_shift_limit_hours: DEFAULT_OVERRIDE_SHIFT_LIMIT_HOURS,
+            max_repetitions: DEFAULT_MAX_REPETITIONS,
+            expansion_threads: DEFAULT_EXPANSION_THREADS,
+            search_cache_seconds: DEFAULT_SEARCH_CACHE_SECONDS,
+            compression: CompressionAlgorithm::all(),
+            compression_min_bytes: DEFAULT_COMPRESSION_MIN_BYTES,
+            metrics_token: None,
+            scim_token: None,
+            error_reporting_dsn: None,
+            invitation_hourly_cap: DEFAULT_INVITATION_HOURLY_CAP,
+            swagger: None,
+            swagger_user: None,
+            swagger_password: None,
+            example_routes: None,
+            sql_logging: false,
+            admin_routes: true,
+            realtime_routes: true,
+            storage: StorageKind::default(),
+            storage_dir: DEFAULT_STORAGE_DIR.to_string(),
+            storage_signing_key: None,
+            s3_endpoint: None,
+            s3_region: None,
+            s3_bucket: None,
+            s3_access_key: None,
+            s3_secret_key: None,
+            webhook_allowed_hosts: Vec::new(),
+        }
+    }
+}
+
+/// Number of invitations a user may send within a clock hour.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvitationCap(pub u32);
+
+impl Default for InvitationCap {
+    fn default() -> Self {
+        Self(DEFAULT_INVITATION_HOURLY_CAP)
+    }
+}
+
+/// Origin of the website, links sent outside of it start with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebsiteOrigin(pub String);
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RealtimeBridgeKind {
+    /// Only clients of the instance handling a change are notified
+    Local,
+    /// Instances exchange changes through Postgres `LISTEN`/`NOTIFY`
+    #[default]
+    Postgres,
+}
+
+impl TryFrom<String> for RealtimeBridgeKind {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "local" => Ok(Self::Local),
+            "postgres" => Ok(Self::Postgres),
+            other => Err(format!("Unknown realtime bridge {other}")),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SwaggerAccess {
+    /// Swagger UI is not served
+    Disabled,
+    /// Anyone can browse Swagger UI
+    Open,
+    /// Only admins and holders of the basic auth credentials can browse Swagger UI
+    Protected,
+}
+
+impl TryFrom<String> for SwaggerAccess {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "disabled" => Ok(Self::Disabled),
+            "open" => Ok(Self::Open),
+            "protected" => Ok(Self::Protected),
+            other => Err(format!("Unknown Swagger UI access {other}")),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionAlgorithm {
+    Gzip,
+    /// Brotli
+    Br,
+}
+
+impl CompressionAlgorithm {
+    pub fn all() -> Vec<Self> {
+        vec![Self::Gzip, Self::Br]
+    }
+}
+
+impl TryFrom<String> for CompressionAlgorithm {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "gzip" => Ok(Self::Gzip),
+            "br" | "brotli" => Ok(Self::Br),
+            other => Err(format!("Unknown compression algorithm {other}")),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageKind {
+    /// Files are kept in a directory of the server
+    #[default]
+    Local,
+    /// Files are kept in an S3 compatible bucket
+    S3,
+}
+
+impl TryFrom<String> for StorageKind {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "local" => Ok(Self::Local),
+            "s3" => Ok(Self::S3),
+            other => Err(format!("Unknown storage {other}")),
+        }
+    }
+}
+
+/// Bearer token of the user provisioning API, which is disabled without it.
+#[derive(Clone)]
+pub struct ScimToken(pub Option<Secret<String>>);
+
+#[cfg(test)]
+mod app_tests {
+    use super::*;
+
+    #[test]
+    fn insane_addresses_are_reported() {
+        let addr = SocketAddr::new(IpAddr::V4(DEFAULT_HOST), DEFAULT_PORT);
+        let settings = ApplicationSettings::new(addr, "https://bimetable.app".to_string());
+        assert!(settings.address_problems().is_empty());
+
+        for origin in [
+            "bimetable.app",
+            "ftp://bimetable.app",
+            "https://bimetable.app/api",
+        ] {
+            let settings = ApplicationSettings::new(addr, origin.to_string());
+            assert_eq!(settings.address_problems().len(), 1, "{origin}");
+        }
+
+        let addr = SocketAddr::new(IpAddr::V4(DEFAULT_HOST), 0);
+        let settings = ApplicationSettings::new(addr, DEFAULT_ORIGIN.to_string());
+        assert_eq!(settings.address_problems().len(), 1);
+    }
+}
@@ -0,0 +1,66 @@
+use crate::config::Settings;
+use crate::modules::database::check_schema;
+use sqlx::postgres::PgPoolOptions;
+use std::fmt::Display;
+use std::time::Duration;
+
+/// How long the check waits for a database connection
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Problems of the configuration found by `--check-config`.
+#[derive(Debug, Default, PartialEq)]
+pub struct ConfigReport {
+    pub problems: Vec<String>,
+}
+
+impl ConfigReport {
+    pub fn is_healthy(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+impl Display for ConfigReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_healthy() {
+            return write!(f, "Configuration is valid");
+        }
+
+        write!(f, "Configuration has {} problems:", self.problems.len())?;
+        for problem in &self.problems {
+            write!(f, "\n - {problem}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks the settings before a rollout, without starting the server.
+///
+/// Token secrets must be strong, the port and origin sane and the database reachable with a matching schema.
+/// Pending migrations are fine when the server applies them on start.
+pub async fn check_config(settings: &Settings) -> ConfigReport {
+    let mut problems = settings.jwt.secret_problems();
+    problems.extend(settings.app.address_problems());
+
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .acquire_timeout(CONNECT_TIMEOUT)
+        .connect(&settings.postgres.database_url)
+        .await;
+    match pool {
+        Ok(pool) => match check_schema(&pool).await {
+            Ok(mut schema) => {
+                if settings.postgres.is_migrating {
+                    schema.pending.clear();
+                }
+                if !schema.is_healthy() {
+                    problems.push(schema.to_string());
+                }
+            }
+            Err(e) => problems.push(format!("Failed to check the database schema: {e}")),
+        },
+        Err(e) => problems.push(format!("Failed to connect to the database: {e}")),
+    }
+
+    ConfigReport { problems }
+}
@@ -1,9 +1,13 @@
 use crate::config::app::{ApplicationSettings, ApplicationSettingsModel, NAME_ORIGIN, NAME_PORT};
 use crate::config::database::{PostgresSettings, PostgresSettingsModel, NAME_POSTGRES};
 use crate::config::environment::Environment;
+use crate::config::passwords::{PasswordSettings, PasswordSettingsModel};
+use crate::config::retention::{RetentionSettings, RetentionSettingsModel};
+use crate::config::signups::{SignupSettings, SignupSettingsModel};
 use crate::config::tokens::{
     JwtSettings, JwtSettingsModel, NAME_ACCESS_SECRET, NAME_REFRESH_SECRET,
 };
+use crate::config::usernames::{UsernameSettings, UsernameSettingsModel};
 use config::{Config, ConfigError};
 use secrecy::Secret;
 use serde::Deserialize;
@@ -11,9 +15,14 @@ use std::env;
 use tracing::{error, info, warn};
 
 pub mod app;
+pub mod check;
 pub mod database;
 pub mod environment;
+pub mod passwords;
+pub mod retention;
+pub mod signups;
 pub mod tokens;
+pub mod usernames;
 
 const CONFIG_DIR: &str = "configuration";
 const CONFIG_FILE_NAME: &str = "settings.toml";
@@ -23,6 +32,10 @@ pub struct SettingsModel {
     pub app: Option<ApplicationSettingsModel>,
     pub jwt: Option<JwtSettingsModel>,
     pub postgres: Option<PostgresSettingsModel>,
+    pub passwords: Option<PasswordSettingsModel>,
+    pub usernames: Option<UsernameSettingsModel>,
+    pub retention: Option<RetentionSettingsModel>,
+    pub signups: Option<SignupSettingsModel>,
 }
 
 impl SettingsModel {
@@ -45,6 +58,10 @@ pub struct Settings {
     pub app: ApplicationSettings,
     pub jwt: JwtSettings,
     pub postgres: PostgresSettings,
+    pub passwords: PasswordSettings,
+    pub usernames: UsernameSettings,
+    pub retention: RetentionSettings,
+    pub signups: SignupSettings,
     pub environment: Environment,
 }
 
@@ -75,10 +92,46 @@ impl Settings {
             |x| x.to_settings(),
         );
 
+        let passwords = model.passwords.map_or_else(
+            || {
+                warn!("Using default `passwords` settings!");
+                PasswordSettings::default()
+            },
+            |x| x.to_settings(),
+        );
+
+        let usernames = model.usernames.map_or_else(
+            || {
+                warn!("Using default `usernames` settings!");
+                UsernameSettings::default()
+            },
+            |x| x.to_settings(),
+        );
+
+        let retention = model.retention.map_or_else(
+            || {
+                warn!("Using default `retention` settings!");
+                RetentionSettings::default()
+            },
+            |x| x.to_settings(),
+        );
+
+        let signups = model.signups.map_or_else(
+            || {
+                warn!("Using default `signups` settings!");
+                SignupSettings::default()
+            },
+            |x| x.to_settings(),
+        );
+
         return Self {
             app,
             jwt,
             postgres,
+            passwords,
+            usernames,
+            retention,
+            signups,
             environment: Environment::Development,
         };
     }
@@ -88,6 +141,10 @@ impl Settings {
             app: ApplicationSettings::from_env(),
             jwt: JwtSettings::from_env(),
             postgres: PostgresSettings::from_env(),
+            passwords: PasswordSettings::from_env(),
+            usernames: UsernameSettings::from_env(),
+            retention: RetentionSettings::from_env(),
+            signups: SignupSettings::from_env(),
             environment: Environment::Production,
         }
     }
@@ -98,12 +155,20 @@ impl Default for Settings {
         let app = ApplicationSettings::default();
         let jwt = JwtSettings::default();
         let postgres = PostgresSettings::default();
+        let passwords = PasswordSettings::default();
+        let usernames = UsernameSettings::default();
+        let retention = RetentionSettings::default();
+        let signups = SignupSettings::default();
         let environment = Environment::default();
 
         Self {
             app,
             jwt,
             postgres,
+            passwords,
+            usernames,
+            retention,
+            signups,
             environment,
         }
     }
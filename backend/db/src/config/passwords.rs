@@ -0,0 +1,77 @@
+use crate::config::try_get_env;
+use anyhow::anyhow;
+use argon2::{Algorithm, Argon2, Params, Version};
+use serde::Deserialize;
+use tracing::warn;
+
+pub const NAME_ARGON2_MEMORY_COST: &str = "ARGON2_MEMORY_COST";
+pub const NAME_ARGON2_ITERATIONS: &str = "ARGON2_ITERATIONS";
+pub const NAME_ARGON2_PARALLELISM: &str = "ARGON2_PARALLELISM";
+
+#[derive(Deserialize)]
+pub struct PasswordSettingsModel {
+    pub memory_cost: Option<u32>,
+    pub iterations: Option<u32>,
+    pub parallelism: Option<u32>,
+}
+
+impl PasswordSettingsModel {
+    pub fn to_settings(self) -> PasswordSettings {
+        let default = PasswordSettings::default();
+        let settings = PasswordSettings {
+            memory_cost: self.memory_cost.unwrap_or(default.memory_cost),
+            iterations: self.iterations.unwrap_or(default.iterations),
+            parallelism: self.parallelism.unwrap_or(default.parallelism),
+        };
+        settings.hasher().expect("Invalid argon2 parameters");
+        settings
+    }
+}
+
+/// Argon2id parameters used for new password hashes.
+///
+/// Hashes made with weaker parameters are replaced at login.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PasswordSettings {
+    /// Memory size in KiB
+    pub memory_cost: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl PasswordSettings {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let get = |name: &str, default: u32| {
+            try_get_env(name).map_or(default, |x| {
+                warn!("Using custom {name}");
+                x.parse::<u32>().expect("Invalid argon2 parameter")
+            })
+        };
+
+        let settings = Self {
+            memory_cost: get(NAME_ARGON2_MEMORY_COST, default.memory_cost),
+            iterations: get(NAME_ARGON2_ITERATIONS, default.iterations),
+            parallelism: get(NAME_ARGON2_PARALLELISM, default.parallelism),
+        };
+        settings.hasher().expect("Invalid argon2 parameters");
+        settings
+    }
+
+    pub fn hasher(&self) -> anyhow::Result<Argon2<'static>> {
+        let params = Params::new(self.memory_cost, self.iterations, self.parallelism, None)
+            .map_err(|e| anyhow!(e).context("invalid argon2 parameters"))?;
+
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+}
+
+impl Default for PasswordSettings {
+    fn default() -> Self {
+        Self {
+            memory_cost: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use crate::config::try_get_env;
+use bimetable_domain::api::admin::RetentionPolicy;
+use serde::Deserialize;
+use time::{Duration, OffsetDateTime};
+use tracing::warn;
+
+pub const NAME_RETENTION_TRASH_DAYS: &str = "RETENTION_TRASH_DAYS";
+pub const NAME_RETENTION_BLACKLIST_DAYS: &str = "RETENTION_BLACKLIST_DAYS";
+pub const NAME_RETENTION_AUDIT_LOG_DAYS: &str = "RETENTION_AUDIT_LOG_DAYS";
+pub const NAME_RETENTION_INVITATION_EXPIRY_DAYS: &str = "RETENTION_INVITATION_EXPIRY_DAYS";
+
+const DEFAULT_BLACKLIST_DAYS: u32 = 1;
+
+#[derive(Deserialize)]
+pub struct RetentionSettingsModel {
+    pub trash_days: Option<u32>,
+    pub blacklist_days: Option<u32>,
+    pub audit_log_days: Option<u32>,
+    pub invitation_expiry_days: Option<u32>,
+}
+
+impl RetentionSettingsModel {
+    pub fn to_settings(self) -> RetentionSettings {
+        let default = RetentionSettings::default();
+        let settings = RetentionSettings {
+            trash_days: self.trash_days.or(default.trash_days),
+            blacklist_days: self.blacklist_days.unwrap_or(default.blacklist_days),
+            audit_log_days: self.audit_log_days.or(default.audit_log_days),
+            invitation_expiry_days: self
+                .invitation_expiry_days
+                .or(default.invitation_expiry_days),
+        };
+        settings.check().expect("Invalid retention settings");
+        settings
+    }
+}
+
+/// How long data is kept before the purge job removes it, in days.
+///
+/// Data without a limit is kept until it is removed by hand.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetentionSettings {
+    /// Soft deleted events, counted from their deletion
+    pub trash_days: Option<u32>,
+    /// Revoked tokens, counted from their expiry after which they are rejected anyway
+    pub blacklist_days: u32,
+    /// Dispatched change messages of the outbox, counted from the change
+    pub audit_log_days: Option<u32>,
+    /// Unanswered invitations, counted from sending
+    pub invitation_expiry_days: Option<u32>,
+}
+
+impl RetentionSettings {
+    pub fn from_env() -> Self {
+        let get = |name: &str| {
+            try_get_env(name).map(|x| {
+                warn!("Using custom {name}");
+                x.parse::<u32>().expect("Invalid retention days")
+            })
+        };
+
+        let default = Self::default();
+        let settings = Self {
+            trash_days: get(NAME_RETENTION_TRASH_DAYS).or(default.trash_days),
+            blacklist_days: get(NAME_RETENTION_BLACKLIST_DAYS).unwrap_or(default.blacklist_days),
+            audit_log_days: get(NAME_RETENTION_AUDIT_LOG_DAYS).or(default.audit_log_days),
+            invitation_expiry_days: get(NAME_RETENTION_INVITATION_EXPIRY_DAYS)
+                .or(default.invitation_expiry_days),
+        };
+        settings.check().expect("Invalid retention settings");
+        settings
+    }
+
+    fn check(&self) -> Result<(), String> {
+        let limits = [
+            ("trash", self.trash_days),
+            ("audit log", self.audit_log_days),
+            ("invitation expiry", self.invitation_expiry_days),
+        ];
+        for (name, days) in limits {
+            if days == Some(0) {
+                return Err(format!("Retention of {name} must be at least one day"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Moment before which data kept for `days` is purged.
+    pub fn cutoff(days: u32, now: OffsetDateTime) -> OffsetDateTime {
+        now - Duration::days(days.into())
+    }
+}
+
+impl Default for RetentionSettings {
+    fn default() -> Self {
+        Self {
+            trash_days: None,
+            blacklist_days: DEFAULT_BLACKLIST_DAYS,
+            audit_log_days: None,
+            invitation_expiry_days: None,
+        }
+    }
+}
+
+impl From<&RetentionSettings> for RetentionPolicy {
+    fn from(settings: &RetentionSettings) -> Self {
+        Self {
+            trash_days: settings.trash_days,
+            blacklist_days: settings.blacklist_days,
+            audit_log_days: settings.audit_log_days,
+            invitation_expiry_days: settings.invitation_expiry_days,
+        }
+    }
+}
+
+#[cfg(test)]
+mod retention_tests {
+    use super::*;
+
+    #[test]
+    fn data_is_kept_unless_limited() {
+        let settings = RetentionSettingsModel {
+            trash_days: Some(30),
+            blacklist_days: None,
+            audit_log_days: None,
+            invitation_expiry_days: None,
+        }
+        .to_settings();
+
+        assert_eq!(
+            settings,
+            RetentionSettings {
+                trash_days: Some(30),
+                ..RetentionSettings::default()
+            }
+        );
+    }
+
+    #[test]
+    fn limits_are_at_least_a_day() {
+        let settings = RetentionSettings {
+            audit_log_days: Some(0),
+            ..RetentionSettings::default()
+        };
+        assert!(settings.check().is_err());
+        assert!(RetentionSettings::default().check().is_ok());
+    }
+}
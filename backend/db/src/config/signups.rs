@@ -0,0 +1,138 @@
+use crate::config::try_get_env;
+use serde::Deserialize;
+use tracing::warn;
+
+pub const NAME_SIGNUP_ENABLED: &str = "SIGNUP_ENABLED";
+pub const NAME_SIGNUP_INVITE_ONLY: &str = "SIGNUP_INVITE_ONLY";
+pub const NAME_SIGNUP_EMAIL_DOMAINS: &str = "SIGNUP_EMAIL_DOMAINS";
+
+#[derive(Deserialize)]
+pub struct SignupSettingsModel {
+    pub enabled: Option<bool>,
+    pub invite_only: Option<bool>,
+    pub email_domains: Option<Vec<String>>,
+}
+
+impl SignupSettingsModel {
+    pub fn to_settings(self) -> SignupSettings {
+        let default = SignupSettings::default();
+        let settings = SignupSettings {
+            enabled: self.enabled.unwrap_or(default.enabled),
+            invite_only: self.invite_only.unwrap_or(default.invite_only),
+            email_domains: self
+                .email_domains
+                .map_or(default.email_domains, normalize_domains),
+        };
+        settings.check().expect("Invalid signup settings");
+        settings
+    }
+}
+
+/// Who may register on the instance by themselves.
+///
+/// Accounts provisioned by admins or SCIM are not subject to the policy.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignupSettings {
+    /// Whether registration is open at all
+    pub enabled: bool,
+    /// Whether registering requires a signup code minted by an admin
+    pub invite_only: bool,
+    /// Domains of the logins which may register, any domain when empty
+    pub email_domains: Vec<String>,
+}
+
+impl SignupSettings {
+    pub fn from_env() -> Self {
+        let get = |name: &str, default: bool| {
+            try_get_env(name).map_or(default, |x| {
+                warn!("Using custom {name}");
+                x.parse::<bool>().expect("Invalid signup flag")
+            })
+        };
+
+        let default = Self::default();
+        let settings = Self {
+            enabled: get(NAME_SIGNUP_ENABLED, default.enabled),
+            invite_only: get(NAME_SIGNUP_INVITE_ONLY, default.invite_only),
+            email_domains: try_get_env(NAME_SIGNUP_EMAIL_DOMAINS)
+                .map_or(default.email_domains, |x| {
+                    normalize_domains(x.split(',').map(String::from).collect())
+                }),
+        };
+        settings.check().expect("Invalid signup settings");
+        settings
+    }
+
+    fn check(&self) -> Result<(), String> {
+        if let Some(domain) = self
+            .email_domains
+            .iter()
+            .find(|domain| domain.is_empty() || domain.contains('@'))
+        {
+            return Err(format!("Invalid signup email domain {domain:?}"));
+        }
+        Ok(())
+    }
+
+    /// Whether the domain of the login is allowed, domains are compared case-insensitively.
+    pub fn allows_login(&self, login: &str) -> bool {
+        if self.email_domains.is_empty() {
+            return true;
+        }
+        let Some((_, domain)) = login.rsplit_once('@') else {
+            return false;
+        };
+        self.email_domains
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(domain))
+    }
+}
+
+impl Default for SignupSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            invite_only: false,
+            email_domains: vec![],
+        }
+    }
+}
+
+fn normalize_domains(domains: Vec<String>) -> Vec<String> {
+    domains
+        .into_iter()
+        .map(|domain| domain.trim().to_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod signup_tests {
+    use super::*;
+
+    #[test]
+    fn any_domain_is_allowed_unless_limited() {
+        assert!(SignupSettings::default().allows_login("adimac@example.com"));
+
+        let settings = SignupSettingsModel {
+            enabled: None,
+            invite_only: None,
+            email_domains: Some(vec![" School.edu ".to_string()]),
+        }
+        .to_settings();
+
+        assert!(settings.allows_login("adimac@school.EDU"));
+        assert!(!settings.allows_login("adimac@example.com"));
+        assert!(!settings.allows_login("adimac@sub.school.edu"));
+        assert!(!settings.allows_login("adimac"));
+    }
+
+    #[test]
+    fn domains_are_checked() {
+        let settings = SignupSettings {
+            email_domains: vec!["adimac@school.edu".to_string()],
+            ..SignupSettings::default()
+        };
+        assert!(settings.check().is_err());
+        assert!(SignupSettings::default().check().is_ok());
+    }
+}
@@ -0,0 +1,379 @@
+use super::try_get_secret_env;
+use crate::config::{get_env, try_get_env};
+use cookie::SameSite;
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+use time::Duration;
+use tracing::log::warn;
+
+pub const NAME_ACCESS_SECRET: &str = "ACCESS_SECRET";
+pub const NAME_REFRESH_SECRET: &str = "REFRESH_SECRET";
+pub const NAME_INVITATION_SECRET: &str = "INVITATION_SECRET";
+pub const NAME_ACCESS_EXPIRATION: &str = "ACCESS_EXPIRATION";
+pub const NAME_REFRESH_EXPIRATION: &str = "REFRESH_EXPIRATION";
+pub const NAME_COOKIE_DOMAIN: &str = "COOKIE_DOMAIN";
+pub const NAME_COOKIE_SAME_SITE: &str = "COOKIE_SAME_SITE";
+pub const NAME_COOKIE_SECURE: &str = "COOKIE_SECURE";
+
+const DEFAULT_ACCESS_SECRET: &str = "JWT_ACCESS_SECRET";
+const DEFAULT_REFRESH_SECRET: &str = "JWT_REFRESH_SECRET";
+const DEFAULT_INVITATION_SECRET: &str = "INVITATION_SECRET";
+
+/// Shortest secret considered strong, the key length of HMAC-SHA256
+const MIN_SECRET_LENGTH: usize = 32;
+
+const ACCESS_EXPIRATION: Duration = Duration::minutes(5);
+const REFRESH_EXPIRATION: Duration = Duration::days(7);
+const SUPER_EXPIRATION: Duration = Duration::days(2137);
+
+#[derive(Deserialize)]
+pub struct JwtSettingsModel {
+    pub access: Option<TokenDataModel>,
+    pub refresh: Option<TokenDataModel>,
+    /// Secret signing the sign-up links of email invitations
+    pub invitation_secret: Option<Secret<String>>,
+    pub is_super_user: Option<bool>,
+    pub cookie: Option<CookieSettingsModel>,
+}
+
+#[derive(Deserialize)]
+pub struct TokenDataModel {
+    pub token: Option<String>,
+    pub expiration: Option<Duration>,
+}
+
+impl TokenDataModel {
+    fn to_access(self) -> AccessTokenData {
+        AccessTokenData(TokenData::new(
+            &self.token.unwrap_or(DEFAULT_ACCESS_SECRET.to_string()),
+            self.expiration.map_or(ACCESS_EXPIRATION, |expiration| {
+                warn!("Using custom access token expiration of {}", &expiration);
+                expiration
+            }),
+        ))
+    }
+
+    fn to_refresh(self) -> RefreshTokenData {
+        RefreshTokenData(TokenData::new(
+            &self.token.unwrap_or(DEFAULT_REFRESH_SECRET.to_string()),
+            self.expiration.map_or(REFRESH_EXPIRATION, |expiration| {
+                warn!("Using custom refresh token expiration of {}", &expiration);
+                expiration
+            }),
+        ))
+    }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum CookieSameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl From<CookieSameSite> for SameSite {
+    fn from(val: CookieSameSite) -> Self {
+        match val {
+            CookieSameSite::Strict => SameSite::Strict,
+            CookieSameSite::Lax => SameSite::Lax,
+            CookieSameSite::None => SameSite::None,
+        }
+    }
+}
+
+impl TryFrom<String> for CookieSameSite {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "strict" => Ok(Self::Strict),
+            "lax" => Ok(Self::Lax),
+            "none" => Ok(Self::None),
+            other => Err(format!(
+                "{other} is not supported SameSite. Use `strict`, `lax` or `none`"
+            )),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CookieSettingsModel {
+    pub domain: Option<String>,
+    pub same_site: Option<CookieSameSite>,
+    pub secure: Option<bool>,
+}
+
+impl CookieSettingsModel {
+    pub fn to_settings(self) -> CookieSettings {
+        let default = CookieSettings::default();
+        CookieSettings {
+            domain: self.domain,
+            same_site: self.same_site.map_or(default.same_site, SameSite::from),
+            secure: self.secure.unwrap_or(default.secure),
+        }
+    }
+}
+
+/// Attributes of the cookies carrying auth tokens.
+#[derive(Clone)]
+pub struct CookieSettings {
+    pub domain: Option<String>,
+    pub same_site: SameSite,
+    pub secure: bool,
+}
+
+impl CookieSettings {
+    fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            domain: try_get_env(NAME_COOKIE_DOMAIN),
+            same_site: try_get_env(NAME_COOKIE_SAME_SITE).map_or(default.same_site, |x| {
+                CookieSameSite::try_from(x)
+                    .expect("Invalid cookie SameSite")
+                    .into()
+            }),
+            secure: try_get_env(NAME_COOKIE_SECURE).map_or(default.secure, |x| {
+                x.parse::<bool>().expect("Invalid cookie Secure flag")
+            }),
+        }
+    }
+}
+
+impl Default for CookieSettings {
+    fn default() -> Self {
+        Self {
+            domain: None,
+            same_site: SameSite::Strict,
+            secure: true,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct JwtSettings {
+    pub access: AccessTokenData,
+    pub refresh: RefreshTokenData,
+    /// Secret signing the sign-up links of email invitations, kept apart from the token secrets
+    pub invitation: Secret<String>,
+    pub cookie: CookieSettings,
+}
+
+impl JwtSettings {
+    pub fn new(access: &str, refresh: &str) -> Self {
+        Self {
+            access: AccessTokenData(TokenData::new(access, SUPER_EXPIRATION)),
+            refresh: RefreshTokenData(TokenData::new(refresh, SUPER_EXPIRATION)),
+            invitation: default_invitation_secret(),
+            cookie: CookieSettings::default(),
+        }
+    }
+
+    fn super_user() -> Self {
+        Self {
+            access: AccessTokenData::super_token(),
+            refresh: RefreshTokenData::super_token(),
+            invitation: default_invitation_secret(),
+            cookie: CookieSettings::default(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self {
+            access: AccessTokenData::from_env(),
+            refresh: RefreshTokenData::from_env(),
+            invitation: try_get_secret_env(NAME_INVITATION_SECRET).unwrap_or_else(|| {
+                warn!("Using default invitation secret");
+                default_invitation_secret()
+            }),
+            cookie: CookieSettings::from_env(),
+        }
+    }
+
+    /// Weaknesses of the secrets, none when they are long, distinct and not the defaults.
+    pub fn secret_problems(&self) -> Vec<String> {
+        let access = self.access.0.token.expose_secret();
+        let refresh = self.refresh.0.token.expose_secret();
+        let invitation = self.invitation.expose_secret();
+        let mut problems = vec![];
+        for (name, secret, default) in [
+            ("access", access, DEFAULT_ACCESS_SECRET),
+            ("refresh", refresh, DEFAULT_REFRESH_SECRET),
+            ("invitation", invitation, DEFAULT_INVITATION_SECRET),
+        ] {
+            if secret == default {
+                problems.push(format!("The {name} token secret is the default one"));
+            } else if secret.len() < MIN_SECRET_LENGTH {
+                problems.push(format!(
+                    "The {name} token secret is shorter than {MIN_SECRET_LENGTH} bytes"
+                ));
+            }
+        }
+        if access == refresh {
+            problems.push("Access and refresh tokens share their secret".to_string());
+        }
+        if invitation == access || invitation == refresh {
+            problems.push("Invitations share their secret with tokens".to_string());
+        }
+        problems
+    }
+}
+
+impl Default for JwtSettings {
+    fn default() -> Self {
+        Self {
+            access: AccessTokenData::default(),
+            refresh: RefreshTokenData::default(),
+            invitation: default_invitation_secret(),
+            cookie: CookieSettings::default(),
+        }
+    }
+}
+
+fn default_invitation_secret() -> Secret<String> {
+    Secret::new(DEFAULT_INVITATION_SECRET.to_string())
+}
+
+/// Reads the token expiration in seconds from the environment.
+fn env_expiration(name: &str, default: Duration) -> Duration {
+    try_get_env(name).map_or(default, |x| {
+        Duration::seconds(x.parse::<i64>().expect("Invalid token expiration"))
+    })
+}
+
+#[derive(Clone)]
+pub struct TokenData {
+    pub token: Secret<String>,
+    pub expiration: Duration,
+}
+
+#[derive(Clone)]
+pub struct AccessTokenData(pub TokenData);
+
+impl AccessTokenData {
+    fn super_token() -> Self {
+        Self(TokenData::new(DEFAULT_ACCESS_SECRET, SUPER_EXPIRATION))
+    }
+    fn from_env() -> Self {
+        Self(TokenData::new(
+            &get_env(NAME_ACCESS_SECRET),
+            env_expiration(NAME_ACCESS_EXPIRATION, ACCESS_EXPIRATION),
+        ))
+    }
+}
+
+impl Default for AccessTokenData {
+    fn default() -> Self {
+        Self(TokenData::new(DEFAULT_ACCESS_SECRET, ACCESS_EXPIRATION))
+    }
+}
+
+#[derive(Clone)]
+pub struct RefreshTokenData(pub TokenData);
+
+impl RefreshTokenData {
+    fn super_token() -> Self {
+        Self(TokenData::new(DEFAULT_REFRESH_SECRET, SUPER_EXPIRATION))
+    }
+    fn from_env() -> Self {
+        Self(TokenData::new(
+            &get_env(NAME_REFRESH_SECRET),
+            env_expiration(NAME_REFRESH_EXPIRATION, REFRESH_EXPIRATION),
+        ))
+    }
+}
+
+impl Default for RefreshTokenData {
+    fn default() -> Self {
+        Self(TokenData::new(DEFAULT_REFRESH_SECRET, REFRESH_EXPIRATION))
+    }
+}
+
+impl TokenData {
+    fn new(token: &str, expiration: Duration) -> Self {
+        Self {
+            token: Secret::new(token.to_owned()),
+            expiration,
+        }
+    }
+}
+
+impl JwtSettingsModel {
+    pub fn to_settings(self) -> JwtSettings {
+        let cookie = self
+            .cookie
+            .map_or_else(CookieSettings::default, |x| x.to_settings());
+
+        let invitation = self.invitation_secret.unwrap_or_else(|| {
+            warn!("Using default invitation secret");
+            default_invitation_secret()
+        });
+
+        if self.is_super_user.unwrap_or(false) {
+            warn!("Using super tokens");
+            return JwtSettings {
+                invitation,
+                cookie,
+                ..JwtSettings::super_user()
+            };
+        }
+
+        let access = self.access.map_or_else(
+            || {
+                warn!("Using default access token");
+                AccessTokenData::default()
+            },
+            |t| t.to_access(),
+        );
+
+        let refresh = self.refresh.map_or_else(
+            || {
+                warn!("Using default refresh token");
+                RefreshTokenData::default()
+            },
+            |t| t.to_refresh(),
+        );
+
+        JwtSettings {
+            access,
+            refresh,
+            invitation,
+            cookie,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tokens_tests {
+    use super::*;
+
+    #[test]
+    fn weak_secrets_are_reported() {
+        let strong = JwtSettings {
+            invitation: Secret::new("i".repeat(32)),
+            ..JwtSettings::new(&"a".repeat(32), &"r".repeat(32))
+        };
+        assert!(strong.secret_problems().is_empty());
+
+        assert_eq!(JwtSettings::default().secret_problems().len(), 3);
+        assert_eq!(
+            JwtSettings::new("short", "short").secret_problems().len(),
+            4
+        );
+
+        let shared = JwtSettings {
+            invitation: Secret::new("a".repeat(32)),
+            ..JwtSettings::new(&"a".repeat(32), &"r".repeat(32))
+        };
+        assert_eq!(shared.secret_problems().len(), 1);
+    }
+
+    #[test]
+    fn same_site_from_string() {
+        assert!(matches!(
+            CookieSameSite::try_from("None".to_string()),
+            Ok(CookieSameSite::None)
+        ));
+        assert!(CookieSameSite::try_from("sometimes".to_string()).is_err());
+    }
+}
@@ -0,0 +1,161 @@
+use crate::config::try_get_env;
+use serde::Deserialize;
+use tracing::warn;
+
+pub const NAME_USERNAME_SCRIPTS: &str = "USERNAME_SCRIPTS";
+pub const NAME_USERNAME_MIN_LENGTH: &str = "USERNAME_MIN_LENGTH";
+pub const NAME_USERNAME_MAX_LENGTH: &str = "USERNAME_MAX_LENGTH";
+pub const NAME_USERNAME_MIXED_SCRIPTS: &str = "USERNAME_MIXED_SCRIPTS";
+
+const DEFAULT_MIN_LENGTH: usize = 4;
+const DEFAULT_MAX_LENGTH: usize = 20;
+
+#[derive(Deserialize)]
+pub struct UsernameSettingsModel {
+    pub scripts: Option<Vec<Script>>,
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    pub mixed_scripts: Option<bool>,
+}
+
+impl UsernameSettingsModel {
+    pub fn to_settings(self) -> UsernameSettings {
+        let default = UsernameSettings::default();
+        let settings = UsernameSettings {
+            scripts: self.scripts.unwrap_or(default.scripts),
+            min_length: self.min_length.unwrap_or(default.min_length),
+            max_length: self.max_length.unwrap_or(default.max_length),
+            mixed_scripts: self.mixed_scripts.unwrap_or(default.mixed_scripts),
+        };
+        settings.check().expect("Invalid username settings");
+        settings
+    }
+}
+
+/// Policy of logins and usernames, which are NFC normalized before it is applied.
+///
+/// Without mixed scripts, names may not combine letters of different scripts,
+/// which keeps out homoglyphs like a Greek `ο` within Latin letters.
+/// Han, kana and Hangul may still be written together with Latin.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UsernameSettings {
+    /// Scripts of which letters may be used, digits and ASCII punctuation are always allowed
+    pub scripts: Vec<Script>,
+    /// Length range in characters
+    pub min_length: usize,
+    pub max_length: usize,
+    pub mixed_scripts: bool,
+}
+
+impl UsernameSettings {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let get = |name: &str, default: usize| {
+            try_get_env(name).map_or(default, |x| {
+                warn!("Using custom {name}");
+                x.parse::<usize>().expect("Invalid username length")
+            })
+        };
+
+        let settings = Self {
+            scripts: try_get_env(NAME_USERNAME_SCRIPTS).map_or(default.scripts, |x| {
+                x.split(',')
+                    .map(|script| Script::try_from(script.trim()).expect("Invalid username script"))
+                    .collect()
+            }),
+            min_length: get(NAME_USERNAME_MIN_LENGTH, default.min_length),
+            max_length: get(NAME_USERNAME_MAX_LENGTH, default.max_length),
+            mixed_scripts: try_get_env(NAME_USERNAME_MIXED_SCRIPTS).map_or(
+                default.mixed_scripts,
+                |x| {
+                    x.parse::<bool>()
+                        .expect("Invalid username mixed scripts flag")
+                },
+            ),
+        };
+        settings.check().expect("Invalid username settings");
+        settings
+    }
+
+    fn check(&self) -> Result<(), String> {
+        if self.scripts.is_empty() {
+            return Err("No username scripts allowed".to_string());
+        }
+        if self.min_length == 0 || self.min_length > self.max_length {
+            return Err(format!(
+                "Invalid username length range {}..={}",
+                self.min_length, self.max_length
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for UsernameSettings {
+    fn default() -> Self {
+        Self {
+            scripts: vec![Script::Latin],
+            min_length: DEFAULT_MIN_LENGTH,
+            max_length: DEFAULT_MAX_LENGTH,
+            mixed_scripts: false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+    Hebrew,
+    Arabic,
+    Han,
+    Kana,
+    Hangul,
+}
+
+impl Script {
+    /// Script of a character, `None` for characters of no supported script.
+    ///
+    /// ASCII characters count as Latin.
+    pub fn of(c: char) -> Option<Self> {
+        match c as u32 {
+            0x0000..=0x02AF | 0x1E00..=0x1EFF => Some(Self::Latin),
+            0x0370..=0x03FF | 0x1F00..=0x1FFF => Some(Self::Greek),
+            0x0400..=0x052F => Some(Self::Cyrillic),
+            0x0590..=0x05FF => Some(Self::Hebrew),
+            0x0600..=0x06FF => Some(Self::Arabic),
+            0x3400..=0x4DBF | 0x4E00..=0x9FFF => Some(Self::Han),
+            0x3040..=0x30FF => Some(Self::Kana),
+            0x1100..=0x11FF | 0xAC00..=0xD7AF => Some(Self::Hangul),
+            _ => None,
+        }
+    }
+
+    /// Whether letters of both scripts may form a single name without mixed scripts.
+    pub fn is_combinable_with(self, other: Self) -> bool {
+        let is_east_asian = |script: Self| matches!(script, Self::Han | Self::Kana | Self::Hangul);
+        self == other
+            || ((is_east_asian(self) || self == Self::Latin)
+                && (is_east_asian(other) || other == Self::Latin))
+    }
+}
+
+impl TryFrom<&str> for Script {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "latin" => Ok(Self::Latin),
+            "greek" => Ok(Self::Greek),
+            "cyrillic" => Ok(Self::Cyrillic),
+            "hebrew" => Ok(Self::Hebrew),
+            "arabic" => Ok(Self::Arabic),
+            "han" => Ok(Self::Han),
+            "kana" => Ok(Self::Kana),
+            "hangul" => Ok(Self::Hangul),
+            other => Err(format!("Unknown script {other}")),
+        }
+    }
+}
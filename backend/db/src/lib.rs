@@ -0,0 +1,7 @@
+//! Persistence of Bimetable: configuration, Postgres queries and the services built on them.
+//!
+//! Errors of the services don't know about HTTP, the server maps them to responses.
+
+pub mod config;
+pub mod modules;
+pub mod utils;
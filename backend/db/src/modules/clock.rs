@@ -0,0 +1,63 @@
+use std::sync::{Arc, Mutex};
+
+use time::{Duration, OffsetDateTime};
+
+/// Source of the current time for time dependent logic, e.g. deletions, token expiry and reminders.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> OffsetDateTime;
+}
+
+/// Clock shared by the app state, handlers and jobs.
+pub type SharedClock = Arc<dyn Clock>;
+
+/// Wall-clock time, used outside of tests.
+#[derive(Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::now_utc()
+    }
+}
+
+/// Clock standing still until it is moved, so that tests control the time.
+#[derive(Clone)]
+pub struct TestClock(Arc<Mutex<OffsetDateTime>>);
+
+impl TestClock {
+    pub fn new(now: OffsetDateTime) -> Self {
+        Self(Arc::new(Mutex::new(now)))
+    }
+
+    pub fn set(&self, now: OffsetDateTime) {
+        *self.0.lock().unwrap() = now;
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.0.lock().unwrap() += by;
+    }
+}
+
+impl Clock for TestClock {
+    fn now(&self) -> OffsetDateTime {
+        *self.0.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod clock_tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_clock_moves_only_when_told() {
+        let clock = TestClock::new(datetime!(2023-03-01 12:00 UTC));
+        assert_eq!(clock.now(), datetime!(2023-03-01 12:00 UTC));
+
+        clock.advance(Duration::days(1));
+        assert_eq!(clock.now(), datetime!(2023-03-02 12:00 UTC));
+
+        clock.set(datetime!(2020-01-01 0:00 UTC));
+        assert_eq!(clock.now(), datetime!(2020-01-01 0:00 UTC));
+    }
+}
@@ -0,0 +1,298 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::config::database::PostgresSettings;
+use sqlx::migrate::{Migrate, MigrateError, Migrator};
+use sqlx::postgres::PgConnectOptions;
+pub use sqlx::PgPool;
+use sqlx::{migrate, query, query_as, query_scalar, ConnectOptions, PgConnection};
+use tracing::info;
+use uuid::Uuid;
+
+/// Connects to Postgres, sqlx logs the statements with `sql_logging`.
+pub async fn get_postgres_pool(config: PostgresSettings, sql_logging: bool) -> PgPool {
+    info!("Connecting to Postgres database");
+    let options = connect_options(&config.database_url, sql_logging)
+        .expect("Invalid postgres connection string");
+    let pool = PgPool::connect_with(options)
+        .await
+        .expect("Cannot establish postgres connection");
+    info!("Postgres Connection established");
+    pool
+}
+
+/// Options of the Postgres pool, sqlx logs statements only when enabled.
+pub fn connect_options(database_url: &str, enabled: bool) -> Result<PgConnectOptions, sqlx::Error> {
+    let mut options = PgConnectOptions::from_str(database_url)?;
+    if !enabled {
+        options.disable_statement_logging();
+    }
+    Ok(options)
+}
+
+/// Applies the embedded migrations, returning versions of the newly applied ones.
+///
+/// Holds a Postgres advisory lock for the whole run, so that replicas starting at once
+/// don't race each other.
+pub async fn run_migrations(pool: &PgPool) -> Result<Vec<i64>, MigrateError> {
+    let mut migrator = migrate!("../migrations");
+    migrator.set_locking(false);
+
+    let mut conn = pool.acquire().await?;
+    conn.lock().await?;
+    let res = apply_migrations(&migrator, &mut conn).await;
+    conn.unlock().await?;
+
+    res
+}
+
+async fn apply_migrations(
+    migrator: &Migrator,
+    conn: &mut PgConnection,
+) -> Result<Vec<i64>, MigrateError> {
+    conn.ensure_migrations_table().await?;
+    let applied: HashSet<i64> = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect();
+
+    migrator.run(&mut *conn).await?;
+
+    let versions: Vec<i64> = migrator
+        .iter()
+        .filter(|migration| {
+            !migration.migration_type.is_down_migration() && !applied.contains(&migration.version)
+        })
+        .map(|migration| {
+            info!(
+                "Applied migration {} ({})",
+                migration.version, migration.description
+            );
+            migration.version
+        })
+        .collect();
+
+    if versions.is_empty() {
+        info!("Database schema is up to date");
+    }
+
+    Ok(versions)
+}
+
+/// Tables queried by the server, checked on top of the migration state.
+const REQUIRED_TABLES: [&str; 26] = [
+    "users",
+    "credentials",
+    "jwt_blacklist",
+    "events",
+    "recurrence_rules",
+    "event_overrides",
+    "event_override_participants",
+    "user_events",
+    "user_event_invitations",
+    "event_tokens",
+    "jobs",
+    "outbox",
+    "event_feed_tokens",
+    "reminders",
+    "invitation_counters",
+    "busy_visibility",
+    "event_pauses",
+    "instance_stats",
+    "user_archives",
+    "username_history",
+    "quiet_hours",
+    "external_invitations",
+    "event_digests",
+    "locked_occurrences",
+    "signup_codes",
+    "maintenance",
+];
+
+/// Differences between the database schema and the one expected by the server.
+#[derive(Debug, Default, PartialEq)]
+pub struct SchemaReport {
+    /// Embedded migrations which were not applied
+    pub pending: Vec<i64>,
+    /// Applied migrations which differ from the embedded ones
+    pub modified: Vec<i64>,
+    /// Applied migrations unknown to the server
+    pub unknown: Vec<i64>,
+    /// Migrations which failed partway
+    pub dirty: Vec<i64>,
+    pub missing_tables: Vec<String>,
+}
+
+impl SchemaReport {
+    pub fn is_healthy(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Display for SchemaReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_healthy() {
+            return write!(f, "Database schema is healthy");
+        }
+
+        write!(f, "Database schema does not match the server:")?;
+        let versions = [
+            ("pending migrations", &self.pending),
+            ("modified migrations", &self.modified),
+            ("unknown migrations", &self.unknown),
+            ("dirty migrations", &self.dirty),
+        ];
+        for (name, list) in versions {
+            if !list.is_empty() {
+                write!(f, "\n - {name}: {list:?}")?;
+            }
+        }
+        if !self.missing_tables.is_empty() {
+            write!(f, "\n - missing tables: {:?}", self.missing_tables)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Compares the database with the embedded migrations and the tables required by the server.
+///
+/// Only reads the database, so it can be used when migrations are run elsewhere.
+pub async fn check_schema(pool: &PgPool) -> Result<SchemaReport, sqlx::Error> {
+    let migrator = migrate!("../migrations");
+    let mut report = SchemaReport::default();
+
+    let has_migrations_table: bool =
+        query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+            .fetch_one(pool)
+            .await?;
+    let applied: Vec<(i64, Vec<u8>, bool)> = if has_migrations_table {
+        query_as("SELECT version, checksum, success FROM _sqlx_migrations ORDER BY version")
+            .fetch_all(pool)
+            .await?
+    } else {
+        Vec::new()
+    };
+
+    let embedded: HashMap<i64, &[u8]> = migrator
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .map(|migration| (migration.version, migration.checksum.as_ref()))
+        .collect();
+    let applied_versions: HashSet<i64> = applied.iter().map(|(version, ..)| *version).collect();
+
+    for (version, checksum, success) in &applied {
+        match embedded.get(version) {
+            None => report.unknown.push(*version),
+            Some(expected) if *expected != checksum.as_slice() => report.modified.push(*version),
+            Some(_) => (),
+        }
+        if !success {
+            report.dirty.push(*version);
+        }
+    }
+    report.pending = embedded
+        .keys()
+        .filter(|version| !applied_versions.contains(version))
+        .copied()
+        .collect();
+    report.pending.sort();
+
+    report.missing_tables = query_scalar(
+        "SELECT name FROM UNNEST(CAST($1 AS TEXT[])) AS name WHERE to_regclass(name) IS NULL",
+    )
+    .bind(&REQUIRED_TABLES[..])
+    .fetch_all(pool)
+    .await?;
+
+    Ok(report)
+}
+
+/// Raised by a trigger when the owner of an event is added as its participant.
+pub const OWNER_PARTICIPANT_CONSTRAINT: &str = "user_events_not_owner";
+/// Raised by a trigger when an invitation is sent to a participant of the event.
+pub const PARTICIPANT_INVITATION_CONSTRAINT: &str = "invitations_not_participant";
+
+/// Name of the database constraint violated by a failed query.
+pub fn violated_constraint(e: &sqlx::Error) -> Option<&str> {
+    e.as_database_error()?.constraint()
+}
+
+/// Time conflicting bulk operations wait for each other before giving up
+pub const LOCK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+/// SQLSTATE of locks not acquired within `lock_timeout`
+const LOCK_NOT_AVAILABLE: &str = "55P03";
+
+/// Takes the advisory lock of the id until the end of the transaction,
+/// waiting at most [`LOCK_TIMEOUT`] for the transaction holding it.
+///
+/// Bulk operations lock the events they change, so that conflicting ones queue up instead of interleaving.
+/// Other queries don't wait for these locks.
+pub async fn lock_for_transaction(conn: &mut PgConnection, id: Uuid) -> Result<(), sqlx::Error> {
+    query(&format!(
+        "SET LOCAL lock_timeout = '{}ms'",
+        LOCK_TIMEOUT.as_millis()
+    ))
+    .execute(&mut *conn)
+    .await?;
+    query("SELECT pg_advisory_xact_lock($1)")
+        .bind(advisory_key(id))
+        .execute(&mut *conn)
+        .await?;
+    // Row locks taken later in the transaction wait as usual
+    query("SET LOCAL lock_timeout TO DEFAULT")
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Folds the id into the 64 bit key of advisory locks, colliding ids merely wait for each other.
+fn advisory_key(id: Uuid) -> i64 {
+    let id = id.as_u128();
+    ((id >> 64) as u64 ^ id as u64) as i64
+}
+
+/// Whether the query failed waiting for a lock held by another transaction.
+pub fn is_lock_timeout(e: &sqlx::Error) -> bool {
+    e.as_database_error()
+        .and_then(|e| e.code())
+        .is_some_and(|code| code == LOCK_NOT_AVAILABLE)
+}
+
+pub struct PgQuery<'c, T> {
+    pub payload: T,
+    pub conn: &'c mut PgConnection,
+}
+
+impl<'c, T> PgQuery<'c, T>
+where
+    T: Send + Sync,
+{
+    pub fn new(payload: T, conn: &'c mut PgConnection) -> Self {
+        Self { payload, conn }
+    }
+}
+
+/// Id of a user in arguments of queries, so that it can't be swapped with an [`EventId`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UserId(pub Uuid);
+
+/// Id of an event in arguments of queries, so that it can't be swapped with a [`UserId`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EventId(pub Uuid);
+
+impl Display for UserId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Display for EventId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
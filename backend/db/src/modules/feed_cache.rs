@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use http::header::{IF_MODIFIED_SINCE, IF_NONE_MATCH};
+use http::HeaderMap;
+use time::format_description::FormatItem;
+use time::macros::format_description;
+use time::{Date, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+use tracing::{error, trace};
+use uuid::Uuid;
+
+/// Most feeds kept, older ones are dropped when the cache fills up.
+const MAX_ENTRIES: usize = 1000;
+const HTTP_DATE: &[FormatItem<'_>] = format_description!(
+    "[weekday repr:short], [day] [month repr:short] [year] [hour]:[minute]:[second] GMT"
+);
+
+/// Version of the content of an event feed.
+///
+/// It changes with every recorded change of the event and every day, as the feed horizon moves along.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeedVersion {
+    pub event_id: Uuid,
+    /// Time of the latest change of the event
+    pub changed_at: Option<OffsetDateTime>,
+    pub day: Date,
+}
+
+impl FeedVersion {
+    pub fn etag(&self) -> String {
+        let changed_at = self
+            .changed_at
+            .map_or(0, |changed_at| changed_at.unix_timestamp_nanos());
+        format!("\"{}-{changed_at:x}-{}\"", self.event_id.simple(), self.day)
+    }
+
+    /// Time since which the content is the same, with the precision of HTTP dates.
+    pub fn last_modified(&self) -> OffsetDateTime {
+        let day_start = PrimitiveDateTime::new(self.day, Time::MIDNIGHT).assume_utc();
+        let last_modified = self
+            .changed_at
+            .map_or(day_start, |changed_at| changed_at.max(day_start));
+        last_modified.replace_nanosecond(0).unwrap_or(last_modified)
+    }
+}
+
+/// Generated iCalendar body of a feed with its validators.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedFeed {
+    pub etag: String,
+    pub last_modified: OffsetDateTime,
+    pub body: String,
+}
+
+impl CachedFeed {
+    pub fn new(version: &FeedVersion, body: String) -> Self {
+        Self {
+            etag: version.etag(),
+            last_modified: version.last_modified(),
+            body,
+        }
+    }
+
+    /// Whether the client already has this feed, as told by its conditional request headers.
+    ///
+    /// `If-None-Match` takes precedence over `If-Modified-Since`.
+    pub fn is_fresh(&self, headers: &HeaderMap) -> bool {
+        if let Some(tags) = headers.get(IF_NONE_MATCH) {
+            return tags.to_str().is_ok_and(|tags| {
+                tags.split(',')
+                    .map(str::trim)
+                    .any(|tag| tag == "*" || tag.trim_start_matches("W/") == self.etag)
+            });
+        }
+        headers
+            .get(IF_MODIFIED_SINCE)
+            .and_then(|since| since.to_str().ok())
+            .and_then(parse_http_date)
+            .is_some_and(|since| self.last_modified <= since)
+    }
+}
+
+/// Generated feeds of events, reused until their content version changes.
+///
+/// Calendar apps poll feeds every few minutes, while events change rarely.
+#[derive(Clone, Default)]
+pub struct FeedCache(Arc<Mutex<HashMap<(Uuid, Uuid), CachedFeed>>>);
+
+impl FeedCache {
+    /// Gets the feed of the event generated for the user, if it is still of the given version.
+    pub fn get(&self, user_id: Uuid, version: &FeedVersion) -> Option<CachedFeed> {
+        let entries = self.0.lock().ok()?;
+        let feed = entries.get(&(user_id, version.event_id))?;
+        if feed.etag != version.etag() {
+            return None;
+        }
+        trace!("Reusing cached feed of event {}", version.event_id);
+        Some(feed.clone())
+    }
+
+    pub fn insert(&self, user_id: Uuid, event_id: Uuid, feed: CachedFeed) {
+        let Ok(mut entries) = self.0.lock() else {
+            error!("Feed cache is poisoned");
+            return;
+        };
+        if entries.len() >= MAX_ENTRIES && !entries.contains_key(&(user_id, event_id)) {
+            entries.clear();
+        }
+        entries.insert((user_id, event_id), feed);
+    }
+}
+
+/// Formats the time as an HTTP date, e.g. `Wed, 08 Mar 2023 09:45:00 GMT`.
+pub fn http_date(at: OffsetDateTime) -> String {
+    at.to_offset(UtcOffset::UTC)
+        .format(HTTP_DATE)
+        .unwrap_or_default()
+}
+
+fn parse_http_date(date: &str) -> Option<OffsetDateTime> {
+    PrimitiveDateTime::parse(date, HTTP_DATE)
+        .ok()
+        .map(PrimitiveDateTime::assume_utc)
+}
+
+#[cfg(test)]
+mod feed_cache_tests {
+    use http::HeaderValue;
+    use time::macros::{date, datetime};
+
+    use super::*;
+
+    const USER_ID: Uuid = uuid::uuid!("29e40c2a-7595-42d3-98e8-9fe93ce99972");
+    const EVENT_ID: Uuid = uuid::uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
+
+    fn version(changed_at: Option<OffsetDateTime>) -> FeedVersion {
+        FeedVersion {
+            event_id: EVENT_ID,
+            changed_at,
+            day: date!(2023 - 03 - 08),
+        }
+    }
+
+    #[test]
+    fn feeds_are_reused_until_their_version_changes() {
+        let cache = FeedCache::default();
+        let old = version(Some(datetime!(2023-03-08 9:45:00.5 UTC)));
+        cache.insert(
+            USER_ID,
+            EVENT_ID,
+            CachedFeed::new(&old, "BEGIN".to_string()),
+        );
+
+        assert!(cache.get(USER_ID, &old).is_some());
+        assert!(cache.get(Uuid::nil(), &old).is_none());
+        assert!(cache
+            .get(USER_ID, &version(Some(datetime!(2023-03-08 10:00 UTC))))
+            .is_none());
+    }
+
+    #[test]
+    fn feeds_change_daily() {
+        let unchanged = version(Some(datetime!(2023-03-01 12:00 UTC)));
+        assert_eq!(unchanged.last_modified(), datetime!(2023-03-08 0:00 UTC));
+
+        let changed = version(Some(datetime!(2023-03-08 9:45:00.5 UTC)));
+        assert_eq!(changed.last_modified(), datetime!(2023-03-08 9:45 UTC));
+    }
+
+    #[test]
+    fn conditional_headers_are_checked() {
+        let feed = CachedFeed::new(
+            &version(Some(datetime!(2023-03-08 9:45 UTC))),
+            String::new(),
+        );
+        let headers = |name, value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(name, HeaderValue::from_str(value).unwrap());
+            headers
+        };
+
+        assert!(feed.is_fresh(&headers(IF_NONE_MATCH, &feed.etag)));
+        assert!(!feed.is_fresh(&headers(IF_NONE_MATCH, "\"other\"")));
+        assert!(feed.is_fresh(&headers(IF_MODIFIED_SINCE, "Wed, 08 Mar 2023 09:45:00 GMT")));
+        assert!(!feed.is_fresh(&headers(IF_MODIFIED_SINCE, "Wed, 08 Mar 2023 09:44:59 GMT")));
+        assert!(!feed.is_fresh(&HeaderMap::new()));
+    }
+
+    #[test]
+    fn http_dates_round_trip() {
+        let at = datetime!(2023-03-08 9:45 UTC);
+        assert_eq!(http_date(at), "Wed, 08 Mar 2023 09:45:00 GMT");
+        assert_eq!(parse_http_date(&http_date(at)), Some(at));
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{query, query_as, PgConnection, PgPool};
+use time::OffsetDateTime;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::modules::clock::{SharedClock, SystemClock};
+
+const DEFAULT_MAX_ATTEMPTS: i32 = 5;
+const MAX_BACKOFF_EXPONENT: i32 = 12;
+
+/// Job claimed from the queue.
+#[derive(Debug)]
+pub struct Job {
+    pub id: Uuid,
+    pub kind: String,
+    pub payload: Value,
+    pub attempts: i32,
+    pub max_attempts: i32,
+}
+
+impl Job {
+    pub fn payload<T: DeserializeOwned>(&self) -> anyhow::Result<T> {
+        Ok(serde_json::from_value(self.payload.clone())?)
+    }
+}
+
+/// Job waiting to be added to the queue.
+#[derive(Debug)]
+pub struct NewJob {
+    kind: String,
+    payload: Value,
+    run_at: OffsetDateTime,
+    max_attempts: i32,
+}
+
+impl NewJob {
+    pub fn new(kind: &str, payload: impl Serialize) -> anyhow::Result<Self> {
+        Ok(Self {
+            kind: kind.to_string(),
+            payload: serde_json::to_value(payload)?,
+            run_at: OffsetDateTime::now_utc(),
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+        })
+    }
+
+    pub fn run_at(mut self, run_at: OffsetDateTime) -> Self {
+        self.run_at = run_at;
+        self
+    }
+
+    pub fn max_attempts(mut self, max_attempts: i32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+}
+
+/// Adds a job to the queue.
+///
+/// Accepts a connection, so that the job can be enqueued in the same transaction as the change it follows.
+pub async fn enqueue(conn: &mut PgConnection, job: NewJob) -> Result<Uuid, sqlx::Error> {
+    let id = query!(
+        r#"
+            INSERT INTO jobs (kind, payload, run_at, max_attempts)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id
+        "#,
+        job.kind,
+        job.payload,
+        job.run_at,
+        job.max_attempts,
+    )
+    .fetch_one(conn)
+    .await?
+    .id;
+
+    debug!("Enqueued {} job {id}", job.kind);
+    Ok(id)
+}
+
+#[async_trait]
+pub trait JobHandler: Send + Sync + 'static {
+    /// Kind of the jobs processed by this handler.
+    fn kind(&self) -> &'static str;
+
+    async fn handle(&self, job: &Job, pool: &PgPool) -> anyhow::Result<()>;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct JobSettings {
+    /// Time between checks of an empty queue
+    pub poll_interval: std::time::Duration,
+    /// Time after which a claimed, but unfinished job can be claimed again
+    pub visibility_timeout: time::Duration,
+    /// Maximum number of jobs claimed at once
+    pub batch_size: i64,
+}
+
+impl Default for JobSettings {
+    fn default() -> Self {
+        Self {
+            poll_interval: std::time::Duration::from_secs(5),
+            visibility_timeout: time::Duration::minutes(5),
+            batch_size: 10,
+        }
+    }
+}
+
+pub struct JobRunner {
+    pool: PgPool,
+    settings: JobSettings,
+    handlers: HashMap<&'static str, Arc<dyn JobHandler>>,
+    clock: SharedClock,
+}
+
+impl JobRunner {
+    pub fn new(pool: PgPool, settings: JobSettings) -> Self {
+        Self {
+            pool,
+            settings,
+            handlers: HashMap::new(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Decides which jobs are due and when failed ones are retried by the clock, e.g. a test one.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn register(mut self, handler: impl JobHandler) -> Self {
+        self.handlers.insert(handler.kind(), Arc::new(handler));
+        self
+    }
+
+    /// Claims a batch of due jobs and processes them, returning the number of claimed jobs.
+    pub async fn run_once(&self) -> Result<usize, sqlx::Error> {
+        let jobs = self.claim().await?;
+        let claimed = jobs.len();
+
+        for job in jobs {
+            let Some(handler) = self.handlers.get(job.kind.as_str()) else {
+                continue;
+            };
+
+            match handler.handle(&job, &self.pool).await {
+                Ok(()) => self.complete(&job).await?,
+                Err(e) => {
+                    warn!("Job {} ({}) failed: {e:#}", job.id, job.kind);
+                    self.fail(&job, &e).await?;
+                }
+            }
+        }
+
+        Ok(claimed)
+    }
+
+    /// Runs the queue in a background task until [`JobsHandle::shutdown`] is called.
+    pub fn spawn(self) -> JobsHandle {
+        let (shutdown, mut shutdown_rx) = watch::channel(false);
+
+        info!(
+            "Spawning job runner with handlers: {:?}",
+            self.handlers.keys()
+        );
+        let task = tokio::spawn(async move {
+            loop {
+                let claimed = self.run_once().await.unwrap_or_else(|e| {
+                    error!("Failed to process jobs: {e:?}");
+                    0
+                });
+
+                if *shutdown_rx.borrow() {
+                    break;
+                }
+                if claimed > 0 {
+                    continue;
+                }
+
+                tokio::select! {
+                    _ = shutdown_rx.changed() => break,
+                    _ = tokio::time::sleep(self.settings.poll_interval) => {}
+                }
+            }
+            info!("Job runner stopped");
+        });
+
+        JobsHandle { shutdown, task }
+    }
+
+    async fn claim(&self) -> Result<Vec<Job>, sqlx::Error> {
+        let kinds: Vec<String> = self.handlers.keys().map(|kind| kind.to_string()).collect();
+        let now = self.clock.now();
+
+        query_as!(
+            Job,
+            r#"
+                UPDATE jobs
+                SET attempts = attempts + 1, locked_until = $4
+                WHERE id IN (
+                    SELECT id FROM jobs
+                    WHERE kind = ANY($1)
+                    AND failed_at IS NULL
+                    AND run_at <= $3
+                    AND (locked_until IS NULL OR locked_until <= $3)
+                    ORDER BY run_at
+                    LIMIT $2
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING id, kind, payload, attempts, max_attempts
+            "#,
+            &kinds,
+            self.settings.batch_size,
+            now,
+            now + self.settings.visibility_timeout,
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn complete(&self, job: &Job) -> Result<(), sqlx::Error> {
+        query!(
+            r#"
+                DELETE FROM jobs
+                WHERE id = $1
+            "#,
+            job.id,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        debug!("Completed {} job {}", job.kind, job.id);
+        Ok(())
+    }
+
+    async fn fail(&self, job: &Job, e: &anyhow::Error) -> Result<(), sqlx::Error> {
+        let backoff = 2_f64.powi(job.attempts.min(MAX_BACKOFF_EXPONENT));
+        let now = self.clock.now();
+
+        query!(
+            r#"
+                UPDATE jobs
+                SET last_error = $2,
+                    locked_until = NULL,
+                    run_at = $3,
+                    failed_at = CASE WHEN attempts >= max_attempts THEN $4::timestamptz END
+                WHERE id = $1
+            "#,
+            job.id,
+            format!("{e:#}"),
+            now + time::Duration::seconds_f64(backoff),
+            now,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        if job.attempts >= job.max_attempts {
+            error!(
+                "Job {} ({}) failed permanently after {} attempts",
+                job.id, job.kind, job.attempts
+            );
+        }
+        Ok(())
+    }
+}
+
+pub struct JobsHandle {
+    shutdown: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl JobsHandle {
+    /// Stops claiming new jobs and waits for the ones in progress.
+    pub async fn shutdown(self) {
+        info!("Shutting down job runner");
+        let _ = self.shutdown.send(true);
+        if let Err(e) = self.task.await {
+            error!("Job runner task failed: {e:?}");
+        }
+    }
+}
@@ -0,0 +1,6 @@
+pub mod clock;
+pub mod database;
+pub mod feed_cache;
+pub mod jobs;
+pub mod outbox;
+pub mod storage;
@@ -0,0 +1,173 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{query, query_as, PgConnection, PgPool};
+use time::OffsetDateTime;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use super::jobs::{enqueue, Job, JobHandler, NewJob};
+
+pub const DISPATCH_JOB: &str = "outbox.dispatch";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Topic {
+    EventCreated,
+    EventUpdated,
+    EventDeleted,
+    ParticipantsChanged,
+    InvitationCreated,
+    InvitationResponded,
+    EmailInvitationCreated,
+    ReminderDue,
+    EventDigest,
+}
+
+impl Topic {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Topic::EventCreated => "eventCreated",
+            Topic::EventUpdated => "eventUpdated",
+            Topic::EventDeleted => "eventDeleted",
+            Topic::ParticipantsChanged => "participantsChanged",
+            Topic::InvitationCreated => "invitationCreated",
+            Topic::InvitationResponded => "invitationResponded",
+            Topic::EmailInvitationCreated => "emailInvitationCreated",
+            Topic::ReminderDue => "reminderDue",
+            Topic::EventDigest => "eventDigest",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutboxMessage {
+    pub id: Uuid,
+    pub topic: String,
+    pub aggregate_id: Uuid,
+    pub payload: Value,
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DispatchOutbox {
+    message_id: Uuid,
+}
+
+/// Records a notification together with the job dispatching it.
+///
+/// Meant to be called inside the transaction of the mutation it describes,
+/// so that notifications about rolled back changes are never sent.
+pub async fn record(
+    conn: &mut PgConnection,
+    topic: Topic,
+    aggregate_id: Uuid,
+    payload: impl Serialize,
+) -> anyhow::Result<Uuid> {
+    let message_id = query!(
+        r#"
+            INSERT INTO outbox (topic, aggregate_id, payload)
+            VALUES ($1, $2, $3)
+            RETURNING id
+        "#,
+        topic.as_str(),
+        aggregate_id,
+        serde_json::to_value(payload)?,
+    )
+    .fetch_one(&mut *conn)
+    .await?
+    .id;
+
+    enqueue(
+        conn,
+        NewJob::new(DISPATCH_JOB, DispatchOutbox { message_id })?,
+    )
+    .await?;
+
+    debug!(
+        "Recorded {} outbox message for {aggregate_id}",
+        topic.as_str()
+    );
+    Ok(message_id)
+}
+
+/// Delivery channel for outbox messages (WebSocket, webhook, email, ...).
+#[async_trait]
+pub trait Dispatcher: Send + Sync + 'static {
+    async fn dispatch(&self, message: &OutboxMessage) -> anyhow::Result<()>;
+}
+
+/// Dispatcher writing messages to the application log.
+pub struct LogDispatcher;
+
+#[async_trait]
+impl Dispatcher for LogDispatcher {
+    async fn dispatch(&self, message: &OutboxMessage) -> anyhow::Result<()> {
+        info!(
+            "Notification {} for {}: {}",
+            message.topic, message.aggregate_id, message.payload
+        );
+        Ok(())
+    }
+}
+
+/// Job handler passing outbox messages to every dispatcher.
+///
+/// A failing dispatcher retries the whole message, so delivery is at least once.
+#[derive(Default)]
+pub struct OutboxHandler {
+    dispatchers: Vec<Arc<dyn Dispatcher>>,
+}
+
+impl OutboxHandler {
+    pub fn with(mut self, dispatcher: impl Dispatcher) -> Self {
+        self.dispatchers.push(Arc::new(dispatcher));
+        self
+    }
+}
+
+#[async_trait]
+impl JobHandler for OutboxHandler {
+    fn kind(&self) -> &'static str {
+        DISPATCH_JOB
+    }
+
+    async fn handle(&self, job: &Job, pool: &PgPool) -> anyhow::Result<()> {
+        let DispatchOutbox { message_id } = job.payload()?;
+
+        let Some(message) = query_as!(
+            OutboxMessage,
+            r#"
+                SELECT id, topic, aggregate_id, payload, created_at FROM outbox
+                WHERE id = $1 AND dispatched_at IS NULL
+            "#,
+            message_id,
+        )
+        .fetch_optional(pool)
+        .await?
+        else {
+            debug!("Outbox message {message_id} was already dispatched");
+            return Ok(());
+        };
+
+        for dispatcher in &self.dispatchers {
+            dispatcher.dispatch(&message).await?;
+        }
+
+        query!(
+            r#"
+                UPDATE outbox
+                SET dispatched_at = now()
+                WHERE id = $1
+            "#,
+            message_id,
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
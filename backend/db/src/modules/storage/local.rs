@@ -0,0 +1,142 @@
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use secrecy::{ExposeSecret, Secret};
+use sha2::Sha256;
+use time::OffsetDateTime;
+use tokio::fs;
+use tracing::warn;
+
+use super::{validate_key, Storage};
+use crate::config::app::ApplicationSettings;
+use bimetable_domain::api::API_V1;
+
+/// Storage in a directory of the server, downloaded through the API with HMAC signed URLs.
+///
+/// Without a configured signing key a random one is used, and URLs break on restart.
+pub struct LocalStorage {
+    root: PathBuf,
+    signing_key: Secret<Vec<u8>>,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>, signing_key: Secret<Vec<u8>>) -> Self {
+        Self {
+            root: root.into(),
+            signing_key,
+        }
+    }
+
+    pub fn from_settings(settings: &ApplicationSettings) -> Self {
+        let signing_key = match &settings.storage_signing_key {
+            Some(key) => key.expose_secret().as_bytes().to_vec(),
+            None => {
+                warn!("Using a random storage signing key");
+                let mut key = vec![0; 32];
+                rand::thread_rng().fill_bytes(&mut key);
+                key
+            }
+        };
+        Self::new(&settings.storage_dir, Secret::new(signing_key))
+    }
+
+    fn path(&self, key: &str) -> anyhow::Result<PathBuf> {
+        validate_key(key)?;
+        Ok(self.root.join(key))
+    }
+
+    fn mac(&self, key: &str, expires: i64) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.signing_key.expose_secret())
+            .expect("HMAC accepts keys of any length");
+        mac.update(format!("{key}\n{expires}").as_bytes());
+        mac
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, body: Vec<u8>) -> anyhow::Result<()> {
+        let path = self.path(key)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+        fs::write(path, body).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        match fs::read(self.path(key)?).await {
+            Ok(body) => Ok(Some(body)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        match fs::remove_file(self.path(key)?).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn signed_url(&self, key: &str, expires_at: OffsetDateTime) -> anyhow::Result<String> {
+        validate_key(key)?;
+        let expires = expires_at.unix_timestamp();
+        let signature = hex::encode(self.mac(key, expires).finalize().into_bytes());
+        Ok(format!(
+            "{API_V1}/storage/{key}?expires={expires}&signature={signature}"
+        ))
+    }
+
+    fn verify_download(&self, key: &str, expires: i64, signature: &str) -> bool {
+        let Ok(signature) = hex::decode(signature) else {
+            return false;
+        };
+        self.mac(key, expires).verify_slice(&signature).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod local_storage_tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn storage() -> LocalStorage {
+        LocalStorage::new("storage", Secret::new(b"signing".to_vec()))
+    }
+
+    #[test]
+    fn verifies_own_signatures() {
+        let storage = storage();
+        let expires_at = datetime!(2023-03-08 10:30 UTC);
+        let url = storage.signed_url("exports/feed.ics", expires_at).unwrap();
+
+        let (path, query) = url.split_once('?').unwrap();
+        assert_eq!(path, "/api/v1/storage/exports/feed.ics");
+        let signature = query.split_once("&signature=").unwrap().1;
+        let expires = expires_at.unix_timestamp();
+
+        assert!(storage.verify_download("exports/feed.ics", expires, signature));
+        assert!(!storage.verify_download("exports/other.ics", expires, signature));
+        assert!(!storage.verify_download("exports/feed.ics", expires + 1, signature));
+        assert!(!storage.verify_download("exports/feed.ics", expires, "zz"));
+    }
+
+    #[test]
+    fn rejects_signatures_of_other_keys() {
+        let other = LocalStorage::new("storage", Secret::new(b"other".to_vec()));
+        let expires_at = datetime!(2023-03-08 10:30 UTC);
+        let url = other.signed_url("exports/feed.ics", expires_at).unwrap();
+        let signature = url.split_once("&signature=").unwrap().1;
+
+        assert!(!storage().verify_download(
+            "exports/feed.ics",
+            expires_at.unix_timestamp(),
+            signature
+        ));
+    }
+}
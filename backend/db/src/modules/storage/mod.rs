@@ -0,0 +1,108 @@
+use std::ops::Deref;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use time::OffsetDateTime;
+
+use crate::config::app::{ApplicationSettings, StorageKind};
+
+pub mod local;
+pub mod s3;
+
+pub use self::local::LocalStorage;
+pub use self::s3::S3Storage;
+
+/// Blob store of generated files, selected by the `storage` setting.
+#[async_trait]
+pub trait Storage: Send + Sync + 'static {
+    /// Stores the object, replacing an existing one with the same key.
+    async fn put(&self, key: &str, body: Vec<u8>) -> anyhow::Result<()>;
+
+    async fn get(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>>;
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()>;
+
+    /// URL letting anyone download the object until `expires_at`.
+    fn signed_url(&self, key: &str, expires_at: OffsetDateTime) -> anyhow::Result<String>;
+
+    /// Whether a signature of [`Storage::signed_url`] is valid for downloads through the API.
+    ///
+    /// Backends serving their URLs on their own never accept downloads through the API.
+    fn verify_download(&self, _key: &str, _expires: i64, _signature: &str) -> bool {
+        false
+    }
+}
+
+/// Storage shared by all requests.
+#[derive(Clone)]
+pub struct Blobs(Arc<dyn Storage>);
+
+impl Blobs {
+    pub fn new(storage: impl Storage) -> Self {
+        Self(Arc::new(storage))
+    }
+
+    pub fn from_settings(settings: &ApplicationSettings) -> Self {
+        match settings.storage {
+            StorageKind::Local => Self::new(LocalStorage::from_settings(settings)),
+            StorageKind::S3 => Self::new(S3Storage::from_settings(settings)),
+        }
+    }
+}
+
+impl Deref for Blobs {
+    type Target = dyn Storage;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+/// Checks that the key is a relative path of plain segments, so that it can't escape its backend.
+pub fn validate_key(key: &str) -> anyhow::Result<()> {
+    let is_valid = !key.is_empty()
+        && key.split('/').all(|segment| {
+            !segment.is_empty()
+                && segment != "."
+                && segment != ".."
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        });
+    anyhow::ensure!(is_valid, "Invalid storage key {key}");
+    Ok(())
+}
+
+/// Content type of an object, given by the extension of its key.
+pub fn content_type(key: &str) -> &'static str {
+    match key.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("ics") => "text/calendar; charset=utf-8",
+        Some("pdf") => "application/pdf",
+        Some("json") => "application/json",
+        Some("zip") => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod storage_tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_relative_paths() {
+        assert!(validate_key("exports/fd1dcdf7/feed.ics").is_ok());
+        assert!(validate_key("").is_err());
+        assert!(validate_key("/etc/passwd").is_err());
+        assert!(validate_key("exports/../secret").is_err());
+        assert!(validate_key("exports//feed.ics").is_err());
+        assert!(validate_key("exports/feed.ics?x=1").is_err());
+    }
+
+    #[test]
+    fn content_type_follows_extension() {
+        assert_eq!(content_type("a/feed.ics"), "text/calendar; charset=utf-8");
+        assert_eq!(content_type("a/plan.pdf"), "application/pdf");
+        assert_eq!(content_type("archives/a/b.zip"), "application/zip");
+        assert_eq!(content_type("a/blob"), "application/octet-stream");
+    }
+}
@@ -0,0 +1,32 @@
+use thiserror::Error;
+
+use crate::utils::auth::errors::AuthError;
+use crate::utils::users::errors::UserError;
+
+#[derive(Error, Debug)]
+pub enum AdminError {
+    #[error("Admin privileges required")]
+    Forbidden,
+    #[error("User does not exist")]
+    UserNotFound,
+    #[error(transparent)]
+    Unexpected(#[from] anyhow::Error),
+}
+
+impl From<UserError> for AdminError {
+    fn from(e: UserError) -> Self {
+        Self::Unexpected(anyhow::Error::from(e))
+    }
+}
+
+impl From<AuthError> for AdminError {
+    fn from(e: AuthError) -> Self {
+        Self::Unexpected(anyhow::Error::from(e))
+    }
+}
+
+impl From<sqlx::Error> for AdminError {
+    fn from(e: sqlx::Error) -> Self {
+        Self::Unexpected(anyhow::Error::from(e))
+    }
+}
@@ -0,0 +1,292 @@
+pub mod errors;
+pub mod purge;
+pub mod signups;
+pub mod stats;
+
+use std::fmt::Display;
+
+use crate::modules::database::PgQuery;
+use crate::utils::admin::errors::AdminError;
+use crate::utils::auth::deactivate_user;
+use crate::utils::users::is_admin;
+use sqlx::{query, PgPool};
+use time::OffsetDateTime;
+use tracing::{debug, trace};
+use uuid::Uuid;
+
+pub async fn ensure_admin(pool: &PgPool, user_id: Uuid) -> Result<(), AdminError> {
+    if !is_admin(pool, user_id).await? {
+        return Err(AdminError::Forbidden);
+    }
+
+    Ok(())
+}
+
+/// Record counts of the whole instance.
+#[derive(Debug, PartialEq)]
+pub struct InstanceStats {
+    pub users: i64,
+    pub admins: i64,
+    pub events: i64,
+    /// Soft deleted events waiting to be purged
+    pub deleted_events: i64,
+    pub participations: i64,
+    pub pending_invitations: i64,
+    /// Jobs which are neither finished nor failed
+    pub pending_jobs: i64,
+    pub failed_jobs: i64,
+}
+
+impl Display for InstanceStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let stats = [
+            ("users", self.users),
+            ("admins", self.admins),
+            ("events", self.events),
+            ("deleted events", self.deleted_events),
+            ("participations", self.participations),
+            ("pending invitations", self.pending_invitations),
+            ("pending jobs", self.pending_jobs),
+            ("failed jobs", self.failed_jobs),
+        ];
+        for (name, count) in stats {
+            writeln!(f, "{name:<20} {count}")?;
+        }
+
+        Ok(())
+    }
+}
+
+struct AdminQuery;
+
+impl<'c> PgQuery<'c, AdminQuery> {
+    async fn set_admin(&mut self, user_id: Uuid, is_admin: bool) -> Result<bool, AdminError> {
+        let affected = query!(
+            r#"
+                UPDATE users SET is_admin = $1
+                WHERE id = $2
+            "#,
+            is_admin,
+            user_id,
+        )
+        .execute(&mut *self.conn)
+        .await?
+        .rows_affected();
+
+        Ok(affected == 1)
+    }
+
+    async fn purge_deleted(&mut self, deleted_before: OffsetDateTime) -> Result<u64, AdminError> {
+        let event_ids: Vec<Uuid> = query!(
+            r#"
+                SELECT id FROM events
+                WHERE deleted_at < $1
+            "#,
+            deleted_before,
+        )
+        .fetch_all(&mut *self.conn)
+        .await?
+        .into_iter()
+        .map(|event| event.id)
+        .collect();
+
+        // Not removed along with their events
+        query!(
+            r#"
+                DELETE FROM user_event_invitations
+                WHERE event_id = any($1)
+            "#,
+            &event_ids,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+        query!(
+            r#"
+                DELETE FROM event_tokens
+                WHERE event_id = any($1)
+            "#,
+            &event_ids,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        let purged = query!(
+            r#"
+                DELETE FROM events
+                WHERE id = any($1)
+            "#,
+            &event_ids,
+        )
+        .execute(&mut *self.conn)
+        .await?
+        .rows_affected();
+
+        trace!("Purged {purged} events deleted before {deleted_before}");
+        Ok(purged)
+    }
+
+    /// Hands events of deactivated owners over to their active co-owners, returning how many changed hands.
+    async fn hand_over_deactivated_events(
+        &mut self,
+        deactivated_before: OffsetDateTime,
+    ) -> Result<u64, AdminError> {
+        // Successors leave the participants first, owners cannot participate in their own events
+        let successors = query!(
+            r#"
+                DELETE FROM user_events
+                USING (
+                    SELECT DISTINCT ON (events.id) events.id AS event_id, user_events.user_id
+                    FROM events
+                    JOIN user_events ON user_events.event_id = events.id AND user_events.is_owner
+                    JOIN users ON users.id = user_events.user_id AND users.deactivated_at IS NULL
+                    WHERE events.deleted_at IS NULL
+                    AND events.owner_id IN (SELECT id FROM users WHERE deactivated_at < $1)
+                    ORDER BY events.id, user_events.user_id
+                ) AS successors
+                WHERE user_events.event_id = successors.event_id
+                AND user_events.user_id = successors.user_id
+                RETURNING user_events.event_id, user_events.user_id
+            "#,
+            deactivated_before,
+        )
+        .fetch_all(&mut *self.conn)
+        .await?;
+
+        let event_ids: Vec<Uuid> = successors.iter().map(|row| row.event_id).collect();
+        let owner_ids: Vec<Uuid> = successors.iter().map(|row| row.user_id).collect();
+        let handed_over = query!(
+            r#"
+                UPDATE events SET owner_id = successors.owner_id
+                FROM UNNEST($1::uuid[], $2::uuid[]) AS successors(event_id, owner_id)
+                WHERE events.id = successors.event_id
+            "#,
+            &event_ids,
+            &owner_ids,
+        )
+        .execute(&mut *self.conn)
+        .await?
+        .rows_affected();
+
+        trace!("Handed over {handed_over} events of users deactivated before {deactivated_before}");
+        Ok(handed_over)
+    }
+
+    async fn delete_deactivated_events(
+        &mut self,
+        deactivated_before: OffsetDateTime,
+        now: OffsetDateTime,
+    ) -> Result<u64, AdminError> {
+        let deleted = query!(
+            r#"
+                UPDATE events SET deleted_at = $2
+                WHERE deleted_at IS NULL
+                AND owner_id IN (SELECT id FROM users WHERE deactivated_at < $1)
+            "#,
+            deactivated_before,
+            now,
+        )
+        .execute(&mut *self.conn)
+        .await?
+        .rows_affected();
+
+        trace!("Deleted {deleted} events of users deactivated before {deactivated_before}");
+        Ok(deleted)
+    }
+
+    async fn get_stats(&mut self) -> Result<InstanceStats, AdminError> {
+        let stats = query!(
+            r#"
+                SELECT
+                    (SELECT COUNT(*) FROM users) AS "users!",
+                    (SELECT COUNT(*) FROM users WHERE is_admin) AS "admins!",
+                    (SELECT COUNT(*) FROM events WHERE deleted_at IS NULL) AS "events!",
+                    (SELECT COUNT(*) FROM events WHERE deleted_at IS NOT NULL) AS "deleted_events!",
+                    (SELECT COUNT(*) FROM user_events) AS "participations!",
+                    (SELECT COUNT(*) FROM user_event_invitations) AS "pending_invitations!",
+                    (SELECT COUNT(*) FROM jobs WHERE failed_at IS NULL) AS "pending_jobs!",
+                    (SELECT COUNT(*) FROM jobs WHERE failed_at IS NOT NULL) AS "failed_jobs!"
+            "#
+        )
+        .fetch_one(&mut *self.conn)
+        .await?;
+
+        Ok(InstanceStats {
+            users: stats.users,
+            admins: stats.admins,
+            events: stats.events,
+            deleted_events: stats.deleted_events,
+            participations: stats.participations,
+            pending_invitations: stats.pending_invitations,
+            pending_jobs: stats.pending_jobs,
+            failed_jobs: stats.failed_jobs,
+        })
+    }
+}
+
+/// Grants or takes away admin privileges, `false` when the user does not exist.
+pub async fn set_admin(pool: &PgPool, user_id: Uuid, is_admin: bool) -> Result<bool, AdminError> {
+    let mut conn = pool.acquire().await?;
+    let mut q = PgQuery::new(AdminQuery, &mut conn);
+
+    q.set_admin(user_id, is_admin).await
+}
+
+/// Permanently deletes events soft deleted before `deleted_before`, returning their count.
+pub async fn purge_deleted_events(
+    pool: &PgPool,
+    deleted_before: OffsetDateTime,
+) -> Result<u64, AdminError> {
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(AdminQuery, &mut transaction);
+
+    let purged = q.purge_deleted(deleted_before).await?;
+    transaction.commit().await?;
+    debug!("Purged {purged} deleted events");
+
+    Ok(purged)
+}
+
+/// Deactivates the account of another user, see [`deactivate_user`].
+pub async fn deactivate_other_user(
+    pool: &PgPool,
+    user_id: Uuid,
+    now: OffsetDateTime,
+) -> Result<(), AdminError> {
+    let mut transaction = pool.begin().await?;
+    if !deactivate_user(&mut transaction, user_id, now).await? {
+        return Err(AdminError::UserNotFound);
+    }
+    transaction.commit().await?;
+
+    Ok(())
+}
+
+/// Soft deletes events owned by users deactivated before `deactivated_before`, returning their count.
+///
+/// Events with an active co-owner are handed over to one of them instead.
+///
+/// Ends the grace period of deactivated accounts, their events are then purged with other deleted ones.
+/// They count as deleted at `now`.
+pub async fn delete_deactivated_events(
+    pool: &PgPool,
+    deactivated_before: OffsetDateTime,
+    now: OffsetDateTime,
+) -> Result<u64, AdminError> {
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(AdminQuery, &mut transaction);
+
+    let handed_over = q.hand_over_deactivated_events(deactivated_before).await?;
+    debug!("Handed over {handed_over} events of deactivated users to their co-owners");
+    let deleted = q.delete_deactivated_events(deactivated_before, now).await?;
+    debug!("Deleted {deleted} events of deactivated users");
+    transaction.commit().await?;
+
+    Ok(deleted)
+}
+
+pub async fn get_instance_stats(pool: &PgPool) -> Result<InstanceStats, AdminError> {
+    let mut conn = pool.acquire().await?;
+    let mut q = PgQuery::new(AdminQuery, &mut conn);
+
+    q.get_stats().await
+}
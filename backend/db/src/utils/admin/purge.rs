@@ -0,0 +1,194 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::{query, PgPool};
+use time::{Duration, OffsetDateTime};
+use tracing::{debug, trace};
+
+use crate::config::retention::RetentionSettings;
+use crate::modules::clock::SharedClock;
+use crate::modules::database::PgQuery;
+use crate::modules::jobs::{enqueue, Job, JobHandler, NewJob};
+use crate::utils::admin::errors::AdminError;
+use crate::utils::admin::purge_deleted_events;
+
+pub const PURGE_JOB: &str = "admin.purge";
+
+/// Time between purges of data past its retention
+const PURGE_INTERVAL: Duration = Duration::hours(1);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Purge {}
+
+/// Counts of records removed by a purge.
+#[derive(Debug, Default, PartialEq)]
+pub struct PurgeReport {
+    pub events: u64,
+    pub revoked_tokens: u64,
+    pub changes: u64,
+    pub invitations: u64,
+}
+
+struct PurgeQuery;
+
+impl<'c> PgQuery<'c, PurgeQuery> {
+    async fn purge_revoked_tokens(
+        &mut self,
+        expired_before: OffsetDateTime,
+    ) -> Result<u64, AdminError> {
+        let purged = query!(
+            r#"
+                DELETE FROM jwt_blacklist
+                WHERE expiry < $1
+            "#,
+            expired_before,
+        )
+        .execute(&mut *self.conn)
+        .await?
+        .rows_affected();
+
+        trace!("Purged {purged} revoked tokens expired before {expired_before}");
+        Ok(purged)
+    }
+
+    /// Removes dispatched outbox messages, the ones still waiting are kept regardless of their age.
+    async fn purge_changes(&mut self, changed_before: OffsetDateTime) -> Result<u64, AdminError> {
+        let purged = query!(
+            r#"
+                DELETE FROM outbox
+                WHERE dispatched_at IS NOT NULL AND created_at < $1
+            "#,
+            changed_before,
+        )
+        .execute(&mut *self.conn)
+        .await?
+        .rows_affected();
+
+        trace!("Purged {purged} changes made before {changed_before}");
+        Ok(purged)
+    }
+
+    async fn expire_invitations(&mut self, sent_before: OffsetDateTime) -> Result<u64, AdminError> {
+        let direct = query!(
+            r#"
+                DELETE FROM user_event_invitations
+                WHERE created_at < $1
+            "#,
+            sent_before,
+        )
+        .execute(&mut *self.conn)
+        .await?
+        .rows_affected();
+
+        let by_email = query!(
+            r#"
+                DELETE FROM external_invitations
+                WHERE created_at < $1
+            "#,
+            sent_before,
+        )
+        .execute(&mut *self.conn)
+        .await?
+        .rows_affected();
+
+        let expired = direct + by_email;
+
+        trace!("Expired {expired} invitations sent before {sent_before}");
+        Ok(expired)
+    }
+
+    async fn is_scheduled(&mut self) -> Result<bool, AdminError> {
+        let is_scheduled = query!(
+            r#"
+                SELECT EXISTS (
+                    SELECT 1 FROM jobs
+                    WHERE kind = $1 AND failed_at IS NULL
+                ) AS "is_scheduled!"
+            "#,
+            PURGE_JOB,
+        )
+        .fetch_one(&mut *self.conn)
+        .await?
+        .is_scheduled;
+
+        Ok(is_scheduled)
+    }
+}
+
+/// Removes the data kept longer than the retention settings allow at `now`.
+pub async fn purge_expired(
+    pool: &PgPool,
+    retention: &RetentionSettings,
+    now: OffsetDateTime,
+) -> Result<PurgeReport, AdminError> {
+    let cutoff = |days| RetentionSettings::cutoff(days, now);
+    let mut report = PurgeReport::default();
+    if let Some(days) = retention.trash_days {
+        report.events = purge_deleted_events(pool, cutoff(days)).await?;
+    }
+
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(PurgeQuery, &mut transaction);
+    report.revoked_tokens = q
+        .purge_revoked_tokens(cutoff(retention.blacklist_days))
+        .await?;
+    if let Some(days) = retention.audit_log_days {
+        report.changes = q.purge_changes(cutoff(days)).await?;
+    }
+    if let Some(days) = retention.invitation_expiry_days {
+        report.invitations = q.expire_invitations(cutoff(days)).await?;
+    }
+    transaction.commit().await?;
+    debug!("Purged expired data {report:?}");
+
+    Ok(report)
+}
+
+/// Enqueues the purge, unless one is already waiting.
+///
+/// Called on start, which also restarts purges after their job failed permanently.
+pub async fn schedule_purge(pool: &PgPool) -> Result<(), AdminError> {
+    let mut transaction = pool.begin().await?;
+    if PgQuery::new(PurgeQuery, &mut transaction)
+        .is_scheduled()
+        .await?
+    {
+        return Ok(());
+    }
+
+    enqueue(&mut transaction, NewJob::new(PURGE_JOB, Purge {})?).await?;
+    transaction.commit().await?;
+    debug!("Scheduled purge of expired data");
+
+    Ok(())
+}
+
+/// Job handler purging expired data and scheduling the next purge.
+pub struct PurgeHandler {
+    retention: RetentionSettings,
+    clock: SharedClock,
+}
+
+impl PurgeHandler {
+    pub fn new(retention: RetentionSettings, clock: SharedClock) -> Self {
+        Self { retention, clock }
+    }
+}
+
+#[async_trait]
+impl JobHandler for PurgeHandler {
+    fn kind(&self) -> &'static str {
+        PURGE_JOB
+    }
+
+    async fn handle(&self, job: &Job, pool: &PgPool) -> anyhow::Result<()> {
+        let Purge {} = job.payload()?;
+        let now = self.clock.now();
+        purge_expired(pool, &self.retention, now).await?;
+
+        let mut conn = pool.acquire().await?;
+        let next = NewJob::new(PURGE_JOB, Purge {})?.run_at(now + PURGE_INTERVAL);
+        enqueue(&mut conn, next).await?;
+
+        Ok(())
+    }
+}
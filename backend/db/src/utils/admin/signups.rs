@@ -0,0 +1,67 @@
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+use sqlx::{query, PgPool};
+use time::OffsetDateTime;
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::modules::database::PgQuery;
+use crate::utils::admin::errors::AdminError;
+use bimetable_domain::api::admin::SignupCode;
+
+const SIGNUP_CODE_LENGTH: usize = 12;
+
+struct SignupCodeQuery;
+
+impl<'c> PgQuery<'c, SignupCodeQuery> {
+    async fn insert(
+        &mut self,
+        codes: &[String],
+        created_by: Uuid,
+        expires_at: OffsetDateTime,
+    ) -> Result<(), AdminError> {
+        query!(
+            r#"
+                INSERT INTO signup_codes (code, created_by, expires_at)
+                SELECT code, $2, $3 FROM unnest($1::text[]) AS code
+            "#,
+            codes,
+            created_by,
+            expires_at,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Code handed out to a person invited to register, it can be used once.
+fn generate_code() -> String {
+    thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(SIGNUP_CODE_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+/// Mints `count` signup codes which can be used until `expires_at`.
+pub async fn mint_signup_codes(
+    pool: &PgPool,
+    created_by: Uuid,
+    count: u32,
+    expires_at: OffsetDateTime,
+) -> Result<Vec<SignupCode>, AdminError> {
+    let codes: Vec<String> = (0..count).map(|_| generate_code()).collect();
+
+    let mut conn = pool.acquire().await?;
+    PgQuery::new(SignupCodeQuery, &mut conn)
+        .insert(&codes, created_by, expires_at)
+        .await?;
+    debug!("Minted {count} signup codes");
+
+    Ok(codes
+        .into_iter()
+        .map(|code| SignupCode { code, expires_at })
+        .collect())
+}
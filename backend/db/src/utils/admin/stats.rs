@@ -0,0 +1,247 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::{query, PgPool};
+use time::{Duration, OffsetDateTime, Time, UtcOffset};
+use tracing::{debug, trace};
+
+use crate::modules::database::PgQuery;
+use crate::modules::jobs::{enqueue, Job, JobHandler, NewJob};
+use crate::utils::admin::errors::AdminError;
+use bimetable_domain::api::admin::DailyStats;
+use bimetable_domain::events::models::{RecurrenceRule, RecurrenceRuleKind, TimeRange};
+
+pub const COMPUTE_STATS_JOB: &str = "admin.compute_stats";
+
+/// Time between computations of the statistics of the current day
+const STATS_INTERVAL: Duration = Duration::hours(1);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ComputeStats {}
+
+struct StatsQuery;
+
+impl<'c> PgQuery<'c, StatsQuery> {
+    async fn count_entries(&mut self, day: TimeRange) -> Result<i64, AdminError> {
+        let events = query!(
+            r#"
+                SELECT id, starts_at, ends_at, recurrence AS "recurrence: Option<sqlx::types::Json<RecurrenceRuleKind>>", until, count, interval as "interval: Option<i32>"
+                FROM events
+                LEFT JOIN recurrence_rules ON recurrence_rules.event_id = id
+                WHERE starts_at < $1 AND (until >= $2 OR (recurrence IS NULL AND starts_at >= $2) OR (recurrence IS NOT NULL AND until IS NULL)) AND deleted_at IS NULL
+            "#,
+            day.end,
+            day.start,
+        )
+        .fetch_all(&mut *self.conn)
+        .await?;
+        let event_ids: Vec<_> = events.iter().map(|event| event.id).collect();
+        let pauses = query!(
+            r#"
+                SELECT event_id, starts_at, ends_at
+                FROM event_pauses
+                WHERE event_id = any($1) AND starts_at < $2 AND ends_at > $3
+            "#,
+            &event_ids,
+            day.end,
+            day.start,
+        )
+        .fetch_all(&mut *self.conn)
+        .await?;
+
+        let mut entries = 0;
+        for event in events {
+            let event_range = TimeRange::new(event.starts_at, event.ends_at);
+            let starts = match RecurrenceRule::from_db_data(
+                event.recurrence.map(|kind| kind.0),
+                event.until,
+                event.count,
+                event.interval,
+            ) {
+                Some(rule) => rule
+                    .get_event_range(day, event_range)
+                    .map_err(|e| AdminError::Unexpected(e.into()))?
+                    .into_iter()
+                    .map(|entry| entry.start)
+                    .collect(),
+                None => vec![event_range.start],
+            };
+            entries += starts
+                .into_iter()
+                .filter(|start| day.start <= *start && *start < day.end)
+                .filter(|start| {
+                    !pauses.iter().any(|pause| {
+                        pause.event_id == event.id
+                            && pause.starts_at <= *start
+                            && *start < pause.ends_at
+                    })
+                })
+                .count() as i64;
+        }
+
+        Ok(entries)
+    }
+
+    async fn save_stats(&mut self, day: TimeRange, entries: i64) -> Result<(), AdminError> {
+        query!(
+            r#"
+                INSERT INTO instance_stats (day, users, events, entries, pending_invitations, database_bytes)
+                SELECT
+                    $1,
+                    (SELECT COUNT(*) FROM users),
+                    (SELECT COUNT(*) FROM events WHERE deleted_at IS NULL),
+                    $2,
+                    (SELECT COUNT(*) FROM user_event_invitations),
+                    pg_database_size(current_database())
+                ON CONFLICT (day) DO UPDATE
+                SET users = excluded.users,
+                    events = excluded.events,
+                    entries = excluded.entries,
+                    pending_invitations = excluded.pending_invitations,
+                    database_bytes = excluded.database_bytes,
+                    computed_at = now()
+            "#,
+            day.start,
+            entries,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_stats(&mut self, days: i64) -> Result<Vec<DailyStats>, AdminError> {
+        let stats = query!(
+            r#"
+                SELECT day, users, events, entries, pending_invitations, database_bytes, computed_at
+                FROM instance_stats
+                ORDER BY day DESC
+                LIMIT $1
+            "#,
+            days,
+        )
+        .fetch_all(&mut *self.conn)
+        .await?
+        .into_iter()
+        .map(|stats| DailyStats {
+            day: stats.day,
+            users: stats.users,
+            events: stats.events,
+            entries: stats.entries,
+            pending_invitations: stats.pending_invitations,
+            database_bytes: stats.database_bytes,
+            computed_at: stats.computed_at,
+        })
+        .collect();
+
+        Ok(stats)
+    }
+
+    async fn is_scheduled(&mut self) -> Result<bool, AdminError> {
+        let is_scheduled = query!(
+            r#"
+                SELECT EXISTS (
+                    SELECT 1 FROM jobs
+                    WHERE kind = $1 AND failed_at IS NULL
+                ) AS "is_scheduled!"
+            "#,
+            COMPUTE_STATS_JOB,
+        )
+        .fetch_one(&mut *self.conn)
+        .await?
+        .is_scheduled;
+
+        Ok(is_scheduled)
+    }
+}
+
+/// UTC day containing the moment.
+fn day_of(at: OffsetDateTime) -> TimeRange {
+    let start = at.to_offset(UtcOffset::UTC).replace_time(Time::MIDNIGHT);
+    TimeRange::new(start, start + Duration::days(1))
+}
+
+/// Computes the statistics of the day containing `at`, replacing the ones computed before.
+pub async fn compute_daily_stats(pool: &PgPool, at: OffsetDateTime) -> Result<(), AdminError> {
+    let day = day_of(at);
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(StatsQuery, &mut transaction);
+
+    let entries = q.count_entries(day).await?;
+    q.save_stats(day, entries).await?;
+    transaction.commit().await?;
+    trace!("Computed statistics of {}", day.start);
+
+    Ok(())
+}
+
+/// Gets the statistics of the latest `days` days, starting with the newest.
+pub async fn get_daily_stats(pool: &PgPool, days: u32) -> Result<Vec<DailyStats>, AdminError> {
+    let mut conn = pool.acquire().await?;
+    PgQuery::new(StatsQuery, &mut conn)
+        .get_stats(days.into())
+        .await
+}
+
+/// Enqueues the computation of statistics, unless one is already waiting.
+///
+/// Called on start, which also restarts computations after their job failed permanently.
+pub async fn schedule_stats(pool: &PgPool) -> Result<(), AdminError> {
+    let mut transaction = pool.begin().await?;
+    if PgQuery::new(StatsQuery, &mut transaction)
+        .is_scheduled()
+        .await?
+    {
+        return Ok(());
+    }
+
+    enqueue(
+        &mut transaction,
+        NewJob::new(COMPUTE_STATS_JOB, ComputeStats {})?,
+    )
+    .await?;
+    transaction.commit().await?;
+    debug!("Scheduled computation of statistics");
+
+    Ok(())
+}
+
+/// Job handler computing the statistics of the current day and scheduling the next computation.
+#[derive(Default)]
+pub struct StatsHandler;
+
+#[async_trait]
+impl JobHandler for StatsHandler {
+    fn kind(&self) -> &'static str {
+        COMPUTE_STATS_JOB
+    }
+
+    async fn handle(&self, job: &Job, pool: &PgPool) -> anyhow::Result<()> {
+        let ComputeStats {} = job.payload()?;
+        let now = OffsetDateTime::now_utc();
+        compute_daily_stats(pool, now).await?;
+
+        let mut conn = pool.acquire().await?;
+        let next = NewJob::new(COMPUTE_STATS_JOB, ComputeStats {})?.run_at(now + STATS_INTERVAL);
+        enqueue(&mut conn, next).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod stats_tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn days_are_utc_days() {
+        assert_eq!(
+            day_of(datetime!(2023-03-15 13:20 UTC)),
+            TimeRange::new(
+                datetime!(2023-03-15 0:00 UTC),
+                datetime!(2023-03-16 0:00 UTC)
+            )
+        );
+    }
+}
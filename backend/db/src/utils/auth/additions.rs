@@ -0,0 +1,206 @@
+use anyhow::anyhow;
+use argon2::password_hash::SaltString;
+use argon2::{
+    password_hash, Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier,
+};
+use rand;
+use rand::seq::IteratorRandom;
+use rand::thread_rng;
+use std::collections::HashSet;
+use unicode_normalization::UnicodeNormalization;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use super::models::ValidatedUserData;
+use crate::config::passwords::PasswordSettings;
+use crate::config::usernames::{Script, UsernameSettings};
+
+pub fn hash_pass(password: String, settings: &PasswordSettings) -> anyhow::Result<String> {
+    let salt = SaltString::generate(thread_rng());
+    Ok(settings
+        .hasher()?
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| anyhow!(e).context("failed to hash password"))?
+        .to_string())
+}
+
+pub fn verify_pass(password: String, hash: String) -> anyhow::Result<bool> {
+    let hash = PasswordHash::new(&hash).map_err(|e| anyhow!(e).context("password hash invalid"))?;
+    let res = Argon2::default().verify_password(password.as_bytes(), &hash);
+    match res {
+        Ok(()) => Ok(true),
+        Err(password_hash::Error::Password) => Ok(false),
+        Err(e) => Err(anyhow!(e).context("failed to verify password")),
+    }
+}
+
+/// Checks whether the hash was made with a different algorithm or weaker parameters than the current ones.
+pub fn needs_rehash(hash: &str, settings: &PasswordSettings) -> anyhow::Result<bool> {
+    let hash = PasswordHash::new(hash).map_err(|e| anyhow!(e).context("password hash invalid"))?;
+    if Algorithm::try_from(hash.algorithm).ok() != Some(Algorithm::Argon2id) {
+        return Ok(true);
+    }
+
+    let params =
+        Params::try_from(&hash).map_err(|e| anyhow!(e).context("password hash params invalid"))?;
+    Ok(params.m_cost() < settings.memory_cost
+        || params.t_cost() < settings.iterations
+        || params.p_cost() < settings.parallelism)
+}
+
+pub fn pass_is_strong(user_password: &str, user_inputs: &[&str]) -> bool {
+    let score = zxcvbn::zxcvbn(user_password, user_inputs);
+    score.map_or(false, |entropy| entropy.score() >= 3)
+}
+
+/// Normalizes a login or username to NFC, so that equal looking names are stored alike.
+pub fn normalize_name(name: &str) -> String {
+    name.nfc().collect()
+}
+
+pub fn validate_usernames(
+    login: &str,
+    username: &str,
+    settings: &UsernameSettings,
+) -> Result<(), ValidationErrors> {
+    let mut errors = ValidatedUserData {
+        login: login.to_string(),
+        username: username.to_string(),
+    }
+    .validate()
+    .err()
+    .unwrap_or_default();
+
+    for (field, name) in [("login", login), ("username", username)] {
+        if let Err(e) = follows_username_policy(name, settings) {
+            errors.add(field, e);
+        }
+    }
+
+    if errors.errors().is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+pub fn follows_username_policy(
+    name: &str,
+    settings: &UsernameSettings,
+) -> Result<(), ValidationError> {
+    let length = name.chars().count();
+    if length < settings.min_length || length > settings.max_length {
+        return Err(ValidationError::new("Length out of the allowed range"));
+    }
+
+    let mut scripts: Vec<Script> = vec![];
+    for c in name.chars() {
+        if c.is_ascii() && !c.is_ascii_alphabetic() {
+            continue;
+        }
+        match Script::of(c) {
+            Some(script) if settings.scripts.contains(&script) => {
+                if !scripts.contains(&script) {
+                    scripts.push(script);
+                }
+            }
+            _ => {
+                return Err(ValidationError::new(
+                    "Characters of disallowed scripts detected",
+                ))
+            }
+        }
+    }
+
+    let is_mixed = scripts.iter().any(|script| {
+        scripts
+            .iter()
+            .any(|other| !script.is_combinable_with(*other))
+    });
+    if is_mixed && !settings.mixed_scripts {
+        return Err(ValidationError::new("Characters of mixed scripts detected"));
+    }
+    Ok(())
+}
+
+pub fn random_username_tag(used_tags: HashSet<i32>) -> Option<i32> {
+    let mut rng = thread_rng();
+    (0..10000)
+        .filter(|x| !used_tags.contains(x))
+        .choose(&mut rng)
+}
+
+#[test]
+fn rehash_weaker_hashes() {
+    let settings = PasswordSettings::default();
+    let stronger = PasswordSettings {
+        memory_cost: settings.memory_cost * 2,
+        ..settings.clone()
+    };
+    let hash = hash_pass("#strong#_#pass#".to_string(), &settings).unwrap();
+
+    assert!(!needs_rehash(&hash, &settings).unwrap());
+    assert!(needs_rehash(&hash, &stronger).unwrap());
+    assert!(needs_rehash(
+        "$argon2i$v=19$m=4096,t=3,p=1$M0g3ODVzWmQ$fHLpcolZURzJzej/xbDQqTb+OINmUOl8uEFVLah0z8Y",
+        &settings
+    )
+    .unwrap());
+}
+
+#[test]
+fn random_username_tag_overflow() {
+    let set = HashSet::<i32>::from_iter(0..10000);
+    let res = random_username_tag(set);
+
+    assert_eq!(None, res)
+}
+
+#[test]
+fn username_policy_scripts() {
+    let settings = UsernameSettings {
+        scripts: vec![Script::Latin, Script::Greek, Script::Han, Script::Kana],
+        ..UsernameSettings::default()
+    };
+
+    assert!(follows_username_policy("Zażółć_gęślą", &settings).is_ok());
+    assert!(follows_username_policy("Σωκράτης", &settings).is_ok());
+    assert!(follows_username_policy("山田たろう", &settings).is_ok());
+    assert!(follows_username_policy("thΣtruΣsigma", &settings).is_err());
+    assert!(follows_username_policy("Сергей", &settings).is_err());
+    assert!(follows_username_policy("Sergei🙂", &settings).is_err());
+    assert!(follows_username_policy("thΣtruΣsigma", &UsernameSettings::default()).is_err());
+    assert!(follows_username_policy(
+        "thΣtruΣsigma",
+        &UsernameSettings {
+            mixed_scripts: true,
+            ..settings
+        }
+    )
+    .is_ok());
+}
+
+#[test]
+fn username_policy_length() {
+    let settings = UsernameSettings {
+        scripts: vec![Script::Latin, Script::Han],
+        min_length: 2,
+        ..UsernameSettings::default()
+    };
+
+    assert!(follows_username_policy("王芳", &settings).is_ok());
+    assert!(follows_username_policy("王", &settings).is_err());
+    assert!(follows_username_policy(&"a".repeat(21), &settings).is_err());
+}
+
+#[test]
+fn normalize_name_composes() {
+    assert_eq!(normalize_name("Zaz\u{307}o\u{301}łc\u{301}"), "Zażółć");
+}
+
+#[test]
+fn random_username_tag_not_overflowing() {
+    let set = HashSet::<i32>::from_iter(1..10000);
+    let res = random_username_tag(set);
+
+    assert!(res.is_some())
+}
@@ -0,0 +1,38 @@
+use thiserror::Error;
+use validator::ValidationErrors;
+
+#[derive(Error, Debug)]
+pub enum AuthError {
+    #[error("User already exists")]
+    UserAlreadyExists,
+    #[error("Missing credential")]
+    MissingCredential,
+    #[error("Password is too weak")]
+    WeakPassword,
+    #[error("Incorrect email or password")]
+    WrongLoginOrPassword,
+    #[error("Invalid or expired token")]
+    InvalidToken,
+    #[error("Invalid login or username")]
+    InvalidUsername(#[from] ValidationErrors),
+    #[error("To many users named like you")]
+    TagOverflow,
+    #[error("User does not exist")]
+    UserNotFound,
+    #[error("Account is deactivated")]
+    Deactivated,
+    #[error("Registration is disabled")]
+    SignupDisabled,
+    #[error("Email domain is not allowed")]
+    EmailDomainNotAllowed,
+    #[error("Invalid or used signup code")]
+    InvalidSignupCode,
+    #[error(transparent)]
+    Unexpected(#[from] anyhow::Error),
+}
+
+impl From<sqlx::Error> for AuthError {
+    fn from(e: sqlx::Error) -> Self {
+        Self::Unexpected(anyhow::Error::from(e))
+    }
+}
@@ -0,0 +1,439 @@
+pub mod additions;
+pub mod errors;
+pub mod models;
+use self::additions::{normalize_name, validate_usernames};
+use crate::config::passwords::PasswordSettings;
+use crate::config::signups::SignupSettings;
+use crate::config::usernames::UsernameSettings;
+use crate::modules::database::PgQuery;
+use crate::utils::auth::additions::{hash_pass, needs_rehash, random_username_tag, verify_pass};
+use crate::utils::invitations::email::attach_email_invitations;
+use errors::*;
+use secrecy::{ExposeSecret, SecretString};
+use sqlx::{query, Acquire, PgConnection, PgPool, Postgres};
+use std::collections::HashSet;
+use time::OffsetDateTime;
+use tracing::{debug, trace};
+use uuid::Uuid;
+
+/// Registration by the user themselves, which is subject to the signup policy of the instance.
+pub struct Signup<'a> {
+    pub settings: &'a SignupSettings,
+    /// Code minted by an admin, required when signups are invite-only
+    pub code: Option<&'a str>,
+    /// Time of the registration, codes expired before it are rejected
+    pub now: OffsetDateTime,
+}
+
+/// Creates the account, applying the signup policy unless it is provisioned without `signup`.
+pub async fn try_register_user<'c>(
+    acq: impl Acquire<'c, Database = Postgres>,
+    login: &str,
+    password: SecretString,
+    username: &str,
+    settings: &PasswordSettings,
+    usernames: &UsernameSettings,
+    signup: Option<Signup<'_>>,
+) -> Result<Uuid, AuthError> {
+    if signup
+        .as_ref()
+        .is_some_and(|signup| !signup.settings.enabled)
+    {
+        trace!("Attempted to register while signups are disabled");
+        return Err(AuthError::SignupDisabled);
+    }
+
+    let login = normalize_name(login);
+    let username = normalize_name(username);
+    let mut transaction = acq.begin().await?;
+
+    let mut user = PgQuery::new(AuthUser::new(&login), &mut transaction);
+
+    if !user.is_new().await? {
+        trace!("User with a specified name already exists");
+
+        return Err(AuthError::UserAlreadyExists);
+    }
+
+    if login.trim().is_empty() {
+        trace!("Attempted to register with empty login");
+        return Err(AuthError::MissingCredential);
+    }
+
+    if password.expose_secret().trim().is_empty() {
+        trace!("Attempted to register with empty password");
+        return Err(AuthError::MissingCredential);
+    }
+
+    if username.trim().is_empty() {
+        trace!("Attempted to register with empty username");
+        return Err(AuthError::MissingCredential);
+    }
+
+    validate_usernames(&login, &username, usernames)?;
+
+    if let Some(signup) = &signup {
+        if !signup.settings.allows_login(&login) {
+            trace!("Attempted to register with a login of another domain");
+            return Err(AuthError::EmailDomainNotAllowed);
+        }
+    }
+
+    let tag = random_username_tag(user.get_username_tags(&username).await?)
+        .ok_or(AuthError::TagOverflow)?;
+
+    if !additions::pass_is_strong(password.expose_secret(), &[login.as_str()]) {
+        trace!("Attempted to register with weak password");
+        return Err(AuthError::WeakPassword);
+    }
+
+    let hashed_pass = hash_pass(password.expose_secret().to_owned(), settings)?;
+
+    let user_id = user.create_account(hashed_pass, &username, tag).await?;
+    if let Some(signup) = signup.filter(|signup| signup.settings.invite_only) {
+        let code = signup.code.ok_or(AuthError::InvalidSignupCode)?;
+        if !user
+            .redeem_signup_code(code.trim(), user_id, signup.now)
+            .await?
+        {
+            trace!("Attempted to register with an invalid signup code");
+            return Err(AuthError::InvalidSignupCode);
+        }
+    }
+    attach_email_invitations(&mut transaction, &login, user_id).await?;
+
+    transaction.commit().await?;
+
+    Ok(user_id)
+}
+
+pub async fn verify_user_credentials<'c>(
+    conn: &mut PgConnection,
+    login: &str,
+    password: SecretString,
+    settings: &PasswordSettings,
+) -> Result<Uuid, AuthError> {
+    debug!("Verifying credentials");
+    if login.trim().is_empty() {
+        trace!("Attempted to login the user with empty login");
+        return Err(AuthError::MissingCredential)?;
+    }
+
+    if password.expose_secret().trim().is_empty() {
+        trace!("Attempted to login the user with empty password");
+        return Err(AuthError::MissingCredential)?;
+    }
+
+    let login = normalize_name(login);
+    let mut q = PgQuery::new(AuthUser::new(&login), conn);
+    let user_id = q.verify_credentials(password, settings).await?;
+
+    Ok(user_id)
+}
+
+/// Sets a new password for the login and revokes every token issued to its user.
+pub async fn reset_user_password<'c>(
+    acq: impl Acquire<'c, Database = Postgres>,
+    login: &str,
+    password: SecretString,
+    settings: &PasswordSettings,
+) -> Result<Uuid, AuthError> {
+    let login = normalize_name(login);
+    let mut transaction = acq.begin().await?;
+    let mut user = PgQuery::new(AuthUser::new(&login), &mut transaction);
+
+    let user_id = user.get_user_id().await?.ok_or(AuthError::UserNotFound)?;
+
+    if password.expose_secret().trim().is_empty() {
+        trace!("Attempted to reset to an empty password");
+        return Err(AuthError::MissingCredential);
+    }
+
+    if !additions::pass_is_strong(password.expose_secret(), &[login.as_str()]) {
+        trace!("Attempted to reset to a weak password");
+        return Err(AuthError::WeakPassword);
+    }
+
+    let hashed_pass = hash_pass(password.expose_secret().to_owned(), settings)?;
+    user.update_password(hashed_pass).await?;
+    revoke_user_tokens(&mut transaction, user_id).await?;
+
+    transaction.commit().await?;
+    debug!("Reset password of the user {user_id}");
+
+    Ok(user_id)
+}
+
+pub async fn get_token_version(conn: &mut PgConnection, user_id: Uuid) -> Result<i32, AuthError> {
+    let ver = query!(
+        r#"
+            SELECT token_version FROM users
+            WHERE id = $1
+        "#,
+        user_id,
+    )
+    .fetch_optional(conn)
+    .await?
+    .ok_or(AuthError::InvalidToken)?
+    .token_version;
+
+    Ok(ver)
+}
+
+/// Refuses the token until its expiry, after which the purge drops it.
+pub async fn blacklist_token(
+    pool: &PgPool,
+    token_id: Uuid,
+    expiry: OffsetDateTime,
+) -> Result<(), AuthError> {
+    query!(
+        r#"
+            insert into jwt_blacklist (token_id, expiry)
+            values ($1, $2)
+        "#,
+        token_id,
+        expiry,
+    )
+    .execute(pool)
+    .await?;
+
+    trace!("Adding token to blacklist");
+    Ok(())
+}
+
+/// Invalidates every access and refresh token issued to the user.
+pub async fn revoke_user_tokens(conn: &mut PgConnection, user_id: Uuid) -> Result<(), AuthError> {
+    query!(
+        r#"
+            UPDATE users
+            SET token_version = token_version + 1
+            WHERE id = $1
+        "#,
+        user_id,
+    )
+    .execute(conn)
+    .await?;
+
+    debug!("Revoked all tokens of the user {user_id}");
+    Ok(())
+}
+
+/// Disables login of the user and revokes their tokens, keeping their events and participations.
+///
+/// Returns `false` when the user does not exist, deactivating twice keeps the first time.
+pub async fn deactivate_user(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    now: OffsetDateTime,
+) -> Result<bool, AuthError> {
+    let affected = query!(
+        r#"
+            UPDATE users
+            SET deactivated_at = COALESCE(deactivated_at, $2)
+            WHERE id = $1
+        "#,
+        user_id,
+        now,
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+    if affected == 0 {
+        return Ok(false);
+    }
+    revoke_user_tokens(conn, user_id).await?;
+
+    debug!("Deactivated the user {user_id}");
+    Ok(true)
+}
+
+pub struct AuthUser<'c> {
+    login: &'c str,
+}
+
+impl<'c> AuthUser<'c> {
+    fn new(login: &'c str) -> Self {
+        Self { login }
+    }
+}
+
+impl<'c> PgQuery<'c, AuthUser<'c>> {
+    async fn create_user(&mut self, username: &str, tag: i32) -> Result<Uuid, AuthError> {
+        let user_id = query!(
+            r#"
+            insert into users (username, tag)
+            values ($1, $2)
+            returning (id)
+        "#,
+            username,
+            tag
+        )
+        .fetch_one(&mut *self.conn)
+        .await?
+        .id;
+        trace!("Created user");
+        Ok(user_id)
+    }
+
+    async fn create_credentials(
+        &mut self,
+        user_id: &Uuid,
+        hashed_password: String,
+    ) -> Result<(), AuthError> {
+        query!(
+            r#"
+                insert into credentials (user_id, login, password)
+                values ($1, $2, $3)
+            "#,
+            user_id,
+            self.payload.login,
+            hashed_password
+        )
+        .execute(&mut *self.conn)
+        .await?;
+        trace!("Created credentials");
+        Ok(())
+    }
+
+    async fn create_account(
+        &mut self,
+        hashed_password: String,
+        username: &str,
+        tag: i32,
+    ) -> Result<Uuid, AuthError> {
+        let user_id = self.create_user(username, tag).await?;
+        self.create_credentials(&user_id, hashed_password).await?;
+        trace!("Created user account successfully");
+        Ok(user_id)
+    }
+
+    /// Marks the code as used by the user, unless it was used already or expired.
+    async fn redeem_signup_code(
+        &mut self,
+        code: &str,
+        user_id: Uuid,
+        now: OffsetDateTime,
+    ) -> Result<bool, AuthError> {
+        let redeemed = query!(
+            r#"
+                update signup_codes set used_by = $2, used_at = $3
+                where code = $1 and used_at is null and expires_at > $3
+            "#,
+            code,
+            user_id,
+            now
+        )
+        .execute(&mut *self.conn)
+        .await?
+        .rows_affected();
+
+        Ok(redeemed == 1)
+    }
+
+    async fn get_user_id(&mut self) -> Result<Option<Uuid>, AuthError> {
+        let user_id = query!(
+            r#"
+                select user_id from credentials where login = $1
+            "#,
+            self.payload.login
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?
+        .map(|rec| rec.user_id);
+
+        Ok(user_id)
+    }
+
+    async fn is_new(&mut self) -> Result<bool, AuthError> {
+        let is_new = query!(
+            r#"
+                select * from credentials where login = $1
+            "#,
+            self.payload.login
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?
+        .is_none();
+        if is_new {
+            trace!("User with this login does not exist");
+        } else {
+            trace!("User with this login exists");
+        }
+        Ok(is_new)
+    }
+
+    async fn verify_credentials(
+        &mut self,
+        password: SecretString,
+        settings: &PasswordSettings,
+    ) -> Result<Uuid, AuthError> {
+        let res = query!(
+            r#"
+            select users.id, password, deactivated_at from credentials
+            join users on credentials.user_id = users.id
+            where login = $1
+        "#,
+            self.payload.login
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?
+        .ok_or_else(|| {
+            trace!("Wrong login or password");
+            AuthError::WrongLoginOrPassword
+        })?;
+
+        let is_verified = verify_pass(password.expose_secret().to_owned(), res.password.clone())?;
+
+        if is_verified {
+            trace!("Login and password verified");
+            if res.deactivated_at.is_some() {
+                trace!("Attempted to login to a deactivated account");
+                return Err(AuthError::Deactivated);
+            }
+            if needs_rehash(&res.password, settings)? {
+                let hashed_pass = hash_pass(password.expose_secret().to_owned(), settings)?;
+                self.update_password(hashed_pass).await?;
+                debug!("Rehashed password with current parameters");
+            }
+            return Ok(res.id);
+        }
+        trace!("Wrong login or password");
+        Err(AuthError::WrongLoginOrPassword)
+    }
+
+    async fn update_password(&mut self, hashed_password: String) -> Result<(), AuthError> {
+        query!(
+            r#"
+            update credentials
+            set password = $1
+            where login = $2
+        "#,
+            hashed_password,
+            self.payload.login
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!("Updated password");
+        Ok(())
+    }
+
+    async fn get_username_tags(&mut self, username: &str) -> Result<HashSet<i32>, AuthError> {
+        let res = query!(
+            r#"
+            SELECT tag AS "tag!"
+            FROM users
+            WHERE username = $1
+            UNION
+            SELECT tag
+            FROM username_history
+            WHERE username = $1
+        "#,
+            username
+        )
+        .fetch_all(&mut *self.conn)
+        .await?;
+
+        Ok(res.iter().map(|rec| rec.tag).collect())
+    }
+}
@@ -0,0 +1,9 @@
+use validator::Validate;
+
+#[derive(Validate)]
+pub struct ValidatedUserData {
+    #[validate(non_control_character, does_not_contain = " ")]
+    pub login: String,
+    #[validate(non_control_character)]
+    pub username: String,
+}
@@ -0,0 +1,351 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::{query, PgPool};
+use time::{Duration, OffsetDateTime};
+use tracing::{debug, instrument, trace};
+use uuid::Uuid;
+
+use crate::modules::clock::SharedClock;
+use crate::modules::database::PgQuery;
+use crate::modules::jobs::{enqueue, Job, JobHandler, NewJob};
+use crate::modules::outbox::{self, Topic};
+use crate::utils::events::errors::EventError;
+use crate::utils::events::EventQuery;
+use bimetable_domain::api::events::{
+    DigestFrequency, DigestPayload, DigestSummary, EventDigest, UpdateEventDigest,
+};
+
+pub const SEND_DIGESTS_JOB: &str = "events.send_digests";
+
+/// Time between checks for digests which are due
+const DIGEST_INTERVAL: Duration = Duration::hours(1);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SendDigests {}
+
+/// Digest waiting to be sent, locked until the transaction ends.
+struct DueDigest {
+    event_id: Uuid,
+    owner_id: Uuid,
+    name: String,
+    last_sent_at: OffsetDateTime,
+}
+
+struct DigestQuery;
+
+impl<'c> PgQuery<'c, DigestQuery> {
+    async fn set(
+        &mut self,
+        event_id: Uuid,
+        frequency: DigestFrequency,
+        now: OffsetDateTime,
+    ) -> Result<EventDigest, EventError> {
+        let digest = query!(
+            r#"
+                INSERT INTO event_digests (event_id, frequency, last_sent_at)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (event_id) DO UPDATE
+                SET frequency = excluded.frequency
+                RETURNING last_sent_at
+            "#,
+            event_id,
+            frequency.as_str(),
+            now,
+        )
+        .fetch_one(&mut *self.conn)
+        .await?;
+
+        trace!("Set digest of event {event_id} to {frequency:?}");
+        Ok(EventDigest {
+            frequency,
+            last_sent_at: digest.last_sent_at,
+        })
+    }
+
+    async fn get(&mut self, event_id: Uuid) -> Result<Option<EventDigest>, EventError> {
+        query!(
+            r#"
+                SELECT frequency, last_sent_at FROM event_digests
+                WHERE event_id = $1
+            "#,
+            event_id,
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?
+        .map(|digest| {
+            Ok(EventDigest {
+                frequency: DigestFrequency::try_from(digest.frequency)
+                    .map_err(|e| EventError::Unexpected(anyhow::anyhow!(e)))?,
+                last_sent_at: digest.last_sent_at,
+            })
+        })
+        .transpose()
+    }
+
+    async fn delete(&mut self, event_id: Uuid) -> Result<bool, EventError> {
+        let deleted = query!(
+            r#"
+                DELETE FROM event_digests
+                WHERE event_id = $1
+            "#,
+            event_id,
+        )
+        .execute(&mut *self.conn)
+        .await?
+        .rows_affected();
+
+        Ok(deleted == 1)
+    }
+
+    async fn lock_due(&mut self, now: OffsetDateTime) -> Result<Vec<DueDigest>, EventError> {
+        let digests = query!(
+            r#"
+                SELECT event_digests.event_id, event_digests.last_sent_at, events.owner_id, events.name
+                FROM event_digests
+                JOIN events ON events.id = event_digests.event_id
+                WHERE events.deleted_at IS NULL
+                AND ((frequency = $1 AND last_sent_at <= $2) OR (frequency = $3 AND last_sent_at <= $4))
+                FOR UPDATE OF event_digests SKIP LOCKED
+            "#,
+            DigestFrequency::Daily.as_str(),
+            now - DigestFrequency::Daily.period(),
+            DigestFrequency::Weekly.as_str(),
+            now - DigestFrequency::Weekly.period(),
+        )
+        .fetch_all(&mut *self.conn)
+        .await?
+        .into_iter()
+        .map(|digest| DueDigest {
+            event_id: digest.event_id,
+            owner_id: digest.owner_id,
+            name: digest.name,
+            last_sent_at: digest.last_sent_at,
+        })
+        .collect();
+
+        Ok(digests)
+    }
+
+    /// Counts the changes recorded in the outbox, which doubles as the audit log of the event.
+    async fn summarize(
+        &mut self,
+        digest: &DueDigest,
+        until: OffsetDateTime,
+    ) -> Result<DigestSummary, EventError> {
+        let summary = query!(
+            r#"
+                SELECT
+                    COUNT(*) FILTER (WHERE topic = $4) AS "participant_changes!",
+                    COUNT(*) FILTER (WHERE topic = $5 AND payload->>'userId' <> $6) AS "editor_changes!",
+                    COUNT(*) FILTER (WHERE topic = $7 AND (payload->>'isAccepted')::boolean) AS "accepted_invitations!",
+                    COUNT(*) FILTER (WHERE topic = $7 AND NOT (payload->>'isAccepted')::boolean) AS "declined_invitations!"
+                FROM outbox
+                WHERE aggregate_id = $1 AND created_at > $2 AND created_at <= $3
+            "#,
+            digest.event_id,
+            digest.last_sent_at,
+            until,
+            Topic::ParticipantsChanged.as_str(),
+            Topic::EventUpdated.as_str(),
+            digest.owner_id.to_string(),
+            Topic::InvitationResponded.as_str(),
+        )
+        .fetch_one(&mut *self.conn)
+        .await?;
+
+        Ok(DigestSummary {
+            participant_changes: summary.participant_changes,
+            editor_changes: summary.editor_changes,
+            accepted_invitations: summary.accepted_invitations,
+            declined_invitations: summary.declined_invitations,
+        })
+    }
+
+    async fn mark_sent(&mut self, event_id: Uuid, at: OffsetDateTime) -> Result<(), EventError> {
+        query!(
+            r#"
+                UPDATE event_digests SET last_sent_at = $1
+                WHERE event_id = $2
+            "#,
+            at,
+            event_id,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn is_scheduled(&mut self) -> Result<bool, EventError> {
+        let is_scheduled = query!(
+            r#"
+                SELECT EXISTS (
+                    SELECT 1 FROM jobs
+                    WHERE kind = $1 AND failed_at IS NULL
+                ) AS "is_scheduled!"
+            "#,
+            SEND_DIGESTS_JOB,
+        )
+        .fetch_one(&mut *self.conn)
+        .await?
+        .is_scheduled;
+
+        Ok(is_scheduled)
+    }
+}
+
+/// Turns on the digest of the event for its owner, or changes its frequency.
+///
+/// The first digest covers the changes made since it was turned on at `now`.
+#[instrument(skip_all, fields(%user_id, %event_id))]
+pub async fn set_event_digest(
+    pool: &PgPool,
+    user_id: Uuid,
+    event_id: Uuid,
+    body: UpdateEventDigest,
+    now: OffsetDateTime,
+) -> Result<EventDigest, EventError> {
+    let mut transaction = pool.begin().await?;
+    if !PgQuery::new(EventQuery::new(user_id), &mut transaction)
+        .is_owner(event_id)
+        .await?
+    {
+        return Err(EventError::MismatchedPrivileges);
+    }
+
+    let digest = PgQuery::new(DigestQuery, &mut transaction)
+        .set(event_id, body.frequency, now)
+        .await?;
+    transaction.commit().await?;
+
+    Ok(digest)
+}
+
+#[instrument(skip_all, fields(%user_id, %event_id))]
+pub async fn get_event_digest(
+    pool: &PgPool,
+    user_id: Uuid,
+    event_id: Uuid,
+) -> Result<EventDigest, EventError> {
+    let mut conn = pool.acquire().await?;
+    if !PgQuery::new(EventQuery::new(user_id), &mut conn)
+        .is_owner(event_id)
+        .await?
+    {
+        return Err(EventError::MismatchedPrivileges);
+    }
+
+    PgQuery::new(DigestQuery, &mut conn)
+        .get(event_id)
+        .await?
+        .ok_or(EventError::NotFound)
+}
+
+#[instrument(skip_all, fields(%user_id, %event_id))]
+pub async fn delete_event_digest(
+    pool: &PgPool,
+    user_id: Uuid,
+    event_id: Uuid,
+) -> Result<(), EventError> {
+    let mut conn = pool.acquire().await?;
+    if !PgQuery::new(EventQuery::new(user_id), &mut conn)
+        .is_owner(event_id)
+        .await?
+    {
+        return Err(EventError::MismatchedPrivileges);
+    }
+
+    if !PgQuery::new(DigestQuery, &mut conn)
+        .delete(event_id)
+        .await?
+    {
+        return Err(EventError::NotFound);
+    }
+
+    Ok(())
+}
+
+/// Notifies owners about changes of their events whose digest is due at `now`, returning how many were sent.
+///
+/// Periods without changes are skipped silently, the next digest starts after them anyway.
+pub async fn send_due_digests(pool: &PgPool, now: OffsetDateTime) -> Result<u64, EventError> {
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(DigestQuery, &mut transaction);
+
+    let mut sent = 0;
+    for digest in q.lock_due(now).await? {
+        let summary = q.summarize(&digest, now).await?;
+        q.mark_sent(digest.event_id, now).await?;
+        if summary.is_empty() {
+            trace!("No changes of event {} to digest", digest.event_id);
+            continue;
+        }
+
+        let payload = DigestPayload {
+            event_id: digest.event_id,
+            name: digest.name,
+            since: digest.last_sent_at,
+            until: now,
+            summary,
+        };
+        outbox::record(&mut *q.conn, Topic::EventDigest, digest.owner_id, &payload).await?;
+        sent += 1;
+    }
+    transaction.commit().await?;
+    debug!("Sent {sent} event digests");
+
+    Ok(sent)
+}
+
+/// Enqueues sending of digests, unless it is already waiting.
+///
+/// Called on start, which also restarts digests after their job failed permanently.
+pub async fn schedule_digests(pool: &PgPool) -> Result<(), EventError> {
+    let mut transaction = pool.begin().await?;
+    if PgQuery::new(DigestQuery, &mut transaction)
+        .is_scheduled()
+        .await?
+    {
+        return Ok(());
+    }
+
+    enqueue(
+        &mut transaction,
+        NewJob::new(SEND_DIGESTS_JOB, SendDigests {})?,
+    )
+    .await?;
+    transaction.commit().await?;
+    debug!("Scheduled event digests");
+
+    Ok(())
+}
+
+/// Job handler sending the digests which are due and scheduling the next check.
+pub struct DigestHandler {
+    clock: SharedClock,
+}
+
+impl DigestHandler {
+    pub fn new(clock: SharedClock) -> Self {
+        Self { clock }
+    }
+}
+
+#[async_trait]
+impl JobHandler for DigestHandler {
+    fn kind(&self) -> &'static str {
+        SEND_DIGESTS_JOB
+    }
+
+    async fn handle(&self, job: &Job, pool: &PgPool) -> anyhow::Result<()> {
+        let SendDigests {} = job.payload()?;
+        let now = self.clock.now();
+        send_due_digests(pool, now).await?;
+
+        let mut conn = pool.acquire().await?;
+        let next = NewJob::new(SEND_DIGESTS_JOB, SendDigests {})?.run_at(now + DIGEST_INTERVAL);
+        enqueue(&mut conn, next).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,48 @@
+use crate::modules::database::{
+    is_lock_timeout, violated_constraint, OWNER_PARTICIPANT_CONSTRAINT,
+};
+use bimetable_domain::events::errors::RecurrenceError;
+use bimetable_domain::validation::ValidateContentError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum EventError {
+    #[error("Query rejected because of event ownership")]
+    MismatchedPrivileges,
+    #[error("Event data rejected with validation")]
+    InvalidData(#[from] ValidateContentError),
+    #[error("Not Found")]
+    NotFound,
+    #[error("Owner cannot participate in their own event")]
+    OwnerParticipation,
+    #[error("Override would shadow an existing override of the event")]
+    ShadowingOverride,
+    #[error("Event is locked by another operation, try again later")]
+    Locked,
+    #[error("Entry is locked by the owner of the event, only owners may override it")]
+    LockedOccurrence,
+    #[error(transparent)]
+    Unexpected(#[from] anyhow::Error),
+}
+
+impl From<sqlx::Error> for EventError {
+    fn from(e: sqlx::Error) -> Self {
+        if violated_constraint(&e) == Some(OWNER_PARTICIPANT_CONSTRAINT) {
+            return Self::OwnerParticipation;
+        }
+        if is_lock_timeout(&e) {
+            return Self::Locked;
+        }
+        Self::Unexpected(anyhow::Error::from(e))
+    }
+}
+
+impl From<RecurrenceError> for EventError {
+    fn from(e: RecurrenceError) -> Self {
+        match e {
+            RecurrenceError::InvalidData(e) => Self::InvalidData(e),
+            RecurrenceError::NotFound => Self::NotFound,
+            RecurrenceError::Unexpected(e) => Self::Unexpected(e),
+        }
+    }
+}
@@ -0,0 +1,850 @@
+use crate::modules::database::{lock_for_transaction, EventId, PgQuery, UserId};
+use crate::modules::feed_cache::{CachedFeed, FeedCache, FeedVersion};
+use crate::modules::outbox::Topic;
+use crate::modules::storage::Storage;
+use crate::utils::events::errors::EventError;
+use crate::utils::events::{get_owned, get_shared, EventQuery, FeedQuery};
+use bimetable_domain::api::events::{
+    CombinedBusy, CreateEvent, Event, EventExceptions, EventExport, EventFilter, Events,
+    EventsPage, ImportEventsResult, OverrideEvent, OverrideEventData, PauseEvent, RangedOverride,
+    SplitEvent, SuggestSlot, SuggestedSlots, UpdateCoOwner, UpdateEditPrivilege,
+    UpdateEditPrivileges, UpdateEvent, UpdateOverrideStrategy, UpdateRecurrence,
+};
+use bimetable_domain::events::classroom::schedule_events;
+use bimetable_domain::events::ics::events_to_ics;
+use bimetable_domain::events::models::{
+    DayOfWeek, EntriesPage, EventPrivileges, OverrideStrategy, TimeRange,
+};
+use bimetable_domain::events::xlsx::{parse_timetable, timetable_events, TimetableImport};
+use bimetable_domain::limits::{OverrideShiftLimit, RepetitionLimit};
+use bimetable_domain::validation::ValidateContent;
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use time::{Duration, OffsetDateTime, UtcOffset};
+use tracing::instrument;
+use uuid::Uuid;
+
+use super::UserEvent;
+
+/// How far ahead feeds of infinitely recurring events reach
+const FEED_HORIZON: Duration = Duration::days(365);
+/// How far back feeds of infinitely recurring events reach
+const FEED_HISTORY: Duration = Duration::days(365);
+/// How long download links of exports work
+const EXPORT_LINK_LIFETIME: Duration = Duration::minutes(15);
+
+/// Gets events with entries in the search range.
+///
+/// Weekly recurrences are expanded with weeks starting on `week_start`,
+/// falling back to the user's preference.
+#[instrument(skip_all, fields(%user_id))]
+pub async fn get_many_events(
+    user_id: Uuid,
+    search_range: TimeRange,
+    filter: EventFilter,
+    week_start: Option<DayOfWeek>,
+    pool: &PgPool,
+) -> Result<Events, EventError> {
+    fetch_events(
+        user_id,
+        search_range,
+        filter,
+        week_start,
+        &EntriesPage::default(),
+        pool,
+    )
+    .await
+}
+
+/// Gets the busy times of users in the search range, without any details of their events.
+///
+/// Every user other than the viewer must have shared their busy times with the viewer.
+#[instrument(skip_all, fields(%viewer_id))]
+pub async fn get_combined_busy(
+    viewer_id: Uuid,
+    user_ids: &[Uuid],
+    search_range: TimeRange,
+    pool: &PgPool,
+) -> Result<CombinedBusy, EventError> {
+    search_range.validate_content()?;
+    let user_ids: HashSet<Uuid> = user_ids.iter().copied().collect();
+
+    let mut conn = pool.acquire().await?;
+    let mut q = PgQuery::new(EventQuery::new(viewer_id), &mut conn);
+    let others: Vec<Uuid> = user_ids
+        .iter()
+        .copied()
+        .filter(|user_id| *user_id != viewer_id)
+        .collect();
+    if q.busy_visible_owners(&others).await?.len() != others.len() {
+        return Err(EventError::MismatchedPrivileges);
+    }
+    drop(conn);
+
+    let mut users = HashMap::with_capacity(user_ids.len());
+    for user_id in user_ids {
+        let events = get_many_events(user_id, search_range, EventFilter::All, None, pool).await?;
+        users.insert(user_id, events.busy_ranges(&search_range));
+    }
+    let busy = TimeRange::merge(users.values().flatten().copied().collect());
+
+    Ok(CombinedBusy { users, busy })
+}
+
+/// Finds the earliest windows within the search window when the user and all participants are free.
+#[instrument(skip_all, fields(%user_id))]
+pub async fn suggest_free_slots(
+    user_id: Uuid,
+    body: SuggestSlot,
+    pool: &PgPool,
+) -> Result<SuggestedSlots, EventError> {
+    body.validate_content()?;
+    let window = TimeRange::new(body.starts_at, body.ends_at);
+    let duration = Duration::minutes(body.duration_minutes.into());
+
+    let mut participants = body.participants;
+    participants.push(user_id);
+    let combined = get_combined_busy(user_id, &participants, window, pool).await?;
+    let slots = window
+        .gaps(&combined.busy)
+        .into_iter()
+        .filter(|gap| gap.duration() >= duration)
+        .take(body.limit as usize)
+        .collect();
+
+    Ok(SuggestedSlots { slots })
+}
+
+/// Gets a page of entries in the search range, along with their events.
+///
+/// Pages continue from `cursor` with at most `limit` entries.
+/// Events-only pages skip entry expansion and are never cut.
+#[instrument(skip_all, fields(%user_id))]
+pub async fn get_events_page(
+    user_id: Uuid,
+    search_range: TimeRange,
+    filter: EventFilter,
+    week_start: Option<DayOfWeek>,
+    page: EntriesPage,
+    pool: &PgPool,
+) -> Result<EventsPage, EventError> {
+    if page.events_only {
+        let events = fetch_events(user_id, search_range, filter, week_start, &page, pool).await?;
+        return Ok(EventsPage {
+            events,
+            next_cursor: None,
+        });
+    }
+
+    let search_range = match page.cursor {
+        Some(cursor) => TimeRange::new(cursor.max(search_range.start), search_range.end),
+        None => search_range,
+    };
+    let events = fetch_events(user_id, search_range, filter, week_start, &page, pool).await?;
+
+    Ok(events.page(page.cursor, page.limit))
+}
+
+async fn fetch_events(
+    user_id: Uuid,
+    search_range: TimeRange,
+    filter: EventFilter,
+    week_start: Option<DayOfWeek>,
+    page: &EntriesPage,
+    pool: &PgPool,
+) -> Result<Events, EventError> {
+    let mut conn = pool.begin().await?;
+    let mut q = PgQuery::new(EventQuery { user_id }, &mut conn);
+    let week_start = match week_start {
+        Some(week_start) => week_start,
+        None => q.get_week_start().await?,
+    }
+    .into();
+
+    let mut events = Events::new(HashMap::new(), vec![]);
+    if matches!(filter, EventFilter::All | EventFilter::Owned) {
+        events.append(get_owned(search_range, week_start, page, &mut q).await?);
+    }
+    if matches!(filter, EventFilter::All | EventFilter::Shared) {
+        events.append(get_shared(search_range, week_start, page, &mut q).await?);
+    }
+
+    Ok(events)
+}
+
+#[instrument(skip_all, fields(%user_id))]
+pub async fn create_new_event(
+    pool: &PgPool,
+    user_id: Uuid,
+    body: CreateEvent,
+    repetition_limit: RepetitionLimit,
+) -> Result<Uuid, EventError> {
+    body.validate_repetitions(repetition_limit)?;
+    body.validate_content()?;
+
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut transaction);
+    let event_id = q.create_event(body).await?;
+    q.notify(Topic::EventCreated, event_id).await?;
+    transaction.commit().await?;
+
+    Ok(event_id)
+}
+
+/// Reads and validates the events of an `.xlsx` timetable, warning about left out lessons.
+fn read_xlsx_timetable(
+    range: TimeRange,
+    bytes: &[u8],
+    repetition_limit: RepetitionLimit,
+) -> Result<TimetableImport, EventError> {
+    range.validate_content()?;
+    let import = timetable_events(parse_timetable(bytes)?, range);
+    for event in &import.events {
+        event.validate_repetitions(repetition_limit)?;
+        event.validate_content()?;
+    }
+
+    Ok(import)
+}
+
+/// Creates weekly events from the lessons of an `.xlsx` timetable.
+#[instrument(skip_all, fields(%user_id))]
+pub async fn import_xlsx_timetable(
+    pool: &PgPool,
+    user_id: Uuid,
+    range: TimeRange,
+    bytes: &[u8],
+    repetition_limit: RepetitionLimit,
+) -> Result<ImportEventsResult, EventError> {
+    let import = read_xlsx_timetable(range, bytes, repetition_limit)?;
+    let event_ids = create_imported_events(pool, user_id, import.events).await?;
+
+    Ok(ImportEventsResult {
+        event_ids,
+        events: Vec::new(),
+        warnings: import.warnings,
+        mapping: Vec::new(),
+    })
+}
+
+/// Creates validated events of an import at once.
+async fn create_imported_events(
+    pool: &PgPool,
+    user_id: Uuid,
+    events: Vec<CreateEvent>,
+) -> Result<Vec<Uuid>, EventError> {
+    let mut transaction = pool.begin().await?;
+    // Imports create events, so imports of the same user queue up instead
+    lock_for_transaction(&mut transaction, user_id).await?;
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut transaction);
+    let mut event_ids = Vec::with_capacity(events.len());
+    for event in events {
+        let event_id = q.create_event(event).await?;
+        q.notify(Topic::EventCreated, event_id).await?;
+        event_ids.push(event_id);
+    }
+    transaction.commit().await?;
+
+    Ok(event_ids)
+}
+
+/// Lists the events an import of the `.xlsx` timetable would create, without writing anything.
+pub fn preview_xlsx_timetable(
+    range: TimeRange,
+    bytes: &[u8],
+    repetition_limit: RepetitionLimit,
+) -> Result<ImportEventsResult, EventError> {
+    let import = read_xlsx_timetable(range, bytes, repetition_limit)?;
+
+    Ok(ImportEventsResult {
+        event_ids: Vec::new(),
+        events: import.events,
+        warnings: import.warnings,
+        mapping: Vec::new(),
+    })
+}
+
+/// Creates events of a class schedule exported from Google Classroom or Teams, see [`schedule_events`].
+#[instrument(skip_all, fields(%user_id))]
+pub async fn import_classroom_schedule(
+    pool: &PgPool,
+    user_id: Uuid,
+    offset: Option<UtcOffset>,
+    bytes: &[u8],
+    repetition_limit: RepetitionLimit,
+) -> Result<ImportEventsResult, EventError> {
+    let import = schedule_events(bytes, offset, repetition_limit)?;
+    let event_ids = create_imported_events(pool, user_id, import.events).await?;
+
+    Ok(ImportEventsResult {
+        event_ids,
+        events: Vec::new(),
+        warnings: Vec::new(),
+        mapping: import.mapping,
+    })
+}
+
+/// Lists the events an import of the class schedule would create, without writing anything.
+pub fn preview_classroom_schedule(
+    offset: Option<UtcOffset>,
+    bytes: &[u8],
+    repetition_limit: RepetitionLimit,
+) -> Result<ImportEventsResult, EventError> {
+    let import = schedule_events(bytes, offset, repetition_limit)?;
+
+    Ok(ImportEventsResult {
+        event_ids: Vec::new(),
+        events: import.events,
+        warnings: Vec::new(),
+        mapping: import.mapping,
+    })
+}
+
+#[instrument(skip_all, fields(%user_id, %event_id))]
+pub async fn get_one_event(
+    pool: &PgPool,
+    user_id: Uuid,
+    event_id: Uuid,
+) -> Result<Event, EventError> {
+    let mut conn = pool.acquire().await?;
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut conn);
+    let event = q.get_event(event_id).await?.ok_or(EventError::NotFound)?;
+
+    Ok(event)
+}
+
+/// Gets the event like [`get_one_event`], soft deleted events are found by their owner.
+#[instrument(skip_all, fields(%user_id, %event_id))]
+pub async fn get_one_event_including_deleted(
+    pool: &PgPool,
+    user_id: Uuid,
+    event_id: Uuid,
+) -> Result<Event, EventError> {
+    let mut conn = pool.acquire().await?;
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut conn);
+    let event = q
+        .get_event_including_deleted(event_id)
+        .await?
+        .ok_or(EventError::NotFound)?;
+
+    Ok(event)
+}
+
+/// Gets overrides and pauses of the event without expanding its entries,
+/// also of the soft deleted events of the user.
+#[instrument(skip_all, fields(%user_id, %event_id))]
+pub async fn get_one_event_exceptions(
+    pool: &PgPool,
+    user_id: Uuid,
+    event_id: Uuid,
+) -> Result<EventExceptions, EventError> {
+    let mut conn = pool.acquire().await?;
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut conn);
+    q.get_event_including_deleted(event_id)
+        .await?
+        .ok_or(EventError::NotFound)?;
+
+    let overrides = q.get_overrides(vec![event_id], false).await?;
+    let pauses = q
+        .get_pauses(vec![event_id])
+        .await?
+        .remove(&event_id)
+        .unwrap_or_default();
+    Ok(EventExceptions {
+        overrides: overrides.into_iter().map(RangedOverride::from).collect(),
+        pauses,
+    })
+}
+
+#[instrument(skip_all, fields(%user_id, %event_id))]
+pub async fn update_one_event(
+    pool: &PgPool,
+    user_id: Uuid,
+    body: UpdateEvent,
+    event_id: Uuid,
+) -> Result<(), EventError> {
+    body.validate_content()?;
+
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut transaction);
+    if q.can_edit(event_id).await? {
+        q.update_event(event_id, body.data).await?;
+        q.notify(Topic::EventUpdated, event_id).await?;
+        return Ok(transaction.commit().await?);
+    }
+    Err(EventError::MismatchedPrivileges)
+}
+
+#[instrument(skip_all, fields(%user_id, %event_id))]
+pub async fn update_one_event_recurrence(
+    pool: &PgPool,
+    user_id: Uuid,
+    body: UpdateRecurrence,
+    event_id: Uuid,
+    repetition_limit: RepetitionLimit,
+) -> Result<Vec<String>, EventError> {
+    body.validate_content()?;
+
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut transaction);
+    if q.can_edit(event_id).await? {
+        let warnings = q
+            .update_recurrence_rule(event_id, body.recurrence_rule, repetition_limit)
+            .await?;
+        q.notify(Topic::EventUpdated, event_id).await?;
+        transaction.commit().await?;
+        return Ok(warnings);
+    }
+    Err(EventError::MismatchedPrivileges)
+}
+
+#[instrument(skip_all, fields(%user_id, %event_id))]
+pub async fn pause_one_event(
+    pool: &PgPool,
+    user_id: Uuid,
+    body: PauseEvent,
+    event_id: Uuid,
+) -> Result<(), EventError> {
+    body.validate_content()?;
+
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut transaction);
+    if q.can_edit(event_id).await? {
+        q.pause_event(event_id, TimeRange::new(body.starts_at, body.ends_at))
+            .await?;
+        q.notify(Topic::EventUpdated, event_id).await?;
+        return Ok(transaction.commit().await?);
+    }
+    Err(EventError::MismatchedPrivileges)
+}
+
+#[instrument(skip_all, fields(%user_id, %event_id))]
+pub async fn split_one_event(
+    pool: &PgPool,
+    user_id: Uuid,
+    body: SplitEvent,
+    event_id: Uuid,
+) -> Result<Uuid, EventError> {
+    body.validate_content()?;
+
+    let mut transaction = pool.begin().await?;
+    lock_for_transaction(&mut transaction, event_id).await?;
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut transaction);
+    if q.can_edit(event_id).await? {
+        let successor_id = q.split_event(event_id, body).await?;
+        q.notify(Topic::EventUpdated, event_id).await?;
+        q.notify(Topic::EventCreated, successor_id).await?;
+        transaction.commit().await?;
+        return Ok(successor_id);
+    }
+    Err(EventError::MismatchedPrivileges)
+}
+
+#[instrument(skip_all, fields(%user_id, %event_id))]
+pub async fn delete_one_event_temporally(
+    pool: &PgPool,
+    user_id: Uuid,
+    event_id: Uuid,
+    now: OffsetDateTime,
+) -> Result<(), EventError> {
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut transaction);
+    if q.is_owner(event_id).await? {
+        q.temp_delete(event_id, now).await?;
+        q.notify(Topic::EventDeleted, event_id).await?;
+        return Ok(transaction.commit().await?);
+    }
+    Err(EventError::MismatchedPrivileges)
+}
+
+#[instrument(skip_all, fields(%user_id, %event_id))]
+pub async fn create_one_event_override(
+    pool: &PgPool,
+    user_id: Uuid,
+    body: OverrideEvent,
+    event_id: Uuid,
+    shift_limit: OverrideShiftLimit,
+) -> Result<(), EventError> {
+    body.validate_content()?;
+
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut transaction);
+    let privileges = q.get_privileges(event_id).await?;
+    if !privileges.can_edit() {
+        return Err(EventError::MismatchedPrivileges);
+    }
+    let is_owned = matches!(privileges, EventPrivileges::Owned);
+
+    let event = q.get_event_base(event_id).await?;
+    body.data
+        .validate_with_entry(&event.time_range, shift_limit)
+        .map_err(|e| e.at("data"))?;
+    let range = TimeRange::new(body.override_starts_at, body.override_ends_at);
+    if !is_owned && q.covers_locked_occurrence(event_id, range).await? {
+        return Err(EventError::LockedOccurrence);
+    }
+    if event.override_strategy == OverrideStrategy::Reject
+        && q.shadows_override(event_id, range).await?
+    {
+        return Err(EventError::ShadowingOverride);
+    }
+
+    q.create_override(event_id, body).await?;
+    q.notify(Topic::EventUpdated, event_id).await?;
+    Ok(transaction.commit().await?)
+}
+
+/// Overrides a single entry of the event, found by its occurrence id.
+#[instrument(skip_all, fields(%user_id, %event_id, %occurrence_id))]
+pub async fn create_one_occurrence_override(
+    pool: &PgPool,
+    user_id: Uuid,
+    data: OverrideEventData,
+    event_id: Uuid,
+    occurrence_id: Uuid,
+    shift_limit: OverrideShiftLimit,
+    repetition_limit: RepetitionLimit,
+) -> Result<(), EventError> {
+    let mut conn = pool.acquire().await?;
+    let entry = PgQuery::new(EventQuery::new(user_id), &mut conn)
+        .get_occurrence(event_id, occurrence_id, repetition_limit)
+        .await?;
+    drop(conn);
+
+    let body = OverrideEvent {
+        override_starts_at: entry.start,
+        override_ends_at: entry.end,
+        data,
+    };
+    body.validate_content()?;
+    create_one_event_override(pool, user_id, body, event_id, shift_limit).await
+}
+
+/// Locks the entry against overrides by editors, e.g. for exam dates, owners may still override it.
+#[instrument(skip_all, fields(%user_id, %event_id, %occurrence_id))]
+pub async fn lock_one_occurrence(
+    pool: &PgPool,
+    user_id: Uuid,
+    event_id: Uuid,
+    occurrence_id: Uuid,
+    repetition_limit: RepetitionLimit,
+) -> Result<(), EventError> {
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut transaction);
+    if !q.is_owner(event_id).await? {
+        return Err(EventError::MismatchedPrivileges);
+    }
+
+    let entry = q
+        .get_occurrence(event_id, occurrence_id, repetition_limit)
+        .await?;
+    q.lock_occurrence(event_id, occurrence_id, entry).await?;
+    q.notify(Topic::EventUpdated, event_id).await?;
+    Ok(transaction.commit().await?)
+}
+
+#[instrument(skip_all, fields(%user_id, %event_id, %occurrence_id))]
+pub async fn unlock_one_occurrence(
+    pool: &PgPool,
+    user_id: Uuid,
+    event_id: Uuid,
+    occurrence_id: Uuid,
+) -> Result<(), EventError> {
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut transaction);
+    if !q.is_owner(event_id).await? {
+        return Err(EventError::MismatchedPrivileges);
+    }
+
+    if !q.unlock_occurrence(event_id, occurrence_id).await? {
+        return Err(EventError::NotFound);
+    }
+    q.notify(Topic::EventUpdated, event_id).await?;
+    Ok(transaction.commit().await?)
+}
+
+#[instrument(skip_all, fields(%user_id, %event_id))]
+pub async fn update_one_event_override_strategy(
+    pool: &PgPool,
+    user_id: Uuid,
+    body: UpdateOverrideStrategy,
+    event_id: Uuid,
+) -> Result<(), EventError> {
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut transaction);
+    if q.is_owner(event_id).await? {
+        q.set_override_strategy(event_id, body.strategy).await?;
+        q.notify(Topic::EventUpdated, event_id).await?;
+        return Ok(transaction.commit().await?);
+    }
+    Err(EventError::MismatchedPrivileges)
+}
+
+#[instrument(skip_all, fields(%user_id, %event_id))]
+pub async fn delete_one_event_permanently(
+    pool: &PgPool,
+    user_id: Uuid,
+    event_id: Uuid,
+) -> Result<(), EventError> {
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut transaction);
+    if q.is_owner(event_id).await? {
+        q.perm_delete(event_id).await?;
+        q.notify(Topic::EventDeleted, event_id).await?;
+        return Ok(transaction.commit().await?);
+    }
+    Err(EventError::MismatchedPrivileges)
+}
+
+#[instrument(skip_all, fields(%user_id, %event_id))]
+pub async fn update_user_editing_privileges(
+    pool: &PgPool,
+    user_id: Uuid,
+    body: UpdateEditPrivilege,
+    event_id: Uuid,
+) -> Result<(), EventError> {
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut transaction);
+    if q.is_owner(event_id).await? && user_id != body.user_id {
+        q.update_edit_privileges(UserId(body.user_id), EventId(event_id), body.can_edit)
+            .await?;
+        q.notify(Topic::ParticipantsChanged, event_id).await?;
+        return Ok(transaction.commit().await?);
+    }
+    Err(EventError::MismatchedPrivileges)
+}
+
+/// Changes editing privileges of many participants at once.
+///
+/// Nothing is changed when any of the users is the owner or not a participant of the event.
+#[instrument(skip_all, fields(%user_id, %event_id))]
+pub async fn update_many_editing_privileges(
+    pool: &PgPool,
+    user_id: Uuid,
+    body: UpdateEditPrivileges,
+    event_id: Uuid,
+) -> Result<(), EventError> {
+    body.validate_content()?;
+    let mut transaction = pool.begin().await?;
+    lock_for_transaction(&mut transaction, event_id).await?;
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut transaction);
+    if !q.is_owner(event_id).await? || body.changes.iter().any(|change| change.user_id == user_id) {
+        return Err(EventError::MismatchedPrivileges);
+    }
+
+    let updated = q
+        .update_many_edit_privileges(event_id, &body.changes)
+        .await?;
+    if updated != body.changes.len() as u64 {
+        return Err(EventError::NotFound);
+    }
+    q.notify(Topic::ParticipantsChanged, event_id).await?;
+    Ok(transaction.commit().await?)
+}
+
+/// Makes a participant a co-owner of the event, or takes the co-ownership back.
+///
+/// Co-owners manage the event like its owner, but only the owner can hand the event over.
+#[instrument(skip_all, fields(%user_id, %event_id))]
+pub async fn update_event_co_owner(
+    pool: &PgPool,
+    user_id: Uuid,
+    body: UpdateCoOwner,
+    event_id: Uuid,
+) -> Result<(), EventError> {
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut transaction);
+    if !q.is_owner(event_id).await? || user_id == body.user_id {
+        return Err(EventError::MismatchedPrivileges);
+    }
+
+    if !q
+        .update_co_owner(UserId(body.user_id), EventId(event_id), body.is_owner)
+        .await?
+    {
+        return Err(EventError::NotFound);
+    }
+    q.notify(Topic::ParticipantsChanged, event_id).await?;
+    Ok(transaction.commit().await?)
+}
+
+#[instrument(skip_all, fields(%user_id, %event_id, %target_user_id))]
+pub async fn set_event_ownership(
+    pool: &PgPool,
+    user_id: Uuid,
+    target_user_id: Uuid,
+    event_id: Uuid,
+) -> Result<(), EventError> {
+    let mut transaction = pool.begin().await?;
+    lock_for_transaction(&mut transaction, event_id).await?;
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut transaction);
+
+    if q.is_primary_owner(event_id).await? && user_id != target_user_id {
+        q.delete_user_event(UserId(target_user_id), EventId(event_id))
+            .await?;
+        q.update_event_owner(UserId(target_user_id), EventId(event_id))
+            .await?;
+        q.create_user_event(UserEvent::new(UserId(user_id), EventId(event_id), true))
+            .await?;
+        q.notify(Topic::ParticipantsChanged, event_id).await?;
+
+        return Ok(transaction.commit().await?);
+    }
+    Err(EventError::MismatchedPrivileges)
+}
+
+#[instrument(skip_all, fields(%user_id, %event_id))]
+pub async fn delete_user_event(
+    pool: &PgPool,
+    user_id: Uuid,
+    event_id: Uuid,
+) -> Result<(), EventError> {
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut transaction);
+
+    if !q.is_primary_owner(event_id).await? {
+        q.delete_user_event(UserId(user_id), EventId(event_id))
+            .await?;
+        q.notify(Topic::ParticipantsChanged, event_id).await?;
+        return Ok(transaction.commit().await?);
+    }
+    Err(EventError::MismatchedPrivileges)
+}
+
+#[instrument(skip_all, fields(%user_id, %event_id, %new_owner_id))]
+pub async fn delete_owner_from_event(
+    pool: &PgPool,
+    user_id: Uuid,
+    event_id: Uuid,
+    new_owner_id: Uuid,
+) -> Result<(), EventError> {
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut transaction);
+
+    if q.is_primary_owner(event_id).await? && user_id != new_owner_id {
+        q.delete_user_event(UserId(new_owner_id), EventId(event_id))
+            .await?;
+        q.update_event_owner(UserId(new_owner_id), EventId(event_id))
+            .await?;
+        q.notify(Topic::ParticipantsChanged, event_id).await?;
+
+        return Ok(transaction.commit().await?);
+    }
+    Err(EventError::MismatchedPrivileges)
+}
+
+/// Mints a feed token for the event, revoking the previous ones of the user.
+#[instrument(skip_all, fields(%user_id, %event_id))]
+pub async fn create_event_feed_token(
+    pool: &PgPool,
+    user_id: Uuid,
+    event_id: Uuid,
+) -> Result<Uuid, EventError> {
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut transaction);
+    q.get_event(event_id).await?.ok_or(EventError::NotFound)?;
+
+    q.revoke_feed_tokens(event_id).await?;
+    let token = q.create_feed_token(event_id).await?;
+    transaction.commit().await?;
+    Ok(token)
+}
+
+/// Revokes feed tokens of the user for the event, so that their feeds stop working.
+#[instrument(skip_all, fields(%user_id, %event_id))]
+pub async fn revoke_event_feed_tokens(
+    pool: &PgPool,
+    user_id: Uuid,
+    event_id: Uuid,
+) -> Result<(), EventError> {
+    let mut conn = pool.acquire().await?;
+    if !PgQuery::new(EventQuery::new(user_id), &mut conn)
+        .revoke_feed_tokens(event_id)
+        .await?
+    {
+        return Err(EventError::NotFound);
+    }
+
+    Ok(())
+}
+
+/// Renders the event as an iCalendar feed, reusing the cached one while the event is unchanged.
+///
+/// The feed stops working once its creator is no longer a participant of the event.
+#[instrument(skip_all, fields(%event_id))]
+pub async fn get_event_feed(
+    pool: &PgPool,
+    cache: &FeedCache,
+    token: Uuid,
+    event_id: Uuid,
+    now: OffsetDateTime,
+    repetition_limit: RepetitionLimit,
+) -> Result<CachedFeed, EventError> {
+    let mut conn = pool.acquire().await?;
+    let creator_id = PgQuery::new(FeedQuery::new(token), &mut conn)
+        .get_creator(event_id)
+        .await?;
+
+    let mut q = PgQuery::new(EventQuery::new(creator_id), &mut conn);
+    q.get_event(event_id).await?.ok_or(EventError::NotFound)?;
+
+    cached_ics(&mut q, cache, event_id, now, repetition_limit).await
+}
+
+/// Gets the iCalendar body of the event from the cache, generating it when the event has changed since.
+async fn cached_ics(
+    q: &mut PgQuery<'_, EventQuery>,
+    cache: &FeedCache,
+    event_id: Uuid,
+    now: OffsetDateTime,
+    repetition_limit: RepetitionLimit,
+) -> Result<CachedFeed, EventError> {
+    let user_id = q.payload.user_id;
+    let version = FeedVersion {
+        event_id,
+        changed_at: q.get_last_change(event_id).await?,
+        day: now.date(),
+    };
+    if let Some(feed) = cache.get(user_id, &version) {
+        return Ok(feed);
+    }
+
+    let events = q
+        .get_event_entries(
+            event_id,
+            Some(now - FEED_HISTORY),
+            now + FEED_HORIZON,
+            repetition_limit,
+        )
+        .await?;
+    let feed = CachedFeed::new(&version, events_to_ics(&events, now));
+    cache.insert(user_id, event_id, feed.clone());
+    Ok(feed)
+}
+
+/// Stores the event as an iCalendar file, returning a link to download it.
+#[instrument(skip_all, fields(%event_id))]
+pub async fn export_event_ics(
+    pool: &PgPool,
+    storage: &dyn Storage,
+    cache: &FeedCache,
+    user_id: Uuid,
+    event_id: Uuid,
+    now: OffsetDateTime,
+    repetition_limit: RepetitionLimit,
+) -> Result<EventExport, EventError> {
+    let mut conn = pool.acquire().await?;
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut conn);
+    q.get_event(event_id).await?.ok_or(EventError::NotFound)?;
+
+    let feed = cached_ics(&mut q, cache, event_id, now, repetition_limit).await?;
+
+    let key = format!("exports/{event_id}/{}.ics", Uuid::new_v4());
+    storage.put(&key, feed.body.into_bytes()).await?;
+
+    let expires_at = now + EXPORT_LINK_LIFETIME;
+    Ok(EventExport {
+        url: storage.signed_url(&key, expires_at)?,
+        expires_at,
+    })
+}
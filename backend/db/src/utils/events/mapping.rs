@@ -0,0 +1,97 @@
+//! Conversions of the rows read by the database layer into payloads served over HTTP.
+
+use crate::utils::events::{QEvent, QOverride};
+use bimetable_domain::api::events::{Event, EventPayload, Override, RangedOverride};
+
+impl From<QEvent> for Event {
+    fn from(val: QEvent) -> Self {
+        let entries_end = match &val.recurrence_rule {
+            Some(rule) => rule.span.map(|span| span.end),
+            None => Some(val.time_range.end),
+        };
+
+        Event {
+            deleted_at: val.deleted_at,
+            ..Event::new(
+                val.privileges,
+                EventPayload::new(val.name, val.description),
+                val.recurrence_rule,
+                val.time_range.start,
+                entries_end,
+            )
+        }
+    }
+}
+
+impl From<QOverride> for Override {
+    fn from(val: QOverride) -> Self {
+        Self {
+            name: val.name,
+            description: val.description,
+            starts_at: val.starts_at,
+            ends_at: val.ends_at,
+            deleted_at: val.deleted_at,
+            status: val.status,
+            created_at: val.created_at,
+            added_participants: val.added_participants,
+            excluded_participants: val.excluded_participants,
+        }
+    }
+}
+
+impl From<QOverride> for RangedOverride {
+    fn from(val: QOverride) -> Self {
+        Self {
+            override_starts_at: val.override_starts_at,
+            override_ends_at: val.override_ends_at,
+            data: Override::from(val),
+        }
+    }
+}
+
+#[cfg(test)]
+mod mapping_tests {
+    use time::macros::datetime;
+    use uuid::Uuid;
+
+    use bimetable_domain::events::models::{
+        EntriesSpan, EventPrivileges, OverrideStrategy, RecurrenceRule, RecurrenceRuleKind,
+        TimeRange,
+    };
+
+    use super::*;
+
+    fn event(recurrence_rule: Option<RecurrenceRule>) -> QEvent {
+        QEvent {
+            id: Uuid::new_v4(),
+            name: "Fizyka".to_string(),
+            description: None,
+            time_range: TimeRange::new(
+                datetime!(2023-03-08 9:45 UTC),
+                datetime!(2023-03-08 10:30 UTC),
+            ),
+            deleted_at: None,
+            recurrence_rule,
+            privileges: EventPrivileges::Shared { can_edit: true },
+            override_strategy: OverrideStrategy::Latest,
+        }
+    }
+
+    #[test]
+    fn event_entries_end_with_its_span() {
+        let recurring = Event::from(event(Some(RecurrenceRule {
+            span: Some(EntriesSpan {
+                end: datetime!(2023-04-27 10:30 UTC),
+                repetitions: 15,
+            }),
+            interval: 1,
+            kind: RecurrenceRuleKind::Weekly { week_map: 24 },
+        })));
+        assert_eq!(recurring.entries_end, Some(datetime!(2023-04-27 10:30 UTC)));
+        assert!(!recurring.is_owned);
+        assert!(recurring.can_edit);
+
+        let one_off = Event::from(event(None));
+        assert_eq!(one_off.entries_end, Some(datetime!(2023-03-08 10:30 UTC)));
+    }
+}
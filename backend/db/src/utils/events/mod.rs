@@ -0,0 +1,2367 @@
+use anyhow::{anyhow, Context};
+use metrics::histogram;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+use serde_json::json;
+use sqlx::postgres::types::PgInterval;
+use sqlx::query;
+use time::{Duration, OffsetDateTime, Weekday};
+use tracing::log::trace;
+use uuid::Uuid;
+
+use crate::modules::database::{EventId, PgQuery, UserId};
+use crate::modules::outbox::{self, Topic};
+use bimetable_domain::api::events::{
+    CreateEvent, Entry, Event, EventData, EventPayload, Events, OptionalEventData, Override,
+    OverrideEvent, RecurrenceRuleSchema, SplitEvent, UpdateEditPrivilege,
+};
+use bimetable_domain::events::models::{
+    DayOfWeek, EntriesPage, EventPrivileges, OverrideStatus, OverrideStrategy, RecurrenceRule,
+    RecurrenceRuleKind, TimeRange,
+};
+use bimetable_domain::events::occurrences::{self, find_occurrence};
+use bimetable_domain::events::split::{split_recurrence, successor_occurrences};
+use bimetable_domain::events::week_start::WeekAlignedRule;
+use bimetable_domain::limits::RepetitionLimit;
+use bimetable_domain::validation::{ValidateContent, ValidateContentError};
+
+use self::errors::EventError;
+
+/// Time spent expanding the entries of a single recurring event, labeled by its rule `kind`
+pub const RECURRENCE_EXPANSION_SECONDS: &str = "bimetable_recurrence_expansion_seconds";
+/// Fewest events expanded by a single task, splitting cheaper work costs more than it saves
+const MIN_EVENTS_PER_EXPANSION_TASK: usize = 8;
+
+pub mod digest;
+pub mod errors;
+pub mod exe;
+mod mapping;
+
+#[derive(Debug)]
+pub struct QOverride {
+    pub event_id: Uuid,
+    pub override_starts_at: OffsetDateTime,
+    pub override_ends_at: OffsetDateTime,
+    pub created_at: OffsetDateTime,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub starts_at: Option<Duration>,
+    pub ends_at: Option<Duration>,
+    pub deleted_at: Option<OffsetDateTime>,
+    pub status: Option<OverrideStatus>,
+    pub added_participants: Vec<Uuid>,
+    pub excluded_participants: Vec<Uuid>,
+}
+
+#[derive(Debug)]
+#[allow(unused)]
+pub struct QOwnedEvent {
+    id: Uuid,
+    name: String,
+    description: Option<String>,
+    starts_at: OffsetDateTime,
+    ends_at: OffsetDateTime,
+    deleted_at: Option<OffsetDateTime>,
+    recurrence_rule: Option<RecurrenceRule>,
+}
+
+#[derive(Debug)]
+#[allow(unused)]
+pub struct QSharedEvent {
+    id: Uuid,
+    name: String,
+    description: Option<String>,
+    starts_at: OffsetDateTime,
+    ends_at: OffsetDateTime,
+    deleted_at: Option<OffsetDateTime>,
+    recurrence_rule: Option<RecurrenceRule>,
+    can_edit: bool,
+}
+
+#[derive(Debug)]
+pub struct QEvent {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub time_range: TimeRange,
+    pub deleted_at: Option<OffsetDateTime>,
+    pub recurrence_rule: Option<RecurrenceRule>,
+    pub privileges: EventPrivileges,
+    pub override_strategy: OverrideStrategy,
+}
+
+#[derive(Debug)]
+pub struct QEventBase {
+    owner_id: Uuid,
+    name: String,
+    description: Option<String>,
+    time_range: TimeRange,
+    recurrence_rule: Option<RecurrenceRule>,
+    override_strategy: OverrideStrategy,
+}
+
+pub struct UserEvent {
+    pub user_id: UserId,
+    pub event_id: EventId,
+    pub can_edit: bool,
+}
+
+impl UserEvent {
+    pub fn new(user_id: UserId, event_id: EventId, can_edit: bool) -> Self {
+        Self {
+            user_id,
+            event_id,
+            can_edit,
+        }
+    }
+}
+
+pub struct EventQuery {
+    user_id: Uuid,
+}
+
+/// Access to an event through a feed token.
+pub struct FeedQuery {
+    token: Uuid,
+}
+
+impl FeedQuery {
+    pub fn new(token: Uuid) -> Self {
+        Self { token }
+    }
+}
+
+impl<'c> PgQuery<'c, FeedQuery> {
+    /// Gets the user who minted the feed token for the event.
+    pub async fn get_creator(&mut self, event_id: Uuid) -> Result<Uuid, EventError> {
+        let creator_id = query!(
+            r#"
+                SELECT created_by FROM event_feed_tokens
+                WHERE token = $1 AND event_id = $2
+            "#,
+            self.payload.token,
+            event_id,
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?
+        .ok_or(EventError::NotFound)?
+        .created_by;
+
+        Ok(creator_id)
+    }
+}
+
+impl EventQuery {
+    pub fn new(user_id: Uuid) -> Self {
+        Self { user_id }
+    }
+}
+
+impl<'c> PgQuery<'c, EventQuery> {
+    pub async fn create_event(&mut self, event: CreateEvent) -> Result<Uuid, EventError> {
+        let rule = if let Some(rule) = event.recurrence_rule {
+            let rule =
+                rule.to_compute(&TimeRange::new(event.data.starts_at, event.data.ends_at))?;
+            Some(rule)
+        } else {
+            None
+        };
+
+        let event_id = query!(
+            r#"
+                INSERT INTO events (owner_id, name, description, starts_at, ends_at)
+                VALUES
+                ($1, $2, $3, $4, $5)
+                RETURNING id
+            "#,
+            self.payload.user_id,
+            event.data.payload.name,
+            event.data.payload.description,
+            event.data.starts_at,
+            event.data.ends_at,
+        )
+        .fetch_one(&mut *self.conn)
+        .await?
+        .id;
+
+        if let Some(recurrence) = rule {
+            self.create_recurrence_rule(event_id, recurrence).await?;
+        }
+
+        trace!("Created event {event_id}");
+        Ok(event_id)
+    }
+
+    pub async fn create_recurrence_rule(
+        &mut self,
+        event_id: Uuid,
+        recurrence: RecurrenceRule,
+    ) -> Result<(), EventError> {
+        let (until, count) = (
+            recurrence.span.map(|x| x.end),
+            recurrence.span.map(|x| x.repetitions as i32),
+        );
+        let interval = recurrence.interval as i32;
+        query!(
+            r#"
+                INSERT INTO recurrence_rules (event_id, recurrence, until, count, interval)
+                VALUES
+                ($1, $2, $3, $4, $5)
+            "#,
+            event_id,
+            sqlx::types::Json(recurrence.kind) as _,
+            until,
+            count,
+            interval,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!("Created recurrence rule for event {event_id}");
+        Ok(())
+    }
+
+    pub async fn delete_recurrence_rule(&mut self, event_id: Uuid) -> Result<(), EventError> {
+        query!(
+            r#"
+                DELETE FROM recurrence_rules
+                WHERE event_id = $1
+            "#,
+            event_id,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!("Deleted recurrence rule of event {event_id}");
+        Ok(())
+    }
+
+    pub async fn get_event_time_range(&mut self, event_id: Uuid) -> Result<TimeRange, EventError> {
+        let event = query!(
+            r#"
+                SELECT starts_at, ends_at
+                FROM events
+                WHERE id = $1 AND deleted_at IS NULL
+            "#,
+            event_id,
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?
+        .ok_or(EventError::NotFound)?;
+
+        Ok(TimeRange::new(event.starts_at, event.ends_at))
+    }
+
+    /// Replaces the recurrence rule of the event, returning the warnings about the new rule.
+    ///
+    /// Exceptions of the occurrences left out by the new rule are deleted.
+    pub async fn update_recurrence_rule(
+        &mut self,
+        event_id: Uuid,
+        rule: Option<RecurrenceRuleSchema>,
+        repetition_limit: RepetitionLimit,
+    ) -> Result<Vec<String>, EventError> {
+        let event_range = self.get_event_time_range(event_id).await?;
+        let mut warnings = Vec::new();
+        let rule = rule
+            .map(|rule| {
+                rule.validate_repetitions(&event_range, repetition_limit)
+                    .and_then(|_| rule.validate_with_event(&event_range))
+                    .map_err(|e| e.at("recurrenceRule"))?;
+                warnings = rule.warnings_with_event(&event_range);
+                rule.to_compute(&event_range)
+            })
+            .transpose()?;
+
+        self.prune_exceptions(event_id, event_range, rule.as_ref())
+            .await?;
+        self.delete_recurrence_rule(event_id).await?;
+        if let Some(rule) = rule {
+            self.create_recurrence_rule(event_id, rule).await?;
+        }
+
+        trace!("Updated recurrence rule of event {event_id}");
+        Ok(warnings)
+    }
+
+    /// Deletes the overrides, pauses and locks of the event which don't apply to any of its occurrences
+    /// under the rule, so that they don't come back to occurrences of a later rule.
+    async fn prune_exceptions(
+        &mut self,
+        event_id: Uuid,
+        event_range: TimeRange,
+        rule: Option<&RecurrenceRule>,
+    ) -> Result<(), EventError> {
+        let occurrences = |range: TimeRange| match rule {
+            Some(rule) if range.start < range.end => rule.get_event_range(range, event_range),
+            Some(_) => Ok(Vec::new()),
+            None => Ok(vec![event_range]),
+        };
+
+        let overrides = self
+            .get_override_ranges(event_id, OffsetDateTime::UNIX_EPOCH)
+            .await?;
+        for (override_id, range) in overrides {
+            if !occurrences(range)?
+                .iter()
+                .any(|occurrence| occurrence.is_contained(&range))
+            {
+                self.delete_override(override_id).await?;
+            }
+        }
+
+        for (pause_id, range) in self.get_pause_ranges(event_id).await? {
+            if !occurrences(range)?
+                .iter()
+                .any(|occurrence| is_paused(*occurrence, &[range]))
+            {
+                self.delete_pause(pause_id).await?;
+            }
+        }
+
+        let locks = self
+            .get_locked_ranges(event_id, OffsetDateTime::UNIX_EPOCH)
+            .await?;
+        for (occurrence_id, range) in locks {
+            if !occurrences(range)?.contains(&range) {
+                self.unlock_occurrence(event_id, occurrence_id).await?;
+            }
+        }
+
+        trace!("Pruned exceptions of event {event_id}");
+        Ok(())
+    }
+
+    async fn delete_override(&mut self, override_id: Uuid) -> Result<(), EventError> {
+        query!(
+            r#"
+                DELETE FROM event_overrides
+                WHERE id = $1
+            "#,
+            override_id,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!("Deleted override {override_id}");
+        Ok(())
+    }
+
+    /// Gets ids of the pauses of the event, along with their ranges.
+    async fn get_pause_ranges(
+        &mut self,
+        event_id: Uuid,
+    ) -> Result<Vec<(Uuid, TimeRange)>, EventError> {
+        let pauses = query!(
+            r#"
+                SELECT id, starts_at, ends_at FROM event_pauses
+                WHERE event_id = $1
+            "#,
+            event_id,
+        )
+        .fetch_all(&mut *self.conn)
+        .await?
+        .into_iter()
+        .map(|pause| (pause.id, TimeRange::new(pause.starts_at, pause.ends_at)))
+        .collect();
+
+        Ok(pauses)
+    }
+
+    async fn delete_pause(&mut self, pause_id: Uuid) -> Result<(), EventError> {
+        query!(
+            r#"
+                DELETE FROM event_pauses
+                WHERE id = $1
+            "#,
+            pause_id,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!("Deleted pause {pause_id}");
+        Ok(())
+    }
+
+    /// Picks the users out of `owner_ids` who shared their busy times with the user.
+    pub async fn busy_visible_owners(
+        &mut self,
+        owner_ids: &[Uuid],
+    ) -> Result<HashSet<Uuid>, EventError> {
+        let owners = query!(
+            r#"
+                SELECT owner_id FROM busy_visibility
+                WHERE viewer_id = $1 AND owner_id = ANY($2)
+            "#,
+            self.payload.user_id,
+            owner_ids,
+        )
+        .fetch_all(&mut *self.conn)
+        .await?
+        .into_iter()
+        .map(|row| row.owner_id)
+        .collect();
+
+        Ok(owners)
+    }
+
+    /// Records a notification about the event in the outbox.
+    pub async fn notify(&mut self, topic: Topic, event_id: Uuid) -> Result<(), EventError> {
+        outbox::record(
+            &mut *self.conn,
+            topic,
+            event_id,
+            json!({ "userId": self.payload.user_id }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_week_start(&mut self) -> Result<DayOfWeek, EventError> {
+        let week_start = query!(
+            r#"
+                SELECT week_start FROM users
+                WHERE id = $1
+            "#,
+            self.payload.user_id,
+        )
+        .fetch_one(&mut *self.conn)
+        .await?
+        .week_start;
+
+        Ok(DayOfWeek::from_days_from_monday(week_start).unwrap_or_default())
+    }
+
+    pub async fn create_feed_token(&mut self, event_id: Uuid) -> Result<Uuid, EventError> {
+        let token = query!(
+            r#"
+                INSERT INTO event_feed_tokens (event_id, created_by)
+                VALUES ($1, $2)
+                RETURNING token
+            "#,
+            event_id,
+            self.payload.user_id,
+        )
+        .fetch_one(&mut *self.conn)
+        .await?
+        .token;
+
+        trace!("Created feed token for event {event_id}");
+        Ok(token)
+    }
+
+    /// Revokes feed tokens of the user for the event, returning whether there were any.
+    pub async fn revoke_feed_tokens(&mut self, event_id: Uuid) -> Result<bool, EventError> {
+        let res = query!(
+            r#"
+                DELETE FROM event_feed_tokens
+                WHERE event_id = $1 AND created_by = $2
+            "#,
+            event_id,
+            self.payload.user_id,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!("Revoked feed tokens for event {event_id}");
+        Ok(res.rows_affected() > 0)
+    }
+
+    /// Gets the time of the latest change of the event, as recorded in the outbox.
+    pub async fn get_last_change(
+        &mut self,
+        event_id: Uuid,
+    ) -> Result<Option<OffsetDateTime>, EventError> {
+        let changed_at = query!(
+            r#"
+                SELECT MAX(created_at) AS changed_at FROM outbox
+                WHERE aggregate_id = $1
+            "#,
+            event_id,
+        )
+        .fetch_one(&mut *self.conn)
+        .await?
+        .changed_at;
+
+        Ok(changed_at)
+    }
+
+    /// Gets all entries of a single event, entries of an infinite event start after `since`
+    /// and end before `horizon`.
+    ///
+    /// Infinite rules repeating within a day are expanded to at most `limit` entries,
+    /// validation makes such rules end but rules stored before it may not.
+    pub async fn get_event_entries(
+        &mut self,
+        event_id: Uuid,
+        since: Option<OffsetDateTime>,
+        horizon: OffsetDateTime,
+        limit: RepetitionLimit,
+    ) -> Result<Events, EventError> {
+        let event = self.get_event_base(event_id).await?;
+        let search_range = match &event.recurrence_rule {
+            Some(RecurrenceRule {
+                span: Some(span), ..
+            }) => TimeRange::new(event.time_range.start, span.end),
+            Some(rule) => {
+                let start = since.map_or(event.time_range.start, |since| {
+                    since.max(event.time_range.start)
+                });
+                let end = sub_daily_entries_end(rule, start, limit)
+                    .map_or(horizon, |end| end.min(horizon));
+                TimeRange::new(start.min(end), end)
+            }
+            None => event.time_range,
+        };
+        self.get_event_entries_in(event_id, search_range).await
+    }
+
+    /// Gets entries of a single event in the search range, with weeks starting on the user's preference.
+    pub async fn get_event_entries_in(
+        &mut self,
+        event_id: Uuid,
+        search_range: TimeRange,
+    ) -> Result<Events, EventError> {
+        let event = self.get_event_base(event_id).await?;
+        let overrides = self.get_overrides(vec![event_id], false).await?;
+        let pauses = self.get_pauses(vec![event_id]).await?;
+        let week_start = self.get_week_start().await?;
+
+        expand_events(
+            overrides,
+            pauses,
+            vec![QEvent {
+                id: event_id,
+                name: event.name,
+                description: event.description,
+                time_range: event.time_range,
+                deleted_at: None,
+                recurrence_rule: event.recurrence_rule,
+                privileges: EventPrivileges::Shared { can_edit: false },
+                override_strategy: event.override_strategy,
+            }],
+            search_range,
+            week_start.into(),
+            self.payload.user_id,
+            EntriesPage {
+                effective: true,
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    /// Gets the original time range of the entry with the occurrence id.
+    pub async fn get_occurrence(
+        &mut self,
+        event_id: Uuid,
+        occurrence_id: Uuid,
+        limit: RepetitionLimit,
+    ) -> Result<TimeRange, EventError> {
+        let event = self.get_event_base(event_id).await?;
+        Ok(find_occurrence(
+            event_id,
+            event.time_range,
+            event.recurrence_rule.as_ref(),
+            occurrence_id,
+            limit,
+        )?)
+    }
+
+    pub async fn get_event_base(&mut self, event_id: Uuid) -> Result<QEventBase, EventError> {
+        let event = query!(
+            r#"
+                SELECT owner_id, name, description, starts_at, ends_at, override_strategy, recurrence AS "recurrence: Option<sqlx::types::Json<RecurrenceRuleKind>>", until, count, interval AS "interval: Option<i32>"
+                FROM events
+                LEFT JOIN recurrence_rules ON recurrence_rules.event_id = id
+                WHERE id = $1 AND deleted_at IS NULL
+            "#,
+            event_id,
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?
+        .ok_or(EventError::NotFound)?;
+
+        Ok(QEventBase {
+            owner_id: event.owner_id,
+            name: event.name,
+            description: event.description,
+            time_range: TimeRange::new(event.starts_at, event.ends_at),
+            recurrence_rule: RecurrenceRule::from_db_data(
+                event.recurrence.map(|kind| kind.0),
+                event.until,
+                event.count,
+                event.interval,
+            ),
+            override_strategy: OverrideStrategy::from_code(event.override_strategy)
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Ends the recurrence of an event before `split.split_at` and continues it
+    /// with a successor event using the new time range, returning the successor's id.
+    ///
+    /// Overrides and locks from the split on are moved to the successor, over the occurrences replacing theirs.
+    /// Pauses are divided at the split point and the successor keeps the override strategy.
+    pub async fn split_event(
+        &mut self,
+        event_id: Uuid,
+        split: SplitEvent,
+    ) -> Result<Uuid, EventError> {
+        let event = self.get_event_base(event_id).await?;
+        let rule = event.recurrence_rule.ok_or_else(|| {
+            EventError::InvalidData(ValidateContentError::new("Event is not recurring"))
+        })?;
+        let parts = split_recurrence(event.time_range, &rule, split.split_at)?;
+
+        self.delete_recurrence_rule(event_id).await?;
+        self.create_recurrence_rule(event_id, parts.truncated)
+            .await?;
+
+        let successor = CreateEvent {
+            data: EventData {
+                payload: EventPayload::new(event.name, event.description),
+                starts_at: split.starts_at,
+                ends_at: split.ends_at,
+            },
+            recurrence_rule: Some(parts.successor),
+        };
+        successor.validate_content()?;
+
+        let mut owner_query = PgQuery::new(EventQuery::new(event.owner_id), &mut *self.conn);
+        let successor_id = owner_query.create_event(successor).await?;
+        self.set_override_strategy(successor_id, event.override_strategy)
+            .await?;
+        self.copy_user_events(event_id, successor_id).await?;
+        self.split_pauses(event_id, successor_id, split.split_at)
+            .await?;
+
+        // Overrides and locks of the occurrences from the split on follow them to the successor
+        let overrides = self.get_override_ranges(event_id, split.split_at).await?;
+        let locks = self.get_locked_ranges(event_id, split.split_at).await?;
+        let until = overrides
+            .iter()
+            .chain(locks.iter())
+            .map(|(_, range)| range.end)
+            .max();
+        if let Some(until) = until {
+            let occurrences = successor_occurrences(
+                event.time_range,
+                &rule,
+                split.split_at,
+                TimeRange::new(split.starts_at, split.ends_at),
+                until,
+            )?;
+            for (override_id, range) in overrides {
+                let mut covered = occurrences
+                    .iter()
+                    .filter(|(replaced, _)| replaced.is_contained(&range))
+                    .map(|(_, successor)| successor);
+                let successor_range = covered.next().map(|first| {
+                    let last = covered.next_back().unwrap_or(first);
+                    TimeRange::new(first.start, last.end)
+                });
+                self.move_override(override_id, successor_id, successor_range)
+                    .await?;
+            }
+            for (occurrence_id, range) in locks {
+                let successor_range = occurrences
+                    .iter()
+                    .find(|(replaced, _)| *replaced == range)
+                    .map(|(_, successor)| *successor);
+                self.move_locked_occurrence(event_id, occurrence_id, successor_id, successor_range)
+                    .await?;
+            }
+        }
+
+        trace!("Split event {event_id} into successor {successor_id}");
+        Ok(successor_id)
+    }
+
+    /// Gets ids of the overrides of the event starting from `since`, along with their ranges.
+    async fn get_override_ranges(
+        &mut self,
+        event_id: Uuid,
+        since: OffsetDateTime,
+    ) -> Result<Vec<(Uuid, TimeRange)>, EventError> {
+        let overrides = query!(
+            r#"
+                SELECT id, override_starts_at, override_ends_at FROM event_overrides
+                WHERE event_id = $1 AND override_starts_at >= $2
+            "#,
+            event_id,
+            since,
+        )
+        .fetch_all(&mut *self.conn)
+        .await?
+        .into_iter()
+        .map(|ovr| {
+            (
+                ovr.id,
+                TimeRange::new(ovr.override_starts_at, ovr.override_ends_at),
+            )
+        })
+        .collect();
+
+        Ok(overrides)
+    }
+
+    /// Gets ids of the locked occurrences of the event starting from `since`, along with their ranges.
+    async fn get_locked_ranges(
+        &mut self,
+        event_id: Uuid,
+        since: OffsetDateTime,
+    ) -> Result<Vec<(Uuid, TimeRange)>, EventError> {
+        let locks = query!(
+            r#"
+                SELECT occurrence_id, starts_at, ends_at FROM locked_occurrences
+                WHERE event_id = $1 AND starts_at >= $2
+            "#,
+            event_id,
+            since,
+        )
+        .fetch_all(&mut *self.conn)
+        .await?
+        .into_iter()
+        .map(|lock| {
+            (
+                lock.occurrence_id,
+                TimeRange::new(lock.starts_at, lock.ends_at),
+            )
+        })
+        .collect();
+
+        Ok(locks)
+    }
+
+    /// Moves the lock to the occurrence of another event replacing the locked one,
+    /// the lock is dropped when no occurrence replaces it.
+    async fn move_locked_occurrence(
+        &mut self,
+        event_id: Uuid,
+        occurrence_id: Uuid,
+        to_event_id: Uuid,
+        range: Option<TimeRange>,
+    ) -> Result<(), EventError> {
+        let Some(range) = range else {
+            self.unlock_occurrence(event_id, occurrence_id).await?;
+            return Ok(());
+        };
+
+        query!(
+            r#"
+                UPDATE locked_occurrences
+                SET event_id = $3,
+                    occurrence_id = $4,
+                    starts_at = $5,
+                    ends_at = $6
+                WHERE event_id = $1 AND occurrence_id = $2
+            "#,
+            event_id,
+            occurrence_id,
+            to_event_id,
+            occurrences::occurrence_id(to_event_id, range.start),
+            range.start,
+            range.end,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!("Moved lock of occurrence {occurrence_id} to event {to_event_id}");
+        Ok(())
+    }
+
+    /// Divides the pauses of the event at `split_at`, the parts from the split on are moved to another event.
+    async fn split_pauses(
+        &mut self,
+        event_id: Uuid,
+        to_event_id: Uuid,
+        split_at: OffsetDateTime,
+    ) -> Result<(), EventError> {
+        query!(
+            r#"
+                INSERT INTO event_pauses (event_id, starts_at, ends_at)
+                SELECT $2, $3, ends_at FROM event_pauses
+                WHERE event_id = $1 AND starts_at < $3 AND ends_at > $3
+            "#,
+            event_id,
+            to_event_id,
+            split_at,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        query!(
+            r#"
+                UPDATE event_pauses SET ends_at = $2
+                WHERE event_id = $1 AND starts_at < $2 AND ends_at > $2
+            "#,
+            event_id,
+            split_at,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        query!(
+            r#"
+                UPDATE event_pauses SET event_id = $2
+                WHERE event_id = $1 AND starts_at >= $3
+            "#,
+            event_id,
+            to_event_id,
+            split_at,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!("Split pauses of event {event_id} at {split_at}");
+        Ok(())
+    }
+
+    /// Moves the override to another event, over the occurrences replacing the ones it covered.
+    async fn move_override(
+        &mut self,
+        override_id: Uuid,
+        to_event_id: Uuid,
+        range: Option<TimeRange>,
+    ) -> Result<(), EventError> {
+        query!(
+            r#"
+                UPDATE event_overrides
+                SET event_id = $2,
+                    override_starts_at = COALESCE($3, override_starts_at),
+                    override_ends_at = COALESCE($4, override_ends_at)
+                WHERE id = $1
+            "#,
+            override_id,
+            to_event_id,
+            range.map(|range| range.start),
+            range.map(|range| range.end),
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!("Moved override {override_id} to event {to_event_id}");
+        Ok(())
+    }
+
+    pub async fn copy_user_events(
+        &mut self,
+        from_event_id: Uuid,
+        to_event_id: Uuid,
+    ) -> Result<(), EventError> {
+        query!(
+            r#"
+                INSERT INTO user_events (user_id, event_id, can_edit, is_owner)
+                SELECT user_id, $2, can_edit, is_owner
+                FROM user_events
+                WHERE event_id = $1
+            "#,
+            from_event_id,
+            to_event_id,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!("Copied participants of event {from_event_id} to event {to_event_id}");
+        Ok(())
+    }
+
+    pub async fn create_user_event(&mut self, user_event: UserEvent) -> Result<(), EventError> {
+        query!(
+            r#"
+                INSERT INTO user_events (user_id, event_id, can_edit)
+                VALUES
+                ($1, $2, $3)
+            "#,
+            user_event.user_id.0,
+            user_event.event_id.0,
+            user_event.can_edit,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!(
+            "Created user event with user_id {} and event_id {}",
+            user_event.user_id,
+            user_event.event_id
+        );
+        Ok(())
+    }
+
+    pub async fn get_event(&mut self, event_id: Uuid) -> Result<Option<Event>, EventError> {
+        self.find_event(event_id, false).await
+    }
+
+    /// Gets the event like [`get_event`](Self::get_event), soft deleted events are found by their owner.
+    pub async fn get_event_including_deleted(
+        &mut self,
+        event_id: Uuid,
+    ) -> Result<Option<Event>, EventError> {
+        self.find_event(event_id, true).await
+    }
+
+    async fn find_event(
+        &mut self,
+        event_id: Uuid,
+        include_deleted: bool,
+    ) -> Result<Option<Event>, EventError> {
+        let event = query!(
+            r#"
+                SELECT id, owner_id, name, description, starts_at, COALESCE(until, ends_at) AS entries_end, deleted_at, recurrence AS "recurrence: Option<sqlx::types::Json<RecurrenceRuleKind>>", until, count, interval AS "interval: Option<i32>"
+                FROM events
+                LEFT JOIN recurrence_rules ON recurrence_rules.event_id = id
+                WHERE id = $1 AND (deleted_at IS NULL OR ($2 AND owner_id = $3))
+            "#,
+            event_id,
+            include_deleted,
+            self.payload.user_id,
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?;
+
+        if let Some(event) = event {
+            let payload = EventPayload::new(event.name, event.description);
+
+            let rec_rule = RecurrenceRule::from_db_data(
+                event.recurrence.map(|kind| kind.0),
+                event.until,
+                event.count,
+                event.interval,
+            );
+
+            if event.owner_id == self.payload.user_id {
+                trace!("Got owned event {}", event.id);
+
+                return Ok(Some(Event {
+                    deleted_at: event.deleted_at,
+                    ..Event::new(
+                        EventPrivileges::Owned,
+                        payload,
+                        rec_rule,
+                        event.starts_at,
+                        event.entries_end,
+                    )
+                }));
+            }
+
+            let shared = query!(
+                r#"
+                        SELECT * from user_events
+                        WHERE user_id = $1 AND event_id = $2
+                    "#,
+                self.payload.user_id,
+                event_id,
+            )
+            .fetch_optional(&mut *self.conn)
+            .await?;
+
+            if let Some(shared) = shared {
+                trace!("Got shared event {}", event.id);
+
+                return Ok(Some(Event::new(
+                    EventPrivileges::of_participant(shared.can_edit, shared.is_owner),
+                    payload,
+                    rec_rule,
+                    event.starts_at,
+                    event.entries_end,
+                )));
+            }
+        }
+        trace!("There is no event with id {event_id}");
+        Ok(None)
+    }
+
+    // FIXME
+    pub async fn get_owned_event(&mut self, event_id: Uuid) -> Result<QOwnedEvent, EventError> {
+        let event = query!(
+            r#"
+                SELECT id, name, description, starts_at, ends_at, deleted_at, recurrence AS "recurrence: Option<sqlx::types::Json<RecurrenceRuleKind>>", until, count, interval AS "interval: Option<i32>"
+                FROM events
+                LEFT JOIN recurrence_rules ON recurrence_rules.event_id = id
+                WHERE owner_id = $1 AND id = $2
+            "#,
+            self.payload.user_id,
+            event_id
+        )
+            .fetch_one(&mut *self.conn)
+            .await?;
+
+        trace!("Got owned event {event_id}");
+
+        let res = QOwnedEvent {
+            id: event.id,
+            name: event.name,
+            description: event.description,
+            starts_at: event.starts_at,
+            ends_at: event.ends_at,
+            deleted_at: event.deleted_at,
+            recurrence_rule: RecurrenceRule::from_db_data(
+                event.recurrence.map(|kind| kind.0),
+                event.until,
+                event.count,
+                event.interval,
+            ),
+        };
+        Ok(res)
+    }
+
+    // FIXME
+    /// Gets events of the user overlapping the search range, soft deleted ones only with `include_deleted`.
+    ///
+    /// Only events of `event_ids` are fetched when given.
+    pub async fn get_owned_events(
+        &mut self,
+        search_range: TimeRange,
+        include_deleted: bool,
+        event_ids: Option<&[Uuid]>,
+        names_only: bool,
+    ) -> Result<Vec<QEvent>, EventError> {
+        let events = query!(
+            r#"
+                SELECT id, name, CASE WHEN $6 THEN NULL ELSE description END AS description, starts_at, ends_at, deleted_at, override_strategy, recurrence AS "recurrence: Option<sqlx::types::Json<RecurrenceRuleKind>>", until, count, interval as "interval: Option<i32>"
+                FROM events
+                LEFT JOIN recurrence_rules ON recurrence_rules.event_id = id
+                WHERE owner_id = $1 AND starts_at < $2 AND (until >= $3 OR (recurrence IS NULL AND until IS NULL AND ends_at >= $3) OR (recurrence IS NOT NULL AND until IS NULL)) AND (deleted_at IS NULL OR $4)
+                AND ($5::uuid[] IS NULL OR id = ANY($5))
+                ORDER BY starts_at ASC
+            "#,
+            self.payload.user_id,
+            search_range.end,
+            search_range.start,
+            include_deleted,
+            event_ids,
+            names_only,
+        )
+        .fetch_all(&mut *self.conn)
+        .await?;
+
+        if !events.is_empty() {
+            trace!(
+                "Got {} owned events in search range {search_range}",
+                events.len()
+            );
+        } else {
+            trace!("No owned events in search range {search_range}");
+        }
+
+        let events = events
+            .into_iter()
+            .map(|event| QEvent {
+                id: event.id,
+                name: event.name,
+                description: event.description,
+                time_range: TimeRange::new(event.starts_at, event.ends_at),
+                deleted_at: event.deleted_at,
+                recurrence_rule: RecurrenceRule::from_db_data(
+                    event.recurrence.map(|kind| kind.0),
+                    event.until,
+                    event.count,
+                    event.interval,
+                ),
+                privileges: EventPrivileges::Owned,
+                override_strategy: OverrideStrategy::from_code(event.override_strategy)
+                    .unwrap_or_default(),
+            })
+            .collect();
+
+        Ok(events)
+    }
+
+    // FIXME
+    pub async fn get_shared_events(
+        &mut self,
+        search_range: TimeRange,
+        event_ids: Option<&[Uuid]>,
+        names_only: bool,
+    ) -> Result<Vec<QEvent>, EventError> {
+        let shared_events = query!(
+            r#"
+                SELECT id, name, CASE WHEN $5 THEN NULL ELSE description END AS description, starts_at, ends_at, deleted_at, override_strategy, recurrence AS "recurrence: Option<sqlx::types::Json<RecurrenceRuleKind>>", until, count, interval as "interval: Option<i32>", can_edit, is_owner
+                FROM user_events
+                JOIN events ON user_events.event_id = events.id
+                LEFT JOIN recurrence_rules ON recurrence_rules.event_id = id
+                WHERE user_id = $1 AND starts_at < $2 AND (until >= $3 OR (recurrence IS NULL AND until IS NULL AND ends_at >= $3) OR (recurrence IS NOT NULL AND until IS NULL)) AND deleted_at IS NULL AND owner_id <> $1
+                AND ($4::uuid[] IS NULL OR id = ANY($4))
+                ORDER BY events.starts_at ASC
+            "#,
+            self.payload.user_id,
+            search_range.end,
+            search_range.start,
+            event_ids,
+            names_only,
+        )
+            .fetch_all(&mut *self.conn)
+            .await?;
+
+        if !shared_events.is_empty() {
+            trace!("Got shared events in search range {search_range}");
+        } else {
+            trace!("No shared events in search range {search_range}");
+        }
+
+        let shared_events = shared_events
+            .into_iter()
+            .map(|event| QEvent {
+                id: event.id,
+                name: event.name,
+                description: event.description,
+                time_range: TimeRange::new(event.starts_at, event.ends_at),
+                deleted_at: event.deleted_at,
+                recurrence_rule: RecurrenceRule::from_db_data(
+                    event.recurrence.map(|kind| kind.0),
+                    event.until,
+                    event.count,
+                    event.interval,
+                ),
+                privileges: EventPrivileges::of_participant(event.can_edit, event.is_owner),
+                override_strategy: OverrideStrategy::from_code(event.override_strategy)
+                    .unwrap_or_default(),
+            })
+            .collect();
+
+        Ok(shared_events)
+    }
+
+    /// Gets recurring events the user was added to through overrides in the search range.
+    pub async fn get_guest_events(
+        &mut self,
+        search_range: TimeRange,
+        event_ids: Option<&[Uuid]>,
+        names_only: bool,
+    ) -> Result<Vec<QEvent>, EventError> {
+        let guest_events = query!(
+            r#"
+                SELECT DISTINCT events.id, events.name, CASE WHEN $5 THEN NULL ELSE events.description END AS description, events.starts_at, events.ends_at, events.deleted_at, events.override_strategy, recurrence AS "recurrence: sqlx::types::Json<RecurrenceRuleKind>", until, count, interval
+                FROM event_override_participants
+                JOIN event_overrides ON event_overrides.id = override_id
+                JOIN events ON events.id = event_overrides.event_id
+                JOIN recurrence_rules ON recurrence_rules.event_id = events.id
+                WHERE user_id = $1 AND NOT is_excluded AND override_starts_at < $2 AND override_ends_at > $3
+                AND events.deleted_at IS NULL AND owner_id <> $1
+                AND NOT EXISTS (SELECT 1 FROM user_events WHERE user_events.event_id = events.id AND user_events.user_id = $1)
+                AND ($4::uuid[] IS NULL OR events.id = ANY($4))
+            "#,
+            self.payload.user_id,
+            search_range.end,
+            search_range.start,
+            event_ids,
+            names_only,
+        )
+            .fetch_all(&mut *self.conn)
+            .await?;
+
+        if !guest_events.is_empty() {
+            trace!("Got guest events in search range {search_range}");
+        }
+
+        let guest_events = guest_events
+            .into_iter()
+            .map(|event| QEvent {
+                id: event.id,
+                name: event.name,
+                description: event.description,
+                time_range: TimeRange::new(event.starts_at, event.ends_at),
+                deleted_at: event.deleted_at,
+                recurrence_rule: RecurrenceRule::from_db_data(
+                    Some(event.recurrence.0),
+                    event.until,
+                    event.count,
+                    Some(event.interval),
+                ),
+                privileges: EventPrivileges::Guest,
+                override_strategy: OverrideStrategy::from_code(event.override_strategy)
+                    .unwrap_or_default(),
+            })
+            .collect();
+
+        Ok(guest_events)
+    }
+
+    /// Gets overrides of the events, without their descriptions with `names_only`.
+    pub async fn get_overrides(
+        &mut self,
+        event_ids: Vec<Uuid>,
+        names_only: bool,
+    ) -> Result<Vec<QOverride>, EventError> {
+        let overrides = query!(
+            r#"
+                SELECT event_id, override_starts_at, override_ends_at, created_at, name, CASE WHEN $2 THEN NULL ELSE description END AS description, starts_at, ends_at, deleted_at, status,
+                COALESCE(ARRAY_AGG(user_id) FILTER (WHERE NOT is_excluded), '{}') AS "added_participants!",
+                COALESCE(ARRAY_AGG(user_id) FILTER (WHERE is_excluded), '{}') AS "excluded_participants!"
+                FROM event_overrides
+                LEFT JOIN event_override_participants ON override_id = id
+                WHERE event_id = any($1)
+                GROUP BY id
+                ORDER BY override_starts_at ASC
+            "#,
+            event_ids as _,
+            names_only,
+        )
+            .fetch_all(&mut *self.conn)
+            .await?;
+
+        if !overrides.is_empty() {
+            trace!("Got events' overrides for {overrides:#?}");
+        }
+
+        let mut res = Vec::new();
+        for ovr in overrides.into_iter() {
+            let starts_at = match ovr.starts_at {
+                Some(entry_offset) => Some(to_time_duration(entry_offset)?),
+                None => None,
+            };
+            let ends_at = match ovr.ends_at {
+                Some(entry_offset) => Some(to_time_duration(entry_offset)?),
+                None => None,
+            };
+
+            res.push(QOverride {
+                event_id: ovr.event_id,
+                override_starts_at: ovr.override_starts_at,
+                override_ends_at: ovr.override_ends_at,
+                created_at: ovr.created_at,
+                name: ovr.name,
+                description: ovr.description,
+                starts_at,
+                ends_at,
+                deleted_at: ovr.deleted_at,
+                status: ovr.status.and_then(OverrideStatus::from_code),
+                added_participants: ovr.added_participants,
+                excluded_participants: ovr.excluded_participants,
+            });
+        }
+
+        Ok(res)
+    }
+
+    /// Gets suppression ranges of the events grouped by event.
+    pub async fn get_pauses(
+        &mut self,
+        event_ids: Vec<Uuid>,
+    ) -> Result<HashMap<Uuid, Vec<TimeRange>>, EventError> {
+        let pauses = query!(
+            r#"
+                SELECT event_id, starts_at, ends_at
+                FROM event_pauses
+                WHERE event_id = any($1)
+                ORDER BY starts_at ASC
+            "#,
+            event_ids as _
+        )
+        .fetch_all(&mut *self.conn)
+        .await?;
+
+        let mut res: HashMap<Uuid, Vec<TimeRange>> = HashMap::new();
+        for pause in pauses {
+            res.entry(pause.event_id)
+                .or_default()
+                .push(TimeRange::new(pause.starts_at, pause.ends_at));
+        }
+
+        Ok(res)
+    }
+
+    pub async fn pause_event(
+        &mut self,
+        event_id: Uuid,
+        range: TimeRange,
+    ) -> Result<(), EventError> {
+        let event = self.get_event_base(event_id).await?;
+        if event.recurrence_rule.is_none() {
+            return Err(EventError::InvalidData(ValidateContentError::new(
+                "Event is not recurring",
+            )));
+        }
+
+        query!(
+            r#"
+                INSERT INTO event_pauses (event_id, starts_at, ends_at)
+                VALUES ($1, $2, $3)
+            "#,
+            event_id,
+            range.start,
+            range.end
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!("Paused event {event_id} in {range}");
+        Ok(())
+    }
+
+    /// Whether an override of the event lies entirely within the range.
+    pub async fn shadows_override(
+        &mut self,
+        event_id: Uuid,
+        range: TimeRange,
+    ) -> Result<bool, EventError> {
+        let shadows = query!(
+            r#"
+                SELECT EXISTS (
+                    SELECT 1 FROM event_overrides
+                    WHERE event_id = $1 AND override_starts_at >= $2 AND override_ends_at <= $3
+                ) AS "shadows!"
+            "#,
+            event_id,
+            range.start,
+            range.end,
+        )
+        .fetch_one(&mut *self.conn)
+        .await?
+        .shadows;
+
+        Ok(shadows)
+    }
+
+    /// Whether an entry locked by the owner lies entirely within the range, so that an override would cover it.
+    pub async fn covers_locked_occurrence(
+        &mut self,
+        event_id: Uuid,
+        range: TimeRange,
+    ) -> Result<bool, EventError> {
+        let covers = query!(
+            r#"
+                SELECT EXISTS (
+                    SELECT 1 FROM locked_occurrences
+                    WHERE event_id = $1 AND starts_at >= $2 AND ends_at <= $3
+                ) AS "covers!"
+            "#,
+            event_id,
+            range.start,
+            range.end,
+        )
+        .fetch_one(&mut *self.conn)
+        .await?
+        .covers;
+
+        Ok(covers)
+    }
+
+    pub async fn lock_occurrence(
+        &mut self,
+        event_id: Uuid,
+        occurrence_id: Uuid,
+        entry: TimeRange,
+    ) -> Result<(), EventError> {
+        query!(
+            r#"
+                INSERT INTO locked_occurrences (event_id, occurrence_id, starts_at, ends_at)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT DO NOTHING
+            "#,
+            event_id,
+            occurrence_id,
+            entry.start,
+            entry.end,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!("Locked occurrence {occurrence_id} of event {event_id}");
+        Ok(())
+    }
+
+    pub async fn unlock_occurrence(
+        &mut self,
+        event_id: Uuid,
+        occurrence_id: Uuid,
+    ) -> Result<bool, EventError> {
+        let unlocked = query!(
+            r#"
+                DELETE FROM locked_occurrences
+                WHERE event_id = $1 AND occurrence_id = $2
+            "#,
+            event_id,
+            occurrence_id,
+        )
+        .execute(&mut *self.conn)
+        .await?
+        .rows_affected();
+
+        trace!("Unlocked occurrence {occurrence_id} of event {event_id}");
+        Ok(unlocked == 1)
+    }
+
+    pub async fn get_locked_occurrences(
+        &mut self,
+        event_ids: Vec<Uuid>,
+    ) -> Result<HashSet<Uuid>, EventError> {
+        let locked = query!(
+            r#"
+                SELECT occurrence_id
+                FROM locked_occurrences
+                WHERE event_id = any($1)
+            "#,
+            event_ids as _
+        )
+        .fetch_all(&mut *self.conn)
+        .await?
+        .into_iter()
+        .map(|lock| lock.occurrence_id)
+        .collect();
+
+        Ok(locked)
+    }
+
+    pub async fn set_override_strategy(
+        &mut self,
+        event_id: Uuid,
+        strategy: OverrideStrategy,
+    ) -> Result<(), EventError> {
+        query!(
+            r#"
+                UPDATE events SET override_strategy = $1
+                WHERE id = $2
+            "#,
+            strategy.code(),
+            event_id,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!("Set override strategy of event {event_id} to {strategy:?}");
+        Ok(())
+    }
+
+    pub async fn create_override(
+        &mut self,
+        event_id: Uuid,
+        ovr: OverrideEvent,
+    ) -> Result<(), EventError> {
+        let override_id = query!(
+            r#"
+                INSERT INTO event_overrides (event_id, override_starts_at, override_ends_at, name, description, starts_at, ends_at, status)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                RETURNING id
+            "#,
+            event_id,
+            ovr.override_starts_at,
+            ovr.override_ends_at,
+            ovr.data.name,
+            ovr.data.description,
+            ovr.data.starts_at as _,
+            ovr.data.ends_at as _,
+            ovr.data.status.map(OverrideStatus::code),
+        ).fetch_one(&mut *self.conn).await?.id;
+
+        trace!("Created event override for event {event_id}");
+
+        let excluded = vec![false; ovr.data.added_participants.len()]
+            .into_iter()
+            .chain(vec![true; ovr.data.excluded_participants.len()])
+            .collect::<Vec<bool>>();
+        let participants = ovr
+            .data
+            .added_participants
+            .into_iter()
+            .chain(ovr.data.excluded_participants)
+            .collect::<Vec<Uuid>>();
+        if !participants.is_empty() {
+            query!(
+                r#"
+                    INSERT INTO event_override_participants (override_id, user_id, is_excluded)
+                    SELECT $1, * FROM UNNEST($2::uuid[], $3::bool[])
+                "#,
+                override_id,
+                &participants,
+                &excluded,
+            )
+            .execute(&mut *self.conn)
+            .await?;
+
+            trace!("Changed participants of event override {override_id}");
+        }
+
+        Ok(())
+    }
+    pub async fn update_event(
+        &mut self,
+        event_id: Uuid,
+        event: OptionalEventData,
+    ) -> Result<(), EventError> {
+        // only empty string will delete description because it is an optional parameter
+        query!(
+            r#"
+                UPDATE events
+                SET
+                name = COALESCE($1, name),
+                description = COALESCE($2, description),
+                starts_at = COALESCE($3, starts_at),
+                ends_at = COALESCE($4, ends_at)
+                WHERE id = $5
+            "#,
+            event.name,
+            event.description,
+            event.starts_at,
+            event.ends_at,
+            event_id,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!("Updated event {event_id}");
+
+        Ok(())
+    }
+
+    pub async fn temp_delete(
+        &mut self,
+        event_id: Uuid,
+        now: OffsetDateTime,
+    ) -> Result<(), EventError> {
+        query!(
+            r#"
+                UPDATE events
+                SET
+                deleted_at = $1
+                WHERE id = $2
+            "#,
+            now,
+            event_id
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!("Temporarily deleted event {event_id}");
+
+        Ok(())
+    }
+
+    pub async fn perm_delete(&mut self, event_id: Uuid) -> Result<(), EventError> {
+        query!(
+            r#"
+                DELETE FROM events
+                WHERE id = $1
+            "#,
+            event_id
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!("Permanently deleted event {event_id}");
+
+        Ok(())
+    }
+
+    /// Gets how the user takes part in the event.
+    ///
+    /// Strangers get `NotFound`, so that only participants and guests learn that the event exists
+    /// and get `MismatchedPrivileges` for actions they aren't allowed to take.
+    pub async fn get_privileges(&mut self, event_id: Uuid) -> Result<EventPrivileges, EventError> {
+        let access = query!(
+            r#"
+                SELECT
+                    events.owner_id = $2 AS "is_owner!",
+                    user_events.is_owner AS "is_co_owner?",
+                    user_events.can_edit AS "can_edit?",
+                    EXISTS (
+                        SELECT 1 FROM event_override_participants
+                        JOIN event_overrides ON event_overrides.id = override_id
+                        WHERE event_overrides.event_id = events.id AND user_id = $2 AND NOT is_excluded
+                    ) AS "is_guest!"
+                FROM events
+                LEFT JOIN user_events ON user_events.event_id = events.id AND user_events.user_id = $2
+                WHERE events.id = $1
+            "#,
+            event_id,
+            self.payload.user_id,
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?
+        .ok_or(EventError::NotFound)?;
+
+        let privileges = match (access.is_owner, access.is_co_owner, access.can_edit) {
+            (true, _, _) => EventPrivileges::Owned,
+            (false, Some(is_owner), Some(can_edit)) => {
+                EventPrivileges::of_participant(can_edit, is_owner)
+            }
+            _ if access.is_guest => EventPrivileges::Guest,
+            _ => {
+                trace!(
+                    "User {} does not take part in the event {event_id}",
+                    self.payload.user_id
+                );
+                return Err(EventError::NotFound);
+            }
+        };
+
+        trace!(
+            "User {} has privileges {privileges:?} to the event {event_id}",
+            self.payload.user_id
+        );
+        Ok(privileges)
+    }
+
+    /// Checks whether the user owns or co-owns the event, see [`Self::get_privileges`].
+    pub async fn is_owner(&mut self, event_id: Uuid) -> Result<bool, EventError> {
+        Ok(matches!(
+            self.get_privileges(event_id).await?,
+            EventPrivileges::Owned
+        ))
+    }
+
+    /// Checks whether the user is the owner of the event, who can hand it over to someone else.
+    pub async fn is_primary_owner(&mut self, event_id: Uuid) -> Result<bool, EventError> {
+        self.get_privileges(event_id).await?;
+        let owner_id = query!(
+            r#"
+                SELECT owner_id FROM events WHERE id = $1
+            "#,
+            event_id
+        )
+        .fetch_one(&mut *self.conn)
+        .await?
+        .owner_id;
+
+        Ok(owner_id == self.payload.user_id)
+    }
+
+    /// Checks whether the user owns or edits the event, see [`Self::get_privileges`].
+    pub async fn can_edit(&mut self, event_id: Uuid) -> Result<bool, EventError> {
+        Ok(self.get_privileges(event_id).await?.can_edit())
+    }
+
+    pub async fn update_edit_privileges(
+        &mut self,
+        target_user_id: UserId,
+        event_id: EventId,
+        can_edit: bool,
+    ) -> Result<(), EventError> {
+        query!(
+            r#"
+                UPDATE user_events
+                SET can_edit = $1
+                WHERE user_id = $2
+                AND event_id = $3
+                AND NOT is_owner
+            "#,
+            can_edit,
+            target_user_id.0,
+            event_id.0,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!("Updated editing privileges for user {target_user_id} and event {event_id} to {can_edit}");
+
+        Ok(())
+    }
+
+    /// Applies the changes to participants of the event, returning how many were changed.
+    pub async fn update_many_edit_privileges(
+        &mut self,
+        event_id: Uuid,
+        changes: &[UpdateEditPrivilege],
+    ) -> Result<u64, EventError> {
+        let user_ids: Vec<Uuid> = changes.iter().map(|change| change.user_id).collect();
+        let can_edit: Vec<bool> = changes.iter().map(|change| change.can_edit).collect();
+        let updated = query!(
+            r#"
+                UPDATE user_events
+                SET can_edit = changes.can_edit
+                FROM UNNEST($1::uuid[], $2::bool[]) AS changes(user_id, can_edit)
+                WHERE user_events.user_id = changes.user_id
+                AND event_id = $3
+                AND NOT is_owner
+            "#,
+            &user_ids,
+            &can_edit,
+            event_id,
+        )
+        .execute(&mut *self.conn)
+        .await?
+        .rows_affected();
+
+        trace!("Updated editing privileges of {updated} participants of event {event_id}");
+
+        Ok(updated)
+    }
+
+    /// Makes the participant a co-owner of the event or takes it back, returning whether they participate.
+    ///
+    /// Co-owners keep their editing privileges after they are taken back.
+    pub async fn update_co_owner(
+        &mut self,
+        target_user_id: UserId,
+        event_id: EventId,
+        is_owner: bool,
+    ) -> Result<bool, EventError> {
+        let updated = query!(
+            r#"
+                UPDATE user_events
+                SET is_owner = $1, can_edit = can_edit OR $1
+                WHERE user_id = $2
+                AND event_id = $3
+            "#,
+            is_owner,
+            target_user_id.0,
+            event_id.0,
+        )
+        .execute(&mut *self.conn)
+        .await?
+        .rows_affected();
+
+        trace!("Updated co-ownership for user {target_user_id} and event {event_id} to {is_owner}");
+
+        Ok(updated == 1)
+    }
+
+    pub async fn update_event_owner(
+        &mut self,
+        owner_id: UserId,
+        event_id: EventId,
+    ) -> Result<(), EventError> {
+        query!(
+            r#"
+                UPDATE events
+                SET owner_id = $1
+                WHERE id = $2
+            "#,
+            owner_id.0,
+            event_id.0,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!("Set owner of the event {event_id} to {owner_id}");
+
+        Ok(())
+    }
+
+    pub async fn delete_user_event(
+        &mut self,
+        user_id: UserId,
+        event_id: EventId,
+    ) -> Result<(), EventError> {
+        query!(
+            r#"
+                DELETE FROM user_events
+                WHERE user_id = $1
+                AND event_id = $2
+            "#,
+            user_id.0,
+            event_id.0
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!("Removed user {user_id} from event {event_id}");
+
+        Ok(())
+    }
+}
+
+async fn get_owned(
+    search_range: TimeRange,
+    week_start: Weekday,
+    page: &EntriesPage,
+    query: &mut PgQuery<'_, EventQuery>,
+) -> Result<Events, EventError> {
+    let owned_events = query
+        .get_owned_events(
+            search_range,
+            page.include_deleted,
+            page.event_ids.as_deref(),
+            page.names_only,
+        )
+        .await?;
+    if page.events_only {
+        return Ok(map_events_only(owned_events));
+    }
+    let event_ids: Vec<Uuid> = owned_events.iter().map(|ev| ev.id).collect();
+    let owned_events_overrides = query
+        .get_overrides(event_ids.clone(), page.names_only)
+        .await?;
+    let owned_events_pauses = query.get_pauses(event_ids.clone()).await?;
+    let locked = query.get_locked_occurrences(event_ids).await?;
+
+    let mut events = expand_events(
+        owned_events_overrides,
+        owned_events_pauses,
+        owned_events,
+        search_range,
+        week_start,
+        query.payload.user_id,
+        page.clone(),
+    )
+    .await?;
+    events.mark_locked(&locked);
+    Ok(events)
+}
+
+async fn get_shared(
+    search_range: TimeRange,
+    week_start: Weekday,
+    page: &EntriesPage,
+    query: &mut PgQuery<'_, EventQuery>,
+) -> Result<Events, EventError> {
+    let event_ids = page.event_ids.as_deref();
+    let mut shared_events = query
+        .get_shared_events(search_range, event_ids, page.names_only)
+        .await?;
+    if page.events_only {
+        return Ok(map_events_only(shared_events));
+    }
+    shared_events.extend(
+        query
+            .get_guest_events(search_range, event_ids, page.names_only)
+            .await?,
+    );
+    let event_ids: Vec<Uuid> = shared_events.iter().map(|ev| ev.id).collect();
+    let shared_events_overrides = query
+        .get_overrides(event_ids.clone(), page.names_only)
+        .await?;
+    let shared_events_pauses = query.get_pauses(event_ids.clone()).await?;
+    let locked = query.get_locked_occurrences(event_ids).await?;
+
+    let mut events = expand_events(
+        shared_events_overrides,
+        shared_events_pauses,
+        shared_events,
+        search_range,
+        week_start,
+        query.payload.user_id,
+        page.clone(),
+    )
+    .await?;
+    events.mark_locked(&locked);
+    Ok(events)
+}
+
+/// Start of the last of `limit` entries from `start` of a rule repeating within a day.
+fn sub_daily_entries_end(
+    rule: &RecurrenceRule,
+    start: OffsetDateTime,
+    limit: RepetitionLimit,
+) -> Option<OffsetDateTime> {
+    let step = match rule.kind {
+        RecurrenceRuleKind::Hourly => Duration::HOUR,
+        RecurrenceRuleKind::Minutely => Duration::MINUTE,
+        _ => return None,
+    };
+    let steps = i64::from(rule.interval) * i64::from(limit.0.saturating_sub(1));
+    start.checked_add(Duration::seconds(steps.checked_mul(step.whole_seconds())?))
+}
+
+/// Expands entries like [`map_events`] on a blocking thread, so that long expansions don't stall the async workers.
+async fn expand_events(
+    overrides: Vec<QOverride>,
+    pauses: HashMap<Uuid, Vec<TimeRange>>,
+    events: Vec<QEvent>,
+    search_range: TimeRange,
+    week_start: Weekday,
+    user_id: Uuid,
+    page: EntriesPage,
+) -> Result<Events, EventError> {
+    tokio::task::spawn_blocking(move || {
+        map_events(
+            overrides,
+            pauses,
+            events,
+            search_range,
+            week_start,
+            user_id,
+            page,
+        )
+    })
+    .await
+    .context("Failed to expand entries")?
+}
+
+/// Expands entries of the events as seen by `user_id`.
+///
+/// Entries are hidden from participants excluded by their override,
+/// guests only see the entries they were added to.
+/// Entries starting within a pause of their event are skipped.
+/// Entries are kept when they overlap the search range after their time override, wherever they were moved from.
+/// Overrides covering the same entry are combined with the strategy of their event.
+/// With `effective` the overrides are resolved against their event payloads.
+/// Entries are normalized, see [`Events::normalize_entries`].
+pub fn map_events(
+    overrides: Vec<QOverride>,
+    pauses: HashMap<Uuid, Vec<TimeRange>>,
+    events: Vec<QEvent>,
+    search_range: TimeRange,
+    week_start: Weekday,
+    user_id: Uuid,
+    page: EntriesPage,
+) -> Result<Events, EventError> {
+    let ovrs = group_overrides(overrides);
+    // Events are expanded in parallel, in order of the input
+    let expanded = events
+        .into_par_iter()
+        .with_min_len(MIN_EVENTS_PER_EXPANSION_TASK)
+        .map(|event| {
+            let entries = expand_entries(
+                &event,
+                &ovrs,
+                &pauses,
+                search_range,
+                week_start,
+                user_id,
+                &page,
+            )?;
+            Ok((event, entries))
+        })
+        .collect::<Result<Vec<(QEvent, Option<Vec<Entry>>)>, EventError>>()?;
+
+    let mut events: HashMap<Uuid, Event> = HashMap::with_capacity(expanded.len());
+    let mut entries: Vec<Entry> = vec![];
+    for (event, new_entries) in expanded {
+        let Some(new_entries) = new_entries else {
+            events.insert(event.id, Event::from(event));
+            continue;
+        };
+        // Guests don't see events without any entry they were added to
+        let is_guest = matches!(event.privileges, EventPrivileges::Guest);
+        if !(is_guest && new_entries.is_empty()) {
+            events.insert(event.id, Event::from(event));
+        }
+        entries.extend(new_entries);
+    }
+
+    let mut events = Events::new(events, entries);
+    events.normalize_entries();
+    Ok(events)
+}
+
+/// Expands entries of a single event in the search range, none when the event doesn't recur.
+fn expand_entries(
+    event: &QEvent,
+    ovrs: &HashMap<Uuid, Vec<(TimeRange, Override)>>,
+    pauses: &HashMap<Uuid, Vec<TimeRange>>,
+    search_range: TimeRange,
+    week_start: Weekday,
+    user_id: Uuid,
+    page: &EntriesPage,
+) -> Result<Option<Vec<Entry>>, EventError> {
+    let Some(rule) = &event.recurrence_rule else {
+        return Ok(None);
+    };
+    let expansion_started = Instant::now();
+    let aligned_rule = WeekAlignedRule::new(rule, week_start);
+    let event_ovrs = EventOverrides {
+        overrides: ovrs.get(&event.id).map_or(&[], Vec::as_slice),
+        strategy: event.override_strategy,
+        all_overrides: page.all_overrides,
+    };
+    let expansion_range = event_ovrs.expansion_range(search_range, event.time_range.duration());
+    let entry_ranges = aligned_rule.get_event_range(expansion_range, event.time_range)?;
+
+    let mut new_entries: Vec<Entry> = get_entries(event.id, entry_ranges, &event_ovrs);
+    new_entries.retain(|entry| is_within_search(entry, search_range));
+
+    if let Some(event_pauses) = pauses.get(&event.id) {
+        new_entries.retain(|entry| !is_paused(entry.time_range, event_pauses));
+    }
+    let is_guest = matches!(event.privileges, EventPrivileges::Guest);
+    new_entries.retain(|entry| match &entry.recurrence_override {
+        Some(ovr) => ovr.is_attended_by(user_id, is_guest),
+        None => !is_guest,
+    });
+    let can_edit = event.privileges.can_edit();
+    new_entries
+        .iter_mut()
+        .for_each(|entry| entry.can_edit = can_edit);
+    if page.effective {
+        let payload = EventPayload::new(event.name.clone(), event.description.clone());
+        new_entries
+            .iter_mut()
+            .for_each(|entry| entry.resolve(&payload));
+    }
+
+    histogram!(RECURRENCE_EXPANSION_SECONDS, "kind" => rule.kind.name())
+        .record(expansion_started.elapsed().as_secs_f64());
+    Ok(Some(new_entries))
+}
+
+/// Maps events without expanding their entries.
+pub fn map_events_only(events: Vec<QEvent>) -> Events {
+    let events = events
+        .into_iter()
+        .map(|event| (event.id, Event::from(event)))
+        .collect();
+
+    Events::new(events, vec![])
+}
+
+/// Entries starting within a pause are not generated.
+fn is_paused(entry_range: TimeRange, pauses: &[TimeRange]) -> bool {
+    pauses
+        .iter()
+        .any(|pause| pause.start <= entry_range.start && entry_range.start < pause.end)
+}
+
+fn group_overrides(overrides: Vec<QOverride>) -> HashMap<Uuid, Vec<(TimeRange, Override)>> {
+    let mut ovrs: HashMap<Uuid, Vec<(TimeRange, Override)>> = HashMap::new();
+    overrides.into_iter().for_each(|ovr| {
+        let range = TimeRange::new(ovr.override_starts_at, ovr.override_ends_at);
+        let event_id = ovr.event_id;
+        let entry_override = Override::from(ovr);
+
+        ovrs.entry(event_id)
+            .and_modify(|ranges| ranges.push((range, entry_override.clone())))
+            .or_insert(vec![(range, entry_override)]);
+    });
+    if !ovrs.is_empty() {
+        trace!("Grouped overrides {ovrs:#?}");
+    }
+
+    ovrs
+}
+
+/// Overrides of one event and how to apply them to its entries.
+struct EventOverrides<'a> {
+    overrides: &'a [(TimeRange, Override)],
+    strategy: OverrideStrategy,
+    /// Lists every covering override on the entries
+    all_overrides: bool,
+}
+
+impl EventOverrides<'_> {
+    /// Range to expand entries in, so that it catches every entry overlapping the search range.
+    ///
+    /// Entries starting up to their duration before the search range still overlap it,
+    /// time-shifting overrides may move entries from even further into it.
+    fn expansion_range(&self, search_range: TimeRange, duration: Duration) -> TimeRange {
+        let later_end = self
+            .overrides
+            .iter()
+            .filter_map(|ovr| ovr.1.ends_at)
+            .max()
+            .unwrap_or(Duration::ZERO)
+            .max(Duration::ZERO);
+        let earlier_start = self
+            .overrides
+            .iter()
+            .filter_map(|ovr| ovr.1.starts_at)
+            .min()
+            .unwrap_or(Duration::ZERO)
+            .min(Duration::ZERO);
+
+        TimeRange::new(
+            search_range
+                .start
+                .checked_sub(duration + later_end)
+                .unwrap_or(search_range.start),
+            search_range
+                .end
+                .checked_sub(earlier_start)
+                .unwrap_or(search_range.end),
+        )
+    }
+
+    /// Overrides covering the entry, oldest first.
+    fn covering(&self, entry_range: TimeRange) -> Vec<Override> {
+        let mut covering: Vec<Override> = self
+            .overrides
+            .iter()
+            .filter(|ovr| entry_range.is_contained(&ovr.0))
+            .map(|ovr| ovr.1.clone())
+            .collect();
+        covering.sort_by_key(|ovr| ovr.created_at);
+        covering
+    }
+}
+
+/// Combines the overrides covering an entry, oldest first, into the one applied to it.
+fn resolve_overrides(covering: &[Override], strategy: OverrideStrategy) -> Option<Override> {
+    match strategy {
+        OverrideStrategy::Latest | OverrideStrategy::Reject => covering.last().cloned(),
+        OverrideStrategy::Merge => covering
+            .iter()
+            .cloned()
+            .reduce(|merged, newer| merged.merged_with(newer)),
+    }
+}
+
+fn get_one_entry(event_id: Uuid, entry_range: TimeRange, ovrs: &EventOverrides) -> Entry {
+    let covering = ovrs.covering(entry_range);
+    let mut entry = Entry::new(
+        event_id,
+        entry_range,
+        resolve_overrides(&covering, ovrs.strategy),
+    );
+    if ovrs.all_overrides {
+        entry.overrides = covering;
+    }
+    entry
+}
+
+fn get_entries(event_id: Uuid, entry_ranges: Vec<TimeRange>, ovrs: &EventOverrides) -> Vec<Entry> {
+    trace!(
+        "Got {} entries with {} overrides for event {event_id}",
+        entry_ranges.len(),
+        ovrs.overrides.len()
+    );
+    entry_ranges
+        .into_iter()
+        .map(|entry_range| get_one_entry(event_id, entry_range, ovrs))
+        .collect::<Vec<Entry>>()
+}
+
+fn to_time_duration(val: PgInterval) -> Result<Duration, EventError> {
+    if val.days != 0 || val.months != 0 {
+        Err(EventError::Unexpected(anyhow!(
+            "Invalid interval data format in database type"
+        )))
+    } else {
+        Ok(Duration::microseconds(val.microseconds))
+    }
+}
+
+/// Whether the entry overlaps the search range where it finally happens, after its time override.
+fn is_within_search(entry: &Entry, search_range: TimeRange) -> bool {
+    entry.effective_range().is_overlapping(&search_range)
+}
+
+#[cfg(test)]
+mod map_events_tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn event(time_range: TimeRange, kind: RecurrenceRuleKind) -> QEvent {
+        QEvent {
+            id: Uuid::new_v4(),
+            name: "Event".to_string(),
+            description: None,
+            time_range,
+            deleted_at: None,
+            recurrence_rule: Some(RecurrenceRule {
+                span: None,
+                interval: 1,
+                kind,
+            }),
+            privileges: EventPrivileges::Owned,
+            override_strategy: OverrideStrategy::Latest,
+        }
+    }
+
+    fn entries(overrides: Vec<QOverride>, event: QEvent, search_range: TimeRange) -> Vec<Entry> {
+        map_events(
+            overrides,
+            HashMap::new(),
+            vec![event],
+            search_range,
+            Weekday::Monday,
+            Uuid::new_v4(),
+            EntriesPage::default(),
+        )
+        .unwrap()
+        .entries
+    }
+
+    #[test]
+    fn multi_day_entries_starting_long_before_search_are_included() {
+        let event = event(
+            TimeRange::new(
+                datetime!(2022-12-20 10:00 UTC),
+                datetime!(2023-01-10 10:00 UTC),
+            ),
+            RecurrenceRuleKind::Yearly { is_by_day: false },
+        );
+        let search_range = TimeRange::new(
+            datetime!(2024-01-01 00:00 UTC),
+            datetime!(2024-01-31 00:00 UTC),
+        );
+
+        let entries = entries(vec![], event, search_range);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries[0].time_range,
+            TimeRange::new(
+                datetime!(2023-12-19 10:00 UTC),
+                datetime!(2024-01-09 10:00 UTC),
+            )
+        );
+    }
+
+    #[test]
+    fn multi_day_entries_without_overrides_are_included() {
+        let event = event(
+            TimeRange::new(
+                datetime!(2023-03-03 18:00 UTC),
+                datetime!(2023-03-06 08:00 UTC),
+            ),
+            RecurrenceRuleKind::Weekly { week_map: 4 },
+        );
+        let search_range = TimeRange::new(
+            datetime!(2023-03-12 00:00 UTC),
+            datetime!(2023-03-13 00:00 UTC),
+        );
+
+        let entries = entries(vec![], event, search_range);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries[0].time_range,
+            TimeRange::new(
+                datetime!(2023-03-10 18:00 UTC),
+                datetime!(2023-03-13 08:00 UTC),
+            )
+        );
+    }
+
+    #[test]
+    fn parallel_expansion_orders_entries_by_start() {
+        let events: Vec<QEvent> = (0..50)
+            .map(|i| {
+                event(
+                    TimeRange::new_relative(
+                        datetime!(2023-03-01 10:00 UTC) + Duration::minutes(i),
+                        Duration::minutes(30),
+                    ),
+                    RecurrenceRuleKind::Daily,
+                )
+            })
+            .collect();
+        let ids: Vec<Uuid> = events.iter().map(|event| event.id).collect();
+        let search_range = TimeRange::new(
+            datetime!(2023-03-10 00:00 UTC),
+            datetime!(2023-03-12 00:00 UTC),
+        );
+
+        let mapped = map_events(
+            vec![],
+            HashMap::new(),
+            events,
+            search_range,
+            Weekday::Monday,
+            Uuid::new_v4(),
+            EntriesPage::default(),
+        )
+        .unwrap();
+        assert_eq!(mapped.events.len(), 50);
+        // Events start a minute apart, so their entries of each day follow the order of events
+        let entry_ids: Vec<Uuid> = mapped.entries.iter().map(|entry| entry.event_id).collect();
+        let expected: Vec<Uuid> = ids.iter().chain(ids.iter()).copied().collect();
+        assert_eq!(entry_ids, expected);
+    }
+
+    #[test]
+    fn entries_moved_from_far_outside_search_are_included() {
+        let event = event(
+            TimeRange::new(
+                datetime!(2023-03-01 10:00 UTC),
+                datetime!(2023-03-01 11:00 UTC),
+            ),
+            RecurrenceRuleKind::Daily,
+        );
+        let moved = QOverride {
+            event_id: event.id,
+            override_starts_at: datetime!(2023-03-05 10:00 UTC),
+            override_ends_at: datetime!(2023-03-05 11:00 UTC),
+            created_at: datetime!(2023-03-01 00:00 UTC),
+            name: None,
+            description: None,
+            starts_at: Some(Duration::days(3)),
+            ends_at: Some(Duration::days(3)),
+            deleted_at: None,
+            status: None,
+            added_participants: vec![],
+            excluded_participants: vec![],
+        };
+        let search_range = TimeRange::new(
+            datetime!(2023-03-08 00:00 UTC),
+            datetime!(2023-03-08 12:00 UTC),
+        );
+
+        let entries = entries(vec![moved], event, search_range);
+        let ranges: Vec<TimeRange> = entries.iter().map(|entry| entry.time_range).collect();
+        assert_eq!(
+            ranges,
+            vec![
+                TimeRange::new(
+                    datetime!(2023-03-05 10:00 UTC),
+                    datetime!(2023-03-05 11:00 UTC),
+                ),
+                TimeRange::new(
+                    datetime!(2023-03-08 10:00 UTC),
+                    datetime!(2023-03-08 11:00 UTC),
+                ),
+            ]
+        );
+        assert_eq!(
+            entries[0].range_with_time_override(),
+            Some(TimeRange::new(
+                datetime!(2023-03-08 10:00 UTC),
+                datetime!(2023-03-08 11:00 UTC),
+            ))
+        );
+    }
+
+    fn shifted(event_id: Uuid, occurrence: TimeRange, by: Duration) -> QOverride {
+        QOverride {
+            event_id,
+            override_starts_at: occurrence.start,
+            override_ends_at: occurrence.end,
+            created_at: datetime!(2023-03-01 00:00 UTC),
+            name: None,
+            description: None,
+            starts_at: Some(by),
+            ends_at: Some(by),
+            deleted_at: None,
+            status: None,
+            added_participants: vec![],
+            excluded_participants: vec![],
+        }
+    }
+
+    #[test]
+    fn entries_moved_out_of_search_are_excluded() {
+        let event = event(
+            TimeRange::new(
+                datetime!(2023-03-01 10:00 UTC),
+                datetime!(2023-03-01 11:00 UTC),
+            ),
+            RecurrenceRuleKind::Daily,
+        );
+        let occurrence = TimeRange::new(
+            datetime!(2023-03-08 10:00 UTC),
+            datetime!(2023-03-08 11:00 UTC),
+        );
+        // The entry still overlaps the search range with its original time only
+        let later = shifted(event.id, occurrence, Duration::hours(2));
+        let search_range = TimeRange::new(
+            datetime!(2023-03-08 10:30 UTC),
+            datetime!(2023-03-08 12:00 UTC),
+        );
+
+        let entries = entries(vec![later], event, search_range);
+        assert!(entries.is_empty());
+    }
+
+    #[test]
+    fn entries_moved_into_search_are_included() {
+        let event = event(
+            TimeRange::new(
+                datetime!(2023-03-01 10:00 UTC),
+                datetime!(2023-03-01 11:00 UTC),
+            ),
+            RecurrenceRuleKind::Daily,
+        );
+        let occurrence = TimeRange::new(
+            datetime!(2023-03-08 10:00 UTC),
+            datetime!(2023-03-08 11:00 UTC),
+        );
+        let later = shifted(event.id, occurrence, Duration::hours(2));
+        let search_range = TimeRange::new(
+            datetime!(2023-03-08 12:30 UTC),
+            datetime!(2023-03-08 14:00 UTC),
+        );
+
+        let entries = entries(vec![later], event, search_range);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].time_range, occurrence);
+        assert_eq!(
+            entries[0].effective_range(),
+            TimeRange::new(
+                datetime!(2023-03-08 12:00 UTC),
+                datetime!(2023-03-08 13:00 UTC),
+            )
+        );
+    }
+}
@@ -0,0 +1,341 @@
+use hmac::{Hmac, Mac};
+use secrecy::{ExposeSecret, Secret};
+use serde_json::json;
+use sha2::Sha256;
+use sqlx::{query, PgConnection, PgPool};
+use tracing::{debug, trace};
+use uuid::Uuid;
+
+use super::errors::InvitationError;
+use super::{create_direct_invitation, Invitation};
+use crate::config::app::{InvitationCap, WebsiteOrigin};
+use crate::modules::database::{EventId, PgQuery, UserId};
+use crate::modules::outbox::{self, Topic};
+use bimetable_domain::api::invitations::{DirectInvitation, EmailInvitation};
+use bimetable_domain::validation::ValidateContent;
+
+/// Emails are matched with logins regardless of their case.
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+/// Signs the invitation to the email, the token goes to the sign-up link.
+pub fn sign_invitation(key: &Secret<String>, invitation_id: Uuid, email: &str) -> String {
+    let signature = hex::encode(mac(key, invitation_id, email).finalize().into_bytes());
+    format!("{invitation_id}.{signature}")
+}
+
+/// Id of the invitation the token was signed for, the email comes from the stored invitation.
+fn token_invitation_id(token: &str) -> Option<Uuid> {
+    let (id, _) = token.split_once('.')?;
+    Uuid::parse_str(id).ok()
+}
+
+fn verify_invitation(key: &Secret<String>, token: &str, invitation_id: Uuid, email: &str) -> bool {
+    let Some((_, signature)) = token.split_once('.') else {
+        return false;
+    };
+    let Ok(signature) = hex::decode(signature) else {
+        return false;
+    };
+    mac(key, invitation_id, email)
+        .verify_slice(&signature)
+        .is_ok()
+}
+
+fn mac(key: &Secret<String>, invitation_id: Uuid, email: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.expose_secret().as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(format!("email-invitation\n{invitation_id}\n{email}").as_bytes());
+    mac
+}
+
+struct EmailInvitations;
+
+impl<'c> PgQuery<'c, EmailInvitations> {
+    async fn registered_user(&mut self, email: &str) -> Result<Option<Uuid>, InvitationError> {
+        let user_id = query!(
+            r#"
+            SELECT user_id FROM credentials
+            WHERE lower(login) = $1
+        "#,
+            email
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?
+        .map(|rec| rec.user_id);
+
+        Ok(user_id)
+    }
+
+    async fn was_sent(&mut self, event_id: EventId, email: &str) -> Result<bool, InvitationError> {
+        let was_sent = query!(
+            r#"
+            SELECT id FROM external_invitations
+            WHERE event_id = $1 AND email = $2
+        "#,
+            event_id.0,
+            email
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?
+        .is_some();
+
+        if was_sent {
+            trace!("Email invitation was previously sent");
+        }
+
+        Ok(was_sent)
+    }
+
+    async fn get_email(&mut self, id: Uuid) -> Result<Option<String>, InvitationError> {
+        let email = query!(
+            r#"
+            SELECT email FROM external_invitations
+            WHERE id = $1
+            FOR UPDATE
+        "#,
+            id
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?
+        .map(|rec| rec.email);
+
+        Ok(email)
+    }
+
+    async fn get_ids_for(&mut self, email: &str) -> Result<Vec<Uuid>, InvitationError> {
+        let ids = query!(
+            r#"
+            SELECT id FROM external_invitations
+            WHERE email = $1
+            FOR UPDATE
+        "#,
+            email
+        )
+        .fetch_all(&mut *self.conn)
+        .await?
+        .into_iter()
+        .map(|rec| rec.id)
+        .collect();
+
+        Ok(ids)
+    }
+
+    async fn create(&mut self, inv: &EmailInvitation) -> Result<Uuid, InvitationError> {
+        let id = query!(
+            r#"
+            INSERT INTO external_invitations (event_id, sender_id, email, can_edit)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id
+        "#,
+            inv.event_id,
+            inv.sender_id,
+            inv.email,
+            inv.can_edit
+        )
+        .fetch_one(&mut *self.conn)
+        .await?
+        .id;
+
+        trace!("Created email invitation for event: {}", inv.event_id);
+
+        Ok(id)
+    }
+
+    /// Turns the invitations into participations of the user, returning their events.
+    async fn attach(&mut self, ids: &[Uuid], user_id: Uuid) -> Result<Vec<Uuid>, InvitationError> {
+        query!(
+            r#"
+            INSERT INTO user_events (user_id, event_id, can_edit)
+            SELECT $2, event_id, can_edit
+            FROM external_invitations
+            WHERE id = ANY($1)
+            ON CONFLICT DO NOTHING
+        "#,
+            ids,
+            user_id
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        let event_ids = query!(
+            r#"
+            DELETE FROM external_invitations
+            WHERE id = ANY($1)
+            RETURNING event_id
+        "#,
+            ids
+        )
+        .fetch_all(&mut *self.conn)
+        .await?
+        .into_iter()
+        .map(|rec| rec.event_id)
+        .collect();
+
+        Ok(event_ids)
+    }
+}
+
+/// Invites someone by their email address, it works as a direct invitation when they already have an account.
+///
+/// Others get a signed sign-up link, and join the event once they register with the email.
+pub async fn create_email_invitation(
+    pool: &PgPool,
+    mut inv: EmailInvitation,
+    cap: InvitationCap,
+    key: &Secret<String>,
+    origin: &WebsiteOrigin,
+) -> Result<(), InvitationError> {
+    inv.email = normalize_email(&inv.email);
+    inv.validate_content()?;
+
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(EmailInvitations, &mut transaction);
+    if let Some(receiver_id) = q.registered_user(&inv.email).await? {
+        trace!("Email belongs to the user {receiver_id}");
+        transaction.rollback().await?;
+        return create_direct_invitation(
+            pool,
+            DirectInvitation {
+                event_id: inv.event_id,
+                sender_id: inv.sender_id,
+                receiver_id,
+                can_edit: inv.can_edit,
+            },
+            cap,
+        )
+        .await;
+    }
+
+    let (event_id, sender_id) = (EventId(inv.event_id), UserId(inv.sender_id));
+    let mut sender = PgQuery::new(Invitation, &mut transaction);
+    sender.check_sender(event_id, sender_id).await?;
+
+    let mut q = PgQuery::new(EmailInvitations, &mut transaction);
+    if q.was_sent(event_id, &inv.email).await? {
+        trace!("Email invitation already created");
+        return Ok(());
+    }
+
+    let mut sender = PgQuery::new(Invitation, &mut transaction);
+    let sent = sender.count_sent_direct(sender_id).await?;
+    if sent > i32::try_from(cap.0).unwrap_or(i32::MAX) {
+        debug!("User {} exceeded the invitation cap", inv.sender_id);
+        return Err(InvitationError::TooMany);
+    }
+
+    let mut q = PgQuery::new(EmailInvitations, &mut transaction);
+    let id = q.create(&inv).await?;
+    let token = sign_invitation(key, id, &inv.email);
+    outbox::record(
+        &mut transaction,
+        Topic::EmailInvitationCreated,
+        inv.event_id,
+        json!({
+            "senderId": inv.sender_id,
+            "email": inv.email,
+            "signUpLink": format!("{}/register?invitation={token}", origin.0),
+        }),
+    )
+    .await?;
+
+    transaction.commit().await?;
+    Ok(())
+}
+
+/// Lets a user who has just registered with the `login` join the events it was invited to.
+pub async fn attach_email_invitations(
+    conn: &mut PgConnection,
+    login: &str,
+    user_id: Uuid,
+) -> anyhow::Result<usize> {
+    let mut q = PgQuery::new(EmailInvitations, &mut *conn);
+    let ids = q.get_ids_for(&normalize_email(login)).await?;
+    if ids.is_empty() {
+        return Ok(0);
+    }
+
+    let event_ids = q.attach(&ids, user_id).await?;
+    for event_id in &event_ids {
+        outbox::record(
+            &mut *conn,
+            Topic::ParticipantsChanged,
+            *event_id,
+            json!({ "userId": user_id }),
+        )
+        .await?;
+    }
+
+    debug!(
+        "User {user_id} joined {} event(s) they were invited to by email",
+        event_ids.len()
+    );
+    Ok(event_ids.len())
+}
+
+/// Accepts the invitation of a sign-up link, for users who registered with another email.
+pub async fn accept_email_invitation(
+    pool: &PgPool,
+    key: &Secret<String>,
+    token: &str,
+    user_id: Uuid,
+) -> Result<(), InvitationError> {
+    let id = token_invitation_id(token).ok_or(InvitationError::Missing)?;
+
+    let mut transaction = pool.begin().await?;
+    let email = PgQuery::new(EmailInvitations, &mut transaction)
+        .get_email(id)
+        .await?
+        .ok_or(InvitationError::Missing)?;
+
+    if !verify_invitation(key, token, id, &email) {
+        debug!("Sign-up link of the invitation {id} has an invalid signature");
+        return Err(InvitationError::Missing);
+    }
+
+    let event_ids = PgQuery::new(EmailInvitations, &mut transaction)
+        .attach(&[id], user_id)
+        .await?;
+    for event_id in event_ids {
+        outbox::record(
+            &mut transaction,
+            Topic::ParticipantsChanged,
+            event_id,
+            json!({ "userId": user_id }),
+        )
+        .await?;
+    }
+
+    transaction.commit().await?;
+    debug!("User {user_id} accepted the email invitation {id}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod email_invitation_tests {
+    use super::*;
+
+    #[test]
+    fn verifies_own_signatures() {
+        let key = Secret::new("signing".to_string());
+        let id = Uuid::new_v4();
+        let token = sign_invitation(&key, id, "kasia@example.com");
+
+        assert_eq!(token_invitation_id(&token), Some(id));
+        assert!(verify_invitation(&key, &token, id, "kasia@example.com"));
+        assert!(!verify_invitation(&key, &token, id, "other@example.com"));
+        assert!(!verify_invitation(
+            &Secret::new("other".to_string()),
+            &token,
+            id,
+            "kasia@example.com"
+        ));
+        assert!(!verify_invitation(&key, "garbage", id, "kasia@example.com"));
+    }
+
+    #[test]
+    fn emails_ignore_case() {
+        assert_eq!(normalize_email(" Kasia@Example.COM "), "kasia@example.com");
+    }
+}
@@ -0,0 +1,35 @@
+use thiserror::Error;
+
+use crate::modules::database::{
+    violated_constraint, OWNER_PARTICIPANT_CONSTRAINT, PARTICIPANT_INVITATION_CONSTRAINT,
+};
+use bimetable_domain::validation::ValidateContentError;
+
+#[derive(Error, Debug)]
+pub enum InvitationError {
+    #[error("Invitation is missing")]
+    Missing,
+    #[error("Event is missing")]
+    EventNotFound,
+    #[error("Only owners and editors of the event can invite others")]
+    MismatchedPrivileges,
+    #[error("Too many invitations sent, try again within an hour")]
+    TooMany,
+    #[error("Receiver already participates in the event")]
+    AlreadyParticipant,
+    #[error("Invitation data rejected with validation")]
+    InvalidData(#[from] ValidateContentError),
+    #[error(transparent)]
+    Unexpected(#[from] anyhow::Error),
+}
+
+impl From<sqlx::Error> for InvitationError {
+    fn from(e: sqlx::Error) -> Self {
+        match violated_constraint(&e) {
+            Some(OWNER_PARTICIPANT_CONSTRAINT | PARTICIPANT_INVITATION_CONSTRAINT) => {
+                Self::AlreadyParticipant
+            }
+            _ => Self::Unexpected(anyhow::Error::from(e)),
+        }
+    }
+}
@@ -0,0 +1,449 @@
+pub mod email;
+pub mod errors;
+
+use crate::config::app::InvitationCap;
+use crate::modules::database::{EventId, PgQuery, UserId};
+use crate::modules::outbox::{self, Topic};
+use serde_json::json;
+use sqlx::{query, query_as, PgPool};
+use tracing::{debug, trace};
+use uuid::Uuid;
+
+use bimetable_domain::api::invitations::{
+    DirectInvitation, InvitationCount, ReceivedInvitation, RespondDirectInvitation,
+};
+use bimetable_domain::validation::ValidateContent;
+
+use self::errors::InvitationError;
+
+struct Invitation;
+
+impl<'c> PgQuery<'c, Invitation> {
+    async fn get_all_direct(
+        &mut self,
+        receiver_id: UserId,
+    ) -> Result<Vec<ReceivedInvitation>, InvitationError> {
+        let res = query_as!(
+            ReceivedInvitation,
+            r#"
+            SELECT event_id, sender_id, users.username AS sender_username, users.tag AS sender_tag,
+            receiver_id, can_edit, seen_at IS NOT NULL AS "is_seen!"
+            FROM user_event_invitations
+            JOIN users ON users.id = sender_id
+            WHERE receiver_id = $1
+        "#,
+            receiver_id.0
+        )
+        .fetch_all(&mut *self.conn)
+        .await?;
+
+        trace!("Got {} direct invitations", res.len());
+
+        Ok(res)
+    }
+
+    async fn count_direct(
+        &mut self,
+        receiver_id: UserId,
+    ) -> Result<InvitationCount, InvitationError> {
+        let res = query!(
+            r#"
+            SELECT
+                COUNT(*) AS "pending!",
+                COUNT(*) FILTER (WHERE seen_at IS NULL) AS "unseen!"
+            FROM user_event_invitations
+            WHERE receiver_id = $1
+        "#,
+            receiver_id.0
+        )
+        .fetch_one(&mut *self.conn)
+        .await?;
+
+        Ok(InvitationCount {
+            pending: res.pending as u32,
+            unseen: res.unseen as u32,
+        })
+    }
+
+    async fn mark_seen_direct(&mut self, receiver_id: UserId) -> Result<u64, InvitationError> {
+        let affected = query!(
+            r#"
+            UPDATE user_event_invitations
+            SET seen_at = now()
+            WHERE receiver_id = $1 AND seen_at IS NULL
+        "#,
+            receiver_id.0
+        )
+        .execute(&mut *self.conn)
+        .await?
+        .rows_affected();
+
+        trace!("Marked {affected} direct invitations as seen");
+
+        Ok(affected)
+    }
+
+    async fn get_one_direct(
+        &mut self,
+        event_id: EventId,
+        sender_id: UserId,
+        receiver_id: UserId,
+    ) -> Result<Option<DirectInvitation>, InvitationError> {
+        let res = query_as!(
+            DirectInvitation,
+            r#"
+            SELECT event_id, sender_id, receiver_id, can_edit FROM user_event_invitations
+            WHERE event_id = $1 AND sender_id = $2 AND receiver_id = $3
+        "#,
+            event_id.0,
+            sender_id.0,
+            receiver_id.0
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?;
+
+        Ok(res)
+    }
+
+    async fn delete_remaining_direct_for_event(
+        &mut self,
+        event_id: EventId,
+        receiver_id: UserId,
+    ) -> Result<(), InvitationError> {
+        let affected = query!(
+            r#"
+            DELETE FROM user_event_invitations
+            WHERE event_id = $1 AND receiver_id = $2
+        "#,
+            event_id.0,
+            receiver_id.0
+        )
+        .execute(&mut *self.conn)
+        .await?
+        .rows_affected();
+
+        trace!(
+            "Deleted {affected} remaining direct invitations for event {:?}",
+            event_id.0
+        );
+
+        Ok(())
+    }
+
+    /// Counts another invitation in the sender's current hour, forgetting the previous hours.
+    async fn count_sent_direct(&mut self, sender_id: UserId) -> Result<i32, InvitationError> {
+        query!(
+            r#"
+            DELETE FROM invitation_counters
+            WHERE sender_id = $1 AND window_start < date_trunc('hour', now())
+        "#,
+            sender_id.0
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        let sent = query!(
+            r#"
+            INSERT INTO invitation_counters (sender_id, window_start, sent)
+            VALUES ($1, date_trunc('hour', now()), 1)
+            ON CONFLICT (sender_id, window_start)
+            DO UPDATE SET sent = invitation_counters.sent + 1
+            RETURNING sent
+        "#,
+            sender_id.0
+        )
+        .fetch_one(&mut *self.conn)
+        .await?
+        .sent;
+
+        Ok(sent)
+    }
+
+    async fn was_sent_direct(
+        &mut self,
+        event_id: EventId,
+        sender_id: UserId,
+        receiver_id: UserId,
+    ) -> Result<bool, InvitationError> {
+        let was_sent = query!(
+            r#"
+            SELECT * FROM user_event_invitations
+            WHERE event_id = $1 AND sender_id = $2 AND receiver_id = $3
+        "#,
+            event_id.0,
+            sender_id.0,
+            receiver_id.0
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?
+        .is_some();
+
+        if was_sent {
+            trace!("Direct invitation was previously sent");
+        }
+
+        Ok(was_sent)
+    }
+
+    async fn can_edit_direct(
+        &mut self,
+        event_id: EventId,
+        sender_id: UserId,
+        receiver_id: UserId,
+    ) -> Result<bool, InvitationError> {
+        let can_edit = query!(
+            r#"
+            SELECT can_edit FROM user_event_invitations
+            WHERE event_id = $1 AND sender_id = $2 AND receiver_id = $3
+        "#,
+            event_id.0,
+            sender_id.0,
+            receiver_id.0
+        )
+        .fetch_one(&mut *self.conn)
+        .await?
+        .can_edit;
+
+        Ok(can_edit)
+    }
+
+    /// Editing privilege the sender grants by default.
+    async fn default_can_edit(&mut self, sender_id: UserId) -> Result<bool, InvitationError> {
+        let can_edit = query!(
+            r#"
+            SELECT invite_can_edit FROM users
+            WHERE id = $1
+        "#,
+            sender_id.0
+        )
+        .fetch_one(&mut *self.conn)
+        .await?
+        .invite_can_edit;
+
+        Ok(can_edit)
+    }
+
+    /// Checks that the event exists and the sender owns it, co-owns it or can edit it.
+    ///
+    /// Senders who don't take part in the event get `EventNotFound`, like for a missing event.
+    async fn check_sender(
+        &mut self,
+        event_id: EventId,
+        sender_id: UserId,
+    ) -> Result<(), InvitationError> {
+        let sender = query!(
+            r#"
+            SELECT
+                events.owner_id = $2 OR user_events.user_id IS NOT NULL AS "is_participant!",
+                events.owner_id = $2 OR COALESCE(user_events.is_owner OR user_events.can_edit, false) AS "can_invite!"
+            FROM events
+            LEFT JOIN user_events ON user_events.event_id = events.id AND user_events.user_id = $2
+            WHERE events.id = $1 AND events.deleted_at IS NULL
+        "#,
+            event_id.0,
+            sender_id.0
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?
+        .ok_or(InvitationError::EventNotFound)?;
+
+        if !sender.is_participant {
+            debug!("User {sender_id} does not take part in the event {event_id}");
+            return Err(InvitationError::EventNotFound);
+        }
+        if !sender.can_invite {
+            debug!("User {sender_id} can't invite others to the event {event_id}");
+            return Err(InvitationError::MismatchedPrivileges);
+        }
+
+        Ok(())
+    }
+
+    async fn create_direct(
+        &mut self,
+        event_id: EventId,
+        sender_id: UserId,
+        receiver_id: UserId,
+        can_edit: bool,
+    ) -> Result<(), InvitationError> {
+        let _res = query!(
+            r#"
+                INSERT INTO user_event_invitations (event_id, sender_id, receiver_id, can_edit)
+                VALUES ($1, $2, $3, $4)
+            "#,
+            event_id.0,
+            sender_id.0,
+            receiver_id.0,
+            can_edit
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!("Created user event invitation for event: {}", event_id);
+
+        Ok(())
+    }
+
+    async fn delete_direct(
+        &mut self,
+        event_id: EventId,
+        sender_id: UserId,
+        receiver_id: UserId,
+    ) -> Result<(), InvitationError> {
+        query!(
+            r#"
+            DELETE FROM user_event_invitations
+            WHERE event_id = $1 AND sender_id = $2 AND receiver_id = $3
+        "#,
+            event_id.0,
+            sender_id.0,
+            receiver_id.0
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_user_event(
+        &mut self,
+        event_id: EventId,
+        receiver_id: UserId,
+        can_edit: bool,
+    ) -> Result<(), InvitationError> {
+        query!(
+            r#"
+            INSERT INTO user_events (user_id, event_id, can_edit)
+            VALUES ($1, $2, $3)
+        "#,
+            receiver_id.0,
+            event_id.0,
+            can_edit
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(())
+    }
+}
+
+pub async fn get_all_direct_invitations(
+    pool: &PgPool,
+    user_id: &Uuid,
+) -> Result<Vec<ReceivedInvitation>, InvitationError> {
+    let mut conn = pool.acquire().await?;
+    let mut q = PgQuery::new(Invitation, &mut conn);
+    let invitations = q.get_all_direct(UserId(*user_id)).await?;
+    Ok(invitations)
+}
+
+pub async fn count_direct_invitations(
+    pool: &PgPool,
+    user_id: &Uuid,
+) -> Result<InvitationCount, InvitationError> {
+    let mut conn = pool.acquire().await?;
+    let mut q = PgQuery::new(Invitation, &mut conn);
+    q.count_direct(UserId(*user_id)).await
+}
+
+/// Stamps all invitations received by the user as seen.
+pub async fn mark_direct_invitations_seen(
+    pool: &PgPool,
+    user_id: &Uuid,
+) -> Result<u64, InvitationError> {
+    let mut conn = pool.acquire().await?;
+    let mut q = PgQuery::new(Invitation, &mut conn);
+    q.mark_seen_direct(UserId(*user_id)).await
+}
+
+pub async fn get_default_can_edit(
+    pool: &PgPool,
+    sender_id: &Uuid,
+) -> Result<bool, InvitationError> {
+    let mut conn = pool.acquire().await?;
+    let mut q = PgQuery::new(Invitation, &mut conn);
+    q.default_can_edit(UserId(*sender_id)).await
+}
+
+/// Sends an invitation unless the sender already used up their hourly `cap`,
+/// only owners and editors of an existing event may send them.
+pub async fn create_direct_invitation(
+    pool: &PgPool,
+    inv: DirectInvitation,
+    cap: InvitationCap,
+) -> Result<(), InvitationError> {
+    inv.validate_content()?;
+
+    let mut transaction = pool.begin().await?;
+    let (event_id, sender_id, receiver_id) = (
+        EventId(inv.event_id),
+        UserId(inv.sender_id),
+        UserId(inv.receiver_id),
+    );
+    let mut q = PgQuery::new(Invitation, &mut transaction);
+    q.check_sender(event_id, sender_id).await?;
+    if !q.was_sent_direct(event_id, sender_id, receiver_id).await? {
+        let sent = q.count_sent_direct(sender_id).await?;
+        if sent > i32::try_from(cap.0).unwrap_or(i32::MAX) {
+            debug!("User {} exceeded the invitation cap", inv.sender_id);
+            return Err(InvitationError::TooMany);
+        }
+        q.create_direct(event_id, sender_id, receiver_id, inv.can_edit)
+            .await?;
+        outbox::record(
+            &mut *q.conn,
+            Topic::InvitationCreated,
+            inv.event_id,
+            json!({ "senderId": inv.sender_id, "receiverId": inv.receiver_id }),
+        )
+        .await?;
+    } else {
+        trace!("Direct invitation already created");
+    }
+
+    transaction.commit().await?;
+    Ok(())
+}
+
+pub async fn respond_to_direct_invitation(
+    pool: &PgPool,
+    response: RespondDirectInvitation,
+) -> Result<(), InvitationError> {
+    let mut transaction = pool.begin().await?;
+    let (event_id, sender_id, receiver_id) = (
+        EventId(response.event_id),
+        UserId(response.sender_id),
+        UserId(response.receiver_id),
+    );
+    let mut q = PgQuery::new(Invitation, &mut transaction);
+
+    if let Some(_inv) = q.get_one_direct(event_id, sender_id, receiver_id).await? {
+        if response.is_accepted {
+            trace!("Invitation was accepted");
+            let can_edit = q.can_edit_direct(event_id, sender_id, receiver_id).await?;
+            q.create_user_event(event_id, receiver_id, can_edit).await?;
+            trace!("Created user event");
+        }
+        q.delete_direct(event_id, sender_id, receiver_id).await?;
+        trace!("Deleted direct invitation");
+        q.delete_remaining_direct_for_event(event_id, receiver_id)
+            .await?;
+        outbox::record(
+            &mut *q.conn,
+            Topic::InvitationResponded,
+            response.event_id,
+            json!({
+                "senderId": response.sender_id,
+                "receiverId": response.receiver_id,
+                "isAccepted": response.is_accepted,
+            }),
+        )
+        .await?;
+
+        transaction.commit().await?;
+        return Ok(());
+    }
+
+    trace!("Direct invitation missing");
+    Err(InvitationError::Missing)
+}
@@ -0,0 +1,8 @@
+pub mod admin;
+pub mod auth;
+pub mod events;
+pub mod invitations;
+pub mod reminders;
+pub mod scim;
+pub mod search;
+pub mod users;
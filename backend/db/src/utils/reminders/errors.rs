@@ -0,0 +1,31 @@
+use crate::utils::events::errors::EventError;
+use bimetable_domain::validation::ValidateContentError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ReminderError {
+    #[error("Reminder is missing")]
+    Missing,
+    #[error("Event is missing")]
+    EventMissing,
+    #[error("Reminder data rejected with validation")]
+    InvalidData(#[from] ValidateContentError),
+    #[error(transparent)]
+    Unexpected(#[from] anyhow::Error),
+}
+
+impl From<sqlx::Error> for ReminderError {
+    fn from(e: sqlx::Error) -> Self {
+        Self::Unexpected(anyhow::Error::from(e))
+    }
+}
+
+impl From<EventError> for ReminderError {
+    fn from(e: EventError) -> Self {
+        match e {
+            EventError::NotFound => Self::EventMissing,
+            EventError::InvalidData(e) => Self::InvalidData(e),
+            e => Self::Unexpected(anyhow::Error::from(e)),
+        }
+    }
+}
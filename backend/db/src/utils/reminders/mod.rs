@@ -0,0 +1,461 @@
+pub mod errors;
+pub mod webhook;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use http::header::CONTENT_TYPE;
+use serde::{Deserialize, Serialize};
+use sqlx::{query, PgConnection, PgPool, Postgres, Transaction};
+use time::serde::iso8601;
+use time::{Duration, OffsetDateTime};
+use tracing::{debug, trace};
+use uuid::Uuid;
+
+use crate::modules::clock::{SharedClock, SystemClock};
+use crate::modules::database::PgQuery;
+use crate::modules::jobs::{enqueue, Job, JobHandler, NewJob};
+use crate::modules::outbox::{self, Topic};
+use crate::utils::events::errors::EventError;
+use crate::utils::events::EventQuery;
+use crate::utils::users::quiet_hours::QuietHoursQuery;
+use bimetable_domain::api::events::{Entry, Event, Events};
+use bimetable_domain::api::reminders::{
+    CreateReminder, Reminder, ReminderPayload, ReminderWebhook, WebhookFormat,
+};
+use bimetable_domain::api::users::{delivery_at, Delivery};
+use bimetable_domain::events::ics::events_to_ics;
+use bimetable_domain::events::models::TimeRange;
+use bimetable_domain::validation::ValidateContent;
+
+use self::errors::ReminderError;
+use self::webhook::{check_webhook_url, webhook_client};
+
+pub const FIRE_REMINDER_JOB: &str = "reminders.fire";
+
+/// How far ahead the next entry of a reminded event is looked for
+const NEXT_ENTRY_WINDOW: Duration = Duration::days(400);
+
+#[derive(Debug)]
+pub struct QReminder {
+    id: Uuid,
+    event_id: Uuid,
+    user_id: Uuid,
+    minutes_before: i32,
+    webhook_url: Option<String>,
+    webhook_format: String,
+    is_critical: bool,
+}
+
+impl QReminder {
+    fn offset(&self) -> Duration {
+        Duration::minutes(self.minutes_before.into())
+    }
+
+    fn webhook(&self) -> Result<Option<ReminderWebhook>, ReminderError> {
+        let Some(url) = &self.webhook_url else {
+            return Ok(None);
+        };
+        let format = WebhookFormat::try_from(self.webhook_format.clone())
+            .map_err(|e| ReminderError::Unexpected(anyhow::anyhow!(e)))?;
+
+        Ok(Some(ReminderWebhook {
+            url: url.clone(),
+            format,
+        }))
+    }
+}
+
+impl TryFrom<QReminder> for Reminder {
+    type Error = ReminderError;
+
+    fn try_from(reminder: QReminder) -> Result<Self, Self::Error> {
+        Ok(Self {
+            webhook: reminder.webhook()?,
+            id: reminder.id,
+            event_id: reminder.event_id,
+            minutes_before: reminder.minutes_before as u32,
+            critical: reminder.is_critical,
+        })
+    }
+}
+
+pub struct ReminderQuery {
+    user_id: Uuid,
+}
+
+impl<'c> PgQuery<'c, ReminderQuery> {
+    async fn create(&mut self, body: &CreateReminder) -> Result<QReminder, ReminderError> {
+        let (webhook_url, webhook_format) = match &body.webhook {
+            Some(webhook) => (Some(webhook.url.clone()), webhook.format),
+            None => (None, WebhookFormat::default()),
+        };
+
+        let reminder = query!(
+            r#"
+                INSERT INTO reminders (event_id, user_id, minutes_before, webhook_url, webhook_format, is_critical)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING id
+            "#,
+            body.event_id,
+            self.payload.user_id,
+            body.minutes_before as i32,
+            webhook_url,
+            webhook_format.as_str(),
+            body.critical,
+        )
+        .fetch_one(&mut *self.conn)
+        .await?;
+
+        trace!(
+            "Created reminder {} for event {}",
+            reminder.id,
+            body.event_id
+        );
+
+        Ok(QReminder {
+            id: reminder.id,
+            event_id: body.event_id,
+            user_id: self.payload.user_id,
+            minutes_before: body.minutes_before as i32,
+            webhook_url,
+            webhook_format: webhook_format.as_str().to_string(),
+            is_critical: body.critical,
+        })
+    }
+
+    async fn get_all(&mut self) -> Result<Vec<QReminder>, ReminderError> {
+        let reminders = sqlx::query_as!(
+            QReminder,
+            r#"
+                SELECT id, event_id, user_id, minutes_before, webhook_url, webhook_format, is_critical
+                FROM reminders
+                WHERE user_id = $1
+                ORDER BY created_at
+            "#,
+            self.payload.user_id,
+        )
+        .fetch_all(&mut *self.conn)
+        .await?;
+
+        trace!("Got {} reminders", reminders.len());
+        Ok(reminders)
+    }
+
+    async fn delete(&mut self, reminder_id: Uuid) -> Result<(), ReminderError> {
+        let deleted = query!(
+            r#"
+                DELETE FROM reminders
+                WHERE id = $1 AND user_id = $2
+            "#,
+            reminder_id,
+            self.payload.user_id,
+        )
+        .execute(&mut *self.conn)
+        .await?
+        .rows_affected();
+
+        if deleted == 0 {
+            return Err(ReminderError::Missing);
+        }
+
+        trace!("Deleted reminder {reminder_id}");
+        Ok(())
+    }
+}
+
+/// Access to a reminder from its scheduled job.
+pub struct ReminderJobQuery {
+    reminder_id: Uuid,
+}
+
+impl<'c> PgQuery<'c, ReminderJobQuery> {
+    async fn get_reminder(&mut self) -> Result<Option<QReminder>, ReminderError> {
+        let reminder = sqlx::query_as!(
+            QReminder,
+            r#"
+                SELECT id, event_id, user_id, minutes_before, webhook_url, webhook_format, is_critical
+                FROM reminders
+                WHERE id = $1
+            "#,
+            self.payload.reminder_id,
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?;
+
+        Ok(reminder)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FireReminder {
+    reminder_id: Uuid,
+    #[serde(with = "iso8601")]
+    entry_start: OffsetDateTime,
+}
+
+/// Gets the first entry of the event starting at or after `from`, as seen by the user.
+async fn next_entry(
+    q: &mut PgQuery<'_, EventQuery>,
+    event_id: Uuid,
+    from: OffsetDateTime,
+) -> Result<Option<Entry>, EventError> {
+    let search_range = TimeRange::new(from, from + NEXT_ENTRY_WINDOW);
+    let events = q.get_event_entries_in(event_id, search_range).await?;
+    let Some(event) = events.events.get(&event_id) else {
+        return Ok(None);
+    };
+
+    if event.recurrence_rule.is_none() {
+        let entry = (event.entries_start >= from).then(|| {
+            let end = event.entries_end.unwrap_or(event.entries_start);
+            Entry::new(event_id, TimeRange::new(event.entries_start, end), None)
+        });
+        return Ok(entry);
+    }
+
+    Ok(events
+        .entries
+        .into_iter()
+        .find(|entry| entry.time_range.start >= from))
+}
+
+/// Enqueues the reminder of the first entry starting at or after `from`.
+async fn schedule_next(
+    conn: &mut PgConnection,
+    reminder: &QReminder,
+    from: OffsetDateTime,
+) -> Result<Option<OffsetDateTime>, ReminderError> {
+    let mut q = PgQuery::new(EventQuery::new(reminder.user_id), conn);
+    let Some(entry) = next_entry(&mut q, reminder.event_id, from).await? else {
+        trace!("No more entries for reminder {}", reminder.id);
+        return Ok(None);
+    };
+
+    let run_at = entry.time_range.start - reminder.offset();
+    let job = NewJob::new(
+        FIRE_REMINDER_JOB,
+        FireReminder {
+            reminder_id: reminder.id,
+            entry_start: entry.time_range.start,
+        },
+    )?
+    .run_at(run_at);
+    enqueue(q.conn, job).await?;
+
+    trace!("Scheduled reminder {} at {run_at}", reminder.id);
+    Ok(Some(run_at))
+}
+
+pub async fn create_reminder(
+    pool: &PgPool,
+    user_id: Uuid,
+    body: CreateReminder,
+    now: OffsetDateTime,
+) -> Result<Uuid, ReminderError> {
+    body.validate_content()?;
+
+    let mut transaction = pool.begin().await?;
+    PgQuery::new(EventQuery::new(user_id), &mut transaction)
+        .get_event(body.event_id)
+        .await?
+        .ok_or(ReminderError::EventMissing)?;
+
+    let reminder = PgQuery::new(ReminderQuery { user_id }, &mut transaction)
+        .create(&body)
+        .await?;
+    let from = now + reminder.offset();
+    schedule_next(&mut transaction, &reminder, from).await?;
+    transaction.commit().await?;
+
+    Ok(reminder.id)
+}
+
+pub async fn get_user_reminders(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<Reminder>, ReminderError> {
+    let mut conn = pool.acquire().await?;
+    PgQuery::new(ReminderQuery { user_id }, &mut conn)
+        .get_all()
+        .await?
+        .into_iter()
+        .map(Reminder::try_from)
+        .collect()
+}
+
+/// Deletes the reminder, its already scheduled job finds nothing to send.
+pub async fn delete_reminder(
+    pool: &PgPool,
+    user_id: Uuid,
+    reminder_id: Uuid,
+) -> Result<(), ReminderError> {
+    let mut conn = pool.acquire().await?;
+    PgQuery::new(ReminderQuery { user_id }, &mut conn)
+        .delete(reminder_id)
+        .await
+}
+
+/// Job handler sending due reminders and scheduling the following ones.
+///
+/// Reminders without a webhook become outbox notifications for their user.
+/// A failing webhook retries the job, so delivery is at least once.
+/// Webhooks only reach public addresses, besides the allowed hosts.
+/// Quiet hours of the user defer or suppress reminders which are not critical,
+/// a deferred reminder schedules the following one once it is sent.
+pub struct ReminderHandler {
+    client: reqwest::Client,
+    clock: SharedClock,
+    allowed_hosts: Arc<Vec<String>>,
+}
+
+impl Default for ReminderHandler {
+    fn default() -> Self {
+        Self::new(Arc::new(SystemClock), Vec::new())
+    }
+}
+
+impl ReminderHandler {
+    pub fn new(clock: SharedClock, allowed_hosts: Vec<String>) -> Self {
+        let allowed_hosts = Arc::new(allowed_hosts);
+        Self {
+            client: webhook_client(allowed_hosts.clone()),
+            clock,
+            allowed_hosts,
+        }
+    }
+
+    async fn deliver(
+        &self,
+        conn: &mut PgConnection,
+        reminder: &QReminder,
+        event: Event,
+        entry: Entry,
+    ) -> anyhow::Result<()> {
+        let name = entry.effective.as_ref().map_or_else(
+            || event.payload.name.clone(),
+            |effective| effective.name.clone(),
+        );
+        let payload = ReminderPayload {
+            reminder_id: reminder.id,
+            event_id: reminder.event_id,
+            name,
+            minutes_before: reminder.minutes_before as u32,
+            entry,
+        };
+
+        let Some(webhook) = reminder.webhook()? else {
+            outbox::record(conn, Topic::ReminderDue, reminder.user_id, &payload).await?;
+            return Ok(());
+        };
+
+        check_webhook_url(&webhook.url, &self.allowed_hosts)?;
+        let request = self.client.post(&webhook.url);
+        let request = match webhook.format {
+            WebhookFormat::Json => request.json(&payload),
+            WebhookFormat::Ics => {
+                let events = Events::new(
+                    HashMap::from([(reminder.event_id, event)]),
+                    vec![payload.entry],
+                );
+                request
+                    .header(CONTENT_TYPE, "text/calendar; charset=utf-8")
+                    .body(events_to_ics(&events, self.clock.now()))
+            }
+        };
+        request.send().await?.error_for_status()?;
+
+        debug!("Sent reminder {} to its webhook", reminder.id);
+        Ok(())
+    }
+
+    /// Schedules the reminder of the entry after the one starting at `entry_start`.
+    async fn schedule_following(
+        &self,
+        mut transaction: Transaction<'_, Postgres>,
+        reminder: &QReminder,
+        entry_start: OffsetDateTime,
+    ) -> anyhow::Result<()> {
+        let from =
+            (entry_start + Duration::nanoseconds(1)).max(self.clock.now() + reminder.offset());
+        schedule_next(&mut transaction, reminder, from).await?;
+        transaction.commit().await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl JobHandler for ReminderHandler {
+    fn kind(&self) -> &'static str {
+        FIRE_REMINDER_JOB
+    }
+
+    async fn handle(&self, job: &Job, pool: &PgPool) -> anyhow::Result<()> {
+        let FireReminder {
+            reminder_id,
+            entry_start,
+        } = job.payload()?;
+
+        let mut transaction = pool.begin().await?;
+        let Some(reminder) = PgQuery::new(ReminderJobQuery { reminder_id }, &mut transaction)
+            .get_reminder()
+            .await?
+        else {
+            debug!("Reminder {reminder_id} was deleted");
+            return Ok(());
+        };
+
+        let mut q = PgQuery::new(EventQuery::new(reminder.user_id), &mut transaction);
+        let Some(event) = q.get_event(reminder.event_id).await? else {
+            debug!("Reminder {reminder_id} lost access to its event");
+            return Ok(());
+        };
+
+        let entry = next_entry(&mut q, reminder.event_id, entry_start)
+            .await?
+            .filter(|entry| entry.time_range.start == entry_start);
+        let Some(entry) = entry else {
+            debug!("Entry of reminder {reminder_id} no longer starts at {entry_start}");
+            return self
+                .schedule_following(transaction, &reminder, entry_start)
+                .await;
+        };
+
+        let delivery = if reminder.is_critical {
+            Delivery::Now
+        } else {
+            let quiet_hours =
+                PgQuery::new(QuietHoursQuery::new(reminder.user_id), &mut transaction)
+                    .get_all()
+                    .await?;
+            delivery_at(&quiet_hours, self.clock.now())
+        };
+        match delivery {
+            Delivery::Now => {
+                self.deliver(&mut transaction, &reminder, event, entry)
+                    .await?
+            }
+            Delivery::Suppressed => debug!("Suppressed reminder {reminder_id} during quiet hours"),
+            Delivery::DeferredTo(run_at) => {
+                let job = NewJob::new(
+                    FIRE_REMINDER_JOB,
+                    FireReminder {
+                        reminder_id,
+                        entry_start,
+                    },
+                )?
+                .run_at(run_at);
+                enqueue(&mut transaction, job).await?;
+                transaction.commit().await?;
+                debug!("Deferred reminder {reminder_id} until the end of quiet hours at {run_at}");
+                return Ok(());
+            }
+        }
+
+        self.schedule_following(transaction, &reminder, entry_start)
+            .await
+    }
+}
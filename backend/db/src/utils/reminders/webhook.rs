@@ -0,0 +1,131 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use reqwest::redirect::Policy;
+use reqwest::{Client, Url};
+
+const WEBHOOK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Client of reminder webhooks, which only reaches public addresses.
+///
+/// Users choose webhook URLs, so loopback, private and link-local addresses of the server's network
+/// are refused unless their host is allowed in the settings. Redirects are not followed,
+/// so a public webhook can't send the request elsewhere.
+pub fn webhook_client(allowed_hosts: Arc<Vec<String>>) -> Client {
+    Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .redirect(Policy::none())
+        .dns_resolver(Arc::new(PublicResolver { allowed_hosts }))
+        .build()
+        .expect("Failed to build webhook client")
+}
+
+/// Refuses URLs with an IP address of a non-public network, which aren't resolved by the client.
+pub fn check_webhook_url(url: &str, allowed_hosts: &[String]) -> anyhow::Result<()> {
+    let url = Url::parse(url)?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| anyhow!("Webhook has no host"))?;
+    if allowed_hosts.iter().any(|allowed| allowed == host) {
+        return Ok(());
+    }
+    if let Ok(ip) = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+    {
+        if !is_public(ip) {
+            return Err(anyhow!("Webhook address {ip} is not public"));
+        }
+    }
+    Ok(())
+}
+
+/// Resolves webhook hosts, dropping addresses of non-public networks.
+struct PublicResolver {
+    allowed_hosts: Arc<Vec<String>>,
+}
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let is_allowed = self.allowed_hosts.iter().any(|host| host == name.as_str());
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_allowed || is_public(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("Webhook host {} has no public address", name.as_str()).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Whether the address is outside of loopback, private, link-local and unspecified networks.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_v4(ip),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    !(ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        // Shared address space of carrier-grade NAT
+        || (ip.octets()[0] == 100 && (ip.octets()[1] & 0b1100_0000) == 64))
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    !(ip.is_loopback() || ip.is_unspecified() || ip.is_unique_local() || ip.is_unicast_link_local())
+}
+
+#[cfg(test)]
+mod webhook_tests {
+    use super::*;
+
+    #[test]
+    fn only_public_addresses_are_reached() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "0.0.0.0",
+            "100.64.0.1",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "::ffff:169.254.169.254",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{ip} is not public");
+        }
+        for ip in ["93.184.216.34", "1.1.1.1", "2606:4700:4700::1111"] {
+            assert!(is_public(ip.parse().unwrap()), "{ip} is public");
+        }
+    }
+
+    #[test]
+    fn private_ip_urls_are_refused() {
+        let allowed = vec!["127.0.0.1".to_string()];
+
+        assert!(check_webhook_url("http://169.254.169.254/latest/meta-data", &[]).is_err());
+        assert!(check_webhook_url("http://[::1]:8080/bell", &[]).is_err());
+        assert!(check_webhook_url("http://127.0.0.1:8080/bell", &[]).is_err());
+        assert!(check_webhook_url("http://127.0.0.1:8080/bell", &allowed).is_ok());
+        assert!(check_webhook_url("https://hooks.example.com/bell", &[]).is_ok());
+    }
+}
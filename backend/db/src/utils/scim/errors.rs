@@ -0,0 +1,63 @@
+use thiserror::Error;
+
+use crate::utils::auth::errors::AuthError;
+
+/// Postgres code of a violated unique constraint
+const UNIQUE_VIOLATION: &str = "23505";
+
+/// Errors of the provisioning API, answered in the SCIM error format.
+#[derive(Error, Debug)]
+pub enum ScimError {
+    #[error("Provisioning is disabled")]
+    Disabled,
+    #[error("Invalid SCIM token")]
+    Unauthorized,
+    #[error("User does not exist")]
+    NotFound,
+    #[error("User name or external id is already taken")]
+    Conflict,
+    #[error("{0}")]
+    InvalidValue(String),
+    #[error("Unsupported filter")]
+    InvalidFilter,
+    #[error("{0}")]
+    InvalidPath(String),
+    #[error(transparent)]
+    Unexpected(#[from] anyhow::Error),
+}
+
+impl ScimError {
+    pub fn scim_type(&self) -> Option<&'static str> {
+        match self {
+            ScimError::Conflict => Some("uniqueness"),
+            ScimError::InvalidValue(_) => Some("invalidValue"),
+            ScimError::InvalidFilter => Some("invalidFilter"),
+            ScimError::InvalidPath(_) => Some("invalidPath"),
+            _ => None,
+        }
+    }
+}
+
+impl From<AuthError> for ScimError {
+    fn from(e: AuthError) -> Self {
+        match e {
+            AuthError::UserAlreadyExists => Self::Conflict,
+            AuthError::UserNotFound => Self::NotFound,
+            AuthError::MissingCredential
+            | AuthError::WeakPassword
+            | AuthError::InvalidUsername(_)
+            | AuthError::TagOverflow => Self::InvalidValue(e.to_string()),
+            e => Self::Unexpected(anyhow::Error::from(e)),
+        }
+    }
+}
+
+impl From<sqlx::Error> for ScimError {
+    fn from(e: sqlx::Error) -> Self {
+        let code = e.as_database_error().and_then(|e| e.code());
+        if code.as_deref() == Some(UNIQUE_VIOLATION) {
+            return Self::Conflict;
+        }
+        Self::Unexpected(anyhow::Error::from(e))
+    }
+}
@@ -0,0 +1,565 @@
+pub mod errors;
+
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+use secrecy::SecretString;
+use serde_json::Value;
+use sqlx::{query, query_as, PgPool};
+use time::OffsetDateTime;
+use tracing::{debug, trace};
+use uuid::Uuid;
+
+use crate::config::passwords::PasswordSettings;
+use crate::config::usernames::UsernameSettings;
+use crate::modules::database::PgQuery;
+use crate::utils::auth::additions::{
+    hash_pass, normalize_name, pass_is_strong, random_username_tag, validate_usernames,
+};
+use crate::utils::auth::errors::AuthError;
+use crate::utils::auth::{deactivate_user, revoke_user_tokens, try_register_user};
+use crate::utils::scim::errors::ScimError;
+use bimetable_domain::api::scim::{
+    ScimListQuery, ScimListResponse, ScimMeta, ScimPatch, ScimUser, ScimUserRequest,
+    LIST_RESPONSE_SCHEMA, USER_SCHEMA,
+};
+
+const DEFAULT_PAGE_SIZE: u32 = 100;
+const MAX_PAGE_SIZE: u32 = 1000;
+const GENERATED_PASSWORD_LENGTH: usize = 32;
+
+#[derive(Debug)]
+pub struct QScimUser {
+    id: Uuid,
+    login: String,
+    username: String,
+    external_id: Option<String>,
+    active: bool,
+}
+
+impl From<QScimUser> for ScimUser {
+    fn from(user: QScimUser) -> Self {
+        Self {
+            schemas: vec![USER_SCHEMA.to_string()],
+            id: user.id,
+            external_id: user.external_id,
+            user_name: user.login,
+            display_name: user.username,
+            active: user.active,
+            meta: ScimMeta {
+                resource_type: "User".to_string(),
+            },
+        }
+    }
+}
+
+/// Attributes changed by a replacing or patching request, `None` keeps the current value.
+#[derive(Debug, Default, PartialEq)]
+pub struct UserChanges {
+    pub user_name: Option<String>,
+    pub display_name: Option<String>,
+    pub external_id: Option<String>,
+    pub active: Option<bool>,
+    pub password: Option<String>,
+}
+
+impl From<ScimUserRequest> for UserChanges {
+    fn from(body: ScimUserRequest) -> Self {
+        Self {
+            user_name: Some(body.user_name),
+            display_name: body.display_name,
+            external_id: body.external_id,
+            active: Some(body.active),
+            password: body.password,
+        }
+    }
+}
+
+impl TryFrom<ScimPatch> for UserChanges {
+    type Error = ScimError;
+
+    fn try_from(patch: ScimPatch) -> Result<Self, Self::Error> {
+        let mut changes = Self::default();
+        for operation in patch.operations {
+            if !matches!(operation.op.to_lowercase().as_str(), "add" | "replace") {
+                return Err(ScimError::InvalidPath(format!(
+                    "Unsupported operation {}",
+                    operation.op
+                )));
+            }
+            match operation.path {
+                Some(path) => changes.set(&path, operation.value)?,
+                None => {
+                    let Value::Object(attributes) = operation.value else {
+                        return Err(ScimError::InvalidValue(
+                            "Operation without a path needs an object value".to_string(),
+                        ));
+                    };
+                    for (path, value) in attributes {
+                        changes.set(&path, value)?;
+                    }
+                }
+            }
+        }
+
+        Ok(changes)
+    }
+}
+
+impl UserChanges {
+    /// Sets a single attribute, attributes which are not stored are ignored.
+    fn set(&mut self, path: &str, value: Value) -> Result<(), ScimError> {
+        match path.to_lowercase().as_str() {
+            "username" => self.user_name = Some(string_value(path, value)?),
+            "displayname" => self.display_name = Some(string_value(path, value)?),
+            "externalid" => self.external_id = Some(string_value(path, value)?),
+            "password" => self.password = Some(string_value(path, value)?),
+            "active" => self.active = Some(bool_value(value)?),
+            _ => trace!("Ignoring change of unsupported attribute {path}"),
+        }
+        Ok(())
+    }
+}
+
+fn string_value(path: &str, value: Value) -> Result<String, ScimError> {
+    match value {
+        Value::String(value) => Ok(value),
+        _ => Err(ScimError::InvalidValue(format!("{path} must be a string"))),
+    }
+}
+
+/// Reads a boolean, also from the `"True"` and `"False"` strings sent by some identity systems.
+fn bool_value(value: Value) -> Result<bool, ScimError> {
+    match value {
+        Value::Bool(value) => Ok(value),
+        Value::String(value) => value
+            .to_lowercase()
+            .parse::<bool>()
+            .map_err(|_| ScimError::InvalidValue("active must be a boolean".to_string())),
+        _ => Err(ScimError::InvalidValue(
+            "active must be a boolean".to_string(),
+        )),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum UserFilter {
+    UserName(String),
+    ExternalId(String),
+}
+
+impl TryFrom<&str> for UserFilter {
+    type Error = ScimError;
+
+    /// Parses an `eq` filter of the user name or external id, the only ones sent when syncing users.
+    fn try_from(filter: &str) -> Result<Self, Self::Error> {
+        let mut parts = filter.trim().splitn(3, ' ');
+        let (Some(attribute), Some(operator), Some(value)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(ScimError::InvalidFilter);
+        };
+        if !operator.eq_ignore_ascii_case("eq") {
+            return Err(ScimError::InvalidFilter);
+        }
+        let value = value
+            .trim()
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .ok_or(ScimError::InvalidFilter)?
+            .replace("\\\"", "\"");
+
+        match attribute.to_lowercase().as_str() {
+            "username" => Ok(Self::UserName(normalize_name(&value))),
+            "externalid" => Ok(Self::ExternalId(value)),
+            _ => Err(ScimError::InvalidFilter),
+        }
+    }
+}
+
+struct ScimQuery;
+
+impl<'c> PgQuery<'c, ScimQuery> {
+    async fn get_user(&mut self, user_id: Uuid) -> Result<Option<QScimUser>, ScimError> {
+        let user = query_as!(
+            QScimUser,
+            r#"
+                SELECT users.id, login, username, external_id, deactivated_at IS NULL AS "active!"
+                FROM users
+                JOIN credentials ON credentials.user_id = users.id
+                WHERE users.id = $1
+            "#,
+            user_id,
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?;
+
+        Ok(user)
+    }
+
+    async fn find_users(
+        &mut self,
+        filter: Option<&UserFilter>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<(Vec<QScimUser>, i64), ScimError> {
+        let (login, external_id) = match filter {
+            Some(UserFilter::UserName(login)) => (Some(login), None),
+            Some(UserFilter::ExternalId(external_id)) => (None, Some(external_id)),
+            None => (None, None),
+        };
+
+        let total = query!(
+            r#"
+                SELECT COUNT(*) AS "total!"
+                FROM users
+                JOIN credentials ON credentials.user_id = users.id
+                WHERE (CAST($1 AS TEXT) IS NULL OR login = $1)
+                AND (CAST($2 AS TEXT) IS NULL OR external_id = $2)
+            "#,
+            login,
+            external_id,
+        )
+        .fetch_one(&mut *self.conn)
+        .await?
+        .total;
+
+        let users = query_as!(
+            QScimUser,
+            r#"
+                SELECT users.id, login, username, external_id, deactivated_at IS NULL AS "active!"
+                FROM users
+                JOIN credentials ON credentials.user_id = users.id
+                WHERE (CAST($1 AS TEXT) IS NULL OR login = $1)
+                AND (CAST($2 AS TEXT) IS NULL OR external_id = $2)
+                ORDER BY login
+                LIMIT $3 OFFSET $4
+            "#,
+            login,
+            external_id,
+            limit,
+            offset,
+        )
+        .fetch_all(&mut *self.conn)
+        .await?;
+
+        Ok((users, total))
+    }
+
+    async fn set_login(&mut self, user_id: Uuid, login: &str) -> Result<(), ScimError> {
+        query!(
+            r#"
+                UPDATE credentials SET login = $1
+                WHERE user_id = $2
+            "#,
+            login,
+            user_id,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn set_username(
+        &mut self,
+        user_id: Uuid,
+        username: &str,
+        tag: i32,
+    ) -> Result<(), ScimError> {
+        // Former handles stay reserved, so that mentions of them keep pointing to the user
+        query!(
+            r#"
+                INSERT INTO username_history (username, tag, user_id)
+                SELECT username, tag, id FROM users
+                WHERE id = $1
+            "#,
+            user_id,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+        query!(
+            r#"
+                UPDATE users SET username = $1, tag = $2
+                WHERE id = $3
+            "#,
+            username,
+            tag,
+            user_id,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_username_tags(&mut self, username: &str) -> Result<Vec<i32>, ScimError> {
+        let tags = query!(
+            r#"
+                SELECT tag AS "tag!" FROM users
+                WHERE username = $1
+                UNION
+                SELECT tag FROM username_history
+                WHERE username = $1
+            "#,
+            username,
+        )
+        .fetch_all(&mut *self.conn)
+        .await?
+        .into_iter()
+        .map(|user| user.tag)
+        .collect();
+
+        Ok(tags)
+    }
+
+    async fn set_external_id(&mut self, user_id: Uuid, external_id: &str) -> Result<(), ScimError> {
+        query!(
+            r#"
+                UPDATE users SET external_id = $1
+                WHERE id = $2
+            "#,
+            external_id,
+            user_id,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn set_password(
+        &mut self,
+        user_id: Uuid,
+        hashed_password: &str,
+    ) -> Result<(), ScimError> {
+        query!(
+            r#"
+                UPDATE credentials SET password = $1
+                WHERE user_id = $2
+            "#,
+            hashed_password,
+            user_id,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn reactivate(&mut self, user_id: Uuid) -> Result<(), ScimError> {
+        query!(
+            r#"
+                UPDATE users SET deactivated_at = NULL
+                WHERE id = $1
+            "#,
+            user_id,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Password nobody knows, given to users provisioned without one.
+fn generate_password() -> String {
+    thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(GENERATED_PASSWORD_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+pub async fn create_scim_user(
+    pool: &PgPool,
+    body: ScimUserRequest,
+    passwords: &PasswordSettings,
+    usernames: &UsernameSettings,
+    now: OffsetDateTime,
+) -> Result<ScimUser, ScimError> {
+    let display_name = body
+        .display_name
+        .clone()
+        .unwrap_or_else(|| body.user_name.clone());
+    let password = body.password.unwrap_or_else(generate_password);
+
+    let mut transaction = pool.begin().await?;
+    let user_id = try_register_user(
+        &mut transaction,
+        body.user_name.trim(),
+        SecretString::new(password),
+        &display_name,
+        passwords,
+        usernames,
+        None,
+    )
+    .await?;
+
+    let mut q = PgQuery::new(ScimQuery, &mut transaction);
+    if let Some(external_id) = &body.external_id {
+        q.set_external_id(user_id, external_id).await?;
+    }
+    if !body.active {
+        deactivate_user(q.conn, user_id, now).await?;
+    }
+    let user = q.get_user(user_id).await?.ok_or(ScimError::NotFound)?;
+    transaction.commit().await?;
+    debug!("Provisioned user {user_id}");
+
+    Ok(user.into())
+}
+
+pub async fn get_scim_user(pool: &PgPool, user_id: Uuid) -> Result<ScimUser, ScimError> {
+    let mut conn = pool.acquire().await?;
+    let user = PgQuery::new(ScimQuery, &mut conn)
+        .get_user(user_id)
+        .await?
+        .ok_or(ScimError::NotFound)?;
+
+    Ok(user.into())
+}
+
+pub async fn list_scim_users(
+    pool: &PgPool,
+    query: ScimListQuery,
+) -> Result<ScimListResponse, ScimError> {
+    let filter = query
+        .filter
+        .as_deref()
+        .map(UserFilter::try_from)
+        .transpose()?;
+    let start_index = query.start_index.unwrap_or(1).max(1);
+    let count = query.count.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+
+    let mut conn = pool.acquire().await?;
+    let (users, total) = PgQuery::new(ScimQuery, &mut conn)
+        .find_users(filter.as_ref(), (start_index - 1).into(), count.into())
+        .await?;
+
+    Ok(ScimListResponse {
+        schemas: vec![LIST_RESPONSE_SCHEMA.to_string()],
+        total_results: total,
+        start_index,
+        items_per_page: users.len() as u32,
+        resources: users.into_iter().map(ScimUser::from).collect(),
+    })
+}
+
+/// Applies the changes, revoking tokens of the user when their password changes.
+pub async fn update_scim_user(
+    pool: &PgPool,
+    user_id: Uuid,
+    changes: UserChanges,
+    passwords: &PasswordSettings,
+    usernames: &UsernameSettings,
+    now: OffsetDateTime,
+) -> Result<ScimUser, ScimError> {
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(ScimQuery, &mut transaction);
+    let user = q.get_user(user_id).await?.ok_or(ScimError::NotFound)?;
+
+    let login = changes
+        .user_name
+        .map_or_else(|| user.login.clone(), |name| normalize_name(name.trim()));
+    let username = changes
+        .display_name
+        .map_or_else(|| user.username.clone(), |name| normalize_name(&name));
+    if login != user.login || username != user.username {
+        validate_usernames(&login, &username, usernames).map_err(AuthError::from)?;
+    }
+    if login != user.login {
+        q.set_login(user_id, &login).await?;
+    }
+    if username != user.username {
+        let tag = random_username_tag(q.get_username_tags(&username).await?.into_iter().collect())
+            .ok_or(AuthError::TagOverflow)?;
+        q.set_username(user_id, &username, tag).await?;
+    }
+    if let Some(external_id) = &changes.external_id {
+        q.set_external_id(user_id, external_id).await?;
+    }
+    if let Some(password) = changes.password {
+        if !pass_is_strong(&password, &[login.as_str()]) {
+            return Err(AuthError::WeakPassword.into());
+        }
+        q.set_password(user_id, &hash_pass(password, passwords)?)
+            .await?;
+        revoke_user_tokens(q.conn, user_id).await?;
+    }
+    match changes.active {
+        Some(false) if user.active => {
+            deactivate_user(q.conn, user_id, now).await?;
+        }
+        Some(true) if !user.active => q.reactivate(user_id).await?,
+        _ => (),
+    }
+
+    let user = q.get_user(user_id).await?.ok_or(ScimError::NotFound)?;
+    transaction.commit().await?;
+    debug!("Updated provisioned user {user_id}");
+
+    Ok(user.into())
+}
+
+#[cfg(test)]
+mod scim_tests {
+    use serde_json::json;
+
+    use super::*;
+    use bimetable_domain::api::scim::ScimPatchOperation;
+
+    #[test]
+    fn parses_user_filters() {
+        assert_eq!(
+            UserFilter::try_from(r#"userName eq "macmac""#).unwrap(),
+            UserFilter::UserName("macmac".to_string())
+        );
+        assert_eq!(
+            UserFilter::try_from(r#"externalId EQ "id with \"quotes\"""#).unwrap(),
+            UserFilter::ExternalId(r#"id with "quotes""#.to_string())
+        );
+        assert!(UserFilter::try_from(r#"userName co "mac""#).is_err());
+        assert!(UserFilter::try_from(r#"emails eq "mac@example.com""#).is_err());
+        assert!(UserFilter::try_from("userName eq macmac").is_err());
+    }
+
+    #[test]
+    fn reads_patch_operations() {
+        let patch = ScimPatch {
+            operations: vec![
+                ScimPatchOperation {
+                    op: "Replace".to_string(),
+                    path: Some("active".to_string()),
+                    value: json!("False"),
+                },
+                ScimPatchOperation {
+                    op: "add".to_string(),
+                    path: None,
+                    value: json!({ "displayName": "Adimac", "nickName": "mac" }),
+                },
+            ],
+        };
+
+        assert_eq!(
+            UserChanges::try_from(patch).unwrap(),
+            UserChanges {
+                display_name: Some("Adimac".to_string()),
+                active: Some(false),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_removing_attributes() {
+        let patch = ScimPatch {
+            operations: vec![ScimPatchOperation {
+                op: "remove".to_string(),
+                path: Some("externalId".to_string()),
+                value: Value::Null,
+            }],
+        };
+
+        assert!(UserChanges::try_from(patch).is_err());
+    }
+}
@@ -0,0 +1,7 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SearchError {
+    #[error(transparent)]
+    Unexpected(#[from] anyhow::Error),
+}
@@ -0,0 +1,335 @@
+pub mod errors;
+
+use crate::modules::database::PgQuery;
+use crate::utils::search::errors::SearchError;
+use bimetable_domain::api::events::{Event, EventFilter, EventPayload};
+use bimetable_domain::api::search::{
+    EventFacets, SearchEvents, SearchMode, SearchUsers, SearchUsersResult,
+};
+use bimetable_domain::app_errors::DefaultContext;
+use bimetable_domain::events::models::EventPrivileges;
+use bimetable_domain::events::models::{RecurrenceRule, RecurrenceRuleKind};
+use sqlx::{query, query_as, PgPool};
+use time::OffsetDateTime;
+use tracing::trace;
+use uuid::Uuid;
+
+pub struct Search {
+    pub text: String,
+}
+
+/// Users who can still be invited to the event by the searching user.
+///
+/// Participants are left out only for users taking part in the event, so that strangers can't tell them apart.
+#[derive(Debug, Clone, Copy)]
+pub struct Invitees {
+    pub event_id: Uuid,
+    pub user_id: Uuid,
+}
+
+impl<'c> PgQuery<'c, Search> {
+    pub async fn search_users(
+        &mut self,
+        tag: Option<i32>,
+        invitees: Option<Invitees>,
+    ) -> Result<Vec<QueryUser>, SearchError> {
+        let res = query_as!(
+            QueryUser,
+            r#"
+                WITH participants AS (
+                    SELECT user_id FROM user_events WHERE event_id = $3
+                    UNION SELECT owner_id FROM events WHERE id = $3
+                )
+                SELECT id, username, tag FROM users
+                WHERE (
+                    (LOWER(username) LIKE CONCAT(LOWER(CAST($1 AS TEXT)), '%') AND (CAST($2 AS INT) IS NULL OR tag = $2))
+                    OR id IN (SELECT user_id FROM username_history WHERE LOWER(username) = LOWER($1) AND tag = $2)
+                )
+                AND deactivated_at IS NULL
+                AND (CAST($3 AS UUID) IS NULL OR id <> $4)
+                AND NOT (id IN (SELECT user_id FROM participants) AND $4 IN (SELECT user_id FROM participants))
+            "#,
+            self.payload.text.to_lowercase(),
+            tag,
+            invitees.map(|i| i.event_id),
+            invitees.map(|i| i.user_id),
+        )
+        .fetch_all(&mut *self.conn)
+        .await
+        .dc()?;
+
+        let tag_log = match tag {
+            Some(t) => format!(" and with tag {}", t),
+            None => "".to_string(),
+        };
+
+        if res.is_empty() {
+            trace!(
+                "Found no users with usernames starting with {}{}",
+                self.payload.text,
+                tag_log
+            );
+        } else {
+            trace!(
+                "Found {} users with usernames starting with {}{}",
+                res.len(),
+                self.payload.text,
+                tag_log
+            );
+        }
+
+        Ok(res)
+    }
+
+    pub async fn search_users_fuzzy(
+        &mut self,
+        tag: Option<i32>,
+        invitees: Option<Invitees>,
+    ) -> Result<Vec<QueryUser>, SearchError> {
+        let text = self.payload.text.to_lowercase();
+        let res = query_as!(
+            QueryUser,
+            r#"
+                WITH participants AS (
+                    SELECT user_id FROM user_events WHERE event_id = $4
+                    UNION SELECT owner_id FROM events WHERE id = $4
+                )
+                SELECT id, username, tag FROM users
+                WHERE (
+                    ((LOWER(username) LIKE CONCAT('%', CAST($1 AS TEXT), '%') OR LOWER(username) % $2) AND (CAST($3 AS INT) IS NULL OR tag = $3))
+                    OR id IN (SELECT user_id FROM username_history WHERE LOWER(username) = $2 AND tag = $3)
+                )
+                AND deactivated_at IS NULL
+                AND (CAST($4 AS UUID) IS NULL OR id <> $5)
+                AND NOT (id IN (SELECT user_id FROM participants) AND $5 IN (SELECT user_id FROM participants))
+                ORDER BY similarity(LOWER(username), $2) DESC, username ASC
+            "#,
+            escape_like(&text),
+            text,
+            tag,
+            invitees.map(|i| i.event_id),
+            invitees.map(|i| i.user_id),
+        )
+        .fetch_all(&mut *self.conn)
+        .await
+        .dc()?;
+
+        trace!(
+            "Found {} users with usernames similar to {}",
+            res.len(),
+            self.payload.text
+        );
+
+        Ok(res)
+    }
+
+    /// Gets a page of events matching the search, along with the total count and facets.
+    pub async fn search_events(
+        &mut self,
+        user_id: Uuid,
+        filter: EventFilter,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<QueryEventsPage, SearchError> {
+        let is_owned = match filter {
+            EventFilter::All => None,
+            EventFilter::Owned => Some(true),
+            EventFilter::Shared => Some(false),
+        };
+
+        let rows = query!(
+            r#"
+                WITH matches AS (
+                    SELECT events.id, events.name, events.description, events.starts_at, COALESCE(until, events.ends_at) AS entries_end,
+                    recurrence, until, count, interval,
+                    events.owner_id = $1 OR COALESCE(user_events.is_owner, false) AS is_owned, COALESCE(user_events.can_edit, true) AS can_edit
+                    FROM events
+                    LEFT JOIN user_events ON user_events.event_id = events.id AND user_events.user_id = $1
+                    LEFT JOIN recurrence_rules ON recurrence_rules.event_id = events.id
+                    WHERE (events.owner_id = $1 OR user_events.user_id IS NOT NULL)
+                    AND events.deleted_at IS NULL
+                    AND LOWER(events.name) LIKE CONCAT(LOWER(CAST($2 AS TEXT)), '%')
+                ), counted AS (
+                    SELECT *,
+                    COUNT(*) FILTER (WHERE CAST($3 AS BOOL) IS NULL OR is_owned = $3) OVER () AS total,
+                    COUNT(*) FILTER (WHERE is_owned) OVER () AS owned,
+                    COUNT(*) FILTER (WHERE NOT is_owned) OVER () AS shared,
+                    COUNT(*) FILTER (WHERE recurrence IS NOT NULL) OVER () AS recurring,
+                    COUNT(*) FILTER (WHERE recurrence IS NULL) OVER () AS one_off
+                    FROM matches
+                )
+                SELECT facets.total AS "total!", facets.owned AS "owned!", facets.shared AS "shared!", facets.recurring AS "recurring!", facets.one_off AS "one_off!",
+                page.id AS "id?", page.name AS "name?", page.description, page.starts_at AS "starts_at?", page.entries_end, page.recurrence AS "recurrence: Option<sqlx::types::Json<RecurrenceRuleKind>>", page.until, page.count, page.interval AS "interval: Option<i32>", page.is_owned AS "is_owned?", page.can_edit AS "can_edit?"
+                FROM (SELECT total, owned, shared, recurring, one_off FROM counted LIMIT 1) AS facets
+                LEFT JOIN (
+                    SELECT * FROM counted
+                    WHERE CAST($3 AS BOOL) IS NULL OR is_owned = $3
+                    ORDER BY starts_at ASC
+                    LIMIT $4 OFFSET $5
+                ) AS page ON true
+                ORDER BY page.starts_at ASC
+            "#,
+            user_id,
+            self.payload.text.to_lowercase(),
+            is_owned,
+            limit.map(i64::from),
+            offset.map(i64::from),
+        )
+        .fetch_all(&mut *self.conn)
+        .await
+        .dc()?;
+
+        let Some(first) = rows.first() else {
+            trace!("No events with names starting with {}", self.payload.text);
+            return Ok(QueryEventsPage::default());
+        };
+
+        let total = first.total;
+        let facets = EventFacets {
+            owned: first.owned,
+            shared: first.shared,
+            recurring: first.recurring,
+            one_off: first.one_off,
+        };
+
+        let events: Vec<QueryEvent> = rows
+            .into_iter()
+            .filter_map(|event| {
+                Some(QueryEvent {
+                    id: event.id?,
+                    name: event.name?,
+                    description: event.description,
+                    entries_start: event.starts_at?,
+                    entries_end: event.entries_end,
+                    recurrence_rule: RecurrenceRule::from_db_data(
+                        event.recurrence.map(|kind| kind.0),
+                        event.until,
+                        event.count,
+                        event.interval,
+                    ),
+                    privileges: if event.is_owned? {
+                        EventPrivileges::Owned
+                    } else {
+                        EventPrivileges::Shared {
+                            can_edit: event.can_edit?,
+                        }
+                    },
+                })
+            })
+            .collect();
+
+        trace!(
+            "Got {} of {total} events with names starting with {}",
+            events.len(),
+            self.payload.text
+        );
+
+        Ok(QueryEventsPage {
+            events,
+            total,
+            facets,
+        })
+    }
+}
+
+impl Search {
+    pub fn new(text: String) -> Self {
+        Self { text }
+    }
+}
+
+pub async fn get_users(
+    pool: &PgPool,
+    user_id: Uuid,
+    search: SearchUsers,
+) -> Result<Vec<QueryUser>, SearchError> {
+    let mut conn = pool.acquire().await.dc()?;
+    let invitees = search
+        .event_id
+        .map(|event_id| Invitees { event_id, user_id });
+    let mut q = PgQuery::new(Search::new(search.text), &mut conn);
+    match search.mode {
+        SearchMode::Prefix => q.search_users(search.tag, invitees).await,
+        SearchMode::Fuzzy => q.search_users_fuzzy(search.tag, invitees).await,
+    }
+}
+
+pub async fn search_many_events(
+    pool: &PgPool,
+    user_id: Uuid,
+    search: SearchEvents,
+) -> Result<QueryEventsPage, SearchError> {
+    let mut conn = pool.acquire().await.dc()?;
+    let mut q = PgQuery::new(Search::new(search.text), &mut conn);
+
+    q.search_events(user_id, search.filter, search.limit, search.offset)
+        .await
+}
+
+/// Escapes the `LIKE` wildcards, so that the text is matched literally.
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[derive(Debug, PartialEq)]
+pub struct QueryUser {
+    pub id: Uuid,
+    pub username: String,
+    pub tag: i32,
+}
+
+#[derive(Debug, Default)]
+pub struct QueryEventsPage {
+    pub events: Vec<QueryEvent>,
+    pub total: i64,
+    pub facets: EventFacets,
+}
+
+#[derive(Debug)]
+pub struct QueryEvent {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub entries_start: OffsetDateTime,
+    pub entries_end: Option<OffsetDateTime>,
+    pub recurrence_rule: Option<RecurrenceRule>,
+    pub privileges: EventPrivileges,
+}
+
+impl From<QueryUser> for SearchUsersResult {
+    fn from(val: QueryUser) -> Self {
+        Self {
+            id: val.id,
+            username: val.username,
+            tag: val.tag,
+        }
+    }
+}
+
+impl From<QueryEvent> for Event {
+    fn from(val: QueryEvent) -> Self {
+        Event::new(
+            val.privileges,
+            EventPayload {
+                name: val.name,
+                description: val.description,
+            },
+            val.recurrence_rule,
+            val.entries_start,
+            val.entries_end,
+        )
+    }
+}
+
+#[cfg(test)]
+mod search_tests {
+    use super::*;
+
+    #[test]
+    fn escape_like_test() {
+        assert_eq!(escape_like("pkb-pmj"), "pkb-pmj");
+        assert_eq!(escape_like("50%_off\\"), "50\\%\\_off\\\\");
+    }
+}
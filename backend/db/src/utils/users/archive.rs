@@ -0,0 +1,553 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Write};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::{query, PgConnection, PgPool};
+use time::serde::iso8601;
+use time::{Duration, OffsetDateTime};
+use tracing::{debug, trace, warn};
+use uuid::Uuid;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::modules::database::PgQuery;
+use crate::modules::jobs::{enqueue, Job, JobHandler, NewJob};
+use crate::modules::storage::{Blobs, Storage};
+use crate::utils::events::EventQuery;
+use crate::utils::reminders::get_user_reminders;
+use crate::utils::users::errors::UserError;
+use crate::utils::users::quiet_hours::get_user_quiet_hours;
+use bimetable_domain::api::events::{Event, EventExceptions, Events, RangedOverride};
+use bimetable_domain::api::reminders::Reminder;
+use bimetable_domain::api::users::{ArchiveStatus, QuietHours, UserArchive};
+use bimetable_domain::events::ics::events_to_ics;
+use bimetable_domain::events::models::DayOfWeek;
+use bimetable_domain::limits::RepetitionLimit;
+
+pub const BUILD_ARCHIVE_JOB: &str = "users.build_archive";
+
+/// How far ahead entries of recurring events are written to the calendar
+const CALENDAR_HORIZON: Duration = Duration::days(365);
+const ARCHIVE_LINK_LIFETIME: Duration = Duration::minutes(15);
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BuildArchive {
+    archive_id: Uuid,
+}
+
+/// Contents of `data.json` in the archive.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveData {
+    #[serde(with = "iso8601")]
+    pub exported_at: OffsetDateTime,
+    pub profile: ArchivedProfile,
+    /// Events the user owns or takes part in, with their overrides and pauses
+    pub events: Vec<ArchivedEvent>,
+    pub memberships: Vec<ArchivedMembership>,
+    /// Invitations sent or received by the user
+    pub invitations: Vec<ArchivedInvitation>,
+    pub reminders: Vec<Reminder>,
+    pub quiet_hours: Vec<QuietHours>,
+    /// Users who may see when the user is busy
+    pub busy_visible_to: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedProfile {
+    pub id: Uuid,
+    pub username: String,
+    pub tag: i32,
+    pub login: Option<String>,
+    pub is_admin: bool,
+    pub week_start: DayOfWeek,
+    pub invite_can_edit: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedEvent {
+    pub id: Uuid,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedMembership {
+    pub event_id: Uuid,
+    pub can_edit: bool,
+    pub is_co_owner: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedInvitation {
+    pub event_id: Uuid,
+    pub sender_id: Uuid,
+    pub receiver_id: Uuid,
+    pub can_edit: bool,
+    #[serde(with = "iso8601::option")]
+    pub seen_at: Option<OffsetDateTime>,
+}
+
+#[derive(Debug)]
+struct QArchive {
+    id: Uuid,
+    storage_key: Option<String>,
+    requested_at: OffsetDateTime,
+    completed_at: Option<OffsetDateTime>,
+    failed_at: Option<OffsetDateTime>,
+}
+
+impl QArchive {
+    fn status(&self) -> ArchiveStatus {
+        match (self.completed_at, self.failed_at) {
+            (Some(_), _) => ArchiveStatus::Ready,
+            (None, Some(_)) => ArchiveStatus::Failed,
+            (None, None) => ArchiveStatus::Pending,
+        }
+    }
+
+    fn to_archive(&self) -> UserArchive {
+        UserArchive {
+            id: self.id,
+            status: self.status(),
+            requested_at: self.requested_at,
+            url: None,
+            expires_at: None,
+        }
+    }
+}
+
+pub struct ArchiveQuery {
+    user_id: Uuid,
+}
+
+impl<'c> PgQuery<'c, ArchiveQuery> {
+    async fn create_archive(&mut self) -> Result<QArchive, UserError> {
+        let archive = query!(
+            r#"
+                INSERT INTO user_archives (user_id)
+                VALUES ($1)
+                RETURNING id, requested_at
+            "#,
+            self.payload.user_id,
+        )
+        .fetch_one(&mut *self.conn)
+        .await?;
+
+        Ok(QArchive {
+            id: archive.id,
+            storage_key: None,
+            requested_at: archive.requested_at,
+            completed_at: None,
+            failed_at: None,
+        })
+    }
+
+    async fn get_pending_archive(&mut self) -> Result<Option<QArchive>, UserError> {
+        let archive = query!(
+            r#"
+                SELECT id, requested_at FROM user_archives
+                WHERE user_id = $1 AND completed_at IS NULL AND failed_at IS NULL
+                ORDER BY requested_at DESC
+                LIMIT 1
+            "#,
+            self.payload.user_id,
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?
+        .map(|archive| QArchive {
+            id: archive.id,
+            storage_key: None,
+            requested_at: archive.requested_at,
+            completed_at: None,
+            failed_at: None,
+        });
+
+        Ok(archive)
+    }
+
+    async fn get_archive(&mut self, archive_id: Uuid) -> Result<Option<QArchive>, UserError> {
+        let archive = query!(
+            r#"
+                SELECT id, storage_key, requested_at, completed_at, failed_at
+                FROM user_archives
+                WHERE id = $1 AND user_id = $2
+            "#,
+            archive_id,
+            self.payload.user_id,
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?
+        .map(|archive| QArchive {
+            id: archive.id,
+            storage_key: archive.storage_key,
+            requested_at: archive.requested_at,
+            completed_at: archive.completed_at,
+            failed_at: archive.failed_at,
+        });
+
+        Ok(archive)
+    }
+
+    async fn get_profile(&mut self) -> Result<ArchivedProfile, UserError> {
+        let user = query!(
+            r#"
+                SELECT id, username, tag, is_admin, week_start, invite_can_edit, login AS "login?"
+                FROM users
+                LEFT JOIN credentials ON credentials.user_id = users.id
+                WHERE id = $1
+            "#,
+            self.payload.user_id,
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?
+        .ok_or(UserError::NotFound)?;
+
+        Ok(ArchivedProfile {
+            id: user.id,
+            username: user.username,
+            tag: user.tag,
+            login: user.login,
+            is_admin: user.is_admin,
+            week_start: DayOfWeek::from_days_from_monday(user.week_start).unwrap_or_default(),
+            invite_can_edit: user.invite_can_edit,
+        })
+    }
+
+    /// Ids of events the user owns or takes part in, except the deleted ones.
+    async fn get_event_ids(&mut self) -> Result<Vec<Uuid>, UserError> {
+        let event_ids = query!(
+            r#"
+                SELECT id AS "id!" FROM events
+                WHERE owner_id = $1 AND deleted_at IS NULL
+                UNION
+                SELECT event_id FROM user_events
+                JOIN events ON events.id = user_events.event_id
+                WHERE user_id = $1 AND deleted_at IS NULL
+            "#,
+            self.payload.user_id,
+        )
+        .fetch_all(&mut *self.conn)
+        .await?
+        .into_iter()
+        .map(|event| event.id)
+        .collect();
+
+        Ok(event_ids)
+    }
+
+    async fn get_memberships(&mut self) -> Result<Vec<ArchivedMembership>, UserError> {
+        let memberships = query!(
+            r#"
+                SELECT event_id, can_edit, is_owner FROM user_events
+                WHERE user_id = $1
+                ORDER BY event_id
+            "#,
+            self.payload.user_id,
+        )
+        .fetch_all(&mut *self.conn)
+        .await?
+        .into_iter()
+        .map(|membership| ArchivedMembership {
+            event_id: membership.event_id,
+            can_edit: membership.can_edit,
+            is_co_owner: membership.is_owner,
+        })
+        .collect();
+
+        Ok(memberships)
+    }
+
+    async fn get_invitations(&mut self) -> Result<Vec<ArchivedInvitation>, UserError> {
+        let invitations = query!(
+            r#"
+                SELECT event_id, sender_id, receiver_id, can_edit, seen_at
+                FROM user_event_invitations
+                WHERE sender_id = $1 OR receiver_id = $1
+                ORDER BY event_id, sender_id, receiver_id
+            "#,
+            self.payload.user_id,
+        )
+        .fetch_all(&mut *self.conn)
+        .await?
+        .into_iter()
+        .map(|invitation| ArchivedInvitation {
+            event_id: invitation.event_id,
+            sender_id: invitation.sender_id,
+            receiver_id: invitation.receiver_id,
+            can_edit: invitation.can_edit,
+            seen_at: invitation.seen_at,
+        })
+        .collect();
+
+        Ok(invitations)
+    }
+
+    async fn get_busy_viewers(&mut self) -> Result<Vec<Uuid>, UserError> {
+        let viewers = query!(
+            r#"
+                SELECT viewer_id FROM busy_visibility
+                WHERE owner_id = $1
+                ORDER BY viewer_id
+            "#,
+            self.payload.user_id,
+        )
+        .fetch_all(&mut *self.conn)
+        .await?
+        .into_iter()
+        .map(|visibility| visibility.viewer_id)
+        .collect();
+
+        Ok(viewers)
+    }
+}
+
+/// Marks the archive as built, `false` when it was removed along with its user in the meantime.
+async fn complete_archive(pool: &PgPool, archive_id: Uuid, key: &str) -> Result<bool, UserError> {
+    let affected = query!(
+        r#"
+            UPDATE user_archives
+            SET storage_key = $2, completed_at = now()
+            WHERE id = $1
+        "#,
+        archive_id,
+        key,
+    )
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    Ok(affected == 1)
+}
+
+async fn fail_archive(pool: &PgPool, archive_id: Uuid) -> Result<(), UserError> {
+    query!(
+        r#"
+            UPDATE user_archives SET failed_at = now()
+            WHERE id = $1
+        "#,
+        archive_id,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Gathers the data of the user, along with their calendar.
+async fn collect_data(
+    pool: &PgPool,
+    user_id: Uuid,
+    now: OffsetDateTime,
+    repetition_limit: RepetitionLimit,
+) -> anyhow::Result<(ArchiveData, Events)> {
+    let mut conn = pool.acquire().await?;
+    let mut q = PgQuery::new(ArchiveQuery { user_id }, &mut conn);
+    let profile = q.get_profile().await?;
+    let mut event_ids = q.get_event_ids().await?;
+    event_ids.sort();
+    let memberships = q.get_memberships().await?;
+    let invitations = q.get_invitations().await?;
+    let busy_visible_to = q.get_busy_viewers().await?;
+
+    let mut q = PgQuery::new(EventQuery::new(user_id), &mut conn);
+    let mut overrides: HashMap<Uuid, Vec<RangedOverride>> = HashMap::new();
+    for ovr in q.get_overrides(event_ids.clone(), false).await? {
+        overrides
+            .entry(ovr.event_id)
+            .or_default()
+            .push(RangedOverride::from(ovr));
+    }
+    let mut pauses = q.get_pauses(event_ids.clone()).await?;
+
+    let mut events = Vec::new();
+    let mut calendar = Events::new(HashMap::new(), Vec::new());
+    for event_id in event_ids {
+        let Some(mut event) = q.get_event(event_id).await? else {
+            continue;
+        };
+        event.exceptions = Some(EventExceptions {
+            overrides: overrides.remove(&event_id).unwrap_or_default(),
+            pauses: pauses.remove(&event_id).unwrap_or_default(),
+        });
+        events.push(ArchivedEvent {
+            id: event_id,
+            event,
+        });
+        calendar.append(
+            q.get_event_entries(event_id, None, now + CALENDAR_HORIZON, repetition_limit)
+                .await?,
+        );
+    }
+
+    let data = ArchiveData {
+        exported_at: now,
+        profile,
+        events,
+        memberships,
+        invitations,
+        reminders: get_user_reminders(pool, user_id).await?,
+        quiet_hours: get_user_quiet_hours(pool, user_id).await?,
+        busy_visible_to,
+    };
+    Ok((data, calendar))
+}
+
+/// Zips `data.json` and `calendar.ics` of the user.
+pub async fn build_archive(
+    pool: &PgPool,
+    user_id: Uuid,
+    repetition_limit: RepetitionLimit,
+) -> anyhow::Result<Vec<u8>> {
+    let now = OffsetDateTime::now_utc();
+    let (data, calendar) = collect_data(pool, user_id, now, repetition_limit).await?;
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file("data.json", options)?;
+    zip.write_all(&serde_json::to_vec_pretty(&data)?)?;
+    zip.start_file("calendar.ics", options)?;
+    zip.write_all(events_to_ics(&calendar, now).as_bytes())?;
+
+    Ok(zip.finish()?.into_inner())
+}
+
+/// Requests an archive of the user's data, returning the pending one when there is one already.
+pub async fn request_archive(pool: &PgPool, user_id: Uuid) -> Result<UserArchive, UserError> {
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(ArchiveQuery { user_id }, &mut transaction);
+    if let Some(archive) = q.get_pending_archive().await? {
+        trace!("Archive {} is already pending", archive.id);
+        return Ok(archive.to_archive());
+    }
+
+    let archive = q.create_archive().await?;
+    enqueue(
+        &mut transaction,
+        NewJob::new(
+            BUILD_ARCHIVE_JOB,
+            BuildArchive {
+                archive_id: archive.id,
+            },
+        )?,
+    )
+    .await?;
+    transaction.commit().await?;
+    debug!("Requested archive {} of the user {user_id}", archive.id);
+
+    Ok(archive.to_archive())
+}
+
+/// Gets the status of an archive of the user, with a fresh link once it's ready.
+pub async fn get_archive(
+    pool: &PgPool,
+    storage: &dyn Storage,
+    user_id: Uuid,
+    archive_id: Uuid,
+) -> Result<UserArchive, UserError> {
+    let mut conn = pool.acquire().await?;
+    let q_archive = PgQuery::new(ArchiveQuery { user_id }, &mut conn)
+        .get_archive(archive_id)
+        .await?
+        .ok_or(UserError::ArchiveNotFound)?;
+
+    let mut archive = q_archive.to_archive();
+    if let (ArchiveStatus::Ready, Some(key)) = (archive.status, &q_archive.storage_key) {
+        let expires_at = OffsetDateTime::now_utc() + ARCHIVE_LINK_LIFETIME;
+        archive.url = Some(storage.signed_url(key, expires_at)?);
+        archive.expires_at = Some(expires_at);
+    }
+    Ok(archive)
+}
+
+/// Storage keys of the archives of the user.
+pub async fn get_archive_keys(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+) -> Result<Vec<String>, UserError> {
+    let keys = query!(
+        r#"
+            SELECT storage_key AS "storage_key!" FROM user_archives
+            WHERE user_id = $1 AND storage_key IS NOT NULL
+        "#,
+        user_id,
+    )
+    .fetch_all(conn)
+    .await?
+    .into_iter()
+    .map(|archive| archive.storage_key)
+    .collect();
+
+    Ok(keys)
+}
+
+/// Job handler building requested archives and putting them in the storage.
+pub struct ArchiveHandler {
+    storage: Blobs,
+    repetition_limit: RepetitionLimit,
+}
+
+impl ArchiveHandler {
+    pub fn new(storage: Blobs) -> Self {
+        Self {
+            storage,
+            repetition_limit: RepetitionLimit::default(),
+        }
+    }
+
+    /// Expands the calendars of the archives with the configured repetition limit.
+    pub fn with_repetition_limit(mut self, repetition_limit: RepetitionLimit) -> Self {
+        self.repetition_limit = repetition_limit;
+        self
+    }
+
+    async fn store(&self, pool: &PgPool, archive_id: Uuid) -> anyhow::Result<()> {
+        let user_id = query!(
+            r#"
+                SELECT user_id FROM user_archives
+                WHERE id = $1
+            "#,
+            archive_id,
+        )
+        .fetch_optional(pool)
+        .await?
+        .map(|archive| archive.user_id);
+        let Some(user_id) = user_id else {
+            debug!("Skipping archive {archive_id} of a deleted user");
+            return Ok(());
+        };
+
+        let body = build_archive(pool, user_id, self.repetition_limit).await?;
+        let key = format!("archives/{user_id}/{archive_id}.zip");
+        self.storage.put(&key, body).await?;
+        if !complete_archive(pool, archive_id, &key).await? {
+            self.storage.delete(&key).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl JobHandler for ArchiveHandler {
+    fn kind(&self) -> &'static str {
+        BUILD_ARCHIVE_JOB
+    }
+
+    async fn handle(&self, job: &Job, pool: &PgPool) -> anyhow::Result<()> {
+        let BuildArchive { archive_id } = job.payload()?;
+        let result = self.store(pool, archive_id).await;
+        if result.is_err() && job.attempts >= job.max_attempts {
+            warn!("Giving up on archive {archive_id}");
+            fail_archive(pool, archive_id).await?;
+        }
+
+        result
+    }
+}
@@ -0,0 +1,22 @@
+use bimetable_domain::validation::ValidateContentError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum UserError {
+    #[error("User not found")]
+    NotFound,
+    #[error("Archive not found")]
+    ArchiveNotFound,
+    #[error("Quiet hours not found")]
+    QuietHoursNotFound,
+    #[error("User data rejected with validation")]
+    InvalidData(#[from] ValidateContentError),
+    #[error(transparent)]
+    Unexpected(#[from] anyhow::Error),
+}
+
+impl From<sqlx::Error> for UserError {
+    fn from(e: sqlx::Error) -> Self {
+        Self::Unexpected(anyhow::Error::from(e))
+    }
+}
@@ -0,0 +1,503 @@
+pub mod archive;
+pub mod errors;
+pub mod quiet_hours;
+
+use crate::modules::database::PgQuery;
+use crate::modules::outbox::{self, Topic};
+use crate::modules::storage::Storage;
+use crate::utils::users::archive::get_archive_keys;
+use crate::utils::users::errors::UserError;
+use bimetable_domain::api::users::{UpdateUserPreferences, UserHandle, UserPreferences};
+use bimetable_domain::events::models::DayOfWeek;
+use serde_json::json;
+use sqlx::{query, PgPool};
+use tracing::{debug, error, trace};
+use uuid::Uuid;
+
+pub struct UserQuery {
+    pub user_id: Uuid,
+}
+
+impl UserQuery {
+    pub fn new(user_id: Uuid) -> Self {
+        Self { user_id }
+    }
+}
+
+impl<'c> PgQuery<'c, UserQuery> {
+    pub async fn get_preferences(&mut self) -> Result<UserPreferences, UserError> {
+        let user = query!(
+            r#"
+                SELECT week_start, invite_can_edit FROM users
+                WHERE id = $1
+            "#,
+            self.payload.user_id,
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?
+        .ok_or(UserError::NotFound)?;
+
+        Ok(UserPreferences {
+            week_start: DayOfWeek::from_days_from_monday(user.week_start).unwrap_or_default(),
+            invite_can_edit: user.invite_can_edit,
+        })
+    }
+
+    pub async fn get_username(&mut self) -> Result<String, UserError> {
+        let user = query!(
+            r#"
+                SELECT username FROM users
+                WHERE id = $1
+            "#,
+            self.payload.user_id,
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?
+        .ok_or(UserError::NotFound)?;
+
+        Ok(user.username)
+    }
+
+    /// Gets the current handle followed by the former ones, newest first.
+    pub async fn get_handles(&mut self) -> Result<Vec<UserHandle>, UserError> {
+        let current = query!(
+            r#"
+                SELECT username, tag FROM users
+                WHERE id = $1
+            "#,
+            self.payload.user_id,
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?
+        .ok_or(UserError::NotFound)?;
+        let former = query!(
+            r#"
+                SELECT username, tag, replaced_at FROM username_history
+                WHERE user_id = $1
+                ORDER BY replaced_at DESC
+            "#,
+            self.payload.user_id,
+        )
+        .fetch_all(&mut *self.conn)
+        .await?;
+
+        let mut handles = vec![UserHandle {
+            username: current.username,
+            tag: current.tag,
+            replaced_at: None,
+        }];
+        handles.extend(former.into_iter().map(|handle| UserHandle {
+            username: handle.username,
+            tag: handle.tag,
+            replaced_at: Some(handle.replaced_at),
+        }));
+
+        Ok(handles)
+    }
+
+    pub async fn is_admin(&mut self) -> Result<bool, UserError> {
+        let is_admin = query!(
+            r#"
+                SELECT is_admin FROM users
+                WHERE id = $1
+            "#,
+            self.payload.user_id,
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?
+        .is_some_and(|user| user.is_admin);
+
+        Ok(is_admin)
+    }
+
+    pub async fn exists(&mut self, user_id: Uuid) -> Result<bool, UserError> {
+        let exists = query!(
+            r#"
+                SELECT id FROM users
+                WHERE id = $1
+            "#,
+            user_id,
+        )
+        .fetch_optional(&mut *self.conn)
+        .await?
+        .is_some();
+
+        Ok(exists)
+    }
+
+    pub async fn grant_busy_visibility(&mut self, viewer_id: Uuid) -> Result<(), UserError> {
+        query!(
+            r#"
+                INSERT INTO busy_visibility (owner_id, viewer_id)
+                VALUES ($1, $2)
+                ON CONFLICT DO NOTHING
+            "#,
+            self.payload.user_id,
+            viewer_id,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!(
+            "User {} shared busy times with {viewer_id}",
+            self.payload.user_id
+        );
+
+        Ok(())
+    }
+
+    pub async fn revoke_busy_visibility(&mut self, viewer_id: Uuid) -> Result<(), UserError> {
+        query!(
+            r#"
+                DELETE FROM busy_visibility
+                WHERE owner_id = $1 AND viewer_id = $2
+            "#,
+            self.payload.user_id,
+            viewer_id,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!(
+            "User {} stopped sharing busy times with {viewer_id}",
+            self.payload.user_id
+        );
+
+        Ok(())
+    }
+
+    pub async fn update_week_start(&mut self, week_start: DayOfWeek) -> Result<(), UserError> {
+        query!(
+            r#"
+                UPDATE users
+                SET week_start = $1
+                WHERE id = $2
+            "#,
+            week_start.number_days_from_monday() as i16,
+            self.payload.user_id,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!(
+            "Set week start of the user {} to {week_start:?}",
+            self.payload.user_id
+        );
+
+        Ok(())
+    }
+
+    pub async fn update_invite_can_edit(&mut self, can_edit: bool) -> Result<(), UserError> {
+        query!(
+            r#"
+                UPDATE users
+                SET invite_can_edit = $1
+                WHERE id = $2
+            "#,
+            can_edit,
+            self.payload.user_id,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        trace!(
+            "Set editing privilege of invitations of the user {} to {can_edit}",
+            self.payload.user_id
+        );
+
+        Ok(())
+    }
+
+    /// Hands owned events over to their active co-owners, returning their ids.
+    async fn hand_over_owned_events(&mut self) -> Result<Vec<Uuid>, UserError> {
+        // Successors leave the participants first, owners cannot participate in their own events
+        let successors = query!(
+            r#"
+                DELETE FROM user_events
+                USING (
+                    SELECT DISTINCT ON (events.id) events.id AS event_id, user_events.user_id
+                    FROM events
+                    JOIN user_events ON user_events.event_id = events.id AND user_events.is_owner
+                    JOIN users ON users.id = user_events.user_id AND users.deactivated_at IS NULL
+                    WHERE events.owner_id = $1 AND events.deleted_at IS NULL
+                    ORDER BY events.id, user_events.user_id
+                ) AS successors
+                WHERE user_events.event_id = successors.event_id
+                AND user_events.user_id = successors.user_id
+                RETURNING user_events.event_id, user_events.user_id
+            "#,
+            self.payload.user_id,
+        )
+        .fetch_all(&mut *self.conn)
+        .await?;
+
+        let event_ids: Vec<Uuid> = successors.iter().map(|row| row.event_id).collect();
+        let owner_ids: Vec<Uuid> = successors.iter().map(|row| row.user_id).collect();
+        query!(
+            r#"
+                UPDATE events SET owner_id = successors.owner_id
+                FROM UNNEST($1::uuid[], $2::uuid[]) AS successors(event_id, owner_id)
+                WHERE events.id = successors.event_id
+            "#,
+            &event_ids,
+            &owner_ids,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(event_ids)
+    }
+
+    /// Permanently deletes events the user still owns, returning the ids of the undeleted ones.
+    async fn delete_owned_events(&mut self) -> Result<Vec<Uuid>, UserError> {
+        let events = query!(
+            r#"
+                SELECT id, deleted_at FROM events
+                WHERE owner_id = $1
+            "#,
+            self.payload.user_id,
+        )
+        .fetch_all(&mut *self.conn)
+        .await?;
+        let event_ids: Vec<Uuid> = events.iter().map(|event| event.id).collect();
+
+        // Not removed along with their events
+        query!(
+            r#"
+                DELETE FROM user_event_invitations
+                WHERE event_id = any($1)
+            "#,
+            &event_ids,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+        query!(
+            r#"
+                DELETE FROM event_tokens
+                WHERE event_id = any($1)
+            "#,
+            &event_ids,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+        query!(
+            r#"
+                DELETE FROM events
+                WHERE id = any($1)
+            "#,
+            &event_ids,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(events
+            .into_iter()
+            .filter(|event| event.deleted_at.is_none())
+            .map(|event| event.id)
+            .collect())
+    }
+
+    /// Removes the user from events of others, returning their ids.
+    async fn leave_events(&mut self) -> Result<Vec<Uuid>, UserError> {
+        let event_ids = query!(
+            r#"
+                DELETE FROM user_events
+                WHERE user_id = $1
+                RETURNING event_id
+            "#,
+            self.payload.user_id,
+        )
+        .fetch_all(&mut *self.conn)
+        .await?
+        .into_iter()
+        .map(|row| row.event_id)
+        .collect();
+        query!(
+            r#"
+                DELETE FROM event_override_participants
+                WHERE user_id = $1
+            "#,
+            self.payload.user_id,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(event_ids)
+    }
+
+    /// Deletes the user with the rest of their data, `false` when the user does not exist.
+    async fn delete_user(&mut self) -> Result<bool, UserError> {
+        query!(
+            r#"
+                DELETE FROM user_event_invitations
+                WHERE sender_id = $1 OR receiver_id = $1
+            "#,
+            self.payload.user_id,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+        query!(
+            r#"
+                DELETE FROM event_feed_tokens
+                WHERE created_by = $1
+            "#,
+            self.payload.user_id,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+        query!(
+            r#"
+                DELETE FROM credentials
+                WHERE user_id = $1
+            "#,
+            self.payload.user_id,
+        )
+        .execute(&mut *self.conn)
+        .await?;
+
+        // Reminders, busy visibility, invitation counters and archives are removed along with the user
+        let affected = query!(
+            r#"
+                DELETE FROM users
+                WHERE id = $1
+            "#,
+            self.payload.user_id,
+        )
+        .execute(&mut *self.conn)
+        .await?
+        .rows_affected();
+
+        Ok(affected == 1)
+    }
+
+    async fn notify(&mut self, topic: Topic, event_ids: &[Uuid]) -> Result<(), UserError> {
+        for event_id in event_ids {
+            outbox::record(
+                &mut *self.conn,
+                topic,
+                *event_id,
+                json!({ "userId": self.payload.user_id }),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+pub async fn get_user_preferences(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<UserPreferences, UserError> {
+    let mut conn = pool.acquire().await?;
+    let mut q = PgQuery::new(UserQuery::new(user_id), &mut conn);
+
+    q.get_preferences().await
+}
+
+pub async fn get_username(pool: &PgPool, user_id: Uuid) -> Result<String, UserError> {
+    let mut conn = pool.acquire().await?;
+    let mut q = PgQuery::new(UserQuery::new(user_id), &mut conn);
+
+    q.get_username().await
+}
+
+/// Gets the handles of the user, so that mentions of former handles can be resolved after renames.
+pub async fn get_user_handles(pool: &PgPool, user_id: Uuid) -> Result<Vec<UserHandle>, UserError> {
+    let mut conn = pool.acquire().await?;
+    let mut q = PgQuery::new(UserQuery::new(user_id), &mut conn);
+
+    q.get_handles().await
+}
+
+pub async fn is_admin(pool: &PgPool, user_id: Uuid) -> Result<bool, UserError> {
+    let mut conn = pool.acquire().await?;
+    let mut q = PgQuery::new(UserQuery::new(user_id), &mut conn);
+
+    q.is_admin().await
+}
+
+pub async fn update_user_preferences(
+    pool: &PgPool,
+    user_id: Uuid,
+    body: UpdateUserPreferences,
+) -> Result<UserPreferences, UserError> {
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(UserQuery::new(user_id), &mut transaction);
+
+    if let Some(week_start) = body.week_start {
+        q.update_week_start(week_start).await?;
+    }
+    if let Some(can_edit) = body.invite_can_edit {
+        q.update_invite_can_edit(can_edit).await?;
+    }
+    let preferences = q.get_preferences().await?;
+    transaction.commit().await?;
+
+    Ok(preferences)
+}
+
+/// Lets the viewer see when the user is busy, without any details of the events.
+pub async fn grant_busy_visibility(
+    pool: &PgPool,
+    user_id: Uuid,
+    viewer_id: Uuid,
+) -> Result<(), UserError> {
+    let mut transaction = pool.begin().await?;
+    let mut q = PgQuery::new(UserQuery::new(user_id), &mut transaction);
+
+    if !q.exists(viewer_id).await? {
+        return Err(UserError::NotFound);
+    }
+    q.grant_busy_visibility(viewer_id).await?;
+    transaction.commit().await?;
+
+    Ok(())
+}
+
+pub async fn revoke_busy_visibility(
+    pool: &PgPool,
+    user_id: Uuid,
+    viewer_id: Uuid,
+) -> Result<(), UserError> {
+    let mut conn = pool.acquire().await?;
+    let mut q = PgQuery::new(UserQuery::new(user_id), &mut conn);
+
+    q.revoke_busy_visibility(viewer_id).await
+}
+
+/// Erases the user along with all of their data.
+///
+/// Events with an active co-owner are handed over to one of them, other owned events are deleted for every participant.
+/// The user leaves events of others, which keep their changes made by the user.
+pub async fn delete_user_account(
+    pool: &PgPool,
+    storage: &dyn Storage,
+    user_id: Uuid,
+) -> Result<(), UserError> {
+    let mut transaction = pool.begin().await?;
+    let archive_keys = get_archive_keys(&mut transaction, user_id).await?;
+    let mut q = PgQuery::new(UserQuery::new(user_id), &mut transaction);
+
+    let handed_over = q.hand_over_owned_events().await?;
+    let deleted = q.delete_owned_events().await?;
+    let left = q.leave_events().await?;
+    if !q.delete_user().await? {
+        return Err(UserError::NotFound);
+    }
+    q.notify(Topic::ParticipantsChanged, &handed_over).await?;
+    q.notify(Topic::EventDeleted, &deleted).await?;
+    q.notify(Topic::ParticipantsChanged, &left).await?;
+    transaction.commit().await?;
+    debug!(
+        "Erased the user {user_id}, handing over {} and deleting {} events",
+        handed_over.len(),
+        deleted.len()
+    );
+
+    for key in archive_keys {
+        if let Err(e) = storage.delete(&key).await {
+            error!("Failed to delete archive {key} of an erased user: {e:?}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use sqlx::{query, PgPool};
+use time::Time;
+use tracing::trace;
+use uuid::Uuid;
+
+use crate::modules::database::PgQuery;
+use crate::utils::users::errors::UserError;
+use bimetable_domain::api::users::{CreateQuietHours, QuietAction, QuietHours};
+use bimetable_domain::events::models::{days_from_week_map, week_map_from_days};
+use bimetable_domain::validation::ValidateContent;
+
+/// Days of quiet hours created without any
+const EVERY_DAY: u8 = 0b1111111;
+
+struct QQuietHours {
+    id: Uuid,
+    starts_at: Time,
+    ends_at: Time,
+    week_map: i16,
+    action: String,
+}
+
+impl TryFrom<QQuietHours> for QuietHours {
+    type Error = UserError;
+
+    fn try_from(quiet_hours: QQuietHours) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: quiet_hours.id,
+            starts_at: quiet_hours.starts_at,
+            ends_at: quiet_hours.ends_at,
+            days: days_from_week_map(quiet_hours.week_map as u8),
+            action: QuietAction::try_from(quiet_hours.action)
+                .map_err(|e| UserError::Unexpected(anyhow::anyhow!(e)))?,
+        })
+    }
+}
+
+pub struct QuietHoursQuery {
+    pub user_id: Uuid,
+}
+
+impl QuietHoursQuery {
+    pub fn new(user_id: Uuid) -> Self {
+        Self { user_id }
+    }
+}
+
+impl<'c> PgQuery<'c, QuietHoursQuery> {
+    async fn create(&mut self, body: &CreateQuietHours) -> Result<QuietHours, UserError> {
+        let week_map = match week_map_from_days(&body.days) {
+            0 => EVERY_DAY,
+            week_map => week_map,
+        };
+
+        let quiet_hours = sqlx::query_as!(
+            QQuietHours,
+            r#"
+                INSERT INTO quiet_hours (user_id, starts_at, ends_at, week_map, action)
+                VALUES ($1, $2, $3, $4, $5)
+                RETURNING id, starts_at, ends_at, week_map, action
+            "#,
+            self.payload.user_id,
+            body.starts_at,
+            body.ends_at,
+            week_map as i16,
+            body.action.as_str(),
+        )
+        .fetch_one(&mut *self.conn)
+        .await?;
+
+        trace!(
+            "Created quiet hours {} of the user {}",
+            quiet_hours.id,
+            self.payload.user_id
+        );
+
+        QuietHours::try_from(quiet_hours)
+    }
+
+    pub async fn get_all(&mut self) -> Result<Vec<QuietHours>, UserError> {
+        sqlx::query_as!(
+            QQuietHours,
+            r#"
+                SELECT id, starts_at, ends_at, week_map, action
+                FROM quiet_hours
+                WHERE user_id = $1
+                ORDER BY created_at
+            "#,
+            self.payload.user_id,
+        )
+        .fetch_all(&mut *self.conn)
+        .await?
+        .into_iter()
+        .map(QuietHours::try_from)
+        .collect()
+    }
+
+    async fn delete(&mut self, quiet_hours_id: Uuid) -> Result<(), UserError> {
+        let deleted = query!(
+            r#"
+                DELETE FROM quiet_hours
+                WHERE id = $1 AND user_id = $2
+            "#,
+            quiet_hours_id,
+            self.payload.user_id,
+        )
+        .execute(&mut *self.conn)
+        .await?
+        .rows_affected();
+
+        if deleted == 0 {
+            return Err(UserError::QuietHoursNotFound);
+        }
+
+        trace!("Deleted quiet hours {quiet_hours_id}");
+        Ok(())
+    }
+}
+
+pub async fn get_user_quiet_hours(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<QuietHours>, UserError> {
+    let mut conn = pool.acquire().await?;
+    PgQuery::new(QuietHoursQuery::new(user_id), &mut conn)
+        .get_all()
+        .await
+}
+
+pub async fn create_quiet_hours(
+    pool: &PgPool,
+    user_id: Uuid,
+    body: CreateQuietHours,
+) -> Result<QuietHours, UserError> {
+    body.validate_content()?;
+
+    let mut conn = pool.acquire().await?;
+    PgQuery::new(QuietHoursQuery::new(user_id), &mut conn)
+        .create(&body)
+        .await
+}
+
+pub async fn delete_quiet_hours(
+    pool: &PgPool,
+    user_id: Uuid,
+    quiet_hours_id: Uuid,
+) -> Result<(), UserError> {
+    let mut conn = pool.acquire().await?;
+    PgQuery::new(QuietHoursQuery::new(user_id), &mut conn)
+        .delete(quiet_hours_id)
+        .await
+}
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+use time::serde::iso8601;
+use time::OffsetDateTime;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Deserialize, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceStatus {
+    /// Whether writes of non-admin users are rejected
+    pub is_enabled: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, IntoParams)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct GetStatsQuery {
+    /// Number of the latest days, 30 by default
+    #[param(maximum = 366)]
+    pub days: Option<u32>,
+}
+
+/// Aggregates of the instance, computed by a background job a few times a day.
+#[derive(Debug, Deserialize, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyStats {
+    /// Start of the UTC day
+    #[serde(with = "iso8601")]
+    pub day: OffsetDateTime,
+    pub users: i64,
+    /// Events which are not deleted
+    pub events: i64,
+    /// Entries starting within the day
+    pub entries: i64,
+    pub pending_invitations: i64,
+    /// Size of the whole database
+    pub database_bytes: i64,
+    /// Time of the last computation, the counts are taken at that time
+    #[serde(with = "iso8601")]
+    pub computed_at: OffsetDateTime,
+}
+
+/// Retention policy of the deployment in days, data without a limit is kept.
+#[derive(Debug, Deserialize, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionPolicy {
+    /// Soft deleted events, counted from their deletion
+    pub trash_days: Option<u32>,
+    /// Revoked tokens, counted from their expiry
+    pub blacklist_days: u32,
+    /// Dispatched change messages, counted from the change
+    pub audit_log_days: Option<u32>,
+    /// Unanswered invitations, counted from sending
+    pub invitation_expiry_days: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct MintSignupCodes {
+    /// Number of codes, at most 100
+    #[schema(minimum = 1, maximum = 100)]
+    pub count: u32,
+    /// Days the codes can be used for, 14 by default
+    #[schema(minimum = 1, maximum = 366)]
+    pub valid_days: Option<u32>,
+}
+
+/// Code required to register when signups are invite-only, it can be used once.
+#[derive(Debug, Deserialize, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SignupCode {
+    pub code: String,
+    #[serde(with = "iso8601")]
+    pub expires_at: OffsetDateTime,
+}
@@ -17,11 +17,28 @@ impl LoginCredentials {
     }
 }
 
+/// Tokens for clients authenticating with the `Authorization: Bearer` header.
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthTokens {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub token_type: String,
+    /// Access token lifetime in seconds
+    pub expires_in: i64,
+}
+
 #[derive(Serialize, Deserialize, IntoParams, ToSchema)]
 pub struct RegisterCredentials {
     pub login: String,
     pub password: String,
     pub username: String,
+    /// Token of the sign-up link from an email invitation, for registering with another email
+    #[serde(default)]
+    pub invitation: Option<String>,
+    /// Code minted by an admin, required when signups are invite-only
+    #[serde(default, rename = "signupCode")]
+    pub signup_code: Option<String>,
 }
 
 impl RegisterCredentials {
@@ -30,6 +47,8 @@ impl RegisterCredentials {
             login: login.into(),
             password: password.into(),
             username: username.into(),
+            invitation: None,
+            signup_code: None,
         }
     }
 }
@@ -0,0 +1,1914 @@
+use crate::events::count_to_until::count_to_until;
+use crate::events::errors::RecurrenceError;
+use crate::events::models::{
+    DayOfWeek, EntriesSpan, EventPrivileges, OverrideStatus, OverrideStrategy, RecurrenceRule,
+    RecurrenceRuleKind, TimeRange,
+};
+use crate::events::normalize::AnchorAdjustment;
+use crate::events::occurrences::occurrence_id;
+use crate::events::summary::recurrence_summary;
+use crate::events::until_to_count::until_to_count;
+use crate::validation::ValidateContent;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use time::serde::iso8601;
+use time::{Duration, OffsetDateTime, UtcOffset};
+use utoipa::{IntoParams, ToResponse, ToSchema};
+use uuid::Uuid;
+
+// Core data models
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct OptionalEventData {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(with = "iso8601::option", skip_serializing_if = "Option::is_none")]
+    pub starts_at: Option<OffsetDateTime>,
+    #[serde(with = "iso8601::option", skip_serializing_if = "Option::is_none")]
+    pub ends_at: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct OverrideEventData {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Shift of the entry start, an ISO 8601 duration
+    #[serde(
+        default,
+        with = "iso8601_duration::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schema(value_type = Option<String>, example = "-PT15M")]
+    pub starts_at: Option<Duration>,
+    /// Shift of the entry end, an ISO 8601 duration
+    #[serde(
+        default,
+        with = "iso8601_duration::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schema(value_type = Option<String>, example = "PT1H30M")]
+    pub ends_at: Option<Duration>,
+    /// Reason of the override, limiting the fields it may set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<OverrideStatus>,
+    /// Guests of the overridden entries
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added_participants: Vec<Uuid>,
+    /// Participants absent from the overridden entries
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded_participants: Vec<Uuid>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct EventPayload {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl EventPayload {
+    pub fn new(name: String, description: Option<String>) -> Self {
+        Self { name, description }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OptionalEventPayload {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct EventData {
+    pub payload: EventPayload,
+    #[serde(with = "iso8601")]
+    pub starts_at: OffsetDateTime,
+    #[serde(with = "iso8601")]
+    pub ends_at: OffsetDateTime,
+}
+
+// Queries
+#[derive(Debug, Deserialize, Serialize, IntoParams, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct GetEventsQuery {
+    /// Start of the search range
+    #[serde(with = "iso8601")]
+    pub starts_at: OffsetDateTime,
+    /// End of the search range
+    #[serde(with = "iso8601")]
+    pub ends_at: OffsetDateTime,
+    pub filter: EventFilter,
+    /// Overrides the user's preferred first day of the week
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub week_start: Option<DayOfWeek>,
+    /// Start of the first returned entry, taken from `nextCursor`
+    #[serde(
+        default,
+        with = "iso8601::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cursor: Option<OffsetDateTime>,
+    /// Maximum number of returned entries
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    /// Returns events without expanding their entries
+    #[serde(default)]
+    pub events_only: bool,
+    /// Adds the effective name, description and time range to each entry
+    #[serde(default)]
+    pub effective: bool,
+    /// Adds every override covering each entry, not only the applied one
+    #[serde(default)]
+    pub all_overrides: bool,
+    /// Order of the returned entries, ties are broken by event id
+    #[serde(default)]
+    pub sort: EntrySort,
+    #[serde(default)]
+    pub direction: SortDirection,
+    /// Splits entries spanning midnight into segments per day, midnights are taken in the offset of `startsAt`
+    #[serde(default)]
+    pub split_by_day: bool,
+    /// Adds soft deleted events of the user, marked with `deletedAt`
+    #[serde(default)]
+    pub include_deleted: bool,
+    /// Comma separated ids of the only events to fetch, e.g. of the visible calendars
+    #[serde(
+        default,
+        with = "comma_separated::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[param(value_type = Option<String>)]
+    #[schema(value_type = Option<String>)]
+    pub event_ids: Option<Vec<Uuid>>,
+    /// Fields of events and overrides to return, `name` leaves out their descriptions
+    #[serde(default)]
+    pub fields: EventFields,
+}
+
+#[derive(Debug, Deserialize, Serialize, IntoParams)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct GetCombinedQuery {
+    /// Comma separated ids of the users, who shared their busy times or the requester
+    #[serde(with = "comma_separated")]
+    #[param(value_type = String)]
+    pub user_ids: Vec<Uuid>,
+    /// Start of the search range
+    #[serde(with = "iso8601")]
+    pub starts_at: OffsetDateTime,
+    /// End of the search range
+    #[serde(with = "iso8601")]
+    pub ends_at: OffsetDateTime,
+}
+
+/// (De)serializes ids as a single comma separated string.
+mod comma_separated {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use uuid::Uuid;
+
+    pub fn serialize<S: Serializer>(ids: &[Uuid], serializer: S) -> Result<S::Ok, S::Error> {
+        let ids: Vec<String> = ids.iter().map(Uuid::to_string).collect();
+        serializer.serialize_str(&ids.join(","))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Uuid>, D::Error> {
+        String::deserialize(deserializer)?
+            .split(',')
+            .filter(|id| !id.trim().is_empty())
+            .map(|id| Uuid::parse_str(id.trim()).map_err(D::Error::custom))
+            .collect()
+    }
+
+    pub mod option {
+        use serde::{Deserializer, Serializer};
+        use uuid::Uuid;
+
+        pub fn serialize<S: Serializer>(
+            ids: &Option<Vec<Uuid>>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match ids {
+                Some(ids) => super::serialize(ids, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Vec<Uuid>>, D::Error> {
+            super::deserialize(deserializer).map(Some)
+        }
+    }
+}
+
+/// (De)serializes time shifts as ISO 8601 durations of days, hours, minutes and seconds,
+/// e.g. `-PT15M` or `P1DT2H30.5S`. Weeks are accepted on input, years and months are not fixed lengths.
+pub mod iso8601_duration {
+    use serde::de::{Error, Unexpected};
+    use serde::{Deserialize, Deserializer, Serializer};
+    use time::Duration;
+
+    const EXPECTED: &str = "an ISO 8601 duration of weeks, days, hours, minutes and seconds";
+
+    pub fn format(duration: Duration) -> String {
+        let mut text = String::from(if duration.is_negative() { "-P" } else { "P" });
+        let duration = duration.abs();
+        let seconds = duration.whole_seconds();
+        let nanos = duration.subsec_nanoseconds();
+        let (days, hours, minutes, seconds) = (
+            seconds / 86_400,
+            seconds % 86_400 / 3_600,
+            seconds % 3_600 / 60,
+            seconds % 60,
+        );
+
+        if days > 0 {
+            text.push_str(&format!("{days}D"));
+        }
+        if days > 0 && hours == 0 && minutes == 0 && seconds == 0 && nanos == 0 {
+            return text;
+        }
+        text.push('T');
+        if hours > 0 {
+            text.push_str(&format!("{hours}H"));
+        }
+        if minutes > 0 {
+            text.push_str(&format!("{minutes}M"));
+        }
+        if seconds > 0 || nanos > 0 || (days == 0 && hours == 0 && minutes == 0) {
+            text.push_str(&seconds.to_string());
+            if nanos > 0 {
+                let fraction = format!("{nanos:09}");
+                text.push('.');
+                text.push_str(fraction.trim_end_matches('0'));
+            }
+            text.push('S');
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Option<Duration> {
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(text) => (true, text),
+            None => (false, text),
+        };
+        let text = text.strip_prefix('P')?;
+        let (date, time) = match text.split_once('T') {
+            Some((_, "")) => return None,
+            Some((date, time)) => (date, time),
+            None => (text, ""),
+        };
+
+        let mut seconds: i64 = 0;
+        let mut nanos = 0;
+        let mut is_empty = true;
+        for (part, designators) in [(date, "WD"), (time, "HMS")] {
+            let mut designators = designators;
+            let mut rest = part;
+            while !rest.is_empty() {
+                let end = rest.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+                let (number, tail) = rest.split_at(end);
+                let designator = tail.chars().next()?;
+                rest = &tail[designator.len_utf8()..];
+                // Components follow their order and appear once
+                designators = &designators[designators.find(designator)? + 1..];
+
+                let (whole, fraction) = match number.split_once('.') {
+                    Some((whole, fraction)) if designator == 'S' => (whole, Some(fraction)),
+                    Some(_) => return None,
+                    None => (number, None),
+                };
+                if whole.is_empty() {
+                    return None;
+                }
+                let unit = match designator {
+                    'W' => 604_800,
+                    'D' => 86_400,
+                    'H' => 3_600,
+                    'M' => 60,
+                    _ => 1,
+                };
+                seconds = whole
+                    .parse::<i64>()
+                    .ok()?
+                    .checked_mul(unit)?
+                    .checked_add(seconds)?;
+                if let Some(fraction) = fraction {
+                    if fraction.is_empty() || fraction.len() > 9 {
+                        return None;
+                    }
+                    nanos = format!("{fraction:0<9}").parse().ok()?;
+                }
+                is_empty = false;
+            }
+        }
+        if is_empty {
+            return None;
+        }
+
+        let duration = Duration::new(seconds, nanos);
+        Some(if negative { -duration } else { duration })
+    }
+
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            duration: &Option<Duration>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match duration {
+                Some(duration) => serializer.serialize_str(&format(*duration)),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Duration>, D::Error> {
+            Option::<String>::deserialize(deserializer)?
+                .map(|text| {
+                    parse(&text)
+                        .ok_or_else(|| D::Error::invalid_value(Unexpected::Str(&text), &EXPECTED))
+                })
+                .transpose()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CombinedBusy {
+    /// Busy time ranges of each user
+    pub users: HashMap<Uuid, Vec<TimeRange>>,
+    /// Time ranges when any of the users is busy
+    pub busy: Vec<TimeRange>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SuggestSlot {
+    /// Users who shared their busy times with the requester, the requester always takes part
+    pub participants: Vec<Uuid>,
+    /// Length of the slot
+    pub duration_minutes: u32,
+    /// Start of the search window
+    #[serde(with = "iso8601")]
+    pub starts_at: OffsetDateTime,
+    /// End of the search window
+    #[serde(with = "iso8601")]
+    pub ends_at: OffsetDateTime,
+    /// Maximum number of suggested windows
+    #[serde(default = "default_suggested_slots")]
+    pub limit: u32,
+}
+
+fn default_suggested_slots() -> u32 {
+    5
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SuggestedSlots {
+    /// Earliest windows when all participants are free, each lasting at least the requested duration
+    pub slots: Vec<TimeRange>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum EventFilter {
+    All,
+    Owned,
+    Shared,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, ToSchema, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum EntrySort {
+    #[default]
+    Start,
+    End,
+    EventName,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, ToSchema, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Projection of events in list responses, e.g. grid views only render names.
+#[derive(Debug, Default, Deserialize, Serialize, ToSchema, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum EventFields {
+    #[default]
+    Full,
+    Name,
+}
+
+// Send payloads
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[schema(example = json!({
+    "data": {
+        "payload": { "name": "Fizyka", "description": "fizyka kwantowa :O" },
+        "startsAt": "2023-03-08T09:45:00Z",
+        "endsAt": "2023-03-08T10:30:00Z"
+    },
+    "recurrenceRule": {
+        "time_rules": { "endsAt": { "count": 15 }, "interval": 1 },
+        "kind": { "weekly": { "days": ["wednesday", "thursday"] } }
+    }
+}))]
+pub struct CreateEvent {
+    pub data: EventData,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recurrence_rule: Option<RecurrenceRuleSchema>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateEventResult {
+    pub event_id: Uuid,
+    /// Remarks about the accepted event, see [`WarnContent`](crate::validation::WarnContent)
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    /// Change of the first occurrence, made only on request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalized: Option<AnchorAdjustment>,
+}
+
+#[derive(Debug, Deserialize, Serialize, IntoParams)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct CreateEventQuery {
+    /// Move the first occurrence of a recurring event onto its rule grid
+    #[serde(default)]
+    pub normalize: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, IntoParams)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct GetEventQuery {
+    /// Adds the overrides and pauses of the event
+    #[serde(default)]
+    pub exceptions: bool,
+    /// Finds the event of the user even when it's soft deleted, marked with `deletedAt`
+    #[serde(default)]
+    pub include_deleted: bool,
+}
+
+/// Window without entries of a recurring event
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct PauseEvent {
+    #[serde(with = "iso8601")]
+    pub starts_at: OffsetDateTime,
+    #[serde(with = "iso8601")]
+    pub ends_at: OffsetDateTime,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateRecurrenceResult {
+    /// Remarks about the accepted rule
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EventFeedToken {
+    pub token: Uuid,
+}
+
+/// Link to a stored export, which anyone holding it can download until it expires.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EventExport {
+    pub url: String,
+    #[serde(with = "iso8601")]
+    pub expires_at: OffsetDateTime,
+}
+
+/// School year of an imported timetable.
+#[derive(Debug, Deserialize, Serialize, IntoParams)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct ImportTimetableQuery {
+    /// First day of lessons, its offset applies to the times in the timetable
+    #[serde(with = "iso8601")]
+    pub starts_at: OffsetDateTime,
+    /// End of the last lesson
+    #[serde(with = "iso8601")]
+    pub ends_at: OffsetDateTime,
+    /// Only read and validate the timetable, listing the events which would be created
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportEventsResult {
+    /// Created events, none in dry runs
+    pub event_ids: Vec<Uuid>,
+    /// Events which would be created, listed in dry runs only
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<CreateEvent>,
+    /// Lessons which were left out
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    /// What became of every item of an imported class schedule
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mapping: Vec<ImportMapping>,
+}
+
+/// Item of an imported class schedule and the event made of it.
+#[derive(Debug, Deserialize, Serialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportMapping {
+    /// Title of the course or meeting in the export
+    pub source: String,
+    /// Position of the event in `eventIds`, or in `events` of a dry run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event: Option<usize>,
+    /// Why the item was left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, IntoParams)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct ImportScheduleQuery {
+    /// Offset in minutes of Teams times outside of UTC, which have no offset of their own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset_minutes: Option<i16>,
+    /// Only read and validate the schedule, listing the events which would be created
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, IntoParams)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct EventFeedQuery {
+    /// Feed token of the event
+    pub token: Uuid,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct UpdateEvent {
+    pub data: OptionalEventData,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct UpdateRecurrence {
+    /// New recurrence rule of the event, `null` turns the event into a one-off event
+    pub recurrence_rule: Option<RecurrenceRuleSchema>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SplitEvent {
+    /// Start of the first occurrence to be changed
+    #[serde(with = "iso8601")]
+    pub split_at: OffsetDateTime,
+    /// New time range of the first changed occurrence
+    #[serde(with = "iso8601")]
+    pub starts_at: OffsetDateTime,
+    #[serde(with = "iso8601")]
+    pub ends_at: OffsetDateTime,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct OverrideEvent {
+    #[serde(with = "iso8601")]
+    pub override_starts_at: OffsetDateTime,
+    #[serde(with = "iso8601")]
+    pub override_ends_at: OffsetDateTime,
+    pub data: OverrideEventData,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteEvent {
+    pub event_id: Uuid,
+    pub is_permanent: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteOverride {
+    pub override_id: Uuid,
+    pub is_permanent: bool,
+}
+
+// Receive payloads
+#[derive(Debug, Deserialize, Serialize, ToResponse, ToSchema, PartialEq)]
+pub struct Events {
+    pub events: HashMap<Uuid, Event>,
+    pub entries: Vec<Entry>,
+}
+
+impl Events {
+    pub fn new(events: HashMap<Uuid, Event>, entries: Vec<Entry>) -> Self {
+        Self { events, entries }
+    }
+
+    pub fn merge(mut self, other: Self) -> Self {
+        self.append(other);
+        self
+    }
+
+    /// Marks the entries locked by the owners of their events, only owners may still override them.
+    pub fn mark_locked(&mut self, locked: &HashSet<Uuid>) {
+        if locked.is_empty() {
+            return;
+        }
+        for entry in self.entries.iter_mut() {
+            if !locked.contains(&entry.occurrence_id) {
+                continue;
+            }
+            entry.is_locked = true;
+            entry.can_edit &= self
+                .events
+                .get(&entry.event_id)
+                .is_some_and(|event| event.is_owned);
+        }
+    }
+
+    /// Moves events and entries of `other` into `self`, keeping entries normalized.
+    pub fn append(&mut self, other: Self) {
+        self.events.extend(other.events);
+        self.entries.extend(other.entries);
+        self.normalize_entries();
+    }
+
+    /// Orders entries by start, end, event id and occurrence id, keeping one entry of each occurrence.
+    ///
+    /// Of a duplicated occurrence the editable entry is kept,
+    /// so merging owned and shared events gives the same entries in either order.
+    pub fn normalize_entries(&mut self) {
+        self.entries.sort_by(|a, b| {
+            a.time_range
+                .start
+                .cmp(&b.time_range.start)
+                .then_with(|| a.time_range.end.cmp(&b.time_range.end))
+                .then_with(|| a.event_id.cmp(&b.event_id))
+                .then_with(|| a.occurrence_id.cmp(&b.occurrence_id))
+                .then_with(|| b.can_edit.cmp(&a.can_edit))
+        });
+        let mut occurrences = HashSet::new();
+        self.entries
+            .retain(|entry| occurrences.insert((entry.event_id, entry.occurrence_id)));
+    }
+
+    /// Sorts entries by `sort`, breaking ties by event id, start and occurrence id.
+    ///
+    /// Event names are taken from the effective block when entries were resolved.
+    pub fn sort_entries(&mut self, sort: EntrySort, direction: SortDirection) {
+        let events = &self.events;
+        let name = |entry: &Entry| match &entry.effective {
+            Some(effective) => effective.name.to_lowercase(),
+            None => events
+                .get(&entry.event_id)
+                .map(|event| event.payload.name.to_lowercase())
+                .unwrap_or_default(),
+        };
+
+        self.entries.sort_by(|a, b| {
+            let order = match sort {
+                EntrySort::Start => a.time_range.start.cmp(&b.time_range.start),
+                EntrySort::End => a.time_range.end.cmp(&b.time_range.end),
+                EntrySort::EventName => name(a).cmp(&name(b)),
+            };
+            let order = match direction {
+                SortDirection::Asc => order,
+                SortDirection::Desc => order.reverse(),
+            };
+            order
+                .then_with(|| a.event_id.cmp(&b.event_id))
+                .then_with(|| a.time_range.start.cmp(&b.time_range.start))
+                .then_with(|| a.occurrence_id.cmp(&b.occurrence_id))
+        });
+    }
+
+    /// Replaces entries spanning midnight in the offset with their segments within `range`, one per day.
+    ///
+    /// Segments are cut from the effective time range and stay next to each other.
+    pub fn split_by_day(&mut self, offset: UtcOffset, range: &TimeRange) {
+        self.entries = std::mem::take(&mut self.entries)
+            .into_iter()
+            .flat_map(|entry| {
+                let effective = entry.effective_range();
+                let days = TimeRange::new(
+                    effective.start.to_offset(offset),
+                    effective.end.to_offset(offset),
+                )
+                .split_by_days();
+                if days.len() < 2 {
+                    return vec![entry];
+                }
+
+                let last = days.len() - 1;
+                days.into_iter()
+                    .enumerate()
+                    .filter(|(_, day)| day.is_overlapping(range))
+                    .map(|(i, day)| Entry {
+                        segment: Some(EntrySegment {
+                            time_range: day,
+                            continues_from_previous_day: i > 0,
+                            continues_to_next_day: i < last,
+                        }),
+                        ..entry.clone()
+                    })
+                    .collect()
+            })
+            .collect();
+    }
+
+    /// Merged time ranges within `range` when the entries take place.
+    ///
+    /// Deleted entries are skipped and overridden ones are taken with their shifted times.
+    pub fn busy_ranges(&self, range: &TimeRange) -> Vec<TimeRange> {
+        let busy = self
+            .entries
+            .iter()
+            .filter(|entry| {
+                entry
+                    .recurrence_override
+                    .as_ref()
+                    .is_none_or(|ovr| !ovr.is_cancelled())
+            })
+            .filter_map(|entry| entry.effective_range().intersection(range))
+            .collect();
+
+        TimeRange::merge(busy)
+    }
+
+    /// Cuts out at most `limit` entries starting at or after `cursor`.
+    ///
+    /// Entries sharing a start time are never split between pages.
+    /// Recurring events are kept only with their entries, one-off events by their start.
+    pub fn page(mut self, cursor: Option<OffsetDateTime>, limit: Option<usize>) -> EventsPage {
+        self.entries.sort_by_key(|entry| entry.time_range.start);
+        if let Some(cursor) = cursor {
+            self.entries
+                .retain(|entry| entry.time_range.start >= cursor);
+        }
+        let next_cursor = limit.and_then(|limit| self.truncate_entries(limit));
+
+        if cursor.is_some() || limit.is_some() {
+            let paged: HashSet<Uuid> = self.entries.iter().map(|entry| entry.event_id).collect();
+            self.events.retain(|id, event| match event.recurrence_rule {
+                Some(_) => paged.contains(id),
+                None => {
+                    cursor.is_none_or(|cursor| event.entries_start >= cursor)
+                        && next_cursor.is_none_or(|next| event.entries_start < next)
+                }
+            });
+        }
+
+        EventsPage {
+            events: self,
+            next_cursor,
+        }
+    }
+
+    /// Returns the start of the first entry left out.
+    fn truncate_entries(&mut self, limit: usize) -> Option<OffsetDateTime> {
+        let next = self.entries.get(limit)?.time_range.start;
+        let starts_at = |entries: &[Entry], i: usize| entries[i].time_range.start;
+
+        let mut end = limit;
+        while end > 0 && starts_at(&self.entries, end - 1) == next {
+            end -= 1;
+        }
+        if end == 0 {
+            end = limit;
+            while end < self.entries.len() && starts_at(&self.entries, end) == next {
+                end += 1;
+            }
+        }
+
+        let next_cursor = self.entries.get(end).map(|entry| entry.time_range.start);
+        self.entries.truncate(end);
+        next_cursor
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToResponse, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EventsPage {
+    #[serde(flatten)]
+    pub events: Events,
+    /// Cursor of the next page, absent on the last one
+    #[serde(
+        default,
+        with = "iso8601::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub next_cursor: Option<OffsetDateTime>,
+}
+
+/// Recurrence rule of a request or response.
+///
+/// `time_rules` is the only snake case field of the v1 API, it's kept in responses and `timeRules` is accepted as well.
+#[derive(Debug, Deserialize, Serialize, ToSchema, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RecurrenceRuleSchema {
+    #[serde(alias = "timeRules")]
+    pub time_rules: TimeRules,
+    pub kind: RecurrenceRuleKind,
+}
+
+impl RecurrenceRuleSchema {
+    pub fn to_compute(
+        self,
+        event_time_range: &TimeRange,
+    ) -> Result<RecurrenceRule, RecurrenceError> {
+        let span = self
+            .time_rules
+            .ends_at
+            .as_ref()
+            .map(|ends_at| {
+                let (count, until) = match ends_at {
+                    RecurrenceEndsAt::Until(until) => {
+                        let count =
+                            self.until_to_count(event_time_range.start, *until, event_time_range)?;
+                        (count, *until)
+                    }
+                    RecurrenceEndsAt::Count(count) => {
+                        let until =
+                            self.count_to_until(event_time_range.start, *count, event_time_range)?;
+                        (*count, until)
+                    }
+                };
+
+                <Result<_, RecurrenceError>>::Ok(EntriesSpan {
+                    end: until,
+                    repetitions: count,
+                })
+            })
+            .transpose()?;
+
+        Ok(RecurrenceRule {
+            span,
+            interval: self.time_rules.interval,
+            kind: self.kind,
+        })
+    }
+    /// Returns the end of the nth occurrence of the event, starting from a specified point in time.
+    ///
+    /// The first event in the given time bound counts as the 0th event.
+    ///
+    /// Currently, the point in time the search starts in must be the same as the beggining of any event occurrence.
+    ///
+    /// ```rust
+    /// use bimetable_domain::events::models::RecurrenceRuleKind;
+    /// use bimetable_domain::events::models::RecurrenceRule;
+    /// use bimetable_domain::events::models::TimeRange;
+    /// use time::macros::datetime;
+    /// use bimetable_domain::api::events::{RecurrenceEndsAt, RecurrenceRuleSchema, TimeRules};
+    ///
+    /// let event = TimeRange::new(
+    ///     datetime!(2023-02-18 10:00 UTC),
+    ///     datetime!(2023-02-18 12:15 UTC),
+    /// );
+    /// let rec_rules = RecurrenceRuleSchema {
+    ///     kind: RecurrenceRuleKind::Daily,
+    ///     time_rules: TimeRules {
+    ///         ends_at: Some(RecurrenceEndsAt::Count(15)),
+    ///         interval: 3,
+    ///     },
+    /// };
+    ///
+    /// assert_eq!(
+    ///     rec_rules.count_to_until(datetime!(2023-02-21 10:00 UTC), 1, &event).unwrap(),
+    ///     datetime!(2023-02-24 12:15 UTC)
+    /// )
+    /// ```
+    pub fn count_to_until(
+        &self,
+        part_starts_at: OffsetDateTime,
+        count: u32,
+        event: &TimeRange,
+    ) -> Result<OffsetDateTime, RecurrenceError> {
+        self.time_rules.validate_content()?;
+        count_to_until(
+            count,
+            self.time_rules.interval,
+            part_starts_at,
+            event,
+            &self.kind,
+        )
+    }
+
+    pub fn until_to_count(
+        &self,
+        part_starts_at: OffsetDateTime,
+        until: OffsetDateTime,
+        event: &TimeRange,
+    ) -> Result<u32, RecurrenceError> {
+        self.time_rules.validate_content()?;
+        until_to_count(
+            until,
+            part_starts_at,
+            self.time_rules.interval,
+            event.duration(),
+            &self.kind,
+        )
+    }
+}
+
+/// End of a recurrence, always tagged in responses: `{"until": "<date>"}` or `{"count": <n>}`.
+///
+/// Requests may also send the bare date or count.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase", from = "RecurrenceEndsAtRepr")]
+pub enum RecurrenceEndsAt {
+    #[serde(with = "iso8601")]
+    Until(OffsetDateTime),
+    Count(u32),
+}
+
+/// Accepted request forms of [`RecurrenceEndsAt`].
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RecurrenceEndsAtRepr {
+    Tagged(TaggedEndsAt),
+    Count(u32),
+    #[serde(with = "iso8601")]
+    Until(OffsetDateTime),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum TaggedEndsAt {
+    #[serde(with = "iso8601")]
+    Until(OffsetDateTime),
+    Count(u32),
+}
+
+impl From<RecurrenceEndsAtRepr> for RecurrenceEndsAt {
+    fn from(repr: RecurrenceEndsAtRepr) -> Self {
+        match repr {
+            RecurrenceEndsAtRepr::Tagged(TaggedEndsAt::Until(until))
+            | RecurrenceEndsAtRepr::Until(until) => Self::Until(until),
+            RecurrenceEndsAtRepr::Tagged(TaggedEndsAt::Count(count))
+            | RecurrenceEndsAtRepr::Count(count) => Self::Count(count),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct TimeRules {
+    pub ends_at: Option<RecurrenceEndsAt>,
+    pub interval: u32,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Event {
+    pub payload: EventPayload,
+    pub recurrence_rule: Option<RecurrenceRule>,
+    /// Human-readable description of the recurrence rule
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence_summary: Option<String>,
+    #[serde(with = "iso8601")]
+    pub entries_start: OffsetDateTime,
+    #[serde(with = "iso8601::option")]
+    pub entries_end: Option<OffsetDateTime>,
+    pub is_owned: bool,
+    pub can_edit: bool,
+    /// When the event was deleted, only listed for owners requesting `includeDeleted`
+    #[serde(
+        default,
+        with = "iso8601::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub deleted_at: Option<OffsetDateTime>,
+    /// Overrides and pauses of the event, when requested with `exceptions`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exceptions: Option<EventExceptions>,
+}
+
+impl Event {
+    pub fn new(
+        privileges: EventPrivileges,
+        payload: EventPayload,
+        recurrence_rule: Option<RecurrenceRule>,
+        entries_start: OffsetDateTime,
+        entries_end: Option<OffsetDateTime>,
+    ) -> Self {
+        let is_owned = matches!(privileges, EventPrivileges::Owned);
+        let can_edit = privileges.can_edit();
+
+        Self {
+            payload,
+            recurrence_summary: recurrence_rule
+                .as_ref()
+                .map(|rule| recurrence_summary(rule, entries_start)),
+            recurrence_rule,
+            entries_start,
+            entries_end,
+            is_owned,
+            can_edit,
+            deleted_at: None,
+            exceptions: None,
+        }
+    }
+}
+
+/// Occurrences of an event differing from its recurrence rule.
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EventExceptions {
+    /// Overrides in order of their ranges, cancelled ones have `deletedAt` set
+    pub overrides: Vec<RangedOverride>,
+    /// Ranges in which entries of the event are skipped
+    pub pauses: Vec<TimeRange>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RangedOverride {
+    /// Start of the range of overridden entries
+    #[serde(with = "iso8601")]
+    pub override_starts_at: OffsetDateTime,
+    /// End of the range of overridden entries
+    #[serde(with = "iso8601")]
+    pub override_ends_at: OffsetDateTime,
+    #[serde(flatten)]
+    pub data: Override,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    pub event_id: Uuid,
+    /// Stable identifier of the occurrence, derived from the event and the original start
+    pub occurrence_id: Uuid,
+    pub time_range: TimeRange,
+    #[serde(rename = "override")]
+    #[schema(rename = "override")]
+    pub recurrence_override: Option<Override>,
+    /// Whether the user may edit or override the entry, as allowed by its event
+    pub can_edit: bool,
+    /// Whether the owner locked the entry, so that editors may not override it
+    #[serde(default)]
+    pub is_locked: bool,
+    /// Entry as it finally happens, with its override applied to the event
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effective: Option<EffectiveEntry>,
+    /// Every override covering the entry, oldest first, when requested with `allOverrides`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overrides: Vec<Override>,
+    /// Part of the entry within one day, when entries spanning midnight are split with `splitByDay`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segment: Option<EntrySegment>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EntrySegment {
+    /// Part of the effective time range of the entry within the day
+    pub time_range: TimeRange,
+    /// Whether the entry started on an earlier day
+    pub continues_from_previous_day: bool,
+    /// Whether the entry ends on a later day
+    pub continues_to_next_day: bool,
+}
+
+impl Entry {
+    pub fn new(
+        event_id: Uuid,
+        time_range: TimeRange,
+        recurrence_override: Option<Override>,
+    ) -> Self {
+        Self {
+            event_id,
+            occurrence_id: occurrence_id(event_id, time_range.start),
+            time_range,
+            recurrence_override,
+            can_edit: false,
+            is_locked: false,
+            effective: None,
+            overrides: vec![],
+            segment: None,
+        }
+    }
+
+    /// Fills in the effective block from the payload of the entry's event.
+    pub fn resolve(&mut self, payload: &EventPayload) {
+        let ovr = self.recurrence_override.as_ref();
+        self.effective = Some(EffectiveEntry {
+            name: ovr
+                .and_then(|ovr| ovr.name.clone())
+                .unwrap_or_else(|| payload.name.clone()),
+            description: ovr
+                .and_then(|ovr| ovr.description.clone())
+                .or_else(|| payload.description.clone()),
+            time_range: self.effective_range(),
+            status: ovr.and_then(|ovr| ovr.status),
+        });
+    }
+
+    /// Time range of the entry after its time override, the original one when the override doesn't apply.
+    pub fn effective_range(&self) -> TimeRange {
+        self.range_with_time_override().unwrap_or(self.time_range)
+    }
+
+    pub fn range_with_time_override(&self) -> Option<TimeRange> {
+        self.time_range.shift(
+            self.recurrence_override
+                .as_ref()
+                .and_then(|x| Some(x.starts_at?))
+                .unwrap_or(Duration::seconds(0)),
+            self.recurrence_override
+                .as_ref()
+                .and_then(|x| Some(x.ends_at?))
+                .unwrap_or(Duration::seconds(0)),
+        )
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectiveEntry {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub time_range: TimeRange,
+    /// Reason of the applied override
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<OverrideStatus>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Override {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Shift of the entry start, an ISO 8601 duration
+    #[serde(
+        default,
+        with = "iso8601_duration::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schema(value_type = Option<String>, example = "-PT15M")]
+    pub starts_at: Option<Duration>,
+    /// Shift of the entry end, an ISO 8601 duration
+    #[serde(
+        default,
+        with = "iso8601_duration::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schema(value_type = Option<String>, example = "PT1H30M")]
+    pub ends_at: Option<Duration>,
+    #[serde(
+        default,
+        with = "iso8601::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub deleted_at: Option<OffsetDateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<OverrideStatus>,
+    pub created_at: OffsetDateTime,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added_participants: Vec<Uuid>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded_participants: Vec<Uuid>,
+}
+
+impl Override {
+    /// Whether the overridden entry doesn't take place, by deletion or by its status.
+    pub fn is_cancelled(&self) -> bool {
+        self.deleted_at.is_some() || self.status == Some(OverrideStatus::Cancelled)
+    }
+
+    /// Whether the user takes part in the overridden entry.
+    pub fn is_attended_by(&self, user_id: Uuid, is_guest: bool) -> bool {
+        if is_guest {
+            self.added_participants.contains(&user_id)
+        } else {
+            !self.excluded_participants.contains(&user_id)
+        }
+    }
+
+    /// Layers a newer override on top of this one, keeping the fields it leaves unset.
+    pub fn merged_with(self, newer: Override) -> Override {
+        let mut added_participants: Vec<Uuid> = self
+            .added_participants
+            .into_iter()
+            .filter(|id| !newer.excluded_participants.contains(id))
+            .collect();
+        let mut excluded_participants: Vec<Uuid> = self
+            .excluded_participants
+            .into_iter()
+            .filter(|id| !newer.added_participants.contains(id))
+            .collect();
+        for id in newer.added_participants {
+            if !added_participants.contains(&id) {
+                added_participants.push(id);
+            }
+        }
+        for id in newer.excluded_participants {
+            if !excluded_participants.contains(&id) {
+                excluded_participants.push(id);
+            }
+        }
+
+        Override {
+            name: newer.name.or(self.name),
+            description: newer.description.or(self.description),
+            starts_at: newer.starts_at.or(self.starts_at),
+            ends_at: newer.ends_at.or(self.ends_at),
+            deleted_at: newer.deleted_at.or(self.deleted_at),
+            status: newer.status.or(self.status),
+            created_at: newer.created_at,
+            added_participants,
+            excluded_participants,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct UpdateOverrideStrategy {
+    pub strategy: OverrideStrategy,
+}
+
+/// How often the owner gets a digest of changes made to the event.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum DigestFrequency {
+    Daily,
+    Weekly,
+}
+
+impl DigestFrequency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DigestFrequency::Daily => "daily",
+            DigestFrequency::Weekly => "weekly",
+        }
+    }
+
+    /// Time between digests.
+    pub fn period(&self) -> Duration {
+        match self {
+            DigestFrequency::Daily => Duration::days(1),
+            DigestFrequency::Weekly => Duration::weeks(1),
+        }
+    }
+}
+
+impl TryFrom<String> for DigestFrequency {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "daily" => Ok(Self::Daily),
+            "weekly" => Ok(Self::Weekly),
+            other => Err(format!("Unknown digest frequency {other}")),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct UpdateEventDigest {
+    pub frequency: DigestFrequency,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EventDigest {
+    pub frequency: DigestFrequency,
+    /// End of the period covered by the latest digest, changes made since are in the next one
+    #[serde(with = "iso8601")]
+    pub last_sent_at: OffsetDateTime,
+}
+
+/// Counts of changes made to the event during a digest period.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DigestSummary {
+    /// Participants who joined, left or had their privileges changed
+    pub participant_changes: i64,
+    /// Updates and overrides made by editors other than the owner
+    pub editor_changes: i64,
+    pub accepted_invitations: i64,
+    pub declined_invitations: i64,
+}
+
+impl DigestSummary {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Body of digest notifications, sent to the owner of the event.
+#[derive(Debug, Serialize, ToSchema, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DigestPayload {
+    pub event_id: Uuid,
+    pub name: String,
+    #[serde(with = "iso8601")]
+    pub since: OffsetDateTime,
+    #[serde(with = "iso8601")]
+    pub until: OffsetDateTime,
+    pub summary: DigestSummary,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct UpdateEditPrivilege {
+    pub user_id: Uuid,
+    pub can_edit: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct UpdateEditPrivileges {
+    /// Changes applied together, each participant may appear once
+    pub changes: Vec<UpdateEditPrivilege>,
+}
+
+/// Co-ownership of a participant, co-owners manage the event like its owner
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct UpdateCoOwner {
+    pub user_id: Uuid,
+    pub is_owner: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct UpdateEventOwner {
+    pub user_id: Uuid,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NewEventOwner {
+    pub user_id: Uuid,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+    use time::macros::datetime;
+    use time::UtcOffset;
+    use uuid::Uuid;
+
+    use crate::{
+        api::events::{
+            Entry, EntrySegment, EntrySort, Event, EventFields, EventPayload, EventPrivileges,
+            Events, GetEventsQuery, Override, SortDirection,
+        },
+        events::models::{DayOfWeek, RecurrenceRule, RecurrenceRuleKind, TimeRange},
+        validation::ValidateContent,
+    };
+
+    #[test]
+    fn weekly_kind_serialization() {
+        let kind = RecurrenceRuleKind::Weekly { week_map: 24 };
+        let value = json!({ "weekly": { "days": ["wednesday", "thursday"] } });
+
+        assert_eq!(serde_json::to_value(kind).unwrap(), value);
+        assert_eq!(
+            serde_json::from_value::<RecurrenceRuleKind>(value).unwrap(),
+            kind
+        );
+    }
+
+    #[test]
+    fn weekly_kind_strict_deserialization() {
+        for value in [
+            json!({ "weekly": { "days": [] } }),
+            json!({ "weekly": { "days": ["monday", "monday"] } }),
+            json!({ "weekly": { "days": ["mon"] } }),
+            json!({ "weekly": { "weekMap": 24 } }),
+        ] {
+            assert!(serde_json::from_value::<RecurrenceRuleKind>(value).is_err());
+        }
+    }
+
+    #[test]
+    fn entry_without_override_resolves_to_event() {
+        let range = TimeRange::new(
+            datetime!(2023-02-18 10:00 UTC),
+            datetime!(2023-02-18 12:00 UTC),
+        );
+        let mut entry = Entry::new(Uuid::new_v4(), range, None);
+
+        entry.resolve(&EventPayload::new(
+            String::from("A"),
+            Some(String::from("B")),
+        ));
+
+        let effective = entry.effective.unwrap();
+        assert_eq!(effective.name, "A");
+        assert_eq!(effective.description.as_deref(), Some("B"));
+        assert_eq!(effective.time_range, range);
+    }
+
+    #[test]
+    fn merge_events_1() {
+        let mut entries = vec![];
+        let id = Uuid::new_v4();
+        entries.push(Entry::new(
+            id,
+            TimeRange::new(
+                datetime!(2023-02-18 10:00 UTC),
+                datetime!(2023-02-18 12:00 UTC),
+            ),
+            None,
+        ));
+        entries.push(Entry::new(
+            id,
+            TimeRange::new(
+                datetime!(2023-02-19 10:00 UTC),
+                datetime!(2023-02-19 12:00 UTC),
+            ),
+            None,
+        ));
+        entries.push(Entry::new(
+            id,
+            TimeRange::new(
+                datetime!(2023-02-20 10:00 UTC),
+                datetime!(2023-02-20 12:00 UTC),
+            ),
+            None,
+        ));
+        let events = Events::new(
+            HashMap::from([(
+                id,
+                Event::new(
+                    EventPrivileges::Owned,
+                    EventPayload::new(String::from("A"), None),
+                    None,
+                    datetime!(2023-02-18 10:00 UTC),
+                    Some(datetime!(2023-02-20 12:00 UTC)),
+                ),
+            )]),
+            entries,
+        );
+
+        let mut other_entries = vec![];
+        let other_id = Uuid::new_v4();
+        other_entries.push(Entry::new(
+            other_id,
+            TimeRange::new(
+                datetime!(2023-02-17 10:00 UTC),
+                datetime!(2023-02-17 12:00 UTC),
+            ),
+            None,
+        ));
+        other_entries.push(Entry::new(
+            other_id,
+            TimeRange::new(
+                datetime!(2023-02-20 10:00 UTC),
+                datetime!(2023-02-20 12:00 UTC),
+            ),
+            None,
+        ));
+        other_entries.push(Entry::new(
+            other_id,
+            TimeRange::new(
+                datetime!(2023-02-21 10:00 UTC),
+                datetime!(2023-02-21 12:00 UTC),
+            ),
+            None,
+        ));
+
+        let other_events = Events::new(
+            HashMap::from([(
+                id,
+                Event::new(
+                    EventPrivileges::Owned,
+                    EventPayload::new(String::from("A"), None),
+                    None,
+                    datetime!(2023-02-17 10:00 UTC),
+                    Some(datetime!(2023-02-21 12:00 UTC)),
+                ),
+            )]),
+            other_entries,
+        );
+
+        let merged = events.merge(other_events);
+        let mut expected = vec![];
+
+        expected.push(Entry::new(
+            other_id,
+            TimeRange::new(
+                datetime!(2023-02-17 10:00 UTC),
+                datetime!(2023-02-17 12:00 UTC),
+            ),
+            None,
+        ));
+        expected.push(Entry::new(
+            id,
+            TimeRange::new(
+                datetime!(2023-02-18 10:00 UTC),
+                datetime!(2023-02-18 12:00 UTC),
+            ),
+            None,
+        ));
+        expected.push(Entry::new(
+            id,
+            TimeRange::new(
+                datetime!(2023-02-19 10:00 UTC),
+                datetime!(2023-02-19 12:00 UTC),
+            ),
+            None,
+        ));
+        expected.push(Entry::new(
+            id,
+            TimeRange::new(
+                datetime!(2023-02-20 10:00 UTC),
+                datetime!(2023-02-20 12:00 UTC),
+            ),
+            None,
+        ));
+
+        expected.push(Entry::new(
+            other_id,
+            TimeRange::new(
+                datetime!(2023-02-20 10:00 UTC),
+                datetime!(2023-02-20 12:00 UTC),
+            ),
+            None,
+        ));
+        expected.push(Entry::new(
+            other_id,
+            TimeRange::new(
+                datetime!(2023-02-21 10:00 UTC),
+                datetime!(2023-02-21 12:00 UTC),
+            ),
+            None,
+        ));
+
+        println!("{:#?}", merged);
+        for (a, b) in merged.entries.iter().zip(expected.iter()) {
+            assert_eq!(a.time_range.start, b.time_range.start)
+        }
+    }
+
+    #[test]
+    fn merged_entries_are_sorted_and_deduplicated() {
+        let shared_id = Uuid::new_v4();
+        let owned_id = Uuid::new_v4();
+        let range = |day| {
+            TimeRange::new(
+                datetime!(2023-02-18 10:00 UTC) + time::Duration::days(day),
+                datetime!(2023-02-18 12:00 UTC) + time::Duration::days(day),
+            )
+        };
+        let event = || {
+            Event::new(
+                EventPrivileges::Owned,
+                EventPayload::new(String::from("A"), None),
+                None,
+                datetime!(2023-02-18 10:00 UTC),
+                None,
+            )
+        };
+        let editable = |event_id, day| Entry {
+            can_edit: true,
+            ..Entry::new(event_id, range(day), None)
+        };
+
+        let owned = || {
+            Events::new(
+                HashMap::from([(shared_id, event()), (owned_id, event())]),
+                vec![
+                    editable(shared_id, 1),
+                    editable(owned_id, 0),
+                    editable(shared_id, 0),
+                ],
+            )
+        };
+        // The boundary entry of the first day is fetched again as shared
+        let shared = || {
+            Events::new(
+                HashMap::from([(shared_id, event())]),
+                vec![
+                    Entry::new(shared_id, range(0), None),
+                    Entry::new(shared_id, range(2), None),
+                ],
+            )
+        };
+
+        let merged = owned().merge(shared());
+        assert_eq!(merged, shared().merge(owned()));
+
+        let entries: Vec<(Uuid, TimeRange, bool)> = merged
+            .entries
+            .iter()
+            .map(|entry| (entry.event_id, entry.time_range, entry.can_edit))
+            .collect();
+        let mut first_day = vec![(shared_id, range(0), true), (owned_id, range(0), true)];
+        first_day.sort_by_key(|(event_id, _, _)| *event_id);
+        assert_eq!(
+            entries,
+            [
+                first_day,
+                vec![(shared_id, range(1), true), (shared_id, range(2), false)]
+            ]
+            .concat()
+        );
+    }
+
+    fn paged_events() -> (Uuid, Uuid, Events) {
+        let recurring_id = Uuid::new_v4();
+        let one_off_id = Uuid::new_v4();
+        let recurring = Event::new(
+            EventPrivileges::Owned,
+            EventPayload::new(String::from("A"), None),
+            Some(RecurrenceRule {
+                span: None,
+                interval: 1,
+                kind: RecurrenceRuleKind::Daily,
+            }),
+            datetime!(2023-02-18 10:00 UTC),
+            None,
+        );
+        let one_off = Event::new(
+            EventPrivileges::Owned,
+            EventPayload::new(String::from("B"), None),
+            None,
+            datetime!(2023-02-20 8:00 UTC),
+            Some(datetime!(2023-02-20 9:00 UTC)),
+        );
+        let entries = [18, 19, 20, 20, 21]
+            .into_iter()
+            .map(|day| {
+                let start = datetime!(2023-02-01 10:00 UTC).replace_day(day).unwrap();
+                Entry::new(
+                    recurring_id,
+                    TimeRange::new(start, start + time::Duration::hours(2)),
+                    None,
+                )
+            })
+            .collect();
+
+        let events = Events::new(
+            HashMap::from([(recurring_id, recurring), (one_off_id, one_off)]),
+            entries,
+        );
+        (recurring_id, one_off_id, events)
+    }
+
+    #[test]
+    fn page_does_not_split_entries_with_same_start() {
+        let (recurring_id, one_off_id, events) = paged_events();
+
+        let page = events.page(None, Some(3));
+
+        assert_eq!(page.events.entries.len(), 2);
+        assert_eq!(page.next_cursor, Some(datetime!(2023-02-20 10:00 UTC)));
+        assert!(page.events.events.contains_key(&recurring_id));
+        assert!(page.events.events.contains_key(&one_off_id));
+    }
+
+    #[test]
+    fn page_from_cursor() {
+        let (_, one_off_id, events) = paged_events();
+
+        let page = events.page(Some(datetime!(2023-02-20 10:00 UTC)), Some(1));
+
+        assert_eq!(page.events.entries.len(), 2);
+        assert_eq!(page.next_cursor, Some(datetime!(2023-02-21 10:00 UTC)));
+        assert!(!page.events.events.contains_key(&one_off_id));
+    }
+
+    #[test]
+    fn last_page_has_no_cursor() {
+        let (_, one_off_id, events) = paged_events();
+
+        let page = events.page(Some(datetime!(2023-02-20 0:00 UTC)), Some(10));
+
+        assert_eq!(page.events.entries.len(), 3);
+        assert_eq!(page.next_cursor, None);
+        assert!(page.events.events.contains_key(&one_off_id));
+    }
+
+    /// Parses the query string like axum's `Query` extractor does.
+    fn parse_query(query: &str) -> Result<GetEventsQuery, serde_urlencoded::de::Error> {
+        serde_urlencoded::from_str(query)
+    }
+
+    #[test]
+    fn events_query_uses_camel_case() {
+        let query = parse_query(
+            "startsAt=2023-02-20T00:00:00Z&endsAt=2023-02-27T00:00:00Z&filter=all&weekStart=sunday&eventsOnly=true",
+        )
+        .unwrap();
+
+        assert_eq!(query.week_start, Some(DayOfWeek::Sunday));
+        assert!(query.events_only);
+    }
+
+    #[test]
+    fn events_query_rejects_unknown_params() {
+        let snake_case =
+            parse_query("starts_at=2023-02-20T00:00:00Z&endsAt=2023-02-27T00:00:00Z&filter=all");
+        let typo = parse_query(
+            "startsAt=2023-02-20T00:00:00Z&endsAt=2023-02-27T00:00:00Z&filter=all&limt=5",
+        );
+
+        assert!(snake_case.is_err());
+        assert!(typo.is_err());
+    }
+
+    #[test]
+    fn sorted_query_cannot_be_paged() {
+        let query = parse_query(
+            "startsAt=2023-02-20T00:00:00Z&endsAt=2023-02-27T00:00:00Z&filter=all&sort=eventName&direction=desc",
+        )
+        .unwrap();
+        assert_eq!(query.sort, EntrySort::EventName);
+        assert_eq!(query.direction, SortDirection::Desc);
+        assert!(query.validate_content().is_ok());
+
+        let paged = GetEventsQuery {
+            limit: Some(10),
+            ..query
+        };
+        assert!(paged.validate_content().is_err());
+    }
+
+    #[test]
+    fn events_query_takes_comma_separated_event_ids() {
+        let first = Uuid::new_v4();
+        let second = Uuid::new_v4();
+        let query = parse_query(&format!(
+            "startsAt=2023-02-20T00:00:00Z&endsAt=2023-02-27T00:00:00Z&filter=all&eventIds={first},{second}"
+        ))
+        .unwrap();
+        assert_eq!(query.event_ids, Some(vec![first, second]));
+
+        let query =
+            parse_query("startsAt=2023-02-20T00:00:00Z&endsAt=2023-02-27T00:00:00Z&filter=all")
+                .unwrap();
+        assert_eq!(query.event_ids, None);
+    }
+
+    #[test]
+    fn events_query_projects_fields() {
+        let query =
+            parse_query("startsAt=2023-02-20T00:00:00Z&endsAt=2023-02-27T00:00:00Z&filter=all")
+                .unwrap();
+        assert_eq!(query.fields, EventFields::Full);
+
+        let query = parse_query(
+            "startsAt=2023-02-20T00:00:00Z&endsAt=2023-02-27T00:00:00Z&filter=all&fields=name",
+        )
+        .unwrap();
+        assert_eq!(query.fields, EventFields::Name);
+    }
+
+    #[test]
+    fn entries_spanning_midnight_are_split_by_day() {
+        let event_id = Uuid::new_v4();
+        let mut events = Events::new(
+            HashMap::new(),
+            vec![
+                Entry::new(
+                    event_id,
+                    TimeRange::new(
+                        datetime!(2023-03-06 20:00 UTC),
+                        datetime!(2023-03-08 9:00 UTC),
+                    ),
+                    None,
+                ),
+                Entry::new(
+                    event_id,
+                    TimeRange::new(
+                        datetime!(2023-03-08 10:00 UTC),
+                        datetime!(2023-03-08 12:00 UTC),
+                    ),
+                    None,
+                ),
+            ],
+        );
+        let offset = UtcOffset::from_hms(2, 0, 0).unwrap();
+        let range = TimeRange::new(datetime!(2023-03-07 0:00 +2), datetime!(2023-03-09 0:00 +2));
+
+        events.split_by_day(offset, &range);
+        let segments: Vec<_> = events
+            .entries
+            .iter()
+            .map(|entry| entry.segment.clone())
+            .collect();
+        assert_eq!(
+            segments,
+            vec![
+                // The part before midnight in the offset is out of the range
+                Some(EntrySegment {
+                    time_range: TimeRange::new(
+                        datetime!(2023-03-07 0:00 +2),
+                        datetime!(2023-03-08 0:00 +2)
+                    ),
+                    continues_from_previous_day: true,
+                    continues_to_next_day: true,
+                }),
+                Some(EntrySegment {
+                    time_range: TimeRange::new(
+                        datetime!(2023-03-08 0:00 +2),
+                        datetime!(2023-03-08 11:00 +2)
+                    ),
+                    continues_from_previous_day: true,
+                    continues_to_next_day: false,
+                }),
+                None,
+            ]
+        );
+        assert!(events.entries[..2]
+            .iter()
+            .all(|entry| entry.occurrence_id == events.entries[0].occurrence_id));
+    }
+
+    #[test]
+    fn sort_entries_by_name_breaks_ties_by_event_id() {
+        let (recurring_id, one_off_id, mut events) = paged_events();
+        let range = TimeRange::new(
+            datetime!(2023-02-20 8:00 UTC),
+            datetime!(2023-02-20 9:00 UTC),
+        );
+        events.entries.push(Entry::new(one_off_id, range, None));
+
+        events.sort_entries(EntrySort::EventName, SortDirection::Desc);
+        assert_eq!(events.entries[0].event_id, one_off_id);
+        assert!(events.entries[1..]
+            .iter()
+            .all(|entry| entry.event_id == recurring_id));
+
+        events.sort_entries(EntrySort::End, SortDirection::Asc);
+        let ends: Vec<_> = events
+            .entries
+            .iter()
+            .map(|entry| entry.time_range.end)
+            .collect();
+        assert!(ends.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn merged_override_keeps_unset_fields() {
+        let (hubert, mabi) = (Uuid::new_v4(), Uuid::new_v4());
+        let older = Override {
+            name: Some("Fizyka".to_string()),
+            description: Some("Blok fizyki".to_string()),
+            starts_at: None,
+            ends_at: None,
+            deleted_at: None,
+            status: None,
+            created_at: datetime!(2023-04-01 8:00 UTC),
+            added_participants: vec![hubert],
+            excluded_participants: vec![mabi],
+        };
+        let newer = Override {
+            name: Some("Chemia".to_string()),
+            description: None,
+            starts_at: None,
+            ends_at: None,
+            deleted_at: None,
+            status: None,
+            created_at: datetime!(2023-04-02 8:00 UTC),
+            added_participants: vec![mabi],
+            excluded_participants: vec![],
+        };
+
+        let merged = older.merged_with(newer);
+
+        assert_eq!(merged.name.as_deref(), Some("Chemia"));
+        assert_eq!(merged.description.as_deref(), Some("Blok fizyki"));
+        assert_eq!(merged.created_at, datetime!(2023-04-02 8:00 UTC));
+        assert_eq!(merged.added_participants, vec![hubert, mabi]);
+        assert!(merged.excluded_participants.is_empty());
+    }
+}
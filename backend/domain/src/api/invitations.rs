@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Deserialize, Serialize, Debug, ToSchema, Clone, Copy)]
+pub struct CreateDirectInvitation {
+    pub event_id: Uuid,
+    pub receiver_id: Uuid,
+    /// Defaults to the `inviteCanEdit` preference of the sender
+    #[serde(default)]
+    pub can_edit: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize, Debug, ToSchema, Clone, Copy)]
+pub struct DirectInvitation {
+    pub event_id: Uuid,
+    pub sender_id: Uuid,
+    pub receiver_id: Uuid,
+    pub can_edit: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, ToSchema, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct CreateEmailInvitation {
+    pub event_id: Uuid,
+    /// Address of someone who may not have an account yet
+    pub email: String,
+    /// Defaults to the `inviteCanEdit` preference of the sender
+    #[serde(default)]
+    pub can_edit: Option<bool>,
+}
+
+/// Invitation waiting for its receiver to register with the email address.
+#[derive(Deserialize, Serialize, Debug, ToSchema, Clone)]
+pub struct EmailInvitation {
+    pub event_id: Uuid,
+    pub sender_id: Uuid,
+    pub email: String,
+    pub can_edit: bool,
+}
+
+/// Invitation as fetched by its receiver.
+#[derive(Deserialize, Serialize, Debug, ToSchema, Clone, PartialEq, Eq)]
+pub struct ReceivedInvitation {
+    pub event_id: Uuid,
+    pub sender_id: Uuid,
+    /// Current handle of the sender, `sender_id` stays the same after renames
+    pub sender_username: String,
+    pub sender_tag: i32,
+    pub receiver_id: Uuid,
+    pub can_edit: bool,
+    /// Whether the receiver has already seen the invitation in their inbox
+    pub is_seen: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, ToSchema, Clone, Copy)]
+pub struct RespondDirectInvitation {
+    pub event_id: Uuid,
+    pub sender_id: Uuid,
+    pub receiver_id: Uuid,
+    pub is_accepted: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct InvitationCount {
+    /// Invitations waiting for the user's response
+    pub pending: u32,
+    /// Pending invitations the user has not seen yet
+    pub unseen: u32,
+}
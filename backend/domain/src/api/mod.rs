@@ -0,0 +1,11 @@
+/// Prefix of the first version of the API.
+pub const API_V1: &str = "/api/v1";
+
+pub mod admin;
+pub mod auth;
+pub mod events;
+pub mod invitations;
+pub mod reminders;
+pub mod scim;
+pub mod search;
+pub mod users;
@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::api::events::Entry;
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[schema(example = json!({
+    "eventId": "fd1dcdf7-de06-4aad-ba6e-f2097217a5b1",
+    "minutesBefore": 5,
+    "webhook": { "url": "https://bell.example.com/ring", "format": "json" }
+}))]
+pub struct CreateReminder {
+    pub event_id: Uuid,
+    pub minutes_before: u32,
+    /// Receives the entry instead of the user
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<ReminderWebhook>,
+    /// Delivered during quiet hours as well
+    #[serde(default)]
+    pub critical: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema, Clone, PartialEq)]
+pub struct ReminderWebhook {
+    pub url: String,
+    #[serde(default)]
+    pub format: WebhookFormat,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum WebhookFormat {
+    #[default]
+    Json,
+    Ics,
+}
+
+impl WebhookFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookFormat::Json => "json",
+            WebhookFormat::Ics => "ics",
+        }
+    }
+}
+
+impl TryFrom<String> for WebhookFormat {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "json" => Ok(Self::Json),
+            "ics" => Ok(Self::Ics),
+            other => Err(format!("Unknown webhook format {other}")),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateReminderResult {
+    pub reminder_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Reminder {
+    pub id: Uuid,
+    pub event_id: Uuid,
+    pub minutes_before: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<ReminderWebhook>,
+    #[serde(default)]
+    pub critical: bool,
+}
+
+/// Body of reminder notifications and JSON webhooks.
+#[derive(Debug, Serialize, ToSchema, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReminderPayload {
+    pub reminder_id: Uuid,
+    pub event_id: Uuid,
+    pub name: String,
+    pub minutes_before: u32,
+    pub entry: Entry,
+}
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+pub const USER_SCHEMA: &str = "urn:ietf:params:scim:schemas:core:2.0:User";
+pub const LIST_RESPONSE_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:ListResponse";
+pub const ERROR_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:Error";
+
+/// User of the SCIM core schema.
+///
+/// `userName` is the login and `displayName` the username shown to other users.
+#[derive(Debug, Deserialize, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimUser {
+    pub schemas: Vec<String>,
+    pub id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    pub user_name: String,
+    pub display_name: String,
+    /// Inactive users are deactivated, they can't sign in and are hidden from search
+    pub active: bool,
+    pub meta: ScimMeta,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimMeta {
+    pub resource_type: String,
+}
+
+/// User sent by an identity system, attributes of the schema which are not stored here are ignored.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimUserRequest {
+    pub user_name: String,
+    /// Defaults to `userName`
+    pub display_name: Option<String>,
+    pub external_id: Option<String>,
+    #[serde(default = "default_active")]
+    pub active: bool,
+    /// Without a password the user can't sign in until an admin resets it
+    pub password: Option<String>,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+/// Changes of a SCIM `PatchOp` request.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct ScimPatch {
+    #[serde(rename = "Operations")]
+    pub operations: Vec<ScimPatchOperation>,
+}
+
+/// `add` or `replace` operation, `remove` is not supported.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct ScimPatchOperation {
+    pub op: String,
+    /// Attribute to change, without it `value` holds the changed attributes
+    pub path: Option<String>,
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub value: Value,
+}
+
+#[derive(Debug, Deserialize, Serialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ScimListQuery {
+    /// Only `userName eq "<login>"` and `externalId eq "<id>"` filters are supported
+    pub filter: Option<String>,
+    /// 1-based index of the first user
+    pub start_index: Option<u32>,
+    pub count: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimListResponse {
+    pub schemas: Vec<String>,
+    pub total_results: i64,
+    pub start_index: u32,
+    pub items_per_page: u32,
+    #[serde(rename = "Resources")]
+    pub resources: Vec<ScimUser>,
+}
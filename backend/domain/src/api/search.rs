@@ -0,0 +1,70 @@
+use crate::api::events::{Event, EventFilter};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Clone, ToSchema, IntoParams)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct SearchUsers {
+    /// Searched username
+    pub text: String,
+    /// Exact tag of the user, a former handle with the tag finds the renamed user too
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<i32>,
+    #[serde(default)]
+    pub mode: SearchMode,
+    /// Event whose participants are left out along with the searching user, e.g. when picking invitees
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<Uuid>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchMode {
+    /// Usernames starting with the text
+    #[default]
+    Prefix,
+    /// Usernames containing or similar to the text, most similar first
+    Fuzzy,
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct SearchUsersResult {
+    pub id: Uuid,
+    pub username: String,
+    pub tag: i32,
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema, IntoParams)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct SearchEvents {
+    /// Start of the searched event name
+    pub text: String,
+    pub filter: EventFilter,
+    /// Maximum number of returned events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    /// Number of skipped events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct SearchEventsResult {
+    pub events: Vec<Event>,
+    /// Number of events matching the search with the filter applied
+    pub total: i64,
+    pub facets: EventFacets,
+}
+
+/// Numbers of events matching the search text, regardless of the filter.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EventFacets {
+    pub owned: i64,
+    pub shared: i64,
+    pub recurring: i64,
+    pub one_off: i64,
+}
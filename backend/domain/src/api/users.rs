@@ -0,0 +1,292 @@
+use crate::events::models::{week_map_from_days, DayOfWeek};
+use serde::{Deserialize, Serialize};
+use time::serde::iso8601;
+use time::{Date, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UserPreferences {
+    /// First day of the week used for weekly recurrences
+    pub week_start: DayOfWeek,
+    /// Editing privilege of invitations sent without one
+    pub invite_can_edit: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct UpdateUserPreferences {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub week_start: Option<DayOfWeek>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invite_can_edit: Option<bool>,
+}
+
+/// Hours in which the user is not notified, e.g. nights or weekends.
+///
+/// They start at `startsAt` on each of their days and end at `endsAt`, on the next day when it is earlier.
+/// Both are UTC times of the day written as `HH:MM`.
+#[derive(Debug, Deserialize, Serialize, ToSchema, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct QuietHours {
+    pub id: Uuid,
+    #[serde(with = "clock_time")]
+    #[schema(value_type = String, example = "22:00")]
+    pub starts_at: Time,
+    #[serde(with = "clock_time")]
+    #[schema(value_type = String, example = "07:00")]
+    pub ends_at: Time,
+    pub days: Vec<DayOfWeek>,
+    pub action: QuietAction,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[schema(example = json!({
+    "startsAt": "22:00",
+    "endsAt": "07:00",
+    "days": ["sunday", "monday", "tuesday", "wednesday", "thursday"],
+    "action": "defer"
+}))]
+pub struct CreateQuietHours {
+    #[serde(with = "clock_time")]
+    #[schema(value_type = String)]
+    pub starts_at: Time,
+    #[serde(with = "clock_time")]
+    #[schema(value_type = String)]
+    pub ends_at: Time,
+    /// Days the quiet hours start on, every day when empty
+    #[serde(default)]
+    pub days: Vec<DayOfWeek>,
+    #[serde(default)]
+    pub action: QuietAction,
+}
+
+/// What happens to notifications due during quiet hours.
+///
+/// Reminders marked as critical are delivered anyway.
+#[derive(Debug, Default, Deserialize, Serialize, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum QuietAction {
+    /// Delivered once the quiet hours end
+    #[default]
+    Defer,
+    /// Dropped
+    Suppress,
+}
+
+impl QuietAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuietAction::Defer => "defer",
+            QuietAction::Suppress => "suppress",
+        }
+    }
+}
+
+impl TryFrom<String> for QuietAction {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "defer" => Ok(Self::Defer),
+            "suppress" => Ok(Self::Suppress),
+            other => Err(format!("Unknown quiet hours action {other}")),
+        }
+    }
+}
+
+mod clock_time {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use time::format_description::FormatItem;
+    use time::macros::format_description;
+    use time::Time;
+
+    const CLOCK_TIME: &[FormatItem<'_>] = format_description!("[hour]:[minute]");
+
+    pub fn serialize<S: Serializer>(time: &Time, serializer: S) -> Result<S::Ok, S::Error> {
+        let time = time.format(CLOCK_TIME).map_err(serde::ser::Error::custom)?;
+        serializer.serialize_str(&time)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Time, D::Error> {
+        let time = String::deserialize(deserializer)?;
+        Time::parse(&time, CLOCK_TIME).map_err(|_| D::Error::custom("expected a time as HH:MM"))
+    }
+}
+
+/// Archive of all data of a user, built in the background.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UserArchive {
+    pub id: Uuid,
+    pub status: ArchiveStatus,
+    #[serde(with = "iso8601")]
+    pub requested_at: OffsetDateTime,
+    /// Link to the zip of `data.json` and `calendar.ics`, once the archive is ready
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(
+        with = "iso8601::option",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub expires_at: Option<OffsetDateTime>,
+}
+
+/// Handle of a user, shown as `username#tag`.
+///
+/// Handles change when users are renamed, the id of the user stays the same.
+#[derive(Debug, Deserialize, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UserHandle {
+    pub username: String,
+    pub tag: i32,
+    /// Time of the rename which replaced the handle, unset for the current one
+    #[serde(
+        with = "iso8601::option",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub replaced_at: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ArchiveStatus {
+    Pending,
+    Ready,
+    Failed,
+}
+
+impl QuietHours {
+    /// End of these quiet hours if they are in effect at `at`.
+    pub fn end_covering(&self, at: OffsetDateTime) -> Option<OffsetDateTime> {
+        let at = at.to_offset(UtcOffset::UTC);
+        let week_map = week_map_from_days(&self.days);
+        let starts_on =
+            |date: Date| week_map & 1 << (6 - date.weekday().number_days_from_monday()) != 0;
+
+        [at.date().previous_day(), Some(at.date())]
+            .into_iter()
+            .flatten()
+            .filter(|date| starts_on(*date))
+            .find_map(|date| {
+                let start = PrimitiveDateTime::new(date, self.starts_at).assume_utc();
+                let end_date = if self.ends_at > self.starts_at {
+                    date
+                } else {
+                    date.next_day()?
+                };
+                let end = PrimitiveDateTime::new(end_date, self.ends_at).assume_utc();
+                (start <= at && at < end).then_some(end)
+            })
+    }
+}
+
+/// How a notification due at some time is handled.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Delivery {
+    Now,
+    DeferredTo(OffsetDateTime),
+    Suppressed,
+}
+
+/// Applies the quiet hours of the user to a notification due at `at`.
+///
+/// Suppressing quiet hours win over deferring ones, deferred notifications wait for the latest end.
+pub fn delivery_at(quiet_hours: &[QuietHours], at: OffsetDateTime) -> Delivery {
+    let mut delivery = Delivery::Now;
+    for quiet in quiet_hours {
+        let Some(end) = quiet.end_covering(at) else {
+            continue;
+        };
+        delivery = match (quiet.action, delivery) {
+            (QuietAction::Suppress, _) => return Delivery::Suppressed,
+            (QuietAction::Defer, Delivery::DeferredTo(later)) => {
+                Delivery::DeferredTo(later.max(end))
+            }
+            (QuietAction::Defer, _) => Delivery::DeferredTo(end),
+        };
+    }
+    delivery
+}
+
+#[cfg(test)]
+mod quiet_hours_tests {
+    use time::macros::{datetime, time};
+
+    use super::*;
+
+    fn quiet(
+        starts_at: Time,
+        ends_at: Time,
+        days: &[DayOfWeek],
+        action: QuietAction,
+    ) -> QuietHours {
+        QuietHours {
+            id: Uuid::nil(),
+            starts_at,
+            ends_at,
+            days: days.to_vec(),
+            action,
+        }
+    }
+
+    #[test]
+    fn nights_end_on_the_next_day() {
+        // 2023-03-10 is a Friday
+        let night = quiet(
+            time!(22:00),
+            time!(7:00),
+            &[DayOfWeek::Friday],
+            QuietAction::Defer,
+        );
+
+        assert_eq!(night.end_covering(datetime!(2023-03-10 21:59 UTC)), None);
+        assert_eq!(
+            night.end_covering(datetime!(2023-03-10 22:00 UTC)),
+            Some(datetime!(2023-03-11 7:00 UTC))
+        );
+        assert_eq!(
+            night.end_covering(datetime!(2023-03-11 6:59 UTC)),
+            Some(datetime!(2023-03-11 7:00 UTC))
+        );
+        assert_eq!(night.end_covering(datetime!(2023-03-11 7:00 UTC)), None);
+        // Saturday nights are not quiet
+        assert_eq!(night.end_covering(datetime!(2023-03-11 23:00 UTC)), None);
+    }
+
+    #[test]
+    fn suppressing_quiet_hours_win() {
+        let at = datetime!(2023-03-10 12:30 UTC);
+        let every_day = [
+            DayOfWeek::Monday,
+            DayOfWeek::Tuesday,
+            DayOfWeek::Wednesday,
+            DayOfWeek::Thursday,
+            DayOfWeek::Friday,
+            DayOfWeek::Saturday,
+            DayOfWeek::Sunday,
+        ];
+        let lunch = quiet(time!(12:00), time!(13:00), &every_day, QuietAction::Defer);
+        let meeting = quiet(time!(12:15), time!(14:00), &every_day, QuietAction::Defer);
+        let exam = quiet(
+            time!(12:00),
+            time!(12:45),
+            &every_day,
+            QuietAction::Suppress,
+        );
+
+        assert_eq!(delivery_at(&[], at), Delivery::Now);
+        assert_eq!(
+            delivery_at(&[lunch.clone(), meeting.clone()], at),
+            Delivery::DeferredTo(datetime!(2023-03-10 14:00 UTC))
+        );
+        assert_eq!(
+            delivery_at(&[lunch, exam, meeting], at),
+            Delivery::Suppressed
+        );
+    }
+}
@@ -0,0 +1,12 @@
+use anyhow::Context;
+
+pub trait DefaultContext<C, T, E>: Context<T, E> {
+    fn dc(self) -> anyhow::Result<T>
+    where
+        Self: Sized,
+    {
+        self.context("No context provided")
+    }
+}
+
+impl<C, T, E> DefaultContext<C, T, E> for C where C: Context<T, E> {}
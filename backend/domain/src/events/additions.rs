@@ -7,7 +7,7 @@ use time::{
 
 use crate::app_errors::DefaultContext;
 
-use super::{errors::EventError, models::TimeRange};
+use super::{errors::RecurrenceError, models::TimeRange};
 
 pub fn get_amount_from_week_map(week_map: &str) -> u8 {
     week_map.chars().map(|x| x as u8 - 48).sum::<u8>()
@@ -183,7 +183,7 @@ impl TimeStart for OffsetDateTime {
     }
 }
 
-pub fn next_good_month(time: OffsetDateTime, chg: i64) -> Result<OffsetDateTime, EventError> {
+pub fn next_good_month(time: OffsetDateTime, chg: i64) -> Result<OffsetDateTime, RecurrenceError> {
     let mut first_day = time.replace_day(1).dc()?;
     first_day = first_day.add_months(chg).dc()?;
     while first_day.replace_day(time.day()).is_err() {
@@ -196,7 +196,7 @@ pub fn nth_good_month(
     mut monthly_step: OffsetDateTime,
     mut count: u32,
     chg: i64,
-) -> Result<OffsetDateTime, EventError> {
+) -> Result<OffsetDateTime, RecurrenceError> {
     while count > 0 {
         monthly_step = next_good_month(monthly_step, chg)?;
         count -= 1;
@@ -208,7 +208,7 @@ pub fn nth_good_month(
 pub fn next_good_month_by_weekday(
     time: OffsetDateTime,
     chg: i64,
-) -> Result<OffsetDateTime, EventError> {
+) -> Result<OffsetDateTime, RecurrenceError> {
     let mut first_day = time.replace_day(1).dc()?;
     first_day = first_day.add_months(chg).dc()?;
     let day_offset = (time.day() - 1) / 7 * 7 + 1;
@@ -227,7 +227,7 @@ pub fn nth_53_week_year_by_weekday(
     yearly_step: OffsetDateTime,
     mut count: u32,
     chg: u32,
-) -> Result<OffsetDateTime, EventError> {
+) -> Result<OffsetDateTime, RecurrenceError> {
     let mut year_number = yearly_step.year();
     while count > 0 {
         year_number = year_number.checked_add(i32::try_from(chg).dc()?).dc()?;
@@ -271,7 +271,7 @@ pub fn day_from_week_and_weekday(
 mod test {
     use time::Month;
 
-    use crate::utils::events::additions::{CyclicTimeTo, TimeTo};
+    use crate::events::additions::{CyclicTimeTo, TimeTo};
 
     #[test]
     fn time_to_test() {
@@ -0,0 +1,508 @@
+use serde::Deserialize;
+use time::format_description::well_known::Iso8601;
+use time::serde::iso8601;
+use time::{OffsetDateTime, PrimitiveDateTime, UtcOffset};
+
+use crate::api::events::{CreateEvent, EventData, EventPayload, ImportMapping};
+use crate::events::models::TimeRange;
+use crate::events::rrule::from_rrule;
+use crate::limits::RepetitionLimit;
+use crate::validation::{ValidateContent, ValidateContentError};
+
+/// Schedule exported from a classroom tool, told apart by the shape of the JSON.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ScheduleExport {
+    /// Google Calendar event list of the calendar of a Google Classroom course
+    Google(GoogleEvents),
+    /// Microsoft Graph event list of a calendar with Teams class meetings
+    Microsoft(GraphEvents),
+}
+
+#[derive(Deserialize)]
+struct GoogleEvents {
+    /// Name of the course calendar
+    summary: Option<String>,
+    items: Vec<GoogleEvent>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GoogleEvent {
+    summary: Option<String>,
+    description: Option<String>,
+    location: Option<String>,
+    status: Option<String>,
+    recurring_event_id: Option<String>,
+    start: GoogleTime,
+    end: GoogleTime,
+    #[serde(default)]
+    recurrence: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GoogleTime {
+    #[serde(default, with = "iso8601::option")]
+    date_time: Option<OffsetDateTime>,
+}
+
+#[derive(Deserialize)]
+struct GraphEvents {
+    value: Vec<GraphEvent>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphEvent {
+    subject: Option<String>,
+    body_preview: Option<String>,
+    location: Option<GraphLocation>,
+    #[serde(default)]
+    is_all_day: bool,
+    #[serde(default)]
+    is_cancelled: bool,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    start: GraphTime,
+    end: GraphTime,
+    recurrence: Option<GraphRecurrence>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphLocation {
+    display_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphTime {
+    date_time: String,
+    time_zone: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GraphRecurrence {
+    pattern: GraphPattern,
+    range: GraphRange,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphPattern {
+    #[serde(rename = "type")]
+    kind: String,
+    interval: u32,
+    #[serde(default)]
+    days_of_week: Vec<String>,
+    day_of_month: Option<u8>,
+    month: Option<u8>,
+    index: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphRange {
+    #[serde(rename = "type")]
+    kind: String,
+    end_date: Option<String>,
+    number_of_occurrences: Option<u32>,
+}
+
+/// Events read from a class schedule, along with what became of every item of the export.
+#[derive(Debug, Default)]
+pub struct ScheduleImport {
+    pub events: Vec<CreateEvent>,
+    pub mapping: Vec<ImportMapping>,
+}
+
+impl ScheduleImport {
+    fn push(&mut self, source: String, event: Result<CreateEvent, String>, limit: RepetitionLimit) {
+        let event = event.and_then(|event| {
+            match event
+                .validate_repetitions(limit)
+                .and_then(|()| event.validate_content())
+            {
+                Ok(()) => Ok(event),
+                Err(e) => Err(e.message()),
+            }
+        });
+        match event {
+            Ok(event) => {
+                self.mapping.push(ImportMapping {
+                    source,
+                    event: Some(self.events.len()),
+                    skipped: None,
+                });
+                self.events.push(event);
+            }
+            Err(reason) => self.mapping.push(ImportMapping {
+                source,
+                event: None,
+                skipped: Some(reason),
+            }),
+        }
+    }
+}
+
+/// Reads meetings of a Google Classroom course calendar or of Teams as events.
+///
+/// Items which can't become events, like cancelled meetings, changed occurrences of a series
+/// or series repeating more than the limit, are left out and listed in the mapping with the reason.
+/// Graph times outside of UTC are read in the `offset` when it's given.
+pub fn schedule_events(
+    bytes: &[u8],
+    offset: Option<UtcOffset>,
+    repetition_limit: RepetitionLimit,
+) -> Result<ScheduleImport, ValidateContentError> {
+    let export: ScheduleExport = serde_json::from_slice(bytes).map_err(|_| {
+        ValidateContentError::new(
+            "Expected a Google Calendar event list of a Classroom course or a Microsoft Graph event list",
+        )
+    })?;
+
+    let mut import = ScheduleImport::default();
+    match export {
+        ScheduleExport::Google(events) => {
+            for item in events.items {
+                let source = item
+                    .summary
+                    .clone()
+                    .or_else(|| events.summary.clone())
+                    .unwrap_or_default();
+                let course = events.summary.as_deref();
+                import.push(source, google_event(item, course), repetition_limit);
+            }
+        }
+        ScheduleExport::Microsoft(events) => {
+            for item in events.value {
+                let source = item.subject.clone().unwrap_or_default();
+                import.push(source, graph_event(item, offset), repetition_limit);
+            }
+        }
+    }
+
+    Ok(import)
+}
+
+fn google_event(item: GoogleEvent, course: Option<&str>) -> Result<CreateEvent, String> {
+    if item.status.as_deref() == Some("cancelled") {
+        return Err("Meeting is cancelled".to_string());
+    }
+    if item.recurring_event_id.is_some() {
+        return Err("Changed occurrence of a series, only the series is imported".to_string());
+    }
+    let (Some(starts_at), Some(ends_at)) = (item.start.date_time, item.end.date_time) else {
+        return Err("All-day items are not meeting times".to_string());
+    };
+
+    let mut rules = item
+        .recurrence
+        .iter()
+        .filter(|line| line.starts_with("RRULE:"));
+    let rrule = rules.next();
+    if rules.next().is_some() {
+        return Err("Meetings with many recurrence rules are not supported".to_string());
+    }
+    if item.recurrence.len() > usize::from(rrule.is_some()) {
+        return Err("Exceptions of recurring meetings are not supported".to_string());
+    }
+
+    let name = item
+        .summary
+        .or_else(|| course.map(str::to_string))
+        .unwrap_or_default();
+    let range = TimeRange::new(starts_at, ends_at);
+    Ok(CreateEvent {
+        data: EventData {
+            payload: EventPayload::new(name, description(item.description, item.location)),
+            starts_at,
+            ends_at,
+        },
+        recurrence_rule: rrule
+            .map(|rrule| from_rrule(rrule, &range))
+            .transpose()
+            .map_err(|e| e.message())?,
+    })
+}
+
+fn graph_event(item: GraphEvent, offset: Option<UtcOffset>) -> Result<CreateEvent, String> {
+    if item.is_cancelled {
+        return Err("Meeting is cancelled".to_string());
+    }
+    if matches!(item.kind.as_deref(), Some("occurrence" | "exception")) {
+        return Err("Changed occurrence of a series, only the series is imported".to_string());
+    }
+    if item.is_all_day {
+        return Err("All-day items are not meeting times".to_string());
+    }
+
+    let starts_at = graph_time(&item.start, offset)?;
+    let ends_at = graph_time(&item.end, offset)?;
+    let range = TimeRange::new(starts_at, ends_at);
+    let recurrence_rule = item
+        .recurrence
+        .map(|recurrence| {
+            let rrule = graph_rrule(&recurrence)?;
+            from_rrule(&rrule, &range).map_err(|e| e.message())
+        })
+        .transpose()?;
+
+    let location = item.location.and_then(|location| location.display_name);
+    Ok(CreateEvent {
+        data: EventData {
+            payload: EventPayload::new(
+                item.subject.unwrap_or_default(),
+                description(item.body_preview, location),
+            ),
+            starts_at,
+            ends_at,
+        },
+        recurrence_rule,
+    })
+}
+
+/// Reads a Graph date-time, which has no offset of its own.
+fn graph_time(time: &GraphTime, offset: Option<UtcOffset>) -> Result<OffsetDateTime, String> {
+    let offset = match time.time_zone.as_deref() {
+        None | Some("UTC") => UtcOffset::UTC,
+        Some(zone) => offset.ok_or_else(|| {
+            format!("Times in the {zone} time zone need the offset of the import")
+        })?,
+    };
+    PrimitiveDateTime::parse(&time.date_time, &Iso8601::DEFAULT)
+        .map(|time| time.assume_offset(offset))
+        .map_err(|_| format!("Invalid time {}", time.date_time))
+}
+
+/// Writes a Graph recurrence as an `RRULE`, so that it's checked against the start like imported rules.
+fn graph_rrule(recurrence: &GraphRecurrence) -> Result<String, String> {
+    let pattern = &recurrence.pattern;
+    let mut parts = vec![];
+    match pattern.kind.as_str() {
+        "daily" => parts.push("FREQ=DAILY".to_string()),
+        "weekly" => {
+            let days = pattern
+                .days_of_week
+                .iter()
+                .map(|day| match day.as_str() {
+                    "monday" => Ok("MO"),
+                    "tuesday" => Ok("TU"),
+                    "wednesday" => Ok("WE"),
+                    "thursday" => Ok("TH"),
+                    "friday" => Ok("FR"),
+                    "saturday" => Ok("SA"),
+                    "sunday" => Ok("SU"),
+                    other => Err(format!("Unknown day of the week {other}")),
+                })
+                .collect::<Result<Vec<&str>, _>>()?;
+            parts.push("FREQ=WEEKLY".to_string());
+            if !days.is_empty() {
+                parts.push(format!("BYDAY={}", days.join(",")));
+            }
+        }
+        "absoluteMonthly" => {
+            parts.push("FREQ=MONTHLY".to_string());
+            if let Some(day) = pattern.day_of_month {
+                parts.push(format!("BYMONTHDAY={day}"));
+            }
+        }
+        "absoluteYearly" => {
+            parts.push("FREQ=YEARLY".to_string());
+            if let Some(month) = pattern.month {
+                parts.push(format!("BYMONTH={month}"));
+            }
+            if let Some(day) = pattern.day_of_month {
+                parts.push(format!("BYMONTHDAY={day}"));
+            }
+        }
+        "relativeMonthly" => {
+            let ordinal = match pattern.index.as_deref() {
+                None | Some("first") => 1,
+                Some("second") => 2,
+                Some("third") => 3,
+                Some("fourth") => 4,
+                Some(index) => return Err(format!("Unsupported {index} day of the month")),
+            };
+            let [day] = pattern.days_of_week.as_slice() else {
+                return Err("Monthly meetings on many days are not supported".to_string());
+            };
+            parts.push("FREQ=MONTHLY".to_string());
+            parts.push(format!(
+                "BYDAY={ordinal}{}",
+                day.get(..2).unwrap_or_default().to_uppercase()
+            ));
+        }
+        other => return Err(format!("Unsupported recurrence {other}")),
+    }
+    parts.push(format!("INTERVAL={}", pattern.interval));
+
+    let range = &recurrence.range;
+    match (
+        range.kind.as_str(),
+        &range.end_date,
+        range.number_of_occurrences,
+    ) {
+        ("noEnd", _, _) => {}
+        ("endDate", Some(end_date), _) => {
+            parts.push(format!("UNTIL={}", end_date.replace('-', "")))
+        }
+        ("numbered", _, Some(count)) => parts.push(format!("COUNT={count}")),
+        (other, _, _) => return Err(format!("Unsupported recurrence range {other}")),
+    }
+
+    Ok(parts.join(";"))
+}
+
+/// Joins the description and the room of a meeting.
+fn description(description: Option<String>, location: Option<String>) -> Option<String> {
+    let description = description.filter(|text| !text.trim().is_empty());
+    let location = location.filter(|room| !room.trim().is_empty());
+    let lines: Vec<String> = [
+        description.map(|text| text.trim().to_string()),
+        location.map(|room| format!("Location: {}", room.trim())),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    Some(lines.join("\n")).filter(|text| !text.is_empty())
+}
+
+#[cfg(test)]
+mod classroom_tests {
+    use serde_json::json;
+    use time::macros::{datetime, offset};
+
+    use super::*;
+    use crate::api::events::RecurrenceEndsAt;
+    use crate::events::models::{week_map_from_days, DayOfWeek, RecurrenceRuleKind};
+
+    const LIMIT: RepetitionLimit = RepetitionLimit(10_000);
+
+    #[test]
+    fn course_calendar_meetings_become_events() {
+        let export = json!({
+            "kind": "calendar#events",
+            "summary": "Fizyka 2B",
+            "items": [
+                {
+                    "summary": "Fizyka",
+                    "location": "sala 12",
+                    "start": { "dateTime": "2023-09-04T08:00:00+02:00", "timeZone": "Europe/Warsaw" },
+                    "end": { "dateTime": "2023-09-04T08:45:00+02:00", "timeZone": "Europe/Warsaw" },
+                    "recurrence": ["RRULE:FREQ=WEEKLY;BYDAY=MO;UNTIL=20240621"]
+                },
+                {
+                    "summary": "Fizyka",
+                    "status": "cancelled",
+                    "start": { "dateTime": "2023-09-05T08:00:00+02:00" },
+                    "end": { "dateTime": "2023-09-05T08:45:00+02:00" }
+                },
+                {
+                    "start": { "date": "2023-09-06" },
+                    "end": { "date": "2023-09-07" }
+                }
+            ]
+        });
+
+        let import = schedule_events(export.to_string().as_bytes(), None, LIMIT).unwrap();
+
+        assert_eq!(import.events.len(), 1);
+        let event = &import.events[0];
+        assert_eq!(event.data.starts_at, datetime!(2023-09-04 8:00 +2));
+        assert_eq!(
+            event.data.payload.description.as_deref(),
+            Some("Location: sala 12")
+        );
+        let rule = event.recurrence_rule.as_ref().unwrap();
+        assert_eq!(
+            rule.kind,
+            RecurrenceRuleKind::Weekly {
+                week_map: week_map_from_days(&[DayOfWeek::Monday])
+            }
+        );
+        assert!(matches!(
+            rule.time_rules.ends_at,
+            Some(RecurrenceEndsAt::Until(_))
+        ));
+
+        assert_eq!(import.mapping[0].event, Some(0));
+        assert_eq!(
+            import.mapping[1].skipped.as_deref(),
+            Some("Meeting is cancelled")
+        );
+        // Items without a title are named after the course
+        assert_eq!(import.mapping[2].source, "Fizyka 2B");
+        assert!(import.mapping[2].skipped.is_some());
+    }
+
+    #[test]
+    fn teams_meetings_become_events() {
+        let export = json!({
+            "@odata.context": "https://graph.microsoft.com/v1.0/$metadata#users('me')/events",
+            "value": [
+                {
+                    "subject": "Matematyka",
+                    "bodyPreview": "Join on Teams",
+                    "type": "seriesMaster",
+                    "start": { "dateTime": "2023-09-05T10:00:00.0000000", "timeZone": "Central European Standard Time" },
+                    "end": { "dateTime": "2023-09-05T10:45:00.0000000", "timeZone": "Central European Standard Time" },
+                    "recurrence": {
+                        "pattern": { "type": "weekly", "interval": 2, "daysOfWeek": ["tuesday", "thursday"], "firstDayOfWeek": "monday" },
+                        "range": { "type": "numbered", "startDate": "2023-09-05", "numberOfOccurrences": 10 }
+                    }
+                },
+                {
+                    "subject": "Matematyka",
+                    "type": "exception",
+                    "start": { "dateTime": "2023-09-07T11:00:00.0000000", "timeZone": "UTC" },
+                    "end": { "dateTime": "2023-09-07T11:45:00.0000000", "timeZone": "UTC" }
+                }
+            ]
+        });
+
+        let import =
+            schedule_events(export.to_string().as_bytes(), Some(offset!(+2)), LIMIT).unwrap();
+
+        assert_eq!(import.events.len(), 1);
+        let event = &import.events[0];
+        assert_eq!(event.data.starts_at, datetime!(2023-09-05 10:00 +2));
+        let rule = event.recurrence_rule.as_ref().unwrap();
+        assert_eq!(rule.time_rules.interval, 2);
+        assert_eq!(rule.time_rules.ends_at, Some(RecurrenceEndsAt::Count(9)));
+        assert!(import.mapping[1].skipped.is_some());
+
+        let import = schedule_events(export.to_string().as_bytes(), None, LIMIT).unwrap();
+        assert_eq!(
+            import.mapping[0].skipped.as_deref(),
+            Some("Times in the Central European Standard Time time zone need the offset of the import")
+        );
+    }
+
+    #[test]
+    fn series_over_the_repetition_limit_are_left_out() {
+        let export = json!({
+            "summary": "Fizyka 2B",
+            "items": [{
+                "start": { "dateTime": "2023-09-04T08:00:00+02:00" },
+                "end": { "dateTime": "2023-09-04T08:45:00+02:00" },
+                "recurrence": ["RRULE:FREQ=DAILY;COUNT=100000"]
+            }]
+        });
+
+        let import = schedule_events(export.to_string().as_bytes(), None, LIMIT).unwrap();
+
+        assert!(import.events.is_empty());
+        assert_eq!(
+            import.mapping[0].skipped.as_deref(),
+            Some("recurrenceRule.time_rules.endsAt: Recurrence has more than 10000 entries")
+        );
+    }
+
+    #[test]
+    fn unknown_exports_are_rejected() {
+        assert!(schedule_events(b"{\"courses\": []}", None, LIMIT).is_err());
+        assert!(schedule_events(b"not json", None, LIMIT).is_err());
+    }
+}
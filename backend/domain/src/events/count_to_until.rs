@@ -1,7 +1,7 @@
 use time::{Date, Duration, Month, OffsetDateTime};
 
 use crate::app_errors::DefaultContext;
-use crate::utils::events::models::{RecurrenceRuleKind, TimeRange};
+use crate::events::models::{RecurrenceRuleKind, TimeRange};
 use crate::validation::{ValidateContent, ValidateContentError};
 
 use super::{
@@ -10,7 +10,7 @@ use super::{
         next_good_month_by_weekday, nth_53_week_year_by_weekday, nth_good_month, AddTime,
         CyclicTimeTo,
     },
-    errors::EventError,
+    errors::RecurrenceError,
 };
 
 pub struct CountToUntilData {
@@ -42,7 +42,7 @@ pub fn count_to_until(
     start: OffsetDateTime,
     event: &TimeRange,
     kind: &RecurrenceRuleKind,
-) -> Result<OffsetDateTime, EventError> {
+) -> Result<OffsetDateTime, RecurrenceError> {
     let conv_data = CountToUntilData {
         part_starts_at: start,
         count,
@@ -62,10 +62,12 @@ pub fn count_to_until(
             weekly_c_to_u(conv_data, &string_week_map)
         }
         RecurrenceRuleKind::Daily => daily_c_to_u(conv_data),
+        RecurrenceRuleKind::Hourly => fixed_step_c_to_u(conv_data, Duration::HOUR),
+        RecurrenceRuleKind::Minutely => fixed_step_c_to_u(conv_data, Duration::MINUTE),
     }
 }
 
-pub fn daily_c_to_u(conv_data: CountToUntilData) -> Result<OffsetDateTime, EventError> {
+pub fn daily_c_to_u(conv_data: CountToUntilData) -> Result<OffsetDateTime, RecurrenceError> {
     Ok(conv_data
         .part_starts_at
         .add_days(conv_data.count.checked_mul(conv_data.interval).dc()? as i64)?
@@ -73,10 +75,26 @@ pub fn daily_c_to_u(conv_data: CountToUntilData) -> Result<OffsetDateTime, Event
         .dc()?)
 }
 
+/// End of the last entry of rules repeating every `interval` steps of the same length.
+pub fn fixed_step_c_to_u(
+    conv_data: CountToUntilData,
+    step: Duration,
+) -> Result<OffsetDateTime, RecurrenceError> {
+    let steps = conv_data.count.checked_mul(conv_data.interval).dc()? as i64;
+    Ok(conv_data
+        .part_starts_at
+        .checked_add(Duration::seconds(
+            steps.checked_mul(step.whole_seconds()).dc()?,
+        ))
+        .dc()?
+        .checked_add(conv_data.event_duration)
+        .dc()?)
+}
+
 pub fn weekly_c_to_u(
     conv_data: CountToUntilData,
     week_map: &str,
-) -> Result<OffsetDateTime, EventError> {
+) -> Result<OffsetDateTime, RecurrenceError> {
     // get amount of event recurrences in 1 week
     let week_event_num = get_amount_from_week_map(week_map);
 
@@ -105,7 +123,7 @@ pub fn weekly_c_to_u(
         .dc()?)
 }
 
-pub fn monthly_c_to_u_by_day(conv_data: CountToUntilData) -> Result<OffsetDateTime, EventError> {
+pub fn monthly_c_to_u_by_day(conv_data: CountToUntilData) -> Result<OffsetDateTime, RecurrenceError> {
     let base_date = conv_data.part_starts_at;
 
     let target_date = if conv_data.part_starts_at.day() <= 28 {
@@ -119,7 +137,7 @@ pub fn monthly_c_to_u_by_day(conv_data: CountToUntilData) -> Result<OffsetDateTi
 
 pub fn monthly_c_to_u_by_weekday(
     conv_data: CountToUntilData,
-) -> Result<OffsetDateTime, EventError> {
+) -> Result<OffsetDateTime, RecurrenceError> {
     if conv_data.part_starts_at.day() <= 28 {
         monthly_c_to_u_for_other_days(conv_data)
     } else {
@@ -129,7 +147,7 @@ pub fn monthly_c_to_u_by_weekday(
 
 fn monthly_c_to_u_for_other_days(
     conv_data: CountToUntilData,
-) -> Result<OffsetDateTime, EventError> {
+) -> Result<OffsetDateTime, RecurrenceError> {
     let week_number = (conv_data.part_starts_at.day() - 1) / 7;
 
     let first_target_month_day = conv_data
@@ -151,7 +169,7 @@ fn monthly_c_to_u_for_other_days(
 
 fn monthly_c_to_u_for_last_days(
     mut conv_data: CountToUntilData,
-) -> Result<OffsetDateTime, EventError> {
+) -> Result<OffsetDateTime, RecurrenceError> {
     let mut monthly_step = conv_data.part_starts_at;
     while conv_data.count != 0 {
         monthly_step = next_good_month_by_weekday(monthly_step, conv_data.interval as i64)?;
@@ -161,7 +179,7 @@ fn monthly_c_to_u_for_last_days(
     Ok(monthly_step.checked_add(conv_data.event_duration).dc()?)
 }
 
-pub fn yearly_c_to_u_by_day(conv_data: CountToUntilData) -> Result<OffsetDateTime, EventError> {
+pub fn yearly_c_to_u_by_day(conv_data: CountToUntilData) -> Result<OffsetDateTime, RecurrenceError> {
     let base_date = conv_data.part_starts_at;
 
     let target_date = if (
@@ -177,7 +195,7 @@ pub fn yearly_c_to_u_by_day(conv_data: CountToUntilData) -> Result<OffsetDateTim
     Ok(target_date.checked_add(conv_data.event_duration).dc()?)
 }
 
-pub fn yearly_c_to_u_by_weekday(conv_data: CountToUntilData) -> Result<OffsetDateTime, EventError> {
+pub fn yearly_c_to_u_by_weekday(conv_data: CountToUntilData) -> Result<OffsetDateTime, RecurrenceError> {
     let (base_year, target_week, target_weekday) = conv_data.part_starts_at.to_iso_week_date();
 
     let target_date = if conv_data.part_starts_at.iso_week() == 53 {
@@ -207,8 +225,8 @@ pub fn yearly_c_to_u_by_weekday(conv_data: CountToUntilData) -> Result<OffsetDat
 
 #[cfg(test)]
 mod recurrence_tests {
-    use crate::routes::events::models::{RecurrenceEndsAt, RecurrenceRuleSchema, TimeRules};
-    use crate::utils::events::models::{RecurrenceRuleKind, TimeRange};
+    use crate::api::events::{RecurrenceEndsAt, RecurrenceRuleSchema, TimeRules};
+    use crate::events::models::{RecurrenceRuleKind, TimeRange};
     use time::macros::datetime;
 
     #[test]
@@ -233,6 +251,50 @@ mod recurrence_tests {
         )
     }
 
+    #[test]
+    fn hourly_recurrence_test() {
+        let event = TimeRange::new(
+            datetime!(2023-02-18 10:00 UTC),
+            datetime!(2023-02-18 10:10 UTC),
+        );
+        let rec_rules = RecurrenceRuleSchema {
+            time_rules: TimeRules {
+                ends_at: Some(RecurrenceEndsAt::Count(5)),
+                interval: 6,
+            },
+            kind: RecurrenceRuleKind::Hourly,
+        };
+
+        assert_eq!(
+            rec_rules
+                .count_to_until(datetime!(2023-02-18 10:00 UTC), 5, &event)
+                .unwrap(),
+            datetime!(2023-02-19 16:10 UTC)
+        )
+    }
+
+    #[test]
+    fn minutely_recurrence_test() {
+        let event = TimeRange::new(
+            datetime!(2023-02-18 23:30 UTC),
+            datetime!(2023-02-18 23:35 UTC),
+        );
+        let rec_rules = RecurrenceRuleSchema {
+            time_rules: TimeRules {
+                ends_at: Some(RecurrenceEndsAt::Count(4)),
+                interval: 20,
+            },
+            kind: RecurrenceRuleKind::Minutely,
+        };
+
+        assert_eq!(
+            rec_rules
+                .count_to_until(datetime!(2023-02-18 23:30 UTC), 4, &event)
+                .unwrap(),
+            datetime!(2023-02-19 0:55 UTC)
+        )
+    }
+
     #[test]
     fn weekly_recurrence_test() {
         let event = TimeRange::new(
@@ -0,0 +1,13 @@
+use crate::validation::ValidateContentError;
+use thiserror::Error;
+
+/// Error of computations on recurring events, raised before anything is stored.
+#[derive(Error, Debug)]
+pub enum RecurrenceError {
+    #[error("Event data rejected with validation")]
+    InvalidData(#[from] ValidateContentError),
+    #[error("Not Found")]
+    NotFound,
+    #[error(transparent)]
+    Unexpected(#[from] anyhow::Error),
+}
@@ -1,6 +1,6 @@
 use std::cmp::max;
 
-use time::{ext::NumericalDuration, util::weeks_in_year, OffsetDateTime, Weekday};
+use time::{ext::NumericalDuration, util::weeks_in_year, Duration, OffsetDateTime, Weekday};
 
 use crate::app_errors::DefaultContext;
 
@@ -9,7 +9,7 @@ use super::{
         iso_year_start, max_date_time, next_good_month, next_good_month_by_weekday, AddTime,
         CyclicTimeTo, TimeStart, TimeTo,
     },
-    errors::EventError,
+    errors::RecurrenceError,
     models::TimeRange,
 };
 
@@ -20,30 +20,49 @@ pub struct EventRangeData {
     pub interval: u32,
 }
 
-pub fn get_daily_events(range_data: EventRangeData) -> Result<Vec<TimeRange>, EventError> {
-    let day_amount = (range_data.range.start - range_data.event_range.end).whole_days();
+pub fn get_daily_events(range_data: EventRangeData) -> Result<Vec<TimeRange>, RecurrenceError> {
+    get_fixed_step_events(range_data, Duration::DAY)
+}
+
+pub fn get_hourly_events(range_data: EventRangeData) -> Result<Vec<TimeRange>, RecurrenceError> {
+    get_fixed_step_events(range_data, Duration::HOUR)
+}
+
+pub fn get_minutely_events(range_data: EventRangeData) -> Result<Vec<TimeRange>, RecurrenceError> {
+    get_fixed_step_events(range_data, Duration::MINUTE)
+}
+
+/// Entries repeating every `interval` steps of the same length, unlike weeks of a week map or months.
+fn get_fixed_step_events(
+    range_data: EventRangeData,
+    step: Duration,
+) -> Result<Vec<TimeRange>, RecurrenceError> {
+    let step_seconds = step.whole_seconds();
+    let step_amount =
+        (range_data.range.start - range_data.event_range.end).whole_seconds() / step_seconds;
     let offset_from_origin_event = max(
-        day_amount - day_amount.rem_euclid(range_data.interval as i64),
+        step_amount - step_amount.rem_euclid(range_data.interval as i64),
         0,
     )
-    .days();
+    .checked_mul(step_seconds)
+    .dc()?
+    .seconds();
+    let interval_step = (range_data.interval as i64 * step_seconds).seconds();
 
-    let mut daily_event = range_data
+    let mut event = range_data
         .event_range
         .checked_add(offset_from_origin_event)
         .dc()?;
     let mut res = Vec::new();
 
-    while !daily_event.is_after(&range_data.range)
-        && daily_event.start < range_data.rec_ends_at.unwrap_or(max_date_time())
+    while !event.is_after(&range_data.range)
+        && event.start < range_data.rec_ends_at.unwrap_or(max_date_time())
     {
-        if daily_event.is_overlapping(&range_data.range) {
-            res.push(daily_event);
+        if event.is_overlapping(&range_data.range) {
+            res.push(event);
         }
 
-        daily_event = daily_event
-            .checked_add((range_data.interval as i64).days())
-            .dc()?;
+        event = event.checked_add(interval_step).dc()?;
     }
 
     Ok(res)
@@ -52,7 +71,7 @@ pub fn get_daily_events(range_data: EventRangeData) -> Result<Vec<TimeRange>, Ev
 pub fn get_weekly_events(
     range_data: EventRangeData,
     week_map: &str,
-) -> Result<Vec<TimeRange>, EventError> {
+) -> Result<Vec<TimeRange>, RecurrenceError> {
     let week_amount = (range_data.range.start - range_data.event_range.end).whole_weeks();
     let offset_from_origin_event = max(
         week_amount - week_amount.rem_euclid(range_data.interval as i64),
@@ -99,7 +118,7 @@ pub fn get_weekly_events(
 pub fn get_monthly_events_by_day(
     range_data: EventRangeData,
     is_by_day: bool,
-) -> Result<Vec<TimeRange>, EventError> {
+) -> Result<Vec<TimeRange>, RecurrenceError> {
     let (event_end_year, event_end_month, _) = range_data.event_range.end.to_calendar_date();
     let (range_start_year, range_start_month, _) = range_data.range.start.to_calendar_date();
 
@@ -151,7 +170,7 @@ pub fn get_monthly_events_by_day(
 
 pub fn get_yearly_events_by_weekday(
     range_data: EventRangeData,
-) -> Result<Vec<TimeRange>, EventError> {
+) -> Result<Vec<TimeRange>, RecurrenceError> {
     let (range_base_year, ..) = range_data.range.start.to_iso_week_date();
     let (event_base_year, target_week_number, target_weekday) =
         range_data.event_range.start.to_iso_week_date();
@@ -194,7 +213,7 @@ pub fn get_yearly_events_by_weekday(
 mod event_range_tests {
     use time::macros::datetime;
 
-    use crate::utils::events::models::{EntriesSpan, RecurrenceRule, RecurrenceRuleKind};
+    use crate::events::models::{EntriesSpan, RecurrenceRule, RecurrenceRuleKind};
 
     use super::*;
 
@@ -236,6 +255,74 @@ mod event_range_tests {
         )
     }
 
+    #[test]
+    fn hourly_range() {
+        let event = TimeRange::new(
+            datetime!(2023-03-01 8:00 UTC),
+            datetime!(2023-03-01 8:15 UTC),
+        );
+        let rec_rules = RecurrenceRule {
+            span: Some(EntriesSpan {
+                end: datetime!(2023-03-02 8:15 UTC),
+                repetitions: 6,
+            }),
+            interval: 4,
+            kind: RecurrenceRuleKind::Hourly,
+        };
+        let part = TimeRange {
+            start: datetime!(2023-03-01 10:00 UTC),
+            end: datetime!(2023-03-01 20:00 UTC),
+        };
+
+        assert_eq!(
+            rec_rules.get_event_range(part, event).unwrap(),
+            vec![
+                TimeRange::new(
+                    datetime!(2023-03-01 12:00 UTC),
+                    datetime!(2023-03-01 12:15 UTC)
+                ),
+                TimeRange::new(
+                    datetime!(2023-03-01 16:00 UTC),
+                    datetime!(2023-03-01 16:15 UTC)
+                ),
+            ]
+        )
+    }
+
+    #[test]
+    fn minutely_range_ends_with_span() {
+        let event = TimeRange::new(
+            datetime!(2023-03-01 8:00 UTC),
+            datetime!(2023-03-01 8:05 UTC),
+        );
+        let rec_rules = RecurrenceRule {
+            span: Some(EntriesSpan {
+                end: datetime!(2023-03-01 8:50 UTC),
+                repetitions: 3,
+            }),
+            interval: 15,
+            kind: RecurrenceRuleKind::Minutely,
+        };
+        let part = TimeRange {
+            start: datetime!(2023-03-01 8:20 UTC),
+            end: datetime!(2023-03-01 10:00 UTC),
+        };
+
+        assert_eq!(
+            rec_rules.get_event_range(part, event).unwrap(),
+            vec![
+                TimeRange::new(
+                    datetime!(2023-03-01 8:30 UTC),
+                    datetime!(2023-03-01 8:35 UTC)
+                ),
+                TimeRange::new(
+                    datetime!(2023-03-01 8:45 UTC),
+                    datetime!(2023-03-01 8:50 UTC)
+                ),
+            ]
+        )
+    }
+
     #[test]
     fn weekly_range_1() {
         let event = TimeRange::new(
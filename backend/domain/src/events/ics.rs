@@ -0,0 +1,197 @@
+use time::macros::format_description;
+use time::{OffsetDateTime, UtcOffset};
+
+use crate::api::events::Events;
+
+const PRODUCT_ID: &str = "-//Bimetable//Bimetable//EN";
+/// Longest content line in octets, without the line break.
+const LINE_LIMIT: usize = 75;
+
+/// Renders event entries (with overrides applied) as an iCalendar document.
+///
+/// Every entry becomes a separate `VEVENT`, so that overridden occurrences don't need `RECURRENCE-ID`s.
+pub fn events_to_ics(events: &Events, stamp: OffsetDateTime) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{PRODUCT_ID}"),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+
+    for entry in &events.entries {
+        let Some(event) = events.events.get(&entry.event_id) else {
+            continue;
+        };
+        let recurrence_override = entry.recurrence_override.as_ref();
+        if recurrence_override.is_some_and(|ovr| ovr.is_cancelled()) {
+            continue;
+        }
+        let Some(time_range) = entry.range_with_time_override() else {
+            continue;
+        };
+
+        let name = recurrence_override
+            .and_then(|ovr| ovr.name.as_ref())
+            .unwrap_or(&event.payload.name);
+        let description = recurrence_override
+            .and_then(|ovr| ovr.description.as_ref())
+            .or(event.payload.description.as_ref());
+
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!(
+            "UID:{}-{}@bimetable",
+            entry.event_id,
+            entry.time_range.start.unix_timestamp()
+        ));
+        lines.push(format!("DTSTAMP:{}", format_time(stamp)));
+        lines.push(format!("DTSTART:{}", format_time(time_range.start)));
+        lines.push(format!("DTEND:{}", format_time(time_range.end)));
+        lines.push(format!("SUMMARY:{}", escape_text(name)));
+        if let Some(description) = description {
+            lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| fold_line(line) + "\r\n").collect()
+}
+
+fn format_time(time: OffsetDateTime) -> String {
+    let format = format_description!("[year][month][day]T[hour][minute][second]Z");
+    time.to_offset(UtcOffset::UTC)
+        .format(&format)
+        .expect("Failed to format iCalendar date")
+}
+
+/// Folds the content line into lines of at most 75 octets, continued after a line break and a space.
+///
+/// Lines are only broken between characters, so multi-octet UTF-8 characters stay whole.
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut length = 0;
+    for char in line.chars() {
+        if length + char.len_utf8() > LINE_LIMIT {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(char);
+        length += char.len_utf8();
+    }
+    folded
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod ics_tests {
+    use std::collections::HashMap;
+
+    use time::macros::datetime;
+    use uuid::uuid;
+
+    use crate::api::events::{Entry, Event, EventPayload, Override};
+    use crate::events::models::EventPrivileges;
+    use crate::events::models::TimeRange;
+
+    use super::*;
+
+    #[test]
+    fn escape_text_test() {
+        assert_eq!(
+            escape_text("a, b; c\\d\ne"),
+            "a\\, b\\; c\\\\d\\ne".to_string()
+        );
+    }
+
+    #[test]
+    fn fold_line_test() {
+        let short = "SUMMARY:Fizyka";
+        assert_eq!(fold_line(short), short);
+
+        let line = format!("DESCRIPTION:{}", "Zażółć gęślą jaźń. ".repeat(6));
+        let folded = fold_line(&line);
+        assert!(folded.contains("\r\n "));
+        assert!(folded
+            .split("\r\n")
+            .all(|part| !part.is_empty() && part.len() <= LINE_LIMIT));
+        assert!(folded
+            .split("\r\n")
+            .skip(1)
+            .all(|part| part.starts_with(' ')));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+
+    #[test]
+    fn entries_to_ics() {
+        let id = uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
+        let entry = |day: u8| {
+            Entry::new(
+                id,
+                TimeRange::new(
+                    datetime!(2023-03-08 09:45 UTC).replace_day(day).unwrap(),
+                    datetime!(2023-03-08 10:30 UTC).replace_day(day).unwrap(),
+                ),
+                None,
+            )
+        };
+        let mut renamed = entry(9);
+        renamed.recurrence_override = Some(Override {
+            name: Some("Fizyka, laborki".to_string()),
+            description: None,
+            starts_at: None,
+            ends_at: None,
+            deleted_at: None,
+            status: None,
+            created_at: datetime!(2023-03-01 12:00 UTC),
+            added_participants: vec![],
+            excluded_participants: vec![],
+        });
+        let events = Events::new(
+            HashMap::from([(
+                id,
+                Event::new(
+                    EventPrivileges::Owned,
+                    EventPayload::new("Fizyka".to_string(), None),
+                    None,
+                    datetime!(2023-03-08 09:45 UTC),
+                    None,
+                ),
+            )]),
+            vec![entry(8), renamed],
+        );
+
+        assert_eq!(
+            events_to_ics(&events, datetime!(2023-03-01 12:00 UTC)),
+            [
+                "BEGIN:VCALENDAR",
+                "VERSION:2.0",
+                "PRODID:-//Bimetable//Bimetable//EN",
+                "CALSCALE:GREGORIAN",
+                "BEGIN:VEVENT",
+                "UID:fd1dcdf7-de06-4aad-ba6e-f2097217a5b1-1678268700@bimetable",
+                "DTSTAMP:20230301T120000Z",
+                "DTSTART:20230308T094500Z",
+                "DTEND:20230308T103000Z",
+                "SUMMARY:Fizyka",
+                "END:VEVENT",
+                "BEGIN:VEVENT",
+                "UID:fd1dcdf7-de06-4aad-ba6e-f2097217a5b1-1678355100@bimetable",
+                "DTSTAMP:20230301T120000Z",
+                "DTSTART:20230309T094500Z",
+                "DTEND:20230309T103000Z",
+                "SUMMARY:Fizyka\\, laborki",
+                "END:VEVENT",
+                "END:VCALENDAR",
+                "",
+            ]
+            .join("\r\n")
+        );
+    }
+}
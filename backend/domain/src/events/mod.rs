@@ -0,0 +1,17 @@
+pub mod additions;
+pub mod classroom;
+pub mod count_to_until;
+pub mod errors;
+pub mod event_range;
+pub mod ics;
+pub mod models;
+pub mod near_entriies;
+pub mod normalize;
+pub mod occurrences;
+pub mod rrule;
+pub mod split;
+pub mod summary;
+pub mod time_range;
+pub mod until_to_count;
+pub mod week_start;
+pub mod xlsx;
@@ -0,0 +1,391 @@
+use crate::events::event_range::EventRangeData;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use time::Weekday;
+use tracing::trace;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+pub use super::time_range::TimeRange;
+
+use super::{
+    errors::RecurrenceError,
+    event_range::{
+        get_daily_events, get_hourly_events, get_minutely_events, get_monthly_events_by_day,
+        get_weekly_events, get_yearly_events_by_weekday,
+    },
+};
+
+pub struct EventPart {
+    pub starts_at: OffsetDateTime,
+    pub length: Option<EntriesSpan>,
+}
+
+/// Computational struct.
+///
+/// Used for generating event entries and to be stored in the db.
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq, Clone)]
+pub struct RecurrenceRule {
+    pub span: Option<EntriesSpan>,
+    pub interval: u32,
+    pub kind: RecurrenceRuleKind,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq, Clone, Copy)]
+pub struct EntriesSpan {
+    pub end: OffsetDateTime,
+    pub repetitions: u32,
+}
+
+impl RecurrenceRule {
+    pub fn from_db_data(
+        kind: Option<RecurrenceRuleKind>,
+        until: Option<OffsetDateTime>,
+        count: Option<i32>,
+        interval: Option<i32>,
+    ) -> Option<Self> {
+        kind.and_then(|rec_kind| {
+            Some(Self {
+                span: if let (Some(u), Some(c)) = (until, count) {
+                    Some(EntriesSpan {
+                        end: u,
+                        repetitions: c as u32,
+                    })
+                } else {
+                    None
+                },
+                interval: interval? as u32,
+                kind: rec_kind,
+            })
+        })
+    }
+
+    /// Returns all event occurences in a given range.
+    ///
+    /// For an event occurrence to be included in the result, it must overlap with the given range,
+    /// which means that the occurrence must end strictly after the range, and vice versa.
+    ///
+    /// ```rust
+    /// use bimetable_domain::events::models::{EntriesSpan, RecurrenceRuleKind};
+    /// use bimetable_domain::events::models::RecurrenceRule;
+    /// use bimetable_domain::events::models::TimeRange;
+    /// use time::macros::datetime;
+    /// use bimetable_domain::api::events::{RecurrenceEndsAt, RecurrenceRuleSchema, TimeRules};
+    ///
+    /// let event = TimeRange::new(
+    ///     datetime!(2023-02-17 22:45 UTC),
+    ///     datetime!(2023-02-18 0:00 UTC),
+    /// );
+    /// let rec_rules = RecurrenceRule {
+    ///     span: Some(EntriesSpan {
+    ///         end: datetime!(2100-12-31 23:59 UTC),
+    ///         repetitions: 50
+    ///     }),
+    ///     interval: 2,
+    ///     kind: RecurrenceRuleKind::Daily,
+    /// };
+    /// let part = TimeRange {
+    ///     start: datetime!(2023-02-21 0:00 UTC),
+    ///     end: datetime!(2023-02-27 22:45 UTC),
+    /// };
+    ///
+    /// assert_eq!(
+    ///     rec_rules.get_event_range(part, event).unwrap(),
+    ///     vec![
+    ///         TimeRange::new(
+    ///             datetime!(2023-02-21 22:45 UTC),
+    ///             datetime!(2023-02-22 0:00 UTC)
+    ///         ),
+    ///         TimeRange::new(
+    ///             datetime!(2023-02-23 22:45 UTC),
+    ///             datetime!(2023-02-24 0:00 UTC)
+    ///         ),
+    ///         TimeRange::new(
+    ///             datetime!(2023-02-25 22:45 UTC),
+    ///             datetime!(2023-02-26 0:00 UTC)
+    ///         ),
+    ///     ]
+    /// )
+    /// ```
+    pub fn get_event_range(
+        &self,
+        part: TimeRange,
+        event: TimeRange,
+    ) -> Result<Vec<TimeRange>, RecurrenceError> {
+        // self.validate_content()?;
+
+        let mut range_data = EventRangeData {
+            range: part,
+            event_range: event,
+            rec_ends_at: self.span.map(|x| x.end),
+            interval: self.interval,
+        };
+
+        let res = match self.kind {
+            RecurrenceRuleKind::Yearly { is_by_day: true } => {
+                // year and 12 months are the same
+                range_data.interval *= 12;
+                get_monthly_events_by_day(range_data, true)
+            }
+            RecurrenceRuleKind::Yearly { is_by_day: false } => {
+                get_yearly_events_by_weekday(range_data)
+            }
+            RecurrenceRuleKind::Monthly { is_by_day } => {
+                get_monthly_events_by_day(range_data, is_by_day)
+            }
+            RecurrenceRuleKind::Weekly { week_map } => {
+                let string_week_map = format!("{:0>7b}", week_map % 128);
+                get_weekly_events(range_data, &string_week_map)
+            }
+            RecurrenceRuleKind::Daily => get_daily_events(range_data),
+            RecurrenceRuleKind::Hourly => get_hourly_events(range_data),
+            RecurrenceRuleKind::Minutely => get_minutely_events(range_data),
+        }?;
+
+        trace!("Got {} event entries using a time range search", res.len());
+
+        Ok(res)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema, PartialEq, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum RecurrenceRuleKind {
+    #[serde(rename_all = "camelCase")]
+    Yearly { is_by_day: bool },
+    #[serde(rename_all = "camelCase")]
+    Monthly { is_by_day: bool },
+    #[serde(rename_all = "camelCase")]
+    Weekly {
+        /// Days of the week with an occurrence, stored as a Monday-first bitmap
+        #[serde(rename = "days", with = "week_days")]
+        #[schema(value_type = Vec<DayOfWeek>, example = json!(["wednesday", "thursday"]))]
+        week_map: u8,
+    },
+    #[serde(rename_all = "camelCase")]
+    Daily,
+    /// Entries within a day, e.g. medication reminders, such rules must end
+    #[serde(rename_all = "camelCase")]
+    Hourly,
+    #[serde(rename_all = "camelCase")]
+    Minutely,
+}
+
+impl RecurrenceRuleKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RecurrenceRuleKind::Yearly { .. } => "yearly",
+            RecurrenceRuleKind::Monthly { .. } => "monthly",
+            RecurrenceRuleKind::Weekly { .. } => "weekly",
+            RecurrenceRuleKind::Daily => "daily",
+            RecurrenceRuleKind::Hourly => "hourly",
+            RecurrenceRuleKind::Minutely => "minutely",
+        }
+    }
+
+    /// Whether entries repeat within a day.
+    pub fn is_sub_daily(&self) -> bool {
+        matches!(
+            self,
+            RecurrenceRuleKind::Hourly | RecurrenceRuleKind::Minutely
+        )
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, ToSchema, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum DayOfWeek {
+    #[default]
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl DayOfWeek {
+    pub fn number_days_from_monday(self) -> u8 {
+        Weekday::from(self).number_days_from_monday()
+    }
+
+    pub fn from_days_from_monday(days: i16) -> Option<Self> {
+        match days {
+            0 => Some(Self::Monday),
+            1 => Some(Self::Tuesday),
+            2 => Some(Self::Wednesday),
+            3 => Some(Self::Thursday),
+            4 => Some(Self::Friday),
+            5 => Some(Self::Saturday),
+            6 => Some(Self::Sunday),
+            _ => None,
+        }
+    }
+}
+
+/// Converts days of the week to a Monday-first week map.
+pub fn week_map_from_days(days: &[DayOfWeek]) -> u8 {
+    days.iter()
+        .fold(0, |map, day| map | 1 << (6 - day.number_days_from_monday()))
+}
+
+/// Converts a Monday-first week map to days of the week.
+pub fn days_from_week_map(week_map: u8) -> Vec<DayOfWeek> {
+    (0..7)
+        .filter(|day| week_map & 1 << (6 - day) != 0)
+        .filter_map(DayOfWeek::from_days_from_monday)
+        .collect()
+}
+
+/// (De)serializes a week map as an array of days of the week.
+mod week_days {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{days_from_week_map, week_map_from_days, DayOfWeek};
+
+    pub fn serialize<S: Serializer>(week_map: &u8, serializer: S) -> Result<S::Ok, S::Error> {
+        days_from_week_map(*week_map).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+        let days = Vec::<DayOfWeek>::deserialize(deserializer)?;
+        let week_map = week_map_from_days(&days);
+
+        if days.is_empty() {
+            return Err(D::Error::custom("weekly recurrence needs a day"));
+        }
+        if week_map.count_ones() as usize != days.len() {
+            return Err(D::Error::custom("days of the week must not repeat"));
+        }
+
+        Ok(week_map)
+    }
+}
+
+impl From<DayOfWeek> for Weekday {
+    fn from(val: DayOfWeek) -> Self {
+        match val {
+            DayOfWeek::Monday => Weekday::Monday,
+            DayOfWeek::Tuesday => Weekday::Tuesday,
+            DayOfWeek::Wednesday => Weekday::Wednesday,
+            DayOfWeek::Thursday => Weekday::Thursday,
+            DayOfWeek::Friday => Weekday::Friday,
+            DayOfWeek::Saturday => Weekday::Saturday,
+            DayOfWeek::Sunday => Weekday::Sunday,
+        }
+    }
+}
+
+/// Access of the user to an event.
+#[derive(Debug)]
+pub enum EventPrivileges {
+    Owned,
+    Shared {
+        can_edit: bool,
+    },
+    /// Added to single entries through overrides
+    Guest,
+}
+
+impl EventPrivileges {
+    /// Privileges of a participant, co-owners see the event as owned.
+    pub fn of_participant(can_edit: bool, is_owner: bool) -> Self {
+        if is_owner {
+            EventPrivileges::Owned
+        } else {
+            EventPrivileges::Shared { can_edit }
+        }
+    }
+
+    pub fn can_edit(&self) -> bool {
+        match self {
+            EventPrivileges::Owned => true,
+            EventPrivileges::Shared { can_edit } => *can_edit,
+            EventPrivileges::Guest => false,
+        }
+    }
+}
+
+/// How overrides covering the same entry are combined.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum OverrideStrategy {
+    /// The latest created override applies
+    #[default]
+    Latest,
+    /// Later overrides only replace the fields they set, participants are combined
+    Merge,
+    /// Like `latest`, but new overrides may not cover a whole existing override
+    Reject,
+}
+
+impl OverrideStrategy {
+    pub fn from_code(code: i16) -> Option<Self> {
+        match code {
+            0 => Some(Self::Latest),
+            1 => Some(Self::Merge),
+            2 => Some(Self::Reject),
+            _ => None,
+        }
+    }
+
+    pub fn code(self) -> i16 {
+        match self {
+            Self::Latest => 0,
+            Self::Merge => 1,
+            Self::Reject => 2,
+        }
+    }
+}
+
+/// Reason of an override, so that clients can tell changed entries apart without reading descriptions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum OverrideStatus {
+    /// The entry takes place at another time
+    Moved,
+    /// The entry doesn't take place
+    Cancelled,
+    /// Someone else leads the entry, or it is about something else
+    Substituted,
+    /// The entry takes place elsewhere, described by the override description
+    RoomChange,
+}
+
+impl OverrideStatus {
+    pub fn from_code(code: i16) -> Option<Self> {
+        match code {
+            0 => Some(Self::Moved),
+            1 => Some(Self::Cancelled),
+            2 => Some(Self::Substituted),
+            3 => Some(Self::RoomChange),
+            _ => None,
+        }
+    }
+
+    pub fn code(self) -> i16 {
+        match self {
+            Self::Moved => 0,
+            Self::Cancelled => 1,
+            Self::Substituted => 2,
+            Self::RoomChange => 3,
+        }
+    }
+}
+
+/// Which part of the user's events to fetch.
+#[derive(Debug, Default, Clone)]
+pub struct EntriesPage {
+    pub cursor: Option<OffsetDateTime>,
+    pub limit: Option<usize>,
+    pub events_only: bool,
+    pub effective: bool,
+    pub all_overrides: bool,
+    /// Adds soft deleted events of the user
+    pub include_deleted: bool,
+    /// Only fetches these events, all of them when unset
+    pub event_ids: Option<Vec<Uuid>>,
+    /// Leaves descriptions of the events and their overrides out of the selects
+    pub names_only: bool,
+}
@@ -1,14 +1,14 @@
-use crate::utils::events::count_to_until::count_to_until;
-use crate::utils::events::errors::EventError;
-use crate::utils::events::models::{RecurrenceRule, TimeRange};
-use crate::utils::events::until_to_count::until_to_count;
+use crate::events::count_to_until::count_to_until;
+use crate::events::errors::RecurrenceError;
+use crate::events::models::{RecurrenceRule, TimeRange};
+use crate::events::until_to_count::until_to_count;
 use time::OffsetDateTime;
 
 pub fn raw_prev_entry(
     provided_time: OffsetDateTime,
     first_entry: TimeRange,
     rule: &RecurrenceRule,
-) -> Result<TimeRange, EventError> {
+) -> Result<TimeRange, RecurrenceError> {
     let count = until_to_count(
         provided_time,
         first_entry.start,
@@ -31,7 +31,7 @@ pub fn raw_next_entry(
     provided_time: OffsetDateTime,
     first_entry: TimeRange,
     rule: &RecurrenceRule,
-) -> Result<TimeRange, EventError> {
+) -> Result<TimeRange, RecurrenceError> {
     let count = until_to_count(
         provided_time,
         first_entry.start,
@@ -54,10 +54,13 @@ pub fn prev_entry(
     provided_time: OffsetDateTime,
     first_entry: TimeRange,
     rule: &RecurrenceRule,
-) -> Result<Option<TimeRange>, EventError> {
+) -> Result<Option<TimeRange>, RecurrenceError> {
     if provided_time < first_entry.start {
         return Ok(None);
     };
+    if provided_time < first_entry.end {
+        return Ok(Some(first_entry));
+    }
 
     let prev_entry = raw_prev_entry(provided_time, first_entry, rule)?;
     let next_entry = raw_next_entry(provided_time, first_entry, rule)?;
@@ -82,7 +85,7 @@ pub fn next_entry(
     provided_time: OffsetDateTime,
     first_entry: TimeRange,
     rule: &RecurrenceRule,
-) -> Result<Option<TimeRange>, EventError> {
+) -> Result<Option<TimeRange>, RecurrenceError> {
     if provided_time < first_entry.start {
         return Ok(Some(first_entry));
     };
@@ -106,7 +109,7 @@ pub fn next_entry(
 mod entry_tests {
     use time::macros::datetime;
 
-    use crate::utils::events::models::{EntriesSpan, RecurrenceRuleKind};
+    use crate::events::models::{EntriesSpan, RecurrenceRuleKind};
 
     use super::*;
 
@@ -133,6 +136,15 @@ mod entry_tests {
         );
     }
 
+    #[test]
+    fn prev_entry_test_time_on_first_entry() {
+        let provided_time = datetime!(2022-12-01 12:30:00 +0000);
+        assert_eq!(
+            prev_entry(provided_time, TEST_FIRST_ENTRY, &TEST_RULE).unwrap(),
+            Some(TEST_FIRST_ENTRY)
+        );
+    }
+
     #[test]
     fn prev_entry_test_time_on_entry() {
         let provided_time = datetime!(2023-02-01 12:00:00 +0000);
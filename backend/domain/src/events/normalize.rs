@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+use time::Duration;
+use utoipa::ToSchema;
+
+use crate::api::events::CreateEvent;
+
+use super::models::{RecurrenceRuleKind, TimeRange};
+
+/// Last day of the month present in every month.
+const LAST_COMMON_MONTH_DAY: u8 = 28;
+
+/// Change made to the first occurrence of an event to put it on its recurrence rule grid.
+#[derive(Debug, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AnchorAdjustment {
+    pub reason: String,
+    pub original: TimeRange,
+    pub adjusted: TimeRange,
+}
+
+/// Moves the first occurrence of a recurring event to where its rule produces entries.
+///
+/// - a weekly event starting on a day outside of its week map moves forward to the next
+///   day of the week map,
+/// - a monthly by day event starting after the 28th, which skips shorter months,
+///   moves back to the 28th.
+///
+/// The duration of the event is kept, `None` means the event is left untouched.
+pub fn normalize_anchor(event: &mut CreateEvent) -> Option<AnchorAdjustment> {
+    let rule = event.recurrence_rule.as_ref()?;
+    let original = TimeRange::new(event.data.starts_at, event.data.ends_at);
+    let weekday = original.start.weekday().number_days_from_monday();
+
+    let (shift, reason) = match rule.kind {
+        RecurrenceRuleKind::Weekly { week_map } if week_map & 1 << (6 - weekday) == 0 => {
+            let days = (1..7).find(|days| week_map & 1 << (6 - (weekday + days) % 7) != 0)?;
+            (
+                Duration::days(days.into()),
+                "Weekly event starts on a day outside of its week map",
+            )
+        }
+        RecurrenceRuleKind::Monthly { is_by_day: true }
+            if original.start.day() > LAST_COMMON_MONTH_DAY =>
+        {
+            let days = original.start.day() - LAST_COMMON_MONTH_DAY;
+            (
+                -Duration::days(days.into()),
+                "Monthly event starts on a day missing from shorter months",
+            )
+        }
+        _ => return None,
+    };
+
+    let adjusted = original.checked_add(shift)?;
+    event.data.starts_at = adjusted.start;
+    event.data.ends_at = adjusted.end;
+
+    Some(AnchorAdjustment {
+        reason: reason.to_string(),
+        original,
+        adjusted,
+    })
+}
+
+#[cfg(test)]
+mod normalize_tests {
+    use time::macros::datetime;
+    use time::OffsetDateTime;
+
+    use crate::api::events::{EventData, EventPayload, RecurrenceRuleSchema, TimeRules};
+
+    use super::*;
+
+    fn event(
+        starts_at: OffsetDateTime,
+        ends_at: OffsetDateTime,
+        kind: RecurrenceRuleKind,
+    ) -> CreateEvent {
+        CreateEvent {
+            data: EventData {
+                payload: EventPayload::new("Fizyka".to_string(), None),
+                starts_at,
+                ends_at,
+            },
+            recurrence_rule: Some(RecurrenceRuleSchema {
+                time_rules: TimeRules {
+                    ends_at: None,
+                    interval: 1,
+                },
+                kind,
+            }),
+        }
+    }
+
+    #[test]
+    fn weekly_event_moves_to_next_week_map_day() {
+        // Wednesday, repeated on Mondays and Tuesdays
+        let mut data = event(
+            datetime!(2023-03-01 9:45 UTC),
+            datetime!(2023-03-01 10:30 UTC),
+            RecurrenceRuleKind::Weekly { week_map: 96 },
+        );
+
+        let adjustment = normalize_anchor(&mut data).unwrap();
+
+        assert_eq!(
+            adjustment.adjusted,
+            TimeRange::new(
+                datetime!(2023-03-06 9:45 UTC),
+                datetime!(2023-03-06 10:30 UTC)
+            )
+        );
+        assert_eq!(data.data.starts_at, datetime!(2023-03-06 9:45 UTC));
+    }
+
+    #[test]
+    fn weekly_event_on_week_map_day_is_untouched() {
+        let mut data = event(
+            datetime!(2023-03-01 9:45 UTC),
+            datetime!(2023-03-01 10:30 UTC),
+            RecurrenceRuleKind::Weekly { week_map: 24 },
+        );
+
+        assert_eq!(normalize_anchor(&mut data), None);
+        assert_eq!(data.data.starts_at, datetime!(2023-03-01 9:45 UTC));
+    }
+
+    #[test]
+    fn monthly_event_moves_to_common_day() {
+        let mut data = event(
+            datetime!(2023-01-31 23:00 UTC),
+            datetime!(2023-02-01 1:00 UTC),
+            RecurrenceRuleKind::Monthly { is_by_day: true },
+        );
+
+        let adjustment = normalize_anchor(&mut data).unwrap();
+
+        assert_eq!(
+            adjustment.adjusted,
+            TimeRange::new(
+                datetime!(2023-01-28 23:00 UTC),
+                datetime!(2023-01-29 1:00 UTC)
+            )
+        );
+        assert_eq!(
+            normalize_anchor(&mut event(
+                datetime!(2023-01-31 23:00 UTC),
+                datetime!(2023-02-01 1:00 UTC),
+                RecurrenceRuleKind::Monthly { is_by_day: false },
+            )),
+            None
+        );
+    }
+}
@@ -0,0 +1,121 @@
+//! Stable identifiers of entries, derived from their event and original start.
+
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+use crate::events::errors::RecurrenceError;
+use crate::events::models::{RecurrenceRule, TimeRange};
+use crate::limits::RepetitionLimit;
+
+/// Length of the parts in which entries are expanded while looking for an occurrence.
+const SEARCH_STEP: Duration = Duration::days(366);
+
+/// Identifier of the entry of the event originally starting at `starts_at`.
+///
+/// Name-based UUID, so an entry gets the same id on every expansion, wherever overrides move it.
+pub fn occurrence_id(event_id: Uuid, starts_at: OffsetDateTime) -> Uuid {
+    Uuid::new_v5(&event_id, &starts_at.unix_timestamp_nanos().to_be_bytes())
+}
+
+/// Finds the original time range of the entry with the occurrence id.
+///
+/// Only the first `limit` entries are searched, so that infinite events are never expanded without end.
+pub fn find_occurrence(
+    event_id: Uuid,
+    event: TimeRange,
+    rule: Option<&RecurrenceRule>,
+    id: Uuid,
+    limit: RepetitionLimit,
+) -> Result<TimeRange, RecurrenceError> {
+    let Some(rule) = rule else {
+        return (occurrence_id(event_id, event.start) == id)
+            .then_some(event)
+            .ok_or(RecurrenceError::NotFound);
+    };
+
+    let entries_end = rule.span.map(|span| span.end);
+    let mut part_start = event.start;
+    let mut searched = 0;
+    while searched < limit.0 as usize && entries_end.is_none_or(|end| part_start < end) {
+        let Some(part_end) = part_start.checked_add(SEARCH_STEP) else {
+            break;
+        };
+        let part_end = entries_end.map_or(part_end, |end| part_end.min(end));
+        let entries = rule.get_event_range(TimeRange::new(part_start, part_end), event)?;
+        if let Some(entry) = entries
+            .iter()
+            .find(|entry| occurrence_id(event_id, entry.start) == id)
+        {
+            return Ok(*entry);
+        }
+        searched += entries.len();
+        part_start = part_end;
+    }
+    Err(RecurrenceError::NotFound)
+}
+
+#[cfg(test)]
+mod occurrences_tests {
+    use time::macros::datetime;
+    use uuid::uuid;
+
+    use super::*;
+    use crate::events::models::{EntriesSpan, RecurrenceRuleKind};
+
+    const EVENT_ID: Uuid = uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
+    const EVENT: TimeRange = TimeRange {
+        start: datetime!(2023-03-01 10:00 UTC),
+        end: datetime!(2023-03-01 11:00 UTC),
+    };
+
+    #[test]
+    fn occurrence_ids_are_stable() {
+        let id = occurrence_id(EVENT_ID, EVENT.start);
+
+        assert_eq!(id, occurrence_id(EVENT_ID, EVENT.start));
+        assert_ne!(id, occurrence_id(EVENT_ID, EVENT.end));
+        assert_ne!(id, occurrence_id(Uuid::nil(), EVENT.start));
+    }
+
+    #[test]
+    fn finds_entry_of_occurrence() {
+        let rule = RecurrenceRule {
+            span: None,
+            interval: 1,
+            kind: RecurrenceRuleKind::Daily,
+        };
+        let entry = TimeRange::new(
+            datetime!(2025-03-01 10:00 UTC),
+            datetime!(2025-03-01 11:00 UTC),
+        );
+        let id = occurrence_id(EVENT_ID, entry.start);
+
+        assert_eq!(
+            find_occurrence(EVENT_ID, EVENT, Some(&rule), id, RepetitionLimit::default()).unwrap(),
+            entry
+        );
+        assert!(find_occurrence(EVENT_ID, EVENT, Some(&rule), id, RepetitionLimit(100)).is_err());
+    }
+
+    #[test]
+    fn finds_only_entries_of_event() {
+        let rule = RecurrenceRule {
+            span: Some(EntriesSpan {
+                end: datetime!(2023-03-03 11:00 UTC),
+                repetitions: 2,
+            }),
+            interval: 1,
+            kind: RecurrenceRuleKind::Daily,
+        };
+        let limit = RepetitionLimit::default();
+
+        let id = occurrence_id(EVENT_ID, datetime!(2023-03-04 10:00 UTC));
+        assert!(find_occurrence(EVENT_ID, EVENT, Some(&rule), id, limit).is_err());
+
+        let id = occurrence_id(EVENT_ID, EVENT.start);
+        assert_eq!(
+            find_occurrence(EVENT_ID, EVENT, None, id, limit).unwrap(),
+            EVENT
+        );
+    }
+}
@@ -0,0 +1,501 @@
+use std::collections::HashMap;
+
+use time::macros::format_description;
+use time::{Date, OffsetDateTime, PrimitiveDateTime, UtcOffset, Weekday};
+
+use crate::api::events::{RecurrenceEndsAt, RecurrenceRuleSchema, TimeRules};
+use crate::events::models::{
+    days_from_week_map, week_map_from_days, DayOfWeek, RecurrenceRule, RecurrenceRuleKind,
+    TimeRange,
+};
+use crate::validation::ValidateContentError;
+
+/// Writes a recurrence rule as an RFC 5545 `RRULE` value, e.g. `FREQ=WEEKLY;BYDAY=TU,TH;COUNT=16`.
+///
+/// `starts_at` is the start of the first entry, which fixes the day of monthly and yearly rules.
+pub fn to_rrule(rule: &RecurrenceRule, starts_at: OffsetDateTime) -> String {
+    let mut parts = vec![format!("FREQ={}", freq(&rule.kind))];
+    if rule.interval != 1 {
+        parts.push(format!("INTERVAL={}", rule.interval));
+    }
+
+    match rule.kind {
+        RecurrenceRuleKind::Daily | RecurrenceRuleKind::Hourly | RecurrenceRuleKind::Minutely => (),
+        RecurrenceRuleKind::Weekly { week_map } => {
+            let days: Vec<&str> = days_from_week_map(week_map)
+                .into_iter()
+                .map(|day| weekday_code(day.into()))
+                .collect();
+            parts.push(format!("BYDAY={}", days.join(",")));
+        }
+        RecurrenceRuleKind::Monthly { is_by_day: true } => {
+            parts.push(format!("BYMONTHDAY={}", starts_at.day()));
+        }
+        RecurrenceRuleKind::Monthly { is_by_day: false } => {
+            parts.push(format!(
+                "BYDAY={}{}",
+                weekday_ordinal(starts_at),
+                weekday_code(starts_at.weekday())
+            ));
+        }
+        RecurrenceRuleKind::Yearly { is_by_day: true } => {
+            parts.push(format!("BYMONTH={}", starts_at.month() as u8));
+            parts.push(format!("BYMONTHDAY={}", starts_at.day()));
+        }
+        RecurrenceRuleKind::Yearly { is_by_day: false } => {
+            parts.push(format!("BYWEEKNO={}", starts_at.iso_week()));
+            parts.push(format!("BYDAY={}", weekday_code(starts_at.weekday())));
+        }
+    }
+
+    // Repetitions don't include the first entry, COUNT does
+    if let Some(span) = rule.span {
+        parts.push(format!("COUNT={}", span.repetitions + 1));
+    }
+
+    parts.join(";")
+}
+
+/// Reads an RFC 5545 `RRULE` value of an event, with or without the `RRULE:` prefix.
+///
+/// Only rules expressible by [`RecurrenceRuleKind`] are accepted, so the `BY*` parts
+/// have to agree with the start of the event.
+pub fn from_rrule(
+    rrule: &str,
+    event: &TimeRange,
+) -> Result<RecurrenceRuleSchema, ValidateContentError> {
+    let rrule = rrule.trim();
+    let rrule = rrule.strip_prefix("RRULE:").unwrap_or(rrule);
+    let mut parts: HashMap<String, String> = HashMap::new();
+    for part in rrule.split(';').filter(|part| !part.is_empty()) {
+        let (name, value) = part
+            .split_once('=')
+            .ok_or_else(|| ValidateContentError::new(format!("Invalid RRULE part {part}")))?;
+        if parts
+            .insert(name.to_uppercase(), value.to_uppercase())
+            .is_some()
+        {
+            return Err(ValidateContentError::new(format!(
+                "Repeated RRULE part {name}"
+            )));
+        }
+    }
+
+    let freq = parts
+        .remove("FREQ")
+        .ok_or_else(|| ValidateContentError::new("RRULE is missing FREQ"))?;
+    let interval = match parts.remove("INTERVAL") {
+        Some(interval) => interval
+            .parse::<u32>()
+            .ok()
+            .filter(|interval| *interval > 0)
+            .ok_or_else(|| ValidateContentError::new(format!("Invalid INTERVAL {interval}")))?,
+        None => 1,
+    };
+    let ends_at = match (parts.remove("COUNT"), parts.remove("UNTIL")) {
+        (Some(_), Some(_)) => {
+            return Err(ValidateContentError::new(
+                "RRULE cannot have both COUNT and UNTIL",
+            ))
+        }
+        (Some(count), None) => {
+            let count = count
+                .parse::<u32>()
+                .ok()
+                .filter(|count| *count > 0)
+                .ok_or_else(|| ValidateContentError::new(format!("Invalid COUNT {count}")))?;
+            Some(RecurrenceEndsAt::Count(count - 1))
+        }
+        // UNTIL bounds the start of the last entry, ours bounds its end
+        (None, Some(until)) => Some(RecurrenceEndsAt::Until(
+            parse_until(&until, event.start.offset())? + event.duration(),
+        )),
+        (None, None) => None,
+    };
+    if parts.remove("WKST").is_some_and(|wkst| wkst != "MO") {
+        return Err(ValidateContentError::new("Only WKST=MO is supported"));
+    }
+
+    let start = event.start;
+    let kind = match freq.as_str() {
+        "DAILY" => RecurrenceRuleKind::Daily,
+        "HOURLY" => RecurrenceRuleKind::Hourly,
+        "MINUTELY" => RecurrenceRuleKind::Minutely,
+        "WEEKLY" => {
+            let days = match parts.remove("BYDAY") {
+                Some(days) => days
+                    .split(',')
+                    .map(|day| parse_weekday(day).map(day_of_week))
+                    .collect::<Result<Vec<DayOfWeek>, _>>()?,
+                None => vec![day_of_week(start.weekday())],
+            };
+            RecurrenceRuleKind::Weekly {
+                week_map: week_map_from_days(&days),
+            }
+        }
+        "MONTHLY" => match (parts.remove("BYMONTHDAY"), parts.remove("BYDAY")) {
+            (Some(_), Some(_)) => {
+                return Err(ValidateContentError::new(
+                    "RRULE cannot have both BYMONTHDAY and BYDAY",
+                ))
+            }
+            (Some(day), None) => {
+                expect_part("BYMONTHDAY", &day, start.day())?;
+                RecurrenceRuleKind::Monthly { is_by_day: true }
+            }
+            (None, Some(day)) => {
+                let expected = format!(
+                    "{}{}",
+                    weekday_ordinal(start),
+                    weekday_code(start.weekday())
+                );
+                expect_part("BYDAY", &day, expected)?;
+                RecurrenceRuleKind::Monthly { is_by_day: false }
+            }
+            (None, None) => RecurrenceRuleKind::Monthly { is_by_day: true },
+        },
+        "YEARLY" => match parts.remove("BYWEEKNO") {
+            Some(week) => {
+                expect_part("BYWEEKNO", &week, start.iso_week())?;
+                let day = parts.remove("BYDAY").unwrap_or_default();
+                expect_part("BYDAY", &day, weekday_code(start.weekday()))?;
+                RecurrenceRuleKind::Yearly { is_by_day: false }
+            }
+            None => {
+                if let Some(month) = parts.remove("BYMONTH") {
+                    expect_part("BYMONTH", &month, start.month() as u8)?;
+                }
+                if let Some(day) = parts.remove("BYMONTHDAY") {
+                    expect_part("BYMONTHDAY", &day, start.day())?;
+                }
+                RecurrenceRuleKind::Yearly { is_by_day: true }
+            }
+        },
+        other => {
+            return Err(ValidateContentError::new(format!(
+                "Unsupported FREQ {other}"
+            )))
+        }
+    };
+
+    if let Some(name) = parts.keys().next() {
+        return Err(ValidateContentError::new(format!(
+            "Unsupported RRULE part {name}"
+        )));
+    }
+
+    Ok(RecurrenceRuleSchema {
+        time_rules: TimeRules { ends_at, interval },
+        kind,
+    })
+}
+
+fn freq(kind: &RecurrenceRuleKind) -> &'static str {
+    match kind {
+        RecurrenceRuleKind::Yearly { .. } => "YEARLY",
+        RecurrenceRuleKind::Monthly { .. } => "MONTHLY",
+        RecurrenceRuleKind::Weekly { .. } => "WEEKLY",
+        RecurrenceRuleKind::Daily => "DAILY",
+        RecurrenceRuleKind::Hourly => "HOURLY",
+        RecurrenceRuleKind::Minutely => "MINUTELY",
+    }
+}
+
+/// Which occurrence of its weekday in the month the date is, from 1 to 5.
+fn weekday_ordinal(time: OffsetDateTime) -> u8 {
+    (time.day() - 1) / 7 + 1
+}
+
+fn weekday_code(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Monday => "MO",
+        Weekday::Tuesday => "TU",
+        Weekday::Wednesday => "WE",
+        Weekday::Thursday => "TH",
+        Weekday::Friday => "FR",
+        Weekday::Saturday => "SA",
+        Weekday::Sunday => "SU",
+    }
+}
+
+fn day_of_week(day: Weekday) -> DayOfWeek {
+    DayOfWeek::from_days_from_monday(day.number_days_from_monday().into())
+        .expect("Weekday is within a week")
+}
+
+fn parse_weekday(code: &str) -> Result<Weekday, ValidateContentError> {
+    let day = match code {
+        "MO" => Weekday::Monday,
+        "TU" => Weekday::Tuesday,
+        "WE" => Weekday::Wednesday,
+        "TH" => Weekday::Thursday,
+        "FR" => Weekday::Friday,
+        "SA" => Weekday::Saturday,
+        "SU" => Weekday::Sunday,
+        other => {
+            return Err(ValidateContentError::new(format!(
+                "Unsupported BYDAY {other}"
+            )))
+        }
+    };
+    Ok(day)
+}
+
+fn expect_part(
+    name: &str,
+    value: &str,
+    expected: impl ToString,
+) -> Result<(), ValidateContentError> {
+    if value != expected.to_string() {
+        return Err(ValidateContentError::new(format!(
+            "{name}={value} does not match the event start"
+        )));
+    }
+    Ok(())
+}
+
+/// Reads `UNTIL` as a UTC date-time, or as a date in the offset of the event.
+fn parse_until(until: &str, offset: UtcOffset) -> Result<OffsetDateTime, ValidateContentError> {
+    let invalid = || ValidateContentError::new(format!("Invalid UNTIL {until}"));
+    if let Some(time) = until.strip_suffix('Z') {
+        let format = format_description!("[year][month][day]T[hour][minute][second]");
+        return PrimitiveDateTime::parse(time, &format)
+            .map(PrimitiveDateTime::assume_utc)
+            .map_err(|_| invalid());
+    }
+
+    let format = format_description!("[year][month][day]");
+    let date = Date::parse(until, &format).map_err(|_| invalid())?;
+    // A date includes entries starting at any time of that day
+    Ok(date
+        .next_day()
+        .ok_or_else(invalid)?
+        .midnight()
+        .assume_offset(offset)
+        - time::Duration::nanoseconds(1))
+}
+
+#[cfg(test)]
+mod rrule_tests {
+    use time::macros::datetime;
+    use time::Duration;
+
+    use crate::validation::ValidateContent;
+
+    use super::*;
+
+    fn event(start: OffsetDateTime) -> TimeRange {
+        TimeRange::new(start, start + Duration::minutes(95))
+    }
+
+    fn rule(
+        kind: RecurrenceRuleKind,
+        interval: u32,
+        count: Option<u32>,
+        event: &TimeRange,
+    ) -> RecurrenceRule {
+        RecurrenceRuleSchema {
+            time_rules: TimeRules {
+                ends_at: count.map(RecurrenceEndsAt::Count),
+                interval,
+            },
+            kind,
+        }
+        .to_compute(event)
+        .unwrap()
+    }
+
+    /// Writes the rule out, reads it back and recomputes its span.
+    fn assert_round_trip(rule: RecurrenceRule, event: &TimeRange) -> String {
+        let rrule = to_rrule(&rule, event.start);
+        let parsed = from_rrule(&rrule, event)
+            .unwrap()
+            .to_compute(event)
+            .unwrap();
+        assert_eq!(parsed, rule, "{rrule}");
+        rrule
+    }
+
+    #[test]
+    fn daily_round_trip() {
+        let event = event(datetime!(2023-03-07 11:40 UTC));
+        for (interval, count, expected) in [
+            (1, None, "FREQ=DAILY"),
+            (3, Some(10), "FREQ=DAILY;INTERVAL=3;COUNT=11"),
+        ] {
+            let rule = rule(RecurrenceRuleKind::Daily, interval, count, &event);
+            assert_eq!(assert_round_trip(rule, &event), expected);
+        }
+    }
+
+    #[test]
+    fn sub_daily_round_trip() {
+        let event = event(datetime!(2023-03-07 8:00 UTC));
+        let hourly = rule(RecurrenceRuleKind::Hourly, 8, Some(2), &event);
+        assert_eq!(
+            assert_round_trip(hourly, &event),
+            "FREQ=HOURLY;INTERVAL=8;COUNT=3"
+        );
+        let minutely = rule(RecurrenceRuleKind::Minutely, 120, Some(5), &event);
+        assert_eq!(
+            assert_round_trip(minutely, &event),
+            "FREQ=MINUTELY;INTERVAL=120;COUNT=6"
+        );
+    }
+
+    #[test]
+    fn weekly_maps_round_trip() {
+        let event = event(datetime!(2023-03-06 11:40 UTC));
+        for week_map in 1..128 {
+            let rule = rule(RecurrenceRuleKind::Weekly { week_map }, 2, Some(15), &event);
+            assert_round_trip(rule, &event);
+        }
+
+        let rule = rule(
+            RecurrenceRuleKind::Weekly { week_map: 40 },
+            1,
+            Some(15),
+            &event,
+        );
+        assert_eq!(
+            assert_round_trip(rule, &event),
+            "FREQ=WEEKLY;BYDAY=TU,TH;COUNT=16"
+        );
+    }
+
+    #[test]
+    fn monthly_round_trip() {
+        for (start, expected_by_weekday) in [
+            (datetime!(2023-03-01 8:00 UTC), "BYDAY=1WE"),
+            (datetime!(2023-03-14 8:00 UTC), "BYDAY=2TU"),
+            (datetime!(2023-03-31 8:00 UTC), "BYDAY=5FR"),
+        ] {
+            let event = event(start);
+            let by_day = rule(
+                RecurrenceRuleKind::Monthly { is_by_day: true },
+                1,
+                Some(6),
+                &event,
+            );
+            assert_eq!(
+                assert_round_trip(by_day, &event),
+                format!("FREQ=MONTHLY;BYMONTHDAY={};COUNT=7", start.day())
+            );
+
+            let by_weekday = rule(
+                RecurrenceRuleKind::Monthly { is_by_day: false },
+                2,
+                Some(6),
+                &event,
+            );
+            assert_eq!(
+                assert_round_trip(by_weekday, &event),
+                format!("FREQ=MONTHLY;INTERVAL=2;{expected_by_weekday};COUNT=7")
+            );
+        }
+    }
+
+    #[test]
+    fn yearly_round_trip() {
+        let event = event(datetime!(2023-03-14 8:00 UTC));
+        let by_day = rule(
+            RecurrenceRuleKind::Yearly { is_by_day: true },
+            1,
+            Some(3),
+            &event,
+        );
+        assert_eq!(
+            assert_round_trip(by_day, &event),
+            "FREQ=YEARLY;BYMONTH=3;BYMONTHDAY=14;COUNT=4"
+        );
+
+        let by_weekday = rule(
+            RecurrenceRuleKind::Yearly { is_by_day: false },
+            1,
+            None,
+            &event,
+        );
+        assert_eq!(
+            assert_round_trip(by_weekday, &event),
+            "FREQ=YEARLY;BYWEEKNO=11;BYDAY=TU"
+        );
+    }
+
+    #[test]
+    fn yearly_53rd_week_round_trip() {
+        // 2020 and 2026 are the nearest years with 53 ISO weeks
+        let event = event(datetime!(2020-12-31 8:00 UTC));
+        let rule = rule(
+            RecurrenceRuleKind::Yearly { is_by_day: false },
+            1,
+            Some(1),
+            &event,
+        );
+        assert_eq!(rule.span.unwrap().end, datetime!(2026-12-31 9:35 UTC));
+        assert_eq!(
+            assert_round_trip(rule, &event),
+            "FREQ=YEARLY;BYWEEKNO=53;BYDAY=TH;COUNT=2"
+        );
+    }
+
+    #[test]
+    fn until_bounds_entry_start() {
+        let event = event(datetime!(2023-03-07 11:40 UTC));
+        let schema =
+            from_rrule("RRULE:FREQ=WEEKLY;BYDAY=TU;UNTIL=20230321T114000Z", &event).unwrap();
+        assert_eq!(
+            schema.time_rules.ends_at,
+            Some(RecurrenceEndsAt::Until(datetime!(2023-03-21 13:15 UTC)))
+        );
+        let rule = schema.to_compute(&event).unwrap();
+        assert_eq!(to_rrule(&rule, event.start), "FREQ=WEEKLY;BYDAY=TU;COUNT=3");
+
+        let schema = from_rrule("FREQ=DAILY;UNTIL=20230309", &event).unwrap();
+        let span = schema.to_compute(&event).unwrap().span.unwrap();
+        // The whole day of UNTIL is included: Mar 7, 8 and 9
+        assert_eq!(span.repetitions, 2);
+    }
+
+    #[test]
+    fn defaults_follow_event_start() {
+        let event = event(datetime!(2023-03-14 8:00 UTC));
+        let weekly = from_rrule("FREQ=WEEKLY", &event).unwrap();
+        assert_eq!(
+            weekly.kind,
+            RecurrenceRuleKind::Weekly {
+                week_map: week_map_from_days(&[DayOfWeek::Tuesday])
+            }
+        );
+        let monthly = from_rrule("freq=monthly;interval=2", &event).unwrap();
+        assert_eq!(
+            monthly.kind,
+            RecurrenceRuleKind::Monthly { is_by_day: true }
+        );
+        assert_eq!(monthly.time_rules.interval, 2);
+    }
+
+    #[test]
+    fn rejects_unsupported_rules() {
+        let event = event(datetime!(2023-03-14 8:00 UTC));
+        for rrule in [
+            "",
+            "FREQ=DAILY;INTERVAL=0",
+            "FREQ=DAILY;COUNT=3;UNTIL=20230320",
+            "FREQ=DAILY;COUNT=3;COUNT=4",
+            "FREQ=DAILY;COUNT=0",
+            "FREQ=WEEKLY;BYDAY=XX",
+            "FREQ=WEEKLY;WKST=SU",
+            "FREQ=MONTHLY;BYMONTHDAY=15",
+            "FREQ=MONTHLY;BYDAY=-1TU",
+            "FREQ=YEARLY;BYWEEKNO=12;BYDAY=TU",
+            "FREQ=YEARLY;BYMONTH=4",
+            "FREQ=DAILY;BYHOUR=8",
+        ] {
+            assert!(from_rrule(rrule, &event).is_err(), "{rrule}");
+        }
+
+        // Entries within a day must end, which is up to the callers to validate
+        let hourly = from_rrule("FREQ=HOURLY", &event).unwrap();
+        assert!(hourly.validate_content().is_err());
+    }
+}
@@ -0,0 +1,260 @@
+use time::{Duration, OffsetDateTime};
+
+use crate::api::events::{RecurrenceEndsAt, RecurrenceRuleSchema, TimeRules};
+use crate::validation::ValidateContentError;
+
+use super::{
+    count_to_until::count_to_until,
+    errors::RecurrenceError,
+    models::{RecurrenceRule, TimeRange},
+};
+
+/// Recurrence of an event divided at one of its occurrences.
+#[derive(Debug, PartialEq)]
+pub struct RecurrenceSplit {
+    /// Rule of the original event, ending with the last occurrence before the split.
+    pub truncated: RecurrenceRule,
+    /// Rule of the successor event, starting with the occurrence the split was made at.
+    pub successor: RecurrenceRuleSchema,
+}
+
+/// Splits the recurrence of an event at the occurrence starting at `split_at`.
+///
+/// Occurrences before the split point are kept untouched by the truncated rule,
+/// the rest of them (including the split one) are described by the successor rule
+/// with the same number of remaining repetitions.
+pub fn split_recurrence(
+    first_entry: TimeRange,
+    rule: &RecurrenceRule,
+    split_at: OffsetDateTime,
+) -> Result<RecurrenceSplit, RecurrenceError> {
+    let entries = rule.get_event_range(
+        TimeRange::new(first_entry.start, split_at + Duration::nanoseconds(1)),
+        first_entry,
+    )?;
+
+    let last_kept = match entries.as_slice() {
+        [.., last_kept, split] if split.start == split_at => *last_kept,
+        _ => {
+            return Err(ValidateContentError::new(
+                "Split point must be the start of an occurrence following the first one",
+            )
+            .into())
+        }
+    };
+
+    let truncated = RecurrenceRuleSchema {
+        time_rules: TimeRules {
+            ends_at: Some(RecurrenceEndsAt::Until(last_kept.end)),
+            interval: rule.interval,
+        },
+        kind: rule.kind,
+    }
+    .to_compute(&first_entry)?;
+
+    let successor_ends_at = match (rule.span, truncated.span) {
+        (Some(span), Some(truncated_span)) => Some(RecurrenceEndsAt::Count(
+            span.repetitions
+                .saturating_sub(truncated_span.repetitions)
+                .saturating_sub(1),
+        )),
+        _ => None,
+    };
+
+    Ok(RecurrenceSplit {
+        truncated,
+        successor: RecurrenceRuleSchema {
+            time_rules: TimeRules {
+                ends_at: successor_ends_at,
+                interval: rule.interval,
+            },
+            kind: rule.kind,
+        },
+    })
+}
+
+/// Pairs occurrences of the split recurrence starting from `split_at` until `until`
+/// with the occurrences of the successor replacing them, in order.
+pub fn successor_occurrences(
+    first_entry: TimeRange,
+    rule: &RecurrenceRule,
+    split_at: OffsetDateTime,
+    successor_first_entry: TimeRange,
+    until: OffsetDateTime,
+) -> Result<Vec<(TimeRange, TimeRange)>, RecurrenceError> {
+    let replaced: Vec<TimeRange> = rule
+        .get_event_range(TimeRange::new(split_at, until), first_entry)?
+        .into_iter()
+        .filter(|entry| entry.start >= split_at)
+        .collect();
+    let Some(repetitions) = replaced.len().checked_sub(1) else {
+        return Ok(Vec::new());
+    };
+
+    let successor_rule = RecurrenceRule {
+        span: None,
+        interval: rule.interval,
+        kind: rule.kind,
+    };
+    let successors_until = count_to_until(
+        repetitions as u32,
+        rule.interval,
+        successor_first_entry.start,
+        &successor_first_entry,
+        &rule.kind,
+    )?;
+    let successors = successor_rule.get_event_range(
+        TimeRange::new(successor_first_entry.start, successors_until),
+        successor_first_entry,
+    )?;
+
+    Ok(replaced.into_iter().zip(successors).collect())
+}
+
+#[cfg(test)]
+mod split_tests {
+    use time::macros::datetime;
+
+    use crate::events::models::{EntriesSpan, RecurrenceRuleKind};
+
+    use super::*;
+
+    #[test]
+    fn split_weekly_recurrence() {
+        let first_entry = TimeRange::new(
+            datetime!(2023-03-08 09:45 UTC),
+            datetime!(2023-03-08 10:30 UTC),
+        );
+        let rule = RecurrenceRule {
+            span: Some(EntriesSpan {
+                end: datetime!(2023-04-27 10:30 UTC),
+                repetitions: 15,
+            }),
+            interval: 1,
+            kind: RecurrenceRuleKind::Weekly { week_map: 24 },
+        };
+
+        let split = split_recurrence(first_entry, &rule, datetime!(2023-03-22 09:45 UTC)).unwrap();
+
+        assert_eq!(
+            split.truncated,
+            RecurrenceRule {
+                span: Some(EntriesSpan {
+                    end: datetime!(2023-03-16 10:30 UTC),
+                    repetitions: 3,
+                }),
+                interval: 1,
+                kind: RecurrenceRuleKind::Weekly { week_map: 24 },
+            }
+        );
+        assert_eq!(
+            split.successor.time_rules,
+            TimeRules {
+                ends_at: Some(RecurrenceEndsAt::Count(11)),
+                interval: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn split_infinite_recurrence() {
+        let first_entry = TimeRange::new(
+            datetime!(2023-03-01 12:00 UTC),
+            datetime!(2023-03-01 13:00 UTC),
+        );
+        let rule = RecurrenceRule {
+            span: None,
+            interval: 2,
+            kind: RecurrenceRuleKind::Daily,
+        };
+
+        let split = split_recurrence(first_entry, &rule, datetime!(2023-03-05 12:00 UTC)).unwrap();
+
+        assert_eq!(
+            split.truncated.span,
+            Some(EntriesSpan {
+                end: datetime!(2023-03-03 13:00 UTC),
+                repetitions: 1,
+            })
+        );
+        assert_eq!(split.successor.time_rules.ends_at, None);
+    }
+
+    #[test]
+    fn successor_occurrences_replace_split_ones_in_order() {
+        let first_entry = TimeRange::new(
+            datetime!(2023-03-08 09:45 UTC),
+            datetime!(2023-03-08 10:30 UTC),
+        );
+        let rule = RecurrenceRule {
+            span: Some(EntriesSpan {
+                end: datetime!(2023-04-27 10:30 UTC),
+                repetitions: 15,
+            }),
+            interval: 1,
+            kind: RecurrenceRuleKind::Weekly { week_map: 24 },
+        };
+        let successor_first_entry = TimeRange::new(
+            datetime!(2023-03-22 11:00 UTC),
+            datetime!(2023-03-22 12:00 UTC),
+        );
+
+        let pairs = successor_occurrences(
+            first_entry,
+            &rule,
+            datetime!(2023-03-22 09:45 UTC),
+            successor_first_entry,
+            datetime!(2023-03-29 10:30 UTC),
+        )
+        .unwrap();
+
+        assert_eq!(
+            pairs,
+            vec![
+                (
+                    TimeRange::new(
+                        datetime!(2023-03-22 09:45 UTC),
+                        datetime!(2023-03-22 10:30 UTC)
+                    ),
+                    successor_first_entry,
+                ),
+                (
+                    TimeRange::new(
+                        datetime!(2023-03-23 09:45 UTC),
+                        datetime!(2023-03-23 10:30 UTC)
+                    ),
+                    TimeRange::new(
+                        datetime!(2023-03-23 11:00 UTC),
+                        datetime!(2023-03-23 12:00 UTC)
+                    ),
+                ),
+                (
+                    TimeRange::new(
+                        datetime!(2023-03-29 09:45 UTC),
+                        datetime!(2023-03-29 10:30 UTC)
+                    ),
+                    TimeRange::new(
+                        datetime!(2023-03-29 11:00 UTC),
+                        datetime!(2023-03-29 12:00 UTC)
+                    ),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn split_outside_of_occurrence() {
+        let first_entry = TimeRange::new(
+            datetime!(2023-03-01 12:00 UTC),
+            datetime!(2023-03-01 13:00 UTC),
+        );
+        let rule = RecurrenceRule {
+            span: None,
+            interval: 2,
+            kind: RecurrenceRuleKind::Daily,
+        };
+
+        assert!(split_recurrence(first_entry, &rule, datetime!(2023-03-04 12:00 UTC)).is_err());
+        assert!(split_recurrence(first_entry, &rule, datetime!(2023-03-01 12:00 UTC)).is_err());
+    }
+}
@@ -0,0 +1,179 @@
+use time::macros::format_description;
+use time::{OffsetDateTime, Weekday};
+
+use crate::events::models::{days_from_week_map, RecurrenceRule, RecurrenceRuleKind};
+
+/// Describes a recurrence rule, e.g. "Every 2 weeks on Tue, Thu until 27 Apr 2023".
+///
+/// `starts_at` is the start of the first entry, which fixes the day of monthly and yearly rules.
+pub fn recurrence_summary(rule: &RecurrenceRule, starts_at: OffsetDateTime) -> String {
+    let mut summary = match rule.kind {
+        RecurrenceRuleKind::Daily => every(rule.interval, "day"),
+        RecurrenceRuleKind::Hourly => every(rule.interval, "hour"),
+        RecurrenceRuleKind::Minutely => every(rule.interval, "minute"),
+        RecurrenceRuleKind::Weekly { week_map } => {
+            let days: Vec<&str> = days_from_week_map(week_map)
+                .into_iter()
+                .map(|day| short_weekday(day.into()))
+                .collect();
+            format!("{} on {}", every(rule.interval, "week"), days.join(", "))
+        }
+        RecurrenceRuleKind::Monthly { is_by_day: true } => {
+            format!(
+                "{} on day {}",
+                every(rule.interval, "month"),
+                starts_at.day()
+            )
+        }
+        RecurrenceRuleKind::Monthly { is_by_day: false } => format!(
+            "{} on the {} {}",
+            every(rule.interval, "month"),
+            ordinal((starts_at.day() as u32 - 1) / 7 + 1),
+            short_weekday(starts_at.weekday())
+        ),
+        RecurrenceRuleKind::Yearly { is_by_day: true } => format!(
+            "{} on {}",
+            every(rule.interval, "year"),
+            starts_at
+                .format(format_description!("[day padding:none] [month repr:short]"))
+                .unwrap_or_default()
+        ),
+        RecurrenceRuleKind::Yearly { is_by_day: false } => format!(
+            "{} on {} of week {}",
+            every(rule.interval, "year"),
+            short_weekday(starts_at.weekday()),
+            starts_at.iso_week()
+        ),
+    };
+
+    if let Some(span) = rule.span {
+        let until = span
+            .end
+            .format(format_description!(
+                "[day padding:none] [month repr:short] [year]"
+            ))
+            .unwrap_or_default();
+        summary.push_str(&format!(" until {until}"));
+    }
+
+    summary
+}
+
+fn every(interval: u32, unit: &str) -> String {
+    match interval {
+        1 => format!("Every {unit}"),
+        n => format!("Every {n} {unit}s"),
+    }
+}
+
+fn ordinal(n: u32) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{n}{suffix}")
+}
+
+fn short_weekday(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Monday => "Mon",
+        Weekday::Tuesday => "Tue",
+        Weekday::Wednesday => "Wed",
+        Weekday::Thursday => "Thu",
+        Weekday::Friday => "Fri",
+        Weekday::Saturday => "Sat",
+        Weekday::Sunday => "Sun",
+    }
+}
+
+#[cfg(test)]
+mod summary_tests {
+    use time::macros::datetime;
+
+    use crate::events::models::EntriesSpan;
+
+    use super::*;
+
+    fn rule(interval: u32, kind: RecurrenceRuleKind) -> RecurrenceRule {
+        RecurrenceRule {
+            span: None,
+            interval,
+            kind,
+        }
+    }
+
+    #[test]
+    fn weekly_summary_with_end() {
+        let rule = RecurrenceRule {
+            span: Some(EntriesSpan {
+                end: datetime!(2023-04-27 13:15 UTC),
+                repetitions: 15,
+            }),
+            interval: 2,
+            kind: RecurrenceRuleKind::Weekly { week_map: 40 },
+        };
+        assert_eq!(
+            recurrence_summary(&rule, datetime!(2023-03-07 11:40 UTC)),
+            "Every 2 weeks on Tue, Thu until 27 Apr 2023"
+        );
+    }
+
+    #[test]
+    fn daily_summary() {
+        assert_eq!(
+            recurrence_summary(
+                &rule(1, RecurrenceRuleKind::Daily),
+                datetime!(2023-03-07 11:40 UTC)
+            ),
+            "Every day"
+        );
+        assert_eq!(
+            recurrence_summary(
+                &rule(6, RecurrenceRuleKind::Hourly),
+                datetime!(2023-03-07 11:40 UTC)
+            ),
+            "Every 6 hours"
+        );
+    }
+
+    #[test]
+    fn monthly_summaries() {
+        let starts_at = datetime!(2023-03-14 11:40 UTC);
+        assert_eq!(
+            recurrence_summary(
+                &rule(1, RecurrenceRuleKind::Monthly { is_by_day: true }),
+                starts_at
+            ),
+            "Every month on day 14"
+        );
+        assert_eq!(
+            recurrence_summary(
+                &rule(3, RecurrenceRuleKind::Monthly { is_by_day: false }),
+                starts_at
+            ),
+            "Every 3 months on the 2nd Tue"
+        );
+    }
+
+    #[test]
+    fn yearly_summaries() {
+        let starts_at = datetime!(2023-03-14 11:40 UTC);
+        assert_eq!(
+            recurrence_summary(
+                &rule(1, RecurrenceRuleKind::Yearly { is_by_day: true }),
+                starts_at
+            ),
+            "Every year on 14 Mar"
+        );
+        assert_eq!(
+            recurrence_summary(
+                &rule(1, RecurrenceRuleKind::Yearly { is_by_day: false }),
+                starts_at
+            ),
+            "Every year on Tue of week 11"
+        );
+    }
+}
@@ -0,0 +1,477 @@
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+use time::macros::format_description;
+use time::{Duration, OffsetDateTime, Weekday};
+use utoipa::ToSchema;
+
+use super::additions::CyclicTimeTo;
+
+#[derive(Debug, PartialEq, Clone, Copy, Deserialize, Serialize, ToSchema)]
+pub struct TimeRange {
+    pub start: OffsetDateTime,
+    pub end: OffsetDateTime,
+}
+
+impl TimeRange {
+    pub fn new(start: OffsetDateTime, end: OffsetDateTime) -> Self {
+        Self { start, end }
+    }
+
+    pub fn new_relative(start: OffsetDateTime, length: Duration) -> Self {
+        Self::new(start, start + length)
+    }
+
+    pub fn new_relative_checked(start: OffsetDateTime, length: Duration) -> Option<Self> {
+        Some(Self::new(start, start.checked_add(length)?))
+    }
+
+    pub fn checked_add(self, rhs: Duration) -> Option<Self> {
+        Some(Self::new(
+            self.start.checked_add(rhs)?,
+            self.end.checked_add(rhs)?,
+        ))
+    }
+
+    pub fn is_before(&self, other: &Self) -> bool {
+        self.end <= other.start
+    }
+
+    pub fn is_overlapping(&self, other: &Self) -> bool {
+        self.start < other.end && self.end > other.start
+    }
+
+    pub fn is_contained(&self, other: &Self) -> bool {
+        other.start <= self.start && other.end >= self.end
+    }
+
+    pub fn is_after(&self, other: &Self) -> bool {
+        self.start >= other.end
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+
+    pub fn shift(self, left: Duration, right: Duration) -> Option<Self> {
+        let res = Self::new(self.start.checked_add(left)?, self.end.checked_add(right)?);
+        if res.duration() >= Duration::seconds(0) {
+            Some(res)
+        } else {
+            None
+        }
+    }
+
+    /// Common part of both ranges, if they overlap.
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        self.is_overlapping(other)
+            .then(|| Self::new(self.start.max(other.start), self.end.min(other.end)))
+    }
+
+    /// Range covering both ranges, if they overlap or touch.
+    pub fn union(&self, other: &Self) -> Option<Self> {
+        (self.start <= other.end && other.start <= self.end)
+            .then(|| Self::new(self.start.min(other.start), self.end.max(other.end)))
+    }
+
+    /// Parts of the range not covered by `other`, at most one on each side of it.
+    pub fn difference(&self, other: &Self) -> Vec<Self> {
+        if !self.is_overlapping(other) {
+            return vec![*self];
+        }
+
+        let mut parts = Vec::with_capacity(2);
+        if self.start < other.start {
+            parts.push(Self::new(self.start, other.start));
+        }
+        if other.end < self.end {
+            parts.push(Self::new(other.end, self.end));
+        }
+        parts
+    }
+
+    /// Parts of the range not covered by any of the `busy` ranges, in order.
+    pub fn gaps(&self, busy: &[Self]) -> Vec<Self> {
+        let mut gaps = busy.iter().fold(vec![*self], |free, busy| {
+            free.iter()
+                .flat_map(|range| range.difference(busy))
+                .collect()
+        });
+        gaps.sort_by_key(|range| range.start);
+        gaps
+    }
+
+    /// Sorts the ranges and joins the ones that overlap or touch.
+    pub fn merge(mut ranges: Vec<Self>) -> Vec<Self> {
+        ranges.sort_by_key(|range| range.start);
+        let mut merged: Vec<Self> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if last.end >= range.start => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+
+    /// Splits the range at midnights in the offset of its start.
+    pub fn split_by_days(&self) -> Vec<Self> {
+        self.split_at(|time| time.date().next_day().map(|date| date.midnight()))
+    }
+
+    /// Splits the range at the starts of weeks in the offset of its start.
+    pub fn split_by_weeks(&self, week_start: Weekday) -> Vec<Self> {
+        self.split_at(|time| {
+            let days = match time.weekday().cyclic_time_to(week_start) {
+                0 => 7,
+                days => days,
+            };
+            time.date()
+                .checked_add(Duration::days(days.into()))
+                .map(|date| date.midnight())
+        })
+    }
+
+    /// Cuts the range at consecutive boundaries, an empty range stays whole.
+    fn split_at(
+        &self,
+        next_boundary: impl Fn(OffsetDateTime) -> Option<time::PrimitiveDateTime>,
+    ) -> Vec<Self> {
+        if self.start >= self.end {
+            return if self.start == self.end {
+                vec![*self]
+            } else {
+                vec![]
+            };
+        }
+
+        let offset = self.start.offset();
+        let mut parts = vec![];
+        let mut cursor = self.start;
+        while cursor < self.end {
+            let boundary = next_boundary(cursor)
+                .map(|boundary| boundary.assume_offset(offset))
+                .map_or(self.end, |boundary| boundary.min(self.end));
+            parts.push(Self::new(cursor, boundary));
+            cursor = boundary;
+        }
+        parts
+    }
+}
+
+impl Display for TimeRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let format = format_description!(
+            "[year]-[month]-[day] [hour]:[minute] [offset_hour sign:mandatory]:[offset_minute]"
+        );
+        let start = self.start.format(&format).unwrap();
+        let end = self.end.format(&format).unwrap();
+        write!(f, "{start} - {end}")
+
+        // non panic alternative?
+        // write!(
+        //     f,
+        //     "{} {}:{} {} - {} {}:{} {}",
+        //     self.start.date(),
+        //     self.start.hour(),
+        //     self.start.minute(),
+        //     self.start.offset(),
+        //     self.end.date(),
+        //     self.end.hour(),
+        //     self.end.minute(),
+        //     self.end.offset(),
+        // )
+    }
+}
+
+#[cfg(test)]
+mod time_range_tests {
+    use proptest::prelude::*;
+    use time::macros::datetime;
+    use time::UtcOffset;
+
+    use super::*;
+
+    #[test]
+    fn intersection_and_union() {
+        let a = TimeRange::new(
+            datetime!(2023-03-06 8:00 UTC),
+            datetime!(2023-03-06 10:00 UTC),
+        );
+        let b = TimeRange::new(
+            datetime!(2023-03-06 9:00 UTC),
+            datetime!(2023-03-06 11:00 UTC),
+        );
+        let c = TimeRange::new(
+            datetime!(2023-03-06 10:00 UTC),
+            datetime!(2023-03-06 12:00 UTC),
+        );
+
+        assert_eq!(
+            a.intersection(&b),
+            Some(TimeRange::new(
+                datetime!(2023-03-06 9:00 UTC),
+                datetime!(2023-03-06 10:00 UTC)
+            ))
+        );
+        assert_eq!(a.intersection(&c), None);
+        assert_eq!(
+            a.union(&c),
+            Some(TimeRange::new(
+                datetime!(2023-03-06 8:00 UTC),
+                datetime!(2023-03-06 12:00 UTC)
+            ))
+        );
+        assert_eq!(a.union(&c.checked_add(Duration::minutes(1)).unwrap()), None);
+    }
+
+    #[test]
+    fn difference_cuts_out_the_middle() {
+        let day = TimeRange::new(
+            datetime!(2023-03-06 8:00 UTC),
+            datetime!(2023-03-06 16:00 UTC),
+        );
+        let lesson = TimeRange::new(
+            datetime!(2023-03-06 9:00 UTC),
+            datetime!(2023-03-06 10:00 UTC),
+        );
+
+        assert_eq!(
+            day.difference(&lesson),
+            vec![
+                TimeRange::new(
+                    datetime!(2023-03-06 8:00 UTC),
+                    datetime!(2023-03-06 9:00 UTC)
+                ),
+                TimeRange::new(
+                    datetime!(2023-03-06 10:00 UTC),
+                    datetime!(2023-03-06 16:00 UTC)
+                ),
+            ]
+        );
+        assert!(lesson.difference(&day).is_empty());
+    }
+
+    #[test]
+    fn merge_joins_touching_ranges() {
+        let ranges = vec![
+            TimeRange::new(
+                datetime!(2023-03-06 10:00 UTC),
+                datetime!(2023-03-06 11:00 UTC),
+            ),
+            TimeRange::new(
+                datetime!(2023-03-06 8:00 UTC),
+                datetime!(2023-03-06 9:00 UTC),
+            ),
+            TimeRange::new(
+                datetime!(2023-03-06 9:00 UTC),
+                datetime!(2023-03-06 9:30 UTC),
+            ),
+            TimeRange::new(
+                datetime!(2023-03-06 10:30 UTC),
+                datetime!(2023-03-06 10:45 UTC),
+            ),
+        ];
+
+        assert_eq!(
+            TimeRange::merge(ranges),
+            vec![
+                TimeRange::new(
+                    datetime!(2023-03-06 8:00 UTC),
+                    datetime!(2023-03-06 9:30 UTC)
+                ),
+                TimeRange::new(
+                    datetime!(2023-03-06 10:00 UTC),
+                    datetime!(2023-03-06 11:00 UTC)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn split_by_days_in_start_offset() {
+        let range = TimeRange::new(
+            datetime!(2023-03-06 22:00 +2),
+            datetime!(2023-03-08 1:00 +2),
+        );
+
+        assert_eq!(
+            range.split_by_days(),
+            vec![
+                TimeRange::new(
+                    datetime!(2023-03-06 22:00 +2),
+                    datetime!(2023-03-07 0:00 +2)
+                ),
+                TimeRange::new(datetime!(2023-03-07 0:00 +2), datetime!(2023-03-08 0:00 +2)),
+                TimeRange::new(datetime!(2023-03-08 0:00 +2), datetime!(2023-03-08 1:00 +2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn split_by_weeks_from_week_start() {
+        // Wednesday to the Tuesday after next
+        let range = TimeRange::new(
+            datetime!(2023-03-08 12:00 UTC),
+            datetime!(2023-03-21 12:00 UTC),
+        );
+
+        assert_eq!(
+            range.split_by_weeks(Weekday::Sunday),
+            vec![
+                TimeRange::new(
+                    datetime!(2023-03-08 12:00 UTC),
+                    datetime!(2023-03-12 0:00 UTC)
+                ),
+                TimeRange::new(
+                    datetime!(2023-03-12 0:00 UTC),
+                    datetime!(2023-03-19 0:00 UTC)
+                ),
+                TimeRange::new(
+                    datetime!(2023-03-19 0:00 UTC),
+                    datetime!(2023-03-21 12:00 UTC)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn split_empty_range() {
+        let point = TimeRange::new(
+            datetime!(2023-03-06 8:00 UTC),
+            datetime!(2023-03-06 8:00 UTC),
+        );
+
+        assert_eq!(point.split_by_days(), vec![point]);
+        assert!(TimeRange::new(point.end, point.start - Duration::hours(1))
+            .split_by_days()
+            .is_empty());
+    }
+
+    fn arb_range() -> impl Strategy<Value = TimeRange> {
+        // Starts within a few years around 2023, lasting up to 40 days, with any whole hour offset
+        (
+            1_600_000_000i64..1_750_000_000,
+            0i64..40 * 24 * 60,
+            -12i8..=14,
+        )
+            .prop_map(|(start, minutes, hours)| {
+                let offset = UtcOffset::from_hms(hours, 0, 0).unwrap();
+                let start = OffsetDateTime::from_unix_timestamp(start)
+                    .unwrap()
+                    .to_offset(offset);
+                TimeRange::new_relative(start, Duration::minutes(minutes))
+            })
+    }
+
+    const WEEKDAYS: [Weekday; 7] = [
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+        Weekday::Sunday,
+    ];
+
+    fn total(parts: &[TimeRange]) -> Duration {
+        parts.iter().map(TimeRange::duration).sum()
+    }
+
+    fn is_contiguous(parts: &[TimeRange], whole: &TimeRange) -> bool {
+        parts
+            .first()
+            .is_some_and(|first| first.start == whole.start)
+            && parts.last().is_some_and(|last| last.end == whole.end)
+            && parts.windows(2).all(|pair| pair[0].end == pair[1].start)
+    }
+
+    proptest! {
+        #[test]
+        fn intersection_is_within_both(a in arb_range(), b in arb_range()) {
+            match a.intersection(&b) {
+                Some(common) => {
+                    prop_assert!(common.is_contained(&a) && common.is_contained(&b));
+                    prop_assert!(common.duration() > Duration::ZERO);
+                }
+                None => prop_assert!(!a.is_overlapping(&b)),
+            }
+            prop_assert_eq!(a.intersection(&b), b.intersection(&a));
+        }
+
+        #[test]
+        fn union_covers_both(a in arb_range(), b in arb_range()) {
+            if let Some(union) = a.union(&b) {
+                prop_assert!(a.is_contained(&union) && b.is_contained(&union));
+                let common = a.intersection(&b).map_or(Duration::ZERO, |common| common.duration());
+                prop_assert_eq!(union.duration(), a.duration() + b.duration() - common);
+            } else {
+                prop_assert!(a.is_before(&b) || a.is_after(&b));
+            }
+        }
+
+        #[test]
+        fn difference_leaves_the_rest(a in arb_range(), b in arb_range()) {
+            let parts = a.difference(&b);
+            let common = a.intersection(&b).map_or(Duration::ZERO, |common| common.duration());
+
+            prop_assert_eq!(total(&parts), a.duration() - common);
+            for part in &parts {
+                prop_assert!(part.is_contained(&a));
+                prop_assert!(!part.is_overlapping(&b));
+            }
+        }
+
+        #[test]
+        fn merged_ranges_are_disjoint(ranges in prop::collection::vec(arb_range(), 0..8)) {
+            let merged = TimeRange::merge(ranges.clone());
+
+            prop_assert!(merged.windows(2).all(|pair| pair[0].end < pair[1].start));
+            for range in &ranges {
+                prop_assert!(merged.iter().any(|merged| range.is_contained(merged)));
+            }
+        }
+
+        #[test]
+        fn gaps_avoid_busy_ranges(range in arb_range(), busy in prop::collection::vec(arb_range(), 0..8)) {
+            let gaps = range.gaps(&busy);
+            let covered = TimeRange::merge(busy.clone())
+                .iter()
+                .filter_map(|busy| busy.intersection(&range))
+                .map(|busy| busy.duration())
+                .sum::<Duration>();
+
+            prop_assert_eq!(total(&gaps) + covered, range.duration());
+            for gap in &gaps {
+                prop_assert!(gap.is_contained(&range));
+                prop_assert!(busy.iter().all(|busy| !gap.is_overlapping(busy)));
+            }
+        }
+
+        #[test]
+        fn days_cover_the_range(range in arb_range()) {
+            let days = range.split_by_days();
+
+            prop_assert!(is_contiguous(&days, &range));
+            prop_assert_eq!(total(&days), range.duration());
+            for day in &days {
+                prop_assert!(day.duration() <= Duration::days(1));
+                let last_moment = day.end - Duration::nanoseconds(1);
+                prop_assert!(day.duration() == Duration::ZERO || day.start.date() == last_moment.date());
+            }
+        }
+
+        #[test]
+        fn weeks_cover_the_range(range in arb_range(), week_start in 0usize..7) {
+            let week_start = WEEKDAYS[week_start];
+            let weeks = range.split_by_weeks(week_start);
+
+            prop_assert!(is_contiguous(&weeks, &range));
+            prop_assert_eq!(total(&weeks), range.duration());
+            for week in weeks.iter().skip(1) {
+                prop_assert_eq!(week.start.weekday(), week_start);
+                prop_assert_eq!(week.start.time(), time::Time::MIDNIGHT);
+            }
+        }
+    }
+}
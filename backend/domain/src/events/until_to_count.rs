@@ -1,10 +1,10 @@
 use crate::app_errors::DefaultContext;
-use crate::utils::events::additions::{
+use crate::events::additions::{
     day_from_week_and_weekday, get_amount_from_week_map, get_char, next_good_month,
     next_good_month_by_weekday, nth_53_week_year_by_weekday, TimeStart, TimeTo,
 };
-use crate::utils::events::errors::EventError;
-use crate::utils::events::models::{RecurrenceRuleKind, TimeRange};
+use crate::events::errors::RecurrenceError;
+use crate::events::models::{RecurrenceRuleKind, TimeRange};
 use crate::validation::{ValidateContent, ValidateContentError};
 use time::{Date, Duration, Month, OffsetDateTime};
 
@@ -32,7 +32,7 @@ pub fn until_to_count(
     interval: u32,
     event_duration: Duration,
     kind: &RecurrenceRuleKind,
-) -> Result<u32, EventError> {
+) -> Result<u32, RecurrenceError> {
     let conv_data = UntilToCountData {
         part_starts_at: start,
         until: until - event_duration,
@@ -51,14 +51,24 @@ pub fn until_to_count(
             weekly_u_to_c(conv_data, &string_week_map)
         }
         RecurrenceRuleKind::Daily => daily_u_to_c(conv_data),
+        RecurrenceRuleKind::Hourly => hourly_u_to_c(conv_data),
+        RecurrenceRuleKind::Minutely => minutely_u_to_c(conv_data),
     }
 }
 
-pub fn daily_u_to_c(data: UntilToCountData) -> Result<u32, EventError> {
+pub fn daily_u_to_c(data: UntilToCountData) -> Result<u32, RecurrenceError> {
     Ok(((data.until - data.part_starts_at) / data.interval).whole_days() as u32)
 }
 
-pub fn weekly_u_to_c(data: UntilToCountData, week_map: &str) -> Result<u32, EventError> {
+pub fn hourly_u_to_c(data: UntilToCountData) -> Result<u32, RecurrenceError> {
+    Ok(((data.until - data.part_starts_at) / data.interval).whole_hours() as u32)
+}
+
+pub fn minutely_u_to_c(data: UntilToCountData) -> Result<u32, RecurrenceError> {
+    Ok(((data.until - data.part_starts_at) / data.interval).whole_minutes() as u32)
+}
+
+pub fn weekly_u_to_c(data: UntilToCountData, week_map: &str) -> Result<u32, RecurrenceError> {
     let events_per_week = get_amount_from_week_map(week_map);
     let week_distance = (data.until.week_start() - data.part_starts_at.week_start()).whole_weeks();
 
@@ -89,7 +99,7 @@ pub fn weekly_u_to_c(data: UntilToCountData, week_map: &str) -> Result<u32, Even
     }
 }
 
-pub fn monthly_u_to_c_by_day(data: UntilToCountData) -> Result<u32, EventError> {
+pub fn monthly_u_to_c_by_day(data: UntilToCountData) -> Result<u32, RecurrenceError> {
     if data.part_starts_at.day() <= 28 {
         let month_distance = (data.part_starts_at.year(), data.part_starts_at.month())
             .time_to((data.until.year(), data.until.month())) as u32;
@@ -119,7 +129,7 @@ pub fn monthly_u_to_c_by_day(data: UntilToCountData) -> Result<u32, EventError>
     }
 }
 
-pub fn monthly_u_to_c_by_weekday(data: UntilToCountData) -> Result<u32, EventError> {
+pub fn monthly_u_to_c_by_weekday(data: UntilToCountData) -> Result<u32, RecurrenceError> {
     if data.part_starts_at.day() <= 28 {
         let month_distance = (data.part_starts_at.year(), data.part_starts_at.month())
             .time_to((data.until.year(), data.until.month())) as u32;
@@ -149,7 +159,7 @@ pub fn monthly_u_to_c_by_weekday(data: UntilToCountData) -> Result<u32, EventErr
     }
 }
 
-pub fn yearly_u_to_c_by_day(data: UntilToCountData) -> Result<u32, EventError> {
+pub fn yearly_u_to_c_by_day(data: UntilToCountData) -> Result<u32, RecurrenceError> {
     if let (Month::February, 29) = (data.part_starts_at.month(), data.part_starts_at.day()) {
         let mut yearly_step = data.part_starts_at;
         let mut res = 0;
@@ -171,7 +181,7 @@ pub fn yearly_u_to_c_by_day(data: UntilToCountData) -> Result<u32, EventError> {
     }
 }
 
-pub fn yearly_u_to_c_by_weekday(data: UntilToCountData) -> Result<u32, EventError> {
+pub fn yearly_u_to_c_by_weekday(data: UntilToCountData) -> Result<u32, RecurrenceError> {
     let (start_year, start_week, start_weekday) = data.part_starts_at.to_iso_week_date();
     let (end_year, _end_week, _end_weekday) = data.until.to_iso_week_date();
     if start_week == 53 {
@@ -200,8 +210,8 @@ pub fn yearly_u_to_c_by_weekday(data: UntilToCountData) -> Result<u32, EventErro
 
 #[cfg(test)]
 mod until_to_count_tests {
-    use crate::routes::events::models::{RecurrenceEndsAt, RecurrenceRuleSchema, TimeRules};
-    use crate::utils::events::models::{RecurrenceRuleKind, TimeRange};
+    use crate::api::events::{RecurrenceEndsAt, RecurrenceRuleSchema, TimeRules};
+    use crate::events::models::{RecurrenceRuleKind, TimeRange};
     use time::macros::datetime;
 
     #[test]
@@ -229,6 +239,56 @@ mod until_to_count_tests {
         )
     }
 
+    #[test]
+    fn hourly_until_to_count_test() {
+        let event = TimeRange::new(
+            datetime!(2023-02-18 10:00 UTC),
+            datetime!(2023-02-18 10:10 UTC),
+        );
+        let rec_rules = RecurrenceRuleSchema {
+            time_rules: TimeRules {
+                ends_at: Some(RecurrenceEndsAt::Until(datetime!(2023-02-19 16:10 UTC))),
+                interval: 6,
+            },
+            kind: RecurrenceRuleKind::Hourly,
+        };
+        assert_eq!(
+            rec_rules
+                .until_to_count(
+                    datetime!(2023-02-18 10:00 UTC),
+                    datetime!(2023-02-19 16:10 UTC),
+                    &event
+                )
+                .unwrap(),
+            5
+        )
+    }
+
+    #[test]
+    fn minutely_until_to_count_test() {
+        let event = TimeRange::new(
+            datetime!(2023-02-18 23:30 UTC),
+            datetime!(2023-02-18 23:35 UTC),
+        );
+        let rec_rules = RecurrenceRuleSchema {
+            time_rules: TimeRules {
+                ends_at: Some(RecurrenceEndsAt::Until(datetime!(2023-02-19 0:55 UTC))),
+                interval: 20,
+            },
+            kind: RecurrenceRuleKind::Minutely,
+        };
+        assert_eq!(
+            rec_rules
+                .until_to_count(
+                    datetime!(2023-02-18 23:30 UTC),
+                    datetime!(2023-02-19 0:55 UTC),
+                    &event
+                )
+                .unwrap(),
+            4
+        )
+    }
+
     #[test]
     fn daily_until_to_count_test_2() {
         let event = TimeRange::new(
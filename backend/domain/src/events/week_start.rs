@@ -0,0 +1,188 @@
+use time::{ext::NumericalDuration, Duration, OffsetDateTime, Weekday};
+
+use crate::app_errors::DefaultContext;
+
+use super::{
+    errors::RecurrenceError,
+    models::{EntriesSpan, RecurrenceRule, RecurrenceRuleKind, TimeRange},
+    near_entriies::{next_entry, prev_entry},
+};
+
+/// Recurrence rule with weeks starting on a chosen day.
+///
+/// All of the recurrence computations assume weeks starting on Monday,
+/// so a weekly rule is moved back by the distance between Monday and the week start
+/// (with its week map rotated accordingly) and the results are moved forward again.
+pub struct WeekAlignedRule {
+    rule: RecurrenceRule,
+    offset: Duration,
+}
+
+impl WeekAlignedRule {
+    pub fn new(rule: &RecurrenceRule, week_start: Weekday) -> Self {
+        let days = week_start.number_days_from_monday();
+
+        match rule.kind {
+            RecurrenceRuleKind::Weekly { week_map } if days != 0 => {
+                let offset = (days as i64).days();
+                Self {
+                    rule: RecurrenceRule {
+                        span: rule.span.map(|span| EntriesSpan {
+                            end: span.end - offset,
+                            repetitions: span.repetitions,
+                        }),
+                        interval: rule.interval,
+                        kind: RecurrenceRuleKind::Weekly {
+                            week_map: rotate_week_map(week_map, days),
+                        },
+                    },
+                    offset,
+                }
+            }
+            _ => Self {
+                rule: rule.clone(),
+                offset: Duration::ZERO,
+            },
+        }
+    }
+
+    pub fn get_event_range(
+        &self,
+        part: TimeRange,
+        event: TimeRange,
+    ) -> Result<Vec<TimeRange>, RecurrenceError> {
+        self.rule
+            .get_event_range(self.align(part)?, self.align(event)?)?
+            .into_iter()
+            .map(|range| self.restore(range))
+            .collect()
+    }
+
+    pub fn prev_entry(
+        &self,
+        provided_time: OffsetDateTime,
+        first_entry: TimeRange,
+    ) -> Result<Option<TimeRange>, RecurrenceError> {
+        prev_entry(
+            provided_time - self.offset,
+            self.align(first_entry)?,
+            &self.rule,
+        )?
+        .map(|range| self.restore(range))
+        .transpose()
+    }
+
+    pub fn next_entry(
+        &self,
+        provided_time: OffsetDateTime,
+        first_entry: TimeRange,
+    ) -> Result<Option<TimeRange>, RecurrenceError> {
+        next_entry(
+            provided_time - self.offset,
+            self.align(first_entry)?,
+            &self.rule,
+        )?
+        .map(|range| self.restore(range))
+        .transpose()
+    }
+
+    fn align(&self, range: TimeRange) -> Result<TimeRange, RecurrenceError> {
+        Ok(range.checked_add(-self.offset).dc()?)
+    }
+
+    fn restore(&self, range: TimeRange) -> Result<TimeRange, RecurrenceError> {
+        Ok(range.checked_add(self.offset).dc()?)
+    }
+}
+
+/// Rotates the Monday-first week map, so that it starts with the day `days` after Monday.
+fn rotate_week_map(week_map: u8, days: u8) -> u8 {
+    let week_map = week_map % 128;
+    ((week_map << days) | (week_map >> (7 - days))) % 128
+}
+
+#[cfg(test)]
+mod week_start_tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn rotate_week_map_test() {
+        assert_eq!(rotate_week_map(0b1000000, 1), 0b0000001);
+        assert_eq!(rotate_week_map(0b1000001, 6), 0b1100000);
+        assert_eq!(rotate_week_map(0b0011000, 2), 0b1100000);
+    }
+
+    #[test]
+    fn weekly_range_with_sunday_week_start() {
+        let rule = RecurrenceRule {
+            span: None,
+            interval: 2,
+            kind: RecurrenceRuleKind::Weekly {
+                week_map: 0b1000001,
+            },
+        };
+        let event = TimeRange::new(
+            datetime!(2023-03-05 10:00 UTC),
+            datetime!(2023-03-05 11:00 UTC),
+        );
+        let part = TimeRange::new(
+            datetime!(2023-03-01 00:00 UTC),
+            datetime!(2023-03-22 00:00 UTC),
+        );
+
+        let starts = |ranges: Vec<TimeRange>| ranges.iter().map(|x| x.start).collect::<Vec<_>>();
+
+        assert_eq!(
+            starts(
+                WeekAlignedRule::new(&rule, Weekday::Monday)
+                    .get_event_range(part, event)
+                    .unwrap()
+            ),
+            vec![
+                datetime!(2023-03-05 10:00 UTC),
+                datetime!(2023-03-13 10:00 UTC),
+                datetime!(2023-03-19 10:00 UTC),
+            ]
+        );
+        assert_eq!(
+            starts(
+                WeekAlignedRule::new(&rule, Weekday::Sunday)
+                    .get_event_range(part, event)
+                    .unwrap()
+            ),
+            vec![
+                datetime!(2023-03-05 10:00 UTC),
+                datetime!(2023-03-06 10:00 UTC),
+                datetime!(2023-03-19 10:00 UTC),
+                datetime!(2023-03-20 10:00 UTC),
+            ]
+        );
+    }
+
+    #[test]
+    fn next_entry_with_sunday_week_start() {
+        let rule = RecurrenceRule {
+            span: None,
+            interval: 2,
+            kind: RecurrenceRuleKind::Weekly {
+                week_map: 0b1000001,
+            },
+        };
+        let event = TimeRange::new(
+            datetime!(2023-03-05 10:00 UTC),
+            datetime!(2023-03-05 11:00 UTC),
+        );
+
+        assert_eq!(
+            WeekAlignedRule::new(&rule, Weekday::Sunday)
+                .next_entry(datetime!(2023-03-07 00:00 UTC), event)
+                .unwrap(),
+            Some(TimeRange::new(
+                datetime!(2023-03-19 10:00 UTC),
+                datetime!(2023-03-19 11:00 UTC),
+            ))
+        );
+    }
+}
@@ -0,0 +1,248 @@
+use std::io::Cursor;
+
+use calamine::{open_workbook_from_rs, Data, DataType, Reader, Xlsx};
+use time::{Duration, Time, Weekday};
+
+use crate::api::events::{
+    CreateEvent, EventData, EventPayload, RecurrenceEndsAt, RecurrenceRuleSchema, TimeRules,
+};
+use crate::events::additions::CyclicTimeTo;
+use crate::events::models::{week_map_from_days, DayOfWeek, RecurrenceRuleKind, TimeRange};
+use crate::validation::ValidateContentError;
+
+/// Lesson read from a single cell of a timetable sheet.
+#[derive(Debug, PartialEq)]
+pub struct TimetableCell {
+    pub day: DayOfWeek,
+    pub starts_at: Time,
+    pub ends_at: Time,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+/// Reads lessons from the first sheet of an `.xlsx` timetable.
+///
+/// The first row names the days of the week and the first column holds periods written as
+/// `8:00-8:45`. The first line of a cell is the lesson name, the other lines its description.
+pub fn parse_timetable(bytes: &[u8]) -> Result<Vec<TimetableCell>, ValidateContentError> {
+    let mut workbook: Xlsx<_> = open_workbook_from_rs(Cursor::new(bytes))
+        .map_err(|_| ValidateContentError::new("File is not an xlsx workbook"))?;
+    let sheet = workbook
+        .worksheet_range_at(0)
+        .ok_or_else(|| ValidateContentError::new("Workbook has no sheets"))?
+        .map_err(|_| ValidateContentError::new("Failed to read the timetable sheet"))?;
+
+    let mut rows = sheet.rows();
+    let header = rows
+        .next()
+        .ok_or_else(|| ValidateContentError::new("Timetable sheet is empty"))?;
+    let days = header
+        .iter()
+        .skip(1)
+        .map(|cell| match cell {
+            Data::Empty => Ok(None),
+            Data::String(name) => parse_day(name).map(Some),
+            _ => Err(ValidateContentError::new(
+                "Days must be named in the first row",
+            )),
+        })
+        .collect::<Result<Vec<Option<DayOfWeek>>, _>>()?;
+
+    let mut lessons = Vec::new();
+    for row in rows {
+        let Some((period, cells)) = row.split_first() else {
+            continue;
+        };
+        if cells.iter().all(|cell| cell.is_empty()) {
+            continue;
+        }
+        let (starts_at, ends_at) = match period {
+            Data::String(period) => parse_period(period)?,
+            _ => {
+                return Err(ValidateContentError::new(
+                    "Periods must be written as text in the first column",
+                ))
+            }
+        };
+
+        for (cell, day) in cells.iter().zip(&days) {
+            let Some(text) = cell.as_string().filter(|text| !text.trim().is_empty()) else {
+                continue;
+            };
+            let Some(day) = day else {
+                return Err(ValidateContentError::new(
+                    "Lesson is outside of a day column",
+                ));
+            };
+            let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+            let name = lines.next().unwrap_or_default().to_string();
+            let description = Some(lines.collect::<Vec<&str>>().join("\n"))
+                .filter(|description| !description.is_empty());
+
+            lessons.push(TimetableCell {
+                day: *day,
+                starts_at,
+                ends_at,
+                name,
+                description,
+            });
+        }
+    }
+
+    Ok(lessons)
+}
+
+/// Events read from a timetable, along with remarks about lessons which were left out.
+#[derive(Debug, Default)]
+pub struct TimetableImport {
+    pub events: Vec<CreateEvent>,
+    pub warnings: Vec<String>,
+}
+
+/// Turns lessons into weekly events repeating within `range`.
+///
+/// Times of day are read in the offset of the range start.
+/// Lessons which don't fit into the range are left out with a warning.
+pub fn timetable_events(lessons: Vec<TimetableCell>, range: TimeRange) -> TimetableImport {
+    let mut import = TimetableImport::default();
+    for lesson in lessons {
+        let weekday = Weekday::from(lesson.day);
+        let days_ahead = range.start.weekday().cyclic_time_to(weekday);
+        let date = range.start.date() + Duration::days(days_ahead.into());
+        let starts_at = date
+            .with_time(lesson.starts_at)
+            .assume_offset(range.start.offset());
+        let ends_at = date
+            .with_time(lesson.ends_at)
+            .assume_offset(range.start.offset());
+        if starts_at < range.start || ends_at > range.end {
+            import.warnings.push(format!(
+                "Lesson {} on {weekday} at {}:{:02} does not fit into the imported range",
+                lesson.name,
+                lesson.starts_at.hour(),
+                lesson.starts_at.minute()
+            ));
+            continue;
+        }
+
+        import.events.push(CreateEvent {
+            data: EventData {
+                payload: EventPayload::new(lesson.name, lesson.description),
+                starts_at,
+                ends_at,
+            },
+            recurrence_rule: Some(RecurrenceRuleSchema {
+                time_rules: TimeRules {
+                    ends_at: Some(RecurrenceEndsAt::Until(range.end)),
+                    interval: 1,
+                },
+                kind: RecurrenceRuleKind::Weekly {
+                    week_map: week_map_from_days(&[lesson.day]),
+                },
+            }),
+        });
+    }
+
+    import
+}
+
+fn parse_day(name: &str) -> Result<DayOfWeek, ValidateContentError> {
+    let day = match name.trim().to_lowercase().as_str() {
+        "monday" | "mon" | "poniedziałek" => DayOfWeek::Monday,
+        "tuesday" | "tue" | "wtorek" => DayOfWeek::Tuesday,
+        "wednesday" | "wed" | "środa" => DayOfWeek::Wednesday,
+        "thursday" | "thu" | "czwartek" => DayOfWeek::Thursday,
+        "friday" | "fri" | "piątek" => DayOfWeek::Friday,
+        "saturday" | "sat" | "sobota" => DayOfWeek::Saturday,
+        "sunday" | "sun" | "niedziela" => DayOfWeek::Sunday,
+        other => {
+            return Err(ValidateContentError::new(format!(
+                "Unknown day of the week {other}"
+            )))
+        }
+    };
+    Ok(day)
+}
+
+fn parse_period(period: &str) -> Result<(Time, Time), ValidateContentError> {
+    let invalid = || ValidateContentError::new(format!("Invalid period {period}"));
+    let (start, end) = period.split_once(['-', '–']).ok_or_else(invalid)?;
+    let parse_time = |time: &str| {
+        let (hour, minute) = time.trim().split_once([':', '.']).ok_or_else(invalid)?;
+        let hour = hour.parse::<u8>().map_err(|_| invalid())?;
+        let minute = minute.parse::<u8>().map_err(|_| invalid())?;
+        Time::from_hms(hour, minute, 0).map_err(|_| invalid())
+    };
+
+    let (start, end) = (parse_time(start)?, parse_time(end)?);
+    if start >= end {
+        return Err(invalid());
+    }
+    Ok((start, end))
+}
+
+#[cfg(test)]
+mod xlsx_tests {
+    use time::macros::{datetime, time};
+
+    use super::*;
+
+    #[test]
+    fn period_parsing() {
+        assert_eq!(
+            parse_period("8:00-8:45").unwrap(),
+            (time!(8:00), time!(8:45))
+        );
+        assert_eq!(
+            parse_period("10.50 – 11.35").unwrap(),
+            (time!(10:50), time!(11:35))
+        );
+        assert!(parse_period("8:45-8:00").is_err());
+        assert!(parse_period("8:00").is_err());
+    }
+
+    #[test]
+    fn day_parsing() {
+        assert_eq!(parse_day("Tuesday").unwrap(), DayOfWeek::Tuesday);
+        assert_eq!(parse_day(" Środa ").unwrap(), DayOfWeek::Wednesday);
+        assert!(parse_day("Someday").is_err());
+    }
+
+    #[test]
+    fn lessons_repeat_weekly_from_the_range_start() {
+        let lesson = TimetableCell {
+            day: DayOfWeek::Monday,
+            starts_at: time!(8:00),
+            ends_at: time!(8:45),
+            name: "Fizyka".to_string(),
+            description: None,
+        };
+        let range = TimeRange::new(datetime!(2023-09-01 0:00 +2), datetime!(2024-06-21 0:00 +2));
+
+        let events = timetable_events(vec![lesson], range).events;
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data.starts_at, datetime!(2023-09-04 8:00 +2));
+        assert_eq!(events[0].data.ends_at, datetime!(2023-09-04 8:45 +2));
+    }
+
+    #[test]
+    fn lessons_outside_of_the_range_are_left_out_with_a_warning() {
+        let lesson = TimetableCell {
+            day: DayOfWeek::Friday,
+            starts_at: time!(8:00),
+            ends_at: time!(8:45),
+            name: "Fizyka".to_string(),
+            description: None,
+        };
+        let range = TimeRange::new(datetime!(2023-09-04 0:00 +2), datetime!(2023-09-06 0:00 +2));
+
+        let import = timetable_events(vec![lesson], range);
+
+        assert!(import.events.is_empty());
+        assert_eq!(
+            import.warnings,
+            vec!["Lesson Fizyka on Friday at 8:00 does not fit into the imported range"]
+        );
+    }
+}
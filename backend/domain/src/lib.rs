@@ -0,0 +1,10 @@
+//! Recurrence engine, validation and API schemas of Bimetable.
+//!
+//! Builds without the database and the web stack, so that clients and other targets can share
+//! the models and computations of the server.
+
+pub mod api;
+pub mod app_errors;
+pub mod events;
+pub mod limits;
+pub mod validation;
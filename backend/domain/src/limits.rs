@@ -0,0 +1,24 @@
+use time::Duration;
+
+pub const DEFAULT_OVERRIDE_SHIFT_LIMIT_HOURS: u32 = 7 * 24;
+pub const DEFAULT_MAX_REPETITIONS: u32 = 10_000;
+
+/// Largest offset by which an override may move either end of an entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverrideShiftLimit(pub Duration);
+
+impl Default for OverrideShiftLimit {
+    fn default() -> Self {
+        Self(Duration::hours(DEFAULT_OVERRIDE_SHIFT_LIMIT_HOURS.into()))
+    }
+}
+
+/// Largest number of entries of a recurring event, whether given by `count` or computed from `until`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RepetitionLimit(pub u32);
+
+impl Default for RepetitionLimit {
+    fn default() -> Self {
+        Self(DEFAULT_MAX_REPETITIONS)
+    }
+}
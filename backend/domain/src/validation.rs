@@ -0,0 +1,1377 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use thiserror::Error;
+use time::format_description::well_known::Rfc3339;
+use time::Duration;
+use tracing::error;
+use validator::ValidationErrors;
+
+use crate::api::events::{RecurrenceEndsAt, RecurrenceRuleSchema, TimeRules};
+use crate::api::invitations::{DirectInvitation, EmailInvitation};
+use crate::api::reminders::CreateReminder;
+use crate::api::users::CreateQuietHours;
+use crate::limits::{OverrideShiftLimit, RepetitionLimit};
+use crate::{
+    api::events::{
+        CreateEvent, EntrySort, Event, EventData, GetCombinedQuery, GetEventsQuery,
+        OptionalEventData, OverrideEvent, OverrideEventData, PauseEvent, SortDirection, SplitEvent,
+        SuggestSlot, UpdateEditPrivileges, UpdateEvent, UpdateRecurrence,
+    },
+    app_errors::DefaultContext,
+    events::models::{OverrideStatus, RecurrenceRuleKind, TimeRange},
+};
+
+/// Four weeks
+const MAX_REMINDER_MINUTES: u32 = 4 * 7 * 24 * 60;
+const MAX_COMBINED_USERS: usize = 20;
+const MAX_SUGGESTED_SLOTS: u32 = 50;
+const MAX_PRIVILEGE_CHANGES: usize = 100;
+/// Longest address SMTP can deliver to
+const MAX_EMAIL_LENGTH: usize = 254;
+
+#[derive(Debug, Error)]
+pub enum ValidateContentError {
+    #[error("Data rejected with validation")]
+    Expected(String),
+    #[error("Data rejected with validation")]
+    InvalidFields(Vec<InvalidField>),
+    #[error("Unexpected server error")]
+    Unexpected(#[from] anyhow::Error),
+}
+
+/// Field of a payload rejected with validation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InvalidField {
+    /// Path of the field in the payload, with nested fields joined by dots, e.g. `data.startsAt`
+    pub field: String,
+    pub message: String,
+}
+
+impl ValidateContentError {
+    pub fn new(content: impl ToString) -> Self {
+        Self::Expected(content.to_string())
+    }
+
+    pub fn field(field: &str, content: impl ToString) -> Self {
+        Self::InvalidFields(vec![InvalidField {
+            field: field.to_string(),
+            message: content.to_string(),
+        }])
+    }
+
+    /// Places the error under a field of the enclosing payload.
+    ///
+    /// Errors without a field are attributed to the field itself.
+    pub fn at(self, field: &str) -> Self {
+        match self {
+            Self::Expected(message) => Self::field(field, message),
+            Self::InvalidFields(fields) => Self::InvalidFields(
+                fields
+                    .into_iter()
+                    .map(|invalid| InvalidField {
+                        field: format!("{field}.{}", invalid.field),
+                        ..invalid
+                    })
+                    .collect(),
+            ),
+            e => e,
+        }
+    }
+
+    /// First message of the error, for reports listing errors of many items.
+    pub fn message(&self) -> String {
+        match self {
+            Self::Expected(content) => content.clone(),
+            Self::InvalidFields(fields) => fields
+                .first()
+                .map(|invalid| format!("{}: {}", invalid.field, invalid.message))
+                .unwrap_or_default(),
+            Self::Unexpected(_) => self.to_string(),
+        }
+    }
+
+    /// Body of the response, `error_info` keeps the first message for clients unaware of `fields`.
+    pub fn to_json(&self) -> Value {
+        match self {
+            Self::Expected(content) => json!({ "error_info": format!("{self}: {content}") }),
+            Self::InvalidFields(fields) => {
+                let content = fields
+                    .first()
+                    .map(|invalid| invalid.message.as_str())
+                    .unwrap_or_default();
+                json!({ "error_info": format!("{self}: {content}"), "fields": fields })
+            }
+            Self::Unexpected(_) => json!({ "error_info": self.to_string() }),
+        }
+    }
+}
+
+/// Lists the field errors of `validator`, by the codes of errors without a message.
+pub fn invalid_fields(errors: &ValidationErrors) -> Vec<InvalidField> {
+    let mut fields: Vec<InvalidField> = errors
+        .field_errors()
+        .into_iter()
+        .flat_map(|(field, errors)| {
+            errors.iter().map(move |e| InvalidField {
+                field: field.to_string(),
+                message: e
+                    .message
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_else(|| e.code.to_string()),
+            })
+        })
+        .collect();
+    // Field errors come out of a hash map
+    fields.sort_by(|a, b| a.field.cmp(&b.field));
+    fields
+}
+
+pub trait ValidateContent {
+    fn validate_content(&self) -> Result<(), ValidateContentError>;
+}
+
+/// Non-fatal remarks about content that is accepted as it is, reported back to the client.
+pub trait WarnContent {
+    fn content_warnings(&self) -> Vec<String>;
+}
+
+impl ValidateContent for TimeRange {
+    fn validate_content(&self) -> Result<(), ValidateContentError> {
+        if self.duration() < Duration::seconds(0) {
+            Err(ValidateContentError::new("TimeRange duration is negative"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl ValidateContent for TimeRules {
+    fn validate_content(&self) -> Result<(), ValidateContentError> {
+        if self.interval == 0 {
+            Err(ValidateContentError::field(
+                "interval",
+                "Time rule interval is equal to 0",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Bounds of the rules of one kind of recurrence.
+struct KindLimits {
+    /// Description of the kind used in messages
+    description: &'static str,
+    max_interval: u32,
+}
+
+/// Bounds of every kind of recurrence, longer intervals are better expressed with a coarser kind.
+///
+/// There is no bound on the day of the month entries start on, the event start is always a valid date
+/// and months without that day are skipped.
+fn kind_limits(kind: RecurrenceRuleKind) -> KindLimits {
+    let (description, max_interval) = match kind {
+        RecurrenceRuleKind::Hourly => ("Hourly", 168),
+        RecurrenceRuleKind::Minutely => ("Minutely", 1440),
+        RecurrenceRuleKind::Daily => ("Daily", 365),
+        RecurrenceRuleKind::Weekly { .. } => ("Weekly", 52),
+        RecurrenceRuleKind::Monthly { is_by_day: true } => ("Monthly by day", 12),
+        RecurrenceRuleKind::Monthly { is_by_day: false } => ("Monthly by weekday", 12),
+        RecurrenceRuleKind::Yearly { is_by_day: true } => ("Yearly by day", 10),
+        RecurrenceRuleKind::Yearly { is_by_day: false } => ("Yearly by weekday", 10),
+    };
+    KindLimits {
+        description,
+        max_interval,
+    }
+}
+
+impl ValidateContent for RecurrenceRuleSchema {
+    fn validate_content(&self) -> Result<(), ValidateContentError> {
+        self.time_rules
+            .validate_content()
+            .map_err(|e| e.at("time_rules"))?;
+        if let RecurrenceRuleKind::Weekly { week_map: 0 } = self.kind {
+            return Err(ValidateContentError::field(
+                "kind.weekly.days",
+                "No events in the week map",
+            ));
+        };
+
+        let limits = kind_limits(self.kind);
+        if self.kind.is_sub_daily() && self.time_rules.ends_at.is_none() {
+            return Err(ValidateContentError::field(
+                "time_rules.endsAt",
+                format!("{} recurrence must end", limits.description),
+            ));
+        }
+        if self.time_rules.interval > limits.max_interval {
+            return Err(ValidateContentError::field(
+                "time_rules.interval",
+                format!(
+                    "{} recurrence interval is greater than {}",
+                    limits.description, limits.max_interval
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl ValidateContent for EventData {
+    fn validate_content(&self) -> Result<(), ValidateContentError> {
+        TimeRange::new(self.starts_at, self.ends_at)
+            .validate_content()
+            .map_err(|e| e.at("startsAt"))
+    }
+}
+
+impl RecurrenceRuleSchema {
+    /// Validates the rule against the time range of the event it is going to be attached to.
+    pub fn validate_with_event(&self, event: &TimeRange) -> Result<(), ValidateContentError> {
+        self.validate_content()?;
+
+        let until = match self.time_rules.ends_at {
+            Some(RecurrenceEndsAt::Count(n)) => self.count_to_until(event.start, n, event).dc()?,
+            Some(RecurrenceEndsAt::Until(t)) => t,
+            None => return Ok(()),
+        };
+
+        if until < event.end {
+            Err(ValidateContentError::field(
+                "time_rules.endsAt",
+                "Recurrence ends sooner than the event ends",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl RecurrenceRuleSchema {
+    /// Rejects rules with more entries than the limit, before any of them is computed.
+    ///
+    /// Repetitions of rules ending at `until` are counted against the time range of the event.
+    /// Both `count` and the computed repetitions leave out the first entry, which is added back.
+    pub fn validate_repetitions(
+        &self,
+        event: &TimeRange,
+        limit: RepetitionLimit,
+    ) -> Result<(), ValidateContentError> {
+        let repetitions = match self.time_rules.ends_at {
+            Some(RecurrenceEndsAt::Count(n)) => n,
+            Some(RecurrenceEndsAt::Until(until)) => {
+                match self.until_to_count(event.start, until, event) {
+                    Ok(n) => n,
+                    // Invalid ranges are reported by the other validations
+                    Err(_) => return Ok(()),
+                }
+            }
+            None => return Ok(()),
+        };
+
+        if repetitions.saturating_add(1) > limit.0 {
+            return Err(ValidateContentError::field(
+                "time_rules.endsAt",
+                format!("Recurrence has more than {} entries", limit.0),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl RecurrenceRuleSchema {
+    /// Lists surprising but valid effects of attaching the rule to the event.
+    pub fn warnings_with_event(&self, event: &TimeRange) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        if let RecurrenceRuleKind::Weekly { week_map } = self.kind {
+            // Weekly entries are laid out from the Monday of the week the event starts in
+            let days_before_start = 7 - event.start.weekday().number_days_from_monday() as u32;
+            if week_map.checked_shr(days_before_start).unwrap_or(0) != 0 {
+                warnings.push(
+                    "Week map includes days before the event start, they get entries in its first week"
+                        .to_string(),
+                );
+            }
+        }
+
+        if let Some(RecurrenceEndsAt::Until(until)) = self.time_rules.ends_at {
+            let last_end = self
+                .until_to_count(event.start, until, event)
+                .and_then(|count| self.count_to_until(event.start, count, event));
+            match last_end {
+                Ok(last_end) if last_end != until => warnings.push(format!(
+                    "Recurrence until falls between entries, the last entry ends at {}",
+                    last_end
+                        .format(&Rfc3339)
+                        .unwrap_or_else(|_| last_end.to_string())
+                )),
+                _ => (),
+            }
+        }
+
+        warnings
+    }
+}
+
+impl WarnContent for CreateEvent {
+    fn content_warnings(&self) -> Vec<String> {
+        match &self.recurrence_rule {
+            Some(rule) => {
+                rule.warnings_with_event(&TimeRange::new(self.data.starts_at, self.data.ends_at))
+            }
+            None => Vec::new(),
+        }
+    }
+}
+
+impl CreateEvent {
+    pub fn validate_repetitions(&self, limit: RepetitionLimit) -> Result<(), ValidateContentError> {
+        match &self.recurrence_rule {
+            Some(rule) => rule
+                .validate_repetitions(
+                    &TimeRange::new(self.data.starts_at, self.data.ends_at),
+                    limit,
+                )
+                .map_err(|e| e.at("recurrenceRule")),
+            None => Ok(()),
+        }
+    }
+}
+
+impl ValidateContent for CreateEvent {
+    fn validate_content(&self) -> Result<(), ValidateContentError> {
+        self.data.validate_content().map_err(|e| e.at("data"))?;
+
+        let Some(rule) = &self.recurrence_rule else {
+            return Ok(());
+        };
+
+        rule.validate_with_event(&TimeRange::new(self.data.starts_at, self.data.ends_at))
+            .map_err(|e| e.at("recurrenceRule"))
+    }
+}
+
+impl ValidateContent for UpdateRecurrence {
+    fn validate_content(&self) -> Result<(), ValidateContentError> {
+        match &self.recurrence_rule {
+            Some(rule) => rule.validate_content().map_err(|e| e.at("recurrenceRule")),
+            None => Ok(()),
+        }
+    }
+}
+
+impl ValidateContent for PauseEvent {
+    fn validate_content(&self) -> Result<(), ValidateContentError> {
+        if self.starts_at >= self.ends_at {
+            return Err(ValidateContentError::field(
+                "startsAt",
+                "Pause ends before it starts",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl ValidateContent for SplitEvent {
+    fn validate_content(&self) -> Result<(), ValidateContentError> {
+        TimeRange::new(self.starts_at, self.ends_at)
+            .validate_content()
+            .map_err(|e| e.at("startsAt"))
+    }
+}
+
+impl ValidateContent for OptionalEventData {
+    fn validate_content(&self) -> Result<(), ValidateContentError> {
+        match (self.starts_at, self.ends_at) {
+            (Some(start), Some(end)) if start > end => Err(ValidateContentError::field(
+                "startsAt",
+                "Event ends sooner than it starts",
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl ValidateContent for GetEventsQuery {
+    fn validate_content(&self) -> Result<(), ValidateContentError> {
+        TimeRange::new(self.starts_at, self.ends_at)
+            .validate_content()
+            .map_err(|e| e.at("startsAt"))?;
+        if self.limit == Some(0) {
+            return Err(ValidateContentError::field(
+                "limit",
+                "Page limit must be positive",
+            ));
+        }
+        if let Some(cursor) = self.cursor {
+            if cursor < self.starts_at || cursor >= self.ends_at {
+                return Err(ValidateContentError::field(
+                    "cursor",
+                    "Cursor is outside of the search range",
+                ));
+            }
+        }
+        let is_start_order = self.sort == EntrySort::Start && self.direction == SortDirection::Asc;
+        if !is_start_order && (self.limit.is_some() || self.cursor.is_some()) {
+            return Err(ValidateContentError::new(
+                "Only entries sorted by ascending start can be paged",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl ValidateContent for GetCombinedQuery {
+    fn validate_content(&self) -> Result<(), ValidateContentError> {
+        TimeRange::new(self.starts_at, self.ends_at)
+            .validate_content()
+            .map_err(|e| e.at("startsAt"))?;
+        if self.user_ids.is_empty() {
+            return Err(ValidateContentError::field(
+                "userIds",
+                "No users to combine",
+            ));
+        }
+        if self.user_ids.len() > MAX_COMBINED_USERS {
+            return Err(ValidateContentError::field(
+                "userIds",
+                "Too many users to combine",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl ValidateContent for SuggestSlot {
+    fn validate_content(&self) -> Result<(), ValidateContentError> {
+        TimeRange::new(self.starts_at, self.ends_at)
+            .validate_content()
+            .map_err(|e| e.at("startsAt"))?;
+        if self.duration_minutes == 0 {
+            return Err(ValidateContentError::field(
+                "durationMinutes",
+                "Slot duration must be positive",
+            ));
+        }
+        if self.participants.len() > MAX_COMBINED_USERS {
+            return Err(ValidateContentError::field(
+                "participants",
+                "Too many participants",
+            ));
+        }
+        if self.limit == 0 || self.limit > MAX_SUGGESTED_SLOTS {
+            return Err(ValidateContentError::field(
+                "limit",
+                format!("Between 1 and {MAX_SUGGESTED_SLOTS} slots can be suggested"),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl ValidateContent for UpdateEditPrivileges {
+    fn validate_content(&self) -> Result<(), ValidateContentError> {
+        if self.changes.is_empty() {
+            return Err(ValidateContentError::field(
+                "changes",
+                "No privileges to change",
+            ));
+        }
+        if self.changes.len() > MAX_PRIVILEGE_CHANGES {
+            return Err(ValidateContentError::field(
+                "changes",
+                "Too many privileges to change",
+            ));
+        }
+        let mut user_ids = HashSet::new();
+        if let Some(i) = self
+            .changes
+            .iter()
+            .position(|change| !user_ids.insert(change.user_id))
+        {
+            return Err(ValidateContentError::field(
+                &format!("changes.{i}.userId"),
+                "Privileges of a participant changed more than once",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl ValidateContent for CreateQuietHours {
+    fn validate_content(&self) -> Result<(), ValidateContentError> {
+        if self.starts_at == self.ends_at {
+            return Err(ValidateContentError::field(
+                "endsAt",
+                "Quiet hours end when they start",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl ValidateContent for CreateReminder {
+    fn validate_content(&self) -> Result<(), ValidateContentError> {
+        if self.minutes_before > MAX_REMINDER_MINUTES {
+            return Err(ValidateContentError::field(
+                "minutesBefore",
+                "Reminder is too early",
+            ));
+        }
+        if let Some(webhook) = &self.webhook {
+            let is_http = url::Url::parse(&webhook.url)
+                .is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+            if !is_http {
+                return Err(ValidateContentError::field(
+                    "webhook.url",
+                    "Webhook must be an HTTP URL",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ValidateContent for DirectInvitation {
+    fn validate_content(&self) -> Result<(), ValidateContentError> {
+        if self.receiver_id == self.sender_id {
+            return Err(ValidateContentError::field(
+                "receiver_id",
+                "Sender can't invite themselves",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl ValidateContent for EmailInvitation {
+    fn validate_content(&self) -> Result<(), ValidateContentError> {
+        let is_address = match self.email.split_once('@') {
+            Some((local, domain)) => {
+                !local.is_empty()
+                    && domain.contains('.')
+                    && !domain.starts_with('.')
+                    && !domain.ends_with('.')
+                    && !domain.contains('@')
+            }
+            None => false,
+        };
+        if !is_address
+            || self.email.len() > MAX_EMAIL_LENGTH
+            || self.email.contains(char::is_whitespace)
+        {
+            return Err(ValidateContentError::field(
+                "email",
+                "Invitation needs a valid email address",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl ValidateContent for UpdateEvent {
+    fn validate_content(&self) -> Result<(), ValidateContentError> {
+        self.data.validate_content().map_err(|e| e.at("data"))
+    }
+}
+
+impl ValidateContent for OverrideEvent {
+    fn validate_content(&self) -> Result<(), ValidateContentError> {
+        TimeRange::new(self.override_starts_at, self.override_ends_at)
+            .validate_content()
+            .map_err(|e| e.at("overrideStartsAt"))?;
+        for (field, shift) in [
+            ("data.startsAt", self.data.starts_at),
+            ("data.endsAt", self.data.ends_at),
+        ] {
+            let Some(shift) = shift else {
+                continue;
+            };
+            // Stored as an `INTERVAL` of microseconds
+            let is_storable = shift.whole_nanoseconds() % 1000 == 0
+                && i64::try_from(shift.whole_microseconds()).is_ok();
+            if !is_storable {
+                return Err(ValidateContentError::field(
+                    field,
+                    "Override shift must be a microsecond precision duration",
+                ));
+            }
+        }
+        if self
+            .data
+            .added_participants
+            .iter()
+            .any(|user_id| self.data.excluded_participants.contains(user_id))
+        {
+            return Err(ValidateContentError::field(
+                "data.excludedParticipants",
+                "Participant can't be both added and excluded",
+            ));
+        }
+        self.data.validate_status()
+    }
+}
+
+impl OverrideEventData {
+    /// Validates that the override only sets the fields allowed by its status.
+    fn validate_status(&self) -> Result<(), ValidateContentError> {
+        let Some(status) = self.status else {
+            return Ok(());
+        };
+        let is_shifted = [self.starts_at, self.ends_at]
+            .into_iter()
+            .flatten()
+            .any(|shift| !shift.is_zero());
+        let has_participants =
+            !self.added_participants.is_empty() || !self.excluded_participants.is_empty();
+
+        let problem = match status {
+            OverrideStatus::Moved if !is_shifted => Some("Moved override must shift the entry"),
+            OverrideStatus::Cancelled if is_shifted || self.name.is_some() || has_participants => {
+                Some("Cancelled override may only describe the cancellation")
+            }
+            OverrideStatus::Substituted if is_shifted => {
+                Some("Substituted override can't move the entry")
+            }
+            OverrideStatus::Substituted if self.name.is_none() && !has_participants => {
+                Some("Substituted override must rename the entry or change its participants")
+            }
+            OverrideStatus::RoomChange if is_shifted || self.name.is_some() || has_participants => {
+                Some("Room change override may only describe the new room")
+            }
+            OverrideStatus::RoomChange if self.description.is_none() => {
+                Some("Room change override must describe the new room")
+            }
+            _ => None,
+        };
+        match problem {
+            Some(problem) => Err(ValidateContentError::field("data.status", problem)),
+            None => Ok(()),
+        }
+    }
+
+    /// Validates the shifts against the entry of the event they are going to be applied to.
+    pub fn validate_with_entry(
+        &self,
+        entry: &TimeRange,
+        limit: OverrideShiftLimit,
+    ) -> Result<(), ValidateContentError> {
+        let starts_at = self.starts_at.unwrap_or(Duration::ZERO);
+        let ends_at = self.ends_at.unwrap_or(Duration::ZERO);
+        for (field, shift) in [("startsAt", starts_at), ("endsAt", ends_at)] {
+            if shift.abs() > limit.0 {
+                return Err(ValidateContentError::field(
+                    field,
+                    "Override moves the entry too far from its occurrence",
+                ));
+            }
+        }
+
+        let duration = entry.duration() + ends_at - starts_at;
+        if duration < Duration::ZERO || (duration == Duration::ZERO && !entry.duration().is_zero())
+        {
+            return Err(ValidateContentError::field(
+                "endsAt",
+                "Override leaves the entry without duration",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl ValidateContent for Event {
+    fn validate_content(&self) -> Result<(), ValidateContentError> {
+        if self.is_owned && !self.can_edit {
+            return Err(ValidateContentError::new(
+                "The event owner must have editing privileges for it",
+            ));
+        };
+        if let Some(end) = self.entries_end {
+            TimeRange::new(self.entries_start, end).validate_content()
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod validation_tests {
+    use time::macros::datetime;
+
+    use crate::api::events::{EventPayload, UpdateEditPrivilege};
+    use crate::events::models::{EntriesSpan, RecurrenceRule};
+
+    use super::*;
+
+    #[test]
+    fn email_invitations_need_addresses() {
+        let invitation = |email: &str| EmailInvitation {
+            event_id: uuid::Uuid::nil(),
+            sender_id: uuid::Uuid::nil(),
+            email: email.to_string(),
+            can_edit: false,
+        };
+
+        assert!(invitation("kasia@example.com").validate_content().is_ok());
+        for email in [
+            "",
+            "kasia",
+            "@example.com",
+            "kasia@localhost",
+            "kasia@example.",
+            "ka sia@example.com",
+        ] {
+            assert!(invitation(email).validate_content().is_err(), "{email}");
+        }
+    }
+
+    #[test]
+    fn privilege_changes_are_unique() {
+        let change = |can_edit| UpdateEditPrivilege {
+            user_id: uuid::Uuid::nil(),
+            can_edit,
+        };
+
+        assert!(UpdateEditPrivileges {
+            changes: vec![change(true)]
+        }
+        .validate_content()
+        .is_ok());
+        assert!(UpdateEditPrivileges {
+            changes: vec![change(true), change(false)]
+        }
+        .validate_content()
+        .is_err());
+        assert!(UpdateEditPrivileges { changes: vec![] }
+            .validate_content()
+            .is_err());
+    }
+
+    #[test]
+    fn time_range_validation_ok() {
+        let data = TimeRange::new(
+            datetime!(2023-03-01 13:00 UTC),
+            datetime!(2023-03-01 13:01 UTC),
+        );
+        assert!(data.validate_content().is_ok())
+    }
+
+    #[test]
+    fn time_range_validation_err() {
+        let data = TimeRange::new(
+            datetime!(2023-03-01 13:00 UTC),
+            datetime!(2023-03-01 12:59 UTC),
+        );
+        assert!(data.validate_content().is_err())
+    }
+
+    #[test]
+    fn time_rules_validation_ok_1() {
+        let data = TimeRules {
+            ends_at: Some(RecurrenceEndsAt::Count(0)),
+            interval: 1,
+        };
+        assert!(data.validate_content().is_ok())
+    }
+
+    #[test]
+    fn time_rules_validation_ok_2() {
+        let data = TimeRules {
+            ends_at: None,
+            interval: 1,
+        };
+        assert!(data.validate_content().is_ok())
+    }
+
+    #[test]
+    fn time_rules_validation_err() {
+        let data = TimeRules {
+            ends_at: Some(RecurrenceEndsAt::Count(0)),
+            interval: 0,
+        };
+        assert!(data.validate_content().is_err())
+    }
+
+    #[test]
+    fn recurrence_rule_validation_ok() {
+        let data = RecurrenceRuleSchema {
+            time_rules: TimeRules {
+                ends_at: Some(RecurrenceEndsAt::Until(datetime!(2023-03-05 19:00 UTC))),
+                interval: 1,
+            },
+            kind: RecurrenceRuleKind::Weekly { week_map: 1 },
+        };
+        assert!(data.validate_content().is_ok())
+    }
+
+    #[test]
+    fn recurrence_rule_validation_err_1() {
+        let data = RecurrenceRuleSchema {
+            time_rules: TimeRules {
+                ends_at: Some(RecurrenceEndsAt::Until(datetime!(2023-03-05 19:00 UTC))),
+                interval: 0,
+            },
+            kind: RecurrenceRuleKind::Weekly { week_map: 1 },
+        };
+        assert!(data.validate_content().is_err())
+    }
+
+    #[test]
+    fn recurrence_rule_validation_err_2() {
+        let data = RecurrenceRuleSchema {
+            time_rules: TimeRules {
+                ends_at: Some(RecurrenceEndsAt::Until(datetime!(2023-03-05 19:00 UTC))),
+                interval: 1,
+            },
+            kind: RecurrenceRuleKind::Weekly { week_map: 0 },
+        };
+        assert!(data.validate_content().is_err())
+    }
+
+    #[test]
+    fn recurrence_interval_is_bounded_by_kind() {
+        let cases = [
+            (RecurrenceRuleKind::Hourly, 168, "Hourly"),
+            (RecurrenceRuleKind::Minutely, 1440, "Minutely"),
+            (RecurrenceRuleKind::Daily, 365, "Daily"),
+            (RecurrenceRuleKind::Weekly { week_map: 1 }, 52, "Weekly"),
+            (
+                RecurrenceRuleKind::Monthly { is_by_day: true },
+                12,
+                "Monthly by day",
+            ),
+            (
+                RecurrenceRuleKind::Monthly { is_by_day: false },
+                12,
+                "Monthly by weekday",
+            ),
+            (
+                RecurrenceRuleKind::Yearly { is_by_day: true },
+                10,
+                "Yearly by day",
+            ),
+            (
+                RecurrenceRuleKind::Yearly { is_by_day: false },
+                10,
+                "Yearly by weekday",
+            ),
+        ];
+
+        for (kind, max_interval, description) in cases {
+            let rule = |interval| RecurrenceRuleSchema {
+                time_rules: TimeRules {
+                    ends_at: Some(RecurrenceEndsAt::Count(1)),
+                    interval,
+                },
+                kind,
+            };
+            assert!(rule(max_interval).validate_content().is_ok());
+
+            let e = rule(max_interval + 1).validate_content().unwrap_err();
+            let ValidateContentError::InvalidFields(fields) = e else {
+                panic!("Expected invalid fields, got {e:?}");
+            };
+            assert_eq!(fields[0].field, "time_rules.interval");
+            assert_eq!(
+                fields[0].message,
+                format!("{description} recurrence interval is greater than {max_interval}")
+            );
+        }
+    }
+
+    #[test]
+    fn create_event_validation_ok() {
+        let data = CreateEvent {
+            data: EventData {
+                payload: EventPayload {
+                    name: "test_name".to_string(),
+                    description: Some("test_desc".to_string()),
+                },
+                starts_at: datetime!(2023-03-01 12:00 UTC),
+                ends_at: datetime!(2023-03-02 12:00 UTC),
+            },
+            recurrence_rule: Some(RecurrenceRuleSchema {
+                time_rules: TimeRules {
+                    ends_at: Some(RecurrenceEndsAt::Until(datetime!(2023-03-03 12:00 UTC))),
+                    interval: 1,
+                },
+                kind: RecurrenceRuleKind::Weekly { week_map: 1 },
+            }),
+        };
+
+        assert!(data.validate_content().is_ok())
+    }
+
+    #[test]
+    fn create_event_validation_err_1() {
+        let data = CreateEvent {
+            data: EventData {
+                payload: EventPayload {
+                    name: "test_name".to_string(),
+                    description: Some("test_desc".to_string()),
+                },
+                starts_at: datetime!(2023-03-01 12:00 UTC),
+                ends_at: datetime!(2023-03-02 12:00 UTC),
+            },
+            recurrence_rule: Some(RecurrenceRuleSchema {
+                time_rules: TimeRules {
+                    ends_at: Some(RecurrenceEndsAt::Until(datetime!(2023-03-03 12:00 UTC))),
+                    interval: 0,
+                },
+                kind: RecurrenceRuleKind::Weekly { week_map: 1 },
+            }),
+        };
+
+        assert!(data.validate_content().is_err())
+    }
+
+    #[test]
+    fn create_event_validation_err_2() {
+        let data = CreateEvent {
+            data: EventData {
+                payload: EventPayload {
+                    name: "test_name".to_string(),
+                    description: Some("test_desc".to_string()),
+                },
+                starts_at: datetime!(2023-03-01 12:00 UTC),
+                ends_at: datetime!(2023-03-02 12:00 UTC),
+            },
+            recurrence_rule: Some(RecurrenceRuleSchema {
+                time_rules: TimeRules {
+                    ends_at: Some(RecurrenceEndsAt::Until(datetime!(2023-03-03 12:00 UTC))),
+                    interval: 1,
+                },
+                kind: RecurrenceRuleKind::Weekly { week_map: 0 },
+            }),
+        };
+
+        assert!(data.validate_content().is_err())
+    }
+
+    #[test]
+    fn create_event_validation_err_3() {
+        let data = CreateEvent {
+            data: EventData {
+                payload: EventPayload {
+                    name: "test_name".to_string(),
+                    description: Some("test_desc".to_string()),
+                },
+                starts_at: datetime!(2023-03-01 12:01 UTC),
+                ends_at: datetime!(2023-03-01 12:00 UTC),
+            },
+            recurrence_rule: Some(RecurrenceRuleSchema {
+                time_rules: TimeRules {
+                    ends_at: Some(RecurrenceEndsAt::Until(datetime!(2023-03-03 12:00 UTC))),
+                    interval: 1,
+                },
+                kind: RecurrenceRuleKind::Weekly { week_map: 1 },
+            }),
+        };
+
+        assert!(data.validate_content().is_err())
+    }
+
+    #[test]
+    fn create_event_validation_err_4() {
+        let data = CreateEvent {
+            data: EventData {
+                payload: EventPayload {
+                    name: "test_name".to_string(),
+                    description: Some("test_desc".to_string()),
+                },
+                starts_at: datetime!(2023-03-01 12:00 UTC),
+                ends_at: datetime!(2023-03-02 12:00 UTC),
+            },
+            recurrence_rule: Some(RecurrenceRuleSchema {
+                time_rules: TimeRules {
+                    ends_at: Some(RecurrenceEndsAt::Until(datetime!(2023-03-02 11:59 UTC))),
+                    interval: 1,
+                },
+                kind: RecurrenceRuleKind::Weekly { week_map: 1 },
+            }),
+        };
+
+        assert!(data.validate_content().is_err())
+    }
+
+    #[test]
+    fn invalid_fields_are_nested() {
+        let e = ValidateContentError::field("interval", "Time rule interval is equal to 0")
+            .at("time_rules")
+            .at("recurrenceRule");
+        let ValidateContentError::InvalidFields(fields) = e else {
+            panic!("Expected invalid fields, got {e:?}");
+        };
+        assert_eq!(fields[0].field, "recurrenceRule.time_rules.interval");
+
+        let e = ValidateContentError::new("TimeRange duration is negative").at("startsAt");
+        assert_eq!(e.to_json()["fields"][0]["field"], "startsAt");
+        assert_eq!(
+            e.to_json()["error_info"],
+            "Data rejected with validation: TimeRange duration is negative"
+        );
+    }
+
+    #[test]
+    fn create_event_validation_points_at_field() {
+        let data = CreateEvent {
+            data: EventData {
+                payload: EventPayload {
+                    name: "test_name".to_string(),
+                    description: None,
+                },
+                starts_at: datetime!(2023-03-01 12:00 UTC),
+                ends_at: datetime!(2023-03-02 12:00 UTC),
+            },
+            recurrence_rule: Some(RecurrenceRuleSchema {
+                time_rules: TimeRules {
+                    ends_at: None,
+                    interval: 1,
+                },
+                kind: RecurrenceRuleKind::Weekly { week_map: 0 },
+            }),
+        };
+
+        let e = data.validate_content().unwrap_err();
+        assert_eq!(
+            e.to_json()["fields"][0]["field"],
+            "recurrenceRule.kind.weekly.days"
+        );
+    }
+
+    #[test]
+    fn optional_event_data_validation_ok_1() {
+        let data = OptionalEventData {
+            name: None,
+            description: None,
+            starts_at: None,
+            ends_at: None,
+        };
+
+        assert!(data.validate_content().is_ok())
+    }
+
+    #[test]
+    fn optional_event_data_validation_ok_2() {
+        let data = OptionalEventData {
+            name: None,
+            description: None,
+            starts_at: Some(datetime!(2023-03-01 12:00 UTC)),
+            ends_at: None,
+        };
+
+        assert!(data.validate_content().is_ok())
+    }
+
+    #[test]
+    fn optional_event_data_validation_ok_3() {
+        let data = OptionalEventData {
+            name: None,
+            description: None,
+            starts_at: None,
+            ends_at: Some(datetime!(2023-03-01 12:00 UTC)),
+        };
+
+        assert!(data.validate_content().is_ok())
+    }
+
+    #[test]
+    fn optional_event_data_validation_ok_4() {
+        let data = OptionalEventData {
+            name: None,
+            description: None,
+            starts_at: Some(datetime!(2023-03-01 12:00 UTC)),
+            ends_at: Some(datetime!(2023-03-02 12:00 UTC)),
+        };
+
+        assert!(data.validate_content().is_ok())
+    }
+
+    #[test]
+    fn optional_event_data_validation_err() {
+        let data = OptionalEventData {
+            name: None,
+            description: None,
+            starts_at: Some(datetime!(2023-03-01 12:00 UTC)),
+            ends_at: Some(datetime!(2023-03-01 11:59 UTC)),
+        };
+
+        assert!(data.validate_content().is_err())
+    }
+
+    #[test]
+    fn event_validation_ok() {
+        let data = Event {
+            payload: EventPayload {
+                name: "test_name".to_string(),
+                description: Some("test_desc".to_string()),
+            },
+            recurrence_rule: Some(RecurrenceRule {
+                span: Some(EntriesSpan {
+                    end: datetime!(2023-03-03 13:00 UTC),
+                    repetitions: 2,
+                }),
+                kind: RecurrenceRuleKind::Daily,
+                interval: 2,
+            }),
+            recurrence_summary: Some("Every 2 days until 3 Mar 2023".to_string()),
+            entries_start: datetime!(2023-03-01 12:00 UTC),
+            entries_end: Some(datetime!(2023-03-03 13:00 UTC)),
+            is_owned: true,
+            can_edit: true,
+            deleted_at: None,
+            exceptions: None,
+        };
+
+        assert!(data.validate_content().is_ok())
+    }
+
+    #[test]
+    fn event_validation_err() {
+        let data = Event {
+            payload: EventPayload {
+                name: "test_name".to_string(),
+                description: Some("test_desc".to_string()),
+            },
+            recurrence_rule: None,
+            recurrence_summary: None,
+            entries_start: datetime!(2023-03-01 12:00 UTC),
+            entries_end: Some(datetime!(2023-03-01 13:00 UTC)),
+            is_owned: true,
+            can_edit: false,
+            deleted_at: None,
+            exceptions: None,
+        };
+
+        assert!(data.validate_content().is_err())
+    }
+
+    fn shift(starts_at: Option<Duration>, ends_at: Option<Duration>) -> OverrideEventData {
+        OverrideEventData {
+            name: None,
+            description: None,
+            starts_at,
+            ends_at,
+            status: None,
+            added_participants: vec![],
+            excluded_participants: vec![],
+        }
+    }
+
+    const ENTRY: TimeRange = TimeRange {
+        start: datetime!(2023-03-01 12:00 UTC),
+        end: datetime!(2023-03-01 13:00 UTC),
+    };
+
+    #[test]
+    fn override_shift_validation_ok() {
+        let data = shift(Some(Duration::hours(2)), Some(Duration::minutes(90)));
+        assert!(data
+            .validate_with_entry(&ENTRY, OverrideShiftLimit::default())
+            .is_ok())
+    }
+
+    #[test]
+    fn override_shift_validation_err_empty_entry() {
+        let data = shift(Some(Duration::hours(1)), None);
+        assert!(data
+            .validate_with_entry(&ENTRY, OverrideShiftLimit::default())
+            .is_err())
+    }
+
+    #[test]
+    fn override_shift_validation_err_too_far() {
+        let data = shift(Some(Duration::hours(3)), Some(Duration::hours(3)));
+        assert!(data
+            .validate_with_entry(&ENTRY, OverrideShiftLimit(Duration::hours(2)))
+            .is_err())
+    }
+
+    fn with_status(status: OverrideStatus, data: OverrideEventData) -> OverrideEvent {
+        OverrideEvent {
+            override_starts_at: ENTRY.start,
+            override_ends_at: ENTRY.end,
+            data: OverrideEventData {
+                status: Some(status),
+                ..data
+            },
+        }
+    }
+
+    #[test]
+    fn override_status_allows_its_fields() {
+        let moved = shift(Some(Duration::hours(1)), Some(Duration::hours(1)));
+        let cancelled = OverrideEventData {
+            description: Some("Nauczyciel chory".into()),
+            ..shift(None, None)
+        };
+        let substituted = OverrideEventData {
+            name: Some("Matematyka".into()),
+            ..shift(None, None)
+        };
+        let room_change = OverrideEventData {
+            description: Some("Sala 104".into()),
+            ..shift(None, None)
+        };
+
+        for (status, data) in [
+            (OverrideStatus::Moved, moved),
+            (OverrideStatus::Cancelled, cancelled),
+            (OverrideStatus::Substituted, substituted),
+            (OverrideStatus::RoomChange, room_change),
+        ] {
+            assert!(with_status(status, data).validate_content().is_ok());
+        }
+    }
+
+    #[test]
+    fn override_status_rejects_other_fields() {
+        let renamed = OverrideEventData {
+            name: Some("Fizyka".into()),
+            description: Some("Sala 104".into()),
+            ..shift(None, None)
+        };
+
+        for (status, data) in [
+            (OverrideStatus::Moved, shift(None, Some(Duration::ZERO))),
+            (
+                OverrideStatus::Cancelled,
+                shift(Some(Duration::hours(1)), Some(Duration::hours(1))),
+            ),
+            (OverrideStatus::Substituted, shift(None, None)),
+            (OverrideStatus::RoomChange, renamed),
+            (OverrideStatus::RoomChange, shift(None, None)),
+        ] {
+            assert!(with_status(status, data).validate_content().is_err());
+        }
+    }
+
+    #[test]
+    fn sub_daily_recurrences_must_end() {
+        for (kind, description) in [
+            (RecurrenceRuleKind::Hourly, "Hourly"),
+            (RecurrenceRuleKind::Minutely, "Minutely"),
+        ] {
+            let rule = |ends_at| RecurrenceRuleSchema {
+                time_rules: TimeRules {
+                    ends_at,
+                    interval: 1,
+                },
+                kind,
+            };
+            assert!(rule(Some(RecurrenceEndsAt::Count(24)))
+                .validate_content()
+                .is_ok());
+
+            let e = rule(None).validate_content().unwrap_err();
+            let ValidateContentError::InvalidFields(fields) = e else {
+                panic!("Expected invalid fields, got {e:?}");
+            };
+            assert_eq!(fields[0].field, "time_rules.endsAt");
+            assert_eq!(
+                fields[0].message,
+                format!("{description} recurrence must end")
+            );
+        }
+    }
+
+    #[test]
+    fn override_shift_validation_err_precision() {
+        let data = OverrideEvent {
+            override_starts_at: ENTRY.start,
+            override_ends_at: ENTRY.end,
+            data: shift(Some(Duration::nanoseconds(1)), None),
+        };
+        assert!(data.validate_content().is_err())
+    }
+
+    const EVENT: TimeRange = TimeRange {
+        start: datetime!(2023-03-01 10:00 UTC),
+        end: datetime!(2023-03-01 11:00 UTC),
+    };
+
+    fn rule(kind: RecurrenceRuleKind, ends_at: Option<RecurrenceEndsAt>) -> RecurrenceRuleSchema {
+        RecurrenceRuleSchema {
+            time_rules: TimeRules {
+                ends_at,
+                interval: 1,
+            },
+            kind,
+        }
+    }
+
+    #[test]
+    fn recurrence_rule_warns_about_days_before_start() {
+        // Monday and Wednesday for an event starting on a Wednesday
+        let data = rule(
+            RecurrenceRuleKind::Weekly {
+                week_map: 0b1010000,
+            },
+            None,
+        );
+        assert_eq!(data.warnings_with_event(&EVENT).len(), 1);
+
+        let data = rule(
+            RecurrenceRuleKind::Weekly {
+                week_map: 0b0010100,
+            },
+            None,
+        );
+        assert!(data.warnings_with_event(&EVENT).is_empty());
+    }
+
+    #[test]
+    fn recurrence_rule_warns_about_until_between_entries() {
+        let data = rule(
+            RecurrenceRuleKind::Daily,
+            Some(RecurrenceEndsAt::Until(datetime!(2023-03-05 12:00 UTC))),
+        );
+        assert_eq!(
+            data.warnings_with_event(&EVENT),
+            vec![
+                "Recurrence until falls between entries, the last entry ends at 2023-03-05T11:00:00Z"
+                    .to_string()
+            ]
+        );
+
+        let data = rule(
+            RecurrenceRuleKind::Daily,
+            Some(RecurrenceEndsAt::Until(datetime!(2023-03-05 11:00 UTC))),
+        );
+        assert!(data.warnings_with_event(&EVENT).is_empty());
+    }
+
+    #[test]
+    fn recurrence_rule_repetitions_are_limited() {
+        let limit = RepetitionLimit(3);
+        let data = rule(
+            RecurrenceRuleKind::Daily,
+            Some(RecurrenceEndsAt::Count(4_000_000_000)),
+        );
+        assert!(data
+            .validate_repetitions(&EVENT, RepetitionLimit::default())
+            .is_err());
+
+        // The first entry is followed by `count` repetitions
+        let data = rule(RecurrenceRuleKind::Daily, Some(RecurrenceEndsAt::Count(2)));
+        assert!(data.validate_repetitions(&EVENT, limit).is_ok());
+        let data = rule(RecurrenceRuleKind::Daily, Some(RecurrenceEndsAt::Count(3)));
+        assert!(data.validate_repetitions(&EVENT, limit).is_err());
+
+        // Five entries, from the 1st to the 5th
+        let data = rule(
+            RecurrenceRuleKind::Daily,
+            Some(RecurrenceEndsAt::Until(datetime!(2023-03-05 11:00 UTC))),
+        );
+        assert!(data
+            .validate_repetitions(&EVENT, RepetitionLimit(4))
+            .is_err());
+        assert!(data
+            .validate_repetitions(&EVENT, RepetitionLimit(5))
+            .is_ok());
+    }
+}
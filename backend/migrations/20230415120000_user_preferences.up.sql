@@ -0,0 +1,2 @@
+ALTER TABLE users
+    ADD COLUMN week_start SMALLINT NOT NULL DEFAULT 0 CHECK (week_start BETWEEN 0 AND 6);
@@ -0,0 +1,8 @@
+UPDATE recurrence_rules
+SET recurrence = jsonb_build_object('weekly', jsonb_build_object('weekMap', (
+    SELECT COALESCE(SUM(1 << (7 - n::INT)), 0)
+    FROM unnest(ARRAY ['monday', 'tuesday', 'wednesday', 'thursday', 'friday', 'saturday', 'sunday'])
+        WITH ORDINALITY AS week(day, n)
+    WHERE recurrence -> 'weekly' -> 'days' ? day
+)))
+WHERE recurrence ? 'weekly';
@@ -0,0 +1,8 @@
+UPDATE recurrence_rules
+SET recurrence = jsonb_build_object('weekly', jsonb_build_object('days', (
+    SELECT COALESCE(jsonb_agg(day ORDER BY n), '[]'::jsonb)
+    FROM unnest(ARRAY ['monday', 'tuesday', 'wednesday', 'thursday', 'friday', 'saturday', 'sunday'])
+        WITH ORDINALITY AS week(day, n)
+    WHERE ((recurrence -> 'weekly' ->> 'weekMap')::INT >> (7 - n::INT)) & 1 = 1
+)))
+WHERE recurrence ? 'weekly';
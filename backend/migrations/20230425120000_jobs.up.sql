@@ -0,0 +1,16 @@
+CREATE TABLE jobs
+(
+    id           UUID                 DEFAULT gen_random_uuid(),
+    kind         TEXT        NOT NULL,
+    payload      JSONB       NOT NULL,
+    attempts     INT         NOT NULL DEFAULT 0,
+    max_attempts INT         NOT NULL,
+    run_at       TIMESTAMPTZ NOT NULL DEFAULT now(),
+    locked_until TIMESTAMPTZ,
+    last_error   TEXT,
+    failed_at    TIMESTAMPTZ,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX jobs_pending_idx ON jobs (run_at) WHERE failed_at IS NULL;
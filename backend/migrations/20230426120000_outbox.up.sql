@@ -0,0 +1,10 @@
+CREATE TABLE outbox
+(
+    id            UUID                 DEFAULT gen_random_uuid(),
+    topic         TEXT        NOT NULL,
+    aggregate_id  UUID        NOT NULL,
+    payload       JSONB       NOT NULL,
+    created_at    TIMESTAMPTZ NOT NULL DEFAULT now(),
+    dispatched_at TIMESTAMPTZ,
+    PRIMARY KEY (id)
+);
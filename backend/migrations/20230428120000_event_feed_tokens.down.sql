@@ -0,0 +1 @@
+DROP TABLE event_feed_tokens;
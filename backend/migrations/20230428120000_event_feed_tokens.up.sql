@@ -0,0 +1,10 @@
+CREATE TABLE event_feed_tokens
+(
+    token      UUID                 DEFAULT gen_random_uuid(),
+    event_id   UUID        NOT NULL,
+    created_by UUID        NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (token),
+    FOREIGN KEY (event_id) REFERENCES events (id) ON DELETE CASCADE,
+    FOREIGN KEY (created_by) REFERENCES users (id)
+);
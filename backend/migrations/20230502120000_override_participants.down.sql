@@ -0,0 +1 @@
+DROP TABLE event_override_participants;
@@ -0,0 +1,9 @@
+CREATE TABLE event_override_participants
+(
+    override_id UUID NOT NULL,
+    user_id     UUID NOT NULL,
+    is_excluded BOOL NOT NULL,
+    PRIMARY KEY (override_id, user_id),
+    FOREIGN KEY (override_id) REFERENCES event_overrides (id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users (id)
+);
@@ -0,0 +1,13 @@
+CREATE TABLE reminders
+(
+    id             UUID                 DEFAULT gen_random_uuid(),
+    event_id       UUID        NOT NULL,
+    user_id        UUID        NOT NULL,
+    minutes_before INT         NOT NULL,
+    webhook_url    TEXT,
+    webhook_format TEXT        NOT NULL DEFAULT 'json' CHECK (webhook_format IN ('json', 'ics')),
+    created_at     TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (id),
+    FOREIGN KEY (event_id) REFERENCES events (id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
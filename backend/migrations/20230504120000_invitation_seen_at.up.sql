@@ -0,0 +1,2 @@
+ALTER TABLE user_event_invitations
+    ADD COLUMN seen_at TIMESTAMPTZ;
@@ -0,0 +1,8 @@
+CREATE TABLE invitation_counters
+(
+    sender_id    UUID        NOT NULL,
+    window_start TIMESTAMPTZ NOT NULL,
+    sent         INT         NOT NULL DEFAULT 0,
+    PRIMARY KEY (sender_id, window_start),
+    FOREIGN KEY (sender_id) REFERENCES users (id) ON DELETE CASCADE
+);
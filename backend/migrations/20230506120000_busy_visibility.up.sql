@@ -0,0 +1,8 @@
+CREATE TABLE busy_visibility
+(
+    owner_id  UUID NOT NULL,
+    viewer_id UUID NOT NULL,
+    PRIMARY KEY (owner_id, viewer_id),
+    FOREIGN KEY (owner_id) REFERENCES users (id) ON DELETE CASCADE,
+    FOREIGN KEY (viewer_id) REFERENCES users (id) ON DELETE CASCADE
+);
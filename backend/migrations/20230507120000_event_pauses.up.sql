@@ -0,0 +1,9 @@
+CREATE TABLE event_pauses
+(
+    id         UUID PRIMARY KEY     DEFAULT gen_random_uuid(),
+    event_id   UUID        NOT NULL,
+    starts_at  TIMESTAMPTZ NOT NULL,
+    ends_at    TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    FOREIGN KEY (event_id) REFERENCES events (id) ON DELETE CASCADE
+);
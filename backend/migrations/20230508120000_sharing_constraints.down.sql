@@ -0,0 +1,11 @@
+DROP TRIGGER invitations_not_participant ON user_event_invitations;
+DROP FUNCTION check_receiver_not_participant;
+
+DROP TRIGGER events_owner_not_participant ON events;
+DROP FUNCTION check_owner_not_participant;
+DROP TRIGGER user_events_not_owner ON user_events;
+DROP FUNCTION check_participant_not_owner;
+
+ALTER TABLE event_overrides
+    DROP CONSTRAINT event_overrides_event_id_fkey,
+    ADD FOREIGN KEY (event_id) REFERENCES events (id);
@@ -0,0 +1,56 @@
+ALTER TABLE event_overrides
+    DROP CONSTRAINT event_overrides_event_id_fkey,
+    ADD FOREIGN KEY (event_id) REFERENCES events (id) ON DELETE CASCADE;
+
+CREATE FUNCTION check_participant_not_owner() RETURNS TRIGGER AS
+$$
+BEGIN
+    IF EXISTS(SELECT 1 FROM events WHERE id = NEW.event_id AND owner_id = NEW.user_id) THEN
+        RAISE EXCEPTION 'Owner cannot participate in their own event'
+            USING ERRCODE = 'check_violation', CONSTRAINT = 'user_events_not_owner';
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER user_events_not_owner
+    BEFORE INSERT OR UPDATE
+    ON user_events
+    FOR EACH ROW
+EXECUTE FUNCTION check_participant_not_owner();
+
+CREATE FUNCTION check_owner_not_participant() RETURNS TRIGGER AS
+$$
+BEGIN
+    IF EXISTS(SELECT 1 FROM user_events WHERE event_id = NEW.id AND user_id = NEW.owner_id) THEN
+        RAISE EXCEPTION 'Owner cannot participate in their own event'
+            USING ERRCODE = 'check_violation', CONSTRAINT = 'user_events_not_owner';
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER events_owner_not_participant
+    BEFORE UPDATE OF owner_id
+    ON events
+    FOR EACH ROW
+EXECUTE FUNCTION check_owner_not_participant();
+
+CREATE FUNCTION check_receiver_not_participant() RETURNS TRIGGER AS
+$$
+BEGIN
+    IF EXISTS(SELECT 1 FROM events WHERE id = NEW.event_id AND owner_id = NEW.receiver_id)
+        OR EXISTS(SELECT 1 FROM user_events WHERE event_id = NEW.event_id AND user_id = NEW.receiver_id)
+    THEN
+        RAISE EXCEPTION 'Invitation receiver already participates in the event'
+            USING ERRCODE = 'check_violation', CONSTRAINT = 'invitations_not_participant';
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER invitations_not_participant
+    BEFORE INSERT OR UPDATE
+    ON user_event_invitations
+    FOR EACH ROW
+EXECUTE FUNCTION check_receiver_not_participant();
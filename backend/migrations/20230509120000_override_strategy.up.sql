@@ -0,0 +1,2 @@
+ALTER TABLE events
+    ADD COLUMN override_strategy SMALLINT NOT NULL DEFAULT 0 CHECK (override_strategy BETWEEN 0 AND 2);
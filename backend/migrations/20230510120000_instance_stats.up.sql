@@ -0,0 +1,11 @@
+CREATE TABLE instance_stats
+(
+    day                 TIMESTAMPTZ NOT NULL,
+    users               BIGINT      NOT NULL,
+    events              BIGINT      NOT NULL,
+    entries             BIGINT      NOT NULL,
+    pending_invitations BIGINT      NOT NULL,
+    database_bytes      BIGINT      NOT NULL,
+    computed_at         TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (day)
+);
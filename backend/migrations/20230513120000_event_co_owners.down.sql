@@ -0,0 +1,3 @@
+ALTER TABLE user_events
+    DROP CONSTRAINT user_events_owner_can_edit,
+    DROP COLUMN is_owner;
@@ -0,0 +1,4 @@
+-- Co-owners manage the event like its owner, they always can edit it
+ALTER TABLE user_events
+    ADD COLUMN is_owner BOOL NOT NULL DEFAULT false,
+    ADD CONSTRAINT user_events_owner_can_edit CHECK (can_edit OR NOT is_owner);
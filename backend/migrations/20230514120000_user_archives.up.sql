@@ -0,0 +1,11 @@
+CREATE TABLE user_archives
+(
+    id           UUID                 DEFAULT gen_random_uuid(),
+    user_id      UUID        NOT NULL,
+    storage_key  TEXT,
+    requested_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    completed_at TIMESTAMPTZ,
+    failed_at    TIMESTAMPTZ,
+    PRIMARY KEY (id),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
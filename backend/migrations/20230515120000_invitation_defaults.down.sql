@@ -0,0 +1,2 @@
+ALTER TABLE users
+    DROP COLUMN invite_can_edit;
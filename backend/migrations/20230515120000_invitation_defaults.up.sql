@@ -0,0 +1,2 @@
+ALTER TABLE users
+    ADD COLUMN invite_can_edit BOOLEAN NOT NULL DEFAULT false;
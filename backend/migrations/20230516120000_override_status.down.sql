@@ -0,0 +1,2 @@
+ALTER TABLE event_overrides
+    DROP COLUMN status;
@@ -0,0 +1,2 @@
+ALTER TABLE event_overrides
+    ADD COLUMN status SMALLINT CHECK (status BETWEEN 0 AND 3);
@@ -0,0 +1 @@
+DROP INDEX outbox_aggregate_idx;
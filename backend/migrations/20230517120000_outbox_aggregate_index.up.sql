@@ -0,0 +1 @@
+CREATE INDEX outbox_aggregate_idx ON outbox (aggregate_id, created_at);
@@ -0,0 +1,2 @@
+ALTER TABLE user_event_invitations
+    DROP COLUMN created_at;
@@ -0,0 +1,2 @@
+ALTER TABLE user_event_invitations
+    ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT now();
@@ -0,0 +1,11 @@
+CREATE TABLE username_history
+(
+    username    TEXT        NOT NULL,
+    tag         INT         NOT NULL,
+    user_id     UUID        NOT NULL,
+    replaced_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (username, tag),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE INDEX username_history_user_idx ON username_history (user_id, replaced_at);
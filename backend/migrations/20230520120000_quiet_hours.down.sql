@@ -0,0 +1,4 @@
+ALTER TABLE reminders
+    DROP COLUMN is_critical;
+
+DROP TABLE quiet_hours;
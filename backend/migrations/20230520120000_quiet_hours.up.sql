@@ -0,0 +1,17 @@
+CREATE TABLE quiet_hours
+(
+    id         UUID                 DEFAULT gen_random_uuid(),
+    user_id    UUID        NOT NULL,
+    starts_at  TIME        NOT NULL,
+    ends_at    TIME        NOT NULL CHECK (ends_at <> starts_at),
+    week_map   SMALLINT    NOT NULL CHECK (week_map BETWEEN 1 AND 127),
+    action     TEXT        NOT NULL DEFAULT 'defer' CHECK (action IN ('defer', 'suppress')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (id),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE INDEX quiet_hours_user_idx ON quiet_hours (user_id);
+
+ALTER TABLE reminders
+    ADD COLUMN is_critical BOOLEAN NOT NULL DEFAULT false;
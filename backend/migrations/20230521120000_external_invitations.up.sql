@@ -0,0 +1,15 @@
+CREATE TABLE external_invitations
+(
+    id         UUID                 DEFAULT gen_random_uuid(),
+    event_id   UUID        NOT NULL,
+    sender_id  UUID        NOT NULL,
+    email      TEXT        NOT NULL,
+    can_edit   BOOL        NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (id),
+    UNIQUE (event_id, email),
+    FOREIGN KEY (event_id) REFERENCES events (id) ON DELETE CASCADE,
+    FOREIGN KEY (sender_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE INDEX external_invitations_email_idx ON external_invitations (email);
@@ -0,0 +1,9 @@
+CREATE TABLE event_digests
+(
+    event_id     UUID        NOT NULL,
+    frequency    TEXT        NOT NULL CHECK (frequency IN ('daily', 'weekly')),
+    last_sent_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (event_id),
+    FOREIGN KEY (event_id) REFERENCES events (id) ON DELETE CASCADE
+);
@@ -0,0 +1 @@
+DROP TABLE locked_occurrences;
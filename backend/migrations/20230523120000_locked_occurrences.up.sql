@@ -0,0 +1,10 @@
+CREATE TABLE locked_occurrences
+(
+    event_id      UUID        NOT NULL,
+    occurrence_id UUID        NOT NULL,
+    starts_at     TIMESTAMPTZ NOT NULL,
+    ends_at       TIMESTAMPTZ NOT NULL,
+    created_at    TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (event_id, occurrence_id),
+    FOREIGN KEY (event_id) REFERENCES events (id) ON DELETE CASCADE
+);
@@ -0,0 +1,12 @@
+CREATE TABLE signup_codes
+(
+    code       TEXT        NOT NULL,
+    created_by UUID,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_by    UUID,
+    used_at    TIMESTAMPTZ,
+    PRIMARY KEY (code),
+    FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL,
+    FOREIGN KEY (used_by) REFERENCES users (id) ON DELETE SET NULL
+);
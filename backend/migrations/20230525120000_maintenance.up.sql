@@ -0,0 +1,8 @@
+CREATE TABLE maintenance
+(
+    id         BOOLEAN     NOT NULL DEFAULT TRUE,
+    is_enabled BOOLEAN     NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (id),
+    CHECK (id)
+);
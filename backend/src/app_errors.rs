@@ -1,7 +1,20 @@
-use crate::utils::auth::errors::AuthError;
-use crate::utils::events::errors::EventError;
-use anyhow::Context;
+use crate::modules::error_reporting::capture_unexpected;
+use crate::utils::scim::SCIM_CONTENT_TYPE;
 use axum::response::IntoResponse;
+use axum::Json;
+use bimetable_db::utils::admin::errors::AdminError;
+use bimetable_db::utils::auth::errors::AuthError;
+use bimetable_db::utils::events::errors::EventError;
+use bimetable_db::utils::invitations::errors::InvitationError;
+use bimetable_db::utils::reminders::errors::ReminderError;
+use bimetable_db::utils::scim::errors::ScimError;
+use bimetable_db::utils::search::errors::SearchError;
+use bimetable_db::utils::users::errors::UserError;
+use bimetable_domain::api::scim::ERROR_SCHEMA;
+use bimetable_domain::validation::{invalid_fields, ValidateContentError};
+use http::header::CONTENT_TYPE;
+use http::StatusCode;
+use serde_json::json;
 use thiserror::Error;
 use tracing::error;
 
@@ -17,19 +30,244 @@ pub enum AppError {
 impl IntoResponse for AppError {
     fn into_response(self) -> axum::response::Response {
         match self {
-            AppError::AuthError(e) => e.into_response(),
-            AppError::EventError(e) => e.into_response(),
+            AppError::AuthError(e) => ApiError(e).into_response(),
+            AppError::EventError(e) => ApiError(e).into_response(),
         }
     }
 }
 
-pub trait DefaultContext<C, T, E>: Context<T, E> {
-    fn dc(self) -> anyhow::Result<T>
-    where
-        Self: Sized,
-    {
-        self.context("No context provided")
+/// Error of a service answered as a response.
+///
+/// The services don't depend on the web stack, so their errors get their responses through this wrapper.
+#[derive(Debug)]
+pub struct ApiError<E>(pub E);
+
+impl<E> From<E> for ApiError<E> {
+    fn from(e: E) -> Self {
+        Self(e)
+    }
+}
+
+/// Lets `?` convert the errors that the service error converts from.
+macro_rules! api_error_from {
+    ($error:ty: $($source:ty),+) => {
+        $(
+            impl From<$source> for ApiError<$error> {
+                fn from(e: $source) -> Self {
+                    Self(e.into())
+                }
+            }
+        )+
+    };
+}
+
+api_error_from!(AuthError: sqlx::Error);
+api_error_from!(EventError: anyhow::Error, ValidateContentError);
+api_error_from!(AdminError: sqlx::Error);
+
+fn validation_status(e: &ValidateContentError) -> StatusCode {
+    match e {
+        ValidateContentError::Expected(_) | ValidateContentError::InvalidFields(_) => {
+            StatusCode::UNPROCESSABLE_ENTITY
+        }
+        ValidateContentError::Unexpected(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+impl IntoResponse for ApiError<AuthError> {
+    fn into_response(self) -> axum::response::Response {
+        let status_code = match &self.0 {
+            AuthError::UserAlreadyExists => StatusCode::BAD_REQUEST,
+            AuthError::MissingCredential => StatusCode::BAD_REQUEST,
+            AuthError::WeakPassword => StatusCode::BAD_REQUEST,
+            AuthError::WrongLoginOrPassword => StatusCode::UNAUTHORIZED,
+            AuthError::InvalidToken => StatusCode::UNAUTHORIZED,
+            AuthError::InvalidUsername(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AuthError::TagOverflow => StatusCode::BAD_REQUEST,
+            AuthError::UserNotFound => StatusCode::NOT_FOUND,
+            AuthError::Deactivated => StatusCode::FORBIDDEN,
+            AuthError::SignupDisabled => StatusCode::FORBIDDEN,
+            AuthError::EmailDomainNotAllowed => StatusCode::FORBIDDEN,
+            AuthError::InvalidSignupCode => StatusCode::FORBIDDEN,
+            AuthError::Unexpected(e) => {
+                capture_unexpected(e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+
+        let body = match self.0 {
+            AuthError::InvalidUsername(e) => {
+                json!({ "error_info": "Invalid username", "fields": invalid_fields(&e) })
+            }
+            AuthError::Unexpected(_) => json!({ "error_info": "Unexpected server error" }),
+            e => json!({ "error_info": e.to_string() }),
+        };
+
+        (status_code, Json(body)).into_response()
+    }
+}
+
+impl IntoResponse for ApiError<EventError> {
+    fn into_response(self) -> axum::response::Response {
+        let status_code = match &self.0 {
+            EventError::InvalidData(e) => validation_status(e),
+            EventError::NotFound => StatusCode::NOT_FOUND,
+            EventError::OwnerParticipation => StatusCode::CONFLICT,
+            EventError::ShadowingOverride => StatusCode::CONFLICT,
+            EventError::Locked => StatusCode::LOCKED,
+            EventError::LockedOccurrence => StatusCode::FORBIDDEN,
+            EventError::Unexpected(e) => {
+                capture_unexpected(e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            EventError::MismatchedPrivileges => StatusCode::FORBIDDEN,
+        };
+
+        let body = match self.0 {
+            EventError::Unexpected(_) => json!({ "error_info": "Unexpected server error" }),
+            EventError::InvalidData(e) => e.to_json(),
+            e => json!({ "error_info": e.to_string() }),
+        };
+
+        (status_code, Json(body)).into_response()
+    }
+}
+
+impl IntoResponse for ApiError<AdminError> {
+    fn into_response(self) -> axum::response::Response {
+        let status_code = match &self.0 {
+            AdminError::Forbidden => StatusCode::FORBIDDEN,
+            AdminError::UserNotFound => StatusCode::NOT_FOUND,
+            AdminError::Unexpected(e) => {
+                capture_unexpected(e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+
+        let info = match self.0 {
+            AdminError::Unexpected(_) => "Unexpected server error".to_string(),
+            e => e.to_string(),
+        };
+
+        (status_code, Json(json!({ "error_info": info }))).into_response()
+    }
+}
+
+impl IntoResponse for ApiError<InvitationError> {
+    fn into_response(self) -> axum::response::Response {
+        let status_code = match &self.0 {
+            InvitationError::Missing => StatusCode::NOT_FOUND,
+            InvitationError::EventNotFound => StatusCode::NOT_FOUND,
+            InvitationError::MismatchedPrivileges => StatusCode::FORBIDDEN,
+            InvitationError::TooMany => StatusCode::TOO_MANY_REQUESTS,
+            InvitationError::AlreadyParticipant => StatusCode::CONFLICT,
+            InvitationError::InvalidData(e) => validation_status(e),
+            InvitationError::Unexpected(e) => {
+                capture_unexpected(e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+
+        let body = match self.0 {
+            InvitationError::Unexpected(_) => json!({ "error_info": "Unexpected server error" }),
+            InvitationError::InvalidData(e) => e.to_json(),
+            e => json!({ "error_info": e.to_string() }),
+        };
+
+        (status_code, Json(body)).into_response()
+    }
+}
+
+impl IntoResponse for ApiError<ReminderError> {
+    fn into_response(self) -> axum::response::Response {
+        let status_code = match &self.0 {
+            ReminderError::Missing | ReminderError::EventMissing => StatusCode::NOT_FOUND,
+            ReminderError::InvalidData(e) => validation_status(e),
+            ReminderError::Unexpected(e) => {
+                capture_unexpected(e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+
+        let body = match self.0 {
+            ReminderError::Unexpected(_) => json!({ "error_info": "Unexpected server error" }),
+            ReminderError::InvalidData(e) => e.to_json(),
+            e => json!({ "error_info": e.to_string() }),
+        };
+
+        (status_code, Json(body)).into_response()
+    }
+}
+
+impl IntoResponse for ApiError<ScimError> {
+    fn into_response(self) -> axum::response::Response {
+        let status_code = match &self.0 {
+            ScimError::Disabled => StatusCode::NOT_FOUND,
+            ScimError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ScimError::NotFound => StatusCode::NOT_FOUND,
+            ScimError::Conflict => StatusCode::CONFLICT,
+            ScimError::InvalidValue(_) => StatusCode::BAD_REQUEST,
+            ScimError::InvalidFilter => StatusCode::BAD_REQUEST,
+            ScimError::InvalidPath(_) => StatusCode::BAD_REQUEST,
+            ScimError::Unexpected(e) => {
+                capture_unexpected(e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+
+        let detail = match &self.0 {
+            ScimError::Unexpected(_) => "Unexpected server error".to_string(),
+            e => e.to_string(),
+        };
+        let mut body = json!({
+            "schemas": [ERROR_SCHEMA],
+            "status": status_code.as_u16().to_string(),
+            "detail": detail,
+        });
+        if let Some(scim_type) = self.0.scim_type() {
+            body["scimType"] = json!(scim_type);
+        }
+
+        (status_code, [(CONTENT_TYPE, SCIM_CONTENT_TYPE)], Json(body)).into_response()
+    }
+}
+
+impl IntoResponse for ApiError<SearchError> {
+    fn into_response(self) -> axum::response::Response {
+        let status_code = match &self.0 {
+            SearchError::Unexpected(e) => {
+                capture_unexpected(e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+
+        let info = match self.0 {
+            SearchError::Unexpected(_) => "Unexpected server error".to_string(),
+        };
+
+        (status_code, Json(json!({ "error_info": info }))).into_response()
     }
 }
 
-impl<C, T, E> DefaultContext<C, T, E> for C where C: Context<T, E> {}
+impl IntoResponse for ApiError<UserError> {
+    fn into_response(self) -> axum::response::Response {
+        let status_code = match &self.0 {
+            UserError::NotFound => StatusCode::NOT_FOUND,
+            UserError::ArchiveNotFound => StatusCode::NOT_FOUND,
+            UserError::QuietHoursNotFound => StatusCode::NOT_FOUND,
+            UserError::InvalidData(e) => validation_status(e),
+            UserError::Unexpected(e) => {
+                capture_unexpected(e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+
+        let body = match self.0 {
+            UserError::Unexpected(_) => json!({ "error_info": "Unexpected server error" }),
+            UserError::InvalidData(e) => e.to_json(),
+            e => json!({ "error_info": e.to_string() }),
+        };
+
+        (status_code, Json(body)).into_response()
+    }
+}
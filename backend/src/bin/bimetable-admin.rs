@@ -0,0 +1,142 @@
+use std::io::BufRead;
+
+use anyhow::Context;
+use bimetable_db::config::get_config;
+use bimetable_db::modules::database::{get_postgres_pool, run_migrations};
+use bimetable_db::utils::admin::{
+    delete_deactivated_events, get_instance_stats, purge_deleted_events, set_admin,
+};
+use bimetable_db::utils::auth::{reset_user_password, try_register_user};
+use clap::{Parser, Subcommand};
+use dotenv::dotenv;
+use secrecy::SecretString;
+use time::{Duration, OffsetDateTime};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+/// Administration of a bimetable instance, using the database of the server configuration.
+#[derive(Parser)]
+#[command(name = "bimetable-admin")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Creates a user with the password read from stdin
+    CreateUser {
+        #[arg(long)]
+        login: String,
+        #[arg(long)]
+        username: String,
+        /// Grants admin privileges
+        #[arg(long)]
+        admin: bool,
+    },
+    /// Sets a password read from stdin and signs the user out everywhere
+    ResetPassword {
+        #[arg(long)]
+        login: String,
+    },
+    /// Permanently deletes events soft deleted some days ago
+    PurgeDeleted {
+        #[arg(long, default_value_t = 30)]
+        older_than_days: u32,
+    },
+    /// Deletes events of users deactivated some days ago, ending their grace period
+    DeleteDeactivated {
+        #[arg(long, default_value_t = 30)]
+        older_than_days: u32,
+    },
+    /// Applies pending migrations
+    Migrate,
+    /// Prints record counts of the instance
+    Stats,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenv().ok();
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::EnvFilter::new(
+            std::env::var("RUST_LOG").unwrap_or_else(|_| "bimetable=warn".into()),
+        ))
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .init();
+
+    let cli = Cli::parse();
+    let settings = get_config()?;
+    let pool = get_postgres_pool(settings.postgres, false).await;
+
+    match cli.command {
+        Command::CreateUser {
+            login,
+            username,
+            admin,
+        } => {
+            let password = read_password()?;
+            let user_id = try_register_user(
+                &pool,
+                &login,
+                password,
+                &username,
+                &settings.passwords,
+                &settings.usernames,
+                None,
+            )
+            .await
+            .context("Failed to create user")?;
+            if admin {
+                set_admin(&pool, user_id, true)
+                    .await
+                    .context("Failed to grant admin privileges")?;
+            }
+            println!("Created user {user_id}");
+        }
+        Command::ResetPassword { login } => {
+            let password = read_password()?;
+            let user_id = reset_user_password(&pool, &login, password, &settings.passwords)
+                .await
+                .context("Failed to reset password")?;
+            println!("Reset password of user {user_id}");
+        }
+        Command::PurgeDeleted { older_than_days } => {
+            let deleted_before = OffsetDateTime::now_utc() - Duration::days(older_than_days.into());
+            let purged = purge_deleted_events(&pool, deleted_before)
+                .await
+                .context("Failed to purge events")?;
+            println!("Purged {purged} events deleted before {deleted_before}");
+        }
+        Command::DeleteDeactivated { older_than_days } => {
+            let now = OffsetDateTime::now_utc();
+            let deactivated_before = now - Duration::days(older_than_days.into());
+            let deleted = delete_deactivated_events(&pool, deactivated_before, now)
+                .await
+                .context("Failed to delete events")?;
+            println!("Deleted {deleted} events of users deactivated before {deactivated_before}");
+        }
+        Command::Migrate => {
+            let applied = run_migrations(&pool).await?;
+            println!("Applied {} migrations {applied:?}", applied.len());
+        }
+        Command::Stats => {
+            let stats = get_instance_stats(&pool)
+                .await
+                .context("Failed to get stats")?;
+            print!("{stats}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the first line of stdin, so that passwords stay out of the shell history.
+fn read_password() -> anyhow::Result<SecretString> {
+    let mut password = String::new();
+    std::io::stdin()
+        .lock()
+        .read_line(&mut password)
+        .context("Failed to read password from stdin")?;
+
+    Ok(SecretString::new(password.trim().to_string()))
+}
@@ -1,34 +1,94 @@
 use crate::routes::{
-    auth::models::*, auth::*, events::models::*, events::*, invitations::models::*, invitations::*,
-    search::models::*, search::*,
+    admin::*, auth::*, events::*, invitations::*, reminders::*, scim::*, search::*, users::*,
 };
-use crate::utils::events::models::*;
+use bimetable_domain::api::{
+    admin::*, auth::*, events::*, invitations::*, reminders::*, scim::*, search::*, users::*,
+};
+use bimetable_domain::events::models::*;
+use bimetable_domain::events::normalize::AnchorAdjustment;
 use utoipa::OpenApi;
 
 #[derive(OpenApi)]
 #[openapi(
-info(title = "Bimetable", description = "Bimetable calendar", ),
+info(title = "Bimetable", description = "Bimetable calendar", version = "1"),
+servers(
+(url = "/api/v1", description = "Version 1"),
+(url = "/", description = "Unversioned paths, deprecated in favour of version 1"),
+),
 paths(
 post_register_user,
 post_login_user,
+post_issue_token,
+post_refresh_token,
 post_logout_user,
+post_logout_all_user,
 post_refresh_user_token,
+post_deactivate_user,
 protected_zone,
 create_event,
+import_xlsx_events,
+import_classroom_events,
 get_events,
+get_combined_events,
+suggest_slot,
 get_event,
 delete_event_permanently,
 update_event,
+update_event_recurrence,
+split_event,
+pause_event,
+update_override_strategy,
+get_digest,
+update_digest,
+delete_digest,
+create_event_feed,
+delete_event_feed,
+get_event_ics,
+export_event,
+watch_event_presence,
 create_event_override,
+create_occurrence_override,
+lock_occurrence,
+unlock_occurrence,
 update_edit_privileges,
+update_many_edit_privileges,
+update_co_owner,
 update_event_owner,
 disconnect_user_from_event,
 disconnect_owner_from_event,
 create_direct,
+create_email,
 fetch_direct,
+count_direct,
+mark_seen_direct,
 respond_direct,
 search_users,
 search_events,
+get_preferences,
+update_preferences,
+get_quiet_hours,
+put_quiet_hours,
+remove_quiet_hours,
+get_handles,
+grant_visibility,
+revoke_visibility,
+export_archive,
+get_export,
+delete_account,
+get_maintenance,
+set_maintenance,
+get_stats,
+get_retention,
+post_signup_codes,
+deactivate_user,
+get_reminders,
+put_reminder,
+remove_reminder,
+scim_list_users,
+scim_create_user,
+scim_get_user,
+scim_replace_user,
+scim_patch_user,
 ),
 components(schemas(
 CreateEvent,
@@ -39,25 +99,90 @@ RecurrenceEndsAt,
 RecurrenceEndsAt,
 TimeRules,
 EventFilter,
+EntrySort,
+SortDirection,
+EventFields,
 Event,
 Events,
+EventsPage,
+EventExceptions,
+RangedOverride,
 Entry,
+EffectiveEntry,
+EntrySegment,
 Override,
 OptionalEventData,
 OverrideEvent,
+OverrideEventData,
 UpdateEvent,
+UpdateRecurrence,
+SplitEvent,
+PauseEvent,
+EventFeedToken,
+EventExport,
+RecurrenceRuleSchema,
 LoginCredentials,
+AuthTokens,
 RegisterCredentials,
 CreateEventResult,
+UpdateRecurrenceResult,
+CombinedBusy,
+SuggestSlot,
+SuggestedSlots,
+AnchorAdjustment,
+ImportEventsResult,
+ImportMapping,
 UpdateEditPrivilege,
+UpdateEditPrivileges,
+UpdateCoOwner,
 UpdateEventOwner,
+UpdateOverrideStrategy,
+OverrideStrategy,
+UpdateEventDigest,
+EventDigest,
+DigestFrequency,
+DigestSummary,
+DigestPayload,
+OverrideStatus,
 NewEventOwner,
 SearchUsers,
+SearchMode,
 SearchUsersResult,
 SearchEvents,
+SearchEventsResult,
+EventFacets,
 CreateDirectInvitation,
-RespondDirectInvitation
+CreateEmailInvitation,
+RespondDirectInvitation,
+InvitationCount,
+ReceivedInvitation,
+DayOfWeek,
+UserPreferences,
+UpdateUserPreferences,
+QuietHours,
+CreateQuietHours,
+QuietAction,
+UserHandle,
+UserArchive,
+ArchiveStatus,
+MaintenanceStatus,
+DailyStats,
+RetentionPolicy,
+MintSignupCodes,
+SignupCode,
+CreateReminder,
+CreateReminderResult,
+Reminder,
+ReminderWebhook,
+WebhookFormat,
+ReminderPayload,
+ScimUser,
+ScimMeta,
+ScimUserRequest,
+ScimPatch,
+ScimPatchOperation,
+ScimListResponse
 )),
-tags((name = "auth"),(name = "events"),(name = "event-ownership"),(name = "invitations"),(name = "search"))
+tags((name = "auth"),(name = "events"),(name = "event-ownership"),(name = "invitations"),(name = "search"),(name = "users"),(name = "admin"),(name = "reminders"),(name = "scim"))
 )]
 pub struct ApiDoc;
@@ -1,16 +1,27 @@
 pub mod app_errors;
-pub mod config;
 mod doc;
 pub mod modules;
 pub mod routes;
 pub mod utils;
-pub mod validation;
 
-use crate::config::environment::Environment;
-use crate::modules::Modules;
+use crate::modules::compression::compression_layer;
+use crate::modules::error_reporting::report_errors;
+use crate::modules::maintenance::maintenance_guard;
+use crate::modules::metrics::metrics_handler;
+use crate::modules::query_log::{self, count_queries, DB_QUERIES};
+use crate::modules::search_cache::invalidate_search_cache;
+use crate::modules::storage::download_handler;
+use crate::modules::swagger::{swagger_guard, Swagger};
+use crate::modules::trace_context::trace_request;
+use crate::modules::versioning::deprecated_path;
+use crate::modules::{AppState, Modules};
 use axum::extract::State;
+use axum::middleware;
 use axum::response::Redirect;
+use axum::routing::get;
 use axum::{Extension, Router};
+use bimetable_db::config::environment::Environment;
+use bimetable_domain::api::API_V1;
 use http::{StatusCode, Uri};
 use tracing::info;
 use utoipa::OpenApi;
@@ -18,31 +29,160 @@ use utoipa_swagger_ui::SwaggerUi;
 
 const SWAGGER_URI: &str = "/swagger-ui";
 
+/// Builds the app with the feature routers of its settings.
 pub async fn app(modules: Modules) -> Router {
-    let mut router = Router::new();
-    let state = modules.state();
-    let extensions = modules.extensions();
-
-    if state.environment.is_dev() {
-        info!("Enabling Swagger UI");
-        router = router.merge(
-            SwaggerUi::new(SWAGGER_URI).url("/api-doc/openapi.json", doc::ApiDoc::openapi()),
+    AppBuilder::new(modules).build().await
+}
+
+/// Composes the app from its modules, letting deployments toggle feature routers.
+///
+/// Toggles start from the settings: Swagger UI follows `swagger`, example routes are only served in development by default,
+/// admin and realtime routes are served unless disabled.
+pub struct AppBuilder {
+    modules: Modules,
+    swagger: bool,
+    example: bool,
+    admin: bool,
+    realtime: bool,
+}
+
+impl AppBuilder {
+    pub fn new(modules: Modules) -> Self {
+        let swagger = Swagger::new(&modules.app, modules.environment()).is_enabled();
+        let example = modules
+            .app
+            .example_routes
+            .unwrap_or(modules.environment().is_dev());
+        let admin = modules.app.admin_routes;
+        let realtime = modules.app.realtime_routes;
+        Self {
+            modules,
+            swagger,
+            example,
+            admin,
+            realtime,
+        }
+    }
+
+    /// Serves Swagger UI and the OpenAPI document, still guarded by the configured access.
+    pub fn swagger(mut self, enabled: bool) -> Self {
+        self.swagger = enabled;
+        self
+    }
+
+    /// Serves the example routes at `/ex`.
+    pub fn example(mut self, enabled: bool) -> Self {
+        self.example = enabled;
+        self
+    }
+
+    /// Serves the admin routes at `/admin`.
+    pub fn admin(mut self, enabled: bool) -> Self {
+        self.admin = enabled;
+        self
+    }
+
+    /// Serves the presence WebSocket of events.
+    pub fn realtime(mut self, enabled: bool) -> Self {
+        self.realtime = enabled;
+        self
+    }
+
+    pub async fn build(self) -> Router {
+        let mut router = Router::new();
+        let state = self.modules.state();
+        let extensions = self.modules.extensions();
+        let compression = compression_layer(&self.modules.app);
+
+        if self.swagger && state.swagger.is_enabled() {
+            info!("Enabling Swagger UI");
+            let swagger: Router<AppState> = SwaggerUi::new(SWAGGER_URI)
+                .url("/api-doc/openapi.json", doc::ApiDoc::openapi())
+                .into();
+            router = router.merge(
+                swagger.route_layer(middleware::from_fn_with_state(state.clone(), swagger_guard)),
+            );
+        }
+
+        info!(
+            "Spawning main router with:\n - state: {state}\n - extensions: {extensions}\n - feature routers: {}",
+            self.feature_routers()
         );
+
+        let router = router
+            .nest(API_V1, self.api_router())
+            .merge(
+                self.api_router()
+                    .layer(middleware::from_fn(deprecated_path)),
+            )
+            .route("/metrics", get(metrics_handler))
+            .nest("/scim/v2", routes::scim::router())
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                invalidate_search_cache,
+            ))
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                maintenance_guard,
+            ))
+            .layer(middleware::from_fn_with_state(state.clone(), report_errors))
+            .layer(Extension(extensions.jwt))
+            .layer(Extension(extensions.passwords))
+            .layer(Extension(extensions.usernames))
+            .fallback(not_found)
+            .layer(compression)
+            .layer(middleware::from_fn(trace_request));
+        if query_log::is_enabled(&self.modules.app, self.modules.environment()) {
+            info!("Logging SQL statements, their number is sent in {DB_QUERIES}");
+            return router
+                .layer(middleware::from_fn(count_queries))
+                .with_state(state);
+        }
+        router.with_state(state)
     }
 
-    info!("Spawning main router with:\n - state: {state}\n - extensions: {extensions}");
+    /// Routes of the public API, served under [`API_V1`] and, deprecated, without a version.
+    fn api_router(&self) -> Router<AppState> {
+        let mut events = routes::events::router();
+        if self.realtime {
+            events = events.merge(routes::events::presence_router());
+        }
 
-    router
-        .nest("/auth", routes::auth::router())
-        .nest("/ex", routes::example::router())
-        .nest(
-            "/events",
-            routes::events::router().nest("/invitations", routes::invitations::router()),
-        )
-        .nest("/search", routes::search::router())
-        .layer(Extension(extensions.jwt))
-        .fallback(not_found)
-        .with_state(state)
+        let mut router = Router::new()
+            .nest("/auth", routes::auth::router())
+            .nest(
+                "/events",
+                events.nest("/invitations", routes::invitations::router()),
+            )
+            .nest("/search", routes::search::router())
+            .nest("/reminders", routes::reminders::router())
+            .nest("/users", routes::users::router())
+            .route("/storage/*key", get(download_handler));
+        if self.example {
+            router = router.nest("/ex", routes::example::router());
+        }
+        if self.admin {
+            router = router.nest("/admin", routes::admin::router());
+        }
+        router
+    }
+
+    fn feature_routers(&self) -> String {
+        let toggles = [
+            ("swagger", self.swagger),
+            ("example", self.example),
+            ("admin", self.admin),
+            ("realtime", self.realtime),
+        ];
+        let enabled: Vec<&str> = toggles
+            .into_iter()
+            .filter_map(|(name, enabled)| enabled.then_some(name))
+            .collect();
+        if enabled.is_empty() {
+            return "none".to_string();
+        }
+        enabled.join(", ")
+    }
 }
 
 async fn not_found(
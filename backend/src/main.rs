@@ -1,32 +1,73 @@
-use bimetable::app;
-use bimetable::modules::Modules;
+use bimetable_db::config::check::check_config;
+use bimetable_db::config::get_config;
+use bimetable_http::modules::query_log;
+use bimetable_http::modules::Modules;
+use bimetable_http::AppBuilder;
+use clap::Parser;
 use dotenv::dotenv;
 use std::net::SocketAddr;
 use tracing::info;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
+
+/// Server of bimetable, configured by `configuration/settings.toml` or the environment.
+#[derive(Parser)]
+#[command(name = "bimetable")]
+struct Cli {
+    /// Validates the configuration and exits, with a failure status when it has problems
+    #[arg(long)]
+    check_config: bool,
+}
 
 #[tokio::main]
 async fn main() {
     dotenv().ok();
     tracing_subscriber::registry()
-        .with(tracing_subscriber::EnvFilter::new(
-            std::env::var("RUST_LOG").unwrap_or_else(|_| "bimetable=debug".into()),
-        ))
-        .with(tracing_subscriber::fmt::layer())
+        .with(
+            tracing_subscriber::fmt::layer().with_filter(tracing_subscriber::EnvFilter::new(
+                std::env::var("RUST_LOG").unwrap_or_else(|_| "bimetable=debug".into()),
+            )),
+        )
+        .with(query_log::layer())
         .init();
 
+    if Cli::parse().check_config {
+        let settings = get_config().expect("Failed to load settings");
+        let report = check_config(&settings).await;
+        println!("{report}");
+        std::process::exit(if report.is_healthy() { 0 } else { 1 });
+    }
+
     let modules = Modules::load_from_settings().await;
+    modules.error_reporter().report_panics();
+    modules.configure_expansion();
+    let jobs = modules.job_runner().spawn();
+    modules.schedule_jobs().await;
+    let realtime = modules.listen_realtime().await;
 
     info!("Starting server on {} machine", machine_kind());
     info!("Listening on {}", &modules.app.addr);
     axum::Server::bind(&modules.app.addr)
         .serve(
-            app(modules)
+            AppBuilder::new(modules)
+                .build()
                 .await
                 .into_make_service_with_connect_info::<SocketAddr>(),
         )
+        .with_graceful_shutdown(shutdown_signal())
         .await
         .expect("Failed to run axum server");
+
+    jobs.shutdown().await;
+    if let Some(realtime) = realtime {
+        realtime.shutdown().await;
+    }
+}
+
+async fn shutdown_signal() {
+    tokio::signal::ctrl_c()
+        .await
+        .expect("Failed to listen for shutdown signal");
+    info!("Received shutdown signal");
 }
 
 fn machine_kind<'s>() -> &'s str {
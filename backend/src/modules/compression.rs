@@ -0,0 +1,18 @@
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use tower_http::compression::CompressionLayer;
+
+use bimetable_db::config::app::{ApplicationSettings, CompressionAlgorithm};
+
+/// Compresses responses with the configured algorithms the client accepts.
+///
+/// Images are already compressed and small bodies are not worth it, so both are sent as they are.
+pub fn compression_layer(settings: &ApplicationSettings) -> CompressionLayer<impl Predicate> {
+    let predicate = SizeAbove::new(settings.compression_min_bytes)
+        .and(NotForContentType::GRPC)
+        .and(NotForContentType::IMAGES);
+
+    CompressionLayer::new()
+        .gzip(settings.compression.contains(&CompressionAlgorithm::Gzip))
+        .br(settings.compression.contains(&CompressionAlgorithm::Br))
+        .compress_when(predicate)
+}
@@ -0,0 +1,315 @@
+use std::cell::RefCell;
+use std::panic;
+use std::sync::Arc;
+
+use axum::extract::{FromRequestParts, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use http::{HeaderMap, Request, StatusCode};
+use reqwest::{Client, Url};
+use secrecy::{ExposeSecret, Secret};
+use serde_json::{json, Value};
+use time::OffsetDateTime;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::modules::trace_context::TraceContext;
+use crate::modules::AppState;
+use crate::utils::auth::models::Claims;
+use bimetable_db::config::environment::Environment;
+use bimetable_db::config::tokens::JwtSettings;
+
+const SENTRY_VERSION: u8 = 7;
+const CLIENT: &str = concat!("bimetable/", env!("CARGO_PKG_VERSION"));
+
+tokio::task_local! {
+    /// Unexpected error of the request being handled
+    static UNEXPECTED: RefCell<Option<String>>;
+}
+
+/// Logs an unexpected error and keeps it for the report of the request being handled.
+pub fn capture_unexpected(e: &anyhow::Error) {
+    error!("Internal server error: {e:?}");
+    let _ = UNEXPECTED.try_with(|slot| *slot.borrow_mut() = Some(format!("{e:?}")));
+}
+
+/// Store endpoint and key of a Sentry compatible DSN, `https://<key>@<host>/<project>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dsn {
+    pub store_url: String,
+    pub key: String,
+}
+
+impl TryFrom<&str> for Dsn {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let url = Url::parse(value).map_err(|e| format!("Invalid DSN: {e}"))?;
+        let host = url.host_str().ok_or("DSN without a host")?;
+        let key = url.username();
+        if key.is_empty() {
+            return Err("DSN without a public key".to_string());
+        }
+        let path = url.path().trim_end_matches('/');
+        let (prefix, project) = path.rsplit_once('/').ok_or("DSN without a project")?;
+        if project.is_empty() {
+            return Err("DSN without a project".to_string());
+        }
+        let port = url
+            .port()
+            .map(|port| format!(":{port}"))
+            .unwrap_or_default();
+
+        Ok(Self {
+            store_url: format!(
+                "{}://{host}{port}{prefix}/api/{project}/store/",
+                url.scheme()
+            ),
+            key: key.to_string(),
+        })
+    }
+}
+
+/// Error sent to the reporting service.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorReport {
+    pub message: String,
+    /// `fatal` for panics, `error` otherwise
+    pub level: &'static str,
+    pub method: Option<String>,
+    pub path: Option<String>,
+    pub user_id: Option<Uuid>,
+    pub trace_id: Option<String>,
+}
+
+impl ErrorReport {
+    pub fn panic(message: String) -> Self {
+        Self {
+            message,
+            level: "fatal",
+            method: None,
+            path: None,
+            user_id: None,
+            trace_id: None,
+        }
+    }
+}
+
+/// Sends unexpected errors and panics to a Sentry compatible service, disabled without a DSN.
+#[derive(Clone, Default)]
+pub struct ErrorReporter(Option<Arc<Sender>>);
+
+struct Sender {
+    client: Client,
+    dsn: Dsn,
+    environment: String,
+}
+
+impl ErrorReporter {
+    pub fn new(dsn: Option<&Secret<String>>, environment: &Environment) -> Self {
+        let Some(dsn) = dsn else {
+            return Self(None);
+        };
+        match Dsn::try_from(dsn.expose_secret().as_str()) {
+            Ok(dsn) => Self(Some(Arc::new(Sender {
+                client: Client::new(),
+                dsn,
+                environment: environment.to_string(),
+            }))),
+            Err(e) => {
+                warn!("Error reporting is disabled: {e}");
+                Self(None)
+            }
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Sends the report in the background, failures are only logged.
+    pub fn report(&self, report: ErrorReport) {
+        let Some(sender) = self.0.clone() else {
+            return;
+        };
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            warn!("Dropped error report outside of the runtime");
+            return;
+        };
+        runtime.spawn(async move {
+            let auth = format!(
+                "Sentry sentry_version={SENTRY_VERSION}, sentry_client={CLIENT}, sentry_key={}",
+                sender.dsn.key
+            );
+            let res = sender
+                .client
+                .post(&sender.dsn.store_url)
+                .header("X-Sentry-Auth", auth)
+                .json(&event(&report, &sender.environment))
+                .send()
+                .await
+                .and_then(|res| res.error_for_status());
+            if let Err(e) = res {
+                warn!("Failed to send error report: {e}");
+            }
+        });
+    }
+
+    /// Reports panics of every thread, after the default panic output.
+    pub fn report_panics(&self) {
+        if !self.is_enabled() {
+            return;
+        }
+        let reporter = self.clone();
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            previous(info);
+            let payload = info
+                .payload()
+                .downcast_ref::<&str>()
+                .map(ToString::to_string)
+                .or_else(|| info.payload().downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "Box<dyn Any>".to_string());
+            let message = match info.location() {
+                Some(location) => format!("Panicked at {location}: {payload}"),
+                None => format!("Panicked: {payload}"),
+            };
+            reporter.report(ErrorReport::panic(message));
+        }));
+    }
+}
+
+fn event(report: &ErrorReport, environment: &str) -> Value {
+    let mut event = json!({
+        "event_id": Uuid::new_v4().simple().to_string(),
+        "timestamp": OffsetDateTime::now_utc().unix_timestamp(),
+        "platform": "other",
+        "logger": "bimetable",
+        "level": report.level,
+        "release": CLIENT,
+        "environment": environment,
+        "message": { "formatted": report.message },
+    });
+    if let (Some(method), Some(path)) = (&report.method, &report.path) {
+        event["transaction"] = json!(format!("{method} {path}"));
+        event["request"] = json!({ "method": method, "url": path });
+    }
+    if let Some(user_id) = report.user_id {
+        event["user"] = json!({ "id": user_id });
+    }
+    if let Some(trace_id) = &report.trace_id {
+        event["tags"] = json!({ "trace_id": trace_id });
+    }
+    event
+}
+
+/// Reports unexpected errors and other internal server errors of requests, with the route and the user.
+pub async fn report_errors<B>(
+    State(state): State<AppState>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    if !state.error_reporter.is_enabled() {
+        return next.run(req).await;
+    }
+
+    let method = req.method().to_string();
+    let path = req.uri().path().to_string();
+    let headers = req.headers().clone();
+    let jwt = req.extensions().get::<JwtSettings>().cloned();
+    let trace_id = req
+        .extensions()
+        .get::<TraceContext>()
+        .map(|context| context.trace_id.clone());
+
+    let (res, unexpected) = UNEXPECTED
+        .scope(RefCell::new(None), async {
+            let res = next.run(req).await;
+            let unexpected = UNEXPECTED.with(|slot| slot.borrow_mut().take());
+            (res, unexpected)
+        })
+        .await;
+
+    let message = match unexpected {
+        Some(message) => message,
+        None if res.status() == StatusCode::INTERNAL_SERVER_ERROR => {
+            "Internal server error".to_string()
+        }
+        None => return res,
+    };
+    let user_id = match jwt {
+        Some(jwt) => user_id(&state, headers, jwt).await,
+        None => None,
+    };
+
+    state.error_reporter.report(ErrorReport {
+        message,
+        level: "error",
+        method: Some(method),
+        path: Some(path),
+        user_id,
+        trace_id,
+    });
+    res
+}
+
+/// Signed in user of a request, read again from its headers.
+async fn user_id(state: &AppState, headers: HeaderMap, jwt: JwtSettings) -> Option<Uuid> {
+    let mut req = Request::new(());
+    *req.headers_mut() = headers;
+    req.extensions_mut().insert(jwt);
+    let (mut parts, _) = req.into_parts();
+
+    Claims::from_request_parts(&mut parts, state)
+        .await
+        .ok()
+        .map(|claims| claims.user_id)
+}
+
+#[cfg(test)]
+mod error_reporting_tests {
+    use super::*;
+
+    #[test]
+    fn reads_store_url_of_dsn() {
+        let dsn = Dsn::try_from("https://public@o123.ingest.sentry.io/456").unwrap();
+        assert_eq!(
+            dsn.store_url,
+            "https://o123.ingest.sentry.io/api/456/store/"
+        );
+        assert_eq!(dsn.key, "public");
+
+        let dsn = Dsn::try_from("http://public@localhost:9000/sentry/7").unwrap();
+        assert_eq!(dsn.store_url, "http://localhost:9000/sentry/api/7/store/");
+    }
+
+    #[test]
+    fn rejects_incomplete_dsn() {
+        assert!(Dsn::try_from("https://o123.ingest.sentry.io/456").is_err());
+        assert!(Dsn::try_from("https://public@o123.ingest.sentry.io/").is_err());
+        assert!(Dsn::try_from("not a dsn").is_err());
+    }
+
+    #[test]
+    fn event_carries_request_context() {
+        let user_id = Uuid::new_v4();
+        let report = ErrorReport {
+            message: "Failed".to_string(),
+            level: "error",
+            method: Some("POST".to_string()),
+            path: Some("/api/v1/events".to_string()),
+            user_id: Some(user_id),
+            trace_id: Some("4bf92f3577b34da6a3ce929d0e0e4736".to_string()),
+        };
+        let event = event(&report, "production");
+
+        assert_eq!(event["message"]["formatted"], "Failed");
+        assert_eq!(event["transaction"], "POST /api/v1/events");
+        assert_eq!(event["user"]["id"], user_id.to_string());
+        assert_eq!(
+            event["tags"]["trace_id"],
+            "4bf92f3577b34da6a3ce929d0e0e4736"
+        );
+        assert_eq!(event["environment"], "production");
+    }
+}
@@ -0,0 +1,113 @@
+use axum::extract::{FromRequestParts, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use http::{Method, Request, StatusCode};
+use serde_json::json;
+use sqlx::{query, PgPool};
+use tracing::{debug, error, info};
+
+use crate::modules::versioning::unversioned;
+use crate::modules::AppState;
+use crate::utils::auth::models::Claims;
+use bimetable_db::utils::users::is_admin;
+
+/// Routes which stay writable for everyone, so that admins can sign in during maintenance.
+const EXEMPT_PATHS: [&str; 5] = [
+    "/auth/login",
+    "/auth/logout",
+    "/auth/refresh",
+    "/auth/token",
+    "/auth/token/refresh",
+];
+
+/// SCIM provisioning is authorized with the admin token of the identity provider, so it isn't paused.
+const EXEMPT_PREFIX: &str = "/scim/v2/";
+
+/// Switch of the maintenance mode, stored in the database so that every instance follows it.
+#[derive(Clone)]
+pub struct Maintenance {
+    pool: PgPool,
+}
+
+impl Maintenance {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn is_enabled(&self) -> Result<bool, sqlx::Error> {
+        let maintenance = query!(
+            r#"
+                SELECT is_enabled FROM maintenance
+            "#
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(maintenance.is_some_and(|maintenance| maintenance.is_enabled))
+    }
+
+    pub async fn set(&self, is_enabled: bool) -> Result<(), sqlx::Error> {
+        query!(
+            r#"
+                INSERT INTO maintenance (is_enabled)
+                VALUES ($1)
+                ON CONFLICT (id) DO UPDATE
+                SET is_enabled = excluded.is_enabled, updated_at = now()
+            "#,
+            is_enabled,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "Maintenance mode {}",
+            if is_enabled { "enabled" } else { "disabled" }
+        );
+        Ok(())
+    }
+}
+
+/// Rejects writes of non-admin users while the maintenance mode is enabled.
+///
+/// The mode is read from the database on every write, so switching it takes effect on all instances at once.
+pub async fn maintenance_guard<B>(
+    State(state): State<AppState>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let path = unversioned(req.uri().path());
+    if is_read(req.method()) || EXEMPT_PATHS.contains(&path) || path.starts_with(EXEMPT_PREFIX) {
+        return next.run(req).await;
+    }
+    match state.maintenance.is_enabled().await {
+        Ok(true) => (),
+        Ok(false) => return next.run(req).await,
+        Err(e) => {
+            error!("Failed to check the maintenance mode: {e:?}");
+            return next.run(req).await;
+        }
+    }
+
+    let (mut parts, body) = req.into_parts();
+    if let Ok(claims) = Claims::from_request_parts(&mut parts, &state).await {
+        match is_admin(&state.pool, claims.user_id).await {
+            Ok(true) => return next.run(Request::from_parts(parts, body)).await,
+            Ok(false) => (),
+            Err(e) => error!("Failed to check admin privileges: {e:?}"),
+        }
+    }
+
+    debug!("Rejected {} {} during maintenance", parts.method, parts.uri);
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({
+            "error_info": "Bimetable is under maintenance, changes are disabled for a moment"
+        })),
+    )
+        .into_response()
+}
+
+pub(crate) fn is_read(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
@@ -0,0 +1,142 @@
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use axum::extract::State;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use http::header::{AUTHORIZATION, CONTENT_TYPE};
+use http::{HeaderMap, StatusCode};
+use metrics::gauge;
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use secrecy::{ExposeSecret, Secret};
+use serde_json::json;
+use sqlx::PgPool;
+use tracing::{debug, error};
+
+use crate::utils::secrets::is_secret;
+
+/// Time spent expanding the entries of a single recurring event, labeled by its rule `kind`
+pub const RECURRENCE_EXPANSION_SECONDS: &str = "bimetable_recurrence_expansion_seconds";
+const POOL_SIZE: &str = "bimetable_db_pool_size";
+const POOL_IDLE: &str = "bimetable_db_pool_idle";
+const POOL_WAIT_SECONDS: &str = "bimetable_db_pool_wait_seconds";
+
+const EXPANSION_BUCKETS: [f64; 10] = [
+    0.00001, 0.00005, 0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5,
+];
+
+/// The recorder is process wide, every app instance renders the same metrics.
+static RECORDER: OnceLock<PrometheusHandle> = OnceLock::new();
+
+fn recorder() -> &'static PrometheusHandle {
+    RECORDER.get_or_init(|| {
+        PrometheusBuilder::new()
+            .set_buckets_for_metric(
+                Matcher::Full(RECURRENCE_EXPANSION_SECONDS.to_string()),
+                &EXPANSION_BUCKETS,
+            )
+            .expect("Invalid histogram buckets")
+            .install_recorder()
+            .expect("Failed to install the metrics recorder")
+    })
+}
+
+/// Prometheus exposition of the metrics, readable with a bearer token.
+///
+/// Without a token the endpoint is disabled.
+#[derive(Clone)]
+pub struct Metrics {
+    handle: &'static PrometheusHandle,
+    token: Option<Secret<String>>,
+}
+
+impl Metrics {
+    pub fn new(token: Option<Secret<String>>) -> Self {
+        Self {
+            handle: recorder(),
+            token,
+        }
+    }
+
+    fn is_authorized(&self, headers: &HeaderMap) -> bool {
+        let Some(token) = &self.token else {
+            return false;
+        };
+        headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|provided| is_secret(token.expose_secret(), provided))
+    }
+
+    /// Samples the database pool and renders all metrics in the text format.
+    pub async fn render(&self, pool: &PgPool) -> String {
+        gauge!(POOL_SIZE).set(pool.size() as f64);
+        gauge!(POOL_IDLE).set(pool.num_idle() as f64);
+
+        let started = Instant::now();
+        match pool.acquire().await {
+            Ok(_) => gauge!(POOL_WAIT_SECONDS).set(started.elapsed().as_secs_f64()),
+            Err(e) => error!("Failed to sample the database pool: {e}"),
+        }
+
+        self.handle.run_upkeep();
+        self.handle.render()
+    }
+}
+
+pub async fn metrics_handler(
+    State(metrics): State<Metrics>,
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+) -> Response {
+    if metrics.token.is_none() {
+        return (StatusCode::NOT_FOUND, "404 Not Found").into_response();
+    }
+    if !metrics.is_authorized(&headers) {
+        debug!("Rejected unauthorized metrics scrape");
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({ "error_info": "Invalid metrics token" })),
+        )
+            .into_response();
+    }
+
+    (
+        [(CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics.render(&pool).await,
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod metrics_tests {
+    use http::HeaderValue;
+
+    use super::*;
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {token}")).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn requires_matching_bearer_token() {
+        let metrics = Metrics::new(Some(Secret::from("scrape".to_string())));
+
+        assert!(metrics.is_authorized(&bearer("scrape")));
+        assert!(!metrics.is_authorized(&bearer("other")));
+        assert!(!metrics.is_authorized(&HeaderMap::new()));
+    }
+
+    #[test]
+    fn disabled_without_token() {
+        let metrics = Metrics::new(None);
+
+        assert!(!metrics.is_authorized(&bearer("")));
+    }
+}
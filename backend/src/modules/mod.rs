@@ -1,21 +1,66 @@
-use self::database::get_postgres_pool;
-use crate::config::app::ApplicationSettings;
-use crate::config::environment::Environment;
-use crate::config::get_config;
-use crate::config::tokens::JwtSettings;
+use self::error_reporting::ErrorReporter;
+use self::maintenance::Maintenance;
+use self::metrics::Metrics;
+use self::presence::Presence;
+use self::realtime::{Bridge, BridgeHandle, LocalBridge, PgBridge, Realtime, RealtimeDispatcher};
+use self::search_cache::SearchCache;
+use self::swagger::Swagger;
 use axum::extract::FromRef;
+use bimetable_db::config::app::{
+    ApplicationSettings, InvitationCap, RealtimeBridgeKind, ScimToken, WebsiteOrigin,
+};
+use bimetable_db::config::environment::Environment;
+use bimetable_db::config::get_config;
+use bimetable_db::config::passwords::PasswordSettings;
+use bimetable_db::config::retention::RetentionSettings;
+use bimetable_db::config::signups::SignupSettings;
+use bimetable_db::config::tokens::JwtSettings;
+use bimetable_db::config::usernames::UsernameSettings;
+use bimetable_db::modules::clock::{Clock, SharedClock, SystemClock};
+use bimetable_db::modules::database::{check_schema, get_postgres_pool, run_migrations};
+use bimetable_db::modules::feed_cache::FeedCache;
+use bimetable_db::modules::jobs::{JobRunner, JobSettings};
+use bimetable_db::modules::outbox::{LogDispatcher, OutboxHandler};
+use bimetable_db::modules::storage::Blobs;
+use bimetable_db::utils::admin::purge::{schedule_purge, PurgeHandler};
+use bimetable_db::utils::admin::stats::{schedule_stats, StatsHandler};
+use bimetable_db::utils::events::digest::{schedule_digests, DigestHandler};
+use bimetable_db::utils::reminders::ReminderHandler;
+use bimetable_db::utils::users::archive::ArchiveHandler;
+use bimetable_domain::limits::{OverrideShiftLimit, RepetitionLimit};
 use core::fmt::Display;
 use sqlx::PgPool;
 use std::net::SocketAddr;
-use tracing::{error, info};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
 
-pub mod database;
+pub mod compression;
+pub mod error_reporting;
+pub mod maintenance;
+pub mod metrics;
+pub mod negotiation;
+pub mod presence;
+pub mod query_log;
+pub mod realtime;
+pub mod search_cache;
+pub mod storage;
+pub mod swagger;
+pub mod trace_context;
+pub mod versioning;
 
 pub struct Modules {
     pub app: ApplicationSettings,
+    pub retention: RetentionSettings,
+    pub signups: SignupSettings,
     pool: PgPool,
     jwt: JwtSettings,
+    passwords: PasswordSettings,
+    usernames: UsernameSettings,
     environment: Environment,
+    maintenance: Maintenance,
+    realtime: Realtime,
+    clock: SharedClock,
 }
 
 impl Modules {
@@ -25,12 +70,41 @@ impl Modules {
             .unwrap();
         info!("Settings loaded");
         info!("Loading modules");
-        let pool = get_postgres_pool(settings.postgres).await;
+        let is_migrating = settings.postgres.is_migrating;
+        let sql_logging = query_log::is_enabled(&settings.app, &settings.environment);
+        if settings.app.sql_logging && !sql_logging {
+            warn!("SQL logging is only allowed in development, ignoring it");
+        }
+        let pool = get_postgres_pool(settings.postgres, sql_logging).await;
+        if is_migrating {
+            run_migrations(&pool).await.expect("Auto migration failed");
+        }
+        let report = check_schema(&pool)
+            .await
+            .expect("Failed to check database schema");
+        if !report.is_healthy() {
+            error!("{report}");
+            panic!("{report}");
+        }
+        let maintenance = Maintenance::new(pool.clone());
+        if settings.app.maintenance {
+            maintenance
+                .set(true)
+                .await
+                .expect("Failed to enable the maintenance mode");
+        }
         info!("Modules loaded");
         Self {
             pool,
+            maintenance,
+            realtime: Realtime::default(),
+            clock: Arc::new(SystemClock),
             app: settings.app,
+            retention: settings.retention,
+            signups: settings.signups,
             jwt: settings.jwt,
+            passwords: settings.passwords,
+            usernames: settings.usernames,
             environment: settings.environment,
         }
     }
@@ -43,11 +117,19 @@ impl Modules {
         refresh: &str,
         environment: Environment,
     ) -> Self {
+        let maintenance = Maintenance::new(pool.clone());
         Self {
             pool,
             app: ApplicationSettings::new(addr, origin),
+            retention: RetentionSettings::default(),
+            signups: SignupSettings::default(),
             jwt: JwtSettings::new(access, refresh),
+            passwords: PasswordSettings::default(),
+            usernames: UsernameSettings::default(),
             environment,
+            maintenance,
+            realtime: Realtime::default(),
+            clock: Arc::new(SystemClock),
         }
     }
 
@@ -62,12 +144,104 @@ impl Modules {
     pub fn environment(&self) -> &Environment {
         &self.environment
     }
+
+    /// Replaces the wall-clock time, e.g. with a [`TestClock`](bimetable_db::modules::clock::TestClock).
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.clock = Arc::new(clock);
+    }
+
+    pub fn error_reporter(&self) -> ErrorReporter {
+        ErrorReporter::new(self.app.error_reporting_dsn.as_ref(), &self.environment)
+    }
+
+    /// Sizes the thread pool expanding recurring events, it can only be done once per process.
+    pub fn configure_expansion(&self) {
+        if let Err(e) = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.app.expansion_threads.into())
+            .thread_name(|i| format!("expansion-{i}"))
+            .build_global()
+        {
+            warn!("Failed to configure expansion threads: {e}");
+        }
+    }
+
+    /// Publishes realtime messages to this instance or every instance, as configured.
+    fn realtime_bridge(&self) -> Arc<dyn Bridge> {
+        match self.app.realtime_bridge {
+            RealtimeBridgeKind::Local => Arc::new(LocalBridge::new(self.realtime.clone())),
+            RealtimeBridgeKind::Postgres => Arc::new(PgBridge::new(self.pool.clone())),
+        }
+    }
+
+    pub fn job_runner(&self) -> JobRunner {
+        let realtime = RealtimeDispatcher::shared(self.realtime_bridge());
+        JobRunner::new(self.pool.clone(), JobSettings::default())
+            .with_clock(self.clock.clone())
+            .register(OutboxHandler::default().with(LogDispatcher).with(realtime))
+            .register(ReminderHandler::new(
+                self.clock.clone(),
+                self.app.webhook_allowed_hosts.clone(),
+            ))
+            .register(StatsHandler)
+            .register(PurgeHandler::new(
+                self.retention.clone(),
+                self.clock.clone(),
+            ))
+            .register(
+                ArchiveHandler::new(Blobs::from_settings(&self.app))
+                    .with_repetition_limit(self.app.repetition_limit()),
+            )
+            .register(DigestHandler::new(self.clock.clone()))
+    }
+
+    /// Enqueues the recurring jobs which are not waiting in the queue yet.
+    pub async fn schedule_jobs(&self) {
+        schedule_stats(&self.pool)
+            .await
+            .expect("Failed to schedule statistics");
+        schedule_purge(&self.pool)
+            .await
+            .expect("Failed to schedule purge");
+        schedule_digests(&self.pool)
+            .await
+            .expect("Failed to schedule event digests");
+    }
+
+    /// Starts receiving changes handled by other instances, if they are bridged.
+    pub async fn listen_realtime(&self) -> Option<BridgeHandle> {
+        match self.app.realtime_bridge {
+            RealtimeBridgeKind::Local => None,
+            RealtimeBridgeKind::Postgres => Some(
+                PgBridge::listen(&self.pool, self.realtime.clone())
+                    .await
+                    .expect("Failed to listen for realtime messages"),
+            ),
+        }
+    }
 }
 
 #[derive(Clone, FromRef)]
 pub struct AppState {
     pub environment: Environment,
     pub pool: PgPool,
+    pub maintenance: Maintenance,
+    pub realtime: Realtime,
+    pub presence: Presence,
+    pub override_shift_limit: OverrideShiftLimit,
+    #[from_ref(skip)]
+    pub repetition_limit: RepetitionLimit,
+    pub invitation_cap: InvitationCap,
+    pub website_origin: WebsiteOrigin,
+    pub metrics: Metrics,
+    pub swagger: Swagger,
+    pub search_cache: SearchCache,
+    pub feed_cache: FeedCache,
+    pub retention: RetentionSettings,
+    pub signups: SignupSettings,
+    pub scim_token: ScimToken,
+    pub storage: Blobs,
+    pub error_reporter: ErrorReporter,
+    pub clock: SharedClock,
 }
 
 impl AppState {
@@ -75,30 +249,64 @@ impl AppState {
         Self {
             environment: modules.environment.clone(),
             pool: modules.pool.clone(),
+            maintenance: modules.maintenance.clone(),
+            realtime: modules.realtime.clone(),
+            presence: Presence::new(modules.app.presence_ttl(), modules.realtime_bridge()),
+            override_shift_limit: modules.app.override_shift_limit(),
+            repetition_limit: modules.app.repetition_limit(),
+            invitation_cap: modules.app.invitation_cap(),
+            website_origin: modules.app.website_origin(),
+            metrics: Metrics::new(modules.app.metrics_token.clone()),
+            swagger: Swagger::new(&modules.app, &modules.environment),
+            search_cache: SearchCache::new(Duration::from_secs(modules.app.search_cache_seconds)),
+            feed_cache: FeedCache::default(),
+            retention: modules.retention.clone(),
+            signups: modules.signups.clone(),
+            scim_token: modules.app.scim_token(),
+            storage: Blobs::from_settings(&modules.app),
+            error_reporter: modules.error_reporter(),
+            clock: modules.clock.clone(),
         }
     }
 }
 
+// Copied rather than cloned like the derived fields
+impl FromRef<AppState> for RepetitionLimit {
+    fn from_ref(state: &AppState) -> Self {
+        state.repetition_limit
+    }
+}
+
 impl Display for AppState {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        write!(f, "postgres pool")
+        write!(
+            f,
+            "postgres pool, maintenance switch, realtime fan-out, event presence, website origin, metrics, swagger access, search cache, feed cache, retention policy, signup policy, scim token, blob storage, error reporter, clock"
+        )
     }
 }
 
 pub struct AppExtensions {
     pub jwt: JwtSettings,
+    pub passwords: PasswordSettings,
+    pub usernames: UsernameSettings,
 }
 
 impl AppExtensions {
     fn new(modules: &Modules) -> Self {
         Self {
             jwt: modules.jwt.clone(),
+            passwords: modules.passwords.clone(),
+            usernames: modules.usernames.clone(),
         }
     }
 }
 
 impl Display for AppExtensions {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        write!(f, "token secrets")
+        write!(
+            f,
+            "token secrets, password hashing parameters, username policy"
+        )
     }
 }
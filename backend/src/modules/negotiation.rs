@@ -0,0 +1,152 @@
+use std::convert::Infallible;
+
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use http::header::{ACCEPT, CONTENT_TYPE, VARY};
+use http::request::Parts;
+use http::{HeaderMap, HeaderValue, StatusCode};
+use serde::Serialize;
+use serde_json::json;
+use tracing::error;
+
+pub const MSGPACK: &str = "application/msgpack";
+
+/// Media types of MessagePack bodies, including the unregistered one used by older clients.
+const MSGPACK_TYPES: [&str; 2] = [MSGPACK, "application/x-msgpack"];
+
+/// Format of a response body, picked from the `Accept` header of the request.
+///
+/// JSON is served unless MessagePack is preferred, so clients without `Accept` keep getting JSON.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Json,
+    MessagePack,
+}
+
+impl Format {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let accepted = headers
+            .get_all(ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(media_range);
+
+        let mut best = (Self::Json, 0.0);
+        for (media_type, quality) in accepted {
+            let format = if MSGPACK_TYPES.contains(&media_type.as_str()) {
+                Self::MessagePack
+            } else if matches!(
+                media_type.as_str(),
+                "application/json" | "application/*" | "*/*"
+            ) {
+                Self::Json
+            } else {
+                continue;
+            };
+            if quality > best.1 {
+                best = (format, quality);
+            }
+        }
+        best.0
+    }
+
+    pub fn respond<T: Serialize>(self, value: T) -> Negotiated<T> {
+        Negotiated {
+            format: self,
+            value,
+        }
+    }
+}
+
+/// Lowercase media type and quality of an `Accept` entry.
+fn media_range(entry: &str) -> Option<(String, f32)> {
+    let mut params = entry.split(';');
+    let media_type = params.next()?.trim().to_lowercase();
+    if media_type.is_empty() {
+        return None;
+    }
+    let quality = params
+        .filter_map(|param| param.trim().strip_prefix("q="))
+        .find_map(|q| q.trim().parse::<f32>().ok())
+        .unwrap_or(1.0);
+    Some((media_type, quality))
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Format
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_headers(&parts.headers))
+    }
+}
+
+/// Body serialized in the format negotiated with the client.
+///
+/// MessagePack bodies keep the field names, so they mirror the JSON ones.
+pub struct Negotiated<T> {
+    format: Format,
+    value: T,
+}
+
+impl<T: Serialize> IntoResponse for Negotiated<T> {
+    fn into_response(self) -> Response {
+        let mut res = match self.format {
+            Format::Json => Json(self.value).into_response(),
+            Format::MessagePack => match rmp_serde::to_vec_named(&self.value) {
+                Ok(body) => ([(CONTENT_TYPE, MSGPACK)], body).into_response(),
+                Err(e) => {
+                    error!("Failed to serialize MessagePack response: {e:?}");
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(json!({ "error_info": "Unexpected server error" })),
+                    )
+                        .into_response()
+                }
+            },
+        };
+        res.headers_mut()
+            .insert(VARY, HeaderValue::from_static("accept"));
+        res
+    }
+}
+
+#[cfg(test)]
+mod negotiation_tests {
+    use super::*;
+
+    fn accepting(value: &str) -> Format {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, HeaderValue::from_str(value).unwrap());
+        Format::from_headers(&headers)
+    }
+
+    #[test]
+    fn defaults_to_json() {
+        assert_eq!(Format::from_headers(&HeaderMap::new()), Format::Json);
+        assert_eq!(accepting("*/*"), Format::Json);
+        assert_eq!(accepting("text/html"), Format::Json);
+    }
+
+    #[test]
+    fn picks_preferred_format() {
+        assert_eq!(accepting("application/msgpack"), Format::MessagePack);
+        assert_eq!(accepting("application/x-msgpack"), Format::MessagePack);
+        assert_eq!(
+            accepting("application/json, application/msgpack"),
+            Format::Json
+        );
+        assert_eq!(
+            accepting("application/json;q=0.5, application/msgpack"),
+            Format::MessagePack
+        );
+        assert_eq!(accepting("application/msgpack;q=0"), Format::Json);
+    }
+}
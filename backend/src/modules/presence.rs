@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use axum::extract::ws::{Message, WebSocket};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use time::serde::iso8601;
+use time::{Duration, OffsetDateTime};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+use super::realtime::{Bridge, RealtimeMessage};
+
+/// Realtime topic of claimed and released presence.
+pub const PRESENCE_TOPIC: &str = "presenceChanged";
+
+/// How often connections look for expired claims.
+const SWEEP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// User editing an event, until the claim expires.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Editor {
+    pub user_id: Uuid,
+    pub username: String,
+    #[serde(with = "iso8601")]
+    pub expires_at: OffsetDateTime,
+}
+
+/// Claim or release of an editor, shared between instances through the realtime bridge.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PresenceChange {
+    pub user_id: Uuid,
+    pub username: String,
+    /// Expiry of the claim, none when released
+    #[serde(with = "iso8601::option")]
+    pub expires_at: Option<OffsetDateTime>,
+}
+
+/// Message of a client connected to the presence of an event.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum ClientMessage {
+    /// Claims or refreshes the presence of the client's user
+    Editing,
+    /// Releases the claim before it expires
+    Idle,
+}
+
+/// Editors of events claimed by connected clients, forgotten when not refreshed within the TTL.
+#[derive(Clone)]
+pub struct Presence {
+    ttl: Duration,
+    bridge: Arc<dyn Bridge>,
+    claims: Arc<Mutex<HashMap<Uuid, HashMap<Uuid, Editor>>>>,
+}
+
+impl Presence {
+    pub fn new(ttl: Duration, bridge: Arc<dyn Bridge>) -> Self {
+        Self {
+            ttl,
+            bridge,
+            claims: Arc::default(),
+        }
+    }
+
+    /// Unexpired editors of the event, ordered by username.
+    pub fn editors(&self, event_id: Uuid) -> Vec<Editor> {
+        self.editors_at(event_id, OffsetDateTime::now_utc())
+    }
+
+    fn editors_at(&self, event_id: Uuid, now: OffsetDateTime) -> Vec<Editor> {
+        let mut claims = self.claims.lock().expect("Presence lock poisoned");
+        let Some(editors) = claims.get_mut(&event_id) else {
+            return Vec::new();
+        };
+        editors.retain(|_, editor| editor.expires_at > now);
+        let mut editors: Vec<Editor> = editors.values().cloned().collect();
+        if editors.is_empty() {
+            claims.remove(&event_id);
+        }
+        editors.sort_by(|a, b| a.username.cmp(&b.username));
+        editors
+    }
+
+    /// Applies a claim or release, whichever instance it came from.
+    pub fn apply(&self, event_id: Uuid, change: PresenceChange) {
+        let mut claims = self.claims.lock().expect("Presence lock poisoned");
+        match change.expires_at {
+            Some(expires_at) => {
+                claims.entry(event_id).or_default().insert(
+                    change.user_id,
+                    Editor {
+                        user_id: change.user_id,
+                        username: change.username,
+                        expires_at,
+                    },
+                );
+            }
+            None => {
+                if let Some(editors) = claims.get_mut(&event_id) {
+                    editors.remove(&change.user_id);
+                }
+            }
+        }
+    }
+
+    /// Applies presence messages, ignoring the other topics.
+    pub fn receive(&self, message: &RealtimeMessage) {
+        if message.topic != PRESENCE_TOPIC {
+            return;
+        }
+        match serde_json::from_value::<PresenceChange>(message.payload.clone()) {
+            Ok(change) => self.apply(message.aggregate_id, change),
+            Err(e) => warn!("Skipping malformed presence message: {e}"),
+        }
+    }
+
+    /// Marks the user as editing the event for the TTL.
+    pub async fn claim(&self, event_id: Uuid, user_id: Uuid, username: &str) -> anyhow::Result<()> {
+        self.publish(
+            event_id,
+            PresenceChange {
+                user_id,
+                username: username.to_string(),
+                expires_at: Some(OffsetDateTime::now_utc() + self.ttl),
+            },
+        )
+        .await
+    }
+
+    pub async fn release(
+        &self,
+        event_id: Uuid,
+        user_id: Uuid,
+        username: &str,
+    ) -> anyhow::Result<()> {
+        self.publish(
+            event_id,
+            PresenceChange {
+                user_id,
+                username: username.to_string(),
+                expires_at: None,
+            },
+        )
+        .await
+    }
+
+    async fn publish(&self, event_id: Uuid, change: PresenceChange) -> anyhow::Result<()> {
+        self.apply(event_id, change.clone());
+        self.bridge
+            .publish(&RealtimeMessage {
+                topic: PRESENCE_TOPIC.to_string(),
+                aggregate_id: event_id,
+                payload: serde_json::to_value(change)?,
+            })
+            .await
+    }
+
+    /// Serves a client watching the editors of an event, until it disconnects.
+    ///
+    /// Clients send `{"type": "editing"}` at least once per TTL while editing and `{"type": "idle"}` when done,
+    /// and receive `{"type": "editors", "editors": [...]}` on every change of the editors.
+    /// Claims of a disconnected client are released.
+    pub async fn serve(
+        self,
+        mut socket: WebSocket,
+        mut messages: Receiver<RealtimeMessage>,
+        event_id: Uuid,
+        user_id: Uuid,
+        username: String,
+        can_edit: bool,
+    ) {
+        let mut sweep = tokio::time::interval(SWEEP_INTERVAL);
+        let mut sent = None;
+        let mut is_claimed = false;
+
+        loop {
+            let editors = self.editors(event_id);
+            if sent.as_ref() != Some(&editors) {
+                let message = json!({ "type": "editors", "editors": editors });
+                if socket
+                    .send(Message::Text(message.to_string()))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+                sent = Some(editors);
+            }
+
+            tokio::select! {
+                message = socket.recv() => match message {
+                    Some(Ok(Message::Text(text))) => {
+                        let result = match serde_json::from_str::<ClientMessage>(&text) {
+                            Ok(ClientMessage::Editing) if can_edit => {
+                                is_claimed = true;
+                                self.claim(event_id, user_id, &username).await
+                            }
+                            Ok(ClientMessage::Editing) => {
+                                reject(&mut socket, "Missing editing privileges").await;
+                                Ok(())
+                            }
+                            Ok(ClientMessage::Idle) if is_claimed => {
+                                is_claimed = false;
+                                self.release(event_id, user_id, &username).await
+                            }
+                            Ok(ClientMessage::Idle) => Ok(()),
+                            Err(e) => {
+                                debug!("Unknown presence message: {e}");
+                                reject(&mut socket, "Unknown presence message").await;
+                                Ok(())
+                            }
+                        };
+                        if let Err(e) = result {
+                            error!("Failed to publish presence: {e:?}");
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => (),
+                },
+                message = messages.recv() => match message {
+                    Ok(message) => self.receive(&message),
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Presence connection skipped {skipped} realtime messages");
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = sweep.tick() => (),
+            }
+        }
+
+        if is_claimed {
+            if let Err(e) = self.release(event_id, user_id, &username).await {
+                error!("Failed to release presence: {e:?}");
+            }
+        }
+    }
+}
+
+async fn reject(socket: &mut WebSocket, reason: &str) {
+    let message = json!({ "type": "error", "error_info": reason });
+    let _ = socket.send(Message::Text(message.to_string())).await;
+}
+
+#[cfg(test)]
+mod presence_tests {
+    use super::*;
+    use crate::modules::realtime::{LocalBridge, Realtime};
+
+    fn presence() -> Presence {
+        Presence::new(
+            Duration::seconds(30),
+            Arc::new(LocalBridge::new(Realtime::default())),
+        )
+    }
+
+    fn claim(user_id: Uuid, username: &str, expires_at: OffsetDateTime) -> PresenceChange {
+        PresenceChange {
+            user_id,
+            username: username.to_string(),
+            expires_at: Some(expires_at),
+        }
+    }
+
+    #[test]
+    fn expired_claims_are_forgotten() {
+        let presence = presence();
+        let event_id = Uuid::new_v4();
+        let now = OffsetDateTime::now_utc();
+        presence.apply(
+            event_id,
+            claim(Uuid::new_v4(), "bob", now + Duration::seconds(10)),
+        );
+        presence.apply(
+            event_id,
+            claim(Uuid::new_v4(), "alice", now + Duration::seconds(40)),
+        );
+
+        let editors = presence.editors_at(event_id, now);
+        assert_eq!(editors.len(), 2);
+        assert_eq!(editors[0].username, "alice");
+
+        let editors = presence.editors_at(event_id, now + Duration::seconds(20));
+        assert_eq!(editors.len(), 1);
+        assert_eq!(editors[0].username, "alice");
+
+        assert!(presence
+            .editors_at(event_id, now + Duration::seconds(60))
+            .is_empty());
+        assert!(presence.claims.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn releases_remove_claims() {
+        let presence = presence();
+        let event_id = Uuid::new_v4();
+        let user_id = Uuid::new_v4();
+        let expires_at = OffsetDateTime::now_utc() + Duration::seconds(30);
+        presence.apply(event_id, claim(user_id, "alice", expires_at));
+
+        let release = PresenceChange {
+            user_id,
+            username: "alice".to_string(),
+            expires_at: None,
+        };
+        presence.receive(&RealtimeMessage {
+            topic: PRESENCE_TOPIC.to_string(),
+            aggregate_id: event_id,
+            payload: serde_json::to_value(release).unwrap(),
+        });
+
+        assert!(presence.editors(event_id).is_empty());
+    }
+}
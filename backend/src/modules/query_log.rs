@@ -0,0 +1,138 @@
+use axum::middleware::Next;
+use axum::response::Response;
+use bimetable_db::config::app::ApplicationSettings;
+use bimetable_db::config::environment::Environment;
+use http::{HeaderValue, Request};
+use std::cell::Cell;
+use std::fmt::Debug;
+use std::future::Future;
+use tracing::field::{Field, Visit};
+use tracing::{debug, Event, Subscriber};
+use tracing_subscriber::filter::filter_fn;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::Layer;
+
+/// Header with the number of SQL statements run by the request.
+pub const DB_QUERIES: &str = "x-db-queries";
+
+/// Target of the statements logged by sqlx.
+const SQLX_TARGET: &str = "sqlx::query";
+
+tokio::task_local! {
+    static QUERY_COUNT: Cell<u32>;
+}
+
+/// Whether SQL statements are logged and counted, which is only allowed in development.
+pub fn is_enabled(settings: &ApplicationSettings, environment: &Environment) -> bool {
+    settings.sql_logging && environment.is_dev()
+}
+
+/// Counts the statements logged by sqlx and logs them again with their string literals redacted.
+///
+/// Bind parameters never reach the log, sqlx only logs the statements with their placeholders.
+pub fn layer<S>() -> impl Layer<S>
+where
+    S: Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    QueryLog.with_filter(filter_fn(|metadata| metadata.target() == SQLX_TARGET))
+}
+
+struct QueryLog;
+
+impl<S: Subscriber> Layer<S> for QueryLog {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let _ = QUERY_COUNT.try_with(|count| count.set(count.get() + 1));
+
+        let mut message = Message::default();
+        event.record(&mut message);
+        debug!(target: "bimetable::sql", "{}", redact_literals(&message.0));
+    }
+}
+
+#[derive(Default)]
+struct Message(String);
+
+impl Visit for Message {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{value:?}");
+        }
+    }
+}
+
+/// Replaces string literals of the statement, which might carry user data.
+fn redact_literals(statement: &str) -> String {
+    let mut redacted = String::with_capacity(statement.len());
+    let mut in_literal = false;
+    let mut chars = statement.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, in_literal) {
+            ('\'', false) => {
+                in_literal = true;
+                redacted.push_str("'?");
+            }
+            // Quotes are escaped by doubling them
+            ('\'', true) if chars.peek() == Some(&'\'') => {
+                chars.next();
+            }
+            ('\'', true) => {
+                in_literal = false;
+                redacted.push('\'');
+            }
+            (_, true) => {}
+            (c, false) => redacted.push(c),
+        }
+    }
+    redacted
+}
+
+/// Runs the future, returning its output with the number of statements it ran.
+async fn counted<F: Future>(future: F) -> (F::Output, u32) {
+    QUERY_COUNT
+        .scope(Cell::new(0), async {
+            let output = future.await;
+            (output, QUERY_COUNT.with(Cell::get))
+        })
+        .await
+}
+
+/// Adds the number of statements run by the request in `x-db-queries`, making N+1 patterns visible.
+pub async fn count_queries<B>(req: Request<B>, next: Next<B>) -> Response {
+    let (mut res, count) = counted(next.run(req)).await;
+    res.headers_mut()
+        .insert(DB_QUERIES, HeaderValue::from(count));
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::Registry;
+
+    #[test]
+    fn string_literals_are_redacted() {
+        assert_eq!(
+            redact_literals("SELECT id FROM users WHERE username = 'adimac93' AND tag = $1"),
+            "SELECT id FROM users WHERE username = '?' AND tag = $1"
+        );
+        assert_eq!(
+            redact_literals("SELECT 'it''s' AS quote"),
+            "SELECT '?' AS quote"
+        );
+    }
+
+    #[tokio::test]
+    async fn statements_of_the_future_are_counted() {
+        let _guard = tracing::subscriber::set_default(Registry::default().with(layer()));
+
+        let (_, count) = counted(async {
+            tracing::debug!(target: "sqlx::query", "SELECT 1");
+            tracing::debug!(target: "bimetable", "not a statement");
+            tracing::debug!(target: "sqlx::query", "SELECT 2");
+        })
+        .await;
+
+        assert_eq!(count, 2);
+    }
+}
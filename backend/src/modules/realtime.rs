@@ -0,0 +1,196 @@
+use std::sync::Arc;
+
+use axum::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::postgres::PgListener;
+use sqlx::{query, PgPool};
+use time::Duration;
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use bimetable_db::modules::outbox::{Dispatcher, OutboxMessage};
+
+/// Postgres channel shared by all instances.
+pub const REALTIME_CHANNEL: &str = "bimetable_realtime";
+
+const DEFAULT_CAPACITY: usize = 256;
+const RECONNECT_DELAY: Duration = Duration::seconds(1);
+
+/// Change pushed to connected clients.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RealtimeMessage {
+    pub topic: String,
+    pub aggregate_id: Uuid,
+    pub payload: Value,
+}
+
+impl From<&OutboxMessage> for RealtimeMessage {
+    fn from(message: &OutboxMessage) -> Self {
+        Self {
+            topic: message.topic.clone(),
+            aggregate_id: message.aggregate_id,
+            payload: message.payload.clone(),
+        }
+    }
+}
+
+/// Fan-out of realtime messages to clients connected to this instance.
+#[derive(Clone)]
+pub struct Realtime {
+    sender: broadcast::Sender<RealtimeMessage>,
+}
+
+impl Realtime {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<RealtimeMessage> {
+        self.sender.subscribe()
+    }
+
+    /// Passes the message to local subscribers, returning how many received it.
+    pub fn deliver(&self, message: RealtimeMessage) -> usize {
+        self.sender.send(message).unwrap_or(0)
+    }
+}
+
+impl Default for Realtime {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+/// Transport of realtime messages between backend instances.
+#[async_trait]
+pub trait Bridge: Send + Sync + 'static {
+    async fn publish(&self, message: &RealtimeMessage) -> anyhow::Result<()>;
+}
+
+/// Bridge delivering messages to this instance only, enough for a single replica.
+pub struct LocalBridge {
+    realtime: Realtime,
+}
+
+impl LocalBridge {
+    pub fn new(realtime: Realtime) -> Self {
+        Self { realtime }
+    }
+}
+
+#[async_trait]
+impl Bridge for LocalBridge {
+    async fn publish(&self, message: &RealtimeMessage) -> anyhow::Result<()> {
+        self.realtime.deliver(message.clone());
+        Ok(())
+    }
+}
+
+/// Bridge propagating messages to every instance through Postgres `LISTEN`/`NOTIFY`.
+///
+/// Each instance runs a [`PgBridge::listen`] task delivering notifications to its own clients,
+/// including the ones it published itself.
+pub struct PgBridge {
+    pool: PgPool,
+}
+
+impl PgBridge {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Delivers notifications of all instances to `realtime` in a background task.
+    pub async fn listen(pool: &PgPool, realtime: Realtime) -> Result<BridgeHandle, sqlx::Error> {
+        let mut listener = PgListener::connect_with(pool).await?;
+        listener.listen(REALTIME_CHANNEL).await?;
+        let (shutdown, mut shutdown_rx) = watch::channel(false);
+
+        info!("Listening for realtime messages on {REALTIME_CHANNEL}");
+        let task = tokio::spawn(async move {
+            loop {
+                let notification = tokio::select! {
+                    _ = shutdown_rx.changed() => break,
+                    notification = listener.recv() => notification,
+                };
+
+                match notification {
+                    Ok(notification) => {
+                        match serde_json::from_str::<RealtimeMessage>(notification.payload()) {
+                            Ok(message) => {
+                                let receivers = realtime.deliver(message);
+                                debug!("Delivered realtime message to {receivers} subscribers");
+                            }
+                            Err(e) => warn!("Skipping malformed realtime message: {e}"),
+                        }
+                    }
+                    Err(e) => {
+                        error!("Realtime listener failed: {e:?}");
+                        tokio::time::sleep(RECONNECT_DELAY.unsigned_abs()).await;
+                    }
+                }
+            }
+            info!("Realtime listener stopped");
+        });
+
+        Ok(BridgeHandle { shutdown, task })
+    }
+}
+
+#[async_trait]
+impl Bridge for PgBridge {
+    async fn publish(&self, message: &RealtimeMessage) -> anyhow::Result<()> {
+        query!(
+            "SELECT pg_notify($1, $2)",
+            REALTIME_CHANNEL,
+            serde_json::to_string(message)?,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+pub struct BridgeHandle {
+    shutdown: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl BridgeHandle {
+    pub async fn shutdown(self) {
+        info!("Shutting down realtime listener");
+        let _ = self.shutdown.send(true);
+        if let Err(e) = self.task.await {
+            error!("Realtime listener task failed: {e:?}");
+        }
+    }
+}
+
+/// Dispatcher publishing outbox messages through a bridge.
+pub struct RealtimeDispatcher {
+    bridge: Arc<dyn Bridge>,
+}
+
+impl RealtimeDispatcher {
+    pub fn new(bridge: impl Bridge) -> Self {
+        Self {
+            bridge: Arc::new(bridge),
+        }
+    }
+
+    pub fn shared(bridge: Arc<dyn Bridge>) -> Self {
+        Self { bridge }
+    }
+}
+
+#[async_trait]
+impl Dispatcher for RealtimeDispatcher {
+    async fn dispatch(&self, message: &OutboxMessage) -> anyhow::Result<()> {
+        self.bridge.publish(&message.into()).await
+    }
+}
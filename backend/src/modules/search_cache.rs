@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::State;
+use axum::middleware::Next;
+use axum::response::Response;
+use http::header::{HeaderName, CACHE_CONTROL};
+use http::{HeaderValue, Request};
+use serde::Serialize;
+use tracing::{error, trace};
+use uuid::Uuid;
+
+use crate::modules::maintenance::is_read;
+use crate::modules::AppState;
+use bimetable_domain::api::search::{SearchEventsResult, SearchUsersResult};
+
+/// Most results kept by a single cache, older ones are dropped when it fills up.
+const MAX_ENTRIES: usize = 1000;
+
+/// How long browsers may keep showing expired results while they fetch fresh ones
+const STALE_WHILE_REVALIDATE: Duration = Duration::from_secs(30);
+
+/// Recent results of the search endpoints, reused by type-ahead searches.
+///
+/// Results are kept per user and query for a few seconds and dropped on every successful write.
+#[derive(Clone)]
+pub struct SearchCache {
+    pub users: CachedResults<Vec<SearchUsersResult>>,
+    pub events: CachedResults<SearchEventsResult>,
+}
+
+impl SearchCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            users: CachedResults::new(ttl),
+            events: CachedResults::new(ttl),
+        }
+    }
+
+    pub fn clear(&self) {
+        self.users.clear();
+        self.events.clear();
+    }
+
+    /// `Cache-Control` of results, browsers reuse them for as long as the server does.
+    ///
+    /// Results are private to the searching user and never reused when the server cache is disabled.
+    pub fn cache_control(&self) -> [(HeaderName, HeaderValue); 1] {
+        [(CACHE_CONTROL, cache_control(self.users.ttl))]
+    }
+}
+
+fn cache_control(ttl: Duration) -> HeaderValue {
+    if ttl.is_zero() {
+        return HeaderValue::from_static("private, no-cache");
+    }
+    HeaderValue::try_from(format!(
+        "private, max-age={}, stale-while-revalidate={}",
+        ttl.as_secs(),
+        STALE_WHILE_REVALIDATE.as_secs()
+    ))
+    .expect("Cache-Control is visible ASCII")
+}
+
+/// Results along with the time they were cached at, keyed by the searching user and the serialized query.
+type Entries<V> = HashMap<(Uuid, String), (Instant, V)>;
+
+/// Results keyed by the searching user and the serialized query.
+#[derive(Clone)]
+pub struct CachedResults<V> {
+    ttl: Duration,
+    entries: Arc<Mutex<Entries<V>>>,
+}
+
+impl<V: Clone> CachedResults<V> {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn get(&self, user_id: Uuid, query: &impl Serialize) -> Option<V> {
+        let key = (user_id, key_of(query)?);
+        let entries = self.entries.lock().ok()?;
+        let (cached_at, value) = entries.get(&key)?;
+        if cached_at.elapsed() >= self.ttl {
+            return None;
+        }
+        trace!("Reusing cached search results of {user_id}");
+        Some(value.clone())
+    }
+
+    pub fn insert(&self, user_id: Uuid, query: &impl Serialize, value: V) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let Some(key) = key_of(query) else {
+            return;
+        };
+        let Ok(mut entries) = self.entries.lock() else {
+            error!("Search cache is poisoned");
+            return;
+        };
+        if entries.len() >= MAX_ENTRIES {
+            entries.retain(|_, (cached_at, _)| cached_at.elapsed() < self.ttl);
+        }
+        if entries.len() >= MAX_ENTRIES {
+            entries.clear();
+        }
+        entries.insert((user_id, key), (Instant::now(), value));
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+}
+
+fn key_of(query: &impl Serialize) -> Option<String> {
+    serde_json::to_string(query).ok()
+}
+
+/// Drops cached search results once a write succeeds, as it may change any of them.
+pub async fn invalidate_search_cache<B>(
+    State(state): State<AppState>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let is_write = !is_read(req.method());
+    let res = next.run(req).await;
+    if is_write && res.status().is_success() {
+        state.search_cache.clear();
+    }
+    res
+}
+
+#[cfg(test)]
+mod search_cache_tests {
+    use super::*;
+
+    const USER_ID: Uuid = uuid::uuid!("29e40c2a-7595-42d3-98e8-9fe93ce99972");
+
+    #[test]
+    fn reuses_results_of_the_same_query() {
+        let cache = CachedResults::new(Duration::from_secs(60));
+        cache.insert(USER_ID, &"mac", 1);
+
+        assert_eq!(cache.get(USER_ID, &"mac"), Some(1));
+        assert_eq!(cache.get(USER_ID, &"ma"), None);
+        assert_eq!(cache.get(Uuid::nil(), &"mac"), None);
+
+        cache.clear();
+        assert_eq!(cache.get(USER_ID, &"mac"), None);
+    }
+
+    #[test]
+    fn browsers_keep_results_as_long_as_the_server() {
+        assert_eq!(
+            cache_control(Duration::from_secs(5)),
+            "private, max-age=5, stale-while-revalidate=30"
+        );
+        assert_eq!(cache_control(Duration::ZERO), "private, no-cache");
+    }
+
+    #[test]
+    fn results_expire() {
+        let cache = CachedResults::new(Duration::ZERO);
+        cache.insert(USER_ID, &"mac", 1);
+
+        assert_eq!(cache.get(USER_ID, &"mac"), None);
+    }
+}
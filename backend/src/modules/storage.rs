@@ -0,0 +1,51 @@
+use axum::extract::{Path, Query, State};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use bimetable_db::modules::storage::{content_type, validate_key, Blobs};
+use http::header::CONTENT_TYPE;
+use http::StatusCode;
+use serde::Deserialize;
+use serde_json::json;
+use time::OffsetDateTime;
+use tracing::{debug, error};
+
+#[derive(Debug, Deserialize)]
+pub struct DownloadQuery {
+    /// Unix timestamp after which the URL stops working
+    pub expires: i64,
+    pub signature: String,
+}
+
+/// Serves objects of URLs signed by a backend without its own download host.
+pub async fn download_handler(
+    State(storage): State<Blobs>,
+    Path(key): Path<String>,
+    Query(query): Query<DownloadQuery>,
+) -> Response {
+    if validate_key(&key).is_err()
+        || !storage.verify_download(&key, query.expires, &query.signature)
+    {
+        debug!("Rejected download with an invalid signature");
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error_info": "Invalid download link" })),
+        )
+            .into_response();
+    }
+    if query.expires < OffsetDateTime::now_utc().unix_timestamp() {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error_info": "Download link has expired" })),
+        )
+            .into_response();
+    }
+
+    match storage.get(&key).await {
+        Ok(Some(body)) => ([(CONTENT_TYPE, content_type(&key))], body).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "404 Not Found").into_response(),
+        Err(e) => {
+            error!("Failed to read stored object: {e:?}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
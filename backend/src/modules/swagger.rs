@@ -0,0 +1,155 @@
+use axum::extract::{FromRequestParts, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use http::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+use http::{HeaderMap, Request, StatusCode};
+use secrecy::{ExposeSecret, Secret};
+use serde_json::json;
+use tracing::{debug, error};
+
+use crate::modules::AppState;
+use crate::utils::auth::models::Claims;
+use crate::utils::secrets::is_secret;
+use bimetable_db::config::app::{ApplicationSettings, SwaggerAccess};
+use bimetable_db::config::environment::Environment;
+use bimetable_db::utils::users::is_admin;
+
+/// Access to the Swagger UI and the OpenAPI document behind it.
+///
+/// Protected Swagger UI lets in admins and, when configured, holders of the basic auth credentials.
+#[derive(Clone)]
+pub struct Swagger {
+    access: SwaggerAccess,
+    credentials: Option<(String, Secret<String>)>,
+}
+
+impl Swagger {
+    pub fn new(settings: &ApplicationSettings, environment: &Environment) -> Self {
+        let access = settings.swagger.unwrap_or(if environment.is_dev() {
+            SwaggerAccess::Open
+        } else {
+            SwaggerAccess::Disabled
+        });
+        let credentials = settings
+            .swagger_user
+            .clone()
+            .zip(settings.swagger_password.clone());
+        Self {
+            access,
+            credentials,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.access != SwaggerAccess::Disabled
+    }
+
+    fn is_open(&self) -> bool {
+        self.access == SwaggerAccess::Open
+    }
+
+    fn has_credentials(&self, headers: &HeaderMap) -> bool {
+        let Some((user, password)) = &self.credentials else {
+            return false;
+        };
+        headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Basic "))
+            .and_then(|encoded| STANDARD.decode(encoded).ok())
+            .and_then(|decoded| String::from_utf8(decoded).ok())
+            .is_some_and(|decoded| {
+                decoded
+                    .split_once(':')
+                    .is_some_and(|(provided_user, provided)| {
+                        // Both parts are compared, so the time taken doesn't tell which is wrong
+                        is_secret(user, provided_user)
+                            & is_secret(password.expose_secret(), provided)
+                    })
+            })
+    }
+}
+
+/// Lets only admins and holders of the credentials into a protected Swagger UI.
+pub async fn swagger_guard<B>(
+    State(state): State<AppState>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    if state.swagger.is_open() || state.swagger.has_credentials(req.headers()) {
+        return next.run(req).await;
+    }
+
+    let (mut parts, body) = req.into_parts();
+    if let Ok(claims) = Claims::from_request_parts(&mut parts, &state).await {
+        match is_admin(&state.pool, claims.user_id).await {
+            Ok(true) => return next.run(Request::from_parts(parts, body)).await,
+            Ok(false) => (),
+            Err(e) => error!("Failed to check admin privileges: {e:?}"),
+        }
+    }
+
+    debug!("Rejected unauthorized Swagger UI request {}", parts.uri);
+    (
+        StatusCode::UNAUTHORIZED,
+        [(WWW_AUTHENTICATE, "Basic realm=\"Swagger UI\"")],
+        Json(json!({ "error_info": "Swagger UI is available to admins only" })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod swagger_tests {
+    use http::HeaderValue;
+
+    use super::*;
+
+    fn basic(credentials: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Basic {}", STANDARD.encode(credentials))).unwrap(),
+        );
+        headers
+    }
+
+    fn protected(user: Option<&str>, password: Option<&str>) -> Swagger {
+        let settings = ApplicationSettings {
+            swagger: Some(SwaggerAccess::Protected),
+            swagger_user: user.map(str::to_string),
+            swagger_password: password.map(|password| Secret::from(password.to_string())),
+            ..Default::default()
+        };
+        Swagger::new(&settings, &Environment::Production)
+    }
+
+    #[test]
+    fn defaults_depend_on_environment() {
+        let settings = ApplicationSettings::default();
+
+        assert!(Swagger::new(&settings, &Environment::Development).is_open());
+        assert!(!Swagger::new(&settings, &Environment::Production).is_enabled());
+    }
+
+    #[test]
+    fn requires_matching_basic_credentials() {
+        let swagger = protected(Some("operator"), Some("secret"));
+
+        assert!(swagger.is_enabled());
+        assert!(!swagger.is_open());
+        assert!(swagger.has_credentials(&basic("operator:secret")));
+        assert!(!swagger.has_credentials(&basic("operator:other")));
+        assert!(!swagger.has_credentials(&basic("admin:secret")));
+        assert!(!swagger.has_credentials(&HeaderMap::new()));
+    }
+
+    #[test]
+    fn admins_only_without_credentials() {
+        let swagger = protected(Some("operator"), None);
+
+        assert!(!swagger.has_credentials(&basic("operator:")));
+    }
+}
@@ -0,0 +1,189 @@
+use axum::middleware::Next;
+use axum::response::Response;
+use http::{HeaderMap, HeaderValue, Request};
+use tracing::{info_span, Instrument};
+
+/// Header of the W3C trace context.
+pub const TRACEPARENT: &str = "traceparent";
+
+const VERSION: &str = "00";
+const SAMPLED: u8 = 0x01;
+
+/// W3C trace context of a request.
+///
+/// Continues the trace of the caller when it sent a valid `traceparent`, otherwise starts a new one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    pub trace_id: String,
+    /// Span of the caller, if the trace was continued
+    pub parent_id: Option<String>,
+    pub span_id: String,
+    pub flags: u8,
+}
+
+impl TraceContext {
+    pub fn new() -> Self {
+        Self {
+            trace_id: format!("{:032x}", random_id::<u128>()),
+            parent_id: None,
+            span_id: format!("{:016x}", random_id::<u64>()),
+            flags: SAMPLED,
+        }
+    }
+
+    pub fn continue_from(headers: &HeaderMap) -> Self {
+        let Some((trace_id, parent_id, flags)) = headers
+            .get(TRACEPARENT)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_traceparent)
+        else {
+            return Self::new();
+        };
+        Self {
+            trace_id,
+            parent_id: Some(parent_id),
+            flags,
+            ..Self::new()
+        }
+    }
+
+    /// `traceparent` naming this request as the parent of the following spans.
+    pub fn traceparent(&self) -> String {
+        format!(
+            "{VERSION}-{}-{}-{:02x}",
+            self.trace_id, self.span_id, self.flags
+        )
+    }
+}
+
+impl Default for TraceContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn random_id<T>() -> T
+where
+    T: Default + PartialEq,
+    rand::distributions::Standard: rand::distributions::Distribution<T>,
+{
+    loop {
+        let id = rand::random::<T>();
+        if id != T::default() {
+            return id;
+        }
+    }
+}
+
+/// Reads trace id, parent id and flags of a `traceparent` value.
+fn parse_traceparent(value: &str) -> Option<(String, String, u8)> {
+    let mut parts = value.trim().split('-');
+    let version = parts.next()?;
+    let trace_id = parts.next()?;
+    let parent_id = parts.next()?;
+    let flags = parts.next()?;
+
+    if !is_id(version, 2) || version == "ff" || (version == VERSION && parts.next().is_some()) {
+        return None;
+    }
+    if !is_id(trace_id, 32) || !is_id(parent_id, 16) || !is_id(flags, 2) {
+        return None;
+    }
+    Some((
+        trace_id.to_string(),
+        parent_id.to_string(),
+        u8::from_str_radix(flags, 16).ok()?,
+    ))
+}
+
+/// Lowercase hex of the given length, which is not all zeros.
+fn is_id(value: &str, len: usize) -> bool {
+    value.len() == len
+        && value
+            .bytes()
+            .all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte))
+        && (len == 2 || value.bytes().any(|byte| byte != b'0'))
+}
+
+/// Runs the request within a span of its trace context and returns the context to the caller.
+pub async fn trace_request<B>(mut req: Request<B>, next: Next<B>) -> Response {
+    let context = TraceContext::continue_from(req.headers());
+    let span = info_span!(
+        "request",
+        method = %req.method(),
+        path = %req.uri().path(),
+        trace_id = %context.trace_id,
+        span_id = %context.span_id,
+        parent_id = context.parent_id.as_deref(),
+    );
+    req.extensions_mut().insert(context.clone());
+
+    let mut res = next.run(req).instrument(span).await;
+    if let Ok(traceparent) = HeaderValue::from_str(&context.traceparent()) {
+        res.headers_mut().insert(TRACEPARENT, traceparent);
+    }
+    res
+}
+
+#[cfg(test)]
+mod trace_context_tests {
+    use super::*;
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+    const PARENT_ID: &str = "00f067aa0ba902b7";
+
+    fn with_traceparent(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(TRACEPARENT, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn continues_valid_traceparent() {
+        let context = TraceContext::continue_from(&with_traceparent(&format!(
+            "00-{TRACE_ID}-{PARENT_ID}-01"
+        )));
+
+        assert_eq!(context.trace_id, TRACE_ID);
+        assert_eq!(context.parent_id.as_deref(), Some(PARENT_ID));
+        assert_ne!(context.span_id, PARENT_ID);
+        assert_eq!(
+            context.traceparent(),
+            format!("00-{TRACE_ID}-{}-01", context.span_id)
+        );
+    }
+
+    #[test]
+    fn starts_new_trace_on_invalid_traceparent() {
+        for value in [
+            format!("00-{TRACE_ID}-{PARENT_ID}"),
+            format!("ff-{TRACE_ID}-{PARENT_ID}-01"),
+            format!("00-{}-{PARENT_ID}-01", "0".repeat(32)),
+            format!("00-{TRACE_ID}-{}-01", "0".repeat(16)),
+            format!("00-{}-{PARENT_ID}-01", TRACE_ID.to_uppercase()),
+            format!("00-{TRACE_ID}-{PARENT_ID}-01-extra"),
+        ] {
+            let context = TraceContext::continue_from(&with_traceparent(&value));
+
+            assert_ne!(context.trace_id, TRACE_ID, "{value}");
+            assert_eq!(context.parent_id, None, "{value}");
+        }
+    }
+
+    #[test]
+    fn accepts_future_versions() {
+        let context = TraceContext::continue_from(&with_traceparent(&format!(
+            "01-{TRACE_ID}-{PARENT_ID}-00-extra"
+        )));
+
+        assert_eq!(context.trace_id, TRACE_ID);
+        assert_eq!(context.flags, 0);
+    }
+
+    #[test]
+    fn new_trace_has_valid_ids() {
+        let context = TraceContext::new();
+
+        assert!(parse_traceparent(&context.traceparent()).is_some());
+    }
+}
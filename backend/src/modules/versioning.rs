@@ -0,0 +1,42 @@
+use axum::middleware::Next;
+use axum::response::Response;
+use bimetable_domain::api::API_V1;
+use http::{HeaderValue, Request};
+use tracing::debug;
+
+const DEPRECATION: &str = "deprecation";
+const LINK: &str = "link";
+
+/// Strips the version prefix from a request path.
+pub fn unversioned(path: &str) -> &str {
+    match path.strip_prefix(API_V1) {
+        Some(rest) if rest.starts_with('/') => rest,
+        _ => path,
+    }
+}
+
+/// Marks responses of unversioned paths as deprecated, pointing clients at their `/api/v1` successors.
+pub async fn deprecated_path<B>(req: Request<B>, next: Next<B>) -> Response {
+    let successor = format!("{API_V1}{}", req.uri().path());
+    debug!("Unversioned request to {}", req.uri().path());
+
+    let mut res = next.run(req).await;
+    let headers = res.headers_mut();
+    headers.insert(DEPRECATION, HeaderValue::from_static("true"));
+    if let Ok(link) = HeaderValue::from_str(&format!("<{successor}>; rel=\"successor-version\"")) {
+        headers.insert(LINK, link);
+    }
+    res
+}
+
+#[cfg(test)]
+mod versioning_tests {
+    use super::*;
+
+    #[test]
+    fn strips_version_prefix() {
+        assert_eq!(unversioned("/api/v1/auth/login"), "/auth/login");
+        assert_eq!(unversioned("/auth/login"), "/auth/login");
+        assert_eq!(unversioned("/api/v10/auth/login"), "/api/v10/auth/login");
+    }
+}
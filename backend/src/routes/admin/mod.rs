@@ -0,0 +1,138 @@
+use crate::app_errors::ApiError;
+
+use crate::modules::maintenance::Maintenance;
+use crate::modules::AppState;
+use crate::utils::auth::models::Claims;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::routing::{get, patch, post};
+use axum::{Json, Router};
+use bimetable_db::config::retention::RetentionSettings;
+use bimetable_db::modules::clock::SharedClock;
+use bimetable_db::utils::admin::errors::AdminError;
+use bimetable_db::utils::admin::signups::mint_signup_codes;
+use bimetable_db::utils::admin::stats::get_daily_stats;
+use bimetable_db::utils::admin::{deactivate_other_user, ensure_admin};
+use bimetable_domain::api::admin::{
+    DailyStats, GetStatsQuery, MaintenanceStatus, MintSignupCodes, RetentionPolicy, SignupCode,
+};
+use sqlx::PgPool;
+use time::Duration;
+use tracing::debug;
+use uuid::Uuid;
+
+const DEFAULT_STATS_DAYS: u32 = 30;
+const MAX_STATS_DAYS: u32 = 366;
+const MAX_SIGNUP_CODES: u32 = 100;
+const DEFAULT_SIGNUP_CODE_DAYS: u32 = 14;
+const MAX_SIGNUP_CODE_DAYS: u32 = 366;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/maintenance", get(get_maintenance).put(set_maintenance))
+        .route("/stats", get(get_stats))
+        .route("/retention", get(get_retention))
+        .route("/signup-codes", post(post_signup_codes))
+        .route("/users/:id/deactivate", patch(deactivate_user))
+}
+
+/// Get maintenance mode
+#[utoipa::path(get, path = "/admin/maintenance", tag = "admin", responses((status = 200, description = "Current maintenance mode", body = MaintenanceStatus)))]
+async fn get_maintenance(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    State(maintenance): State<Maintenance>,
+) -> Result<Json<MaintenanceStatus>, ApiError<AdminError>> {
+    ensure_admin(&pool, claims.user_id).await?;
+
+    Ok(Json(MaintenanceStatus {
+        is_enabled: maintenance.is_enabled().await?,
+    }))
+}
+
+/// Set maintenance mode
+#[utoipa::path(put, path = "/admin/maintenance", tag = "admin", request_body = MaintenanceStatus, responses((status = 200, description = "Updated maintenance mode", body = MaintenanceStatus)))]
+async fn set_maintenance(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    State(maintenance): State<Maintenance>,
+    Json(body): Json<MaintenanceStatus>,
+) -> Result<Json<MaintenanceStatus>, ApiError<AdminError>> {
+    ensure_admin(&pool, claims.user_id).await?;
+    maintenance.set(body.is_enabled).await?;
+    debug!("Admin {} switched the maintenance mode", claims.user_id);
+
+    Ok(Json(MaintenanceStatus {
+        is_enabled: body.is_enabled,
+    }))
+}
+
+/// Get usage statistics
+///
+/// Statistics are computed by a background job, the latest day is refreshed every hour.
+#[utoipa::path(get, path = "/admin/stats", tag = "admin", params(GetStatsQuery), responses((status = 200, description = "Statistics of the latest days, newest first", body = [DailyStats])))]
+async fn get_stats(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    Query(query): Query<GetStatsQuery>,
+) -> Result<Json<Vec<DailyStats>>, ApiError<AdminError>> {
+    ensure_admin(&pool, claims.user_id).await?;
+    let days = query.days.unwrap_or(DEFAULT_STATS_DAYS).min(MAX_STATS_DAYS);
+
+    Ok(Json(get_daily_stats(&pool, days).await?))
+}
+
+/// Get retention policy
+///
+/// Data past its retention is purged by a background job every hour.
+#[utoipa::path(get, path = "/admin/retention", tag = "admin", responses((status = 200, description = "Retention policy of the deployment", body = RetentionPolicy)))]
+async fn get_retention(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    State(retention): State<RetentionSettings>,
+) -> Result<Json<RetentionPolicy>, ApiError<AdminError>> {
+    ensure_admin(&pool, claims.user_id).await?;
+
+    Ok(Json(RetentionPolicy::from(&retention)))
+}
+
+/// Mint signup codes
+///
+/// Registering requires one of the codes when signups are invite-only, each can be used once.
+#[utoipa::path(post, path = "/admin/signup-codes", tag = "admin", request_body = MintSignupCodes, responses((status = 201, description = "Minted signup codes", body = [SignupCode])))]
+async fn post_signup_codes(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    State(clock): State<SharedClock>,
+    Json(body): Json<MintSignupCodes>,
+) -> Result<(StatusCode, Json<Vec<SignupCode>>), ApiError<AdminError>> {
+    ensure_admin(&pool, claims.user_id).await?;
+    let count = body.count.clamp(1, MAX_SIGNUP_CODES);
+    let days = body
+        .valid_days
+        .unwrap_or(DEFAULT_SIGNUP_CODE_DAYS)
+        .clamp(1, MAX_SIGNUP_CODE_DAYS);
+    let expires_at = clock.now() + Duration::days(days.into());
+
+    let codes = mint_signup_codes(&pool, claims.user_id, count, expires_at).await?;
+    debug!("Admin {} minted {count} signup codes", claims.user_id);
+
+    Ok((StatusCode::CREATED, Json(codes)))
+}
+
+/// Deactivate user
+///
+/// Signs the user out everywhere, disables their login and hides them from search, their events are kept.
+#[utoipa::path(patch, path = "/admin/users/{id}/deactivate", tag = "admin", responses((status = 204, description = "Deactivated the user"), (status = 404, description = "User does not exist")))]
+async fn deactivate_user(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    State(clock): State<SharedClock>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError<AdminError>> {
+    ensure_admin(&pool, claims.user_id).await?;
+    deactivate_other_user(&pool, id, clock.now()).await?;
+    debug!("Admin {} deactivated the user {id}", claims.user_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
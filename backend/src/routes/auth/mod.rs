@@ -1,21 +1,28 @@
-pub mod models;
-
+use crate::app_errors::ApiError;
 use crate::modules::AppState;
-use crate::routes::auth::models::{LoginCredentials, RegisterCredentials};
-use crate::utils::auth::errors::AuthError;
 use crate::utils::auth::models::*;
 use crate::utils::auth::*;
 use axum::extract::State;
-use axum::{debug_handler, http::StatusCode, Extension, Json};
+use axum::{debug_handler, http::HeaderMap, http::StatusCode, Extension, Json};
 use axum::{routing::post, Router};
 use axum_extra::extract::cookie::Cookie;
 use axum_extra::extract::CookieJar;
+use bimetable_db::modules::clock::SharedClock;
+use bimetable_db::utils::auth::errors::AuthError;
+use bimetable_db::utils::auth::{
+    deactivate_user, get_token_version, revoke_user_tokens, try_register_user,
+    verify_user_credentials, Signup,
+};
+use bimetable_db::utils::invitations::email::accept_email_invitation;
+use bimetable_domain::api::auth::{AuthTokens, LoginCredentials, RegisterCredentials};
 use jsonwebtoken::{DecodingKey, Validation};
 use secrecy::SecretString;
 use serde_json::{json, Value};
 use sqlx::PgPool;
 
-use crate::config::tokens::JwtSettings;
+use bimetable_db::config::passwords::PasswordSettings;
+use bimetable_db::config::tokens::{CookieSettings, JwtSettings};
+use bimetable_db::config::usernames::UsernameSettings;
 use time::Duration;
 use tracing::debug;
 
@@ -23,29 +30,60 @@ pub fn router() -> Router<AppState> {
     Router::new()
         .route("/register", post(post_register_user))
         .route("/login", post(post_login_user))
+        .route("/token", post(post_issue_token))
+        .route("/token/refresh", post(post_refresh_token))
         .route("/validate", post(protected_zone))
         .route("/logout", post(post_logout_user))
+        .route("/logout-all", post(post_logout_all_user))
         .route("/refresh", post(post_refresh_user_token))
+        .route("/deactivate", post(post_deactivate_user))
 }
 
 /// Register user
-#[utoipa::path(post, path = "/auth/register", tag = "auth", request_body = RegisterCredentials, responses((status = 200, description = "User has successfully registered")))]
-#[debug_handler]
+#[utoipa::path(post, path = "/auth/register", tag = "auth", request_body = RegisterCredentials, responses((status = 200, description = "User has successfully registered"), (status = 403, description = "Signup policy of the instance rejected the registration")))]
+#[debug_handler(state = AppState)]
 async fn post_register_user(
-    State(pool): State<PgPool>,
+    State(state): State<AppState>,
     Extension(secrets): Extension<JwtSettings>,
+    Extension(passwords): Extension<PasswordSettings>,
+    Extension(usernames): Extension<UsernameSettings>,
     jar: CookieJar,
     Json(register_credentials): Json<RegisterCredentials>,
-) -> Result<CookieJar, AuthError> {
+) -> Result<CookieJar, ApiError<AuthError>> {
     let user_id = try_register_user(
-        &pool,
+        &state.pool,
         register_credentials.login.trim(),
         SecretString::new(register_credentials.password.trim().to_string()),
         &register_credentials.username,
+        &passwords,
+        &usernames,
+        Some(Signup {
+            settings: &state.signups,
+            code: register_credentials.signup_code.as_deref(),
+            now: state.clock.now(),
+        }),
     )
     .await?;
 
-    let jar = generate_token_cookies(user_id, &register_credentials.login, secrets, jar)?;
+    if let Some(token) = &register_credentials.invitation {
+        // The account exists already, a stale sign-up link must not fail the registration
+        if let Err(e) =
+            accept_email_invitation(&state.pool, &secrets.invitation, token, user_id).await
+        {
+            debug!("Skipping the invitation of the sign-up link: {e}");
+        }
+    }
+
+    let mut conn = state.pool.acquire().await?;
+    let ver = get_token_version(&mut conn, user_id).await?;
+    let jar = generate_token_cookies(
+        user_id,
+        &register_credentials.login,
+        ver,
+        secrets,
+        jar,
+        state.clock.now(),
+    )?;
 
     debug!(
         "User {} ({}) registered successfully",
@@ -59,10 +97,12 @@ async fn post_register_user(
 #[utoipa::path(post, path = "/auth/login", tag = "auth", request_body = LoginCredentials, responses((status = 200, description = "User has successfully logged in")))]
 async fn post_login_user(
     State(pool): State<PgPool>,
+    State(clock): State<SharedClock>,
     Extension(secrets): Extension<JwtSettings>,
+    Extension(passwords): Extension<PasswordSettings>,
     jar: CookieJar,
     Json(login_credentials): Json<LoginCredentials>,
-) -> Result<CookieJar, AuthError> {
+) -> Result<CookieJar, ApiError<AuthError>> {
     // returns if credentials are wrong
     let mut conn = pool.acquire().await?;
 
@@ -70,16 +110,86 @@ async fn post_login_user(
         &mut conn,
         &login_credentials.login,
         SecretString::new(login_credentials.password.clone()),
+        &passwords,
     )
     .await?;
 
-    let jar = generate_token_cookies(user_id, &login_credentials.login, secrets, jar)?;
+    let ver = get_token_version(&mut conn, user_id).await?;
+    let jar = generate_token_cookies(
+        user_id,
+        &login_credentials.login,
+        ver,
+        secrets,
+        jar,
+        clock.now(),
+    )?;
 
     debug!("User {} logged in successfully", user_id);
 
     Ok(jar)
 }
 
+/// Issue bearer tokens
+#[utoipa::path(post, path = "/auth/token", tag = "auth", request_body = LoginCredentials, responses((status = 200, description = "Tokens for the `Authorization: Bearer` header", body = AuthTokens)))]
+async fn post_issue_token(
+    State(pool): State<PgPool>,
+    State(clock): State<SharedClock>,
+    Extension(secrets): Extension<JwtSettings>,
+    Extension(passwords): Extension<PasswordSettings>,
+    Json(login_credentials): Json<LoginCredentials>,
+) -> Result<Json<AuthTokens>, ApiError<AuthError>> {
+    let mut conn = pool.acquire().await?;
+
+    let user_id = verify_user_credentials(
+        &mut conn,
+        &login_credentials.login,
+        SecretString::new(login_credentials.password.clone()),
+        &passwords,
+    )
+    .await?;
+
+    let ver = get_token_version(&mut conn, user_id).await?;
+    let tokens = generate_tokens(
+        user_id,
+        &login_credentials.login,
+        ver,
+        &secrets,
+        clock.now(),
+    )?;
+
+    debug!("Issued bearer tokens for user {}", user_id);
+
+    Ok(Json(tokens))
+}
+
+/// Refresh bearer tokens
+///
+/// Exchanges the refresh token for a new pair, for clients which don't keep cookies.
+#[utoipa::path(post, path = "/auth/token/refresh", tag = "auth", responses((status = 200, description = "Refreshed tokens for the `Authorization: Bearer` header", body = AuthTokens), (status = 401, description = "Refresh token is invalid or revoked")))]
+async fn post_refresh_token(
+    State(pool): State<PgPool>,
+    State(clock): State<SharedClock>,
+    Extension(secrets): Extension<JwtSettings>,
+    refresh_claims: RefreshClaims,
+) -> Result<Json<AuthTokens>, ApiError<AuthError>> {
+    let tokens = generate_tokens(
+        refresh_claims.user_id,
+        &refresh_claims.login,
+        refresh_claims.ver,
+        &secrets,
+        clock.now(),
+    )?;
+
+    refresh_claims.add_token_to_blacklist(&pool).await?;
+
+    debug!(
+        "Bearer tokens of user {} refreshed successfully",
+        &refresh_claims.user_id,
+    );
+
+    Ok(Json(tokens))
+}
+
 /// Validate tokens
 #[utoipa::path(post, path = "/auth/validate", tag = "auth", responses((status = 200, description = "User has valid auth tokens")))]
 async fn protected_zone(claims: Claims) -> Result<Json<Value>, StatusCode> {
@@ -87,36 +197,96 @@ async fn protected_zone(claims: Claims) -> Result<Json<Value>, StatusCode> {
 }
 
 /// Logout user
+///
+/// Bearer clients send the token to revoke, otherwise the tokens of the cookies are revoked.
 #[utoipa::path(post, path = "/auth/logout", tag = "auth")]
 async fn post_logout_user(
     State(state): State<AppState>,
     Extension(secrets): Extension<JwtSettings>,
+    headers: HeaderMap,
     jar: CookieJar,
-) -> Result<CookieJar, AuthError> {
+) -> Result<CookieJar, ApiError<AuthError>> {
     let validation = Validation::default();
+    let access = &secrets.access.0.token;
+    let refresh = &secrets.refresh.0.token;
 
-    if let Ok(Some(data)) = Claims::decode_jwt(&jar, Some(&validation), secrets.access.0.token) {
-        let _ = &data.claims.add_token_to_blacklist(&state.pool).await?;
+    let (access_data, refresh_data) = match bearer_token(&headers) {
+        Some(token) => (
+            Claims::decode_token(token, Some(&validation), access.to_owned()),
+            RefreshClaims::decode_token(token, Some(&validation), refresh.to_owned()),
+        ),
+        None => (
+            Claims::decode_jwt(&jar, Some(&validation), access.to_owned()),
+            RefreshClaims::decode_jwt(&jar, Some(&validation), refresh.to_owned()),
+        ),
+    };
+
+    if let Ok(Some(data)) = access_data {
+        data.claims.add_token_to_blacklist(&state.pool).await?;
     }
 
-    if let Ok(Some(data)) =
-        RefreshClaims::decode_jwt(&jar, Some(&validation), secrets.refresh.0.token)
-    {
-        let _ = &data.claims.add_token_to_blacklist(&state.pool).await?;
+    if let Ok(Some(data)) = refresh_data {
+        data.claims.add_token_to_blacklist(&state.pool).await?;
     }
 
     debug!("User logged out successfully");
 
     Ok(jar
-        .remove(get_remove_cookie(Claims::NAME))
-        .remove(get_remove_cookie(RefreshClaims::NAME)))
+        .remove(get_remove_cookie(Claims::NAME, &secrets.cookie))
+        .remove(get_remove_cookie(RefreshClaims::NAME, &secrets.cookie)))
 }
 
-fn get_remove_cookie(name: &str) -> Cookie {
-    Cookie::build(name, "")
+/// Logout user from all sessions
+#[utoipa::path(post, path = "/auth/logout-all", tag = "auth", responses((status = 200, description = "Revoked all tokens of the user")))]
+async fn post_logout_all_user(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    Extension(secrets): Extension<JwtSettings>,
+    jar: CookieJar,
+) -> Result<CookieJar, ApiError<AuthError>> {
+    let mut conn = pool.acquire().await?;
+    revoke_user_tokens(&mut conn, claims.user_id).await?;
+
+    debug!("User {} logged out from all sessions", claims.user_id);
+
+    Ok(jar
+        .remove(get_remove_cookie(Claims::NAME, &secrets.cookie))
+        .remove(get_remove_cookie(RefreshClaims::NAME, &secrets.cookie)))
+}
+
+/// Deactivate own account
+///
+/// Signs the user out everywhere and disables their login, their events are kept.
+#[utoipa::path(post, path = "/auth/deactivate", tag = "auth", responses((status = 200, description = "Deactivated the account of the user")))]
+async fn post_deactivate_user(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    State(clock): State<SharedClock>,
+    Extension(secrets): Extension<JwtSettings>,
+    jar: CookieJar,
+) -> Result<CookieJar, ApiError<AuthError>> {
+    let mut transaction = pool.begin().await?;
+    if !deactivate_user(&mut transaction, claims.user_id, clock.now()).await? {
+        return Err(AuthError::UserNotFound.into());
+    }
+    transaction.commit().await?;
+
+    debug!("User {} deactivated their account", claims.user_id);
+
+    Ok(jar
+        .remove(get_remove_cookie(Claims::NAME, &secrets.cookie))
+        .remove(get_remove_cookie(RefreshClaims::NAME, &secrets.cookie)))
+}
+
+pub(crate) fn get_remove_cookie<'c>(name: &'c str, settings: &CookieSettings) -> Cookie<'c> {
+    let mut cookie = Cookie::build(name, "")
         .path("/")
         .max_age(Duration::seconds(0))
-        .finish()
+        .finish();
+    if let Some(domain) = &settings.domain {
+        cookie.set_domain(domain.clone());
+    }
+    cookie
 }
 
 /// Refresh access token
@@ -126,8 +296,15 @@ async fn post_refresh_user_token(
     Extension(secrets): Extension<JwtSettings>,
     jar: CookieJar,
     refresh_claims: RefreshClaims,
-) -> Result<CookieJar, AuthError> {
-    let jar = generate_token_cookies(refresh_claims.user_id, &refresh_claims.login, secrets, jar)?;
+) -> Result<CookieJar, ApiError<AuthError>> {
+    let jar = generate_token_cookies(
+        refresh_claims.user_id,
+        &refresh_claims.login,
+        refresh_claims.ver,
+        secrets,
+        jar,
+        state.clock.now(),
+    )?;
 
     refresh_claims.add_token_to_blacklist(&state.pool).await?;
 
@@ -1,86 +1,308 @@
-pub mod models;
+use crate::app_errors::ApiError;
+use crate::modules::negotiation::{Format, Negotiated};
+use crate::modules::presence::Presence;
+use crate::modules::realtime::Realtime;
+use crate::modules::AppState;
 use crate::utils::auth::models::Claims;
-use crate::utils::events::errors::EventError;
-use crate::{modules::AppState, validation::ValidateContent};
+use axum::extract::WebSocketUpgrade;
+use axum::response::{IntoResponse, Response};
 use axum::routing::delete;
 use axum::{
+    body::Bytes,
     extract::{Path, Query, State},
-    routing::{get, patch},
+    routing::{get, patch, post, put},
     Json, Router,
 };
-use http::StatusCode;
-use sqlx::{types::Uuid, PgPool};
+use bimetable_db::modules::clock::SharedClock;
+use bimetable_db::modules::feed_cache::{http_date, FeedCache};
+use bimetable_db::modules::storage::Blobs;
+use bimetable_db::utils::events::digest::{
+    delete_event_digest, get_event_digest, set_event_digest,
+};
+use bimetable_db::utils::events::errors::EventError;
+use bimetable_domain::limits::{OverrideShiftLimit, RepetitionLimit};
+use bimetable_domain::validation::{ValidateContent, ValidateContentError, WarnContent};
+use http::{header, HeaderMap, StatusCode};
+use sqlx::PgPool;
+use time::UtcOffset;
 use tracing::debug;
+use uuid::Uuid;
 
-use crate::routes::events::models::{CreateEventResult, Event, Events, OverrideEvent, UpdateEvent};
-use crate::utils::events::exe::{
-    create_new_event, create_one_event_override, delete_one_event_permanently,
-    delete_one_event_temporally, delete_owner_from_event, delete_user_event, get_many_events,
-    get_one_event, set_event_ownership, update_one_event, update_user_editing_privileges,
+use bimetable_db::utils::events::exe::{
+    create_event_feed_token, create_new_event, create_one_event_override,
+    create_one_occurrence_override, delete_one_event_permanently, delete_one_event_temporally,
+    delete_owner_from_event, delete_user_event, export_event_ics, get_combined_busy,
+    get_event_feed, get_events_page, get_one_event, get_one_event_exceptions,
+    get_one_event_including_deleted, import_classroom_schedule, import_xlsx_timetable,
+    lock_one_occurrence, pause_one_event, preview_classroom_schedule, preview_xlsx_timetable,
+    revoke_event_feed_tokens, set_event_ownership, split_one_event, suggest_free_slots,
+    unlock_one_occurrence, update_event_co_owner, update_many_editing_privileges, update_one_event,
+    update_one_event_override_strategy, update_one_event_recurrence,
+    update_user_editing_privileges,
+};
+use bimetable_db::utils::users::get_username;
+use bimetable_domain::api::events::{
+    CombinedBusy, CreateEventResult, Event, EventExport, EventFeedQuery, EventFeedToken,
+    EventFields, EventsPage, ImportEventsResult, ImportScheduleQuery, ImportTimetableQuery,
+    OverrideEvent, PauseEvent, SplitEvent, SuggestSlot, SuggestedSlots, UpdateEvent,
+    UpdateRecurrence, UpdateRecurrenceResult,
 };
-use crate::utils::events::models::TimeRange;
+use bimetable_domain::events::models::{EntriesPage, TimeRange};
+use bimetable_domain::events::normalize::normalize_anchor;
 
-use self::models::{
-    CreateEvent, GetEventsQuery, NewEventOwner, UpdateEditPrivilege, UpdateEventOwner,
+use bimetable_domain::api::events::{
+    CreateEvent, CreateEventQuery, EventDigest, GetCombinedQuery, GetEventQuery, GetEventsQuery,
+    NewEventOwner, OverrideEventData, UpdateCoOwner, UpdateEditPrivilege, UpdateEditPrivileges,
+    UpdateEventDigest, UpdateEventOwner, UpdateOverrideStrategy,
 };
 
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/", get(get_events).put(create_event))
+        .route("/combined", get(get_combined_events))
+        .route("/suggest-slot", post(suggest_slot))
+        .route("/import/xlsx", post(import_xlsx_events))
+        .route("/import/classroom", post(import_classroom_events))
         .route(
             "/:id",
             get(get_event)
                 .patch(update_event)
                 .delete(delete_event_permanently),
         )
+        .route("/:id/recurrence", patch(update_event_recurrence))
+        .route("/:id/split", patch(split_event))
+        .route("/:id/pause", patch(pause_event))
+        .route("/:id/override-strategy", patch(update_override_strategy))
+        .route("/:id/privileges", patch(update_many_edit_privileges))
+        .route("/:id/co-owners", patch(update_co_owner))
+        .route(
+            "/:id/digest",
+            get(get_digest).put(update_digest).delete(delete_digest),
+        )
+        .route(
+            "/:id/feed",
+            put(create_event_feed).delete(delete_event_feed),
+        )
+        .route("/:id/feed.ics", get(get_event_ics))
+        .route("/:id/export.ics", post(export_event))
         .route("/temp-delete/:id", patch(delete_event_temporarily))
         .route("/override/:id", patch(create_event_override))
+        .route(
+            "/:id/occurrences/:occurrence_id/override",
+            patch(create_occurrence_override),
+        )
+        .route(
+            "/:id/occurrences/:occurrence_id/lock",
+            put(lock_occurrence).delete(unlock_occurrence),
+        )
         .route("/set-edit/:id", patch(update_edit_privileges))
         .route("/set-owner/:id", patch(update_event_owner))
         .route("/leave-event/:id", delete(disconnect_user_from_event))
         .route("/remove-owner/:id", patch(disconnect_owner_from_event))
 }
 
+/// Realtime routes of events, served next to [`router`] unless disabled.
+pub fn presence_router() -> Router<AppState> {
+    Router::new().route("/:id/presence", get(watch_event_presence))
+}
+
 /// Create event
-#[utoipa::path(put, path = "/events", tag = "events", request_body = CreateEvent, responses((status = 200, description = "Created event", body = CreateEventResult)))]
+///
+/// With `normalize=true` the first occurrence of a recurring event is moved onto its rule grid,
+/// a weekly event starting outside of its week map moves to the next day of the week map
+/// and a monthly by day event starting after the 28th moves back to the 28th.
+/// The change is returned as `normalized`.
+#[utoipa::path(put, path = "/events", tag = "events", params(CreateEventQuery), request_body = CreateEvent, responses((status = 200, description = "Created event", body = CreateEventResult)))]
 pub async fn create_event(
     claims: Claims,
     State(pool): State<PgPool>,
-    Json(body): Json<CreateEvent>,
-) -> Result<(StatusCode, Json<CreateEventResult>), EventError> {
+    State(repetition_limit): State<RepetitionLimit>,
+    Query(query): Query<CreateEventQuery>,
+    Json(mut body): Json<CreateEvent>,
+) -> Result<(StatusCode, Json<CreateEventResult>), ApiError<EventError>> {
+    let normalized = if query.normalize {
+        normalize_anchor(&mut body)
+    } else {
+        None
+    };
+    body.validate_repetitions(repetition_limit)?;
     body.validate_content()?;
-    let event_id = create_new_event(&pool, claims.user_id, body).await?;
+    let warnings = body.content_warnings();
+    let event_id = create_new_event(&pool, claims.user_id, body, repetition_limit).await?;
     debug!("Created event: {}", event_id);
 
-    Ok((StatusCode::CREATED, Json(CreateEventResult { event_id })))
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateEventResult {
+            event_id,
+            warnings,
+            normalized,
+        }),
+    ))
+}
+
+/// Import events from an xlsx timetable
+///
+/// The first sheet lists days of the week in its first row and periods like `8:00-8:45`
+/// in its first column. Every filled cell becomes a weekly event, named after the first
+/// line of the cell and described by the remaining ones.
+///
+/// A dry run creates nothing and lists the events instead, so that a large import can be reviewed first.
+#[utoipa::path(post, path = "/events/import/xlsx", tag = "events", params(ImportTimetableQuery), request_body(content = Vec<u8>, content_type = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"), responses((status = 201, description = "Imported events", body = ImportEventsResult), (status = 200, description = "Events of a dry run", body = ImportEventsResult), (status = 423, description = "Another import of the user is running")))]
+async fn import_xlsx_events(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    State(repetition_limit): State<RepetitionLimit>,
+    Query(query): Query<ImportTimetableQuery>,
+    body: Bytes,
+) -> Result<(StatusCode, Json<ImportEventsResult>), ApiError<EventError>> {
+    let range = TimeRange::new(query.starts_at, query.ends_at);
+    if query.dry_run {
+        return Ok((
+            StatusCode::OK,
+            Json(preview_xlsx_timetable(range, &body, repetition_limit)?),
+        ));
+    }
+    let res = import_xlsx_timetable(&pool, claims.user_id, range, &body, repetition_limit).await?;
+    debug!("Imported {} events from a timetable", res.event_ids.len());
+
+    Ok((StatusCode::CREATED, Json(res)))
+}
+
+/// Import events from a Google Classroom or Teams schedule
+///
+/// Accepts the Google Calendar event list of a Classroom course calendar, or the Microsoft Graph
+/// event list of a calendar with Teams class meetings. Every meeting or series becomes an event,
+/// and the mapping tells what became of each item of the export, with the reason when it was left out.
+///
+/// A dry run creates nothing and lists the events instead.
+#[utoipa::path(post, path = "/events/import/classroom", tag = "events", params(ImportScheduleQuery), request_body(content = Vec<u8>, content_type = "application/json"), responses((status = 201, description = "Imported events", body = ImportEventsResult), (status = 200, description = "Events of a dry run", body = ImportEventsResult), (status = 423, description = "Another import of the user is running")))]
+async fn import_classroom_events(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    State(repetition_limit): State<RepetitionLimit>,
+    Query(query): Query<ImportScheduleQuery>,
+    body: Bytes,
+) -> Result<(StatusCode, Json<ImportEventsResult>), ApiError<EventError>> {
+    let offset = query
+        .offset_minutes
+        .map(|minutes| UtcOffset::from_whole_seconds(i32::from(minutes) * 60))
+        .transpose()
+        .map_err(|_| ValidateContentError::field("offsetMinutes", "Offset is out of range"))?;
+    if query.dry_run {
+        return Ok((
+            StatusCode::OK,
+            Json(preview_classroom_schedule(offset, &body, repetition_limit)?),
+        ));
+    }
+    let res =
+        import_classroom_schedule(&pool, claims.user_id, offset, &body, repetition_limit).await?;
+    debug!(
+        "Imported {} events from a class schedule",
+        res.event_ids.len()
+    );
+
+    Ok((StatusCode::CREATED, Json(res)))
+}
+
+/// Get combined busy times of users
+///
+/// Lists when each user is busy along with the merged busy times of all of them,
+/// without any details of their events. Users other than the requester must have shared
+/// their busy times with the requester.
+#[utoipa::path(get, path = "/events/combined", tag = "events", params(GetCombinedQuery), responses((status = 200, body = CombinedBusy, description = "Fetched busy times")))]
+async fn get_combined_events(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    Query(query): Query<GetCombinedQuery>,
+) -> Result<Json<CombinedBusy>, ApiError<EventError>> {
+    query.validate_content()?;
+    let range = TimeRange::new(query.starts_at, query.ends_at);
+    let combined = get_combined_busy(claims.user_id, &query.user_ids, range, &pool).await?;
+
+    Ok(Json(combined))
+}
+
+/// Suggest free slots
+///
+/// Finds the earliest windows when the requester and all participants are free for
+/// at least the given duration. Participants must have shared their busy times with the requester.
+#[utoipa::path(post, path = "/events/suggest-slot", tag = "events", request_body = SuggestSlot, responses((status = 200, body = SuggestedSlots, description = "Suggested free windows")))]
+async fn suggest_slot(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    Json(body): Json<SuggestSlot>,
+) -> Result<Json<SuggestedSlots>, ApiError<EventError>> {
+    body.validate_content()?;
+    let slots = suggest_free_slots(claims.user_id, body, &pool).await?;
+
+    Ok(Json(slots))
 }
 
 /// Get many events
-#[utoipa::path(get, path = "/events", tag = "events", params(GetEventsQuery), responses((status = 200, body = Events, description = "Fetched many events")))]
+///
+/// Every occurrence is listed once. Entries are ordered by `sort`,
+/// ties are broken by event id, start and occurrence id.
+/// With `eventIds` only the listed events and their entries are fetched.
+/// With `fields=name` descriptions of events and overrides are left out, e.g. for grid views.
+///
+/// Served as MessagePack when the client prefers `application/msgpack` in `Accept`.
+#[utoipa::path(get, path = "/events", tag = "events", params(GetEventsQuery), responses((status = 200, body = EventsPage, content_type = ["application/json", "application/msgpack"], description = "Fetched many events")))]
 async fn get_events(
     claims: Claims,
     State(pool): State<PgPool>,
+    format: Format,
     Query(query): Query<GetEventsQuery>,
-) -> Result<Json<Events>, EventError> {
+) -> Result<Negotiated<EventsPage>, ApiError<EventError>> {
     query.validate_content()?;
-    let events = get_many_events(
+    let page = EntriesPage {
+        cursor: query.cursor,
+        limit: query.limit.map(|limit| limit as usize),
+        events_only: query.events_only,
+        effective: query.effective,
+        all_overrides: query.all_overrides,
+        include_deleted: query.include_deleted,
+        event_ids: query.event_ids,
+        names_only: query.fields == EventFields::Name,
+    };
+    let mut events = get_events_page(
         claims.user_id,
         TimeRange::new(query.starts_at, query.ends_at),
         query.filter,
+        query.week_start,
+        page,
         &pool,
     )
     .await?;
-    Ok(Json(events))
+    events.events.sort_entries(query.sort, query.direction);
+    if query.split_by_day {
+        events.events.split_by_day(
+            query.starts_at.offset(),
+            &TimeRange::new(query.starts_at, query.ends_at),
+        );
+    }
+    Ok(format.respond(events))
 }
 
 /// Get event
-#[utoipa::path(get, path = "/events/{id}", tag = "events", responses((status = 200, body = Event)))]
+///
+/// With `exceptions=true` the overrides and pauses of the event are included,
+/// without expanding its entries. Owners find their soft deleted events with `includeDeleted=true`.
+#[utoipa::path(get, path = "/events/{id}", tag = "events", params(GetEventQuery), responses((status = 200, body = Event)))]
 async fn get_event(
     claims: Claims,
     State(pool): State<PgPool>,
     Path(id): Path<Uuid>,
-) -> Result<Json<Event>, EventError> {
-    let event = get_one_event(&pool, claims.user_id, id).await?;
+    Query(query): Query<GetEventQuery>,
+) -> Result<Json<Event>, ApiError<EventError>> {
+    let mut event = if query.include_deleted {
+        get_one_event_including_deleted(&pool, claims.user_id, id).await?
+    } else {
+        get_one_event(&pool, claims.user_id, id).await?
+    };
+    if query.exceptions {
+        event.exceptions = Some(get_one_event_exceptions(&pool, claims.user_id, id).await?);
+    }
 
     Ok(Json(event))
 }
@@ -92,7 +314,7 @@ async fn update_event(
     State(pool): State<PgPool>,
     Path(id): Path<Uuid>,
     Json(body): Json<UpdateEvent>,
-) -> Result<StatusCode, EventError> {
+) -> Result<StatusCode, ApiError<EventError>> {
     body.validate_content()?;
     update_one_event(&pool, claims.user_id, body, id).await?;
     debug!("Updated event: {}", id);
@@ -100,14 +322,260 @@ async fn update_event(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Update event recurrence
+///
+/// Recomputes the recurrence span from the event's time range, a `null` rule removes the recurrence.
+/// Overrides, pauses and locks that no longer apply to any occurrence are deleted.
+#[utoipa::path(patch, path = "/events/{id}/recurrence", tag = "events", request_body = UpdateRecurrence, responses((status = 200, description = "Updated recurrence", body = UpdateRecurrenceResult)))]
+async fn update_event_recurrence(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    State(repetition_limit): State<RepetitionLimit>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<UpdateRecurrence>,
+) -> Result<Json<UpdateRecurrenceResult>, ApiError<EventError>> {
+    body.validate_content()?;
+    let warnings =
+        update_one_event_recurrence(&pool, claims.user_id, body, id, repetition_limit).await?;
+    debug!("Updated recurrence of event: {}", id);
+
+    Ok(Json(UpdateRecurrenceResult { warnings }))
+}
+
+/// Change event time from an occurrence onwards
+///
+/// Past occurrences stay untouched, the following ones are moved to a newly created successor event.
+#[utoipa::path(patch, path = "/events/{id}/split", tag = "events", request_body = SplitEvent, responses((status = 201, description = "Created successor event", body = CreateEventResult), (status = 423, description = "Event is locked by another bulk operation")))]
+async fn split_event(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<SplitEvent>,
+) -> Result<(StatusCode, Json<CreateEventResult>), ApiError<EventError>> {
+    body.validate_content()?;
+    let event_id = split_one_event(&pool, claims.user_id, body, id).await?;
+    debug!("Split event {id} into successor {event_id}");
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateEventResult {
+            event_id,
+            warnings: Vec::new(),
+            normalized: None,
+        }),
+    ))
+}
+
+/// Pause recurring event
+///
+/// No entries starting between the given dates are generated, the event resumes afterwards.
+#[utoipa::path(patch, path = "/events/{id}/pause", tag = "events", request_body = PauseEvent, responses((status = 204, description = "Paused event")))]
+async fn pause_event(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<PauseEvent>,
+) -> Result<StatusCode, ApiError<EventError>> {
+    body.validate_content()?;
+    pause_one_event(&pool, claims.user_id, body, id).await?;
+    debug!("Paused event: {}", id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Update override strategy of event
+///
+/// Decides how overrides covering the same entry are combined.
+#[utoipa::path(patch, path = "/events/{id}/override-strategy", tag = "events", request_body = UpdateOverrideStrategy, responses((status = 204, description = "Updated override strategy")))]
+async fn update_override_strategy(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<UpdateOverrideStrategy>,
+) -> Result<StatusCode, ApiError<EventError>> {
+    update_one_event_override_strategy(&pool, claims.user_id, body, id).await?;
+    debug!("Updated override strategy of event: {}", id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Get digest settings of event
+#[utoipa::path(get, path = "/events/{id}/digest", tag = "events", responses((status = 200, description = "Digest settings", body = EventDigest), (status = 404, description = "Digest is turned off")))]
+async fn get_digest(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<EventDigest>, ApiError<EventError>> {
+    let digest = get_event_digest(&pool, claims.user_id, id).await?;
+
+    Ok(Json(digest))
+}
+
+/// Turn on digest of event
+///
+/// The owner is periodically notified about new participants, changes made by editors and invitation responses.
+#[utoipa::path(put, path = "/events/{id}/digest", tag = "events", request_body = UpdateEventDigest, responses((status = 200, description = "Digest settings", body = EventDigest)))]
+async fn update_digest(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    State(clock): State<SharedClock>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<UpdateEventDigest>,
+) -> Result<Json<EventDigest>, ApiError<EventError>> {
+    let digest = set_event_digest(&pool, claims.user_id, id, body, clock.now()).await?;
+    debug!("Set digest of event {id}");
+
+    Ok(Json(digest))
+}
+
+/// Turn off digest of event
+#[utoipa::path(delete, path = "/events/{id}/digest", tag = "events", responses((status = 204, description = "Turned off digest"), (status = 404, description = "Digest is turned off")))]
+async fn delete_digest(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError<EventError>> {
+    delete_event_digest(&pool, claims.user_id, id).await?;
+    debug!("Deleted digest of event {id}");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Create event feed token
+///
+/// Feeds with the previous tokens of the user for this event stop working.
+#[utoipa::path(put, path = "/events/{id}/feed", tag = "events", responses((status = 200, description = "Token for the event calendar feed", body = EventFeedToken)))]
+async fn create_event_feed(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<EventFeedToken>, ApiError<EventError>> {
+    let token = create_event_feed_token(&pool, claims.user_id, id).await?;
+    debug!("Created feed token for event {id}");
+
+    Ok(Json(EventFeedToken { token }))
+}
+
+/// Revoke event feed tokens
+#[utoipa::path(delete, path = "/events/{id}/feed", tag = "events", responses((status = 204, description = "Revoked feed tokens"), (status = 404, description = "No feed tokens to revoke")))]
+async fn delete_event_feed(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError<EventError>> {
+    revoke_event_feed_tokens(&pool, claims.user_id, id).await?;
+    debug!("Revoked feed tokens for event {id}");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Get event calendar feed
+///
+/// Answers conditional requests by the `ETag` and `Last-Modified` headers of the feed with `304 Not Modified`.
+#[utoipa::path(get, path = "/events/{id}/feed.ics", tag = "events", params(EventFeedQuery), responses((status = 200, description = "iCalendar feed", content_type = "text/calendar"), (status = 304, description = "Feed didn't change since the conditional request")))]
+async fn get_event_ics(
+    State(pool): State<PgPool>,
+    State(clock): State<SharedClock>,
+    State(repetition_limit): State<RepetitionLimit>,
+    State(feeds): State<FeedCache>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<EventFeedQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError<EventError>> {
+    let feed = get_event_feed(
+        &pool,
+        &feeds,
+        query.token,
+        id,
+        clock.now(),
+        repetition_limit,
+    )
+    .await?;
+    let validators = [
+        (header::ETAG, feed.etag.clone()),
+        (header::LAST_MODIFIED, http_date(feed.last_modified)),
+    ];
+    if feed.is_fresh(&headers) {
+        return Ok((StatusCode::NOT_MODIFIED, validators).into_response());
+    }
+
+    Ok((
+        validators,
+        [(header::CONTENT_TYPE, "text/calendar; charset=utf-8")],
+        feed.body,
+    )
+        .into_response())
+}
+
+/// Export event
+///
+/// Stores the event as an iCalendar file, the returned link works for 15 minutes.
+#[utoipa::path(post, path = "/events/{id}/export.ics", tag = "events", responses((status = 200, body = EventExport)))]
+async fn export_event(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    State(storage): State<Blobs>,
+    State(clock): State<SharedClock>,
+    State(feeds): State<FeedCache>,
+    State(repetition_limit): State<RepetitionLimit>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<EventExport>, ApiError<EventError>> {
+    let export = export_event_ics(
+        &pool,
+        &*storage,
+        &feeds,
+        claims.user_id,
+        id,
+        clock.now(),
+        repetition_limit,
+    )
+    .await?;
+    debug!("Exported event {id}");
+
+    Ok(Json(export))
+}
+
+/// Watch event editors
+///
+/// Upgrades to a WebSocket of the users editing the event, see [`Presence::serve`] for its messages.
+#[utoipa::path(get, path = "/events/{id}/presence", tag = "events", responses((status = 101, description = "Switched to the presence WebSocket")))]
+async fn watch_event_presence(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    State(realtime): State<Realtime>,
+    State(presence): State<Presence>,
+    Path(id): Path<Uuid>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, ApiError<EventError>> {
+    let event = get_one_event(&pool, claims.user_id, id).await?;
+    let username = get_username(&pool, claims.user_id)
+        .await
+        .map_err(anyhow::Error::from)?;
+    // Subscribed before the upgrade, not to miss claims made in the meantime
+    let messages = realtime.subscribe();
+    debug!("User {} watches presence of event {id}", claims.user_id);
+
+    Ok(ws.on_upgrade(move |socket| {
+        presence.serve(
+            socket,
+            messages,
+            id,
+            claims.user_id,
+            username,
+            event.can_edit,
+        )
+    }))
+}
+
 /// Delete event temporarily
 #[utoipa::path(patch, path = "/events/{id}", tag = "events")]
 async fn delete_event_temporarily(
     claims: Claims,
     State(pool): State<PgPool>,
+    State(clock): State<SharedClock>,
     Path(id): Path<Uuid>,
-) -> Result<StatusCode, EventError> {
-    delete_one_event_temporally(&pool, claims.user_id, id).await?;
+) -> Result<StatusCode, ApiError<EventError>> {
+    delete_one_event_temporally(&pool, claims.user_id, id, clock.now()).await?;
     debug!("Deleted event temporally: {}", id);
 
     Ok(StatusCode::NO_CONTENT)
@@ -119,7 +587,7 @@ async fn delete_event_permanently(
     claims: Claims,
     State(pool): State<PgPool>,
     Path(id): Path<Uuid>,
-) -> Result<StatusCode, EventError> {
+) -> Result<StatusCode, ApiError<EventError>> {
     delete_one_event_permanently(&pool, claims.user_id, id).await?;
     debug!("Deleted event permanently: {}", id);
 
@@ -131,16 +599,73 @@ async fn delete_event_permanently(
 async fn create_event_override(
     claims: Claims,
     State(pool): State<PgPool>,
+    State(shift_limit): State<OverrideShiftLimit>,
     Path(id): Path<Uuid>,
     Json(body): Json<OverrideEvent>,
-) -> Result<StatusCode, EventError> {
+) -> Result<StatusCode, ApiError<EventError>> {
     body.validate_content()?;
-    create_one_event_override(&pool, claims.user_id, body, id).await?;
+    create_one_event_override(&pool, claims.user_id, body, id, shift_limit).await?;
     debug!("Created override on event: {}", id);
 
     Ok(StatusCode::CREATED)
 }
 
+/// Override a single entry
+///
+/// Finds the entry by the `occurrenceId` of entries, which stays the same when overrides move the entry.
+#[utoipa::path(patch, path = "/events/{id}/occurrences/{occurrence_id}/override", tag = "events", request_body = OverrideEventData, responses((status = 201, description = "Created override")))]
+async fn create_occurrence_override(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    State(shift_limit): State<OverrideShiftLimit>,
+    State(repetition_limit): State<RepetitionLimit>,
+    Path((id, occurrence_id)): Path<(Uuid, Uuid)>,
+    Json(body): Json<OverrideEventData>,
+) -> Result<StatusCode, ApiError<EventError>> {
+    create_one_occurrence_override(
+        &pool,
+        claims.user_id,
+        body,
+        id,
+        occurrence_id,
+        shift_limit,
+        repetition_limit,
+    )
+    .await?;
+    debug!("Created override on occurrence {occurrence_id} of event: {id}");
+
+    Ok(StatusCode::CREATED)
+}
+
+/// Lock a single entry
+///
+/// Editors may no longer override the entry, e.g. an exam date, while owners still may.
+#[utoipa::path(put, path = "/events/{id}/occurrences/{occurrence_id}/lock", tag = "events", responses((status = 204, description = "Locked entry")))]
+async fn lock_occurrence(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    State(repetition_limit): State<RepetitionLimit>,
+    Path((id, occurrence_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, ApiError<EventError>> {
+    lock_one_occurrence(&pool, claims.user_id, id, occurrence_id, repetition_limit).await?;
+    debug!("Locked occurrence {occurrence_id} of event: {id}");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Unlock a single entry
+#[utoipa::path(delete, path = "/events/{id}/occurrences/{occurrence_id}/lock", tag = "events", responses((status = 204, description = "Unlocked entry"), (status = 404, description = "Entry wasn't locked")))]
+async fn unlock_occurrence(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    Path((id, occurrence_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, ApiError<EventError>> {
+    unlock_one_occurrence(&pool, claims.user_id, id, occurrence_id).await?;
+    debug!("Unlocked occurrence {occurrence_id} of event: {id}");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
 /// Update editing privileges
 #[utoipa::path(patch, path = "/events/set-edit/{id}", tag = "event-ownership", request_body = UpdateEditPrivilege)]
 async fn update_edit_privileges(
@@ -148,7 +673,7 @@ async fn update_edit_privileges(
     State(pool): State<PgPool>,
     Path(id): Path<Uuid>,
     Json(body): Json<UpdateEditPrivilege>,
-) -> Result<(), EventError> {
+) -> Result<(), ApiError<EventError>> {
     update_user_editing_privileges(&pool, claims.user_id, body, id).await?;
     debug!(
         "Updated editing privileges for user {} and event {id} to {}",
@@ -158,14 +683,50 @@ async fn update_edit_privileges(
     Ok(())
 }
 
+/// Update editing privileges of many participants
+///
+/// Changes are applied together, none is applied when any participant cannot be changed.
+#[utoipa::path(patch, path = "/events/{id}/privileges", tag = "event-ownership", request_body = UpdateEditPrivileges, responses((status = 204, description = "Updated editing privileges"), (status = 423, description = "Event is locked by another bulk operation")))]
+async fn update_many_edit_privileges(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<UpdateEditPrivileges>,
+) -> Result<StatusCode, ApiError<EventError>> {
+    let changed = body.changes.len();
+    update_many_editing_privileges(&pool, claims.user_id, body, id).await?;
+    debug!("Updated editing privileges of {changed} participants of event {id}");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Update co-ownership of a participant
+///
+/// Co-owners can delete the event and manage its participants, the owner alone can hand the event over.
+#[utoipa::path(patch, path = "/events/{id}/co-owners", tag = "event-ownership", request_body = UpdateCoOwner, responses((status = 204, description = "Updated co-ownership")))]
+async fn update_co_owner(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<UpdateCoOwner>,
+) -> Result<StatusCode, ApiError<EventError>> {
+    update_event_co_owner(&pool, claims.user_id, body, id).await?;
+    debug!(
+        "Updated co-ownership for user {} and event {id} to {}",
+        body.user_id, body.is_owner
+    );
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
 /// Update event owner
-#[utoipa::path(patch, path = "/events/set-owner/{id}", tag = "event-ownership", request_body = UpdateEventOwner)]
+#[utoipa::path(patch, path = "/events/set-owner/{id}", tag = "event-ownership", request_body = UpdateEventOwner, responses((status = 200, description = "Updated event owner"), (status = 423, description = "Event is locked by another bulk operation")))]
 async fn update_event_owner(
     claims: Claims,
     State(pool): State<PgPool>,
     Path(id): Path<Uuid>,
     Json(body): Json<UpdateEventOwner>,
-) -> Result<(), EventError> {
+) -> Result<(), ApiError<EventError>> {
     set_event_ownership(&pool, claims.user_id, body.user_id, id).await?;
     debug!("Updated owner of event {id} to {}", body.user_id);
 
@@ -178,7 +739,7 @@ async fn disconnect_user_from_event(
     claims: Claims,
     State(pool): State<PgPool>,
     Path(id): Path<Uuid>,
-) -> Result<(), EventError> {
+) -> Result<(), ApiError<EventError>> {
     delete_user_event(&pool, claims.user_id, id).await?;
     debug!(
         "User {} has been disconnected from the event {id}",
@@ -195,7 +756,7 @@ async fn disconnect_owner_from_event(
     State(pool): State<PgPool>,
     Path(id): Path<Uuid>,
     Json(body): Json<NewEventOwner>,
-) -> Result<(), EventError> {
+) -> Result<(), ApiError<EventError>> {
     delete_owner_from_event(&pool, claims.user_id, id, body.user_id).await?;
     debug!(
         "Event owner {} left the event {id}, making {} the new owner",
@@ -1,9 +1,9 @@
-use crate::modules::database::PgPool;
 use crate::modules::AppState;
 use axum::extract::State;
 use axum::response::{Html, IntoResponse};
 use axum::routing::get;
 use axum::Router;
+use bimetable_db::modules::database::PgPool;
 use sqlx::query;
 
 /// [Stateful routers](https://docs.rs/axum/latest/axum/extract/struct.State.html#combining-stateful-routers)
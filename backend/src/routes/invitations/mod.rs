@@ -1,48 +1,61 @@
-pub mod models;
+use crate::app_errors::ApiError;
 use axum::{
     debug_handler,
     extract::{Path, State},
     routing::{get, patch, put},
-    Json, Router,
+    Extension, Json, Router,
 };
 use sqlx::PgPool;
 use tracing::debug;
 use uuid::Uuid;
 
-use crate::routes::invitations::models::{
-    CreateDirectInvitation, DirectInvitation, RespondDirectInvitation,
+use crate::modules::AppState;
+use crate::utils::auth::models::Claims;
+use bimetable_db::config::app::{InvitationCap, WebsiteOrigin};
+use bimetable_db::config::tokens::JwtSettings;
+use bimetable_db::utils::invitations::email::create_email_invitation;
+use bimetable_db::utils::invitations::errors::InvitationError;
+use bimetable_db::utils::invitations::{
+    count_direct_invitations, create_direct_invitation, get_all_direct_invitations,
+    get_default_can_edit, mark_direct_invitations_seen, respond_to_direct_invitation,
 };
-use crate::utils::invitations::{
-    create_direct_invitation, get_all_direct_invitations, respond_to_direct_invitation,
-};
-use crate::{
-    modules::AppState,
-    utils::{auth::models::Claims, invitations::errors::InvitationError},
+use bimetable_domain::api::invitations::{
+    CreateDirectInvitation, CreateEmailInvitation, DirectInvitation, EmailInvitation,
+    InvitationCount, ReceivedInvitation, RespondDirectInvitation,
 };
 
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/create", put(create_direct))
+        .route("/email", put(create_email))
         .route("/fetch", get(fetch_direct))
+        .route("/count", get(count_direct))
+        .route("/seen", patch(mark_seen_direct))
         .route("/respond/:id", patch(respond_direct))
 }
 
 /// Create user event invitation
-#[debug_handler]
+#[debug_handler(state = AppState)]
 #[utoipa::path(put, path = "/events/invitations/create", tag = "invitations", request_body = CreateDirectInvitation, responses((status = 200, description = "Created event invitation")))]
 async fn create_direct(
     claims: Claims,
     State(pool): State<PgPool>,
+    State(cap): State<InvitationCap>,
     Json(invitation): Json<CreateDirectInvitation>,
-) -> Result<(), InvitationError> {
+) -> Result<(), ApiError<InvitationError>> {
+    let can_edit = match invitation.can_edit {
+        Some(can_edit) => can_edit,
+        None => get_default_can_edit(&pool, &claims.user_id).await?,
+    };
     create_direct_invitation(
         &pool,
         DirectInvitation {
             event_id: invitation.event_id,
             sender_id: claims.user_id,
             receiver_id: invitation.receiver_id,
-            can_edit: invitation.can_edit,
+            can_edit,
         },
+        cap,
     )
     .await?;
     debug!(
@@ -52,13 +65,51 @@ async fn create_direct(
     Ok(())
 }
 
+/// Invite by email
+///
+/// Users registered with the email get a direct invitation, others get a sign-up link
+/// and join the event when they register with the email.
+#[debug_handler(state = AppState)]
+#[utoipa::path(put, path = "/events/invitations/email", tag = "invitations", request_body = CreateEmailInvitation, responses((status = 200, description = "Created event invitation")))]
+async fn create_email(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    State(cap): State<InvitationCap>,
+    State(origin): State<WebsiteOrigin>,
+    Extension(secrets): Extension<JwtSettings>,
+    Json(invitation): Json<CreateEmailInvitation>,
+) -> Result<(), ApiError<InvitationError>> {
+    let can_edit = match invitation.can_edit {
+        Some(can_edit) => can_edit,
+        None => get_default_can_edit(&pool, &claims.user_id).await?,
+    };
+    create_email_invitation(
+        &pool,
+        EmailInvitation {
+            event_id: invitation.event_id,
+            sender_id: claims.user_id,
+            email: invitation.email,
+            can_edit,
+        },
+        cap,
+        &secrets.invitation,
+        &origin,
+    )
+    .await?;
+    debug!(
+        "Created email invitation from user: {} to event: {}",
+        claims.user_id, invitation.event_id
+    );
+    Ok(())
+}
+
 /// Fetch all invitations
-#[debug_handler]
-#[utoipa::path(get, path = "/events/invitations/fetch", tag = "invitations", responses((status = 200, body = [DirectInvitation], description = "Fetched event invitations")))]
+#[debug_handler(state = AppState)]
+#[utoipa::path(get, path = "/events/invitations/fetch", tag = "invitations", responses((status = 200, body = [ReceivedInvitation], description = "Fetched event invitations")))]
 async fn fetch_direct(
     claims: Claims,
     State(pool): State<PgPool>,
-) -> Result<Json<Vec<DirectInvitation>>, InvitationError> {
+) -> Result<Json<Vec<ReceivedInvitation>>, ApiError<InvitationError>> {
     let invitations = get_all_direct_invitations(&pool, &claims.user_id).await?;
     debug!(
         "Fetched {} event(s) for user: {}",
@@ -68,15 +119,41 @@ async fn fetch_direct(
     Ok(Json(invitations))
 }
 
+/// Count pending invitations
+#[debug_handler(state = AppState)]
+#[utoipa::path(get, path = "/events/invitations/count", tag = "invitations", responses((status = 200, body = InvitationCount, description = "Counted event invitations")))]
+async fn count_direct(
+    claims: Claims,
+    State(pool): State<PgPool>,
+) -> Result<Json<InvitationCount>, ApiError<InvitationError>> {
+    let count = count_direct_invitations(&pool, &claims.user_id).await?;
+    Ok(Json(count))
+}
+
+/// Mark invitations as seen
+#[debug_handler(state = AppState)]
+#[utoipa::path(patch, path = "/events/invitations/seen", tag = "invitations", responses((status = 200, description = "Marked event invitations as seen")))]
+async fn mark_seen_direct(
+    claims: Claims,
+    State(pool): State<PgPool>,
+) -> Result<(), ApiError<InvitationError>> {
+    let affected = mark_direct_invitations_seen(&pool, &claims.user_id).await?;
+    debug!(
+        "Marked {affected} invitation(s) as seen for user: {}",
+        claims.user_id
+    );
+    Ok(())
+}
+
 /// Respond to direct invitation
-#[debug_handler]
+#[debug_handler(state = AppState)]
 #[utoipa::path(patch, path = "/events/invitations/respond/{id}", tag = "invitations", request_body = RespondDirectInvitation, responses((status = 200, description = "Responded to direct event invitation")))]
 async fn respond_direct(
     claims: Claims,
     State(pool): State<PgPool>,
     Path(id): Path<Uuid>,
     Json(response): Json<RespondDirectInvitation>,
-) -> Result<(), InvitationError> {
+) -> Result<(), ApiError<InvitationError>> {
     respond_to_direct_invitation(&pool, response).await?;
     debug!(
         "User: {} responded ({}) invitation for event: {}",
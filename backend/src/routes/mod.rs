@@ -1,5 +1,9 @@
+pub mod admin;
 pub mod auth;
 pub mod events;
 pub mod example;
 pub mod invitations;
+pub mod reminders;
+pub mod scim;
 pub mod search;
+pub mod users;
@@ -0,0 +1,59 @@
+use crate::app_errors::ApiError;
+
+use crate::modules::AppState;
+use crate::utils::auth::models::Claims;
+use axum::extract::{Path, State};
+use axum::routing::{delete, get};
+use axum::{Json, Router};
+use bimetable_db::modules::clock::SharedClock;
+use bimetable_db::utils::reminders::errors::ReminderError;
+use bimetable_db::utils::reminders::{create_reminder, delete_reminder, get_user_reminders};
+use bimetable_domain::api::reminders::{CreateReminder, CreateReminderResult, Reminder};
+use http::StatusCode;
+use sqlx::PgPool;
+use tracing::debug;
+use uuid::Uuid;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(get_reminders).put(put_reminder))
+        .route("/:id", delete(remove_reminder))
+}
+
+/// Get user's reminders
+#[utoipa::path(get, path = "/reminders", tag = "reminders", responses((status = 200, body = [Reminder])))]
+async fn get_reminders(
+    claims: Claims,
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<Reminder>>, ApiError<ReminderError>> {
+    Ok(Json(get_user_reminders(&pool, claims.user_id).await?))
+}
+
+/// Create reminder
+#[utoipa::path(put, path = "/reminders", tag = "reminders", request_body = CreateReminder, responses((status = 201, body = CreateReminderResult)))]
+async fn put_reminder(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    State(clock): State<SharedClock>,
+    Json(body): Json<CreateReminder>,
+) -> Result<(StatusCode, Json<CreateReminderResult>), ApiError<ReminderError>> {
+    let reminder_id = create_reminder(&pool, claims.user_id, body, clock.now()).await?;
+    debug!("Created reminder: {reminder_id}");
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateReminderResult { reminder_id }),
+    ))
+}
+
+/// Delete reminder
+#[utoipa::path(delete, path = "/reminders/{id}", tag = "reminders")]
+async fn remove_reminder(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError<ReminderError>> {
+    delete_reminder(&pool, claims.user_id, id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
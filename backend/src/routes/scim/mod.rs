@@ -0,0 +1,107 @@
+use crate::app_errors::ApiError;
+
+use crate::modules::AppState;
+use crate::utils::scim::{Scim, ScimClient};
+use axum::extract::{Path, Query, State};
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use bimetable_db::config::passwords::PasswordSettings;
+use bimetable_db::config::usernames::UsernameSettings;
+use bimetable_db::modules::clock::SharedClock;
+use bimetable_db::utils::scim::errors::ScimError;
+use bimetable_db::utils::scim::{
+    create_scim_user, get_scim_user, list_scim_users, update_scim_user, UserChanges,
+};
+use bimetable_domain::api::scim::{
+    ScimListQuery, ScimListResponse, ScimPatch, ScimUser, ScimUserRequest,
+};
+use http::StatusCode;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Provisioning API of the SCIM 2.0 core user schema, authorized with the SCIM token.
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/Users", get(scim_list_users).post(scim_create_user))
+        .route(
+            "/Users/:id",
+            get(scim_get_user)
+                .put(scim_replace_user)
+                .patch(scim_patch_user),
+        )
+}
+
+/// List provisioned users
+#[utoipa::path(get, path = "/scim/v2/Users", tag = "scim", params(ScimListQuery), responses((status = 200, body = ScimListResponse, content_type = "application/scim+json")))]
+async fn scim_list_users(
+    _client: ScimClient,
+    State(pool): State<PgPool>,
+    Query(query): Query<ScimListQuery>,
+) -> Result<Scim<ScimListResponse>, ApiError<ScimError>> {
+    Ok(Scim(list_scim_users(&pool, query).await?))
+}
+
+/// Provision user
+#[utoipa::path(post, path = "/scim/v2/Users", tag = "scim", request_body = ScimUserRequest, responses((status = 201, body = ScimUser, content_type = "application/scim+json"), (status = 409, description = "User name or external id is already taken")))]
+async fn scim_create_user(
+    _client: ScimClient,
+    State(pool): State<PgPool>,
+    State(clock): State<SharedClock>,
+    Extension(passwords): Extension<PasswordSettings>,
+    Extension(usernames): Extension<UsernameSettings>,
+    Json(body): Json<ScimUserRequest>,
+) -> Result<(StatusCode, Scim<ScimUser>), ApiError<ScimError>> {
+    let user = create_scim_user(&pool, body, &passwords, &usernames, clock.now()).await?;
+
+    Ok((StatusCode::CREATED, Scim(user)))
+}
+
+/// Get provisioned user
+#[utoipa::path(get, path = "/scim/v2/Users/{id}", tag = "scim", responses((status = 200, body = ScimUser, content_type = "application/scim+json")))]
+async fn scim_get_user(
+    _client: ScimClient,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Scim<ScimUser>, ApiError<ScimError>> {
+    Ok(Scim(get_scim_user(&pool, id).await?))
+}
+
+/// Replace provisioned user
+///
+/// Attributes missing from the body keep their values.
+#[utoipa::path(put, path = "/scim/v2/Users/{id}", tag = "scim", request_body = ScimUserRequest, responses((status = 200, body = ScimUser, content_type = "application/scim+json")))]
+async fn scim_replace_user(
+    _client: ScimClient,
+    State(pool): State<PgPool>,
+    State(clock): State<SharedClock>,
+    Extension(passwords): Extension<PasswordSettings>,
+    Extension(usernames): Extension<UsernameSettings>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<ScimUserRequest>,
+) -> Result<Scim<ScimUser>, ApiError<ScimError>> {
+    let changes = UserChanges::from(body);
+
+    Ok(Scim(
+        update_scim_user(&pool, id, changes, &passwords, &usernames, clock.now()).await?,
+    ))
+}
+
+/// Patch provisioned user
+///
+/// Setting `active` to false deactivates the user.
+#[utoipa::path(patch, path = "/scim/v2/Users/{id}", tag = "scim", request_body = ScimPatch, responses((status = 200, body = ScimUser, content_type = "application/scim+json")))]
+async fn scim_patch_user(
+    _client: ScimClient,
+    State(pool): State<PgPool>,
+    State(clock): State<SharedClock>,
+    Extension(passwords): Extension<PasswordSettings>,
+    Extension(usernames): Extension<UsernameSettings>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<ScimPatch>,
+) -> Result<Scim<ScimUser>, ApiError<ScimError>> {
+    let changes = UserChanges::try_from(body)?;
+
+    Ok(Scim(
+        update_scim_user(&pool, id, changes, &passwords, &usernames, clock.now()).await?,
+    ))
+}
@@ -1,14 +1,20 @@
-pub mod models;
+use crate::app_errors::ApiError;
 
+use crate::modules::negotiation::{Format, Negotiated};
+use crate::modules::search_cache::SearchCache;
 use crate::modules::AppState;
-use crate::routes::events::models::Event;
-use crate::routes::search::models::{SearchEvents, SearchUsers, SearchUsersResult};
 use crate::utils::auth::models::Claims;
-use crate::utils::search::errors::SearchError;
-use crate::utils::search::{get_users, search_many_events};
 use axum::extract::{Query, State};
 use axum::routing::get;
-use axum::{Json, Router};
+use axum::Router;
+use bimetable_db::utils::search::errors::SearchError;
+use bimetable_db::utils::search::{get_users, search_many_events};
+use bimetable_domain::api::events::Event;
+use bimetable_domain::api::search::{
+    SearchEvents, SearchEventsResult, SearchUsers, SearchUsersResult,
+};
+use http::header::HeaderName;
+use http::HeaderValue;
 use sqlx::PgPool;
 use tracing::debug;
 
@@ -19,13 +25,30 @@ pub fn router() -> Router<AppState> {
 }
 
 /// Search users
-#[utoipa::path(get, path = "/search/users", tag = "search", params(SearchUsers), responses((status = 200, description = "Received users", body = SearchUsersResult)))]
+///
+/// With `eventId`, users who already take part in the event are left out, along with the searching user.
+/// Results are reused for a few seconds, unless something changes in the meantime.
+/// Browsers may reuse them as long, `Cache-Control` also lets them show stale results while revalidating.
+/// Served as MessagePack when the client prefers `application/msgpack` in `Accept`.
+#[utoipa::path(get, path = "/search/users", tag = "search", params(SearchUsers), responses((status = 200, description = "Received users", body = SearchUsersResult, content_type = ["application/json", "application/msgpack"])))]
 pub async fn search_users(
-    _claims: Claims,
+    claims: Claims,
     State(pool): State<PgPool>,
+    State(cache): State<SearchCache>,
+    format: Format,
     Query(q): Query<SearchUsers>,
-) -> Result<Json<Vec<SearchUsersResult>>, SearchError> {
-    let search_res: Vec<SearchUsersResult> = get_users(&pool, q)
+) -> Result<
+    (
+        [(HeaderName, HeaderValue); 1],
+        Negotiated<Vec<SearchUsersResult>>,
+    ),
+    ApiError<SearchError>,
+> {
+    if let Some(search_res) = cache.users.get(claims.user_id, &q) {
+        return Ok((cache.cache_control(), format.respond(search_res)));
+    }
+
+    let search_res: Vec<SearchUsersResult> = get_users(&pool, claims.user_id, q.clone())
         .await?
         .into_iter()
         .map(|x| SearchUsersResult::from(x))
@@ -37,27 +60,51 @@ pub async fn search_users(
         debug!("Found {} user(s) with user search", search_res.len());
     }
 
-    Ok(Json(search_res))
+    cache.users.insert(claims.user_id, &q, search_res.clone());
+    Ok((cache.cache_control(), format.respond(search_res)))
 }
 
 /// Search events
-#[utoipa::path(get, path = "/search/events", tag = "search", params(SearchEvents), responses((status = 200, description = "Received events", body = [Event])))]
+///
+/// Results are reused for a few seconds, unless something changes in the meantime.
+/// Browsers may reuse them as long, `Cache-Control` also lets them show stale results while revalidating.
+/// Served as MessagePack when the client prefers `application/msgpack` in `Accept`.
+#[utoipa::path(get, path = "/search/events", tag = "search", params(SearchEvents), responses((status = 200, description = "Received events", body = SearchEventsResult, content_type = ["application/json", "application/msgpack"])))]
 pub async fn search_events(
-    _claims: Claims,
+    claims: Claims,
     State(pool): State<PgPool>,
+    State(cache): State<SearchCache>,
+    format: Format,
     Query(search): Query<SearchEvents>,
-) -> Result<Json<Vec<Event>>, SearchError> {
-    let search_res: Vec<Event> = search_many_events(&pool, search)
-        .await?
-        .into_iter()
-        .map(|x| Event::from(x))
-        .collect();
+) -> Result<
+    (
+        [(HeaderName, HeaderValue); 1],
+        Negotiated<SearchEventsResult>,
+    ),
+    ApiError<SearchError>,
+> {
+    if let Some(res) = cache.events.get(claims.user_id, &search) {
+        return Ok((cache.cache_control(), format.respond(res)));
+    }
 
-    if search_res.is_empty() {
+    let page = search_many_events(&pool, claims.user_id, search.clone()).await?;
+    let events: Vec<Event> = page.events.into_iter().map(Event::from).collect();
+
+    if events.is_empty() {
         debug!("Found no events with event search",);
     } else {
-        debug!("Found {} events with event search", search_res.len());
+        debug!(
+            "Found {} of {} events with event search",
+            events.len(),
+            page.total
+        );
     }
 
-    Ok(Json(search_res))
+    let res = SearchEventsResult {
+        events,
+        total: page.total,
+        facets: page.facets,
+    };
+    cache.events.insert(claims.user_id, &search, res.clone());
+    Ok((cache.cache_control(), format.respond(res)))
 }
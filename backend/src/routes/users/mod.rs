@@ -0,0 +1,215 @@
+use crate::app_errors::ApiError;
+
+use crate::modules::search_cache::SearchCache;
+use crate::modules::AppState;
+use crate::routes::auth::get_remove_cookie;
+use crate::utils::auth::models::{AuthToken, Claims, RefreshClaims};
+use axum::extract::{Path, State};
+use axum::routing::{delete, get, post, put};
+use axum::{Extension, Json, Router};
+use axum_extra::extract::CookieJar;
+use bimetable_db::config::tokens::JwtSettings;
+use bimetable_db::modules::storage::Blobs;
+use bimetable_db::utils::users::archive::{get_archive, request_archive};
+use bimetable_db::utils::users::errors::UserError;
+use bimetable_db::utils::users::quiet_hours::{
+    create_quiet_hours, delete_quiet_hours, get_user_quiet_hours,
+};
+use bimetable_db::utils::users::{
+    delete_user_account, get_user_handles, get_user_preferences, grant_busy_visibility,
+    revoke_busy_visibility, update_user_preferences,
+};
+use bimetable_domain::api::users::{
+    CreateQuietHours, QuietHours, UpdateUserPreferences, UserArchive, UserHandle, UserPreferences,
+};
+use http::header::HeaderName;
+use http::{HeaderValue, StatusCode};
+use sqlx::PgPool;
+use tracing::debug;
+use uuid::Uuid;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/preferences",
+            get(get_preferences).patch(update_preferences),
+        )
+        .route(
+            "/preferences/quiet-hours",
+            get(get_quiet_hours).put(put_quiet_hours),
+        )
+        .route("/preferences/quiet-hours/:id", delete(remove_quiet_hours))
+        .route(
+            "/visibility/:id",
+            put(grant_visibility).delete(revoke_visibility),
+        )
+        .route("/:id/handles", get(get_handles))
+        .route("/me", delete(delete_account))
+        .route("/me/export", post(export_archive))
+        .route("/me/export/:id", get(get_export))
+}
+
+/// Get user preferences
+#[utoipa::path(get, path = "/users/preferences", tag = "users", responses((status = 200, description = "Fetched user preferences", body = UserPreferences)))]
+async fn get_preferences(
+    claims: Claims,
+    State(pool): State<PgPool>,
+) -> Result<Json<UserPreferences>, ApiError<UserError>> {
+    let preferences = get_user_preferences(&pool, claims.user_id).await?;
+
+    Ok(Json(preferences))
+}
+
+/// Update user preferences
+#[utoipa::path(patch, path = "/users/preferences", tag = "users", request_body = UpdateUserPreferences, responses((status = 200, description = "Updated user preferences", body = UserPreferences)))]
+async fn update_preferences(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    Json(body): Json<UpdateUserPreferences>,
+) -> Result<Json<UserPreferences>, ApiError<UserError>> {
+    let preferences = update_user_preferences(&pool, claims.user_id, body).await?;
+    debug!("Updated preferences of the user {}", claims.user_id);
+
+    Ok(Json(preferences))
+}
+
+/// Get quiet hours
+#[utoipa::path(get, path = "/users/preferences/quiet-hours", tag = "users", responses((status = 200, description = "Fetched quiet hours", body = [QuietHours])))]
+async fn get_quiet_hours(
+    claims: Claims,
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<QuietHours>>, ApiError<UserError>> {
+    Ok(Json(get_user_quiet_hours(&pool, claims.user_id).await?))
+}
+
+/// Add quiet hours
+///
+/// Reminders due during quiet hours are deferred until they end or suppressed, unless they are critical.
+#[utoipa::path(put, path = "/users/preferences/quiet-hours", tag = "users", request_body = CreateQuietHours, responses((status = 201, description = "Added quiet hours", body = QuietHours)))]
+async fn put_quiet_hours(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    Json(body): Json<CreateQuietHours>,
+) -> Result<(StatusCode, Json<QuietHours>), ApiError<UserError>> {
+    let quiet_hours = create_quiet_hours(&pool, claims.user_id, body).await?;
+    debug!(
+        "User {} added quiet hours {}",
+        claims.user_id, quiet_hours.id
+    );
+
+    Ok((StatusCode::CREATED, Json(quiet_hours)))
+}
+
+/// Remove quiet hours
+#[utoipa::path(delete, path = "/users/preferences/quiet-hours/{id}", tag = "users", responses((status = 204, description = "Removed quiet hours")))]
+async fn remove_quiet_hours(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError<UserError>> {
+    delete_quiet_hours(&pool, claims.user_id, id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Get handles of a user
+///
+/// Lists the current `username#tag` of the user followed by the former ones, newest first.
+/// Former handles are never given to other users, so mentions of them can be resolved after renames.
+/// Browsers may reuse them for as long as search results, see `Cache-Control`.
+#[utoipa::path(get, path = "/users/{id}/handles", tag = "users", responses((status = 200, description = "Current and former handles", body = [UserHandle]), (status = 404, description = "User does not exist")))]
+async fn get_handles(
+    _claims: Claims,
+    State(pool): State<PgPool>,
+    State(cache): State<SearchCache>,
+    Path(id): Path<Uuid>,
+) -> Result<([(HeaderName, HeaderValue); 1], Json<Vec<UserHandle>>), ApiError<UserError>> {
+    let handles = get_user_handles(&pool, id).await?;
+
+    Ok((cache.cache_control(), Json(handles)))
+}
+
+/// Share busy times with a user
+///
+/// The user may see when you are busy in combined calendars, without any details of your events.
+#[utoipa::path(put, path = "/users/visibility/{id}", tag = "users", responses((status = 204, description = "Shared busy times")))]
+async fn grant_visibility(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError<UserError>> {
+    grant_busy_visibility(&pool, claims.user_id, id).await?;
+    debug!("User {} shared busy times with {id}", claims.user_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Stop sharing busy times with a user
+#[utoipa::path(delete, path = "/users/visibility/{id}", tag = "users", responses((status = 204, description = "Stopped sharing busy times")))]
+async fn revoke_visibility(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError<UserError>> {
+    revoke_busy_visibility(&pool, claims.user_id, id).await?;
+    debug!(
+        "User {} stopped sharing busy times with {id}",
+        claims.user_id
+    );
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Export own data
+///
+/// Builds a zip of all data of the user in the background, poll the returned archive until it's ready.
+#[utoipa::path(post, path = "/users/me/export", tag = "users", responses((status = 202, description = "Requested the archive", body = UserArchive)))]
+async fn export_archive(
+    claims: Claims,
+    State(pool): State<PgPool>,
+) -> Result<(StatusCode, Json<UserArchive>), ApiError<UserError>> {
+    let archive = request_archive(&pool, claims.user_id).await?;
+    debug!(
+        "User {} requested the archive {}",
+        claims.user_id, archive.id
+    );
+
+    Ok((StatusCode::ACCEPTED, Json(archive)))
+}
+
+/// Get own data export
+///
+/// A ready archive comes with a download link, which works for 15 minutes.
+#[utoipa::path(get, path = "/users/me/export/{id}", tag = "users", responses((status = 200, body = UserArchive)))]
+async fn get_export(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    State(storage): State<Blobs>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<UserArchive>, ApiError<UserError>> {
+    let archive = get_archive(&pool, &*storage, claims.user_id, id).await?;
+
+    Ok(Json(archive))
+}
+
+/// Delete own account
+///
+/// Erases the user with all of their data and signs them out.
+/// Owned events are handed over to a co-owner when there is one, otherwise they are deleted for all participants.
+#[utoipa::path(delete, path = "/users/me", tag = "users", responses((status = 204, description = "Erased the user")))]
+async fn delete_account(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    State(storage): State<Blobs>,
+    Extension(secrets): Extension<JwtSettings>,
+    jar: CookieJar,
+) -> Result<(StatusCode, CookieJar), ApiError<UserError>> {
+    delete_user_account(&pool, &*storage, claims.user_id).await?;
+    debug!("User {} erased their account", claims.user_id);
+
+    Ok((
+        StatusCode::NO_CONTENT,
+        jar.remove(get_remove_cookie(Claims::NAME, &secrets.cookie))
+            .remove(get_remove_cookie(RefreshClaims::NAME, &secrets.cookie)),
+    ))
+}
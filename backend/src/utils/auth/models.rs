@@ -1,23 +1,30 @@
-use crate::utils::auth::errors::*;
-use crate::utils::auth::additions::is_ascii_or_latin_extended;
+use crate::app_errors::ApiError;
 use anyhow::Context;
-use axum::{async_trait, extract::FromRequestParts, RequestPartsExt};
-use axum_extra::extract::{
-    cookie::{Cookie, SameSite},
-    CookieJar,
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    RequestPartsExt,
 };
+use axum_extra::extract::{cookie::Cookie, CookieJar};
+use bimetable_db::utils::auth::errors::*;
+use bimetable_db::utils::auth::{blacklist_token, get_token_version};
+use http::header::AUTHORIZATION;
 use http::request::Parts;
+use http::HeaderMap;
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, TokenData, Validation};
 use secrecy::{ExposeSecret, Secret};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use sqlx::{query, PgPool};
+use sqlx::PgPool;
 use time::{Duration, OffsetDateTime};
 use tracing::trace;
 
-use crate::config::tokens::JwtSettings;
+use bimetable_db::config::tokens::{CookieSettings, JwtSettings};
+use bimetable_db::modules::clock::SharedClock;
 use uuid::Uuid;
-use validator::Validate;
+
+/// Seconds a token outlives its expiry, matching the default leeway of `jsonwebtoken`
+const EXPIRY_LEEWAY: u64 = 60;
 
 #[async_trait]
 pub trait AuthToken<'s>
@@ -28,14 +35,22 @@ where
 
     fn jti(&self) -> Uuid;
     fn exp(&self) -> u64;
-    fn generate_cookie(token: String) -> Cookie<'s> {
+    fn user_id(&self) -> Uuid;
+    /// Token version of the user at the time of issuing the token
+    fn ver(&self) -> i32;
+    fn generate_cookie(token: String, settings: &CookieSettings, max_age: Duration) -> Cookie<'s> {
         trace!("Generating cookie with token");
-        Cookie::build(Self::NAME, token)
+        let mut cookie = Cookie::build(Self::NAME, token)
             .http_only(true)
-            .secure(true)
-            .same_site(SameSite::Strict)
+            .secure(settings.secure)
+            .same_site(settings.same_site)
             .path("/")
-            .finish()
+            .max_age(max_age)
+            .finish();
+        if let Some(domain) = &settings.domain {
+            cookie.set_domain(domain.clone());
+        }
+        cookie
     }
     fn generate_jwt(&self, key: &Secret<String>) -> Result<String, AuthError> {
         trace!("Generating JWT token");
@@ -57,8 +72,16 @@ where
         secret: Secret<String>,
     ) -> Result<Option<TokenData<Self>>, AuthError> {
         let token = Self::get_jwt_cookie(jar)?;
+        Self::decode_token(token.value(), validation, secret)
+    }
+
+    fn decode_token(
+        token: &str,
+        validation: Option<&Validation>,
+        secret: Secret<String>,
+    ) -> Result<Option<TokenData<Self>>, AuthError> {
         Ok(decode::<Self>(
-            token.value(),
+            token,
             &DecodingKey::from_secret(secret.expose_secret().as_bytes()),
             validation.unwrap_or(&Validation::default()),
         )
@@ -70,19 +93,7 @@ where
             .context("Failed to convert timestamp to date and time with the timezone")
             .map_err(AuthError::Unexpected)?;
 
-        let _res = query!(
-            r#"
-                insert into jwt_blacklist (token_id, expiry)
-                values ($1, $2)
-            "#,
-            self.jti(),
-            exp,
-        )
-        .execute(pool)
-        .await?;
-
-        trace!("Adding token to blacklist");
-        Ok(())
+        blacklist_token(pool, self.jti(), exp).await
     }
 }
 
@@ -95,6 +106,12 @@ impl<'s> AuthToken<'s> for Claims {
     fn exp(&self) -> u64 {
         self.exp
     }
+    fn user_id(&self) -> Uuid {
+        self.user_id
+    }
+    fn ver(&self) -> i32 {
+        self.ver
+    }
 }
 
 impl<'s> AuthToken<'s> for RefreshClaims {
@@ -106,6 +123,12 @@ impl<'s> AuthToken<'s> for RefreshClaims {
     fn exp(&self) -> u64 {
         self.exp
     }
+    fn user_id(&self) -> Uuid {
+        self.user_id
+    }
+    fn ver(&self) -> i32 {
+        self.ver
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -114,15 +137,24 @@ pub struct Claims {
     pub user_id: Uuid,
     pub login: String,
     pub exp: u64,
+    #[serde(default)]
+    pub ver: i32,
 }
 
 impl Claims {
-    pub fn new(user_id: Uuid, login: &str, duration: Duration) -> Self {
+    pub fn new(
+        user_id: Uuid,
+        login: &str,
+        ver: i32,
+        duration: Duration,
+        now: OffsetDateTime,
+    ) -> Self {
         Self {
             jti: Uuid::new_v4(),
             user_id,
             login: login.to_string(),
-            exp: jsonwebtoken::get_current_timestamp() + duration.whole_seconds().abs() as u64,
+            ver,
+            exp: expiry(now, duration),
         }
     }
 }
@@ -131,16 +163,26 @@ impl Claims {
 impl<S> FromRequestParts<S> for Claims
 where
     S: Send + Sync,
+    PgPool: FromRef<S>,
+    SharedClock: FromRef<S>,
 {
-    type Rejection = AuthError;
+    type Rejection = ApiError<AuthError>;
 
-    async fn from_request_parts(req: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+    async fn from_request_parts(req: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
         let secret = req
             .extensions
             .get::<JwtSettings>()
-            .context("Failed to get JWT secrets")?
+            .context("Failed to get JWT secrets")
+            .map_err(AuthError::Unexpected)?
             .to_owned();
-        verify_token::<Self>(req, &secret.access.0.token).await
+        verify_token::<Self>(
+            req,
+            &secret.access.0.token,
+            &PgPool::from_ref(state),
+            SharedClock::from_ref(state).now(),
+        )
+        .await
+        .map_err(ApiError)
     }
 }
 
@@ -150,15 +192,24 @@ pub struct RefreshClaims {
     pub user_id: Uuid,
     pub login: String,
     pub exp: u64,
+    #[serde(default)]
+    pub ver: i32,
 }
 
 impl RefreshClaims {
-    pub fn new(user_id: Uuid, login: &str, duration: Duration) -> Self {
+    pub fn new(
+        user_id: Uuid,
+        login: &str,
+        ver: i32,
+        duration: Duration,
+        now: OffsetDateTime,
+    ) -> Self {
         Self {
             jti: Uuid::new_v4(),
             user_id,
             login: login.to_string(),
-            exp: jsonwebtoken::get_current_timestamp() + duration.whole_seconds().abs() as u64,
+            ver,
+            exp: expiry(now, duration),
         }
     }
 }
@@ -167,52 +218,106 @@ impl RefreshClaims {
 impl<S> FromRequestParts<S> for RefreshClaims
 where
     S: Send + Sync,
+    PgPool: FromRef<S>,
+    SharedClock: FromRef<S>,
 {
-    type Rejection = AuthError;
+    type Rejection = ApiError<AuthError>;
 
-    async fn from_request_parts(req: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+    async fn from_request_parts(req: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
         let secret = req
             .extensions
             .get::<JwtSettings>()
-            .context("Failed to get JWT secrets")?
+            .context("Failed to get JWT secrets")
+            .map_err(AuthError::Unexpected)?
             .to_owned();
-        verify_token::<Self>(req, &secret.refresh.0.token).await
+        verify_token::<Self>(
+            req,
+            &secret.refresh.0.token,
+            &PgPool::from_ref(state),
+            SharedClock::from_ref(state).now(),
+        )
+        .await
+        .map_err(ApiError)
     }
 }
 
-async fn verify_token<'t, T>(req: &mut Parts, secret: &Secret<String>) -> Result<T, AuthError>
+async fn verify_token<'t, T>(
+    req: &mut Parts,
+    secret: &Secret<String>,
+    pool: &PgPool,
+    now: OffsetDateTime,
+) -> Result<T, AuthError>
 where
     T: AuthToken<'t>,
 {
-    // get extensions - CookieJar
-    let jar = req
-        .extract::<CookieJar>()
-        .await
-        .context("Failed to fetch cookie jar")?;
-
     trace!("Verifying tokens");
 
-    let decoded = T::decode_jwt(&jar, None, secret.to_owned())?;
+    // Expiry is checked against the app clock rather than the system time
+    let mut validation = Validation::default();
+    validation.validate_exp = false;
+    let decoded = match bearer_token(&req.headers) {
+        Some(token) => T::decode_token(token, Some(&validation), secret.to_owned())?,
+        None => {
+            // get extensions - CookieJar
+            let jar = req
+                .extract::<CookieJar>()
+                .await
+                .context("Failed to fetch cookie jar")?;
+            T::decode_jwt(&jar, Some(&validation), secret.to_owned())?
+        }
+    };
     let payload = decoded.ok_or(AuthError::InvalidToken)?;
+    if payload.claims.exp() + EXPIRY_LEEWAY < now.unix_timestamp() as u64 {
+        trace!("Token has expired");
+        return Err(AuthError::InvalidToken);
+    }
+
+    let mut conn = pool.acquire().await?;
+    if get_token_version(&mut conn, payload.claims.user_id()).await? != payload.claims.ver() {
+        trace!("Token was revoked by a newer token version");
+        return Err(AuthError::InvalidToken);
+    }
 
     trace!("Tokens passed the verification step");
 
     Ok(payload.claims)
 }
 
-#[derive(Validate)]
-pub struct ValidatedUserData {
-    #[validate(
-        non_control_character,
-        custom = "is_ascii_or_latin_extended",
-        does_not_contain = " ",
-        length(min = 4, max = 20)
-    )]
-    pub login: String,
-    #[validate(
-        non_control_character,
-        custom = "is_ascii_or_latin_extended",
-        length(min = 4, max = 20)
-    )]
-    pub username: String,
+/// Unix timestamp at which a token issued at `now` stops being valid.
+fn expiry(now: OffsetDateTime, duration: Duration) -> u64 {
+    now.unix_timestamp() as u64 + duration.whole_seconds().unsigned_abs()
+}
+
+/// Gets the token from the `Authorization: Bearer <jwt>` header, which takes precedence over cookies.
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+#[cfg(test)]
+mod models_tests {
+    use super::*;
+    use axum_extra::extract::cookie::SameSite;
+    use bimetable_db::config::tokens::{CookieSameSite, CookieSettingsModel};
+
+    #[test]
+    fn cookie_uses_settings() {
+        let settings = CookieSettingsModel {
+            domain: Some("bimetable.app".to_string()),
+            same_site: Some(CookieSameSite::Lax),
+            secure: Some(false),
+        }
+        .to_settings();
+
+        let cookie = Claims::generate_cookie("token".to_string(), &settings, Duration::minutes(5));
+
+        assert_eq!(cookie.domain(), Some("bimetable.app"));
+        assert_eq!(cookie.same_site(), Some(SameSite::Lax));
+        assert_eq!(cookie.secure(), Some(false));
+        assert_eq!(cookie.max_age(), Some(Duration::minutes(5)));
+    }
 }
@@ -1,4 +1,3 @@
 pub mod auth;
-pub mod events;
-pub mod invitations;
-pub mod search;
+pub mod scim;
+pub mod secrets;
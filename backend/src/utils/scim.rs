@@ -0,0 +1,55 @@
+use crate::app_errors::ApiError;
+use crate::utils::secrets::is_secret;
+use axum::async_trait;
+use axum::extract::{FromRef, FromRequestParts};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use bimetable_db::config::app::ScimToken;
+use bimetable_db::utils::scim::errors::ScimError;
+use http::header::{AUTHORIZATION, CONTENT_TYPE};
+use http::request::Parts;
+use secrecy::ExposeSecret;
+use serde::Serialize;
+use tracing::debug;
+
+pub const SCIM_CONTENT_TYPE: &str = "application/scim+json";
+
+/// Identity system authorized with the bearer token of the provisioning API.
+pub struct ScimClient;
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ScimClient
+where
+    ScimToken: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError<ScimError>;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let ScimToken(token) = ScimToken::from_ref(state);
+        let Some(token) = token else {
+            return Err(ApiError(ScimError::Disabled));
+        };
+        let is_authorized = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|provided| is_secret(token.expose_secret(), provided));
+        if !is_authorized {
+            debug!("Rejected unauthorized provisioning request");
+            return Err(ApiError(ScimError::Unauthorized));
+        }
+
+        Ok(Self)
+    }
+}
+
+/// Body in the SCIM media type.
+pub struct Scim<T>(pub T);
+
+impl<T: Serialize> IntoResponse for Scim<T> {
+    fn into_response(self) -> Response {
+        ([(CONTENT_TYPE, SCIM_CONTENT_TYPE)], Json(self.0)).into_response()
+    }
+}
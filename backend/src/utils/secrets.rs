@@ -0,0 +1,30 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Compares the provided value with a secret in constant time, by comparing MACs of both values.
+///
+/// The MAC has a fixed length, so the time taken doesn't depend on the length of the value either.
+pub fn is_secret(secret: &str, provided: &str) -> bool {
+    let mac = |value: &str| {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(value.as_bytes());
+        mac
+    };
+    let expected = mac(secret).finalize().into_bytes();
+    mac(provided).verify_slice(&expected).is_ok()
+}
+
+#[cfg(test)]
+mod secrets_tests {
+    use super::*;
+
+    #[test]
+    fn compares_secrets() {
+        assert!(is_secret("provisioning-token", "provisioning-token"));
+        assert!(!is_secret("provisioning-token", "provisioning-toke"));
+        assert!(!is_secret("provisioning-token", "provisioning-token "));
+        assert!(!is_secret("provisioning-token", ""));
+        assert!(is_secret("", ""));
+    }
+}
@@ -0,0 +1,731 @@
+mod tools;
+
+use bimetable_db::config::passwords::PasswordSettings;
+use bimetable_db::config::retention::RetentionSettings;
+use bimetable_db::modules::clock::{Clock, TestClock};
+use bimetable_db::utils::admin::purge::{purge_expired, PurgeReport};
+use bimetable_db::utils::admin::stats::compute_daily_stats;
+use bimetable_db::utils::admin::{
+    delete_deactivated_events, get_instance_stats, purge_deleted_events,
+};
+use bimetable_db::utils::auth::errors::AuthError;
+use bimetable_db::utils::auth::reset_user_password;
+use bimetable_domain::api::admin::{DailyStats, MintSignupCodes, RetentionPolicy, SignupCode};
+use bimetable_http::modules::Modules;
+use reqwest::{Client, StatusCode};
+use secrecy::SecretString;
+use serde_json::{json, Value};
+use sqlx::{query, PgPool};
+use time::macros::datetime;
+use time::{Duration, OffsetDateTime};
+use tools::AppData;
+use tracing_test::traced_test;
+use uuid::{uuid, Uuid};
+
+const ADIMAC_ID: Uuid = uuid!("910e81a9-56df-4c24-965a-13eff739f469");
+const PKBPMJ_ID: Uuid = uuid!("29e40c2a-7595-42d3-98e8-9fe93ce99972");
+const MABI19_ID: Uuid = uuid!("32190025-7c15-4adb-82fd-9acc3dc8e7b6");
+const HUBERT_ID: Uuid = uuid!("a9c5900e-a445-4888-8612-4a5c8cadbd9e");
+const FIZYKA_ID: Uuid = uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
+const MATEMATYKA_ID: Uuid = uuid!("6d185de5-ddec-462a-aeea-7628f03d417b");
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn maintenance_mode_test(pool: PgPool) {
+    query!(
+        r#"
+            UPDATE users SET is_admin = true
+            WHERE id = $1
+        "#,
+        ADIMAC_ID,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let app = AppData::new(pool).await;
+    let admin = app.login("macmac").await;
+    let user = app.login("hubhub").await;
+    let preferences = json!({ "weekStart": "sunday" });
+
+    let res = user
+        .put(app.api("/admin/maintenance"))
+        .json(&json!({ "isEnabled": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = admin
+        .put(app.api("/admin/maintenance"))
+        .json(&json!({ "isEnabled": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = user
+        .patch(app.api("/users/preferences"))
+        .json(&preferences)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(res.json::<Value>().await.unwrap()["error_info"].is_string());
+
+    let res = user
+        .get(app.api("/users/preferences"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = admin
+        .patch(app.api("/users/preferences"))
+        .json(&preferences)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = admin
+        .put(app.api("/admin/maintenance"))
+        .json(&json!({ "isEnabled": false }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = user
+        .patch(app.api("/users/preferences"))
+        .json(&preferences)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn maintenance_mode_is_shared_by_instances(pool: PgPool) {
+    query!(
+        r#"
+            UPDATE users SET is_admin = true
+            WHERE id = $1
+        "#,
+        ADIMAC_ID,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let app = AppData::new(pool.clone()).await;
+    let other = AppData::new(pool).await;
+    let admin = app.login("macmac").await;
+    let user = other.login("hubhub").await;
+    let preferences = json!({ "weekStart": "sunday" });
+
+    let res = admin
+        .put(app.api("/admin/maintenance"))
+        .json(&json!({ "isEnabled": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = user
+        .patch(other.api("/users/preferences"))
+        .json(&preferences)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    let res = admin
+        .put(app.api("/admin/maintenance"))
+        .json(&json!({ "isEnabled": false }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = user
+        .patch(other.api("/users/preferences"))
+        .json(&preferences)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn maintenance_mode_keeps_bearer_sign_in(pool: PgPool) {
+    query!(
+        r#"
+            UPDATE users SET is_admin = true
+            WHERE id = $1
+        "#,
+        ADIMAC_ID,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let app = AppData::new(pool).await;
+    let admin = app.login("macmac").await;
+    let res = admin
+        .put(app.api("/admin/maintenance"))
+        .json(&json!({ "isEnabled": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let cli = Client::new();
+    let res = cli
+        .post(app.api("/auth/token"))
+        .json(&json!({ "login": "hubhub", "password": "#strong#_#pass#" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let tokens: Value = res.json().await.unwrap();
+
+    let res = cli
+        .post(app.api("/auth/token/refresh"))
+        .bearer_auth(tokens["refreshToken"].as_str().unwrap())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn purge_deleted_events_test(pool: PgPool) {
+    query!(
+        r#"
+            UPDATE events SET deleted_at = now() - INTERVAL '40 days'
+            WHERE id = $1
+        "#,
+        FIZYKA_ID,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    query!(
+        r#"
+            INSERT INTO user_event_invitations (event_id, sender_id, receiver_id, can_edit)
+            VALUES ($1, $2, $3, false)
+        "#,
+        FIZYKA_ID,
+        PKBPMJ_ID,
+        MABI19_ID,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let stats = get_instance_stats(&pool).await.unwrap();
+    assert_eq!(stats.deleted_events, 1);
+    assert_eq!(stats.pending_invitations, 1);
+
+    let month_ago = OffsetDateTime::now_utc() - Duration::days(30);
+    assert_eq!(purge_deleted_events(&pool, month_ago).await.unwrap(), 1);
+    assert_eq!(purge_deleted_events(&pool, month_ago).await.unwrap(), 0);
+
+    let stats = get_instance_stats(&pool).await.unwrap();
+    assert_eq!(stats.deleted_events, 0);
+    assert_eq!(stats.pending_invitations, 0);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn purge_expired_data_test(pool: PgPool) {
+    let now = datetime!(2023-03-15 12:00 UTC);
+    query!(
+        r#"
+            UPDATE events SET deleted_at = $2
+            WHERE id = $1
+        "#,
+        FIZYKA_ID,
+        now - Duration::days(40),
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    query!(
+        r#"
+            INSERT INTO user_event_invitations (event_id, sender_id, receiver_id, can_edit, created_at)
+            VALUES ($1, $2, $3, false, $4), ($1, $2, $5, false, $6)
+        "#,
+        MATEMATYKA_ID,
+        PKBPMJ_ID,
+        MABI19_ID,
+        now - Duration::days(20),
+        HUBERT_ID,
+        now - Duration::days(2),
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    query!(
+        r#"
+            INSERT INTO jwt_blacklist (token_id, expiry)
+            VALUES (gen_random_uuid(), $1), (gen_random_uuid(), $2)
+        "#,
+        now - Duration::days(3),
+        now - Duration::hours(1),
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    query!(
+        r#"
+            INSERT INTO outbox (topic, aggregate_id, payload, created_at, dispatched_at)
+            VALUES ('eventUpdated', $1, '{}', $2, $2), ('eventUpdated', $1, '{}', $2, NULL)
+        "#,
+        MATEMATYKA_ID,
+        now - Duration::days(400),
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let kept = purge_expired(&pool, &RetentionSettings::default(), now)
+        .await
+        .unwrap();
+    assert_eq!(
+        kept,
+        PurgeReport {
+            revoked_tokens: 1,
+            ..PurgeReport::default()
+        }
+    );
+
+    let retention = RetentionSettings {
+        trash_days: Some(30),
+        blacklist_days: 1,
+        audit_log_days: Some(365),
+        invitation_expiry_days: Some(14),
+    };
+    let purged = purge_expired(&pool, &retention, now).await.unwrap();
+    assert_eq!(
+        purged,
+        PurgeReport {
+            events: 1,
+            revoked_tokens: 0,
+            changes: 1,
+            invitations: 1,
+        }
+    );
+    assert_eq!(
+        purge_expired(&pool, &retention, now).await.unwrap(),
+        PurgeReport::default()
+    );
+
+    let stats = get_instance_stats(&pool).await.unwrap();
+    assert_eq!(stats.deleted_events, 0);
+    assert_eq!(stats.pending_invitations, 1);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn retention_policy_test(pool: PgPool) {
+    query!(
+        r#"
+            UPDATE users SET is_admin = true
+            WHERE id = $1
+        "#,
+        ADIMAC_ID,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let app = AppData::with_modules(pool, |m: &mut Modules| {
+        m.retention.trash_days = Some(30);
+    })
+    .await;
+
+    let res = app
+        .login("hubhub")
+        .await
+        .get(app.api("/admin/retention"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = app
+        .login("macmac")
+        .await
+        .get(app.api("/admin/retention"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(
+        res.json::<RetentionPolicy>().await.unwrap(),
+        RetentionPolicy {
+            trash_days: Some(30),
+            blacklist_days: 1,
+            audit_log_days: None,
+            invitation_expiry_days: None,
+        }
+    );
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn invite_only_signup_test(pool: PgPool) {
+    query!(
+        r#"
+            UPDATE users SET is_admin = true
+            WHERE id = $1
+        "#,
+        ADIMAC_ID,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let clock = TestClock::new(OffsetDateTime::now_utc());
+    let app = {
+        let clock = clock.clone();
+        AppData::with_modules(pool, move |m: &mut Modules| {
+            m.signups.invite_only = true;
+            m.set_clock(clock);
+        })
+        .await
+    };
+    let body = MintSignupCodes {
+        count: 2,
+        valid_days: None,
+    };
+
+    let res = app
+        .login("hubhub")
+        .await
+        .post(app.api("/admin/signup-codes"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = app
+        .login("macmac")
+        .await
+        .post(app.api("/admin/signup-codes"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let codes = res.json::<Vec<SignupCode>>().await.unwrap();
+    assert_eq!(codes.len(), 2);
+    assert!(codes[0].expires_at > clock.now() + Duration::days(13));
+
+    let register = |login: &str, code: Option<&str>| {
+        app.client()
+            .post(app.api("/auth/register"))
+            .json(&json!({
+                "login": login,
+                "password": "#very#_#strong#_#pass#",
+                "username": "Chad",
+                "signupCode": code,
+            }))
+            .send()
+    };
+
+    let res = register("chad", None).await.unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = register("chad", Some("wrong")).await.unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = register("chad", Some(&codes[0].code)).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = register("brad", Some(&codes[0].code)).await.unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    // Expiry follows the app clock, like minting
+    clock.advance(Duration::days(15));
+    let res = register("brad", Some(&codes[1].code)).await.unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn reset_password_test(pool: PgPool) {
+    let app = AppData::new(pool.clone()).await;
+    let client = app.login("macmac").await;
+
+    let user_id = reset_user_password(
+        &pool,
+        "macmac",
+        SecretString::new("#another#_#pass#".to_string()),
+        &PasswordSettings::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(user_id, ADIMAC_ID);
+
+    let res = client
+        .get(app.api("/users/preferences"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    let res = app
+        .client()
+        .post(app.api("/auth/login"))
+        .json(&json!({ "login": "macmac", "password": "#another#_#pass#" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    assert!(matches!(
+        reset_user_password(
+            &pool,
+            "nobody",
+            SecretString::new("#another#_#pass#".to_string()),
+            &PasswordSettings::default(),
+        )
+        .await,
+        Err(AuthError::UserNotFound)
+    ));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn usage_stats_test(pool: PgPool) {
+    query!(
+        r#"
+            UPDATE users SET is_admin = true
+            WHERE id = $1
+        "#,
+        ADIMAC_ID,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let at = datetime!(2023-03-15 12:00 UTC);
+    compute_daily_stats(&pool, at).await.unwrap();
+    compute_daily_stats(&pool, at).await.unwrap();
+
+    let app = AppData::new(pool).await;
+    let res = app
+        .login("hubhub")
+        .await
+        .get(app.api("/admin/stats"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = app
+        .login("macmac")
+        .await
+        .get(app.api("/admin/stats"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let stats: Vec<DailyStats> = res.json().await.unwrap();
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].day, datetime!(2023-03-15 0:00 UTC));
+    assert!(stats[0].users > 0);
+    assert!(stats[0].entries > 0);
+    assert!(stats[0].database_bytes > 0);
+}
+
+async fn search_usernames(app: &AppData, client: &Client, text: &str) -> Vec<String> {
+    let res = client
+        .get(app.api("/search/users"))
+        .query(&[("text", text)])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    res.json::<Vec<Value>>()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|user| user["username"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn admin_deactivates_user_test(pool: PgPool) {
+    query!(
+        r#"
+            UPDATE users SET is_admin = true
+            WHERE id = $1
+        "#,
+        ADIMAC_ID,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let app = AppData::new(pool.clone()).await;
+    let admin = app.login("macmac").await;
+    let user = app.login("hubhub").await;
+    assert_eq!(search_usernames(&app, &admin, "hub").await, vec!["hubertk"]);
+
+    let res = user
+        .patch(app.api(&format!("/admin/users/{ADIMAC_ID}/deactivate")))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = admin
+        .patch(app.api(&format!("/admin/users/{}/deactivate", Uuid::new_v4())))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    let res = admin
+        .patch(app.api(&format!("/admin/users/{HUBERT_ID}/deactivate")))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+    let res = user
+        .get(app.api("/users/preferences"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    let res = app
+        .client()
+        .post(app.api("/auth/login"))
+        .json(&json!({ "login": "hubhub", "password": "#strong#_#pass#" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    assert!(search_usernames(&app, &admin, "hub").await.is_empty());
+
+    let stats = get_instance_stats(&pool).await.unwrap();
+    assert_eq!(stats.users, 4);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn user_deactivates_own_account_test(pool: PgPool) {
+    let clock = TestClock::new(OffsetDateTime::now_utc());
+    let app = {
+        let clock = clock.clone();
+        AppData::with_modules(pool.clone(), move |m: &mut Modules| m.set_clock(clock)).await
+    };
+    let user = app.login("hubhub").await;
+
+    let res = user.post(app.api("/auth/deactivate")).send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = app
+        .client()
+        .post(app.api("/auth/token"))
+        .json(&json!({ "login": "hubhub", "password": "#strong#_#pass#" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let owned_events = || async {
+        query!(
+            r#"
+                SELECT COUNT(*) AS "count!" FROM events
+                WHERE owner_id = $1 AND deleted_at IS NULL
+            "#,
+            HUBERT_ID,
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap()
+        .count
+    };
+    let owned = owned_events().await;
+    assert!(owned > 0);
+
+    let grace_period = Duration::days(7);
+    let now = clock.now();
+    assert_eq!(
+        delete_deactivated_events(&pool, now - grace_period, now)
+            .await
+            .unwrap(),
+        0
+    );
+    assert_eq!(owned_events().await, owned);
+
+    clock.advance(Duration::days(8));
+    let now = clock.now();
+    assert_eq!(
+        delete_deactivated_events(&pool, now - grace_period, now)
+            .await
+            .unwrap(),
+        owned as u64
+    );
+    assert_eq!(owned_events().await, 0);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn deactivated_owner_hands_events_over_to_co_owner(pool: PgPool) {
+    let matematyka_id = uuid!("6d185de5-ddec-462a-aeea-7628f03d417b");
+    query!(
+        r#"
+            UPDATE user_events SET is_owner = true, can_edit = true
+            WHERE user_id = $1 AND event_id = $2
+        "#,
+        ADIMAC_ID,
+        matematyka_id,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    query!(
+        r#"
+            UPDATE users SET deactivated_at = now() - INTERVAL '1 day'
+            WHERE id = $1
+        "#,
+        PKBPMJ_ID,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let now = OffsetDateTime::now_utc();
+    delete_deactivated_events(&pool, now, now).await.unwrap();
+
+    let event = |event_id: Uuid| {
+        let pool = pool.clone();
+        async move {
+            query!(
+                r#"
+                    SELECT owner_id, deleted_at FROM events
+                    WHERE id = $1
+                "#,
+                event_id,
+            )
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+        }
+    };
+    let matematyka = event(matematyka_id).await;
+    assert_eq!(matematyka.owner_id, ADIMAC_ID);
+    assert!(matematyka.deleted_at.is_none());
+    assert!(event(FIZYKA_ID).await.deleted_at.is_some());
+}
@@ -3,9 +3,16 @@ use reqwest::StatusCode;
 use serde_json::json;
 mod tools;
 
-use bimetable::utils::auth::{errors::AuthError, try_register_user, verify_user_credentials};
+use bimetable_db::config::passwords::PasswordSettings;
+use bimetable_db::config::signups::SignupSettings;
+use bimetable_db::config::usernames::{Script, UsernameSettings};
+use bimetable_db::modules::clock::TestClock;
+use bimetable_db::utils::auth::{
+    errors::AuthError, try_register_user, verify_user_credentials, Signup,
+};
 use secrecy::SecretString;
 use sqlx::PgPool;
+use time::{Duration, OffsetDateTime};
 
 #[sqlx::test]
 async fn registration_health_check(db: PgPool) {
@@ -14,6 +21,9 @@ async fn registration_health_check(db: PgPool) {
         &format!("User{}", nanoid!(10)),
         SecretString::new("#very#_#strong#_#pass#".to_string()),
         "Chad",
+        &PasswordSettings::default(),
+        &UsernameSettings::default(),
+        None,
     )
     .await;
 
@@ -30,6 +40,9 @@ async fn registration_missing_credential_0(db: PgPool) {
         "",
         SecretString::new("#very#_#strong#_#pass#".to_string()),
         "Chad",
+        &PasswordSettings::default(),
+        &UsernameSettings::default(),
+        None,
     )
     .await;
 
@@ -46,6 +59,9 @@ async fn registration_missing_credential_1(db: PgPool) {
         "   ",
         SecretString::new("#very#_#strong#_#pass#".to_string()),
         "Chad",
+        &PasswordSettings::default(),
+        &UsernameSettings::default(),
+        None,
     )
     .await;
 
@@ -62,6 +78,9 @@ async fn registration_missing_credential_2(db: PgPool) {
         &format!("User{}", nanoid!(10)),
         SecretString::new("  ".to_string()),
         "Chad",
+        &PasswordSettings::default(),
+        &UsernameSettings::default(),
+        None,
     )
     .await;
 
@@ -73,7 +92,16 @@ async fn registration_missing_credential_2(db: PgPool) {
 
 #[sqlx::test(fixtures("users"))]
 async fn registration_missing_credential_3(db: PgPool) {
-    let res = try_register_user(&db, "  ", SecretString::new("   ".to_string()), "Chad").await;
+    let res = try_register_user(
+        &db,
+        "  ",
+        SecretString::new("   ".to_string()),
+        "Chad",
+        &PasswordSettings::default(),
+        &UsernameSettings::default(),
+        None,
+    )
+    .await;
 
     match res {
         Err(AuthError::MissingCredential) => (),
@@ -88,6 +116,9 @@ async fn registration_weak_password(db: PgPool) {
         &format!("User{}", nanoid!(10)),
         SecretString::new("12345678".to_string()),
         "Chad",
+        &PasswordSettings::default(),
+        &UsernameSettings::default(),
+        None,
     )
     .await;
 
@@ -104,6 +135,9 @@ async fn registration_user_exists_0(db: PgPool) {
         "mabmab",
         SecretString::new("#very#_#strong#_#pass#".to_string()),
         "Chad",
+        &PasswordSettings::default(),
+        &UsernameSettings::default(),
+        None,
     )
     .await;
 
@@ -120,6 +154,9 @@ async fn registration_user_exists_1(db: PgPool) {
         "pkbpkp",
         SecretString::new("#strong#_#pass#".to_string()),
         "Chad",
+        &PasswordSettings::default(),
+        &UsernameSettings::default(),
+        None,
     )
     .await;
 
@@ -136,6 +173,9 @@ async fn registration_invalid_username_0(db: PgPool) {
         "why",
         SecretString::new("#strong#_#pass#".to_string()),
         "Chad",
+        &PasswordSettings::default(),
+        &UsernameSettings::default(),
+        None,
     )
     .await;
 
@@ -152,6 +192,9 @@ async fn registration_invalid_username_1(db: PgPool) {
         "spaced name",
         SecretString::new("#strong#_#pass#".to_string()),
         "Chad",
+        &PasswordSettings::default(),
+        &UsernameSettings::default(),
+        None,
     )
     .await;
 
@@ -168,6 +211,9 @@ async fn registration_invalid_username_2(db: PgPool) {
         "verylongveryverylongnameveryveryverylongname",
         SecretString::new("#strong#_#pass#".to_string()),
         "Chad",
+        &PasswordSettings::default(),
+        &UsernameSettings::default(),
+        None,
     )
     .await;
 
@@ -184,6 +230,9 @@ async fn registration_invalid_username_3(db: PgPool) {
         "thΣtruΣsigma",
         SecretString::new("#strong#_#pass#".to_string()),
         "Chad",
+        &PasswordSettings::default(),
+        &UsernameSettings::default(),
+        None,
     )
     .await;
 
@@ -200,6 +249,9 @@ async fn registration_invalid_username_4(db: PgPool) {
         "deletethis->",
         SecretString::new("#strong#_#pass#".to_string()),
         "Chad",
+        &PasswordSettings::default(),
+        &UsernameSettings::default(),
+        None,
     )
     .await;
 
@@ -209,6 +261,87 @@ async fn registration_invalid_username_4(db: PgPool) {
     }
 }
 
+#[sqlx::test(fixtures("users"))]
+async fn registration_localized_username(db: PgPool) {
+    let usernames = UsernameSettings {
+        scripts: vec![Script::Latin, Script::Greek],
+        ..UsernameSettings::default()
+    };
+    let res = try_register_user(
+        &db,
+        "sokrates",
+        SecretString::new("#very#_#strong#_#pass#".to_string()),
+        "Σωκράτης",
+        &PasswordSettings::default(),
+        &usernames,
+        None,
+    )
+    .await;
+    assert!(res.is_ok(), "Test gives the result {:?}", res);
+
+    let res = try_register_user(
+        &db,
+        "thΣtruΣsigma",
+        SecretString::new("#strong#_#pass#".to_string()),
+        "Chad",
+        &PasswordSettings::default(),
+        &usernames,
+        None,
+    )
+    .await;
+    match res {
+        Err(AuthError::InvalidUsername(_)) => (),
+        _ => panic!("Test gives the result {:?}", res),
+    }
+}
+
+#[sqlx::test]
+async fn registration_signup_policy(db: PgPool) {
+    let register = |login: &'static str, signups: Option<SignupSettings>| {
+        let db = db.clone();
+        async move {
+            try_register_user(
+                &db,
+                login,
+                SecretString::new("#very#_#strong#_#pass#".to_string()),
+                "Chad",
+                &PasswordSettings::default(),
+                &UsernameSettings::default(),
+                signups.as_ref().map(|settings| Signup {
+                    settings,
+                    code: None,
+                    now: OffsetDateTime::now_utc(),
+                }),
+            )
+            .await
+        }
+    };
+    let disabled = SignupSettings {
+        enabled: false,
+        ..SignupSettings::default()
+    };
+    let school = SignupSettings {
+        email_domains: vec!["school.edu".to_string()],
+        ..SignupSettings::default()
+    };
+
+    let res = register("chad@school.edu", Some(disabled.clone())).await;
+    assert!(matches!(res, Err(AuthError::SignupDisabled)), "{res:?}");
+
+    let res = register("chad@example.com", Some(school.clone())).await;
+    assert!(
+        matches!(res, Err(AuthError::EmailDomainNotAllowed)),
+        "{res:?}"
+    );
+
+    let res = register("chad@School.edu", Some(school)).await;
+    assert!(res.is_ok(), "{res:?}");
+
+    // Provisioned accounts skip the policy
+    let res = register("chad@example.com", None).await;
+    assert!(res.is_ok(), "{res:?}");
+}
+
 #[sqlx::test(fixtures("users"))]
 async fn login_health_check(db: PgPool) {
     let mut conn = db.acquire().await.unwrap();
@@ -216,6 +349,7 @@ async fn login_health_check(db: PgPool) {
         &mut conn,
         "macmac",
         SecretString::new("#strong#_#pass#".to_string()),
+        &PasswordSettings::default(),
     )
     .await;
 
@@ -225,11 +359,52 @@ async fn login_health_check(db: PgPool) {
     }
 }
 
+#[sqlx::test(fixtures("users"))]
+async fn login_rehashes_weaker_password(db: PgPool) {
+    let mut conn = db.acquire().await.unwrap();
+    let get_hash = || {
+        sqlx::query_scalar::<_, String>("SELECT password FROM credentials WHERE login = 'macmac'")
+    };
+    assert!(get_hash()
+        .fetch_one(&db)
+        .await
+        .unwrap()
+        .starts_with("$argon2i$"));
+
+    verify_user_credentials(
+        &mut conn,
+        "macmac",
+        SecretString::new("#strong#_#pass#".to_string()),
+        &PasswordSettings::default(),
+    )
+    .await
+    .unwrap();
+
+    assert!(get_hash()
+        .fetch_one(&db)
+        .await
+        .unwrap()
+        .starts_with("$argon2id$"));
+    assert!(verify_user_credentials(
+        &mut conn,
+        "macmac",
+        SecretString::new("#strong#_#pass#".to_string()),
+        &PasswordSettings::default(),
+    )
+    .await
+    .is_ok());
+}
+
 #[sqlx::test(fixtures("users"))]
 async fn login_missing_credential_0(db: PgPool) {
     let mut conn = db.acquire().await.unwrap();
-    let res =
-        verify_user_credentials(&mut conn, "hubhub", SecretString::new("   ".to_string())).await;
+    let res = verify_user_credentials(
+        &mut conn,
+        "hubhub",
+        SecretString::new("   ".to_string()),
+        &PasswordSettings::default(),
+    )
+    .await;
 
     match res {
         Err(AuthError::MissingCredential) => (),
@@ -244,6 +419,7 @@ async fn login_missing_credential_1(db: PgPool) {
         &mut conn,
         "    ",
         SecretString::new("#strong#_#pass#".to_string()),
+        &PasswordSettings::default(),
     )
     .await;
 
@@ -256,7 +432,13 @@ async fn login_missing_credential_1(db: PgPool) {
 #[sqlx::test(fixtures("users"))]
 async fn login_missing_credential_2(db: PgPool) {
     let mut conn = db.acquire().await.unwrap();
-    let res = verify_user_credentials(&mut conn, "    ", SecretString::new("  ".to_string())).await;
+    let res = verify_user_credentials(
+        &mut conn,
+        "    ",
+        SecretString::new("  ".to_string()),
+        &PasswordSettings::default(),
+    )
+    .await;
 
     match res {
         Err(AuthError::MissingCredential) => (),
@@ -271,6 +453,7 @@ async fn login_no_user_found(db: PgPool) {
         &mut conn,
         "different_user",
         SecretString::new("#strong#_#pass#".to_string()),
+        &PasswordSettings::default(),
     )
     .await;
 
@@ -287,6 +470,7 @@ async fn login_wrong_password(db: PgPool) {
         &mut conn,
         "mabmab",
         SecretString::new("#wrong#_#pass#".to_string()),
+        &PasswordSettings::default(),
     )
     .await;
 
@@ -350,3 +534,202 @@ async fn auth_integration_test(db: PgPool) {
 
     assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
 }
+
+#[sqlx::test(fixtures("users"))]
+async fn bearer_token_integration_test(db: PgPool) {
+    let app_data = tools::AppData::new(db).await;
+    let client = reqwest::Client::new();
+
+    let res = client
+        .post(app_data.api("/auth/token"))
+        .json(&json!({ "login": "macmac", "password": "#strong#_#pass#" }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(res.headers().get("set-cookie").is_none());
+
+    let tokens: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(tokens["tokenType"], "Bearer");
+
+    let res = client
+        .post(app_data.api("/auth/validate"))
+        .bearer_auth(tokens["accessToken"].as_str().unwrap())
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = client
+        .post(app_data.api("/auth/validate"))
+        .bearer_auth(tokens["refreshToken"].as_str().unwrap())
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test(fixtures("users"))]
+async fn bearer_token_refresh_test(db: PgPool) {
+    let app_data = tools::AppData::new(db).await;
+    let client = reqwest::Client::new();
+
+    let res = client
+        .post(app_data.api("/auth/token"))
+        .json(&json!({ "login": "macmac", "password": "#strong#_#pass#" }))
+        .send()
+        .await
+        .unwrap();
+    let tokens: serde_json::Value = res.json().await.unwrap();
+
+    let res = client
+        .post(app_data.api("/auth/token/refresh"))
+        .bearer_auth(tokens["refreshToken"].as_str().unwrap())
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(res.headers().get("set-cookie").is_none());
+
+    let refreshed: serde_json::Value = res.json().await.unwrap();
+    let res = client
+        .post(app_data.api("/auth/validate"))
+        .bearer_auth(refreshed["accessToken"].as_str().unwrap())
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[sqlx::test(fixtures("users"))]
+async fn token_expires_with_app_clock(db: PgPool) {
+    let clock = TestClock::new(OffsetDateTime::now_utc());
+    let app_data = {
+        let clock = clock.clone();
+        tools::AppData::with_modules(db, move |modules| modules.set_clock(clock)).await
+    };
+    let client = reqwest::Client::new();
+
+    let res = client
+        .post(app_data.api("/auth/token"))
+        .json(&json!({ "login": "macmac", "password": "#strong#_#pass#" }))
+        .send()
+        .await
+        .unwrap();
+    let tokens: serde_json::Value = res.json().await.unwrap();
+    let access_token = tokens["accessToken"].as_str().unwrap();
+
+    clock.advance(Duration::days(2000));
+    let res = client
+        .post(app_data.api("/auth/validate"))
+        .bearer_auth(access_token)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+
+    clock.advance(Duration::days(200));
+    let res = client
+        .post(app_data.api("/auth/validate"))
+        .bearer_auth(access_token)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test(fixtures("users"))]
+async fn logout_all_integration_test(db: PgPool) {
+    let app_data = tools::AppData::new(db).await;
+    let credentials = json!({ "login": "macmac", "password": "#strong#_#pass#" });
+
+    let browser = app_data.client();
+    let res = browser
+        .post(app_data.api("/auth/login"))
+        .json(&credentials)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let cli = reqwest::Client::new();
+    let tokens: serde_json::Value = cli
+        .post(app_data.api("/auth/token"))
+        .json(&credentials)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let access_token = tokens["accessToken"].as_str().unwrap();
+
+    let res = browser
+        .post(app_data.api("/auth/logout-all"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = cli
+        .post(app_data.api("/auth/validate"))
+        .bearer_auth(access_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    let res = browser
+        .post(app_data.api("/auth/login"))
+        .json(&credentials)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = browser
+        .post(app_data.api("/auth/validate"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[sqlx::test(fixtures("users"))]
+async fn bearer_logout_test(db: PgPool) {
+    let app_data = tools::AppData::new(db.clone()).await;
+    let client = reqwest::Client::new();
+
+    let tokens: serde_json::Value = client
+        .post(app_data.api("/auth/token"))
+        .json(&json!({ "login": "macmac", "password": "#strong#_#pass#" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    for token in [&tokens["accessToken"], &tokens["refreshToken"]] {
+        let res = client
+            .post(app_data.api("/auth/logout"))
+            .bearer_auth(token.as_str().unwrap())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    let blacklisted = sqlx::query_scalar!(r#"SELECT count(*) AS "count!" FROM jwt_blacklist"#)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+    assert_eq!(blacklisted, 2);
+}
@@ -0,0 +1,84 @@
+mod tools;
+
+use bimetable_client::{Client, Error};
+use bimetable_domain::api::events::GetEventQuery;
+use bimetable_domain::api::invitations::{CreateDirectInvitation, RespondDirectInvitation};
+use reqwest::StatusCode;
+use sqlx::PgPool;
+use tools::AppData;
+use uuid::{uuid, Uuid};
+
+const PKBPMJ_ID: Uuid = uuid!("29e40c2a-7595-42d3-98e8-9fe93ce99972");
+const MABI19_ID: Uuid = uuid!("32190025-7c15-4adb-82fd-9acc3dc8e7b6");
+const FIZYKA_ID: Uuid = uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
+
+#[sqlx::test(fixtures("users", "events"))]
+async fn client_reads_events_with_server_models(pool: PgPool) {
+    let app = AppData::new(pool).await;
+    let client = app.login_sdk("pkbpkp").await;
+
+    let query = GetEventQuery {
+        exceptions: true,
+        include_deleted: false,
+    };
+    let event = client.get_event(FIZYKA_ID, &query).await.unwrap();
+    assert_eq!(event.payload.name, "Fizyka");
+    assert!(event.is_owned);
+    assert!(event.exceptions.is_some());
+}
+
+#[sqlx::test(fixtures("users", "events"))]
+async fn client_sends_and_answers_invitations(pool: PgPool) {
+    let app = AppData::new(pool).await;
+    let sender = app.login_sdk("pkbpkp").await;
+    let receiver = app.login_sdk("mabmab").await;
+
+    sender
+        .create_invitation(&CreateDirectInvitation {
+            event_id: FIZYKA_ID,
+            receiver_id: MABI19_ID,
+            can_edit: Some(false),
+        })
+        .await
+        .unwrap();
+
+    let invitations = receiver.fetch_invitations().await.unwrap();
+    assert_eq!(invitations.len(), 1);
+    assert_eq!(invitations[0].sender_id, PKBPMJ_ID);
+
+    receiver
+        .respond_to_invitation(&RespondDirectInvitation {
+            event_id: FIZYKA_ID,
+            sender_id: PKBPMJ_ID,
+            receiver_id: MABI19_ID,
+            is_accepted: true,
+        })
+        .await
+        .unwrap();
+    let event = receiver
+        .get_event(
+            FIZYKA_ID,
+            &GetEventQuery {
+                exceptions: false,
+                include_deleted: false,
+            },
+        )
+        .await
+        .unwrap();
+    assert!(!event.is_owned);
+}
+
+#[sqlx::test(fixtures("users", "events"))]
+async fn client_reports_failed_requests(pool: PgPool) {
+    let app = AppData::new(pool).await;
+    let client = Client::new(app.api(""));
+
+    let res = client.get_preferences().await;
+    assert!(matches!(
+        res,
+        Err(Error::Status {
+            status: StatusCode::UNAUTHORIZED,
+            ..
+        })
+    ));
+}
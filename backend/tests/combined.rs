@@ -0,0 +1,124 @@
+use bimetable_db::utils::events::errors::EventError;
+use bimetable_db::utils::events::exe::{get_combined_busy, suggest_free_slots};
+use bimetable_db::utils::users::errors::UserError;
+use bimetable_db::utils::users::{grant_busy_visibility, revoke_busy_visibility};
+use bimetable_domain::api::events::SuggestSlot;
+use bimetable_domain::events::models::TimeRange;
+use sqlx::PgPool;
+use time::macros::datetime;
+use tracing_test::traced_test;
+use uuid::{uuid, Uuid};
+
+const PKBPMJ_ID: Uuid = uuid!("29e40c2a-7595-42d3-98e8-9fe93ce99972");
+const HUBERT_ID: Uuid = uuid!("a9c5900e-a445-4888-8612-4a5c8cadbd9e");
+
+const WEEK: TimeRange = TimeRange {
+    start: datetime!(2023-03-13 0:00 UTC),
+    end: datetime!(2023-03-20 0:00 UTC),
+};
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn combines_busy_times(pool: PgPool) {
+    grant_busy_visibility(&pool, PKBPMJ_ID, HUBERT_ID)
+        .await
+        .unwrap();
+
+    let combined = get_combined_busy(HUBERT_ID, &[PKBPMJ_ID, HUBERT_ID], WEEK, &pool)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        combined.users[&PKBPMJ_ID],
+        vec![
+            TimeRange::new(
+                datetime!(2023-03-15 9:45 UTC),
+                datetime!(2023-03-15 10:30 UTC)
+            ),
+            TimeRange::new(
+                datetime!(2023-03-16 9:45 UTC),
+                datetime!(2023-03-16 10:30 UTC)
+            ),
+        ]
+    );
+    assert_eq!(combined.users[&HUBERT_ID].len(), 4);
+    assert_eq!(combined.busy, combined.users[&HUBERT_ID]);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn requires_shared_busy_times(pool: PgPool) {
+    let own = get_combined_busy(HUBERT_ID, &[HUBERT_ID], WEEK, &pool).await;
+    assert!(own.is_ok());
+
+    let res = get_combined_busy(HUBERT_ID, &[PKBPMJ_ID, HUBERT_ID], WEEK, &pool).await;
+    assert!(matches!(res, Err(EventError::MismatchedPrivileges)));
+
+    grant_busy_visibility(&pool, PKBPMJ_ID, HUBERT_ID)
+        .await
+        .unwrap();
+    revoke_busy_visibility(&pool, PKBPMJ_ID, HUBERT_ID)
+        .await
+        .unwrap();
+
+    let res = get_combined_busy(HUBERT_ID, &[PKBPMJ_ID], WEEK, &pool).await;
+    assert!(matches!(res, Err(EventError::MismatchedPrivileges)));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn cannot_share_with_missing_user(pool: PgPool) {
+    let res = grant_busy_visibility(&pool, PKBPMJ_ID, Uuid::new_v4()).await;
+
+    assert!(matches!(res, Err(UserError::NotFound)));
+}
+
+fn thursday_morning(participants: Vec<Uuid>, limit: u32) -> SuggestSlot {
+    SuggestSlot {
+        participants,
+        duration_minutes: 60,
+        starts_at: datetime!(2023-03-16 8:00 UTC),
+        ends_at: datetime!(2023-03-16 14:00 UTC),
+        limit,
+    }
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn suggests_common_free_slots(pool: PgPool) {
+    grant_busy_visibility(&pool, PKBPMJ_ID, HUBERT_ID)
+        .await
+        .unwrap();
+
+    let suggested = suggest_free_slots(HUBERT_ID, thursday_morning(vec![PKBPMJ_ID], 5), &pool)
+        .await
+        .unwrap();
+
+    // The last window, 13:15 - 14:00, is too short
+    assert_eq!(
+        suggested.slots,
+        vec![
+            TimeRange::new(
+                datetime!(2023-03-16 8:00 UTC),
+                datetime!(2023-03-16 9:45 UTC)
+            ),
+            TimeRange::new(
+                datetime!(2023-03-16 10:30 UTC),
+                datetime!(2023-03-16 11:40 UTC)
+            ),
+        ]
+    );
+
+    let suggested = suggest_free_slots(HUBERT_ID, thursday_morning(vec![PKBPMJ_ID], 1), &pool)
+        .await
+        .unwrap();
+    assert_eq!(suggested.slots.len(), 1);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn suggests_only_with_shared_busy_times(pool: PgPool) {
+    let res = suggest_free_slots(HUBERT_ID, thursday_morning(vec![PKBPMJ_ID], 5), &pool).await;
+
+    assert!(matches!(res, Err(EventError::MismatchedPrivileges)));
+}
@@ -0,0 +1,46 @@
+mod tools;
+
+use reqwest::header::{ACCEPT_ENCODING, CONTENT_ENCODING};
+use reqwest::StatusCode;
+use sqlx::PgPool;
+use tools::AppData;
+use tracing_test::traced_test;
+
+const OPENAPI: &str = "/api-doc/openapi.json";
+
+#[traced_test]
+#[sqlx::test]
+async fn compresses_large_responses(pool: PgPool) {
+    let app = AppData::new(pool).await;
+    let client = app.client();
+
+    for (accepted, encoding) in [("gzip", "gzip"), ("br", "br"), ("br;q=0, gzip", "gzip")] {
+        let res = client
+            .get(app.api(OPENAPI))
+            .header(ACCEPT_ENCODING, accepted)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[CONTENT_ENCODING], encoding, "{accepted}");
+    }
+
+    let res = client.get(app.api(OPENAPI)).send().await.unwrap();
+    assert!(res.headers().get(CONTENT_ENCODING).is_none());
+}
+
+#[traced_test]
+#[sqlx::test]
+async fn compression_can_be_disabled(pool: PgPool) {
+    let app = AppData::with_modules(pool, |modules| modules.app.compression = vec![]).await;
+
+    let res = app
+        .client()
+        .get(app.api(OPENAPI))
+        .header(ACCEPT_ENCODING, "gzip, br")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(res.headers().get(CONTENT_ENCODING).is_none());
+}
@@ -0,0 +1,107 @@
+mod tools;
+
+use std::net::{SocketAddr, TcpListener};
+use std::time::Duration;
+
+use axum::routing::post;
+use axum::{Json, Router};
+use bimetable_http::modules::Modules;
+use reqwest::StatusCode;
+use secrecy::Secret;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tools::AppData;
+use tracing_test::traced_test;
+use uuid::Uuid;
+
+const HUBERT_ID: &str = "a9c5900e-a445-4888-8612-4a5c8cadbd9e";
+const FIZYKA_ID: &str = "fd1dcdf7-de06-4aad-ba6e-f2097217a5b1";
+
+/// Spawns a stand-in of the reporting service, passing on the received events.
+fn spawn_collector() -> (SocketAddr, mpsc::UnboundedReceiver<Value>) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let router = Router::new().route(
+        "/api/1/store/",
+        post(move |Json(event): Json<Value>| async move {
+            sender.send(event).unwrap();
+            StatusCode::OK
+        }),
+    );
+    tokio::spawn(async move {
+        axum::Server::from_tcp(listener)
+            .unwrap()
+            .serve(router.into_make_service())
+            .await
+            .unwrap()
+    });
+
+    (addr, receiver)
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn unexpected_errors_are_reported(pool: PgPool) {
+    let (collector, mut events) = spawn_collector();
+    // A file in place of the storage directory makes every export fail
+    let storage_dir = std::env::temp_dir().join(format!("bimetable-broken-{}", Uuid::new_v4()));
+    std::fs::write(&storage_dir, "").unwrap();
+
+    let app = AppData::with_modules(pool, |modules: &mut Modules| {
+        modules.app.error_reporting_dsn =
+            Some(Secret::from(format!("http://public@{collector}/1")));
+        modules.app.storage_dir = storage_dir.to_string_lossy().into_owned();
+    })
+    .await;
+    let user = app.login("hubhub").await;
+
+    let res = user
+        .post(app.api(&format!("/api/v1/events/{FIZYKA_ID}/export.ics")))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+    let event = timeout(Duration::from_secs(5), events.recv())
+        .await
+        .expect("No error was reported")
+        .unwrap();
+    assert_eq!(event["level"], "error");
+    assert_eq!(
+        event["request"]["url"],
+        format!("/api/v1/events/{FIZYKA_ID}/export.ics")
+    );
+    assert_eq!(event["user"]["id"], HUBERT_ID);
+    assert!(event["tags"]["trace_id"].is_string());
+    assert_ne!(event["message"]["formatted"], "Internal server error");
+
+    std::fs::remove_file(storage_dir).unwrap();
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn expected_errors_are_not_reported(pool: PgPool) {
+    let (collector, mut events) = spawn_collector();
+    let app = AppData::with_modules(pool, |modules: &mut Modules| {
+        modules.app.error_reporting_dsn =
+            Some(Secret::from(format!("http://public@{collector}/1")));
+    })
+    .await;
+
+    let res = app
+        .client()
+        .post(app.api("/auth/login"))
+        .json(&json!({ "login": "hubhub", "password": "wrong" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    assert!(timeout(Duration::from_millis(200), events.recv())
+        .await
+        .is_err());
+}
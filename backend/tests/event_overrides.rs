@@ -1,16 +1,25 @@
-use bimetable::modules::database::PgQuery;
-use bimetable::routes::events::models::{
-    Entry, EventFilter, Override, OverrideEvent, OverrideEventData,
+use bimetable_db::modules::database::PgQuery;
+use bimetable_db::utils::events::errors::EventError;
+use bimetable_db::utils::events::exe::{
+    create_one_event_override, create_one_occurrence_override, delete_one_event_permanently,
+    get_events_page, get_many_events, get_one_event_exceptions, lock_one_occurrence,
+    pause_one_event, split_one_event, unlock_one_occurrence, update_one_event_override_strategy,
 };
-use bimetable::utils::events::exe::{create_one_event_override, get_many_events};
-use bimetable::utils::events::models::TimeRange;
-use bimetable::utils::events::EventQuery;
-use sqlx::PgPool;
+use bimetable_db::utils::events::EventQuery;
+use bimetable_domain::api::events::{
+    EffectiveEntry, Entry, EventFilter, Override, OverrideEvent, OverrideEventData, PauseEvent,
+    SplitEvent, UpdateOverrideStrategy,
+};
+use bimetable_domain::events::models::{EntriesPage, OverrideStatus, OverrideStrategy, TimeRange};
+use bimetable_domain::events::occurrences::occurrence_id;
+use bimetable_domain::limits::{OverrideShiftLimit, RepetitionLimit};
+use sqlx::{query, PgPool};
 use time::macros::datetime;
 use time::Duration;
 use tracing_test::traced_test;
 use uuid::{uuid, Uuid};
 
+const ADIMAC_ID: Uuid = uuid!("910e81a9-56df-4c24-965a-13eff739f469");
 const PKBPMJ_ID: Uuid = uuid!("29e40c2a-7595-42d3-98e8-9fe93ce99972");
 const MABI19_ID: Uuid = uuid!("32190025-7c15-4adb-82fd-9acc3dc8e7b6");
 const HUBERT_ID: Uuid = uuid!("a9c5900e-a445-4888-8612-4a5c8cadbd9e");
@@ -29,14 +38,23 @@ async fn create_override_test(pool: PgPool) {
             description: Some("new desc".into()),
             starts_at: None,
             ends_at: None,
+            status: None,
+            added_participants: vec![],
+            excluded_participants: vec![],
         },
     };
-    create_one_event_override(&pool, HUBERT_ID, body, INFORMATYKA_ID)
-        .await
-        .unwrap();
+    create_one_event_override(
+        &pool,
+        HUBERT_ID,
+        body,
+        INFORMATYKA_ID,
+        OverrideShiftLimit::default(),
+    )
+    .await
+    .unwrap();
     let mut conn = pool.acquire().await.unwrap();
     let mut q = PgQuery::new(EventQuery::new(HUBERT_ID), &mut conn);
-    let res = q.get_overrides(vec![INFORMATYKA_ID]).await.unwrap();
+    let res = q.get_overrides(vec![INFORMATYKA_ID], false).await.unwrap();
     assert_eq!(res.len(), 1)
 }
 
@@ -51,13 +69,20 @@ async fn does_not_create_override_with_wrong_range(pool: PgPool) {
             description: Some("new desc".into()),
             starts_at: None,
             ends_at: None,
+            status: None,
+            added_participants: vec![],
+            excluded_participants: vec![],
         },
     };
-    assert!(
-        create_one_event_override(&pool, HUBERT_ID, body, INFORMATYKA_ID)
-            .await
-            .is_err()
+    assert!(create_one_event_override(
+        &pool,
+        HUBERT_ID,
+        body,
+        INFORMATYKA_ID,
+        OverrideShiftLimit::default()
     )
+    .await
+    .is_err())
 }
 
 #[traced_test]
@@ -71,14 +96,21 @@ async fn does_not_create_override_without_editing_privileges(pool: PgPool) {
             description: Some("new desc".into()),
             starts_at: None,
             ends_at: None,
+            status: None,
+            added_participants: vec![],
+            excluded_participants: vec![],
         },
     };
 
-    assert!(
-        create_one_event_override(&pool, MABI19_ID, body, INFORMATYKA_ID)
-            .await
-            .is_err()
+    assert!(create_one_event_override(
+        &pool,
+        MABI19_ID,
+        body,
+        INFORMATYKA_ID,
+        OverrideShiftLimit::default()
     )
+    .await
+    .is_err())
 }
 
 #[traced_test]
@@ -91,6 +123,7 @@ async fn get_entries_with_override_1(pool: PgPool) {
             datetime!(2023-03-26 23:59 UTC),
         ),
         EventFilter::Owned,
+        None,
         &pool,
     )
     .await
@@ -106,49 +139,79 @@ async fn get_entries_with_override_1(pool: PgPool) {
         vec![
             Entry {
                 event_id: FIZYKA_ID,
+                occurrence_id: occurrence_id(FIZYKA_ID, datetime!(2023-03-15 9:45 UTC)),
                 time_range: TimeRange {
                     start: datetime!(2023-03-15 9:45 UTC),
                     end: datetime!(2023-03-15 10:30 UTC),
                 },
+                can_edit: true,
+                is_locked: false,
                 recurrence_override: Some(Override {
                     name: None,
                     description: Some("Blok fizyki".into()),
                     starts_at: Some(Duration::minutes(-55)),
                     ends_at: Some(Duration::minutes(50)),
                     deleted_at: None,
+                    status: None,
                     created_at: datetime!(2023-04-01 8:00 UTC),
+                    added_participants: vec![],
+                    excluded_participants: vec![],
                 }),
+                effective: None,
+                overrides: vec![],
+                segment: None,
             },
             Entry {
                 event_id: FIZYKA_ID,
+                occurrence_id: occurrence_id(FIZYKA_ID, datetime!(2023-03-16 9:45 UTC)),
                 time_range: TimeRange {
                     start: datetime!(2023-03-16 9:45 UTC),
                     end: datetime!(2023-03-16 10:30 UTC),
                 },
+                can_edit: true,
+                is_locked: false,
                 recurrence_override: Some(Override {
                     name: None,
                     description: Some("Blok fizyki".into()),
                     starts_at: Some(Duration::minutes(-55)),
                     ends_at: Some(Duration::minutes(50)),
                     deleted_at: None,
+                    status: None,
                     created_at: datetime!(2023-04-01 8:00 UTC),
+                    added_participants: vec![],
+                    excluded_participants: vec![],
                 }),
+                effective: None,
+                overrides: vec![],
+                segment: None,
             },
             Entry {
                 event_id: FIZYKA_ID,
+                occurrence_id: occurrence_id(FIZYKA_ID, datetime!(2023-03-22 9:45 UTC)),
                 time_range: TimeRange {
                     start: datetime!(2023-03-22 9:45 UTC),
                     end: datetime!(2023-03-22 10:30 UTC),
                 },
+                can_edit: true,
+                is_locked: false,
                 recurrence_override: None,
+                effective: None,
+                overrides: vec![],
+                segment: None,
             },
             Entry {
                 event_id: FIZYKA_ID,
+                occurrence_id: occurrence_id(FIZYKA_ID, datetime!(2023-03-23 9:45 UTC)),
                 time_range: TimeRange {
                     start: datetime!(2023-03-23 9:45 UTC),
                     end: datetime!(2023-03-23 10:30 UTC),
                 },
+                can_edit: true,
+                is_locked: false,
                 recurrence_override: None,
+                effective: None,
+                overrides: vec![],
+                segment: None,
             }
         ]
     )
@@ -164,6 +227,7 @@ async fn get_entries_with_override_2(pool: PgPool) {
             datetime!(2024-01-07 23:59 UTC),
         ),
         EventFilter::Owned,
+        None,
         &pool,
     )
     .await
@@ -179,124 +243,199 @@ async fn get_entries_with_override_2(pool: PgPool) {
         vec![
             Entry {
                 event_id: MATEMATYKA_ID,
+                occurrence_id: occurrence_id(MATEMATYKA_ID, datetime!(2023-05-07 8:00 UTC)),
                 time_range: TimeRange {
                     start: datetime!(2023-05-07 8:00 UTC),
                     end: datetime!(2023-05-07 9:35 UTC),
                 },
+                can_edit: true,
+                is_locked: false,
                 recurrence_override: None,
+                effective: None,
+                overrides: vec![],
+                segment: None,
             },
             Entry {
                 event_id: MATEMATYKA_ID,
+                occurrence_id: occurrence_id(MATEMATYKA_ID, datetime!(2023-06-07 8:00 UTC)),
                 time_range: TimeRange {
                     start: datetime!(2023-06-07 8:00 UTC),
                     end: datetime!(2023-06-07 9:35 UTC),
                 },
+                can_edit: true,
+                is_locked: false,
                 recurrence_override: Some(Override {
                     name: Some("Polski".into()),
                     description: None,
                     starts_at: None,
                     ends_at: None,
                     deleted_at: None,
+                    status: None,
                     created_at: datetime!(2023-04-01 8:00 UTC),
+                    added_participants: vec![],
+                    excluded_participants: vec![],
                 }),
+                effective: None,
+                overrides: vec![],
+                segment: None,
             },
             Entry {
                 event_id: MATEMATYKA_ID,
+                occurrence_id: occurrence_id(MATEMATYKA_ID, datetime!(2023-07-07 8:00 UTC)),
                 time_range: TimeRange {
                     start: datetime!(2023-07-07 8:00 UTC),
                     end: datetime!(2023-07-07 9:35 UTC),
                 },
+                can_edit: true,
+                is_locked: false,
                 recurrence_override: Some(Override {
                     name: Some("Polski".into()),
                     description: None,
                     starts_at: None,
                     ends_at: None,
                     deleted_at: None,
+                    status: None,
                     created_at: datetime!(2023-04-01 8:00 UTC),
+                    added_participants: vec![],
+                    excluded_participants: vec![],
                 }),
+                effective: None,
+                overrides: vec![],
+                segment: None,
             },
             Entry {
                 event_id: MATEMATYKA_ID,
+                occurrence_id: occurrence_id(MATEMATYKA_ID, datetime!(2023-08-07 8:00 UTC)),
                 time_range: TimeRange {
                     start: datetime!(2023-08-07 8:00 UTC),
                     end: datetime!(2023-08-07 9:35 UTC),
                 },
+                can_edit: true,
+                is_locked: false,
                 recurrence_override: Some(Override {
                     name: Some("Polski".into()),
                     description: None,
                     starts_at: None,
                     ends_at: None,
                     deleted_at: None,
+                    status: None,
                     created_at: datetime!(2023-04-01 8:00 UTC),
+                    added_participants: vec![],
+                    excluded_participants: vec![],
                 }),
+                effective: None,
+                overrides: vec![],
+                segment: None,
             },
             Entry {
                 event_id: MATEMATYKA_ID,
+                occurrence_id: occurrence_id(MATEMATYKA_ID, datetime!(2023-09-07 8:00 UTC)),
                 time_range: TimeRange {
                     start: datetime!(2023-09-07 8:00 UTC),
                     end: datetime!(2023-09-07 9:35 UTC),
                 },
+                can_edit: true,
+                is_locked: false,
                 recurrence_override: Some(Override {
                     name: Some("Polski".into()),
                     description: None,
                     starts_at: None,
                     ends_at: None,
                     deleted_at: None,
+                    status: None,
                     created_at: datetime!(2023-04-01 8:00 UTC),
+                    added_participants: vec![],
+                    excluded_participants: vec![],
                 }),
+                effective: None,
+                overrides: vec![],
+                segment: None,
             },
             Entry {
                 event_id: MATEMATYKA_ID,
+                occurrence_id: occurrence_id(MATEMATYKA_ID, datetime!(2023-10-07 8:00 UTC)),
                 time_range: TimeRange {
                     start: datetime!(2023-10-07 8:00 UTC),
                     end: datetime!(2023-10-07 9:35 UTC),
                 },
+                can_edit: true,
+                is_locked: false,
                 recurrence_override: Some(Override {
                     name: Some("Geografia".into()),
                     description: Some("Wyciagamy kartelinki".into()),
                     starts_at: None,
                     ends_at: None,
                     deleted_at: None,
+                    status: None,
                     created_at: datetime!(2023-04-01 8:01 UTC),
+                    added_participants: vec![],
+                    excluded_participants: vec![],
                 }),
+                effective: None,
+                overrides: vec![],
+                segment: None,
             },
             Entry {
                 event_id: MATEMATYKA_ID,
+                occurrence_id: occurrence_id(MATEMATYKA_ID, datetime!(2023-11-07 8:00 UTC)),
                 time_range: TimeRange {
                     start: datetime!(2023-11-07 8:00 UTC),
                     end: datetime!(2023-11-07 9:35 UTC),
                 },
+                can_edit: true,
+                is_locked: false,
                 recurrence_override: Some(Override {
                     name: Some("Geografia".into()),
                     description: Some("Wyciagamy kartelinki".into()),
                     starts_at: None,
                     ends_at: None,
                     deleted_at: None,
+                    status: None,
                     created_at: datetime!(2023-04-01 8:01 UTC),
+                    added_participants: vec![],
+                    excluded_participants: vec![],
                 }),
+                effective: None,
+                overrides: vec![],
+                segment: None,
             },
             Entry {
                 event_id: MATEMATYKA_ID,
+                occurrence_id: occurrence_id(MATEMATYKA_ID, datetime!(2023-12-07 8:00 UTC)),
                 time_range: TimeRange {
                     start: datetime!(2023-12-07 8:00 UTC),
                     end: datetime!(2023-12-07 9:35 UTC),
                 },
+                can_edit: true,
+                is_locked: false,
                 recurrence_override: Some(Override {
                     name: Some("Geografia".into()),
                     description: Some("Wyciagamy kartelinki".into()),
                     starts_at: None,
                     ends_at: None,
                     deleted_at: None,
+                    status: None,
                     created_at: datetime!(2023-04-01 8:01 UTC),
+                    added_participants: vec![],
+                    excluded_participants: vec![],
                 }),
+                effective: None,
+                overrides: vec![],
+                segment: None,
             },
             Entry {
                 event_id: MATEMATYKA_ID,
+                occurrence_id: occurrence_id(MATEMATYKA_ID, datetime!(2024-01-07 8:00 UTC)),
                 time_range: TimeRange {
                     start: datetime!(2024-01-07 8:00 UTC),
                     end: datetime!(2024-01-07 9:35 UTC),
                 },
+                can_edit: true,
+                is_locked: false,
                 recurrence_override: None,
+                effective: None,
+                overrides: vec![],
+                segment: None,
             },
         ]
     )
@@ -312,6 +451,7 @@ async fn override_with_range_overlapping_with_search_test(pool: PgPool) {
             datetime!(2023-03-16 8:51 UTC),
         ),
         EventFilter::Owned,
+        None,
         &pool,
     )
     .await
@@ -327,34 +467,886 @@ async fn override_with_range_overlapping_with_search_test(pool: PgPool) {
         vec![
             Entry {
                 event_id: FIZYKA_ID,
+                occurrence_id: occurrence_id(FIZYKA_ID, datetime!(2023-03-15 9:45 UTC)),
                 time_range: TimeRange {
                     start: datetime!(2023-03-15 9:45 UTC),
                     end: datetime!(2023-03-15 10:30 UTC),
                 },
+                can_edit: true,
+                is_locked: false,
                 recurrence_override: Some(Override {
                     name: None,
                     description: Some("Blok fizyki".into()),
                     starts_at: Some(Duration::minutes(-55)),
                     ends_at: Some(Duration::minutes(50)),
                     deleted_at: None,
+                    status: None,
                     created_at: datetime!(2023-04-01 8:00 UTC),
+                    added_participants: vec![],
+                    excluded_participants: vec![],
                 }),
+                effective: None,
+                overrides: vec![],
+                segment: None,
             },
             Entry {
                 event_id: FIZYKA_ID,
+                occurrence_id: occurrence_id(FIZYKA_ID, datetime!(2023-03-16 9:45 UTC)),
                 time_range: TimeRange {
                     start: datetime!(2023-03-16 9:45 UTC),
                     end: datetime!(2023-03-16 10:30 UTC),
                 },
+                can_edit: true,
+                is_locked: false,
                 recurrence_override: Some(Override {
                     name: None,
                     description: Some("Blok fizyki".into()),
                     starts_at: Some(Duration::minutes(-55)),
                     ends_at: Some(Duration::minutes(50)),
                     deleted_at: None,
+                    status: None,
                     created_at: datetime!(2023-04-01 8:00 UTC),
+                    added_participants: vec![],
+                    excluded_participants: vec![],
                 }),
+                effective: None,
+                overrides: vec![],
+                segment: None,
             }
         ]
     )
 }
+
+fn substitution(added: Vec<Uuid>, excluded: Vec<Uuid>) -> OverrideEvent {
+    OverrideEvent {
+        override_starts_at: datetime!(2023-03-22 9:45 UTC),
+        override_ends_at: datetime!(2023-03-22 10:30 UTC),
+        data: OverrideEventData {
+            name: None,
+            description: Some("Zastepstwo".into()),
+            starts_at: None,
+            ends_at: None,
+            status: None,
+            added_participants: added,
+            excluded_participants: excluded,
+        },
+    }
+}
+
+async fn fizyka_entries(pool: &PgPool, user_id: Uuid, filter: EventFilter) -> Vec<Entry> {
+    get_many_events(
+        user_id,
+        TimeRange::new(
+            datetime!(2023-03-20 0:00 UTC),
+            datetime!(2023-03-26 23:59 UTC),
+        ),
+        filter,
+        None,
+        pool,
+    )
+    .await
+    .unwrap()
+    .entries
+    .into_iter()
+    .filter(|entry| entry.event_id == FIZYKA_ID)
+    .collect()
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events", "overrides"))]
+async fn excluded_participant_does_not_see_entry(pool: PgPool) {
+    create_one_event_override(
+        &pool,
+        PKBPMJ_ID,
+        substitution(vec![], vec![HUBERT_ID]),
+        FIZYKA_ID,
+        OverrideShiftLimit::default(),
+    )
+    .await
+    .unwrap();
+
+    let hubert_entries = fizyka_entries(&pool, HUBERT_ID, EventFilter::Shared).await;
+    assert_eq!(hubert_entries.len(), 1);
+    assert_eq!(
+        hubert_entries[0].time_range.start,
+        datetime!(2023-03-23 9:45 UTC)
+    );
+
+    let owner_entries = fizyka_entries(&pool, PKBPMJ_ID, EventFilter::Owned).await;
+    assert_eq!(owner_entries.len(), 2);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events", "overrides"))]
+async fn guest_sees_only_added_entry(pool: PgPool) {
+    create_one_event_override(
+        &pool,
+        PKBPMJ_ID,
+        substitution(vec![MABI19_ID], vec![]),
+        FIZYKA_ID,
+        OverrideShiftLimit::default(),
+    )
+    .await
+    .unwrap();
+
+    let events = get_many_events(
+        MABI19_ID,
+        TimeRange::new(
+            datetime!(2023-03-20 0:00 UTC),
+            datetime!(2023-03-26 23:59 UTC),
+        ),
+        EventFilter::Shared,
+        None,
+        &pool,
+    )
+    .await
+    .unwrap();
+    let guest_event = events.events.get(&FIZYKA_ID).unwrap();
+    assert!(!guest_event.is_owned && !guest_event.can_edit);
+
+    let guest_entries = fizyka_entries(&pool, MABI19_ID, EventFilter::Shared).await;
+    assert_eq!(guest_entries.len(), 1);
+    assert_eq!(
+        guest_entries[0].time_range.start,
+        datetime!(2023-03-22 9:45 UTC)
+    );
+
+    let outside_override = get_many_events(
+        MABI19_ID,
+        TimeRange::new(
+            datetime!(2023-03-27 0:00 UTC),
+            datetime!(2023-04-02 23:59 UTC),
+        ),
+        EventFilter::Shared,
+        None,
+        &pool,
+    )
+    .await
+    .unwrap();
+    assert!(!outside_override.events.contains_key(&FIZYKA_ID));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events", "overrides"))]
+async fn does_not_create_override_adding_and_excluding_participant(pool: PgPool) {
+    assert!(create_one_event_override(
+        &pool,
+        PKBPMJ_ID,
+        substitution(vec![HUBERT_ID], vec![HUBERT_ID]),
+        FIZYKA_ID,
+        OverrideShiftLimit::default(),
+    )
+    .await
+    .is_err())
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events", "overrides"))]
+async fn does_not_create_override_emptying_entry(pool: PgPool) {
+    let body = OverrideEvent {
+        override_starts_at: datetime!(2023-03-14 11:40 UTC),
+        override_ends_at: datetime!(2023-03-15 13:15 UTC),
+        data: OverrideEventData {
+            name: None,
+            description: None,
+            starts_at: Some(Duration::days(1)),
+            ends_at: None,
+            status: None,
+            added_participants: vec![],
+            excluded_participants: vec![],
+        },
+    };
+    let res = create_one_event_override(
+        &pool,
+        HUBERT_ID,
+        body,
+        INFORMATYKA_ID,
+        OverrideShiftLimit::default(),
+    )
+    .await;
+
+    assert!(matches!(res, Err(EventError::InvalidData(_))));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events", "overrides"))]
+async fn does_not_create_override_beyond_shift_limit(pool: PgPool) {
+    let body = OverrideEvent {
+        override_starts_at: datetime!(2023-03-14 11:40 UTC),
+        override_ends_at: datetime!(2023-03-15 13:15 UTC),
+        data: OverrideEventData {
+            name: None,
+            description: None,
+            starts_at: Some(Duration::hours(3)),
+            ends_at: Some(Duration::hours(3)),
+            status: None,
+            added_participants: vec![],
+            excluded_participants: vec![],
+        },
+    };
+    let res = create_one_event_override(
+        &pool,
+        HUBERT_ID,
+        body,
+        INFORMATYKA_ID,
+        OverrideShiftLimit(Duration::hours(2)),
+    )
+    .await;
+
+    assert!(matches!(res, Err(EventError::InvalidData(_))));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events", "overrides"))]
+async fn entries_resolve_effective_payload(pool: PgPool) {
+    let page = get_events_page(
+        PKBPMJ_ID,
+        TimeRange::new(
+            datetime!(2023-03-13 0:00 UTC),
+            datetime!(2023-03-19 0:00 UTC),
+        ),
+        EventFilter::Owned,
+        None,
+        EntriesPage {
+            effective: true,
+            ..Default::default()
+        },
+        &pool,
+    )
+    .await
+    .unwrap();
+    let effective: Vec<EffectiveEntry> = page
+        .events
+        .entries
+        .into_iter()
+        .filter(|entry| entry.event_id == FIZYKA_ID)
+        .filter_map(|entry| entry.effective)
+        .collect();
+
+    assert_eq!(
+        effective[0],
+        EffectiveEntry {
+            name: "Fizyka".into(),
+            description: Some("Blok fizyki".into()),
+            time_range: TimeRange::new(
+                datetime!(2023-03-15 8:50 UTC),
+                datetime!(2023-03-15 11:20 UTC)
+            ),
+            status: None,
+        }
+    );
+    assert_eq!(effective.len(), 2);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events", "overrides"))]
+async fn deleting_event_removes_its_overrides(pool: PgPool) {
+    delete_one_event_permanently(&pool, PKBPMJ_ID, FIZYKA_ID)
+        .await
+        .unwrap();
+
+    let remaining = query!(
+        "SELECT COUNT(*) AS \"count!\" FROM event_overrides WHERE event_id = $1",
+        FIZYKA_ID
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap()
+    .count;
+    assert_eq!(remaining, 0);
+}
+
+/// Renames the Fizyka entry of 2023-03-15, inside the fixture override.
+fn renamed_fizyka() -> OverrideEvent {
+    OverrideEvent {
+        override_starts_at: datetime!(2023-03-15 9:45 UTC),
+        override_ends_at: datetime!(2023-03-15 10:30 UTC),
+        data: OverrideEventData {
+            name: Some("Fizyka rozszerzona".into()),
+            description: None,
+            starts_at: None,
+            ends_at: None,
+            status: None,
+            added_participants: vec![],
+            excluded_participants: vec![],
+        },
+    }
+}
+
+async fn fizyka_week_entries(pool: &PgPool) -> Vec<Entry> {
+    get_events_page(
+        PKBPMJ_ID,
+        TimeRange::new(
+            datetime!(2023-03-13 0:00 UTC),
+            datetime!(2023-03-19 0:00 UTC),
+        ),
+        EventFilter::Owned,
+        None,
+        EntriesPage {
+            all_overrides: true,
+            ..Default::default()
+        },
+        pool,
+    )
+    .await
+    .unwrap()
+    .events
+    .entries
+    .into_iter()
+    .filter(|entry| entry.event_id == FIZYKA_ID)
+    .collect()
+}
+
+async fn set_fizyka_strategy(pool: &PgPool, strategy: OverrideStrategy) {
+    update_one_event_override_strategy(
+        pool,
+        PKBPMJ_ID,
+        UpdateOverrideStrategy { strategy },
+        FIZYKA_ID,
+    )
+    .await
+    .unwrap();
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events", "overrides"))]
+async fn latest_override_applies_by_default(pool: PgPool) {
+    create_one_event_override(
+        &pool,
+        PKBPMJ_ID,
+        renamed_fizyka(),
+        FIZYKA_ID,
+        OverrideShiftLimit::default(),
+    )
+    .await
+    .unwrap();
+
+    let entries = fizyka_week_entries(&pool).await;
+    let ovr = entries[0].recurrence_override.as_ref().unwrap();
+    assert_eq!(ovr.name.as_deref(), Some("Fizyka rozszerzona"));
+    assert_eq!(ovr.description, None);
+    assert_eq!(entries[0].overrides.len(), 2);
+    assert_eq!(entries[0].overrides[1], *ovr);
+    assert_eq!(entries[1].overrides.len(), 1);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events", "overrides"))]
+async fn merge_strategy_layers_overrides(pool: PgPool) {
+    set_fizyka_strategy(&pool, OverrideStrategy::Merge).await;
+    create_one_event_override(
+        &pool,
+        PKBPMJ_ID,
+        renamed_fizyka(),
+        FIZYKA_ID,
+        OverrideShiftLimit::default(),
+    )
+    .await
+    .unwrap();
+
+    let entries = fizyka_week_entries(&pool).await;
+    let ovr = entries[0].recurrence_override.as_ref().unwrap();
+    assert_eq!(ovr.name.as_deref(), Some("Fizyka rozszerzona"));
+    assert_eq!(ovr.description.as_deref(), Some("Blok fizyki"));
+    assert_eq!(ovr.starts_at, Some(Duration::minutes(-55)));
+    assert_eq!(entries[1].recurrence_override.as_ref().unwrap().name, None);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events", "overrides"))]
+async fn reject_strategy_refuses_shadowing_override(pool: PgPool) {
+    set_fizyka_strategy(&pool, OverrideStrategy::Reject).await;
+    let mut shadowing = renamed_fizyka();
+    shadowing.override_starts_at = datetime!(2023-03-14 0:00 UTC);
+    shadowing.override_ends_at = datetime!(2023-03-17 0:00 UTC);
+
+    let res = create_one_event_override(
+        &pool,
+        PKBPMJ_ID,
+        shadowing,
+        FIZYKA_ID,
+        OverrideShiftLimit::default(),
+    )
+    .await;
+    assert!(matches!(res, Err(EventError::ShadowingOverride)));
+
+    create_one_event_override(
+        &pool,
+        PKBPMJ_ID,
+        renamed_fizyka(),
+        FIZYKA_ID,
+        OverrideShiftLimit::default(),
+    )
+    .await
+    .unwrap();
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn strangers_cannot_tell_events_exist_when_overriding(pool: PgPool) {
+    let override_as = |user_id, event_id| {
+        create_one_event_override(
+            &pool,
+            user_id,
+            renamed_fizyka(),
+            event_id,
+            OverrideShiftLimit::default(),
+        )
+    };
+
+    let res = override_as(MABI19_ID, FIZYKA_ID).await;
+    assert!(matches!(res, Err(EventError::NotFound)));
+
+    let res = override_as(MABI19_ID, Uuid::new_v4()).await;
+    assert!(matches!(res, Err(EventError::NotFound)));
+
+    // Read-only participants know about the event
+    let res = override_as(ADIMAC_ID, MATEMATYKA_ID).await;
+    assert!(matches!(res, Err(EventError::MismatchedPrivileges)));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events", "overrides"))]
+async fn editor_cannot_change_override_strategy(pool: PgPool) {
+    let res = update_one_event_override_strategy(
+        &pool,
+        HUBERT_ID,
+        UpdateOverrideStrategy {
+            strategy: OverrideStrategy::Merge,
+        },
+        FIZYKA_ID,
+    )
+    .await;
+
+    assert!(matches!(res, Err(EventError::MismatchedPrivileges)));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events", "overrides"))]
+async fn event_exceptions_list_overrides_and_pauses(pool: PgPool) {
+    query!(
+        r#"
+            UPDATE event_overrides SET deleted_at = '2023-04-02 8:00'
+            WHERE event_id = $1
+        "#,
+        FIZYKA_ID,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    pause_one_event(
+        &pool,
+        PKBPMJ_ID,
+        PauseEvent {
+            starts_at: datetime!(2023-03-20 0:00 UTC),
+            ends_at: datetime!(2023-03-27 0:00 UTC),
+        },
+        FIZYKA_ID,
+    )
+    .await
+    .unwrap();
+
+    let exceptions = get_one_event_exceptions(&pool, HUBERT_ID, FIZYKA_ID)
+        .await
+        .unwrap();
+
+    assert_eq!(exceptions.overrides.len(), 1);
+    let ovr = &exceptions.overrides[0];
+    assert_eq!(ovr.override_starts_at, datetime!(2023-03-15 9:45 UTC));
+    assert_eq!(ovr.override_ends_at, datetime!(2023-03-16 10:30 UTC));
+    assert_eq!(ovr.data.description.as_deref(), Some("Blok fizyki"));
+    assert_eq!(ovr.data.deleted_at, Some(datetime!(2023-04-02 8:00 UTC)));
+    assert_eq!(
+        exceptions.pauses,
+        vec![TimeRange::new(
+            datetime!(2023-03-20 0:00 UTC),
+            datetime!(2023-03-27 0:00 UTC)
+        )]
+    );
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events", "overrides"))]
+async fn event_exceptions_of_missing_event(pool: PgPool) {
+    let res = get_one_event_exceptions(&pool, PKBPMJ_ID, Uuid::new_v4()).await;
+
+    assert!(matches!(res, Err(EventError::NotFound)));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events", "overrides"))]
+async fn override_entry_by_occurrence_id(pool: PgPool) {
+    let entry_id = occurrence_id(FIZYKA_ID, datetime!(2023-03-22 9:45 UTC));
+    create_one_occurrence_override(
+        &pool,
+        PKBPMJ_ID,
+        renamed_fizyka().data,
+        FIZYKA_ID,
+        entry_id,
+        OverrideShiftLimit::default(),
+        RepetitionLimit::default(),
+    )
+    .await
+    .unwrap();
+
+    let entries = fizyka_entries(&pool, PKBPMJ_ID, EventFilter::Owned).await;
+    let entry = entries
+        .iter()
+        .find(|entry| entry.occurrence_id == entry_id)
+        .unwrap();
+    let ovr = entry.recurrence_override.as_ref().unwrap();
+    assert_eq!(ovr.name.as_deref(), Some("Fizyka rozszerzona"));
+    assert_eq!(entry.time_range.start, datetime!(2023-03-22 9:45 UTC));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events", "overrides"))]
+async fn does_not_override_unknown_occurrence(pool: PgPool) {
+    let res = create_one_occurrence_override(
+        &pool,
+        PKBPMJ_ID,
+        renamed_fizyka().data,
+        FIZYKA_ID,
+        occurrence_id(FIZYKA_ID, datetime!(2023-03-22 9:50 UTC)),
+        OverrideShiftLimit::default(),
+        RepetitionLimit::default(),
+    )
+    .await;
+
+    assert!(matches!(res, Err(EventError::NotFound)));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events", "overrides"))]
+async fn cancelled_status_is_exposed_on_entry(pool: PgPool) {
+    let entry_id = occurrence_id(FIZYKA_ID, datetime!(2023-03-22 9:45 UTC));
+    let data = OverrideEventData {
+        name: None,
+        description: Some("Nauczyciel chory".into()),
+        starts_at: None,
+        ends_at: None,
+        status: Some(OverrideStatus::Cancelled),
+        added_participants: vec![],
+        excluded_participants: vec![],
+    };
+    create_one_occurrence_override(
+        &pool,
+        PKBPMJ_ID,
+        data,
+        FIZYKA_ID,
+        entry_id,
+        OverrideShiftLimit::default(),
+        RepetitionLimit::default(),
+    )
+    .await
+    .unwrap();
+
+    let entries = fizyka_entries(&pool, PKBPMJ_ID, EventFilter::Owned).await;
+    let entry = entries
+        .iter()
+        .find(|entry| entry.occurrence_id == entry_id)
+        .unwrap();
+    let ovr = entry.recurrence_override.as_ref().unwrap();
+    assert_eq!(ovr.status, Some(OverrideStatus::Cancelled));
+    assert!(ovr.is_cancelled());
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events", "overrides"))]
+async fn does_not_create_override_with_fields_forbidden_by_status(pool: PgPool) {
+    let data = OverrideEventData {
+        status: Some(OverrideStatus::Cancelled),
+        ..renamed_fizyka().data
+    };
+    let res = create_one_occurrence_override(
+        &pool,
+        PKBPMJ_ID,
+        data,
+        FIZYKA_ID,
+        occurrence_id(FIZYKA_ID, datetime!(2023-03-22 9:45 UTC)),
+        OverrideShiftLimit::default(),
+        RepetitionLimit::default(),
+    )
+    .await;
+
+    assert!(matches!(res, Err(EventError::InvalidData(_))));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn editor_cannot_override_locked_occurrence(pool: PgPool) {
+    let exam = occurrence_id(FIZYKA_ID, datetime!(2023-03-15 9:45 UTC));
+    assert!(matches!(
+        lock_one_occurrence(
+            &pool,
+            HUBERT_ID,
+            FIZYKA_ID,
+            exam,
+            RepetitionLimit::default()
+        )
+        .await,
+        Err(EventError::MismatchedPrivileges)
+    ));
+    lock_one_occurrence(
+        &pool,
+        PKBPMJ_ID,
+        FIZYKA_ID,
+        exam,
+        RepetitionLimit::default(),
+    )
+    .await
+    .unwrap();
+
+    let res = create_one_event_override(
+        &pool,
+        HUBERT_ID,
+        renamed_fizyka(),
+        FIZYKA_ID,
+        OverrideShiftLimit::default(),
+    )
+    .await;
+    assert!(matches!(res, Err(EventError::LockedOccurrence)));
+
+    let week = TimeRange::new(
+        datetime!(2023-03-13 0:00 UTC),
+        datetime!(2023-03-19 0:00 UTC),
+    );
+    let entries = get_many_events(HUBERT_ID, week, EventFilter::Shared, None, &pool)
+        .await
+        .unwrap()
+        .entries;
+    let locked: Vec<_> = entries.iter().filter(|entry| entry.is_locked).collect();
+    assert_eq!(locked.len(), 1);
+    assert_eq!(locked[0].occurrence_id, exam);
+    assert!(!locked[0].can_edit);
+    let entries = get_many_events(PKBPMJ_ID, week, EventFilter::Owned, None, &pool)
+        .await
+        .unwrap()
+        .entries;
+    assert!(entries
+        .iter()
+        .any(|entry| entry.occurrence_id == exam && entry.is_locked && entry.can_edit));
+
+    // Owners may still override locked entries
+    create_one_event_override(
+        &pool,
+        PKBPMJ_ID,
+        renamed_fizyka(),
+        FIZYKA_ID,
+        OverrideShiftLimit::default(),
+    )
+    .await
+    .unwrap();
+
+    unlock_one_occurrence(&pool, PKBPMJ_ID, FIZYKA_ID, exam)
+        .await
+        .unwrap();
+    assert!(matches!(
+        unlock_one_occurrence(&pool, PKBPMJ_ID, FIZYKA_ID, exam).await,
+        Err(EventError::NotFound)
+    ));
+    create_one_event_override(
+        &pool,
+        HUBERT_ID,
+        renamed_fizyka(),
+        FIZYKA_ID,
+        OverrideShiftLimit::default(),
+    )
+    .await
+    .unwrap();
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events", "overrides"))]
+async fn split_moves_later_overrides_to_successor(pool: PgPool) {
+    let body = OverrideEvent {
+        override_starts_at: datetime!(2023-03-29 09:45 UTC),
+        override_ends_at: datetime!(2023-03-30 10:30 UTC),
+        data: OverrideEventData {
+            name: Some("Blok fizyki".into()),
+            description: None,
+            starts_at: None,
+            ends_at: None,
+            status: None,
+            added_participants: vec![],
+            excluded_participants: vec![],
+        },
+    };
+    create_one_event_override(
+        &pool,
+        HUBERT_ID,
+        body,
+        FIZYKA_ID,
+        OverrideShiftLimit::default(),
+    )
+    .await
+    .unwrap();
+
+    let body = SplitEvent {
+        split_at: datetime!(2023-03-22 09:45 UTC),
+        starts_at: datetime!(2023-03-22 11:00 UTC),
+        ends_at: datetime!(2023-03-22 12:00 UTC),
+    };
+    let successor_id = split_one_event(&pool, HUBERT_ID, body, FIZYKA_ID)
+        .await
+        .unwrap();
+
+    let exceptions = get_one_event_exceptions(&pool, HUBERT_ID, FIZYKA_ID)
+        .await
+        .unwrap();
+    assert_eq!(exceptions.overrides.len(), 1);
+    assert_eq!(
+        exceptions.overrides[0].override_starts_at,
+        datetime!(2023-03-15 9:45 UTC)
+    );
+
+    let exceptions = get_one_event_exceptions(&pool, HUBERT_ID, successor_id)
+        .await
+        .unwrap();
+    assert_eq!(exceptions.overrides.len(), 1);
+    let moved = &exceptions.overrides[0];
+    assert_eq!(moved.override_starts_at, datetime!(2023-03-29 11:00 UTC));
+    assert_eq!(moved.override_ends_at, datetime!(2023-03-30 12:00 UTC));
+    assert_eq!(moved.data.name.as_deref(), Some("Blok fizyki"));
+}
+
+fn split_fizyka_at_third_week() -> SplitEvent {
+    SplitEvent {
+        split_at: datetime!(2023-03-22 09:45 UTC),
+        starts_at: datetime!(2023-03-22 11:00 UTC),
+        ends_at: datetime!(2023-03-22 12:00 UTC),
+    }
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn split_divides_pauses_at_split_point(pool: PgPool) {
+    for (starts_at, ends_at) in [
+        (
+            datetime!(2023-03-20 0:00 UTC),
+            datetime!(2023-03-27 0:00 UTC),
+        ),
+        (
+            datetime!(2023-04-03 0:00 UTC),
+            datetime!(2023-04-10 0:00 UTC),
+        ),
+    ] {
+        pause_one_event(
+            &pool,
+            PKBPMJ_ID,
+            PauseEvent { starts_at, ends_at },
+            FIZYKA_ID,
+        )
+        .await
+        .unwrap();
+    }
+
+    let successor_id = split_one_event(&pool, HUBERT_ID, split_fizyka_at_third_week(), FIZYKA_ID)
+        .await
+        .unwrap();
+
+    let exceptions = get_one_event_exceptions(&pool, HUBERT_ID, FIZYKA_ID)
+        .await
+        .unwrap();
+    assert_eq!(
+        exceptions.pauses,
+        vec![TimeRange::new(
+            datetime!(2023-03-20 0:00 UTC),
+            datetime!(2023-03-22 9:45 UTC)
+        )]
+    );
+
+    let exceptions = get_one_event_exceptions(&pool, HUBERT_ID, successor_id)
+        .await
+        .unwrap();
+    assert_eq!(
+        exceptions.pauses,
+        vec![
+            TimeRange::new(
+                datetime!(2023-03-22 9:45 UTC),
+                datetime!(2023-03-27 0:00 UTC)
+            ),
+            TimeRange::new(
+                datetime!(2023-04-03 0:00 UTC),
+                datetime!(2023-04-10 0:00 UTC)
+            ),
+        ]
+    );
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn split_keeps_override_strategy(pool: PgPool) {
+    set_fizyka_strategy(&pool, OverrideStrategy::Reject).await;
+
+    let successor_id = split_one_event(&pool, HUBERT_ID, split_fizyka_at_third_week(), FIZYKA_ID)
+        .await
+        .unwrap();
+
+    let strategy = query!(
+        r#"
+            SELECT override_strategy FROM events
+            WHERE id = $1
+        "#,
+        successor_id,
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap()
+    .override_strategy;
+    assert_eq!(
+        OverrideStrategy::from_code(strategy),
+        Some(OverrideStrategy::Reject)
+    );
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn split_moves_later_locks_to_successor(pool: PgPool) {
+    let earlier = occurrence_id(FIZYKA_ID, datetime!(2023-03-15 9:45 UTC));
+    let later = occurrence_id(FIZYKA_ID, datetime!(2023-03-29 9:45 UTC));
+    for occurrence in [earlier, later] {
+        lock_one_occurrence(
+            &pool,
+            PKBPMJ_ID,
+            FIZYKA_ID,
+            occurrence,
+            RepetitionLimit::default(),
+        )
+        .await
+        .unwrap();
+    }
+
+    let successor_id = split_one_event(&pool, HUBERT_ID, split_fizyka_at_third_week(), FIZYKA_ID)
+        .await
+        .unwrap();
+
+    let entries = get_many_events(
+        HUBERT_ID,
+        TimeRange::new(
+            datetime!(2023-03-13 0:00 UTC),
+            datetime!(2023-04-02 0:00 UTC),
+        ),
+        EventFilter::Shared,
+        None,
+        &pool,
+    )
+    .await
+    .unwrap()
+    .entries;
+    let locked: Vec<_> = entries
+        .iter()
+        .filter(|entry| entry.is_locked)
+        .map(|entry| (entry.event_id, entry.occurrence_id))
+        .collect();
+    assert_eq!(
+        locked,
+        vec![
+            (FIZYKA_ID, earlier),
+            (
+                successor_id,
+                occurrence_id(successor_id, datetime!(2023-03-29 11:00 UTC))
+            ),
+        ]
+    );
+}
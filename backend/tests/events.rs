@@ -1,35 +1,63 @@
 use std::collections::HashMap;
 
-use bimetable::{
-    modules::database::PgQuery,
-    routes::events::models::{
-        CreateEvent, Entry, Event, EventData, EventFilter, EventPayload, Events,
-        OptionalEventData, UpdateEditPrivilege, UpdateEvent,
-    },
+use bimetable_db::{
+    modules::database::{lock_for_transaction, EventId, PgQuery, UserId, LOCK_TIMEOUT},
     utils::events::{
         exe::{
-            delete_one_event_permanently, delete_owner_from_event, delete_user_event,
-            get_many_events, set_event_ownership, update_user_editing_privileges,
+            delete_one_event_permanently, delete_one_event_temporally, delete_owner_from_event,
+            delete_user_event, get_events_page, get_many_events, get_one_event_including_deleted,
+            set_event_ownership, update_many_editing_privileges, update_user_editing_privileges,
         },
-        models::{RecurrenceRule, TimeRange},
         EventQuery,
     },
 };
+use bimetable_domain::{
+    api::events::{
+        CreateEvent, Entry, Event, EventData, EventFilter, EventPayload, Events, OptionalEventData,
+        UpdateEditPrivilege, UpdateEditPrivileges, UpdateEvent,
+    },
+    events::models::{EntriesPage, RecurrenceRule, TimeRange},
+};
 use sqlx::{query, PgPool};
 
-use bimetable::utils::events::exe::{create_new_event, get_one_event, update_one_event};
-use bimetable::utils::events::models::{EntriesSpan, RecurrenceRuleKind};
+use bimetable_db::modules::clock::{Clock, TestClock};
+use bimetable_db::modules::feed_cache::FeedCache;
+use bimetable_db::modules::outbox::{self, Topic};
+use bimetable_db::utils::events::digest::{
+    delete_event_digest, get_event_digest, send_due_digests, set_event_digest,
+};
+use bimetable_db::utils::events::errors::EventError;
+use bimetable_db::utils::events::exe::{
+    create_event_feed_token, create_new_event, get_event_feed, get_one_event, lock_one_occurrence,
+    pause_one_event, revoke_event_feed_tokens, split_one_event, update_event_co_owner,
+    update_one_event, update_one_event_recurrence,
+};
+use bimetable_db::utils::events::UserEvent;
+use bimetable_domain::api::events::{
+    CreateEventResult, DigestFrequency, PauseEvent, RecurrenceEndsAt, RecurrenceRuleSchema,
+    SplitEvent, TimeRules, UpdateCoOwner, UpdateEventDigest, UpdateRecurrence,
+};
+use bimetable_domain::events::models::{EntriesSpan, RecurrenceRuleKind};
+use bimetable_domain::events::occurrences::occurrence_id;
+use bimetable_domain::limits::RepetitionLimit;
+use reqwest::{header, StatusCode};
+use serde_json::json;
 use time::macros::datetime;
+use time::{Duration, OffsetDateTime};
 use tracing::trace;
 use tracing_test::traced_test;
 use uuid::{uuid, Uuid};
 
 mod tools;
 
+use tools::AppData;
+
 const ADIMAC_ID: Uuid = uuid!("910e81a9-56df-4c24-965a-13eff739f469");
 const PKBPMJ_ID: Uuid = uuid!("29e40c2a-7595-42d3-98e8-9fe93ce99972");
 const MABI19_ID: Uuid = uuid!("32190025-7c15-4adb-82fd-9acc3dc8e7b6");
 const HUBERT_ID: Uuid = uuid!("a9c5900e-a445-4888-8612-4a5c8cadbd9e");
+const FIZYKA_ID: Uuid = uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
+const MATEMATYKA_ID: Uuid = uuid!("6d185de5-ddec-462a-aeea-7628f03d417b");
 
 #[traced_test]
 #[sqlx::test(fixtures("users", "events"))]
@@ -61,12 +89,15 @@ async fn create_event_test(pool: PgPool) {
         get_result,
         Some(Event {
             can_edit: true,
+            deleted_at: None,
+            exceptions: None,
             is_owned: true,
             payload: EventPayload {
                 name: "New event".to_string(),
                 description: None
             },
             recurrence_rule: None,
+            recurrence_summary: None,
             entries_start: datetime!(2023-03-07 19:00 UTC),
             entries_end: Some(datetime!(2023-03-07 20:00 UTC)),
         })
@@ -88,7 +119,11 @@ async fn does_not_create_event_with_wrong_time(pool: PgPool) {
         recurrence_rule: None,
     };
 
-    assert!(create_new_event(&pool, ADIMAC_ID, event).await.is_err())
+    assert!(
+        create_new_event(&pool, ADIMAC_ID, event, RepetitionLimit::default())
+            .await
+            .is_err()
+    )
 }
 
 #[traced_test]
@@ -101,6 +136,7 @@ async fn get_many_events_test(pool: PgPool) {
             datetime!(2023-03-13 0:00 UTC),
         ),
         EventFilter::All,
+        None,
         &pool,
     )
     .await
@@ -114,6 +150,8 @@ async fn get_many_events_test(pool: PgPool) {
                     uuid!("d63a1036-e59d-4b7c-a009-9b90a0e703d1"),
                     Event {
                         can_edit: true,
+                        deleted_at: None,
+                        exceptions: None,
                         is_owned: true,
                         recurrence_rule: Some(RecurrenceRule {
                             span: Some(EntriesSpan {
@@ -123,6 +161,9 @@ async fn get_many_events_test(pool: PgPool) {
                             interval: 1,
                             kind: RecurrenceRuleKind::Weekly { week_map: 40 },
                         }),
+                        recurrence_summary: Some(
+                            "Every week on Tue, Thu until 27 Apr 2023".to_string()
+                        ),
                         entries_start: datetime!(2023-03-07 11:40 UTC),
                         entries_end: Some(datetime!(2023-04-27 13:15 UTC)),
                         payload: EventPayload {
@@ -135,6 +176,8 @@ async fn get_many_events_test(pool: PgPool) {
                     uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1"),
                     Event {
                         can_edit: true,
+                        deleted_at: None,
+                        exceptions: None,
                         is_owned: false,
                         recurrence_rule: Some(RecurrenceRule {
                             span: Some(EntriesSpan {
@@ -144,6 +187,9 @@ async fn get_many_events_test(pool: PgPool) {
                             interval: 1,
                             kind: RecurrenceRuleKind::Weekly { week_map: 24 },
                         }),
+                        recurrence_summary: Some(
+                            "Every week on Wed, Thu until 27 Apr 2023".to_string()
+                        ),
                         entries_start: datetime!(2023-03-08 09:45 +00:00:00),
                         entries_end: Some(datetime!(2023-04-27 10:30 UTC)),
                         payload: EventPayload {
@@ -156,8 +202,11 @@ async fn get_many_events_test(pool: PgPool) {
                     uuid!("374ae0ab-d473-4752-b77f-cae55c69245c"),
                     Event {
                         can_edit: true,
+                        deleted_at: None,
+                        exceptions: None,
                         is_owned: false,
                         recurrence_rule: None,
+                        recurrence_summary: None,
                         entries_start: datetime!(2023-03-07 11:30:00.0 +00:00:00),
                         entries_end: Some(datetime!(2023-03-07 13:15:00.0 +00:00:00)),
                         payload: EventPayload {
@@ -176,41 +225,232 @@ async fn get_many_events_test(pool: PgPool) {
                 // },
                 Entry {
                     event_id: uuid!("d63a1036-e59d-4b7c-a009-9b90a0e703d1"),
+                    occurrence_id: occurrence_id(
+                        uuid!("d63a1036-e59d-4b7c-a009-9b90a0e703d1"),
+                        datetime!(2023-03-07 11:40 UTC)
+                    ),
                     time_range: TimeRange::new(
                         datetime!(2023-03-07 11:40 UTC),
                         datetime!(2023-03-07 13:15 UTC)
                     ),
+                    can_edit: true,
+                    is_locked: false,
                     recurrence_override: None,
+                    effective: None,
+                    overrides: vec![],
+                    segment: None,
                 },
                 Entry {
                     event_id: uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1"),
+                    occurrence_id: occurrence_id(
+                        uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1"),
+                        datetime!(2023-03-08 09:45 UTC)
+                    ),
                     time_range: TimeRange::new(
                         datetime!(2023-03-08 09:45 UTC),
                         datetime!(2023-03-08 10:30 UTC)
                     ),
+                    can_edit: true,
+                    is_locked: false,
                     recurrence_override: None,
+                    effective: None,
+                    overrides: vec![],
+                    segment: None,
                 },
                 Entry {
                     event_id: uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1"),
+                    occurrence_id: occurrence_id(
+                        uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1"),
+                        datetime!(2023-03-09 09:45 UTC)
+                    ),
                     time_range: TimeRange::new(
                         datetime!(2023-03-09 09:45 UTC),
                         datetime!(2023-03-09 10:30 UTC)
                     ),
+                    can_edit: true,
+                    is_locked: false,
                     recurrence_override: None,
+                    effective: None,
+                    overrides: vec![],
+                    segment: None,
                 },
                 Entry {
                     event_id: uuid!("d63a1036-e59d-4b7c-a009-9b90a0e703d1"),
+                    occurrence_id: occurrence_id(
+                        uuid!("d63a1036-e59d-4b7c-a009-9b90a0e703d1"),
+                        datetime!(2023-03-09 11:40 UTC)
+                    ),
                     time_range: TimeRange::new(
                         datetime!(2023-03-09 11:40 UTC),
                         datetime!(2023-03-09 13:15 UTC)
                     ),
+                    can_edit: true,
+                    is_locked: false,
                     recurrence_override: None,
+                    effective: None,
+                    overrides: vec![],
+                    segment: None,
                 },
             ],
         }
     )
 }
 
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn get_events_page_test(pool: PgPool) {
+    let search_range = TimeRange::new(
+        datetime!(2023-03-06 0:00 UTC),
+        datetime!(2023-03-13 0:00 UTC),
+    );
+    let get_page = |cursor| {
+        get_events_page(
+            HUBERT_ID,
+            search_range,
+            EventFilter::All,
+            None,
+            EntriesPage {
+                cursor,
+                limit: Some(2),
+                ..Default::default()
+            },
+            &pool,
+        )
+    };
+
+    let first = get_page(None).await.unwrap();
+    let starts: Vec<_> = first
+        .events
+        .entries
+        .iter()
+        .map(|entry| entry.time_range.start)
+        .collect();
+    assert_eq!(
+        starts,
+        vec![
+            datetime!(2023-03-07 11:40 UTC),
+            datetime!(2023-03-08 09:45 UTC)
+        ]
+    );
+    assert_eq!(first.next_cursor, Some(datetime!(2023-03-09 09:45 UTC)));
+    // One-off Infa starts before the next cursor
+    assert!(first
+        .events
+        .events
+        .contains_key(&uuid!("374ae0ab-d473-4752-b77f-cae55c69245c")));
+
+    let second = get_page(first.next_cursor).await.unwrap();
+    let starts: Vec<_> = second
+        .events
+        .entries
+        .iter()
+        .map(|entry| entry.time_range.start)
+        .collect();
+    assert_eq!(
+        starts,
+        vec![
+            datetime!(2023-03-09 09:45 UTC),
+            datetime!(2023-03-09 11:40 UTC)
+        ]
+    );
+    assert_eq!(second.next_cursor, None);
+    assert_eq!(second.events.events.len(), 2);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn get_events_only_test(pool: PgPool) {
+    let page = get_events_page(
+        HUBERT_ID,
+        TimeRange::new(
+            datetime!(2023-03-06 0:00 UTC),
+            datetime!(2023-03-13 0:00 UTC),
+        ),
+        EventFilter::All,
+        None,
+        EntriesPage {
+            events_only: true,
+            ..Default::default()
+        },
+        &pool,
+    )
+    .await
+    .unwrap();
+
+    assert!(page.events.entries.is_empty());
+    assert_eq!(page.events.events.len(), 3);
+    assert_eq!(page.next_cursor, None);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn get_events_of_selected_ids_test(pool: PgPool) {
+    let fizyka_id = uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
+    let informatyka_id = uuid!("d63a1036-e59d-4b7c-a009-9b90a0e703d1");
+    let page = get_events_page(
+        HUBERT_ID,
+        TimeRange::new(
+            datetime!(2023-03-06 0:00 UTC),
+            datetime!(2023-03-13 0:00 UTC),
+        ),
+        EventFilter::All,
+        None,
+        EntriesPage {
+            event_ids: Some(vec![fizyka_id, informatyka_id]),
+            ..Default::default()
+        },
+        &pool,
+    )
+    .await
+    .unwrap();
+
+    // Infa, shared with Hubert, is left out
+    let mut event_ids: Vec<Uuid> = page.events.events.keys().copied().collect();
+    event_ids.sort();
+    let mut expected = vec![fizyka_id, informatyka_id];
+    expected.sort();
+    assert_eq!(event_ids, expected);
+    assert!(!page.events.entries.is_empty());
+    assert!(page
+        .events
+        .entries
+        .iter()
+        .all(|entry| expected.contains(&entry.event_id)));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn get_events_names_only_test(pool: PgPool) {
+    let page = get_events_page(
+        PKBPMJ_ID,
+        TimeRange::new(
+            datetime!(2023-03-06 0:00 UTC),
+            datetime!(2023-03-13 0:00 UTC),
+        ),
+        EventFilter::Owned,
+        None,
+        EntriesPage {
+            names_only: true,
+            ..Default::default()
+        },
+        &pool,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(page.events.events.len(), 2);
+    assert!(!page.events.entries.is_empty());
+    assert!(page
+        .events
+        .events
+        .values()
+        .all(|event| event.payload.description.is_none()));
+    assert_eq!(
+        page.events.events[&FIZYKA_ID].payload.name,
+        "Fizyka".to_string()
+    );
+}
+
 #[traced_test]
 #[sqlx::test(fixtures("users", "events", "user_events"))]
 async fn get_owned_test(pool: PgPool) {
@@ -221,6 +461,7 @@ async fn get_owned_test(pool: PgPool) {
             datetime!(2023-03-13 0:00 UTC),
         ),
         EventFilter::Owned,
+        None,
         &pool,
     )
     .await
@@ -233,6 +474,8 @@ async fn get_owned_test(pool: PgPool) {
                 uuid!("d63a1036-e59d-4b7c-a009-9b90a0e703d1"),
                 Event {
                     can_edit: true,
+                    deleted_at: None,
+                    exceptions: None,
                     is_owned: true,
                     recurrence_rule: Some(RecurrenceRule {
                         span: Some(EntriesSpan {
@@ -242,6 +485,9 @@ async fn get_owned_test(pool: PgPool) {
                         interval: 1,
                         kind: RecurrenceRuleKind::Weekly { week_map: 40 },
                     }),
+                    recurrence_summary: Some(
+                        "Every week on Tue, Thu until 27 Apr 2023".to_string()
+                    ),
                     entries_start: datetime!(2023-03-07 11:40 +00:00:00),
                     entries_end: Some(datetime!(2023-04-27 13:15 UTC)),
                     payload: EventPayload {
@@ -253,19 +499,37 @@ async fn get_owned_test(pool: PgPool) {
             entries: vec![
                 Entry {
                     event_id: uuid!("d63a1036-e59d-4b7c-a009-9b90a0e703d1"),
+                    occurrence_id: occurrence_id(
+                        uuid!("d63a1036-e59d-4b7c-a009-9b90a0e703d1"),
+                        datetime!(2023-03-07 11:40 UTC)
+                    ),
                     time_range: TimeRange::new(
                         datetime!(2023-03-07 11:40 UTC),
                         datetime!(2023-03-07 13:15 UTC)
                     ),
+                    can_edit: true,
+                    is_locked: false,
                     recurrence_override: None,
+                    effective: None,
+                    overrides: vec![],
+                    segment: None,
                 },
                 Entry {
                     event_id: uuid!("d63a1036-e59d-4b7c-a009-9b90a0e703d1"),
+                    occurrence_id: occurrence_id(
+                        uuid!("d63a1036-e59d-4b7c-a009-9b90a0e703d1"),
+                        datetime!(2023-03-09 11:40 UTC)
+                    ),
                     time_range: TimeRange::new(
                         datetime!(2023-03-09 11:40 UTC),
                         datetime!(2023-03-09 13:15 UTC)
                     ),
+                    can_edit: true,
+                    is_locked: false,
                     recurrence_override: None,
+                    effective: None,
+                    overrides: vec![],
+                    segment: None,
                 },
             ],
         }
@@ -282,6 +546,7 @@ async fn get_shared_test(pool: PgPool) {
             datetime!(2023-03-13 0:00 UTC),
         ),
         EventFilter::Shared,
+        None,
         &pool,
     )
     .await
@@ -295,6 +560,8 @@ async fn get_shared_test(pool: PgPool) {
                     uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1"),
                     Event {
                         can_edit: true,
+                        deleted_at: None,
+                        exceptions: None,
                         is_owned: false,
                         recurrence_rule: Some(RecurrenceRule {
                             span: Some(EntriesSpan {
@@ -304,6 +571,9 @@ async fn get_shared_test(pool: PgPool) {
                             interval: 1,
                             kind: RecurrenceRuleKind::Weekly { week_map: 24 },
                         }),
+                        recurrence_summary: Some(
+                            "Every week on Wed, Thu until 27 Apr 2023".to_string()
+                        ),
                         entries_start: datetime!(2023-03-08 09:45 +00:00:00),
                         entries_end: Some(datetime!(2023-04-27 10:30 UTC)),
                         payload: EventPayload {
@@ -316,8 +586,11 @@ async fn get_shared_test(pool: PgPool) {
                     uuid!("374ae0ab-d473-4752-b77f-cae55c69245c"),
                     Event {
                         can_edit: true,
+                        deleted_at: None,
+                        exceptions: None,
                         is_owned: false,
                         recurrence_rule: None,
+                        recurrence_summary: None,
                         entries_start: datetime!(2023-03-07 11:30:00.0 +00:00:00),
                         entries_end: Some(datetime!(2023-03-07 13:15:00.0 +00:00:00)),
                         payload: EventPayload {
@@ -336,25 +609,63 @@ async fn get_shared_test(pool: PgPool) {
                 // },
                 Entry {
                     event_id: uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1"),
+                    occurrence_id: occurrence_id(
+                        uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1"),
+                        datetime!(2023-03-08 09:45 UTC)
+                    ),
                     time_range: TimeRange::new(
                         datetime!(2023-03-08 09:45 UTC),
                         datetime!(2023-03-08 10:30 UTC)
                     ),
+                    can_edit: true,
+                    is_locked: false,
                     recurrence_override: None,
+                    effective: None,
+                    overrides: vec![],
+                    segment: None,
                 },
                 Entry {
                     event_id: uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1"),
+                    occurrence_id: occurrence_id(
+                        uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1"),
+                        datetime!(2023-03-09 09:45 UTC)
+                    ),
                     time_range: TimeRange::new(
                         datetime!(2023-03-09 09:45 UTC),
                         datetime!(2023-03-09 10:30 UTC)
                     ),
+                    can_edit: true,
+                    is_locked: false,
                     recurrence_override: None,
+                    effective: None,
+                    overrides: vec![],
+                    segment: None,
                 },
             ],
         }
     )
 }
 
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn entries_carry_edit_privileges(pool: PgPool) {
+    let res = get_many_events(
+        MABI19_ID,
+        TimeRange::new(
+            datetime!(2023-03-06 0:00 UTC),
+            datetime!(2023-03-13 0:00 UTC),
+        ),
+        EventFilter::Shared,
+        None,
+        &pool,
+    )
+    .await
+    .unwrap();
+
+    assert!(!res.entries.is_empty());
+    assert!(res.entries.iter().all(|entry| !entry.can_edit));
+}
+
 #[traced_test]
 #[sqlx::test(fixtures("users", "events", "user_events"))]
 async fn update_event_test(pool: PgPool) {
@@ -376,6 +687,8 @@ async fn update_event_test(pool: PgPool) {
         get_one_event(&pool, PKBPMJ_ID, event_id).await.unwrap(),
         Event {
             can_edit: true,
+            deleted_at: None,
+            exceptions: None,
             is_owned: true,
             recurrence_rule: Some(RecurrenceRule {
                 span: Some(EntriesSpan {
@@ -385,6 +698,7 @@ async fn update_event_test(pool: PgPool) {
                 interval: 1,
                 kind: RecurrenceRuleKind::Monthly { is_by_day: true },
             }),
+            recurrence_summary: Some("Every month on day 7 until 7 Jan 2024".to_string()),
             entries_start: datetime!(2023-03-07 08:00 +00:00:00),
             entries_end: Some(datetime!(2024-01-07 9:35:00.0 +00:00:00)),
             payload: EventPayload {
@@ -419,150 +733,1068 @@ async fn cannot_update_event_without_permissions(pool: PgPool) {
 
 #[traced_test]
 #[sqlx::test(fixtures("users", "events", "user_events"))]
-async fn delete_event_test(pool: PgPool) {
-    let event_id = uuid!("6d185de5-ddec-462a-aeea-7628f03d417b");
-
-    let mut conn = pool.acquire().await.unwrap();
-    let mut query = PgQuery::new(EventQuery::new(PKBPMJ_ID), &mut conn);
+async fn strangers_cannot_tell_events_exist(pool: PgPool) {
+    let renamed = || UpdateEvent {
+        data: OptionalEventData {
+            name: Some("Polski".to_string()),
+            description: None,
+            starts_at: None,
+            ends_at: None,
+        },
+    };
+    let now = datetime!(2023-03-10 12:00 UTC);
 
-    query.perm_delete(event_id).await.unwrap();
+    let res = update_one_event(&pool, MABI19_ID, renamed(), FIZYKA_ID).await;
+    assert!(matches!(res, Err(EventError::NotFound)));
+    let res = update_one_event(&pool, MABI19_ID, renamed(), Uuid::new_v4()).await;
+    assert!(matches!(res, Err(EventError::NotFound)));
+    let res = delete_one_event_temporally(&pool, MABI19_ID, FIZYKA_ID, now).await;
+    assert!(matches!(res, Err(EventError::NotFound)));
 
-    assert!(query.get_event(event_id).await.unwrap().is_none())
+    // Participants learn that they aren't allowed instead
+    let res = update_one_event(&pool, ADIMAC_ID, renamed(), MATEMATYKA_ID).await;
+    assert!(matches!(res, Err(EventError::MismatchedPrivileges)));
+    let res = delete_one_event_temporally(&pool, HUBERT_ID, FIZYKA_ID, now).await;
+    assert!(matches!(res, Err(EventError::MismatchedPrivileges)));
 }
 
 #[traced_test]
 #[sqlx::test(fixtures("users", "events", "user_events"))]
-async fn cannot_delete_event_if_not_owned(pool: PgPool) {
-    assert!(delete_one_event_permanently(
-        &pool,
-        ADIMAC_ID,
-        uuid!("6d185de5-ddec-462a-aeea-7628f03d417b"),
-    )
-    .await
-    .is_err())
+async fn update_event_recurrence_test(pool: PgPool) {
+    let event_id = uuid!("6d185de5-ddec-462a-aeea-7628f03d417b");
+
+    let body = UpdateRecurrence {
+        recurrence_rule: Some(RecurrenceRuleSchema {
+            time_rules: TimeRules {
+                ends_at: Some(RecurrenceEndsAt::Count(3)),
+                interval: 2,
+            },
+            kind: RecurrenceRuleKind::Daily,
+        }),
+    };
+    let warnings =
+        update_one_event_recurrence(&pool, PKBPMJ_ID, body, event_id, RepetitionLimit::default())
+            .await
+            .unwrap();
+    assert!(warnings.is_empty());
+
+    let event = get_one_event(&pool, PKBPMJ_ID, event_id).await.unwrap();
+    assert_eq!(
+        event.recurrence_rule,
+        Some(RecurrenceRule {
+            span: Some(EntriesSpan {
+                end: datetime!(2023-03-13 9:35 UTC),
+                repetitions: 3,
+            }),
+            interval: 2,
+            kind: RecurrenceRuleKind::Daily,
+        })
+    );
+    assert_eq!(event.entries_end, Some(datetime!(2023-03-13 9:35 UTC)));
 }
 
 #[traced_test]
-#[sqlx::test(fixtures("users", "events", "user_events"))]
-async fn update_edit_privileges_test(pool: PgPool) {
-    update_user_editing_privileges(
+#[sqlx::test(fixtures("users", "events", "user_events", "overrides"))]
+async fn update_event_recurrence_prunes_exceptions_test(pool: PgPool) {
+    // Fizyka takes place on Wednesdays and Thursdays
+    let event_id = FIZYKA_ID;
+    query!(
+        r#"
+            INSERT INTO event_overrides (event_id, override_starts_at, override_ends_at, name)
+            VALUES ($1, '2023-03-23 9:45', '2023-03-23 10:30', 'Thursday')
+        "#,
+        event_id,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    for (starts_at, ends_at) in [
+        (
+            datetime!(2023-03-30 0:00 UTC),
+            datetime!(2023-03-31 0:00 UTC),
+        ),
+        (
+            datetime!(2023-04-05 0:00 UTC),
+            datetime!(2023-04-06 0:00 UTC),
+        ),
+    ] {
+        let body = PauseEvent { starts_at, ends_at };
+        pause_one_event(&pool, PKBPMJ_ID, body, event_id)
+            .await
+            .unwrap();
+    }
+    lock_one_occurrence(
         &pool,
         PKBPMJ_ID,
-        UpdateEditPrivilege {
-            user_id: ADIMAC_ID,
-            can_edit: true,
-        },
-        uuid!("6d185de5-ddec-462a-aeea-7628f03d417b"),
+        event_id,
+        occurrence_id(event_id, datetime!(2023-04-13 9:45 UTC)),
+        RepetitionLimit::default(),
     )
     .await
     .unwrap();
 
-    let mut conn = pool.acquire().await.unwrap();
-    let mut query = PgQuery::new(EventQuery::new(ADIMAC_ID), &mut conn);
+    // Only Wednesdays are left
+    let body = UpdateRecurrence {
+        recurrence_rule: Some(RecurrenceRuleSchema {
+            time_rules: TimeRules {
+                ends_at: Some(RecurrenceEndsAt::Count(8)),
+                interval: 1,
+            },
+            kind: RecurrenceRuleKind::Weekly { week_map: 16 },
+        }),
+    };
+    update_one_event_recurrence(&pool, PKBPMJ_ID, body, event_id, RepetitionLimit::default())
+        .await
+        .unwrap();
+
+    // The override of Wednesday and Thursday still covers a Wednesday
+    let overrides = query!(
+        "SELECT override_starts_at FROM event_overrides WHERE event_id = $1",
+        event_id
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(overrides.len(), 1);
     assert_eq!(
-        query
-            .can_edit(uuid!("6d185de5-ddec-462a-aeea-7628f03d417b"))
-            .await
-            .unwrap(),
-        true
+        overrides[0].override_starts_at,
+        datetime!(2023-03-15 9:45 UTC)
+    );
+
+    let pauses = query!(
+        "SELECT starts_at FROM event_pauses WHERE event_id = $1",
+        event_id
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(pauses.len(), 1);
+    assert_eq!(pauses[0].starts_at, datetime!(2023-04-05 0:00 UTC));
+
+    let locks = query!(
+        "SELECT COUNT(*) AS \"count!\" FROM locked_occurrences WHERE event_id = $1",
+        event_id
     )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(locks.count, 0);
 }
 
 #[traced_test]
 #[sqlx::test(fixtures("users", "events", "user_events"))]
-async fn cannot_update_privileges_without_ownership(pool: PgPool) {
-    assert!(update_user_editing_privileges(
+async fn update_event_recurrence_warns_test(pool: PgPool) {
+    // Monday and Tuesday for an event starting on a Tuesday
+    let body = UpdateRecurrence {
+        recurrence_rule: Some(RecurrenceRuleSchema {
+            time_rules: TimeRules {
+                ends_at: Some(RecurrenceEndsAt::Count(3)),
+                interval: 1,
+            },
+            kind: RecurrenceRuleKind::Weekly { week_map: 96 },
+        }),
+    };
+    let warnings = update_one_event_recurrence(
         &pool,
-        ADIMAC_ID,
-        UpdateEditPrivilege {
-            user_id: PKBPMJ_ID,
-            can_edit: false,
-        },
+        PKBPMJ_ID,
+        body,
         uuid!("6d185de5-ddec-462a-aeea-7628f03d417b"),
+        RepetitionLimit::default(),
     )
     .await
-    .is_err());
+    .unwrap();
+
+    assert_eq!(warnings.len(), 1);
 }
 
 #[traced_test]
 #[sqlx::test(fixtures("users", "events", "user_events"))]
-async fn cannot_self_update_privileges(pool: PgPool) {
-    assert!(update_user_editing_privileges(
+async fn drop_event_recurrence_test(pool: PgPool) {
+    let event_id = uuid!("6d185de5-ddec-462a-aeea-7628f03d417b");
+
+    update_one_event_recurrence(
         &pool,
         PKBPMJ_ID,
-        UpdateEditPrivilege {
-            user_id: PKBPMJ_ID,
-            can_edit: false,
+        UpdateRecurrence {
+            recurrence_rule: None,
         },
-        uuid!("6d185de5-ddec-462a-aeea-7628f03d417b"),
+        event_id,
+        RepetitionLimit::default(),
     )
     .await
-    .is_err());
+    .unwrap();
+
+    let event = get_one_event(&pool, PKBPMJ_ID, event_id).await.unwrap();
+    assert_eq!(event.recurrence_rule, None);
+    assert_eq!(event.entries_end, Some(datetime!(2023-03-07 09:35 UTC)));
 }
 
 #[traced_test]
 #[sqlx::test(fixtures("users", "events", "user_events"))]
-async fn update_event_owner_test(pool: PgPool) {
-    set_event_ownership(
+async fn cannot_update_event_recurrence_without_permissions(pool: PgPool) {
+    assert!(update_one_event_recurrence(
         &pool,
-        PKBPMJ_ID,
         ADIMAC_ID,
+        UpdateRecurrence {
+            recurrence_rule: None,
+        },
         uuid!("6d185de5-ddec-462a-aeea-7628f03d417b"),
+        RepetitionLimit::default(),
     )
     .await
-    .unwrap();
-
-    let mut conn = pool.acquire().await.unwrap();
-    let mut q1 = PgQuery::new(EventQuery::new(ADIMAC_ID), &mut conn);
+    .is_err())
+}
 
-    assert_eq!(
-        q1.is_owner(uuid!("6d185de5-ddec-462a-aeea-7628f03d417b"))
-            .await
-            .unwrap(),
-        true
-    );
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn split_event_test(pool: PgPool) {
+    let event_id = uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
 
-    let mut q2 = PgQuery::new(EventQuery::new(PKBPMJ_ID), &mut conn);
+    let body = SplitEvent {
+        split_at: datetime!(2023-03-22 09:45 UTC),
+        starts_at: datetime!(2023-03-22 11:00 UTC),
+        ends_at: datetime!(2023-03-22 12:00 UTC),
+    };
+    let successor_id = split_one_event(&pool, HUBERT_ID, body, event_id)
+        .await
+        .unwrap();
 
-    assert_eq!(
-        q2.is_owner(uuid!("6d185de5-ddec-462a-aeea-7628f03d417b"))
-            .await
-            .unwrap(),
-        false
-    );
+    let event = get_one_event(&pool, PKBPMJ_ID, event_id).await.unwrap();
+    assert_eq!(event.entries_end, Some(datetime!(2023-03-16 10:30 UTC)));
 
+    let successor = get_one_event(&pool, HUBERT_ID, successor_id).await.unwrap();
+    assert!(successor.can_edit);
+    assert_eq!(successor.payload.name, "Fizyka");
     assert_eq!(
-        q2.can_edit(uuid!("6d185de5-ddec-462a-aeea-7628f03d417b"))
-            .await
-            .unwrap(),
-        true
+        successor.recurrence_rule,
+        Some(RecurrenceRule {
+            span: Some(EntriesSpan {
+                end: datetime!(2023-04-27 12:00 UTC),
+                repetitions: 11,
+            }),
+            interval: 1,
+            kind: RecurrenceRuleKind::Weekly { week_map: 24 },
+        })
     );
 }
 
 #[traced_test]
 #[sqlx::test(fixtures("users", "events", "user_events"))]
-async fn cannot_update_owner_without_ownership(pool: PgPool) {
-    assert!(set_event_ownership(
-        &pool,
-        ADIMAC_ID,
-        PKBPMJ_ID,
-        uuid!("6d185de5-ddec-462a-aeea-7628f03d417b"),
-    )
-    .await
-    .is_err())
-}
+async fn cannot_split_event_at_non_occurrence(pool: PgPool) {
+    let event_id = uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
 
-#[traced_test]
-#[sqlx::test(fixtures("users", "events", "user_events"))]
-async fn cannot_self_update_ownership(pool: PgPool) {
-    assert!(set_event_ownership(
-        &pool,
-        PKBPMJ_ID,
-        PKBPMJ_ID,
-        uuid!("6d185de5-ddec-462a-aeea-7628f03d417b"),
-    )
-    .await
-    .is_err())
+    let body = SplitEvent {
+        split_at: datetime!(2023-03-21 09:45 UTC),
+        starts_at: datetime!(2023-03-21 11:00 UTC),
+        ends_at: datetime!(2023-03-21 12:00 UTC),
+    };
+    assert!(split_one_event(&pool, PKBPMJ_ID, body, event_id)
+        .await
+        .is_err());
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn split_waits_for_locked_event(pool: PgPool) {
+    let event_id = uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
+    let mut transaction = pool.begin().await.unwrap();
+    lock_for_transaction(&mut transaction, event_id)
+        .await
+        .unwrap();
+
+    let body = SplitEvent {
+        split_at: datetime!(2023-03-22 09:45 UTC),
+        starts_at: datetime!(2023-03-22 11:00 UTC),
+        ends_at: datetime!(2023-03-22 12:00 UTC),
+    };
+    let split = tokio::spawn({
+        let pool = pool.clone();
+        async move { split_one_event(&pool, HUBERT_ID, body, event_id).await }
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    assert!(!split.is_finished());
+
+    transaction.commit().await.unwrap();
+    assert!(split.await.unwrap().is_ok());
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn locked_event_times_out(pool: PgPool) {
+    let event_id = uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
+    let mut transaction = pool.begin().await.unwrap();
+    lock_for_transaction(&mut transaction, event_id)
+        .await
+        .unwrap();
+
+    let started = std::time::Instant::now();
+    let res = set_event_ownership(&pool, PKBPMJ_ID, HUBERT_ID, event_id).await;
+    assert!(matches!(res, Err(EventError::Locked)));
+    assert!(started.elapsed() >= LOCK_TIMEOUT);
+
+    transaction.rollback().await.unwrap();
+    set_event_ownership(&pool, PKBPMJ_ID, HUBERT_ID, event_id)
+        .await
+        .unwrap();
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn pause_event_test(pool: PgPool) {
+    let event_id = uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
+
+    let body = PauseEvent {
+        starts_at: datetime!(2023-03-20 0:00 UTC),
+        ends_at: datetime!(2023-03-27 0:00 UTC),
+    };
+    pause_one_event(&pool, HUBERT_ID, body, event_id)
+        .await
+        .unwrap();
+
+    let res = get_many_events(
+        PKBPMJ_ID,
+        TimeRange::new(
+            datetime!(2023-03-13 0:00 UTC),
+            datetime!(2023-04-03 0:00 UTC),
+        ),
+        EventFilter::Owned,
+        None,
+        &pool,
+    )
+    .await
+    .unwrap();
+
+    let starts: Vec<_> = res
+        .entries
+        .iter()
+        .filter(|entry| entry.event_id == event_id)
+        .map(|entry| entry.time_range.start)
+        .collect();
+    assert_eq!(
+        starts,
+        vec![
+            datetime!(2023-03-15 09:45 UTC),
+            datetime!(2023-03-16 09:45 UTC),
+            datetime!(2023-03-29 09:45 UTC),
+            datetime!(2023-03-30 09:45 UTC),
+        ]
+    );
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn cannot_pause_event_without_permissions(pool: PgPool) {
+    let event_id = uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
+
+    let body = PauseEvent {
+        starts_at: datetime!(2023-03-20 0:00 UTC),
+        ends_at: datetime!(2023-03-27 0:00 UTC),
+    };
+    assert!(pause_one_event(&pool, MABI19_ID, body, event_id)
+        .await
+        .is_err());
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn event_feed_test(pool: PgPool) {
+    let event_id = uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
+    let token = create_event_feed_token(&pool, HUBERT_ID, event_id)
+        .await
+        .unwrap();
+
+    let feed = get_event_feed(
+        &pool,
+        &FeedCache::default(),
+        token,
+        event_id,
+        OffsetDateTime::now_utc(),
+        RepetitionLimit::default(),
+    )
+    .await
+    .unwrap()
+    .body;
+
+    assert!(feed.starts_with("BEGIN:VCALENDAR\r\n"));
+    assert_eq!(feed.matches("BEGIN:VEVENT").count(), 16);
+    assert!(feed.contains("SUMMARY:Fizyka\r\n"));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn event_feed_bounds_infinite_sub_daily_rules(pool: PgPool) {
+    let event_id = uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
+    // Stored before sub-daily rules had to end
+    sqlx::query(
+        r#"
+            UPDATE recurrence_rules SET recurrence = '"minutely"', until = NULL, count = NULL, interval = 60
+            WHERE event_id = $1
+        "#,
+    )
+    .bind(event_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    let token = create_event_feed_token(&pool, HUBERT_ID, event_id)
+        .await
+        .unwrap();
+
+    let feed = get_event_feed(
+        &pool,
+        &FeedCache::default(),
+        token,
+        event_id,
+        datetime!(2025-03-08 12:00 UTC),
+        RepetitionLimit(100),
+    )
+    .await
+    .unwrap()
+    .body;
+
+    let entries = feed.matches("BEGIN:VEVENT").count();
+    assert_eq!(entries, 100);
+    // Entries start a year before the feed at the latest
+    assert!(!feed.contains("DTSTART:2023"));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn event_feed_weeks_start_on_preference_of_token_creator(pool: PgPool) {
+    let event_id = uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
+    sqlx::query(
+        r#"
+            UPDATE recurrence_rules SET recurrence = '{"weekly": {"days": ["wednesday", "sunday"]}}', interval = 2
+            WHERE event_id = $1
+        "#,
+    )
+    .bind(event_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    let token = create_event_feed_token(&pool, HUBERT_ID, event_id)
+        .await
+        .unwrap();
+    let now = datetime!(2023-03-08 12:00 UTC);
+
+    // The event starts on Wednesday, 2023-03-08, the following Sunday is in the same week
+    let body = get_event_feed(
+        &pool,
+        &FeedCache::default(),
+        token,
+        event_id,
+        now,
+        RepetitionLimit::default(),
+    )
+    .await
+    .unwrap()
+    .body;
+    assert!(body.contains("DTSTART:20230312T094500Z"));
+    assert!(!body.contains("DTSTART:20230319T094500Z"));
+
+    sqlx::query("UPDATE users SET week_start = 6 WHERE id = $1")
+        .bind(HUBERT_ID)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let body = get_event_feed(
+        &pool,
+        &FeedCache::default(),
+        token,
+        event_id,
+        now,
+        RepetitionLimit::default(),
+    )
+    .await
+    .unwrap()
+    .body;
+    assert!(!body.contains("DTSTART:20230312T094500Z"));
+    assert!(body.contains("DTSTART:20230319T094500Z"));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn event_feed_is_cached_until_event_changes(pool: PgPool) {
+    let event_id = uuid!("6d185de5-ddec-462a-aeea-7628f03d417b");
+    let token = create_event_feed_token(&pool, PKBPMJ_ID, event_id)
+        .await
+        .unwrap();
+    let cache = FeedCache::default();
+    let now = datetime!(2023-03-08 12:00 UTC);
+
+    let first = get_event_feed(
+        &pool,
+        &cache,
+        token,
+        event_id,
+        now,
+        RepetitionLimit::default(),
+    )
+    .await
+    .unwrap();
+    let second = get_event_feed(
+        &pool,
+        &cache,
+        token,
+        event_id,
+        now + Duration::minutes(5),
+        RepetitionLimit::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(first, second);
+
+    let data = OptionalEventData {
+        name: Some("Polski".to_string()),
+        description: None,
+        starts_at: None,
+        ends_at: None,
+    };
+    update_one_event(&pool, PKBPMJ_ID, UpdateEvent { data }, event_id)
+        .await
+        .unwrap();
+
+    let changed = get_event_feed(
+        &pool,
+        &cache,
+        token,
+        event_id,
+        now + Duration::minutes(10),
+        RepetitionLimit::default(),
+    )
+    .await
+    .unwrap();
+    assert_ne!(changed.etag, first.etag);
+    assert!(changed.body.contains("SUMMARY:Polski\r\n"));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn event_feed_answers_conditional_requests(pool: PgPool) {
+    let event_id = uuid!("6d185de5-ddec-462a-aeea-7628f03d417b");
+    let token = create_event_feed_token(&pool, PKBPMJ_ID, event_id)
+        .await
+        .unwrap();
+    let app = AppData::new(pool).await;
+    let client = app.client();
+    let url = app.api(&format!("/api/v1/events/{event_id}/feed.ics?token={token}"));
+
+    let res = client.get(&url).send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let etag = res.headers()[header::ETAG].clone();
+    let last_modified = res.headers()[header::LAST_MODIFIED].clone();
+
+    let res = client
+        .get(&url)
+        .header(header::IF_NONE_MATCH, etag.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(res.headers()[header::ETAG], etag);
+
+    let res = client
+        .get(&url)
+        .header(header::IF_MODIFIED_SINCE, last_modified)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+    let res = client
+        .get(&url)
+        .header(header::IF_NONE_MATCH, "\"stale\"")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn cannot_create_feed_token_without_participation(pool: PgPool) {
+    assert!(create_event_feed_token(
+        &pool,
+        MABI19_ID,
+        uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1")
+    )
+    .await
+    .is_err());
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn cannot_get_feed_with_wrong_token(pool: PgPool) {
+    let event_id = uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
+    create_event_feed_token(&pool, HUBERT_ID, event_id)
+        .await
+        .unwrap();
+
+    assert!(get_event_feed(
+        &pool,
+        &FeedCache::default(),
+        Uuid::new_v4(),
+        event_id,
+        OffsetDateTime::now_utc(),
+        RepetitionLimit::default()
+    )
+    .await
+    .is_err());
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn feed_tokens_are_rotated_and_revoked(pool: PgPool) {
+    let event_id = uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
+    let cache = FeedCache::default();
+    let feed = |token| {
+        get_event_feed(
+            &pool,
+            &cache,
+            token,
+            event_id,
+            OffsetDateTime::now_utc(),
+            RepetitionLimit::default(),
+        )
+    };
+    let old = create_event_feed_token(&pool, HUBERT_ID, event_id)
+        .await
+        .unwrap();
+    let new = create_event_feed_token(&pool, HUBERT_ID, event_id)
+        .await
+        .unwrap();
+
+    assert!(matches!(feed(old).await, Err(EventError::NotFound)));
+    assert!(feed(new).await.is_ok());
+
+    revoke_event_feed_tokens(&pool, HUBERT_ID, event_id)
+        .await
+        .unwrap();
+    assert!(matches!(feed(new).await, Err(EventError::NotFound)));
+    assert!(matches!(
+        revoke_event_feed_tokens(&pool, HUBERT_ID, event_id).await,
+        Err(EventError::NotFound)
+    ));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn delete_event_test(pool: PgPool) {
+    let event_id = uuid!("6d185de5-ddec-462a-aeea-7628f03d417b");
+
+    let mut conn = pool.acquire().await.unwrap();
+    let mut query = PgQuery::new(EventQuery::new(PKBPMJ_ID), &mut conn);
+
+    query.perm_delete(event_id).await.unwrap();
+
+    assert!(query.get_event(event_id).await.unwrap().is_none())
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn deleted_events_are_included_for_owners_on_request(pool: PgPool) {
+    let event_id = uuid!("6d185de5-ddec-462a-aeea-7628f03d417b");
+    delete_one_event_temporally(&pool, PKBPMJ_ID, event_id, datetime!(2023-03-10 12:00 UTC))
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        get_one_event(&pool, PKBPMJ_ID, event_id).await,
+        Err(EventError::NotFound)
+    ));
+    let event = get_one_event_including_deleted(&pool, PKBPMJ_ID, event_id)
+        .await
+        .unwrap();
+    assert_eq!(event.deleted_at, Some(datetime!(2023-03-10 12:00 UTC)));
+    // Participants don't see deleted events of others
+    assert!(matches!(
+        get_one_event_including_deleted(&pool, ADIMAC_ID, event_id).await,
+        Err(EventError::NotFound)
+    ));
+
+    let search_range = TimeRange::new(
+        datetime!(2023-03-01 00:00 UTC),
+        datetime!(2023-04-01 00:00 UTC),
+    );
+    for (user_id, include_deleted, is_listed) in [
+        (PKBPMJ_ID, false, false),
+        (PKBPMJ_ID, true, true),
+        (ADIMAC_ID, true, false),
+    ] {
+        let page = get_events_page(
+            user_id,
+            search_range,
+            EventFilter::All,
+            None,
+            EntriesPage {
+                include_deleted,
+                ..Default::default()
+            },
+            &pool,
+        )
+        .await
+        .unwrap();
+        let event = page.events.events.get(&event_id);
+        assert_eq!(event.is_some(), is_listed, "{user_id} {include_deleted}");
+        if let Some(event) = event {
+            assert!(event.deleted_at.is_some());
+            assert!(page
+                .events
+                .entries
+                .iter()
+                .any(|entry| entry.event_id == event_id));
+        }
+    }
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn cannot_delete_event_if_not_owned(pool: PgPool) {
+    assert!(delete_one_event_permanently(
+        &pool,
+        ADIMAC_ID,
+        uuid!("6d185de5-ddec-462a-aeea-7628f03d417b"),
+    )
+    .await
+    .is_err())
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn update_edit_privileges_test(pool: PgPool) {
+    update_user_editing_privileges(
+        &pool,
+        PKBPMJ_ID,
+        UpdateEditPrivilege {
+            user_id: ADIMAC_ID,
+            can_edit: true,
+        },
+        uuid!("6d185de5-ddec-462a-aeea-7628f03d417b"),
+    )
+    .await
+    .unwrap();
+
+    let mut conn = pool.acquire().await.unwrap();
+    let mut query = PgQuery::new(EventQuery::new(ADIMAC_ID), &mut conn);
+    assert_eq!(
+        query
+            .can_edit(uuid!("6d185de5-ddec-462a-aeea-7628f03d417b"))
+            .await
+            .unwrap(),
+        true
+    )
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn cannot_update_privileges_without_ownership(pool: PgPool) {
+    assert!(update_user_editing_privileges(
+        &pool,
+        ADIMAC_ID,
+        UpdateEditPrivilege {
+            user_id: PKBPMJ_ID,
+            can_edit: false,
+        },
+        uuid!("6d185de5-ddec-462a-aeea-7628f03d417b"),
+    )
+    .await
+    .is_err());
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn cannot_self_update_privileges(pool: PgPool) {
+    assert!(update_user_editing_privileges(
+        &pool,
+        PKBPMJ_ID,
+        UpdateEditPrivilege {
+            user_id: PKBPMJ_ID,
+            can_edit: false,
+        },
+        uuid!("6d185de5-ddec-462a-aeea-7628f03d417b"),
+    )
+    .await
+    .is_err());
+}
+
+async fn can_edit(pool: &PgPool, user_id: Uuid, event_id: Uuid) -> bool {
+    let mut conn = pool.acquire().await.unwrap();
+    PgQuery::new(EventQuery::new(user_id), &mut conn)
+        .can_edit(event_id)
+        .await
+        .unwrap()
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn update_many_edit_privileges_test(pool: PgPool) {
+    let event_id = uuid!("d63a1036-e59d-4b7c-a009-9b90a0e703d1");
+    update_many_editing_privileges(
+        &pool,
+        HUBERT_ID,
+        UpdateEditPrivileges {
+            changes: vec![
+                UpdateEditPrivilege {
+                    user_id: ADIMAC_ID,
+                    can_edit: false,
+                },
+                UpdateEditPrivilege {
+                    user_id: MABI19_ID,
+                    can_edit: true,
+                },
+            ],
+        },
+        event_id,
+    )
+    .await
+    .unwrap();
+
+    assert!(!can_edit(&pool, ADIMAC_ID, event_id).await);
+    assert!(can_edit(&pool, MABI19_ID, event_id).await);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn update_many_edit_privileges_is_atomic(pool: PgPool) {
+    let event_id = uuid!("d63a1036-e59d-4b7c-a009-9b90a0e703d1");
+    let res = update_many_editing_privileges(
+        &pool,
+        HUBERT_ID,
+        UpdateEditPrivileges {
+            changes: vec![
+                UpdateEditPrivilege {
+                    user_id: ADIMAC_ID,
+                    can_edit: false,
+                },
+                UpdateEditPrivilege {
+                    user_id: PKBPMJ_ID,
+                    can_edit: true,
+                },
+            ],
+        },
+        event_id,
+    )
+    .await;
+
+    assert!(matches!(res, Err(EventError::NotFound)));
+    assert!(can_edit(&pool, ADIMAC_ID, event_id).await);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn cannot_update_many_privileges_without_ownership(pool: PgPool) {
+    let res = update_many_editing_privileges(
+        &pool,
+        ADIMAC_ID,
+        UpdateEditPrivileges {
+            changes: vec![UpdateEditPrivilege {
+                user_id: MABI19_ID,
+                can_edit: true,
+            }],
+        },
+        uuid!("d63a1036-e59d-4b7c-a009-9b90a0e703d1"),
+    )
+    .await;
+
+    assert!(matches!(res, Err(EventError::MismatchedPrivileges)));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn owner_cannot_participate_in_own_event(pool: PgPool) {
+    let event_id = uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
+    let mut conn = pool.acquire().await.unwrap();
+    let mut q = PgQuery::new(EventQuery::new(PKBPMJ_ID), &mut conn);
+
+    let res = q
+        .create_user_event(UserEvent::new(UserId(PKBPMJ_ID), EventId(event_id), true))
+        .await;
+    assert!(matches!(res, Err(EventError::OwnerParticipation)));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn co_owner_manages_event(pool: PgPool) {
+    let event_id = uuid!("6d185de5-ddec-462a-aeea-7628f03d417b");
+    update_event_co_owner(
+        &pool,
+        PKBPMJ_ID,
+        UpdateCoOwner {
+            user_id: ADIMAC_ID,
+            is_owner: true,
+        },
+        event_id,
+    )
+    .await
+    .unwrap();
+
+    let event = get_one_event(&pool, ADIMAC_ID, event_id).await.unwrap();
+    assert!(event.is_owned);
+    assert!(event.can_edit);
+
+    // Only the owner hands the event over
+    assert!(matches!(
+        set_event_ownership(&pool, ADIMAC_ID, HUBERT_ID, event_id).await,
+        Err(EventError::MismatchedPrivileges)
+    ));
+
+    let data = OptionalEventData {
+        name: Some("Polski".to_string()),
+        description: None,
+        starts_at: None,
+        ends_at: None,
+    };
+    update_one_event(&pool, ADIMAC_ID, UpdateEvent { data }, event_id)
+        .await
+        .unwrap();
+    let event = get_one_event(&pool, PKBPMJ_ID, event_id).await.unwrap();
+    assert_eq!(event.payload.name, "Polski");
+
+    delete_one_event_permanently(&pool, ADIMAC_ID, event_id)
+        .await
+        .unwrap();
+    assert!(matches!(
+        get_one_event(&pool, PKBPMJ_ID, event_id).await,
+        Err(EventError::NotFound)
+    ));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn co_owner_keeps_editing_privileges(pool: PgPool) {
+    let event_id = uuid!("6d185de5-ddec-462a-aeea-7628f03d417b");
+    let co_owner = |is_owner| UpdateCoOwner {
+        user_id: ADIMAC_ID,
+        is_owner,
+    };
+    update_event_co_owner(&pool, PKBPMJ_ID, co_owner(true), event_id)
+        .await
+        .unwrap();
+
+    let res = update_many_editing_privileges(
+        &pool,
+        PKBPMJ_ID,
+        UpdateEditPrivileges {
+            changes: vec![UpdateEditPrivilege {
+                user_id: ADIMAC_ID,
+                can_edit: false,
+            }],
+        },
+        event_id,
+    )
+    .await;
+    assert!(matches!(res, Err(EventError::NotFound)));
+
+    update_event_co_owner(&pool, PKBPMJ_ID, co_owner(false), event_id)
+        .await
+        .unwrap();
+    let mut conn = pool.acquire().await.unwrap();
+    let mut q = PgQuery::new(EventQuery::new(ADIMAC_ID), &mut conn);
+    assert!(!q.is_owner(event_id).await.unwrap());
+    assert!(q.can_edit(event_id).await.unwrap());
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn co_owner_can_leave_event(pool: PgPool) {
+    let event_id = uuid!("6d185de5-ddec-462a-aeea-7628f03d417b");
+    update_event_co_owner(
+        &pool,
+        PKBPMJ_ID,
+        UpdateCoOwner {
+            user_id: ADIMAC_ID,
+            is_owner: true,
+        },
+        event_id,
+    )
+    .await
+    .unwrap();
+
+    delete_user_event(&pool, ADIMAC_ID, event_id).await.unwrap();
+    assert!(matches!(
+        get_one_event(&pool, ADIMAC_ID, event_id).await,
+        Err(EventError::NotFound)
+    ));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn only_participants_become_co_owners(pool: PgPool) {
+    let event_id = uuid!("6d185de5-ddec-462a-aeea-7628f03d417b");
+    let co_owner = |user_id| UpdateCoOwner {
+        user_id,
+        is_owner: true,
+    };
+
+    let res = update_event_co_owner(&pool, PKBPMJ_ID, co_owner(HUBERT_ID), event_id).await;
+    assert!(matches!(res, Err(EventError::NotFound)));
+
+    let res = update_event_co_owner(&pool, ADIMAC_ID, co_owner(ADIMAC_ID), event_id).await;
+    assert!(matches!(res, Err(EventError::MismatchedPrivileges)));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn update_event_owner_test(pool: PgPool) {
+    set_event_ownership(
+        &pool,
+        PKBPMJ_ID,
+        ADIMAC_ID,
+        uuid!("6d185de5-ddec-462a-aeea-7628f03d417b"),
+    )
+    .await
+    .unwrap();
+
+    let mut conn = pool.acquire().await.unwrap();
+    let mut q1 = PgQuery::new(EventQuery::new(ADIMAC_ID), &mut conn);
+
+    assert_eq!(
+        q1.is_owner(uuid!("6d185de5-ddec-462a-aeea-7628f03d417b"))
+            .await
+            .unwrap(),
+        true
+    );
+
+    let mut q2 = PgQuery::new(EventQuery::new(PKBPMJ_ID), &mut conn);
+
+    assert_eq!(
+        q2.is_owner(uuid!("6d185de5-ddec-462a-aeea-7628f03d417b"))
+            .await
+            .unwrap(),
+        false
+    );
+
+    assert_eq!(
+        q2.can_edit(uuid!("6d185de5-ddec-462a-aeea-7628f03d417b"))
+            .await
+            .unwrap(),
+        true
+    );
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn cannot_update_owner_without_ownership(pool: PgPool) {
+    assert!(set_event_ownership(
+        &pool,
+        ADIMAC_ID,
+        PKBPMJ_ID,
+        uuid!("6d185de5-ddec-462a-aeea-7628f03d417b"),
+    )
+    .await
+    .is_err())
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn cannot_self_update_ownership(pool: PgPool) {
+    assert!(set_event_ownership(
+        &pool,
+        PKBPMJ_ID,
+        PKBPMJ_ID,
+        uuid!("6d185de5-ddec-462a-aeea-7628f03d417b"),
+    )
+    .await
+    .is_err())
 }
 
 #[traced_test]
@@ -653,3 +1885,225 @@ async fn does_not_disconnect_user_as_owner(pool: PgPool) {
     .await
     .is_err())
 }
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn create_event_normalizes_on_request(pool: PgPool) {
+    let app = AppData::new(pool.clone()).await;
+    let client = app.client();
+    client
+        .post(app.api("/api/v1/auth/login"))
+        .json(&serde_json::json!({ "login": "macmac", "password": "#strong#_#pass#" }))
+        .send()
+        .await
+        .unwrap();
+
+    // Wednesday, repeated on Mondays and Tuesdays
+    let event = CreateEvent {
+        data: EventData {
+            starts_at: datetime!(2023-03-01 9:45 UTC),
+            ends_at: datetime!(2023-03-01 10:30 UTC),
+            payload: EventPayload::new("Fizyka".to_string(), None),
+        },
+        recurrence_rule: Some(RecurrenceRuleSchema {
+            time_rules: TimeRules {
+                ends_at: None,
+                interval: 1,
+            },
+            kind: RecurrenceRuleKind::Weekly { week_map: 96 },
+        }),
+    };
+
+    let plain: CreateEventResult = client
+        .put(app.api("/api/v1/events"))
+        .json(&event)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(plain.normalized.is_none());
+    assert_eq!(plain.warnings.len(), 1);
+
+    let normalized: CreateEventResult = client
+        .put(app.api("/api/v1/events?normalize=true"))
+        .json(&event)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let adjustment = normalized.normalized.unwrap();
+    assert_eq!(adjustment.adjusted.start, datetime!(2023-03-06 9:45 UTC));
+    assert!(normalized.warnings.is_empty());
+
+    let event = get_one_event(&pool, ADIMAC_ID, normalized.event_id)
+        .await
+        .unwrap();
+    assert_eq!(event.entries_start, datetime!(2023-03-06 9:45 UTC));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn recurrence_repetitions_are_capped(pool: PgPool) {
+    let event = CreateEvent {
+        data: EventData {
+            starts_at: datetime!(2023-03-07 19:00 UTC),
+            ends_at: datetime!(2023-03-07 20:00 UTC),
+            payload: EventPayload::new("Trening".to_string(), None),
+        },
+        recurrence_rule: Some(RecurrenceRuleSchema {
+            time_rules: TimeRules {
+                ends_at: Some(RecurrenceEndsAt::Count(4_000_000_000)),
+                interval: 1,
+            },
+            kind: RecurrenceRuleKind::Daily,
+        }),
+    };
+    let res = create_new_event(&pool, PKBPMJ_ID, event, RepetitionLimit::default()).await;
+    assert!(matches!(res, Err(EventError::InvalidData(_))));
+
+    let body = UpdateRecurrence {
+        recurrence_rule: Some(RecurrenceRuleSchema {
+            time_rules: TimeRules {
+                ends_at: Some(RecurrenceEndsAt::Until(datetime!(2024-03-07 9:35 UTC))),
+                interval: 1,
+            },
+            kind: RecurrenceRuleKind::Daily,
+        }),
+    };
+    let res = update_one_event_recurrence(
+        &pool,
+        PKBPMJ_ID,
+        body,
+        uuid!("6d185de5-ddec-462a-aeea-7628f03d417b"),
+        RepetitionLimit(100),
+    )
+    .await;
+    assert!(matches!(res, Err(EventError::InvalidData(_))));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn rejected_event_lists_invalid_fields(pool: PgPool) {
+    let app = AppData::new(pool).await;
+    let client = app.client();
+    client
+        .post(app.api("/api/v1/auth/login"))
+        .json(&serde_json::json!({ "login": "macmac", "password": "#strong#_#pass#" }))
+        .send()
+        .await
+        .unwrap();
+
+    let event = CreateEvent {
+        data: EventData {
+            starts_at: datetime!(2023-03-07 19:00 UTC),
+            ends_at: datetime!(2023-03-07 18:59 UTC),
+            payload: EventPayload::new("New event".to_string(), None),
+        },
+        recurrence_rule: None,
+    };
+    let res = client
+        .put(app.api("/api/v1/events"))
+        .json(&event)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert!(body["error_info"].is_string());
+    assert_eq!(body["fields"][0]["field"], "data.startsAt");
+    assert_eq!(
+        body["fields"][0]["message"],
+        "TimeRange duration is negative"
+    );
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn event_digest_test(pool: PgPool) {
+    let event_id = uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
+    let daily = UpdateEventDigest {
+        frequency: DigestFrequency::Daily,
+    };
+    // Whole seconds survive the round trip through the database
+    let clock = TestClock::new(OffsetDateTime::now_utc().replace_nanosecond(0).unwrap());
+
+    assert!(matches!(
+        set_event_digest(&pool, HUBERT_ID, event_id, daily, clock.now()).await,
+        Err(EventError::MismatchedPrivileges)
+    ));
+    assert!(matches!(
+        get_event_digest(&pool, PKBPMJ_ID, event_id).await,
+        Err(EventError::NotFound)
+    ));
+    let digest = set_event_digest(&pool, PKBPMJ_ID, event_id, daily, clock.now())
+        .await
+        .unwrap();
+    assert_eq!(digest.frequency, DigestFrequency::Daily);
+    assert_eq!(digest.last_sent_at, clock.now());
+
+    let mut conn = pool.acquire().await.unwrap();
+    let changes = [
+        (Topic::EventUpdated, json!({ "userId": HUBERT_ID })),
+        (Topic::EventUpdated, json!({ "userId": PKBPMJ_ID })),
+        (Topic::ParticipantsChanged, json!({ "userId": ADIMAC_ID })),
+        (
+            Topic::InvitationResponded,
+            json!({ "senderId": PKBPMJ_ID, "receiverId": ADIMAC_ID, "isAccepted": true }),
+        ),
+    ];
+    for (topic, payload) in changes {
+        outbox::record(&mut conn, topic, event_id, payload)
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(send_due_digests(&pool, clock.now()).await.unwrap(), 0);
+
+    clock.advance(Duration::days(1));
+    assert_eq!(send_due_digests(&pool, clock.now()).await.unwrap(), 1);
+    let payload = query!(
+        r#"
+            SELECT payload FROM outbox
+            WHERE topic = 'eventDigest' AND aggregate_id = $1
+        "#,
+        PKBPMJ_ID
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap()
+    .payload;
+    assert_eq!(payload["eventId"], json!(event_id));
+    assert_eq!(
+        payload["summary"],
+        json!({
+            "participantChanges": 1,
+            "editorChanges": 1,
+            "acceptedInvitations": 1,
+            "declinedInvitations": 0,
+        })
+    );
+
+    // Periods without changes are not sent
+    clock.advance(Duration::days(1));
+    assert_eq!(send_due_digests(&pool, clock.now()).await.unwrap(), 0);
+    assert_eq!(
+        get_event_digest(&pool, PKBPMJ_ID, event_id)
+            .await
+            .unwrap()
+            .last_sent_at,
+        clock.now()
+    );
+
+    delete_event_digest(&pool, PKBPMJ_ID, event_id)
+        .await
+        .unwrap();
+    assert!(matches!(
+        delete_event_digest(&pool, PKBPMJ_ID, event_id).await,
+        Err(EventError::NotFound)
+    ));
+}
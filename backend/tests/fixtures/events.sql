@@ -8,5 +8,5 @@ VALUES
 INSERT INTO recurrence_rules (event_id, recurrence, until, count, interval)
 VALUES
 ('6d185de5-ddec-462a-aeea-7628f03d417b', '{"monthly": {"isByDay": true}}', '2024-01-07 9:35', 10, 1),
-('fd1dcdf7-de06-4aad-ba6e-f2097217a5b1', '{"weekly": {"weekMap": 24}}', '2023-04-27 10:30', 15, 1),
-('d63a1036-e59d-4b7c-a009-9b90a0e703d1', '{"weekly": {"weekMap": 40}}', '2023-04-27 13:15', 15, 1);
+('fd1dcdf7-de06-4aad-ba6e-f2097217a5b1', '{"weekly": {"days": ["wednesday", "thursday"]}}', '2023-04-27 10:30', 15, 1),
+('d63a1036-e59d-4b7c-a009-9b90a0e703d1', '{"weekly": {"days": ["tuesday", "thursday"]}}', '2023-04-27 13:15', 15, 1);
@@ -0,0 +1,272 @@
+use std::io::{Cursor, Write};
+
+use bimetable_db::utils::events::errors::EventError;
+use bimetable_db::utils::events::exe::{
+    get_many_events, import_classroom_schedule, import_xlsx_timetable, preview_classroom_schedule,
+    preview_xlsx_timetable,
+};
+use bimetable_domain::api::events::{EventFilter, ImportMapping};
+use bimetable_domain::events::models::TimeRange;
+use bimetable_domain::limits::RepetitionLimit;
+use serde_json::json;
+use sqlx::PgPool;
+use time::macros::datetime;
+use tracing_test::traced_test;
+use uuid::{uuid, Uuid};
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+const ADIMAC_ID: Uuid = uuid!("910e81a9-56df-4c24-965a-13eff739f469");
+
+const LIMIT: RepetitionLimit = RepetitionLimit(10_000);
+
+const SCHOOL_YEAR: TimeRange = TimeRange {
+    start: datetime!(2023-09-04 0:00 +2),
+    end: datetime!(2023-10-01 0:00 +2),
+};
+
+/// Builds a single sheet workbook with inline string cells.
+fn workbook(rows: &[&[&str]]) -> Vec<u8> {
+    let column = |i: usize| (b'A' + i as u8) as char;
+    let sheet_rows: String = rows
+        .iter()
+        .enumerate()
+        .map(|(r, cells)| {
+            let cells: String = cells
+                .iter()
+                .enumerate()
+                .filter(|(_, text)| !text.is_empty())
+                .map(|(c, text)| {
+                    format!(
+                        r#"<c r="{}{}" t="inlineStr"><is><t xml:space="preserve">{text}</t></is></c>"#,
+                        column(c),
+                        r + 1
+                    )
+                })
+                .collect();
+            format!(r#"<row r="{}">{cells}</row>"#, r + 1)
+        })
+        .collect();
+
+    let files = [
+        (
+            "[Content_Types].xml",
+            r#"<?xml version="1.0" encoding="UTF-8"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/></Types>"#.to_string(),
+        ),
+        (
+            "_rels/.rels",
+            r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#.to_string(),
+        ),
+        (
+            "xl/workbook.xml",
+            r#"<?xml version="1.0" encoding="UTF-8"?><workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="Plan" sheetId="1" r:id="rId1"/></sheets></workbook>"#.to_string(),
+        ),
+        (
+            "xl/_rels/workbook.xml.rels",
+            r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/></Relationships>"#.to_string(),
+        ),
+        (
+            "xl/worksheets/sheet1.xml",
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?><worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>{sheet_rows}</sheetData></worksheet>"#
+            ),
+        ),
+    ];
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, content) in files {
+        zip.start_file(name, FileOptions::default()).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn imports_weekly_lessons(pool: PgPool) {
+    let timetable = workbook(&[
+        &["", "Monday", "Tuesday"],
+        &["8:00-8:45", "Fizyka\nsala 12", ""],
+        &["8:55-9:40", "Matematyka", "Informatyka"],
+    ]);
+
+    let res = import_xlsx_timetable(&pool, ADIMAC_ID, SCHOOL_YEAR, &timetable, LIMIT)
+        .await
+        .unwrap();
+    assert_eq!(res.event_ids.len(), 3);
+    assert!(res.events.is_empty());
+
+    let events = get_many_events(
+        ADIMAC_ID,
+        TimeRange::new(datetime!(2023-09-11 0:00 +2), datetime!(2023-09-18 0:00 +2)),
+        EventFilter::Owned,
+        None,
+        &pool,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(events.entries.len(), 3);
+    assert_eq!(
+        events.entries[0].time_range,
+        TimeRange::new(datetime!(2023-09-11 8:00 +2), datetime!(2023-09-11 8:45 +2))
+    );
+    let fizyka = &events.events[&events.entries[0].event_id];
+    assert_eq!(fizyka.payload.name, "Fizyka");
+    assert_eq!(fizyka.payload.description.as_deref(), Some("sala 12"));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn dry_run_creates_nothing(pool: PgPool) {
+    let timetable = workbook(&[
+        &["", "Monday", "Sunday"],
+        &["8:00-8:45", "Fizyka", ""],
+        &["23:00-23:59", "", "Matematyka"],
+    ]);
+    let first_week = TimeRange::new(
+        datetime!(2023-09-04 0:00 +2),
+        datetime!(2023-09-10 12:00 +2),
+    );
+
+    let res = preview_xlsx_timetable(first_week, &timetable, LIMIT).unwrap();
+    assert!(res.event_ids.is_empty());
+    assert_eq!(res.events.len(), 1);
+    assert_eq!(res.events[0].data.payload.name, "Fizyka");
+    assert_eq!(
+        res.warnings,
+        vec!["Lesson Matematyka on Sunday at 23:00 does not fit into the imported range"]
+    );
+
+    let events = get_many_events(ADIMAC_ID, SCHOOL_YEAR, EventFilter::Owned, None, &pool)
+        .await
+        .unwrap();
+    assert!(events.events.is_empty());
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn rejects_unknown_day(pool: PgPool) {
+    let timetable = workbook(&[&["", "Someday"], &["8:00-8:45", "Fizyka"]]);
+
+    let res = import_xlsx_timetable(&pool, ADIMAC_ID, SCHOOL_YEAR, &timetable, LIMIT).await;
+
+    assert!(matches!(res, Err(EventError::InvalidData(_))));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn rejects_lessons_repeating_over_the_limit(pool: PgPool) {
+    let timetable = workbook(&[&["", "Monday"], &["8:00-8:45", "Fizyka"]]);
+
+    // The school year has four Mondays
+    let res = preview_xlsx_timetable(SCHOOL_YEAR, &timetable, RepetitionLimit(3));
+    assert!(matches!(res, Err(EventError::InvalidData(_))));
+
+    let res = import_xlsx_timetable(
+        &pool,
+        ADIMAC_ID,
+        SCHOOL_YEAR,
+        &timetable,
+        RepetitionLimit(3),
+    )
+    .await;
+    assert!(matches!(res, Err(EventError::InvalidData(_))));
+
+    let events = get_many_events(ADIMAC_ID, SCHOOL_YEAR, EventFilter::Owned, None, &pool)
+        .await
+        .unwrap();
+    assert!(events.events.is_empty());
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn rejects_file_that_is_not_a_workbook(pool: PgPool) {
+    let res = import_xlsx_timetable(&pool, ADIMAC_ID, SCHOOL_YEAR, b"startsAt,endsAt", LIMIT).await;
+
+    assert!(matches!(res, Err(EventError::InvalidData(_))));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn imports_teams_meetings_with_mapping(pool: PgPool) {
+    let export = json!({
+        "value": [
+            {
+                "subject": "Fizyka",
+                "type": "seriesMaster",
+                "location": { "displayName": "sala 12" },
+                "start": { "dateTime": "2023-09-04T06:00:00.0000000", "timeZone": "UTC" },
+                "end": { "dateTime": "2023-09-04T06:45:00.0000000", "timeZone": "UTC" },
+                "recurrence": {
+                    "pattern": { "type": "weekly", "interval": 1, "daysOfWeek": ["monday"] },
+                    "range": { "type": "endDate", "startDate": "2023-09-04", "endDate": "2023-09-30" }
+                }
+            },
+            {
+                "subject": "Matematyka",
+                "isCancelled": true,
+                "start": { "dateTime": "2023-09-05T06:00:00.0000000", "timeZone": "UTC" },
+                "end": { "dateTime": "2023-09-05T06:45:00.0000000", "timeZone": "UTC" }
+            }
+        ]
+    });
+
+    let res =
+        import_classroom_schedule(&pool, ADIMAC_ID, None, export.to_string().as_bytes(), LIMIT)
+            .await
+            .unwrap();
+    assert_eq!(res.event_ids.len(), 1);
+    assert_eq!(
+        res.mapping,
+        vec![
+            ImportMapping {
+                source: "Fizyka".to_string(),
+                event: Some(0),
+                skipped: None,
+            },
+            ImportMapping {
+                source: "Matematyka".to_string(),
+                event: None,
+                skipped: Some("Meeting is cancelled".to_string()),
+            },
+        ]
+    );
+
+    let events = get_many_events(ADIMAC_ID, SCHOOL_YEAR, EventFilter::Owned, None, &pool)
+        .await
+        .unwrap();
+    assert_eq!(events.entries.len(), 4);
+    assert_eq!(
+        events.entries[0].time_range,
+        TimeRange::new(datetime!(2023-09-04 8:00 +2), datetime!(2023-09-04 8:45 +2))
+    );
+    let fizyka = &events.events[&res.event_ids[0]];
+    assert_eq!(
+        fizyka.payload.description.as_deref(),
+        Some("Location: sala 12")
+    );
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn classroom_dry_run_creates_nothing(pool: PgPool) {
+    let export = json!({
+        "summary": "Fizyka 2B",
+        "items": [{
+            "start": { "dateTime": "2023-09-04T08:00:00+02:00" },
+            "end": { "dateTime": "2023-09-04T08:45:00+02:00" },
+            "recurrence": ["RRULE:FREQ=WEEKLY;COUNT=4"]
+        }]
+    });
+
+    let res = preview_classroom_schedule(None, export.to_string().as_bytes(), LIMIT).unwrap();
+    assert!(res.event_ids.is_empty());
+    assert_eq!(res.events.len(), 1);
+    assert_eq!(res.events[0].data.payload.name, "Fizyka 2B");
+
+    let events = get_many_events(ADIMAC_ID, SCHOOL_YEAR, EventFilter::Owned, None, &pool)
+        .await
+        .unwrap();
+    assert!(events.events.is_empty());
+}
@@ -0,0 +1,519 @@
+mod tools;
+
+use bimetable_db::config::app::{InvitationCap, WebsiteOrigin};
+use bimetable_db::config::passwords::PasswordSettings;
+use bimetable_db::config::usernames::UsernameSettings;
+use bimetable_db::utils::auth::try_register_user;
+use bimetable_db::utils::invitations::email::create_email_invitation;
+use bimetable_db::utils::invitations::errors::InvitationError;
+use bimetable_db::utils::invitations::{
+    count_direct_invitations, create_direct_invitation, get_all_direct_invitations,
+    mark_direct_invitations_seen, respond_to_direct_invitation,
+};
+use bimetable_domain::api::invitations::{
+    DirectInvitation, EmailInvitation, RespondDirectInvitation,
+};
+use reqwest::StatusCode;
+use secrecy::{Secret, SecretString};
+use serde_json::json;
+use sqlx::{query, PgPool};
+use tools::AppData;
+use tracing_test::traced_test;
+use uuid::{uuid, Uuid};
+
+const PKBPMJ_ID: Uuid = uuid!("29e40c2a-7595-42d3-98e8-9fe93ce99972");
+const ADIMAC_ID: Uuid = uuid!("910e81a9-56df-4c24-965a-13eff739f469");
+const MABI19_ID: Uuid = uuid!("32190025-7c15-4adb-82fd-9acc3dc8e7b6");
+const HUBERT_ID: Uuid = uuid!("a9c5900e-a445-4888-8612-4a5c8cadbd9e");
+const MATEMATYKA_ID: Uuid = uuid!("6d185de5-ddec-462a-aeea-7628f03d417b");
+const FIZYKA_ID: Uuid = uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
+const INFORMATYKA_ID: Uuid = uuid!("d63a1036-e59d-4b7c-a009-9b90a0e703d1");
+
+fn invitation(event_id: Uuid, sender_id: Uuid) -> DirectInvitation {
+    DirectInvitation {
+        event_id,
+        sender_id,
+        receiver_id: MABI19_ID,
+        can_edit: false,
+    }
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events"))]
+async fn counts_pending_invitations(pool: PgPool) {
+    create_direct_invitation(
+        &pool,
+        invitation(FIZYKA_ID, PKBPMJ_ID),
+        InvitationCap::default(),
+    )
+    .await
+    .unwrap();
+    create_direct_invitation(
+        &pool,
+        invitation(INFORMATYKA_ID, HUBERT_ID),
+        InvitationCap::default(),
+    )
+    .await
+    .unwrap();
+
+    let count = count_direct_invitations(&pool, &MABI19_ID).await.unwrap();
+    assert_eq!(count.pending, 2);
+    assert_eq!(count.unseen, 2);
+
+    let sender_count = count_direct_invitations(&pool, &PKBPMJ_ID).await.unwrap();
+    assert_eq!(sender_count.pending, 0);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events"))]
+async fn responded_invitation_is_not_pending(pool: PgPool) {
+    create_direct_invitation(
+        &pool,
+        invitation(FIZYKA_ID, PKBPMJ_ID),
+        InvitationCap::default(),
+    )
+    .await
+    .unwrap();
+
+    respond_to_direct_invitation(
+        &pool,
+        RespondDirectInvitation {
+            event_id: FIZYKA_ID,
+            sender_id: PKBPMJ_ID,
+            receiver_id: MABI19_ID,
+            is_accepted: false,
+        },
+    )
+    .await
+    .unwrap();
+
+    let count = count_direct_invitations(&pool, &MABI19_ID).await.unwrap();
+    assert_eq!(count.pending, 0);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events"))]
+async fn seen_invitations_are_flagged(pool: PgPool) {
+    create_direct_invitation(
+        &pool,
+        invitation(FIZYKA_ID, PKBPMJ_ID),
+        InvitationCap::default(),
+    )
+    .await
+    .unwrap();
+
+    let affected = mark_direct_invitations_seen(&pool, &MABI19_ID)
+        .await
+        .unwrap();
+    assert_eq!(affected, 1);
+    create_direct_invitation(
+        &pool,
+        invitation(INFORMATYKA_ID, HUBERT_ID),
+        InvitationCap::default(),
+    )
+    .await
+    .unwrap();
+
+    let invitations = get_all_direct_invitations(&pool, &MABI19_ID).await.unwrap();
+    let fizyka = invitations
+        .iter()
+        .find(|inv| inv.event_id == FIZYKA_ID)
+        .unwrap();
+    let informatyka = invitations
+        .iter()
+        .find(|inv| inv.event_id == INFORMATYKA_ID)
+        .unwrap();
+    assert!(fizyka.is_seen);
+    assert!(!informatyka.is_seen);
+
+    let count = count_direct_invitations(&pool, &MABI19_ID).await.unwrap();
+    assert_eq!(count.pending, 2);
+    assert_eq!(count.unseen, 1);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events"))]
+async fn sender_is_capped_per_hour(pool: PgPool) {
+    let cap = InvitationCap(1);
+    create_direct_invitation(&pool, invitation(FIZYKA_ID, PKBPMJ_ID), cap.clone())
+        .await
+        .unwrap();
+
+    let res = create_direct_invitation(
+        &pool,
+        DirectInvitation {
+            receiver_id: HUBERT_ID,
+            ..invitation(FIZYKA_ID, PKBPMJ_ID)
+        },
+        cap.clone(),
+    )
+    .await;
+    assert!(matches!(res, Err(InvitationError::TooMany)));
+
+    // Repeated invitations are not counted and other senders have their own cap
+    create_direct_invitation(&pool, invitation(FIZYKA_ID, PKBPMJ_ID), cap.clone())
+        .await
+        .unwrap();
+    create_direct_invitation(&pool, invitation(INFORMATYKA_ID, HUBERT_ID), cap)
+        .await
+        .unwrap();
+
+    let invitations = get_all_direct_invitations(&pool, &HUBERT_ID).await.unwrap();
+    assert!(invitations.is_empty());
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn cannot_invite_participant(pool: PgPool) {
+    let participant = create_direct_invitation(
+        &pool,
+        DirectInvitation {
+            receiver_id: HUBERT_ID,
+            ..invitation(FIZYKA_ID, PKBPMJ_ID)
+        },
+        InvitationCap::default(),
+    )
+    .await;
+    assert!(matches!(
+        participant,
+        Err(InvitationError::AlreadyParticipant)
+    ));
+
+    let owner = create_direct_invitation(
+        &pool,
+        DirectInvitation {
+            receiver_id: PKBPMJ_ID,
+            ..invitation(FIZYKA_ID, HUBERT_ID)
+        },
+        InvitationCap::default(),
+    )
+    .await;
+    assert!(matches!(owner, Err(InvitationError::AlreadyParticipant)));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events"))]
+async fn cannot_invite_oneself(pool: PgPool) {
+    let res = create_direct_invitation(
+        &pool,
+        DirectInvitation {
+            receiver_id: PKBPMJ_ID,
+            ..invitation(FIZYKA_ID, PKBPMJ_ID)
+        },
+        InvitationCap::default(),
+    )
+    .await;
+    let Err(InvitationError::InvalidData(e)) = res else {
+        panic!("Self invitation gives the result {res:?}");
+    };
+    assert_eq!(e.to_json()["fields"][0]["field"], "receiver_id");
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events"))]
+async fn accepted_invitation_adds_participant(pool: PgPool) {
+    create_direct_invitation(
+        &pool,
+        invitation(FIZYKA_ID, PKBPMJ_ID),
+        InvitationCap::default(),
+    )
+    .await
+    .unwrap();
+
+    respond_to_direct_invitation(
+        &pool,
+        RespondDirectInvitation {
+            event_id: FIZYKA_ID,
+            sender_id: PKBPMJ_ID,
+            receiver_id: MABI19_ID,
+            is_accepted: true,
+        },
+    )
+    .await
+    .unwrap();
+
+    let is_participant = query!(
+        "SELECT 1 AS one FROM user_events WHERE user_id = $1 AND event_id = $2",
+        MABI19_ID,
+        FIZYKA_ID
+    )
+    .fetch_optional(&pool)
+    .await
+    .unwrap()
+    .is_some();
+    assert!(is_participant);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn only_owners_and_editors_invite_to_existing_events(pool: PgPool) {
+    query!(
+        "UPDATE events SET deleted_at = now() WHERE id = $1",
+        INFORMATYKA_ID
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    for (case, event_id, sender_id, expected) in [
+        ("owner", FIZYKA_ID, PKBPMJ_ID, "sent"),
+        ("editor", FIZYKA_ID, HUBERT_ID, "sent"),
+        (
+            "read-only participant",
+            MATEMATYKA_ID,
+            ADIMAC_ID,
+            "forbidden",
+        ),
+        ("stranger", FIZYKA_ID, ADIMAC_ID, "missing"),
+        ("missing event", Uuid::new_v4(), PKBPMJ_ID, "missing"),
+        ("deleted event", INFORMATYKA_ID, HUBERT_ID, "missing"),
+    ] {
+        let res = create_direct_invitation(
+            &pool,
+            invitation(event_id, sender_id),
+            InvitationCap::default(),
+        )
+        .await;
+        let outcome = match res {
+            Ok(()) => "sent",
+            Err(InvitationError::MismatchedPrivileges) => "forbidden",
+            Err(InvitationError::EventNotFound) => "missing",
+            Err(ref e) => panic!("{case} gives the error {e:?}"),
+        };
+        assert_eq!(outcome, expected, "{case}");
+    }
+
+    // Co-owners invite as well
+    query!(
+        "UPDATE user_events SET is_owner = true, can_edit = true WHERE user_id = $1 AND event_id = $2",
+        ADIMAC_ID,
+        MATEMATYKA_ID
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    create_direct_invitation(
+        &pool,
+        invitation(MATEMATYKA_ID, ADIMAC_ID),
+        InvitationCap::default(),
+    )
+    .await
+    .unwrap();
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events"))]
+async fn invitation_without_privilege_follows_sender_preference(pool: PgPool) {
+    let app = AppData::new(pool.clone()).await;
+    let sender = app.login("pkbpkp").await;
+
+    let res = sender
+        .patch(app.api("/api/v1/users/preferences"))
+        .json(&json!({ "inviteCanEdit": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    for (event_id, can_edit) in [(FIZYKA_ID, json!(null)), (MATEMATYKA_ID, json!(false))] {
+        let res = sender
+            .put(app.api("/api/v1/events/invitations/create"))
+            .json(&json!({ "event_id": event_id, "receiver_id": MABI19_ID, "can_edit": can_edit }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    let invitations = get_all_direct_invitations(&pool, &MABI19_ID).await.unwrap();
+    let can_edit = |event_id| {
+        invitations
+            .iter()
+            .find(|invitation| invitation.event_id == event_id)
+            .unwrap()
+            .can_edit
+    };
+    assert!(can_edit(FIZYKA_ID));
+    assert!(!can_edit(MATEMATYKA_ID));
+}
+
+fn email_invitation(email: &str) -> EmailInvitation {
+    EmailInvitation {
+        event_id: FIZYKA_ID,
+        sender_id: PKBPMJ_ID,
+        email: email.to_string(),
+        can_edit: true,
+    }
+}
+
+async fn send_email_invitation(pool: &PgPool, email: &str) -> Result<(), InvitationError> {
+    create_email_invitation(
+        pool,
+        email_invitation(email),
+        InvitationCap::default(),
+        &Secret::new("signing".to_string()),
+        &WebsiteOrigin("https://bimetable.example".to_string()),
+    )
+    .await
+}
+
+async fn register(pool: &PgPool, login: &str) -> Uuid {
+    try_register_user(
+        pool,
+        login,
+        SecretString::new("#very#_#strong#_#pass#".to_string()),
+        "kasia",
+        &PasswordSettings::default(),
+        &UsernameSettings::default(),
+        None,
+    )
+    .await
+    .unwrap()
+}
+
+async fn sign_up_link(pool: &PgPool) -> String {
+    query!(
+        r#"
+            SELECT payload FROM outbox
+            WHERE topic = 'emailInvitationCreated'
+        "#
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap()
+    .payload["signUpLink"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+async fn participates(pool: &PgPool, user_id: Uuid, event_id: Uuid) -> Option<bool> {
+    query!(
+        r#"
+            SELECT can_edit FROM user_events
+            WHERE user_id = $1 AND event_id = $2
+        "#,
+        user_id,
+        event_id
+    )
+    .fetch_optional(pool)
+    .await
+    .unwrap()
+    .map(|rec| rec.can_edit)
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events"))]
+async fn email_invitation_joins_event_on_registration(pool: PgPool) {
+    send_email_invitation(&pool, "Kasia@Example.com")
+        .await
+        .unwrap();
+    // Sending it again does not send another link
+    send_email_invitation(&pool, "kasia@example.com")
+        .await
+        .unwrap();
+
+    let link = sign_up_link(&pool).await;
+    assert!(link.starts_with("https://bimetable.example/register?invitation="));
+
+    let user_id = register(&pool, "kasia@example.com").await;
+    assert_eq!(participates(&pool, user_id, FIZYKA_ID).await, Some(true));
+
+    let pending = query!(r#"SELECT COUNT(*) AS "count!" FROM external_invitations"#)
+        .fetch_one(&pool)
+        .await
+        .unwrap()
+        .count;
+    assert_eq!(pending, 0);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events"))]
+async fn email_of_registered_user_sends_direct_invitation(pool: PgPool) {
+    query!(
+        "UPDATE credentials SET login = 'mabi@example.com' WHERE user_id = $1",
+        MABI19_ID
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    send_email_invitation(&pool, "Mabi@example.com")
+        .await
+        .unwrap();
+
+    let invitations = get_all_direct_invitations(&pool, &MABI19_ID).await.unwrap();
+    assert_eq!(invitations.len(), 1);
+    assert_eq!(invitations[0].event_id, FIZYKA_ID);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events"))]
+async fn sign_up_link_accepts_invitation_with_another_email(pool: PgPool) {
+    let app = AppData::new(pool.clone()).await;
+    let sender = app.login("pkbpkp").await;
+
+    let res = sender
+        .put(app.api("/api/v1/events/invitations/email"))
+        .json(&json!({ "event_id": FIZYKA_ID, "email": "kasia@example.com" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let link = sign_up_link(&pool).await;
+    let token = link.split_once("?invitation=").unwrap().1;
+
+    // A forged token does not let anyone in
+    let (id, _) = token.split_once('.').unwrap();
+    let res = app
+        .client()
+        .post(app.api("/api/v1/auth/register"))
+        .json(&json!({
+            "login": "mallory@example.com",
+            "password": "#very#_#strong#_#pass#",
+            "username": "mallory",
+            "invitation": format!("{id}.00"),
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = app
+        .client()
+        .post(app.api("/api/v1/auth/register"))
+        .json(&json!({
+            "login": "kasia@work.com",
+            "password": "#very#_#strong#_#pass#",
+            "username": "kasia",
+            "invitation": token,
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let participants = query!(
+        r#"
+            SELECT credentials.login FROM user_events
+            JOIN credentials USING (user_id)
+            WHERE event_id = $1 AND NOT is_owner
+        "#,
+        FIZYKA_ID
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap()
+    .into_iter()
+    .map(|rec| rec.login)
+    .collect::<Vec<_>>();
+    assert!(participants.contains(&"kasia@work.com".to_string()));
+    assert!(!participants.contains(&"mallory@example.com".to_string()));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events"))]
+async fn email_invitation_needs_address(pool: PgPool) {
+    let res = send_email_invitation(&pool, "kasia").await;
+    assert!(matches!(res, Err(InvitationError::InvalidData(_))));
+}
@@ -0,0 +1,157 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use axum::async_trait;
+use bimetable_db::modules::clock::TestClock;
+use bimetable_db::modules::jobs::{enqueue, Job, JobHandler, JobRunner, JobSettings, NewJob};
+use serde::{Deserialize, Serialize};
+use sqlx::{query, PgPool};
+use time::{Duration, OffsetDateTime};
+use tracing_test::traced_test;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Greeting {
+    name: String,
+}
+
+struct CountingHandler {
+    handled: Arc<AtomicUsize>,
+    fails: bool,
+}
+
+#[async_trait]
+impl JobHandler for CountingHandler {
+    fn kind(&self) -> &'static str {
+        "greeting"
+    }
+
+    async fn handle(&self, job: &Job, _pool: &PgPool) -> anyhow::Result<()> {
+        let greeting: Greeting = job.payload()?;
+        assert_eq!(greeting.name, "mabi19");
+        self.handled.fetch_add(1, Ordering::SeqCst);
+
+        if self.fails {
+            return Err(anyhow!("greeting failed"));
+        }
+        Ok(())
+    }
+}
+
+fn runner(pool: &PgPool, fails: bool) -> (JobRunner, Arc<AtomicUsize>) {
+    let handled = Arc::new(AtomicUsize::new(0));
+    let runner = JobRunner::new(pool.clone(), JobSettings::default()).register(CountingHandler {
+        handled: handled.clone(),
+        fails,
+    });
+    (runner, handled)
+}
+
+async fn enqueue_greeting(pool: &PgPool, max_attempts: i32) {
+    let mut conn = pool.acquire().await.unwrap();
+    let job = NewJob::new(
+        "greeting",
+        Greeting {
+            name: "mabi19".to_string(),
+        },
+    )
+    .unwrap()
+    .max_attempts(max_attempts);
+    enqueue(&mut conn, job).await.unwrap();
+}
+
+#[traced_test]
+#[sqlx::test]
+async fn completed_job_is_removed(pool: PgPool) {
+    enqueue_greeting(&pool, 5).await;
+    let (runner, handled) = runner(&pool, false);
+
+    assert_eq!(runner.run_once().await.unwrap(), 1);
+    assert_eq!(handled.load(Ordering::SeqCst), 1);
+
+    let count = query!(r#"SELECT COUNT(*) AS "count!" FROM jobs"#)
+        .fetch_one(&pool)
+        .await
+        .unwrap()
+        .count;
+    assert_eq!(count, 0);
+}
+
+#[traced_test]
+#[sqlx::test]
+async fn failed_job_is_retried_later(pool: PgPool) {
+    enqueue_greeting(&pool, 5).await;
+    let (runner, handled) = runner(&pool, true);
+
+    assert_eq!(runner.run_once().await.unwrap(), 1);
+    // backoff moves the job into the future
+    assert_eq!(runner.run_once().await.unwrap(), 0);
+    assert_eq!(handled.load(Ordering::SeqCst), 1);
+
+    let job = query!("SELECT attempts, last_error, failed_at FROM jobs")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(job.attempts, 1);
+    assert_eq!(job.last_error.as_deref(), Some("greeting failed"));
+    assert!(job.failed_at.is_none());
+}
+
+#[traced_test]
+#[sqlx::test]
+async fn failed_job_is_retried_by_the_runner_clock(pool: PgPool) {
+    enqueue_greeting(&pool, 5).await;
+    let clock = TestClock::new(OffsetDateTime::now_utc());
+    let (runner, handled) = runner(&pool, true);
+    let runner = runner.with_clock(Arc::new(clock.clone()));
+
+    assert_eq!(runner.run_once().await.unwrap(), 1);
+    assert_eq!(runner.run_once().await.unwrap(), 0);
+
+    // The backoff of the first failure is two seconds
+    clock.advance(Duration::seconds(3));
+    assert_eq!(runner.run_once().await.unwrap(), 1);
+    assert_eq!(handled.load(Ordering::SeqCst), 2);
+}
+
+#[traced_test]
+#[sqlx::test]
+async fn job_fails_permanently_after_max_attempts(pool: PgPool) {
+    enqueue_greeting(&pool, 1).await;
+    let (runner, _) = runner(&pool, true);
+
+    runner.run_once().await.unwrap();
+
+    let job = query!("SELECT failed_at FROM jobs")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert!(job.failed_at.is_some());
+}
+
+#[traced_test]
+#[sqlx::test]
+async fn claimed_job_is_invisible_to_other_runners(pool: PgPool) {
+    enqueue_greeting(&pool, 5).await;
+    query!("UPDATE jobs SET locked_until = now() + INTERVAL '1 minute'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let (runner, handled) = runner(&pool, false);
+
+    assert_eq!(runner.run_once().await.unwrap(), 0);
+    assert_eq!(handled.load(Ordering::SeqCst), 0);
+}
+
+#[traced_test]
+#[sqlx::test]
+async fn spawned_runner_shuts_down(pool: PgPool) {
+    enqueue_greeting(&pool, 5).await;
+    let (runner, handled) = runner(&pool, false);
+
+    let jobs = runner.spawn();
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    jobs.shutdown().await;
+
+    assert_eq!(handled.load(Ordering::SeqCst), 1);
+}
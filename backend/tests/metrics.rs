@@ -0,0 +1,80 @@
+mod tools;
+
+use bimetable_http::modules::Modules;
+use reqwest::StatusCode;
+use secrecy::Secret;
+use sqlx::PgPool;
+use tools::AppData;
+use tracing_test::traced_test;
+
+const TOKEN: &str = "scrape-token";
+
+fn with_token(modules: &mut Modules) {
+    modules.app.metrics_token = Some(Secret::from(TOKEN.to_string()));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn metrics_require_token(pool: PgPool) {
+    let app = AppData::with_modules(pool, with_token).await;
+
+    let res = app
+        .client()
+        .get(app.api("/metrics"))
+        .bearer_auth("wrong")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn metrics_are_disabled_without_token(pool: PgPool) {
+    let app = AppData::new(pool).await;
+
+    let res = app
+        .client()
+        .get(app.api("/metrics"))
+        .bearer_auth(TOKEN)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn metrics_include_pool_and_expansion(pool: PgPool) {
+    let app = AppData::with_modules(pool, with_token).await;
+    let client = app.client();
+    client
+        .post(app.api("/api/v1/auth/login"))
+        .json(&serde_json::json!({ "login": "macmac", "password": "#strong#_#pass#" }))
+        .send()
+        .await
+        .unwrap();
+    let res = client
+        .get(app.api(
+            "/api/v1/events?startsAt=2023-03-06T00:00:00Z&endsAt=2023-03-13T00:00:00Z&filter=all",
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = client
+        .get(app.api("/metrics"))
+        .bearer_auth(TOKEN)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = res.text().await.unwrap();
+    assert!(body.contains("bimetable_db_pool_size"));
+    assert!(body.contains("bimetable_db_pool_wait_seconds"));
+    assert!(body.contains("bimetable_recurrence_expansion_seconds_bucket{kind=\"weekly\""));
+}
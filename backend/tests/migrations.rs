@@ -0,0 +1,68 @@
+use bimetable_db::modules::database::{check_schema, run_migrations};
+use sqlx::{query, PgPool};
+use tracing_test::traced_test;
+
+#[traced_test]
+#[sqlx::test(migrations = false)]
+async fn run_migrations_test(pool: PgPool) {
+    let applied = run_migrations(&pool).await.unwrap();
+    assert!(!applied.is_empty());
+
+    let users_exist = query!(
+        r#"
+            SELECT to_regclass('users') IS NOT NULL AS "exists!"
+        "#
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap()
+    .exists;
+    assert!(users_exist);
+
+    assert_eq!(run_migrations(&pool).await.unwrap(), Vec::<i64>::new());
+}
+
+#[traced_test]
+#[sqlx::test(migrations = false)]
+async fn concurrent_migrations_apply_once(pool: PgPool) {
+    let (first, second) = tokio::join!(run_migrations(&pool), run_migrations(&pool));
+    let (first, second) = (first.unwrap(), second.unwrap());
+
+    assert!(first.is_empty() || second.is_empty());
+    assert!(!first.is_empty() || !second.is_empty());
+}
+
+#[traced_test]
+#[sqlx::test]
+async fn check_schema_test(pool: PgPool) {
+    let report = check_schema(&pool).await.unwrap();
+    assert!(report.is_healthy(), "{report}");
+}
+
+#[traced_test]
+#[sqlx::test(migrations = false)]
+async fn check_schema_reports_unapplied_migrations(pool: PgPool) {
+    let report = check_schema(&pool).await.unwrap();
+
+    assert!(!report.is_healthy());
+    assert!(!report.pending.is_empty());
+    assert!(report.missing_tables.contains(&"users".to_string()));
+}
+
+#[traced_test]
+#[sqlx::test]
+async fn check_schema_reports_drift(pool: PgPool) {
+    query!("DROP TABLE event_feed_tokens")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE _sqlx_migrations SET checksum = '\\x00' WHERE version = 20230428120000")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let report = check_schema(&pool).await.unwrap();
+
+    assert_eq!(report.modified, vec![20230428120000]);
+    assert_eq!(report.missing_tables, vec!["event_feed_tokens".to_string()]);
+}
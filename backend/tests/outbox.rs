@@ -0,0 +1,104 @@
+use std::sync::{Arc, Mutex};
+
+use axum::async_trait;
+use bimetable_db::modules::jobs::{JobRunner, JobSettings};
+use bimetable_db::modules::outbox::{Dispatcher, OutboxHandler, OutboxMessage};
+use bimetable_db::utils::events::exe::update_one_event;
+use bimetable_domain::api::events::{OptionalEventData, UpdateEvent};
+use sqlx::{query, PgPool};
+use tracing_test::traced_test;
+use uuid::{uuid, Uuid};
+
+const PKBPMJ_ID: Uuid = uuid!("29e40c2a-7595-42d3-98e8-9fe93ce99972");
+const MABI19_ID: Uuid = uuid!("32190025-7c15-4adb-82fd-9acc3dc8e7b6");
+const EVENT_ID: Uuid = uuid!("6d185de5-ddec-462a-aeea-7628f03d417b");
+
+#[derive(Clone, Default)]
+struct RecordingDispatcher {
+    messages: Arc<Mutex<Vec<OutboxMessage>>>,
+}
+
+#[async_trait]
+impl Dispatcher for RecordingDispatcher {
+    async fn dispatch(&self, message: &OutboxMessage) -> anyhow::Result<()> {
+        self.messages.lock().unwrap().push(message.clone());
+        Ok(())
+    }
+}
+
+fn rename() -> UpdateEvent {
+    UpdateEvent {
+        data: OptionalEventData {
+            name: Some("Polski".to_string()),
+            description: None,
+            starts_at: None,
+            ends_at: None,
+        },
+    }
+}
+
+async fn outbox_count(pool: &PgPool) -> i64 {
+    query!(r#"SELECT COUNT(*) AS "count!" FROM outbox"#)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+        .count
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn mutation_records_notification(pool: PgPool) {
+    update_one_event(&pool, PKBPMJ_ID, rename(), EVENT_ID)
+        .await
+        .unwrap();
+
+    let message = query!("SELECT topic, aggregate_id FROM outbox")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(message.topic, "eventUpdated");
+    assert_eq!(message.aggregate_id, EVENT_ID);
+
+    let jobs = query!(r#"SELECT COUNT(*) AS "count!" FROM jobs WHERE kind = 'outbox.dispatch'"#)
+        .fetch_one(&pool)
+        .await
+        .unwrap()
+        .count;
+    assert_eq!(jobs, 1);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn rejected_mutation_records_nothing(pool: PgPool) {
+    assert!(update_one_event(&pool, MABI19_ID, rename(), EVENT_ID)
+        .await
+        .is_err());
+
+    assert_eq!(outbox_count(&pool).await, 0);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn notification_is_dispatched_once(pool: PgPool) {
+    update_one_event(&pool, PKBPMJ_ID, rename(), EVENT_ID)
+        .await
+        .unwrap();
+
+    let dispatcher = RecordingDispatcher::default();
+    let runner = JobRunner::new(pool.clone(), JobSettings::default())
+        .register(OutboxHandler::default().with(dispatcher.clone()));
+
+    assert_eq!(runner.run_once().await.unwrap(), 1);
+    assert_eq!(runner.run_once().await.unwrap(), 0);
+
+    let messages = dispatcher.messages.lock().unwrap().clone();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].aggregate_id, EVENT_ID);
+
+    let dispatched = query!("SELECT dispatched_at FROM outbox")
+        .fetch_one(&pool)
+        .await
+        .unwrap()
+        .dispatched_at;
+    assert!(dispatched.is_some());
+}
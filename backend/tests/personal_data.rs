@@ -0,0 +1,204 @@
+mod tools;
+
+use std::io::{Cursor, Read};
+
+use bimetable_db::modules::jobs::{JobRunner, JobSettings};
+use bimetable_db::modules::storage::{Blobs, LocalStorage};
+use bimetable_db::utils::events::errors::EventError;
+use bimetable_db::utils::events::exe::{get_one_event, update_event_co_owner};
+use bimetable_db::utils::users::archive::ArchiveHandler;
+use bimetable_domain::api::events::UpdateCoOwner;
+use bimetable_domain::api::users::{ArchiveStatus, UserArchive};
+use bimetable_http::modules::Modules;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::StatusCode;
+use secrecy::Secret;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tools::AppData;
+use tracing_test::traced_test;
+use uuid::{uuid, Uuid};
+use zip::ZipArchive;
+
+const PKBPMJ_ID: Uuid = uuid!("29e40c2a-7595-42d3-98e8-9fe93ce99972");
+const ADIMAC_ID: Uuid = uuid!("910e81a9-56df-4c24-965a-13eff739f469");
+const HUBERT_ID: Uuid = uuid!("a9c5900e-a445-4888-8612-4a5c8cadbd9e");
+const MATEMATYKA_ID: Uuid = uuid!("6d185de5-ddec-462a-aeea-7628f03d417b");
+const FIZYKA_ID: Uuid = uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
+
+fn read_file(archive: &mut ZipArchive<Cursor<Vec<u8>>>, name: &str) -> String {
+    let mut content = String::new();
+    archive
+        .by_name(name)
+        .unwrap()
+        .read_to_string(&mut content)
+        .unwrap();
+    content
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn archive_contains_user_data(pool: PgPool) {
+    let dir = std::env::temp_dir().join(format!("bimetable-archives-{}", Uuid::new_v4()));
+    let storage_dir = dir.to_string_lossy().into_owned();
+    let app = AppData::with_modules(pool.clone(), |modules: &mut Modules| {
+        modules.app.storage_dir = storage_dir.clone();
+        modules.app.storage_signing_key = Some(Secret::from("signing".to_string()));
+    })
+    .await;
+    let runner = JobRunner::new(pool, JobSettings::default()).register(ArchiveHandler::new(
+        Blobs::new(LocalStorage::new(&dir, Secret::new(b"signing".to_vec()))),
+    ));
+    let user = app.login("hubhub").await;
+
+    let res = user
+        .post(app.api("/api/v1/users/me/export"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::ACCEPTED);
+    let archive: UserArchive = res.json().await.unwrap();
+    assert_eq!(archive.status, ArchiveStatus::Pending);
+    assert!(archive.url.is_none());
+
+    // A pending archive is not requested twice
+    let res = user
+        .post(app.api("/api/v1/users/me/export"))
+        .send()
+        .await
+        .unwrap();
+    let pending: UserArchive = res.json().await.unwrap();
+    assert_eq!(pending.id, archive.id);
+
+    assert_eq!(runner.run_once().await.unwrap(), 1);
+
+    let res = user
+        .get(app.api(&format!("/api/v1/users/me/export/{}", archive.id)))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let archive: UserArchive = res.json().await.unwrap();
+    assert_eq!(archive.status, ArchiveStatus::Ready);
+
+    let res = app
+        .client()
+        .get(app.api(&archive.url.unwrap()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()[CONTENT_TYPE], "application/zip");
+    let body = res.bytes().await.unwrap().to_vec();
+    let mut zip = ZipArchive::new(Cursor::new(body)).unwrap();
+
+    let data: Value = serde_json::from_str(&read_file(&mut zip, "data.json")).unwrap();
+    assert_eq!(data["profile"]["id"], HUBERT_ID.to_string());
+    assert_eq!(data["profile"]["login"], "hubhub");
+    assert!(data["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|event| event["id"] == FIZYKA_ID.to_string()));
+    assert!(data["memberships"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|membership| membership["eventId"] == FIZYKA_ID.to_string()));
+
+    let calendar = read_file(&mut zip, "calendar.ics");
+    assert!(calendar.starts_with("BEGIN:VCALENDAR\r\n"));
+    assert!(calendar.contains("SUMMARY:Fizyka\r\n"));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn cannot_get_foreign_archive(pool: PgPool) {
+    let app = AppData::new(pool).await;
+    let owner = app.login("hubhub").await;
+    let res = owner
+        .post(app.api("/api/v1/users/me/export"))
+        .send()
+        .await
+        .unwrap();
+    let archive: UserArchive = res.json().await.unwrap();
+
+    let other = app.login("mabmab").await;
+    let res = other
+        .get(app.api(&format!("/api/v1/users/me/export/{}", archive.id)))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn erased_owner_hands_events_over_to_co_owner(pool: PgPool) {
+    update_event_co_owner(
+        &pool,
+        PKBPMJ_ID,
+        UpdateCoOwner {
+            user_id: HUBERT_ID,
+            is_owner: true,
+        },
+        FIZYKA_ID,
+    )
+    .await
+    .unwrap();
+    let app = AppData::new(pool.clone()).await;
+    let user = app.login("pkbpkp").await;
+
+    let res = user
+        .delete(app.api("/api/v1/users/me"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+    // The co-owner takes over, events without one are gone for their participants
+    let event = get_one_event(&pool, HUBERT_ID, FIZYKA_ID).await.unwrap();
+    assert!(event.is_owned);
+    assert!(matches!(
+        get_one_event(&pool, ADIMAC_ID, MATEMATYKA_ID).await,
+        Err(EventError::NotFound)
+    ));
+
+    let res = user
+        .get(app.api("/api/v1/users/preferences"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    let res = app
+        .client()
+        .post(app.api("/auth/login"))
+        .json(&json!({ "login": "pkbpkp", "password": "#strong#_#pass#" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn erased_participant_leaves_shared_events(pool: PgPool) {
+    let app = AppData::new(pool.clone()).await;
+    let user = app.login("hubhub").await;
+
+    let res = user
+        .delete(app.api("/api/v1/users/me"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+    let event = get_one_event(&pool, PKBPMJ_ID, FIZYKA_ID).await.unwrap();
+    assert!(event.is_owned);
+    assert!(matches!(
+        get_one_event(&pool, HUBERT_ID, FIZYKA_ID).await,
+        Err(EventError::NotFound)
+    ));
+}
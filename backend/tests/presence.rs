@@ -0,0 +1,139 @@
+mod tools;
+
+use std::time::Duration;
+
+use bimetable_http::modules::Modules;
+use futures_util::{SinkExt, StreamExt};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::{Error, Message};
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tools::AppData;
+use tracing_test::traced_test;
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+const HUBERT_ID: &str = "a9c5900e-a445-4888-8612-4a5c8cadbd9e";
+const MATEMATYKA_ID: &str = "6d185de5-ddec-462a-aeea-7628f03d417b";
+const FIZYKA_ID: &str = "fd1dcdf7-de06-4aad-ba6e-f2097217a5b1";
+
+async fn access_token(app: &AppData, login: &str) -> String {
+    let res = app
+        .client()
+        .post(app.api("/auth/token"))
+        .json(&json!({ "login": login, "password": "#strong#_#pass#" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let tokens: Value = res.json().await.unwrap();
+    tokens["accessToken"].as_str().unwrap().to_string()
+}
+
+async fn connect(app: &AppData, login: &str, event_id: &str) -> Result<Socket, Error> {
+    let token = access_token(app, login).await;
+    let mut req = format!("ws://{}/api/v1/events/{event_id}/presence", app.addr)
+        .into_client_request()
+        .unwrap();
+    req.headers_mut()
+        .insert("Authorization", format!("Bearer {token}").parse().unwrap());
+
+    connect_async(req).await.map(|(socket, _)| socket)
+}
+
+async fn next_message(socket: &mut Socket) -> Value {
+    loop {
+        let message = timeout(Duration::from_secs(5), socket.next())
+            .await
+            .expect("No presence message received")
+            .unwrap()
+            .unwrap();
+        if let Message::Text(text) = message {
+            return serde_json::from_str(&text).unwrap();
+        }
+    }
+}
+
+async fn send(socket: &mut Socket, message: Value) {
+    socket
+        .send(Message::Text(message.to_string()))
+        .await
+        .unwrap();
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn editors_are_shown_until_they_leave(pool: PgPool) {
+    let app = AppData::new(pool).await;
+    let mut owner = connect(&app, "pkbpkp", FIZYKA_ID).await.unwrap();
+    assert_eq!(
+        next_message(&mut owner).await,
+        json!({ "type": "editors", "editors": [] })
+    );
+
+    let mut editor = connect(&app, "hubhub", FIZYKA_ID).await.unwrap();
+    next_message(&mut editor).await;
+    send(&mut editor, json!({ "type": "editing" })).await;
+
+    let message = next_message(&mut owner).await;
+    assert_eq!(message["editors"][0]["userId"], HUBERT_ID);
+    assert_eq!(message["editors"][0]["username"], "hubertk");
+
+    editor.close(None).await.unwrap();
+    assert_eq!(
+        next_message(&mut owner).await,
+        json!({ "type": "editors", "editors": [] })
+    );
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn presence_expires_without_refresh(pool: PgPool) {
+    let app = AppData::with_modules(pool, |modules: &mut Modules| {
+        modules.app.presence_ttl_seconds = 1;
+    })
+    .await;
+    let mut owner = connect(&app, "pkbpkp", FIZYKA_ID).await.unwrap();
+    next_message(&mut owner).await;
+
+    let mut editor = connect(&app, "hubhub", FIZYKA_ID).await.unwrap();
+    next_message(&mut editor).await;
+    send(&mut editor, json!({ "type": "editing" })).await;
+
+    let message = next_message(&mut owner).await;
+    assert_eq!(message["editors"][0]["userId"], HUBERT_ID);
+    // The editor stays connected, but stops refreshing its claim
+    assert_eq!(
+        next_message(&mut owner).await,
+        json!({ "type": "editors", "editors": [] })
+    );
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn readers_cannot_claim_presence(pool: PgPool) {
+    let app = AppData::new(pool).await;
+    let mut reader = connect(&app, "macmac", MATEMATYKA_ID).await.unwrap();
+    next_message(&mut reader).await;
+
+    send(&mut reader, json!({ "type": "editing" })).await;
+    let message = next_message(&mut reader).await;
+    assert_eq!(message["type"], "error");
+    assert_eq!(message["error_info"], "Missing editing privileges");
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn cannot_watch_foreign_event(pool: PgPool) {
+    let app = AppData::new(pool).await;
+
+    match connect(&app, "mabmab", FIZYKA_ID).await {
+        Err(Error::Http(res)) => assert_eq!(res.status(), StatusCode::NOT_FOUND.as_u16()),
+        _ => panic!("Presence of a foreign event was watched"),
+    }
+}
@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use bimetable_db::modules::jobs::{JobRunner, JobSettings};
+use bimetable_db::modules::outbox::OutboxHandler;
+use bimetable_db::utils::events::exe::update_one_event;
+use bimetable_domain::api::events::{OptionalEventData, UpdateEvent};
+use bimetable_http::modules::realtime::{
+    Bridge, LocalBridge, PgBridge, Realtime, RealtimeDispatcher, RealtimeMessage,
+};
+use serde_json::json;
+use sqlx::PgPool;
+use tokio::sync::broadcast::Receiver;
+use tokio::time::timeout;
+use tracing_test::traced_test;
+use uuid::{uuid, Uuid};
+
+const PKBPMJ_ID: Uuid = uuid!("29e40c2a-7595-42d3-98e8-9fe93ce99972");
+const EVENT_ID: Uuid = uuid!("6d185de5-ddec-462a-aeea-7628f03d417b");
+
+async fn next_message(receiver: &mut Receiver<RealtimeMessage>) -> RealtimeMessage {
+    timeout(Duration::from_secs(5), receiver.recv())
+        .await
+        .expect("No realtime message received")
+        .unwrap()
+}
+
+#[traced_test]
+#[sqlx::test]
+async fn pg_bridge_reaches_every_instance(pool: PgPool) {
+    let first = Realtime::default();
+    let second = Realtime::default();
+    let mut first_rx = first.subscribe();
+    let mut second_rx = second.subscribe();
+    let first_listener = PgBridge::listen(&pool, first).await.unwrap();
+    let second_listener = PgBridge::listen(&pool, second).await.unwrap();
+
+    let message = RealtimeMessage {
+        topic: "eventUpdated".to_string(),
+        aggregate_id: EVENT_ID,
+        payload: json!({ "eventId": EVENT_ID }),
+    };
+    PgBridge::new(pool.clone()).publish(&message).await.unwrap();
+
+    assert_eq!(next_message(&mut first_rx).await, message);
+    assert_eq!(next_message(&mut second_rx).await, message);
+
+    first_listener.shutdown().await;
+    second_listener.shutdown().await;
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn outbox_messages_reach_subscribers(pool: PgPool) {
+    let realtime = Realtime::default();
+    let mut receiver = realtime.subscribe();
+    let runner = JobRunner::new(pool.clone(), JobSettings::default()).register(
+        OutboxHandler::default().with(RealtimeDispatcher::new(LocalBridge::new(realtime))),
+    );
+
+    let update = UpdateEvent {
+        data: OptionalEventData {
+            name: Some("Polski".to_string()),
+            description: None,
+            starts_at: None,
+            ends_at: None,
+        },
+    };
+    update_one_event(&pool, PKBPMJ_ID, update, EVENT_ID)
+        .await
+        .unwrap();
+    runner.run_once().await.unwrap();
+
+    let message = next_message(&mut receiver).await;
+    assert_eq!(message.topic, "eventUpdated");
+    assert_eq!(message.aggregate_id, EVENT_ID);
+}
@@ -0,0 +1,431 @@
+use std::net::{SocketAddr, TcpListener};
+use std::sync::{Arc, Mutex};
+
+use axum::extract::State;
+use axum::routing::post;
+use axum::Router;
+use bimetable_db::modules::clock::SystemClock;
+use bimetable_db::modules::jobs::{JobRunner, JobSettings};
+use bimetable_db::utils::events::exe::create_new_event;
+use bimetable_db::utils::reminders::errors::ReminderError;
+use bimetable_db::utils::reminders::{
+    create_reminder, delete_reminder, get_user_reminders, ReminderHandler,
+};
+use bimetable_db::utils::users::quiet_hours::create_quiet_hours;
+use bimetable_domain::api::events::{
+    CreateEvent, EventData, EventPayload, RecurrenceEndsAt, RecurrenceRuleSchema, TimeRules,
+};
+use bimetable_domain::api::reminders::{CreateReminder, ReminderWebhook, WebhookFormat};
+use bimetable_domain::api::users::{CreateQuietHours, QuietAction};
+use bimetable_domain::events::models::RecurrenceRuleKind;
+use bimetable_domain::limits::RepetitionLimit;
+use http::header::CONTENT_TYPE;
+use http::HeaderMap;
+use serde_json::Value;
+use sqlx::{query, PgPool};
+use time::{Duration, OffsetDateTime, Time};
+use tracing_test::traced_test;
+use uuid::{uuid, Uuid};
+
+const PKBPMJ_ID: Uuid = uuid!("29e40c2a-7595-42d3-98e8-9fe93ce99972");
+const MABI19_ID: Uuid = uuid!("32190025-7c15-4adb-82fd-9acc3dc8e7b6");
+const FIZYKA_ID: Uuid = uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
+
+type Received = Arc<Mutex<Vec<(String, String)>>>;
+
+async fn receive(State(received): State<Received>, headers: HeaderMap, body: String) {
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    received.lock().unwrap().push((content_type, body));
+}
+
+async fn spawn_webhook() -> (String, Received) {
+    let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).unwrap();
+    let addr = listener.local_addr().unwrap();
+    let received = Received::default();
+    let router = Router::new()
+        .route("/bell", post(receive))
+        .with_state(received.clone());
+
+    tokio::spawn(async move {
+        axum::Server::from_tcp(listener)
+            .unwrap()
+            .serve(router.into_make_service())
+            .await
+            .unwrap()
+    });
+
+    (format!("http://{addr}/bell"), received)
+}
+
+/// Daily event with three entries, the first one in an hour.
+async fn create_lesson(pool: &PgPool) -> (Uuid, OffsetDateTime) {
+    let starts_at = OffsetDateTime::now_utc() + Duration::hours(1);
+    let event_id = create_new_event(
+        pool,
+        PKBPMJ_ID,
+        CreateEvent {
+            data: EventData {
+                payload: EventPayload::new("Lekcja".to_string(), None),
+                starts_at,
+                ends_at: starts_at + Duration::minutes(45),
+            },
+            recurrence_rule: Some(RecurrenceRuleSchema {
+                time_rules: TimeRules {
+                    ends_at: Some(RecurrenceEndsAt::Count(3)),
+                    interval: 1,
+                },
+                kind: RecurrenceRuleKind::Daily,
+            }),
+        },
+        RepetitionLimit::default(),
+    )
+    .await
+    .unwrap();
+
+    (event_id, starts_at)
+}
+
+fn reminder(event_id: Uuid, webhook: Option<ReminderWebhook>) -> CreateReminder {
+    CreateReminder {
+        event_id,
+        minutes_before: 10,
+        webhook,
+        critical: false,
+    }
+}
+
+async fn scheduled_runs(pool: &PgPool) -> Vec<OffsetDateTime> {
+    query!("SELECT run_at FROM jobs WHERE kind = 'reminders.fire' ORDER BY run_at")
+        .fetch_all(pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|job| job.run_at)
+        .collect()
+}
+
+/// Runs scheduled reminders as if their time had come, letting webhooks reach the test server.
+async fn fire_reminders(pool: &PgPool) -> usize {
+    let handler = ReminderHandler::new(Arc::new(SystemClock), vec!["127.0.0.1".to_string()]);
+    fire_reminders_with(pool, handler).await
+}
+
+async fn fire_reminders_with(pool: &PgPool, handler: ReminderHandler) -> usize {
+    query!("UPDATE jobs SET run_at = now() WHERE kind = 'reminders.fire'")
+        .execute(pool)
+        .await
+        .unwrap();
+
+    JobRunner::new(pool.clone(), JobSettings::default())
+        .register(handler)
+        .run_once()
+        .await
+        .unwrap()
+}
+
+async fn reminder_messages(pool: &PgPool) -> i64 {
+    query!(r#"SELECT COUNT(*) AS "count!" FROM outbox WHERE topic = 'reminderDue'"#)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+        .count
+}
+
+/// Quiet hours of every day, from an hour ago until in an hour.
+async fn quiet_now(pool: &PgPool, action: QuietAction) {
+    let now = OffsetDateTime::now_utc();
+    let minute = |at: OffsetDateTime| Time::from_hms(at.hour(), at.minute(), 0).unwrap();
+    create_quiet_hours(
+        pool,
+        PKBPMJ_ID,
+        CreateQuietHours {
+            starts_at: minute(now - Duration::hours(1)),
+            ends_at: minute(now + Duration::hours(1)),
+            days: Vec::new(),
+            action,
+        },
+    )
+    .await
+    .unwrap();
+}
+
+fn assert_close(a: OffsetDateTime, b: OffsetDateTime) {
+    assert!((a - b).abs() < Duration::milliseconds(1), "{a} != {b}");
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn reminder_is_scheduled_before_next_entry(pool: PgPool) {
+    let (event_id, starts_at) = create_lesson(&pool).await;
+
+    create_reminder(
+        &pool,
+        PKBPMJ_ID,
+        reminder(event_id, None),
+        OffsetDateTime::now_utc(),
+    )
+    .await
+    .unwrap();
+
+    let runs = scheduled_runs(&pool).await;
+    assert_eq!(runs.len(), 1);
+    assert_close(runs[0], starts_at - Duration::minutes(10));
+    assert_eq!(get_user_reminders(&pool, PKBPMJ_ID).await.unwrap().len(), 1);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn webhook_receives_entry(pool: PgPool) {
+    let (url, received) = spawn_webhook().await;
+    let (event_id, starts_at) = create_lesson(&pool).await;
+    let webhook = ReminderWebhook {
+        url,
+        format: WebhookFormat::Json,
+    };
+    create_reminder(
+        &pool,
+        PKBPMJ_ID,
+        reminder(event_id, Some(webhook)),
+        OffsetDateTime::now_utc(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(fire_reminders(&pool).await, 1);
+
+    let received = received.lock().unwrap().clone();
+    assert_eq!(received.len(), 1);
+    assert!(received[0].0.starts_with("application/json"));
+    let body: Value = serde_json::from_str(&received[0].1).unwrap();
+    assert_eq!(body["name"], "Lekcja");
+    assert_eq!(body["minutesBefore"], 10);
+    assert_eq!(body["entry"]["eventId"], event_id.to_string());
+
+    let runs = scheduled_runs(&pool).await;
+    assert_eq!(runs.len(), 1);
+    assert_close(
+        runs[0],
+        starts_at + Duration::days(1) - Duration::minutes(10),
+    );
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn webhook_cannot_reach_private_addresses(pool: PgPool) {
+    let (url, received) = spawn_webhook().await;
+    let port = url.split(':').nth(2).unwrap().trim_end_matches("/bell");
+    let (event_id, _) = create_lesson(&pool).await;
+    for url in [
+        url.clone(),
+        format!("http://localhost:{port}/bell"),
+        "http://169.254.169.254/latest/meta-data".to_string(),
+        "http://10.0.0.1/bell".to_string(),
+        "http://[::1]/bell".to_string(),
+    ] {
+        let webhook = ReminderWebhook {
+            url,
+            format: WebhookFormat::Json,
+        };
+        create_reminder(
+            &pool,
+            PKBPMJ_ID,
+            reminder(event_id, Some(webhook)),
+            OffsetDateTime::now_utc(),
+        )
+        .await
+        .unwrap();
+    }
+
+    fire_reminders_with(&pool, ReminderHandler::default()).await;
+
+    assert!(received.lock().unwrap().is_empty());
+    let failed = query!(
+        r#"SELECT COUNT(*) AS "count!" FROM jobs WHERE kind = 'reminders.fire' AND last_error IS NOT NULL"#
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap()
+    .count;
+    assert_eq!(failed, 5);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn ics_webhook_receives_calendar(pool: PgPool) {
+    let (url, received) = spawn_webhook().await;
+    let (event_id, _) = create_lesson(&pool).await;
+    let webhook = ReminderWebhook {
+        url,
+        format: WebhookFormat::Ics,
+    };
+    create_reminder(
+        &pool,
+        PKBPMJ_ID,
+        reminder(event_id, Some(webhook)),
+        OffsetDateTime::now_utc(),
+    )
+    .await
+    .unwrap();
+
+    fire_reminders(&pool).await;
+
+    let received = received.lock().unwrap().clone();
+    assert!(received[0].0.starts_with("text/calendar"));
+    assert!(received[0].1.contains("SUMMARY:Lekcja"));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn reminder_without_webhook_notifies_user(pool: PgPool) {
+    let (event_id, _) = create_lesson(&pool).await;
+    create_reminder(
+        &pool,
+        PKBPMJ_ID,
+        reminder(event_id, None),
+        OffsetDateTime::now_utc(),
+    )
+    .await
+    .unwrap();
+
+    fire_reminders(&pool).await;
+
+    let message = query!("SELECT aggregate_id FROM outbox WHERE topic = 'reminderDue'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(message.aggregate_id, PKBPMJ_ID);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn deleted_reminder_is_not_sent(pool: PgPool) {
+    let (url, received) = spawn_webhook().await;
+    let (event_id, _) = create_lesson(&pool).await;
+    let webhook = ReminderWebhook {
+        url,
+        format: WebhookFormat::Json,
+    };
+    let reminder_id = create_reminder(
+        &pool,
+        PKBPMJ_ID,
+        reminder(event_id, Some(webhook)),
+        OffsetDateTime::now_utc(),
+    )
+    .await
+    .unwrap();
+
+    delete_reminder(&pool, PKBPMJ_ID, reminder_id)
+        .await
+        .unwrap();
+    fire_reminders(&pool).await;
+
+    assert!(received.lock().unwrap().is_empty());
+    assert!(scheduled_runs(&pool).await.is_empty());
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn cannot_remind_about_foreign_event(pool: PgPool) {
+    let res = create_reminder(
+        &pool,
+        MABI19_ID,
+        reminder(FIZYKA_ID, None),
+        OffsetDateTime::now_utc(),
+    )
+    .await;
+
+    assert!(matches!(res, Err(ReminderError::EventMissing)));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn cannot_use_non_http_webhook(pool: PgPool) {
+    let webhook = ReminderWebhook {
+        url: "file:///etc/passwd".to_string(),
+        format: WebhookFormat::Json,
+    };
+    let res = create_reminder(
+        &pool,
+        PKBPMJ_ID,
+        reminder(FIZYKA_ID, Some(webhook)),
+        OffsetDateTime::now_utc(),
+    )
+    .await;
+
+    assert!(matches!(res, Err(ReminderError::InvalidData(_))));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn reminder_waits_for_quiet_hours_to_end(pool: PgPool) {
+    let (event_id, _) = create_lesson(&pool).await;
+    create_reminder(
+        &pool,
+        PKBPMJ_ID,
+        reminder(event_id, None),
+        OffsetDateTime::now_utc(),
+    )
+    .await
+    .unwrap();
+    quiet_now(&pool, QuietAction::Defer).await;
+
+    fire_reminders(&pool).await;
+
+    assert_eq!(reminder_messages(&pool).await, 0);
+    let runs = scheduled_runs(&pool).await;
+    assert_eq!(runs.len(), 1);
+    assert!(runs[0] > OffsetDateTime::now_utc() + Duration::minutes(58));
+    assert!(runs[0] <= OffsetDateTime::now_utc() + Duration::hours(1));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn quiet_hours_suppress_reminder(pool: PgPool) {
+    let (event_id, starts_at) = create_lesson(&pool).await;
+    create_reminder(
+        &pool,
+        PKBPMJ_ID,
+        reminder(event_id, None),
+        OffsetDateTime::now_utc(),
+    )
+    .await
+    .unwrap();
+    quiet_now(&pool, QuietAction::Suppress).await;
+
+    fire_reminders(&pool).await;
+
+    assert_eq!(reminder_messages(&pool).await, 0);
+    let runs = scheduled_runs(&pool).await;
+    assert_eq!(runs.len(), 1);
+    assert_close(
+        runs[0],
+        starts_at + Duration::days(1) - Duration::minutes(10),
+    );
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn critical_reminder_ignores_quiet_hours(pool: PgPool) {
+    let (event_id, _) = create_lesson(&pool).await;
+    create_reminder(
+        &pool,
+        PKBPMJ_ID,
+        CreateReminder {
+            critical: true,
+            ..reminder(event_id, None)
+        },
+        OffsetDateTime::now_utc(),
+    )
+    .await
+    .unwrap();
+    quiet_now(&pool, QuietAction::Defer).await;
+
+    fire_reminders(&pool).await;
+
+    assert_eq!(reminder_messages(&pool).await, 1);
+    assert!(get_user_reminders(&pool, PKBPMJ_ID).await.unwrap()[0].critical);
+}
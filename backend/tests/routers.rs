@@ -0,0 +1,87 @@
+mod tools;
+
+use bimetable_http::modules::query_log::{self, DB_QUERIES};
+use bimetable_http::modules::Modules;
+use reqwest::StatusCode;
+use sqlx::PgPool;
+use tools::AppData;
+use tracing_log::LogTracer;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Registry;
+use tracing_test::traced_test;
+
+const FIZYKA_PRESENCE: &str = "/api/v1/events/fd1dcdf7-de06-4aad-ba6e-f2097217a5b1/presence";
+
+fn without_features(modules: &mut Modules) {
+    modules.app.example_routes = Some(false);
+    modules.app.admin_routes = false;
+    modules.app.realtime_routes = false;
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn feature_routers_are_served_by_default(pool: PgPool) {
+    let app = AppData::new(pool).await;
+    let client = app.client();
+
+    let res = client.get(app.api("/api/v1/ex/")).send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    for path in ["/api/v1/admin/maintenance", FIZYKA_PRESENCE] {
+        let res = client.get(app.api(path)).send().await.unwrap();
+        assert_ne!(res.status(), StatusCode::NOT_FOUND, "{path}");
+    }
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn disabled_feature_routers_are_not_found(pool: PgPool) {
+    let app = AppData::with_modules(pool, without_features).await;
+    let client = app.client();
+
+    for path in [
+        "/api/v1/ex/",
+        "/ex/",
+        "/api/v1/admin/maintenance",
+        FIZYKA_PRESENCE,
+    ] {
+        let res = client.get(app.api(path)).send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND, "{path}");
+    }
+
+    let res = client
+        .get(app.api("/api/v1/auth/validate"))
+        .send()
+        .await
+        .unwrap();
+    assert_ne!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(fixtures("users"))]
+async fn sql_logging_counts_queries_of_requests(pool: PgPool) {
+    // sqlx logs statements through `log`, the test runtime runs the server on this thread
+    let _ = LogTracer::init();
+    let _guard = tracing::subscriber::set_default(Registry::default().with(query_log::layer()));
+
+    let app = AppData::new(pool.clone()).await;
+    let res = app
+        .client()
+        .post(app.api("/api/v1/auth/token"))
+        .json(&serde_json::json!({ "login": "macmac", "password": "#strong#_#pass#" }))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.headers().get(DB_QUERIES).is_none());
+
+    let app = AppData::with_modules(pool, |modules| modules.app.sql_logging = true).await;
+    let res = app
+        .client()
+        .post(app.api("/api/v1/auth/token"))
+        .json(&serde_json::json!({ "login": "macmac", "password": "#strong#_#pass#" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let count: u32 = res.headers()[DB_QUERIES].to_str().unwrap().parse().unwrap();
+    assert!(count > 0);
+}
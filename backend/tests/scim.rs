@@ -0,0 +1,261 @@
+mod tools;
+
+use bimetable_domain::api::scim::{ScimListResponse, ScimUser};
+use bimetable_domain::api::search::SearchUsersResult;
+use bimetable_domain::api::users::UserHandle;
+use bimetable_http::modules::Modules;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{RequestBuilder, StatusCode};
+use secrecy::Secret;
+use serde_json::{json, Value};
+use sqlx::{query, PgPool};
+use tools::AppData;
+use tracing_test::traced_test;
+use uuid::{uuid, Uuid};
+
+const TOKEN: &str = "provisioning-token";
+const ADIMAC_ID: Uuid = uuid!("910e81a9-56df-4c24-965a-13eff739f469");
+
+fn with_token(modules: &mut Modules) {
+    modules.app.scim_token = Some(Secret::from(TOKEN.to_string()));
+}
+
+fn scim(request: RequestBuilder) -> RequestBuilder {
+    request
+        .bearer_auth(TOKEN)
+        .header(CONTENT_TYPE, "application/scim+json")
+}
+
+async fn login_status(app: &AppData, login: &str, password: &str) -> StatusCode {
+    app.client()
+        .post(app.api("/auth/login"))
+        .json(&json!({ "login": login, "password": password }))
+        .send()
+        .await
+        .unwrap()
+        .status()
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn provisioning_requires_token(pool: PgPool) {
+    let app = AppData::new(pool.clone()).await;
+    let res = scim(app.client().get(app.api("/scim/v2/Users")))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    let app = AppData::with_modules(pool, with_token).await;
+    let res = app
+        .client()
+        .get(app.api("/scim/v2/Users"))
+        .bearer_auth("wrong")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    let error: Value = res.json().await.unwrap();
+    assert_eq!(error["status"], "401");
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn provision_and_deactivate_user(pool: PgPool) {
+    let app = AppData::with_modules(pool, with_token).await;
+    let client = app.client();
+
+    let res = scim(client.post(app.api("/scim/v2/Users")))
+        .body(
+            json!({
+                "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+                "userName": "student01",
+                "displayName": "Student",
+                "externalId": "S-01",
+                "password": "Tr4vel-Lamp-Orbit",
+                "emails": [{ "value": "student01@example.com", "primary": true }]
+            })
+            .to_string(),
+        )
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::CREATED);
+    assert_eq!(res.headers()[CONTENT_TYPE], "application/scim+json");
+    let user: ScimUser = res.json().await.unwrap();
+    assert_eq!(user.user_name, "student01");
+    assert_eq!(user.external_id.as_deref(), Some("S-01"));
+    assert!(user.active);
+    assert_eq!(
+        login_status(&app, "student01", "Tr4vel-Lamp-Orbit").await,
+        StatusCode::OK
+    );
+
+    let res = scim(client.post(app.api("/scim/v2/Users")))
+        .body(json!({ "userName": "other01", "externalId": "S-01" }).to_string())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+    let error: Value = res.json().await.unwrap();
+    assert_eq!(error["scimType"], "uniqueness");
+
+    let res = scim(client.get(app.api("/scim/v2/Users")))
+        .query(&[("filter", r#"externalId eq "S-01""#)])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let list: ScimListResponse = res.json().await.unwrap();
+    assert_eq!(list.total_results, 1);
+    assert_eq!(list.resources, vec![user]);
+    let user_id = list.resources[0].id;
+
+    let res = scim(client.patch(app.api(&format!("/scim/v2/Users/{user_id}"))))
+        .body(
+            json!({
+                "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+                "Operations": [{ "op": "Replace", "path": "active", "value": "False" }]
+            })
+            .to_string(),
+        )
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let user: ScimUser = res.json().await.unwrap();
+    assert!(!user.active);
+    assert_eq!(
+        login_status(&app, "student01", "Tr4vel-Lamp-Orbit").await,
+        StatusCode::FORBIDDEN
+    );
+
+    let res = scim(client.put(app.api(&format!("/scim/v2/Users/{user_id}"))))
+        .body(json!({ "userName": "student01", "displayName": "StudentOne" }).to_string())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let user: ScimUser = res.json().await.unwrap();
+    assert!(user.active);
+    assert_eq!(user.display_name, "StudentOne");
+    assert_eq!(
+        login_status(&app, "student01", "Tr4vel-Lamp-Orbit").await,
+        StatusCode::OK
+    );
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn users_are_filtered_by_user_name(pool: PgPool) {
+    let app = AppData::with_modules(pool, with_token).await;
+    let client = app.client();
+
+    let res = scim(client.get(app.api("/scim/v2/Users")))
+        .query(&[("filter", r#"userName eq "macmac""#)])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let list: ScimListResponse = res.json().await.unwrap();
+    assert_eq!(list.total_results, 1);
+    assert_eq!(list.resources[0].id, ADIMAC_ID);
+    assert_eq!(list.resources[0].display_name, "adimac93");
+
+    let res = scim(client.get(app.api("/scim/v2/Users")))
+        .query(&[("count", "2"), ("startIndex", "2")])
+        .send()
+        .await
+        .unwrap();
+    let list: ScimListResponse = res.json().await.unwrap();
+    assert_eq!(list.total_results, 4);
+    assert_eq!(list.items_per_page, 2);
+
+    let res = scim(client.get(app.api("/scim/v2/Users")))
+        .query(&[("filter", r#"emails co "example""#)])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let res = scim(client.get(app.api(&format!("/scim/v2/Users/{}", Uuid::new_v4()))))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn renamed_users_keep_their_former_handles(pool: PgPool) {
+    let app = AppData::with_modules(pool.clone(), with_token).await;
+    let client = app.client();
+
+    let res = scim(client.put(app.api(&format!("/scim/v2/Users/{ADIMAC_ID}"))))
+        .body(json!({ "userName": "macmac", "displayName": "adimac" }).to_string())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = client
+        .post(app.api("/auth/login"))
+        .json(&json!({ "login": "macmac", "password": "#strong#_#pass#" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = client
+        .get(app.api(&format!("/users/{ADIMAC_ID}/handles")))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let handles: Vec<UserHandle> = res.json().await.unwrap();
+    assert_eq!(handles.len(), 2);
+    assert_eq!(handles[0].username, "adimac");
+    assert!(handles[0].replaced_at.is_none());
+    assert_eq!(
+        (handles[1].username.as_str(), handles[1].tag),
+        ("adimac93", 0)
+    );
+    assert!(handles[1].replaced_at.is_some());
+
+    let res = client
+        .get(app.api("/search/users"))
+        .query(&[("text", "adimac93"), ("tag", "0")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let users: Vec<SearchUsersResult> = res.json().await.unwrap();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0].id, ADIMAC_ID);
+    assert_eq!(users[0].username, "adimac");
+
+    let res = scim(client.post(app.api("/scim/v2/Users")))
+        .body(
+            json!({
+                "userName": "student01",
+                "displayName": "adimac93",
+                "password": "Tr4vel-Lamp-Orbit"
+            })
+            .to_string(),
+        )
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let tag = query!(
+        r#"
+            SELECT tag FROM users
+            WHERE username = 'adimac93'
+        "#
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap()
+    .tag;
+    assert_ne!(tag, 0);
+}
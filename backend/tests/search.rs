@@ -1,14 +1,24 @@
-use bimetable::modules::database::PgQuery;
-use bimetable::routes::events::models::EventFilter;
-use bimetable::routes::search::models::SearchEvents;
-use bimetable::utils::search::{search_many_events, QueryEvent, QueryUser, Search};
-use sqlx::PgPool;
+mod tools;
+
+use bimetable_db::modules::database::PgQuery;
+use bimetable_db::utils::search::{get_users, search_many_events, QueryEvent, QueryUser, Search};
+use bimetable_domain::api::events::EventFilter;
+use bimetable_domain::api::search::{
+    EventFacets, SearchEvents, SearchEventsResult, SearchMode, SearchUsers, SearchUsersResult,
+};
+use reqwest::header::{ACCEPT, CACHE_CONTROL, CONTENT_TYPE};
+use reqwest::{Client, StatusCode};
+use serde_json::json;
+use sqlx::{query, PgPool};
+use tools::AppData;
 use tracing_test::traced_test;
 use uuid::{uuid, Uuid};
 
 const ADIMAC_ID: Uuid = uuid!("910e81a9-56df-4c24-965a-13eff739f469");
 const PKBPMJ_ID: Uuid = uuid!("29e40c2a-7595-42d3-98e8-9fe93ce99972");
+const MABI19_ID: Uuid = uuid!("32190025-7c15-4adb-82fd-9acc3dc8e7b6");
 const HUBERT_ID: Uuid = uuid!("a9c5900e-a445-4888-8612-4a5c8cadbd9e");
+const FIZYKA_ID: Uuid = uuid!("fd1dcdf7-de06-4aad-ba6e-f2097217a5b1");
 
 #[derive(Debug, PartialEq)]
 struct SimpleEvent {
@@ -30,7 +40,7 @@ impl From<QueryEvent> for SimpleEvent {
 async fn search_users_test(pool: PgPool) {
     let mut conn = pool.acquire().await.unwrap();
     let mut q = PgQuery::new(Search::new("ad".to_string()), &mut conn);
-    let res = q.search_users(None).await.unwrap();
+    let res = q.search_users(None, None).await.unwrap();
 
     assert_eq!(
         res,
@@ -47,7 +57,7 @@ async fn search_users_test(pool: PgPool) {
 async fn search_users_test_case_insensitive(pool: PgPool) {
     let mut conn = pool.acquire().await.unwrap();
     let mut q = PgQuery::new(Search::new("hU".to_string()), &mut conn);
-    let res = q.search_users(None).await.unwrap();
+    let res = q.search_users(None, None).await.unwrap();
 
     assert_eq!(
         res,
@@ -59,19 +69,99 @@ async fn search_users_test_case_insensitive(pool: PgPool) {
     )
 }
 
+#[sqlx::test(fixtures("users"))]
+#[traced_test]
+async fn search_users_fuzzy_test(pool: PgPool) {
+    let mut conn = pool.acquire().await.unwrap();
+    let mut q = PgQuery::new(Search::new("pkbpmj".to_string()), &mut conn);
+    let res = q.search_users_fuzzy(None, None).await.unwrap();
+
+    assert_eq!(
+        res,
+        vec![QueryUser {
+            id: PKBPMJ_ID,
+            username: "pkb-pmj".to_string(),
+            tag: 0000,
+        }]
+    )
+}
+
+#[sqlx::test(fixtures("users"))]
+#[traced_test]
+async fn search_users_fuzzy_matches_substring(pool: PgPool) {
+    let mut conn = pool.acquire().await.unwrap();
+    let mut q = PgQuery::new(Search::new("MAC".to_string()), &mut conn);
+    let res = q.search_users_fuzzy(None, None).await.unwrap();
+
+    assert_eq!(
+        res,
+        vec![QueryUser {
+            id: ADIMAC_ID,
+            username: "adimac93".to_string(),
+            tag: 0000,
+        }]
+    )
+}
+
+#[sqlx::test(fixtures("users"))]
+#[traced_test]
+async fn search_users_fuzzy_escapes_wildcards(pool: PgPool) {
+    let mut conn = pool.acquire().await.unwrap();
+    let mut q = PgQuery::new(Search::new("%".to_string()), &mut conn);
+    let res = q.search_users_fuzzy(None, None).await.unwrap();
+
+    assert!(res.is_empty())
+}
+
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+#[traced_test]
+async fn search_users_leaves_out_participants_of_event(pool: PgPool) {
+    let found = |user_id| {
+        let pool = pool.clone();
+        async move {
+            let search = SearchUsers {
+                text: String::new(),
+                tag: None,
+                mode: SearchMode::Prefix,
+                event_id: Some(FIZYKA_ID),
+            };
+            let mut ids: Vec<Uuid> = get_users(&pool, user_id, search)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|user| user.id)
+                .collect();
+            ids.sort();
+            ids
+        }
+    };
+
+    // The owner and the editor of the event are left out
+    assert_eq!(found(HUBERT_ID).await, vec![MABI19_ID, ADIMAC_ID]);
+
+    // Strangers don't learn who takes part in the event
+    assert_eq!(
+        found(ADIMAC_ID).await,
+        vec![PKBPMJ_ID, MABI19_ID, HUBERT_ID]
+    );
+}
+
 #[sqlx::test(fixtures("users", "events", "user_events"))]
 #[traced_test]
 async fn search_owned_events_test(pool: PgPool) {
     let res: Vec<SimpleEvent> = search_many_events(
         &pool,
+        PKBPMJ_ID,
         SearchEvents {
             text: "ma".to_string(),
-            user_id: PKBPMJ_ID,
             filter: EventFilter::Owned,
+            limit: None,
+            offset: None,
         },
     )
     .await
     .unwrap()
+    .events
     .into_iter()
     .map(|x| SimpleEvent::from(x))
     .collect();
@@ -90,14 +180,17 @@ async fn search_owned_events_test(pool: PgPool) {
 async fn search_shared_events_test(pool: PgPool) {
     let res: Vec<SimpleEvent> = search_many_events(
         &pool,
+        ADIMAC_ID,
         SearchEvents {
             text: "ma".to_string(),
-            user_id: ADIMAC_ID,
             filter: EventFilter::Shared,
+            limit: None,
+            offset: None,
         },
     )
     .await
     .unwrap()
+    .events
     .into_iter()
     .map(|x| SimpleEvent::from(x))
     .collect();
@@ -116,14 +209,17 @@ async fn search_shared_events_test(pool: PgPool) {
 async fn search_many_events_test(pool: PgPool) {
     let mut res: Vec<SimpleEvent> = search_many_events(
         &pool,
+        HUBERT_ID,
         SearchEvents {
             text: "in".to_string(),
-            user_id: HUBERT_ID,
             filter: EventFilter::All,
+            limit: None,
+            offset: None,
         },
     )
     .await
     .unwrap()
+    .events
     .into_iter()
     .map(|x| SimpleEvent::from(x))
     .collect();
@@ -143,3 +239,213 @@ async fn search_many_events_test(pool: PgPool) {
         ]
     )
 }
+
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+#[traced_test]
+async fn search_events_facets_test(pool: PgPool) {
+    let res = search_many_events(
+        &pool,
+        HUBERT_ID,
+        SearchEvents {
+            text: "in".to_string(),
+            filter: EventFilter::Owned,
+            limit: None,
+            offset: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(res.total, 1);
+    assert_eq!(
+        res.facets,
+        EventFacets {
+            owned: 1,
+            shared: 1,
+            recurring: 1,
+            one_off: 1,
+        }
+    );
+}
+
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+#[traced_test]
+async fn search_events_page_test(pool: PgPool) {
+    let search = |offset| SearchEvents {
+        text: "in".to_string(),
+        filter: EventFilter::All,
+        limit: Some(1),
+        offset: Some(offset),
+    };
+
+    let first = search_many_events(&pool, HUBERT_ID, search(0))
+        .await
+        .unwrap();
+    let first: Vec<SimpleEvent> = first.events.into_iter().map(SimpleEvent::from).collect();
+    assert_eq!(
+        first,
+        vec![SimpleEvent {
+            id: uuid!("374ae0ab-d473-4752-b77f-cae55c69245c"),
+            name: "Infa".to_string(),
+        }]
+    );
+
+    let past_end = search_many_events(&pool, HUBERT_ID, search(2))
+        .await
+        .unwrap();
+    assert!(past_end.events.is_empty());
+    assert_eq!(past_end.total, 2);
+    assert_eq!(past_end.facets.owned, 1);
+}
+
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+#[traced_test]
+async fn search_events_of_the_signed_in_user(pool: PgPool) {
+    let app = AppData::new(pool).await;
+    let client = app.client();
+    let res = client
+        .post(app.api("/auth/login"))
+        .json(&json!({ "login": "macmac", "password": "#strong#_#pass#" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = client
+        .get(app.api("/search/events"))
+        .query(&[("text", "ma"), ("filter", "owned")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let res: SearchEventsResult = res.json().await.unwrap();
+    assert!(res.events.is_empty());
+    assert_eq!(res.facets.owned, 0);
+    assert_eq!(res.facets.shared, 1);
+
+    // Events of other users can't be searched
+    let res = client
+        .get(app.api("/search/events"))
+        .query(&[
+            ("text", "ma"),
+            ("filter", "owned"),
+            ("userId", &PKBPMJ_ID.to_string()),
+        ])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+async fn searched_usernames(app: &AppData, client: &Client, text: &str) -> Vec<String> {
+    let res = client
+        .get(app.api("/search/users"))
+        .query(&[("text", text)])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    res.json::<Vec<SearchUsersResult>>()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|user| user.username)
+        .collect()
+}
+
+#[sqlx::test(fixtures("users"))]
+#[traced_test]
+async fn search_results_are_cached_until_a_write(pool: PgPool) {
+    let app = AppData::new(pool.clone()).await;
+    let client = app.client();
+    let res = client
+        .post(app.api("/auth/login"))
+        .json(&json!({ "login": "macmac", "password": "#strong#_#pass#" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    assert_eq!(searched_usernames(&app, &client, "ad").await, ["adimac93"]);
+
+    query!(
+        r#"
+            UPDATE users SET username = 'zadimac'
+            WHERE id = $1
+        "#,
+        ADIMAC_ID,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    assert_eq!(searched_usernames(&app, &client, "ad").await, ["adimac93"]);
+
+    let res = client
+        .post(app.api("/auth/register"))
+        .json(&json!({
+            "login": "admiral",
+            "password": "#very#_#strong#_#pass#",
+            "username": "admiral",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(searched_usernames(&app, &client, "ad").await, ["admiral"]);
+}
+
+#[sqlx::test(fixtures("users"))]
+#[traced_test]
+async fn search_users_in_msgpack(pool: PgPool) {
+    let app = AppData::new(pool).await;
+    let client = app.client();
+    let res = client
+        .post(app.api("/auth/login"))
+        .json(&json!({ "login": "macmac", "password": "#strong#_#pass#" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = client
+        .get(app.api("/search/users"))
+        .query(&[("text", "ad")])
+        .header(ACCEPT, "application/json;q=0.5, application/msgpack")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()[CONTENT_TYPE], "application/msgpack");
+
+    let users: Vec<SearchUsersResult> = rmp_serde::from_slice(&res.bytes().await.unwrap()).unwrap();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0].id, ADIMAC_ID);
+    assert_eq!(users[0].username, "adimac93");
+}
+
+#[sqlx::test(fixtures("users"))]
+#[traced_test]
+async fn browsers_reuse_results_as_long_as_the_server(pool: PgPool) {
+    let app = AppData::new(pool).await;
+    let client = app.client();
+    let res = client
+        .post(app.api("/auth/login"))
+        .json(&json!({ "login": "macmac", "password": "#strong#_#pass#" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    for path in [
+        "/search/users?text=ad".to_string(),
+        format!("/users/{ADIMAC_ID}/handles"),
+    ] {
+        let res = client.get(app.api(&path)).send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers()[CACHE_CONTROL],
+            "private, max-age=5, stale-while-revalidate=30"
+        );
+    }
+}
@@ -0,0 +1,232 @@
+//! Wire formats of the v1 API, changing any of them breaks clients.
+
+use std::fmt::Debug;
+
+use bimetable_db::modules::outbox::Topic;
+use bimetable_domain::api::events::{
+    iso8601_duration, CreateEvent, EntrySort, EventFields, EventFilter, OverrideEventData,
+    RecurrenceEndsAt, RecurrenceRuleSchema, SortDirection, TimeRules, UpdateEvent,
+};
+use bimetable_domain::api::reminders::{CreateReminder, WebhookFormat};
+use bimetable_domain::api::search::SearchMode;
+use bimetable_domain::api::users::{CreateQuietHours, QuietAction, UpdateUserPreferences};
+use bimetable_domain::events::models::{
+    DayOfWeek, OverrideStatus, OverrideStrategy, RecurrenceRuleKind,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{from_value, json, to_value, Value};
+use time::macros::{datetime, time};
+use time::Duration;
+
+/// Asserts that `value` is serialized as `wire` and that `wire` is read back unchanged.
+fn pin<T: Serialize + DeserializeOwned + Debug>(value: T, wire: Value) {
+    assert_eq!(to_value(&value).unwrap(), wire, "{value:?}");
+    let read: T = from_value(wire.clone()).unwrap();
+    assert_eq!(to_value(read).unwrap(), wire);
+}
+
+fn rejects<T: DeserializeOwned + Debug>(wire: Value) {
+    let res = from_value::<T>(wire.clone());
+    assert!(res.is_err(), "{wire} was accepted as {res:?}");
+}
+
+#[test]
+fn query_enums_are_camel_case() {
+    pin(EventFilter::All, json!("all"));
+    pin(EventFilter::Owned, json!("owned"));
+    pin(EventFilter::Shared, json!("shared"));
+    pin(EntrySort::Start, json!("start"));
+    pin(EntrySort::End, json!("end"));
+    pin(EntrySort::EventName, json!("eventName"));
+    pin(SortDirection::Asc, json!("asc"));
+    pin(SortDirection::Desc, json!("desc"));
+    pin(EventFields::Full, json!("full"));
+    pin(EventFields::Name, json!("name"));
+    pin(SearchMode::Prefix, json!("prefix"));
+    pin(SearchMode::Fuzzy, json!("fuzzy"));
+
+    rejects::<EventFilter>(json!("All"));
+    rejects::<EntrySort>(json!("event_name"));
+}
+
+#[test]
+fn event_enums_are_camel_case() {
+    pin(DayOfWeek::Monday, json!("monday"));
+    pin(DayOfWeek::Sunday, json!("sunday"));
+    pin(OverrideStrategy::Latest, json!("latest"));
+    pin(OverrideStrategy::Merge, json!("merge"));
+    pin(OverrideStrategy::Reject, json!("reject"));
+    pin(OverrideStatus::Moved, json!("moved"));
+    pin(OverrideStatus::Cancelled, json!("cancelled"));
+    pin(OverrideStatus::Substituted, json!("substituted"));
+    pin(OverrideStatus::RoomChange, json!("roomChange"));
+    pin(WebhookFormat::Json, json!("json"));
+    pin(WebhookFormat::Ics, json!("ics"));
+    pin(QuietAction::Defer, json!("defer"));
+    pin(QuietAction::Suppress, json!("suppress"));
+}
+
+#[test]
+fn quiet_hours_are_times_of_the_day() {
+    pin(
+        CreateQuietHours {
+            starts_at: time!(22:00),
+            ends_at: time!(7:30),
+            days: vec![DayOfWeek::Friday],
+            action: QuietAction::Defer,
+        },
+        json!({ "startsAt": "22:00", "endsAt": "07:30", "days": ["friday"], "action": "defer" }),
+    );
+    rejects::<CreateQuietHours>(json!({ "startsAt": "22:00:00", "endsAt": "07:00" }));
+}
+
+#[test]
+fn outbox_topics_are_camel_case() {
+    pin(Topic::EventCreated, json!("eventCreated"));
+    pin(Topic::EventUpdated, json!("eventUpdated"));
+    pin(Topic::EventDeleted, json!("eventDeleted"));
+    pin(Topic::ParticipantsChanged, json!("participantsChanged"));
+    pin(Topic::InvitationCreated, json!("invitationCreated"));
+    pin(Topic::InvitationResponded, json!("invitationResponded"));
+    pin(
+        Topic::EmailInvitationCreated,
+        json!("emailInvitationCreated"),
+    );
+    pin(Topic::ReminderDue, json!("reminderDue"));
+    pin(Topic::EventDigest, json!("eventDigest"));
+}
+
+/// Recurrence kinds are also stored as JSON in the database.
+#[test]
+fn recurrence_kinds_are_externally_tagged() {
+    pin(
+        RecurrenceRuleKind::Yearly { is_by_day: true },
+        json!({ "yearly": { "isByDay": true } }),
+    );
+    pin(
+        RecurrenceRuleKind::Monthly { is_by_day: false },
+        json!({ "monthly": { "isByDay": false } }),
+    );
+    pin(
+        RecurrenceRuleKind::Weekly {
+            week_map: 0b0011000,
+        },
+        json!({ "weekly": { "days": ["wednesday", "thursday"] } }),
+    );
+    pin(RecurrenceRuleKind::Daily, json!("daily"));
+    pin(RecurrenceRuleKind::Hourly, json!("hourly"));
+    pin(RecurrenceRuleKind::Minutely, json!("minutely"));
+}
+
+#[test]
+fn recurrence_ends_are_tagged_in_responses() {
+    pin(RecurrenceEndsAt::Count(15), json!({ "count": 15 }));
+    pin(
+        RecurrenceEndsAt::Until(datetime!(2023-03-08 10:30 UTC)),
+        json!({ "until": "+002023-03-08T10:30:00.000000000Z" }),
+    );
+}
+
+#[test]
+fn recurrence_ends_accept_bare_values() {
+    let count: RecurrenceEndsAt = from_value(json!(15)).unwrap();
+    assert_eq!(count, RecurrenceEndsAt::Count(15));
+
+    let until: RecurrenceEndsAt = from_value(json!("2023-03-08T10:30:00Z")).unwrap();
+    assert_eq!(
+        until,
+        RecurrenceEndsAt::Until(datetime!(2023-03-08 10:30 UTC))
+    );
+
+    rejects::<RecurrenceEndsAt>(json!({ "count": 15, "until": "2023-03-08T10:30:00Z" }));
+    rejects::<RecurrenceEndsAt>(json!({ "after": 15 }));
+}
+
+#[test]
+fn recurrence_rule_keeps_snake_case_time_rules() {
+    let rule = RecurrenceRuleSchema {
+        time_rules: TimeRules {
+            ends_at: Some(RecurrenceEndsAt::Count(2)),
+            interval: 1,
+        },
+        kind: RecurrenceRuleKind::Daily,
+    };
+    pin(
+        rule,
+        json!({
+            "time_rules": { "endsAt": { "count": 2 }, "interval": 1 },
+            "kind": "daily"
+        }),
+    );
+
+    let camel_case: RecurrenceRuleSchema = from_value(json!({
+        "timeRules": { "endsAt": 2, "interval": 1 },
+        "kind": "daily"
+    }))
+    .unwrap();
+    assert_eq!(
+        camel_case.time_rules.ends_at,
+        Some(RecurrenceEndsAt::Count(2))
+    );
+}
+
+#[test]
+fn override_shifts_are_iso8601_durations() {
+    for (duration, wire) in [
+        (Duration::ZERO, "PT0S"),
+        (Duration::minutes(-15), "-PT15M"),
+        (Duration::minutes(90), "PT1H30M"),
+        (Duration::days(2), "P2D"),
+        (Duration::new(93_600, 500_000_000), "P1DT2H0.5S"),
+    ] {
+        assert_eq!(iso8601_duration::format(duration), wire);
+        assert_eq!(iso8601_duration::parse(wire), Some(duration));
+    }
+    assert_eq!(iso8601_duration::parse("P1W"), Some(Duration::weeks(1)));
+    for wire in [
+        "", "P", "PT", "P1DT", "P1Y", "P1M", "PT1S1M", "PT1.S", "P1.5D", "15M",
+    ] {
+        assert_eq!(iso8601_duration::parse(wire), None, "{wire}");
+    }
+
+    pin(
+        OverrideEventData {
+            name: None,
+            description: None,
+            starts_at: Some(Duration::minutes(-15)),
+            ends_at: Some(Duration::minutes(90)),
+            status: None,
+            added_participants: vec![],
+            excluded_participants: vec![],
+        },
+        json!({ "startsAt": "-PT15M", "endsAt": "PT1H30M" }),
+    );
+    rejects::<OverrideEventData>(json!({ "startsAt": [900, 0] }));
+    rejects::<OverrideEventData>(json!({ "endsAt": 900 }));
+}
+
+#[test]
+fn request_bodies_deny_unknown_fields() {
+    rejects::<RecurrenceRuleSchema>(json!({
+        "time_rules": { "interval": 1 },
+        "kind": "daily",
+        "frequency": "daily"
+    }));
+    rejects::<TimeRules>(json!({ "interval": 1, "count": 2 }));
+    rejects::<CreateEvent>(json!({
+        "data": {
+            "payload": { "name": "Fizyka" },
+            "startsAt": "2023-03-08T09:45:00Z",
+            "endsAt": "2023-03-08T10:30:00Z",
+            "isPrivate": true
+        }
+    }));
+    rejects::<UpdateEvent>(json!({ "data": { "title": "Fizyka" } }));
+    rejects::<CreateReminder>(json!({
+        "eventId": "fd1dcdf7-de06-4aad-ba6e-f2097217a5b1",
+        "minutesBefore": 5,
+        "minutes_before": 5
+    }));
+    rejects::<UpdateUserPreferences>(json!({ "weekStart": "monday", "theme": "dark" }));
+}
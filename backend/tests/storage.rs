@@ -0,0 +1,61 @@
+mod tools;
+
+use bimetable_domain::api::events::EventExport;
+use bimetable_http::modules::Modules;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::StatusCode;
+use secrecy::Secret;
+use sqlx::PgPool;
+use tools::AppData;
+use tracing_test::traced_test;
+use uuid::Uuid;
+
+const FIZYKA_ID: &str = "fd1dcdf7-de06-4aad-ba6e-f2097217a5b1";
+
+fn with_local_storage(modules: &mut Modules) {
+    let dir = std::env::temp_dir().join(format!("bimetable-storage-{}", Uuid::new_v4()));
+    modules.app.storage_dir = dir.to_string_lossy().into_owned();
+    modules.app.storage_signing_key = Some(Secret::from("signing".to_string()));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn export_is_downloaded_with_signed_link(pool: PgPool) {
+    let app = AppData::with_modules(pool, with_local_storage).await;
+    let user = app.login("hubhub").await;
+
+    let res = user
+        .post(app.api(&format!("/api/v1/events/{FIZYKA_ID}/export.ics")))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let export: EventExport = res.json().await.unwrap();
+    assert!(export.url.starts_with("/api/v1/storage/exports/"));
+
+    // Anyone holding the link may download the export
+    let res = app.client().get(app.api(&export.url)).send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()[CONTENT_TYPE], "text/calendar; charset=utf-8");
+    let feed = res.text().await.unwrap();
+    assert!(feed.starts_with("BEGIN:VCALENDAR\r\n"));
+    assert!(feed.contains("SUMMARY:Fizyka\r\n"));
+
+    let tampered = export.url.replace("expires=", "expires=1");
+    let res = app.client().get(app.api(&tampered)).send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users", "events", "user_events"))]
+async fn cannot_export_foreign_event(pool: PgPool) {
+    let app = AppData::with_modules(pool, with_local_storage).await;
+    let user = app.login("mabmab").await;
+
+    let res = user
+        .post(app.api(&format!("/api/v1/events/{FIZYKA_ID}/export.ics")))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
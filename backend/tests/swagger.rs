@@ -0,0 +1,88 @@
+mod tools;
+
+use bimetable_db::config::app::SwaggerAccess;
+use bimetable_http::modules::Modules;
+use reqwest::StatusCode;
+use secrecy::Secret;
+use sqlx::{query, PgPool};
+use tools::AppData;
+use tracing_test::traced_test;
+use uuid::{uuid, Uuid};
+
+const ADIMAC_ID: Uuid = uuid!("910e81a9-56df-4c24-965a-13eff739f469");
+const OPENAPI: &str = "/api-doc/openapi.json";
+
+fn protected(modules: &mut Modules) {
+    modules.app.swagger = Some(SwaggerAccess::Protected);
+    modules.app.swagger_user = Some("operator".to_string());
+    modules.app.swagger_password = Some(Secret::from("explore".to_string()));
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn protected_swagger_requires_credentials(pool: PgPool) {
+    let app = AppData::with_modules(pool, protected).await;
+    let client = app.client();
+
+    let res = client.get(app.api(OPENAPI)).send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    let res = client
+        .get(app.api(OPENAPI))
+        .basic_auth("operator", Some("wrong"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    let res = client
+        .get(app.api(OPENAPI))
+        .basic_auth("operator", Some("explore"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn protected_swagger_lets_admins_in(pool: PgPool) {
+    query!(
+        r#"
+            UPDATE users SET is_admin = true
+            WHERE id = $1
+        "#,
+        ADIMAC_ID,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let app = AppData::with_modules(pool, protected).await;
+    let admin = app.login("macmac").await;
+    let user = app.login("hubhub").await;
+
+    let res = admin.get(app.api(OPENAPI)).send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = user.get(app.api(OPENAPI)).send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn disabled_swagger_is_not_served(pool: PgPool) {
+    let app = AppData::with_modules(pool, |modules| {
+        modules.app.swagger = Some(SwaggerAccess::Disabled)
+    })
+    .await;
+
+    let res = app
+        .client()
+        .get(app.api(OPENAPI))
+        .basic_auth("operator", Some("explore"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
@@ -1,12 +1,18 @@
-use bimetable::app;
-use bimetable::config::environment::Environment;
-use bimetable::modules::Modules;
+// Each test crate uses only some of the tools
+#![allow(dead_code)]
+
+use bimetable_client::Client as SdkClient;
+use bimetable_db::config::environment::Environment;
+use bimetable_domain::api::auth::LoginCredentials;
+use bimetable_http::app;
+use bimetable_http::modules::Modules;
 use dotenv::dotenv;
-use reqwest::Client;
+use reqwest::{Client, StatusCode};
+use serde_json::json;
 use sqlx::PgPool;
 use std::net::{SocketAddr, TcpListener};
 
-async fn spawn_app(pool: PgPool) -> SocketAddr {
+async fn spawn_app(pool: PgPool, configure: impl FnOnce(&mut Modules)) -> SocketAddr {
     dotenv().ok();
 
     let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).unwrap();
@@ -17,7 +23,7 @@ async fn spawn_app(pool: PgPool) -> SocketAddr {
     let access = "SECRET";
     let refresh = "VERY_SECRET";
 
-    let modules = Modules::use_custom(
+    let mut modules = Modules::use_custom(
         pool,
         addr,
         origin,
@@ -25,6 +31,7 @@ async fn spawn_app(pool: PgPool) -> SocketAddr {
         refresh,
         Environment::Development,
     );
+    configure(&mut modules);
 
     tokio::spawn(async move {
         axum::Server::from_tcp(listener)
@@ -43,8 +50,13 @@ pub struct AppData {
 
 impl AppData {
     pub async fn new(pool: PgPool) -> Self {
+        Self::with_modules(pool, |_| ()).await
+    }
+
+    /// Spawns the app after adjusting its modules, e.g. their settings.
+    pub async fn with_modules(pool: PgPool, configure: impl FnOnce(&mut Modules)) -> Self {
         Self {
-            addr: spawn_app(pool).await,
+            addr: spawn_app(pool, configure).await,
         }
     }
 
@@ -58,4 +70,28 @@ impl AppData {
     pub fn api(&self, uri: &str) -> String {
         format!("http://{}{uri}", self.addr)
     }
+
+    /// Signs in the fixture user with a new client, which keeps the session cookies.
+    pub async fn login(&self, login: &str) -> Client {
+        let client = self.client();
+        let res = client
+            .post(self.api("/auth/login"))
+            .json(&json!({ "login": login, "password": "#strong#_#pass#" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        client
+    }
+
+    /// Signs in the fixture user with a new client of the SDK.
+    pub async fn login_sdk(&self, login: &str) -> SdkClient {
+        let client = SdkClient::new(self.api(""));
+        client
+            .login(&LoginCredentials::new(login, "#strong#_#pass#"))
+            .await
+            .unwrap();
+        client
+    }
 }
@@ -0,0 +1,58 @@
+mod tools;
+
+use bimetable_http::modules::trace_context::TRACEPARENT;
+use sqlx::PgPool;
+use tools::AppData;
+use tracing_test::traced_test;
+
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+const PARENT_ID: &str = "00f067aa0ba902b7";
+
+fn traceparent(res: &reqwest::Response) -> Vec<String> {
+    res.headers()
+        .get(TRACEPARENT)
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .split('-')
+        .map(str::to_string)
+        .collect()
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn responses_continue_caller_trace(pool: PgPool) {
+    let app = AppData::new(pool).await;
+
+    let res = app
+        .client()
+        .get(app.api("/api/v1/events"))
+        .header(TRACEPARENT, format!("00-{TRACE_ID}-{PARENT_ID}-01"))
+        .send()
+        .await
+        .unwrap();
+
+    let parts = traceparent(&res);
+    assert_eq!(parts[0], "00");
+    assert_eq!(parts[1], TRACE_ID);
+    assert_ne!(parts[2], PARENT_ID);
+    assert_eq!(parts[3], "01");
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn responses_start_trace_without_caller(pool: PgPool) {
+    let app = AppData::new(pool).await;
+
+    let res = app
+        .client()
+        .get(app.api("/api/v1/events"))
+        .header(TRACEPARENT, "garbage")
+        .send()
+        .await
+        .unwrap();
+
+    let parts = traceparent(&res);
+    assert_eq!(parts[1].len(), 32);
+    assert_ne!(parts[1], TRACE_ID);
+}
@@ -0,0 +1,180 @@
+use bimetable_db::utils::events::exe::{create_new_event, get_many_events};
+use bimetable_db::utils::users::errors::UserError;
+use bimetable_db::utils::users::quiet_hours::{
+    create_quiet_hours, delete_quiet_hours, get_user_quiet_hours,
+};
+use bimetable_db::utils::users::{get_user_preferences, update_user_preferences};
+use bimetable_domain::api::events::{
+    CreateEvent, EventData, EventFilter, EventPayload, RecurrenceRuleSchema, TimeRules,
+};
+use bimetable_domain::api::users::{
+    CreateQuietHours, QuietAction, UpdateUserPreferences, UserPreferences,
+};
+use bimetable_domain::events::models::{DayOfWeek, RecurrenceRuleKind, TimeRange};
+use bimetable_domain::limits::RepetitionLimit;
+use sqlx::PgPool;
+use time::macros::{datetime, time};
+use time::OffsetDateTime;
+use tracing_test::traced_test;
+use uuid::{uuid, Uuid};
+
+const PKBPMJ_ID: Uuid = uuid!("29e40c2a-7595-42d3-98e8-9fe93ce99972");
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn default_preferences_test(pool: PgPool) {
+    assert_eq!(
+        get_user_preferences(&pool, PKBPMJ_ID).await.unwrap(),
+        UserPreferences {
+            week_start: DayOfWeek::Monday,
+            invite_can_edit: false,
+        }
+    );
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn update_preferences_test(pool: PgPool) {
+    let preferences = update_user_preferences(
+        &pool,
+        PKBPMJ_ID,
+        UpdateUserPreferences {
+            week_start: Some(DayOfWeek::Sunday),
+            invite_can_edit: Some(true),
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(preferences.week_start, DayOfWeek::Sunday);
+    assert!(preferences.invite_can_edit);
+    assert_eq!(
+        get_user_preferences(&pool, PKBPMJ_ID).await.unwrap(),
+        preferences
+    );
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn quiet_hours_test(pool: PgPool) {
+    let night = create_quiet_hours(
+        &pool,
+        PKBPMJ_ID,
+        CreateQuietHours {
+            starts_at: time!(22:00),
+            ends_at: time!(7:00),
+            days: Vec::new(),
+            action: QuietAction::Suppress,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(night.days.len(), 7);
+    assert_eq!(
+        get_user_quiet_hours(&pool, PKBPMJ_ID).await.unwrap(),
+        vec![night.clone()]
+    );
+
+    let res = create_quiet_hours(
+        &pool,
+        PKBPMJ_ID,
+        CreateQuietHours {
+            starts_at: time!(12:00),
+            ends_at: time!(12:00),
+            days: vec![DayOfWeek::Monday],
+            action: QuietAction::Defer,
+        },
+    )
+    .await;
+    assert!(matches!(res, Err(UserError::InvalidData(_))));
+
+    assert!(matches!(
+        delete_quiet_hours(&pool, Uuid::new_v4(), night.id).await,
+        Err(UserError::QuietHoursNotFound)
+    ));
+    delete_quiet_hours(&pool, PKBPMJ_ID, night.id)
+        .await
+        .unwrap();
+    assert!(get_user_quiet_hours(&pool, PKBPMJ_ID)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+async fn entry_starts(pool: &PgPool, week_start: Option<DayOfWeek>) -> Vec<OffsetDateTime> {
+    let mut starts: Vec<OffsetDateTime> = get_many_events(
+        PKBPMJ_ID,
+        TimeRange::new(
+            datetime!(2023-03-01 0:00 UTC),
+            datetime!(2023-03-22 0:00 UTC),
+        ),
+        EventFilter::Owned,
+        week_start,
+        pool,
+    )
+    .await
+    .unwrap()
+    .entries
+    .into_iter()
+    .map(|entry| entry.time_range.start)
+    .collect();
+    starts.sort();
+    starts
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn week_start_affects_weekly_entries(pool: PgPool) {
+    create_new_event(
+        &pool,
+        PKBPMJ_ID,
+        CreateEvent {
+            data: EventData {
+                payload: EventPayload::new("Basen".to_string(), None),
+                starts_at: datetime!(2023-03-05 10:00 UTC),
+                ends_at: datetime!(2023-03-05 11:00 UTC),
+            },
+            recurrence_rule: Some(RecurrenceRuleSchema {
+                time_rules: TimeRules {
+                    ends_at: None,
+                    interval: 2,
+                },
+                kind: RecurrenceRuleKind::Weekly {
+                    week_map: 0b1000001,
+                },
+            }),
+        },
+        RepetitionLimit::default(),
+    )
+    .await
+    .unwrap();
+
+    update_user_preferences(
+        &pool,
+        PKBPMJ_ID,
+        UpdateUserPreferences {
+            week_start: Some(DayOfWeek::Sunday),
+            invite_can_edit: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        entry_starts(&pool, None).await,
+        vec![
+            datetime!(2023-03-05 10:00 UTC),
+            datetime!(2023-03-06 10:00 UTC),
+            datetime!(2023-03-19 10:00 UTC),
+            datetime!(2023-03-20 10:00 UTC),
+        ]
+    );
+    assert_eq!(
+        entry_starts(&pool, Some(DayOfWeek::Monday)).await,
+        vec![
+            datetime!(2023-03-05 10:00 UTC),
+            datetime!(2023-03-13 10:00 UTC),
+            datetime!(2023-03-19 10:00 UTC),
+        ]
+    );
+}
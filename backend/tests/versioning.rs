@@ -0,0 +1,45 @@
+mod tools;
+
+use reqwest::StatusCode;
+use serde_json::json;
+use sqlx::PgPool;
+use tools::AppData;
+use tracing_test::traced_test;
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn versioned_paths_are_current(pool: PgPool) {
+    let app = AppData::new(pool).await;
+
+    let res = app
+        .client()
+        .post(app.api("/api/v1/auth/login"))
+        .json(&json!({ "login": "macmac", "password": "#strong#_#pass#" }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(res.headers().get("deprecation").is_none());
+}
+
+#[traced_test]
+#[sqlx::test(fixtures("users"))]
+async fn unversioned_paths_are_deprecated(pool: PgPool) {
+    let app = AppData::new(pool).await;
+
+    let res = app
+        .client()
+        .post(app.api("/auth/login"))
+        .json(&json!({ "login": "macmac", "password": "#strong#_#pass#" }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()["deprecation"], "true");
+    assert_eq!(
+        res.headers()["link"],
+        r#"</api/v1/auth/login>; rel="successor-version""#
+    );
+}
@@ -257,8 +257,8 @@ export async function fetchEventStore(start: dayjs.Dayjs, end: dayjs.Dayjs) {
     // TODO: test this (this will require putting some events in the database)
     const { data, pending, refresh, error } = await useAPI<APIEventsGetResult>("/api/events", {
         params: {
-            starts_at: start.toISOString(),
-            ends_at: end.toISOString(),
+            startsAt: start.toISOString(),
+            endsAt: end.toISOString(),
             filter: "all",
         },
         default: () => ({